@@ -0,0 +1,193 @@
+//! Timings of the paths a render spends its time on, in groups:
+//!
+//! - `vector`: the arithmetic everything else is built from, per operation.
+//! - `sphere`: one ray against one sphere, hitting and missing.
+//! - `closest_hit`: one camera ray against the 1000 spheres of
+//!   [`Scene::benchmark`], through the BVH and by testing every sphere in
+//!   turn. The ratio between the two is what the BVH buys.
+//! - `packets`: four neighbouring camera rays into the 1000 spheres traced
+//!   one at a time and as a [`RayPacket`], and a frame of the same scene
+//!   rendered with packets and without.
+//! - `render`: a whole frame of the demo scene, of the benchmark scene and of
+//!   the 500 spheres of [`Scene::random_spheres`] at 160x120, one sample a
+//!   pixel, on every core.
+//!
+//! Run them with `cargo bench`, or just those whose group/name matches a
+//! pattern with `cargo bench -- sphere`. Criterion prints a confidence
+//! interval for the time of one iteration, the middle figure its estimate,
+//! and compares it with the last run it saved under `target/criterion`; the
+//! scenes are made from fixed seeds, so a change it reports between two
+//! commits on the same machine is the code's, unless it also counts many
+//! outliers, which mean the machine was busy and the run is worth repeating.
+//! `cargo test --benches` runs each benchmark once, as a check that they
+//! still work.
+
+use std::time::Duration;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use basic_raytracer::ray::{Ray, RayPacket, EPSILON, PACKET_SIZE};
+use basic_raytracer::render::{render, IntegratorKind, RenderSettings};
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene::{RayKind, Scene};
+use basic_raytracer::shapes::{Shape, Sphere};
+use basic_raytracer::vector::Vector;
+
+fn vector(c: &mut Criterion) {
+    let a = Vector::new(0.3, -1.2, 2.5);
+    let b = Vector::new(-0.7, 0.4, 1.1);
+    let mut group = c.benchmark_group("vector");
+    group.bench_function("add", |bench| bench.iter(|| black_box(a) + black_box(b)));
+    group.bench_function("dot", |bench| bench.iter(|| black_box(a) * black_box(b)));
+    group.bench_function("cross", |bench| {
+        bench.iter(|| black_box(a).cross(black_box(b)))
+    });
+    group.bench_function("normalize", |bench| bench.iter(|| black_box(a).normalize()));
+    group.bench_function("reflect", |bench| {
+        bench.iter(|| black_box(a).reflect(black_box(b)))
+    });
+    group.finish();
+}
+
+fn sphere(c: &mut Criterion) {
+    let sphere = Sphere::new(Vector::new(0.0, 0.0, -5.0), 1.0);
+    let hit = Ray::new(Vector::zero(), Vector::new(0.05, 0.1, -1.0).normalize());
+    let miss = Ray::new(Vector::zero(), Vector::new(0.5, 0.0, -1.0).normalize());
+    let mut group = c.benchmark_group("sphere");
+    group.bench_function("hit", |bench| {
+        bench.iter(|| black_box(&sphere).intersect(black_box(&hit), EPSILON, Scalar::INFINITY))
+    });
+    group.bench_function("miss", |bench| {
+        bench.iter(|| black_box(&sphere).intersect(black_box(&miss), EPSILON, Scalar::INFINITY))
+    });
+    group.finish();
+}
+
+fn closest_hit(c: &mut Criterion) {
+    let scene = Scene::benchmark(1000, 1);
+    let (width, height) = (32, 24);
+    let rays: Vec<Ray> = (0..width * height)
+        .map(|i| {
+            let u = ((i % width) as Scalar + 0.5) / width as Scalar;
+            let v = ((i / width) as Scalar + 0.5) / height as Scalar;
+            scene.camera.ray(u, v, width as Scalar / height as Scalar)
+        })
+        .collect();
+    // Builds the BVH before it's timed.
+    scene.intersect(&rays[0], EPSILON, Scalar::INFINITY);
+    let mut group = c.benchmark_group("closest_hit");
+    let mut next = rays.iter().cycle();
+    group.bench_function("1000 spheres, bvh", |bench| {
+        bench.iter(|| {
+            let ray = next.next().unwrap();
+            scene
+                .intersect(ray, EPSILON, Scalar::INFINITY)
+                .map(|hit| hit.record.t)
+        })
+    });
+    let mut next = rays.iter().cycle();
+    group.bench_function("1000 spheres, every one", |bench| {
+        bench.iter(|| {
+            let ray = next.next().unwrap();
+            let mut closest = Scalar::INFINITY;
+            for object in &scene.objects {
+                if let Some(record) = object.shape.intersect(ray, EPSILON, closest) {
+                    closest = record.t;
+                }
+            }
+            closest
+        })
+    });
+    group.finish();
+}
+
+fn packets(c: &mut Criterion) {
+    let scene = Scene::benchmark(1000, 1);
+    let (width, height) = (32, 24);
+    let rays: Vec<Ray> = (0..width * height)
+        .map(|i| {
+            let u = ((i % width) as Scalar + 0.5) / width as Scalar;
+            let v = ((i / width) as Scalar + 0.5) / height as Scalar;
+            scene.camera.ray(u, v, width as Scalar / height as Scalar)
+        })
+        .collect();
+    let packets: Vec<RayPacket> = rays.chunks(PACKET_SIZE).map(RayPacket::new).collect();
+    scene.intersect(&rays[0], EPSILON, Scalar::INFINITY);
+    let mut group = c.benchmark_group("packets");
+    let mut next = rays.chunks(PACKET_SIZE).cycle();
+    group.bench_function("4 rays, one at a time", |bench| {
+        bench.iter(|| {
+            let rays = next.next().unwrap();
+            rays.iter()
+                .map(|ray| {
+                    let hit = scene.trace(ray, RayKind::Camera, EPSILON, Scalar::INFINITY);
+                    hit.map(|hit| hit.record.t)
+                })
+                .count()
+        })
+    });
+    let mut next = packets.iter().cycle();
+    group.bench_function("4 rays, as a packet", |bench| {
+        bench.iter(|| {
+            let packet = next.next().unwrap();
+            let hits = scene.trace_packet(packet, RayKind::Camera, EPSILON, Scalar::INFINITY);
+            hits.iter().filter(|hit| hit.is_some()).count()
+        })
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("packets");
+    group
+        .sample_size(20)
+        .measurement_time(Duration::from_secs(10));
+    for &packets in [true, false].iter() {
+        let settings = RenderSettings {
+            width: 160,
+            height: 120,
+            spp: 1,
+            integrator: IntegratorKind::Whitted,
+            packets,
+            ..RenderSettings::default()
+        };
+        let name = if packets {
+            "1000 spheres, packets"
+        } else {
+            "1000 spheres, no packets"
+        };
+        group.bench_function(name, |bench| {
+            bench.iter(|| render(&scene, &settings).unwrap().0.pixels)
+        });
+    }
+    group.finish();
+}
+
+fn frame(c: &mut Criterion) {
+    let settings = RenderSettings {
+        width: 160,
+        height: 120,
+        spp: 1,
+        integrator: IntegratorKind::Whitted,
+        ..RenderSettings::default()
+    };
+    let mut group = c.benchmark_group("render");
+    // A frame takes milliseconds, so fewer samples over longer.
+    group
+        .sample_size(20)
+        .measurement_time(Duration::from_secs(10));
+    let demo = Scene::demo();
+    group.bench_function("demo scene", |bench| {
+        bench.iter(|| render(&demo, &settings).unwrap().0.pixels)
+    });
+    let benchmark = Scene::benchmark(1000, 1);
+    group.bench_function("1000 spheres", |bench| {
+        bench.iter(|| render(&benchmark, &settings).unwrap().0.pixels)
+    });
+    let spheres = Scene::random_spheres(500, 7);
+    group.bench_function("500 random spheres", |bench| {
+        bench.iter(|| render(&spheres, &settings).unwrap().0.pixels)
+    });
+    group.finish();
+}
+
+criterion_group!(benches, vector, sphere, closest_hit, packets, frame);
+criterion_main!(benches);
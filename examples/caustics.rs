@@ -0,0 +1,62 @@
+//! A glass sphere on a diffuse floor under a point light, rendered with a
+//! caustic photon map: the sphere focuses the light into a bright spot on
+//! the floor, ringed by its shadow.
+//!
+//!     cargo run --release --example caustics [spp] [photons]
+
+use std::path::Path;
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::error::Error;
+use basic_raytracer::light::PointLight;
+use basic_raytracer::material::{Dielectric, Lambertian};
+use basic_raytracer::render::{self, IntegratorKind, RenderSettings};
+use basic_raytracer::scene::Scene;
+use basic_raytracer::shapes::{Plane, Sphere};
+use basic_raytracer::vector::{Color, Vector};
+
+fn glass_sphere() -> Scene {
+    let mut scene = Scene::new(Camera::new(
+        Vector::new(0.0, 3.0, 4.0),
+        Vector::new(0.0, 0.3, 0.0),
+        45.0,
+    ));
+    scene.add(
+        Plane::new(Vector::new(0.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0)),
+        Arc::new(Lambertian::new(Color::new(0.75, 0.75, 0.75))),
+    );
+    scene.add(
+        Sphere::new(Vector::new(0.0, 1.0, 0.0), 0.6),
+        Arc::new(Dielectric::new(1.5)),
+    );
+    scene.add_light(PointLight::new(
+        Vector::new(0.0, 4.0, 0.0),
+        Color::new(1.0, 1.0, 1.0),
+        16.0,
+    ));
+    scene
+}
+
+fn main() -> Result<(), Error> {
+    let mut args = std::env::args().skip(1);
+    let spp = args
+        .next()
+        .map_or(16, |spp| spp.parse().expect("spp must be a number"));
+    let photons = args.next().map_or(2_000_000, |photons| {
+        photons.parse().expect("photons must be a number")
+    });
+    let settings = RenderSettings {
+        width: 400,
+        height: 300,
+        integrator: IntegratorKind::Path,
+        spp,
+        caustic_photons: photons,
+        photon_k: 64,
+        photon_radius: 0.05,
+        ..RenderSettings::default()
+    };
+    let (image, stats) = render::render(&glass_sphere(), &settings)?;
+    println!("{}", stats);
+    image.write_png(Path::new("caustics.png"))
+}
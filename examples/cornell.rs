@@ -0,0 +1,78 @@
+//! A Cornell-style box rendered with the path tracer: a white sphere beside a
+//! red wall picks up red bounce light that direct lighting alone can't show.
+//!
+//!     cargo run --release --example cornell [spp]
+
+use std::path::Path;
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::error::Error;
+use basic_raytracer::material::Lambertian;
+use basic_raytracer::render::{self, IntegratorKind, RenderSettings};
+use basic_raytracer::scene::Scene;
+use basic_raytracer::shapes::{Plane, Quad, Sphere};
+use basic_raytracer::vector::{Color, Vector};
+
+fn cornell_box() -> Scene {
+    let mut scene = Scene::new(Camera::new(
+        Vector::new(0.0, 1.0, 3.4),
+        Vector::new(0.0, 1.0, 0.0),
+        45.0,
+    ));
+    let white = Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+    let red = Arc::new(Lambertian::new(Color::new(0.65, 0.05, 0.05)));
+    let green = Arc::new(Lambertian::new(Color::new(0.12, 0.45, 0.15)));
+
+    scene.add(
+        Plane::new(Vector::new(0.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0)),
+        white.clone(),
+    );
+    scene.add(
+        Plane::new(Vector::new(0.0, 2.0, 0.0), Vector::new(0.0, -1.0, 0.0)),
+        white.clone(),
+    );
+    scene.add(
+        Plane::new(Vector::new(0.0, 0.0, -1.0), Vector::new(0.0, 0.0, 1.0)),
+        white.clone(),
+    );
+    scene.add(
+        Plane::new(Vector::new(-1.0, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0)),
+        red,
+    );
+    scene.add(
+        Plane::new(Vector::new(1.0, 0.0, 0.0), Vector::new(-1.0, 0.0, 0.0)),
+        green,
+    );
+    scene.add(
+        Sphere::new(Vector::new(-0.45, 0.4, -0.3), 0.4),
+        white.clone(),
+    );
+    scene.add(Sphere::new(Vector::new(0.45, 0.3, 0.1), 0.3), white);
+    scene.add_area_light(
+        Quad::new(
+            Vector::new(-0.25, 1.999, -0.45),
+            Vector::new(0.5, 0.0, 0.0),
+            Vector::new(0.0, 0.0, 0.5),
+        ),
+        Color::new(15.0, 15.0, 15.0),
+    );
+    scene
+}
+
+fn main() -> Result<(), Error> {
+    let spp = std::env::args()
+        .nth(1)
+        .map_or(256, |spp| spp.parse().expect("spp must be a number"));
+    let settings = RenderSettings {
+        width: 400,
+        height: 400,
+        integrator: IntegratorKind::Path,
+        spp,
+        max_depth: 8,
+        ..RenderSettings::default()
+    };
+    let (image, stats) = render::render(&cornell_box(), &settings)?;
+    println!("{}", stats);
+    image.write_png(Path::new("cornell.png"))
+}
@@ -0,0 +1,61 @@
+//! Two metaballs drifting together over three frames: apart, just touching
+//! as a neck grows between them, then merged into one rounded lump.
+//!
+//!     cargo run --release --example metaballs
+
+use std::path::Path;
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::error::Error;
+use basic_raytracer::light::PointLight;
+use basic_raytracer::material::Lambertian;
+use basic_raytracer::render::{self, IntegratorKind, RenderSettings};
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene::Scene;
+use basic_raytracer::shapes::{Blob, Metaballs, Plane};
+use basic_raytracer::vector::{Color, Vector};
+
+fn blobs(gap: Scalar) -> Scene {
+    let mut scene = Scene::new(Camera::new(
+        Vector::new(0.0, 1.6, 6.0),
+        Vector::new(0.0, 0.2, 0.0),
+        40.0,
+    ));
+    scene.add(
+        Plane::new(Vector::new(0.0, -0.6, 0.0), Vector::new(0.0, 1.0, 0.0)),
+        Arc::new(Lambertian::new(Color::new(0.6, 0.6, 0.6))),
+    );
+    scene.add(
+        Metaballs::new(
+            vec![
+                Blob::new(Vector::new(-gap / 2.0, 0.2, 0.0), 1.2, 1.0),
+                Blob::new(Vector::new(gap / 2.0, 0.2, 0.0), 1.0, 1.0),
+            ],
+            0.5,
+        ),
+        Arc::new(Lambertian::new(Color::new(0.3, 0.6, 0.9))),
+    );
+    scene.add_light(PointLight::new(
+        Vector::new(2.0, 4.0, 4.0),
+        Color::new(1.0, 1.0, 1.0),
+        40.0,
+    ));
+    scene
+}
+
+fn main() -> Result<(), Error> {
+    let settings = RenderSettings {
+        width: 400,
+        height: 300,
+        integrator: IntegratorKind::Whitted,
+        spp: 4,
+        ..RenderSettings::default()
+    };
+    for (frame, &gap) in [2.4, 1.3, 0.8].iter().enumerate() {
+        let (image, stats) = render::render(&blobs(gap), &settings)?;
+        println!("frame {}: {}", frame, stats);
+        image.write_png(Path::new(&format!("metaballs_{}.png", frame)))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,115 @@
+//! A flat brick wall with a normal map, lit by a point light that sweeps
+//! across it at a grazing angle. The mortar lines and bevels catch the light
+//! on the side facing it and fall dark on the other, though the quad itself
+//! is perfectly flat.
+//!
+//!     cargo run --release --example normal_map [frames]
+
+use std::path::Path;
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::error::Error;
+use basic_raytracer::image::Image;
+use basic_raytracer::light::PointLight;
+use basic_raytracer::material::{Lambertian, NormalMapped};
+use basic_raytracer::render::{self, IntegratorKind, RenderSettings};
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene::Scene;
+use basic_raytracer::shapes::Quad;
+use basic_raytracer::texture::ImageTexture;
+use basic_raytracer::vector::{Color, Vector};
+
+const SIZE: u32 = 256;
+const ROWS: Scalar = 4.0;
+const BRICKS_PER_ROW: Scalar = 2.0;
+const MORTAR: Scalar = 0.04;
+const BEVEL: Scalar = 0.06;
+
+/// The height of the wall at `(x, y)` in `[0, 1]²`: 1 on a brick's face,
+/// sloping down over its bevel to 0 in the mortar.
+fn height(x: Scalar, y: Scalar) -> Scalar {
+    let row = (y * ROWS).floor();
+    let shift = if row as i64 % 2 == 0 { 0.0 } else { 0.5 };
+    let across = (x * BRICKS_PER_ROW + shift).rem_euclid(1.0);
+    let down = (y * ROWS).rem_euclid(1.0);
+    // Distance to the nearest mortar line, in units of one brick's height.
+    let edge = (across.min(1.0 - across) * ROWS / BRICKS_PER_ROW).min(down.min(1.0 - down));
+    ((edge - MORTAR) / BEVEL).clamp(0.0, 1.0)
+}
+
+fn brick_textures() -> (ImageTexture, ImageTexture) {
+    let mut albedo = Image::new(SIZE, SIZE);
+    let mut normals = Image::new(SIZE, SIZE);
+    let step = 1.0 / SIZE as Scalar;
+    let depth = 0.015;
+    for py in 0..SIZE {
+        for px in 0..SIZE {
+            let (x, y) = ((px as Scalar + 0.5) * step, (py as Scalar + 0.5) * step);
+            let h = height(x, y);
+            albedo.set(
+                px,
+                py,
+                if h > 0.0 {
+                    Color::new(0.55, 0.2, 0.12)
+                } else {
+                    Color::new(0.6, 0.6, 0.58)
+                },
+            );
+            let dx = depth * (height(x + step, y) - height(x - step, y)) / (2.0 * step);
+            let dy = depth * (height(x, y + step) - height(x, y - step)) / (2.0 * step);
+            // Image rows grow down, but the map's green points up.
+            let normal = Vector::new(-dx, dy, 1.0).normalize();
+            normals.set(px, py, 0.5 * (normal + Vector::new(1.0, 1.0, 1.0)));
+        }
+    }
+    (ImageTexture::new(albedo), ImageTexture::new(normals))
+}
+
+fn brick_wall(light_x: Scalar) -> Scene {
+    let mut scene = Scene::new(Camera::new(
+        Vector::new(0.0, 0.0, 2.6),
+        Vector::new(0.0, 0.0, 0.0),
+        45.0,
+    ));
+    let (albedo, normals) = brick_textures();
+    let bricks = Lambertian::textured(Box::new(albedo));
+    scene.add(
+        Quad::new(
+            Vector::new(-1.0, 1.0, 0.0),
+            Vector::new(2.0, 0.0, 0.0),
+            Vector::new(0.0, -2.0, 0.0),
+        ),
+        Arc::new(NormalMapped::new(Arc::new(bricks), Box::new(normals))),
+    );
+    scene.add_light(PointLight::new(
+        Vector::new(light_x, 0.6, 0.35),
+        Color::new(1.0, 0.95, 0.85),
+        2.0,
+    ));
+    scene
+}
+
+fn main() -> Result<(), Error> {
+    let frames: u32 = std::env::args()
+        .nth(1)
+        .map_or(4, |frames| frames.parse().expect("frames must be a number"));
+    let settings = RenderSettings {
+        width: 400,
+        height: 400,
+        integrator: IntegratorKind::Whitted,
+        spp: 4,
+        ..RenderSettings::default()
+    };
+    for frame in 0..frames {
+        let t = if frames > 1 {
+            frame as Scalar / (frames - 1) as Scalar
+        } else {
+            0.5
+        };
+        let (image, stats) = render::render(&brick_wall(-1.5 + 3.0 * t), &settings)?;
+        println!("frame {}: {}", frame, stats);
+        image.write_png(Path::new(&format!("normal_map_{}.png", frame)))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,51 @@
+//! Times the scenes in `scenes/` and weighs their geometry, in whichever
+//! precision the crate was built with. Run it both ways to compare:
+//!
+//!     cargo run --release --example precision
+//!     cargo run --release --example precision --features f32
+
+use std::fs;
+use std::mem;
+use std::time::Duration;
+
+use basic_raytracer::error::Error;
+use basic_raytracer::render::{self, RenderSettings};
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene_file;
+
+fn main() -> Result<(), Error> {
+    let settings = RenderSettings {
+        width: 320,
+        height: 240,
+        spp: 4,
+        ..RenderSettings::default()
+    };
+    let mut scenes: Vec<_> = fs::read_dir("scenes")
+        .map_err(|source| Error::Io {
+            path: "scenes".into(),
+            operation: "read",
+            source,
+        })?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .collect();
+    scenes.sort();
+    println!("{}-bit math", 8 * mem::size_of::<Scalar>());
+    let mut total = Duration::ZERO;
+    for path in &scenes {
+        let scene = scene_file::load(path)?;
+        let (_, stats) = render::render(&scene, &settings)?;
+        total += stats.elapsed;
+        println!(
+            "{:<24} {:>8.3} s {:>10} bytes of geometry",
+            path.file_name().unwrap_or_default().to_string_lossy(),
+            stats.elapsed.as_secs_f64(),
+            scene.geometry_bytes()
+        );
+    }
+    println!("{:<24} {:>8.3} s", "total", total.as_secs_f64());
+    Ok(())
+}
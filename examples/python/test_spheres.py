@@ -0,0 +1,144 @@
+"""Renders 100 randomly placed spheres through the Python bindings, and checks
+what the bindings promise. Run it with pytest, or on its own once the
+module is built and on the path:
+
+    cargo build --release --features extension-module
+    cp target/release/libbasic_raytracer.so examples/python/basic_raytracer.so
+    python3 examples/python/test_spheres.py [spheres.ppm]
+
+which also writes the render to the PPM file if one is named.
+"""
+
+import random
+import sys
+import threading
+
+import basic_raytracer as rt
+
+
+def spheres(count=100, seed=7):
+    rng = random.Random(seed)
+    scene = rt.Scene(
+        camera=rt.Camera(position=(0, 6, 14), look_at=(0, 0, 0), fov=40),
+        background=(0.05, 0.05, 0.1),
+    )
+    scene.add(rt.Plane(point=(0, 0, 0), normal=(0, 1, 0), color=(0.6, 0.6, 0.6)))
+    for _ in range(count):
+        radius = rng.uniform(0.2, 0.6)
+        center = (rng.uniform(-6, 6), radius, rng.uniform(-6, 4))
+        material = rng.choice(["lambertian", "lambertian", "glossy", "dielectric"])
+        color = (rng.random(), rng.random(), rng.random())
+        scene.add(rt.Sphere(center=center, radius=radius, color=color, material=material))
+    scene.add(rt.PointLight(position=(4, 10, 6), intensity=300))
+    scene.add(rt.PointLight(position=(-6, 8, 2), color=(0.6, 0.7, 1.0), intensity=150))
+    return scene
+
+
+def test_a_hundred_spheres_render():
+    scene = spheres()
+    assert len(scene.objects) == 101
+    pixels = rt.render(scene, width=160, height=120, spp=2)
+    assert len(pixels) == 160 * 120 * 4
+    assert all(alpha == 255 for alpha in pixels[3::4])
+    # Many different colors, not a blank image.
+    assert len({pixels[i : i + 3] for i in range(0, len(pixels), 4)}) > 500
+
+
+def test_renders_are_repeatable():
+    scene = spheres(count=10)
+    first = rt.render(scene, width=48, height=32, seed=3)
+    assert rt.render(scene, width=48, height=32, seed=3) == first
+
+
+def test_progress_comes_row_by_row_while_other_threads_run():
+    ticks = []
+    stop = threading.Event()
+
+    def tick():
+        while not stop.is_set():
+            ticks.append(None)
+            stop.wait(0.001)
+
+    ticker = threading.Thread(target=tick)
+    ticker.start()
+    rows = []
+    try:
+        rt.render(spheres(), width=64, height=48, spp=8, progress=lambda done, of: rows.append((done, of)))
+    finally:
+        stop.set()
+        ticker.join()
+    assert rows == [(done, 48) for done in range(1, 49)]
+    # The render didn't keep the GIL to itself.
+    assert len(ticks) > 1
+
+
+def test_progress_can_cancel():
+    try:
+        rt.render(spheres(), width=64, height=48, progress=lambda done, of: done == 10)
+    except rt.RenderError as error:
+        assert "cancelled" in str(error)
+    else:
+        raise AssertionError("the render wasn't cancelled")
+
+    def fail(done, of):
+        raise ValueError("from the callback")
+
+    try:
+        rt.render(spheres(count=1), width=8, height=8, progress=fail)
+    except ValueError as error:
+        assert "from the callback" in str(error)
+    else:
+        raise AssertionError("the callback's exception was lost")
+
+
+def test_mistakes_are_errors():
+    for bad in [
+        lambda: rt.Sphere(center=(0, 0)),
+        lambda: rt.Sphere(material="velvet"),
+        lambda: rt.render(rt.Scene(), integrator="magic"),
+        lambda: rt.Scene().add("a sphere"),
+    ]:
+        try:
+            bad()
+        except (TypeError, ValueError):
+            pass
+        else:
+            raise AssertionError("accepted a mistake")
+    try:
+        rt.render(spheres(count=1), width=8, height=8, spp=0)
+    except rt.RenderError as error:
+        assert "spp" in str(error)
+    else:
+        raise AssertionError("rendered with no samples")
+
+
+def test_reprs_say_what_things_are():
+    sphere = rt.Sphere(center=(1, 2, 3), radius=0.5, material="glossy", exponent=10)
+    assert repr(sphere) == (
+        "Sphere(center=(1, 2, 3), radius=0.5, color=(0.8, 0.8, 0.8), material='glossy', exponent=10)"
+    )
+    assert repr(rt.PointLight(position=(0, 5, 0), intensity=20)) == (
+        "PointLight(position=(0, 5, 0), color=(1, 1, 1), intensity=20)"
+    )
+    assert repr(rt.Camera()) == "Camera(position=(0, 0, 0), look_at=(0, 0, -1), fov=45)"
+    assert repr(spheres(count=3)).startswith("<Scene with 4 objects and 2 lights")
+
+
+if __name__ == "__main__":
+    tests = [value for name, value in sorted(globals().items()) if name.startswith("test_")]
+    for test in tests:
+        test()
+        print("ok", test.__name__)
+    if len(sys.argv) > 1:
+        width, height = 640, 480
+        pixels = rt.render(
+            spheres(),
+            width=width,
+            height=height,
+            spp=4,
+            progress=lambda done, of: print("\r%d/%d rows" % (done, of), end="", file=sys.stderr),
+        )
+        print(file=sys.stderr)
+        rgb = bytes(b for i, b in enumerate(pixels) if i % 4 != 3)
+        with open(sys.argv[1], "wb") as ppm:
+            ppm.write(b"P6 %d %d 255\n" % (width, height) + rgb)
@@ -0,0 +1,28 @@
+//! Writes the golden images `tests/golden.rs` compares renders with afresh,
+//! for after a change meant to alter them. Look over what changed before
+//! committing the new images:
+//!
+//!     cargo run --example regen-goldens
+//!     git diff --stat tests/golden
+//!
+//! The goldens are made with `f64` math whatever features this was built
+//! with, and in release mode, which renders the same images only faster.
+
+use std::process::{exit, Command};
+
+fn main() {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let status = Command::new(cargo)
+        .args(["test", "--release", "--test", "golden"])
+        .env("UPDATE_GOLDEN", "1")
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .status();
+    match status {
+        Ok(status) if status.success() => println!("wrote the golden images in tests/golden"),
+        Ok(status) => exit(status.code().unwrap_or(1)),
+        Err(error) => {
+            eprintln!("couldn't run cargo: {}", error);
+            exit(1);
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! Veach's multiple importance sampling scene: four plates of increasing
+//! glossiness reflecting four lights of increasing size. Light sampling
+//! alone is noisy on the sharp plates under large lights, BSDF sampling alone
+//! is noisy on the rough plates under small lights; MIS handles both.
+//!
+//!     cargo run --release --example veach_mis [spp] [light|bsdf|mis]
+
+use std::path::Path;
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::error::Error;
+use basic_raytracer::material::{Glossy, Lambertian};
+use basic_raytracer::render::{self, IntegratorKind, RenderSettings};
+use basic_raytracer::scene::Scene;
+use basic_raytracer::shapes::{Plane, Quad, Sphere};
+use basic_raytracer::vector::{Color, Vector};
+
+fn veach_scene() -> Scene {
+    let camera = Vector::new(0.0, 2.0, 9.0);
+    let mut scene = Scene::new(Camera::new(camera, Vector::new(0.0, 1.2, 0.0), 38.0));
+    scene.add(
+        Plane::new(Vector::new(0.0, 0.0, -4.0), Vector::new(0.0, 0.0, 1.0)),
+        Arc::new(Lambertian::new(Color::new(0.1, 0.1, 0.1))),
+    );
+
+    let lights = [(-3.0, 0.05), (-1.0, 0.15), (1.0, 0.4), (3.0, 0.9)];
+    for &(x, radius) in &lights {
+        let power = 0.8 / (radius * radius);
+        scene.add_area_light(
+            Sphere::new(Vector::new(x, 3.5, -2.5), radius),
+            Color::new(power, power, power),
+        );
+    }
+
+    // Each plate is tilted so that the camera sees the row of lights
+    // reflected across its middle.
+    let aim = Vector::new(0.0, 3.5, -2.5);
+    let plates = [
+        (0.0, 0.0, 8.0),
+        (0.7, 0.5, 40.0),
+        (1.3, 1.0, 200.0),
+        (1.8, 1.5, 2000.0),
+    ];
+    for &(y, z, exponent) in &plates {
+        let center = Vector::new(0.0, y, z);
+        let normal = ((camera - center).normalize() + (aim - center).normalize()).normalize();
+        let across = Vector::new(1.0, 0.0, 0.0);
+        let along = normal.cross(across).normalize();
+        let u = 8.0 * across;
+        let v = 0.8 * along;
+        scene.add(
+            Quad::new(center - 0.5 * u - 0.5 * v, u, v),
+            Arc::new(Glossy::new(Color::new(0.7, 0.7, 0.7), exponent)),
+        );
+    }
+    scene
+}
+
+fn main() -> Result<(), Error> {
+    let mut args = std::env::args().skip(1);
+    let spp = args
+        .next()
+        .map_or(64, |spp| spp.parse().expect("spp must be a number"));
+    let (next_event_estimation, multiple_importance_sampling) = match args.next().as_deref() {
+        Some("light") => (true, false),
+        Some("bsdf") => (false, false),
+        _ => (true, true),
+    };
+    let settings = RenderSettings {
+        width: 480,
+        height: 320,
+        integrator: IntegratorKind::Path,
+        spp,
+        max_depth: 2,
+        next_event_estimation,
+        multiple_importance_sampling,
+        ..RenderSettings::default()
+    };
+    let (image, stats) = render::render(&veach_scene(), &settings)?;
+    println!("{}", stats);
+    image.write_png(Path::new("veach_mis.png"))
+}
@@ -0,0 +1,37 @@
+//! The renderer in a web page. Built for `wasm32-unknown-unknown` without the
+//! default features, which need files and threads, it renders the demo scene
+//! into a buffer that `index.html`, beside this file, copies onto a canvas:
+//!
+//!     cargo build --release --example wasm --target wasm32-unknown-unknown --no-default-features
+//!     cp target/wasm32-unknown-unknown/release/examples/wasm.wasm examples/wasm/
+//!
+//! and then serve `examples/wasm` and open `index.html`.
+
+use std::ptr;
+use std::sync::{Mutex, PoisonError};
+
+use basic_raytracer::render::{self, RenderSettings};
+use basic_raytracer::scene::Scene;
+
+/// The last render, kept for the page to read out of the module's memory.
+static PIXELS: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+
+/// Renders the demo scene `width` by `height` and returns where its RGBA
+/// bytes start, or null if the settings can't make an image. They stay there
+/// until the next render.
+#[no_mangle]
+pub extern "C" fn render_demo(width: u32, height: u32) -> *const u8 {
+    let settings = RenderSettings {
+        width,
+        height,
+        ..RenderSettings::default()
+    };
+    let mut pixels = PIXELS.lock().unwrap_or_else(PoisonError::into_inner);
+    match render::render_to_buffer(&Scene::demo(), &settings) {
+        Ok(buffer) => {
+            *pixels = buffer;
+            pixels.as_ptr()
+        }
+        Err(_) => ptr::null(),
+    }
+}
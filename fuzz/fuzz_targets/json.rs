@@ -0,0 +1,17 @@
+//! Any bytes through the JSON parser, which has to return an error rather
+//! than panic, and has to read back what it writes of what it accepts.
+
+#![no_main]
+
+use basic_raytracer::json;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let text = match std::str::from_utf8(data) {
+        Ok(text) => text,
+        Err(_) => return,
+    };
+    if let Ok(value) = json::parse(text) {
+        assert_eq!(json::parse(&value.to_string()), Ok(value));
+    }
+});
@@ -0,0 +1,18 @@
+//! Any bytes as an OBJ file, whose mesh has to either fail to parse, fail to
+//! validate or be ready to render.
+
+#![no_main]
+
+use basic_raytracer::obj;
+use basic_raytracer::shapes::Shape;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let text = match std::str::from_utf8(data) {
+        Ok(text) => text,
+        Err(_) => return,
+    };
+    if let Ok(mesh) = obj::parse(text) {
+        let _ = mesh.validate().map(|()| mesh.bounds());
+    }
+});
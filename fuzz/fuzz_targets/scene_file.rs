@@ -0,0 +1,18 @@
+//! Any bytes as a scene file, which has to load or fail with an error. Paths
+//! in it are relative to `scenes/`, so the corpus's models and textures load.
+
+#![no_main]
+
+use std::path::Path;
+
+use basic_raytracer::scene_file;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let text = match std::str::from_utf8(data) {
+        Ok(text) => text,
+        Err(_) => return,
+    };
+    let base = Path::new(env!("CARGO_MANIFEST_DIR")).join("../scenes");
+    let _ = scene_file::parse(text, &base);
+});
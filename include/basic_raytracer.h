@@ -0,0 +1,264 @@
+/*
+ * The C API of basic-raytracer, built with the `ffi` feature:
+ *
+ *     cargo build --release --features ffi
+ *
+ * and linked against target/release/libbasic_raytracer.so (or .dylib, or
+ * basic_raytracer.dll). src/ffi.rs documents each function in full.
+ *
+ * Ownership: rt_scene_new returns a scene the caller owns and frees with
+ * rt_scene_free, once. Everything else passed by pointer is only read, or
+ * written, during the call, and stays the caller's. Functions return RT_OK
+ * or another status below, and never let a panic out; after a failure,
+ * rt_last_error says what went wrong.
+ */
+
+#ifndef BASIC_RAYTRACER_H
+#define BASIC_RAYTRACER_H
+
+/* Generated from src/ffi.rs by cbindgen; don't edit it by hand. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+#define RT_OK 0
+
+/**
+ * A pointer that mustn't be null was.
+ */
+#define RT_NULL_POINTER 1
+
+/**
+ * A parameter is out of range, such as the index of a material the scene
+ * doesn't have, or a buffer the wrong size.
+ */
+#define RT_INVALID_ARGUMENT 2
+
+/**
+ * The scene or settings can't make an image.
+ */
+#define RT_RENDER_FAILED 3
+
+/**
+ * Something went wrong inside the renderer.
+ */
+#define RT_PANIC 4
+
+/**
+ * The progress callback asked for the render to stop.
+ */
+#define RT_CANCELLED 5
+
+#define RT_LAMBERTIAN 0
+
+#define RT_GLOSSY 1
+
+#define RT_DIELECTRIC 2
+
+#define RT_EMISSIVE 3
+
+#define RT_WHITTED 0
+
+#define RT_PATH 1
+
+#define RT_AMBIENT_OCCLUSION 2
+
+/**
+ * A scene built through the API, with the materials its objects refer to.
+ */
+typedef struct RtScene RtScene;
+
+typedef struct RtVector {
+    double x;
+    double y;
+    double z;
+} RtVector;
+
+typedef struct RtCamera {
+    struct RtVector position;
+    struct RtVector look_at;
+    /**
+     * The vertical field of view in degrees.
+     */
+    double fov;
+} RtCamera;
+
+typedef struct RtMaterial {
+    /**
+     * One of `RT_LAMBERTIAN`, `RT_GLOSSY`, `RT_DIELECTRIC` or
+     * `RT_EMISSIVE`.
+     */
+    uint32_t kind;
+    /**
+     * The albedo, or the radiance of an emissive material; a dielectric
+     * doesn't use it.
+     */
+    struct RtVector color;
+    /**
+     * The exponent of a glossy material's lobe.
+     */
+    double exponent;
+    /**
+     * A dielectric's index of refraction.
+     */
+    double ior;
+} RtMaterial;
+
+typedef struct RtSphere {
+    struct RtVector center;
+    double radius;
+    uint32_t material;
+} RtSphere;
+
+typedef struct RtPlane {
+    struct RtVector point;
+    struct RtVector normal;
+    uint32_t material;
+} RtPlane;
+
+typedef struct RtPointLight {
+    struct RtVector position;
+    struct RtVector color;
+    double intensity;
+} RtPointLight;
+
+typedef struct RtSettings {
+    uint32_t width;
+    uint32_t height;
+    uint32_t spp;
+    uint32_t max_depth;
+    /**
+     * One of `RT_WHITTED`, `RT_PATH` or `RT_AMBIENT_OCCLUSION`.
+     */
+    uint32_t integrator;
+    uint64_t seed;
+} RtSettings;
+
+/**
+ * Called by `rt_render_with_progress` with how many of the image's rows
+ * are done and how many there are, and the `user_data` it was given.
+ * Returning anything but zero cancels the render.
+ */
+typedef int32_t (*RtProgress)(uint32_t, uint32_t, void*);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * A new, empty scene with a camera at the origin looking down -z, and a
+ * black background. Free it with `rt_scene_free`. Returns null only if the
+ * renderer panicked.
+ */
+struct RtScene *rt_scene_new(void);
+
+/**
+ * Frees a scene from `rt_scene_new`. Null is ignored.
+ *
+ * # Safety
+ *
+ * `scene` must be null or a scene from `rt_scene_new` not yet freed.
+ */
+void rt_scene_free(struct RtScene *scene);
+
+/**
+ * # Safety
+ *
+ * `scene` must be a live scene, and `camera` valid to read.
+ */
+int32_t rt_scene_set_camera(struct RtScene *scene, const struct RtCamera *camera);
+
+/**
+ * # Safety
+ *
+ * `scene` must be a live scene, and `color` valid to read.
+ */
+int32_t rt_scene_set_background(struct RtScene *scene, const struct RtVector *color);
+
+/**
+ * Adds a material for objects to refer to, writing its index to `index`
+ * if that isn't null.
+ *
+ * # Safety
+ *
+ * `scene` must be a live scene, `material` valid to read, and `index` null
+ * or valid to write.
+ */
+int32_t rt_scene_add_material(struct RtScene *scene,
+                              const struct RtMaterial *material,
+                              uint32_t *index);
+
+/**
+ * # Safety
+ *
+ * `scene` must be a live scene, and `sphere` valid to read.
+ */
+int32_t rt_scene_add_sphere(struct RtScene *scene, const struct RtSphere *sphere);
+
+/**
+ * # Safety
+ *
+ * `scene` must be a live scene, and `plane` valid to read.
+ */
+int32_t rt_scene_add_plane(struct RtScene *scene, const struct RtPlane *plane);
+
+/**
+ * # Safety
+ *
+ * `scene` must be a live scene, and `light` valid to read.
+ */
+int32_t rt_scene_add_point_light(struct RtScene *scene, const struct RtPointLight *light);
+
+/**
+ * Fills in the settings the renderer uses by default.
+ *
+ * # Safety
+ *
+ * `settings` must be null or valid to write.
+ */
+int32_t rt_settings_default(struct RtSettings *settings);
+
+/**
+ * Renders the scene into `buffer`, which must hold exactly `length` bytes,
+ * 4 for each pixel: 8-bit sRGB red, green and blue, then an opaque alpha,
+ * row by row from the top.
+ *
+ * # Safety
+ *
+ * `scene` must be a live scene, `settings` valid to read, and `buffer`
+ * valid to write `length` bytes to.
+ */
+int32_t rt_render(const struct RtScene *scene,
+                  const struct RtSettings *settings,
+                  uint8_t *buffer,
+                  size_t length);
+
+/**
+ * Renders like `rt_render`, but a row at a time from the top, calling
+ * `progress` after each one is in `buffer` unless it's null. The callback
+ * is made on the calling thread while the workers carry on, so it may take
+ * its time; if it returns nonzero the render stops with `RT_CANCELLED` and
+ * `buffer` holds the rows done so far.
+ *
+ * # Safety
+ *
+ * As for `rt_render`; `progress` must be safe to call with `user_data`.
+ */
+int32_t rt_render_with_progress(const struct RtScene *scene,
+                                const struct RtSettings *settings,
+                                uint8_t *buffer,
+                                size_t length,
+                                RtProgress progress,
+                                void *user_data);
+
+/**
+ * What went wrong in the last call on this thread that failed, or an
+ * empty string. It stays valid until the next call that fails.
+ */
+const char *rt_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* BASIC_RAYTRACER_H */
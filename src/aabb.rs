@@ -0,0 +1,149 @@
+use crate::ray::{Lanes, Ray, RayPacket, PACKET_SIZE};
+use crate::scalar::Scalar;
+use crate::vector::Vector;
+
+/// An axis-aligned bounding box.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Aabb {
+    pub min: Vector,
+    pub max: Vector,
+}
+
+impl Aabb {
+    pub fn new(min: Vector, max: Vector) -> Aabb {
+        Aabb { min, max }
+    }
+
+    /// A box containing nothing, which any union replaces.
+    pub fn empty() -> Aabb {
+        let inf = Scalar::INFINITY;
+        Aabb::new(Vector::new(inf, inf, inf), Vector::new(-inf, -inf, -inf))
+    }
+
+    pub fn from_points(points: &[Vector]) -> Aabb {
+        points
+            .iter()
+            .fold(Aabb::empty(), |bounds, &point| bounds.grow(point))
+    }
+
+    pub fn grow(&self, point: Vector) -> Aabb {
+        Aabb::new(
+            Vector::new(
+                self.min.x.min(point.x),
+                self.min.y.min(point.y),
+                self.min.z.min(point.z),
+            ),
+            Vector::new(
+                self.max.x.max(point.x),
+                self.max.y.max(point.y),
+                self.max.z.max(point.z),
+            ),
+        )
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        self.grow(other.min).grow(other.max)
+    }
+
+    /// The box both boxes contain, inside out if they don't overlap.
+    pub fn intersection(&self, other: &Aabb) -> Aabb {
+        Aabb::new(
+            Vector::new(
+                self.min.x.max(other.min.x),
+                self.min.y.max(other.min.y),
+                self.min.z.max(other.min.z),
+            ),
+            Vector::new(
+                self.max.x.min(other.max.x),
+                self.max.y.min(other.max.y),
+                self.max.z.min(other.max.z),
+            ),
+        )
+    }
+
+    pub fn centroid(&self) -> Vector {
+        0.5 * (self.min + self.max)
+    }
+
+    pub fn extent(&self) -> Vector {
+        self.max - self.min
+    }
+
+    /// Whether the ray passes through the box for some `t` between `t_min`
+    /// and `t_max`.
+    pub fn hit(&self, ray: &Ray, t_min: Scalar, t_max: Scalar) -> bool {
+        self.clip(ray, t_min, t_max).is_some()
+    }
+
+    /// The part of `t_min..t_max` along which the ray is inside the box, if
+    /// any.
+    pub fn clip(
+        &self,
+        ray: &Ray,
+        mut t_min: Scalar,
+        mut t_max: Scalar,
+    ) -> Option<(Scalar, Scalar)> {
+        let axes = [
+            (ray.origin.x, ray.direction.x, self.min.x, self.max.x),
+            (ray.origin.y, ray.direction.y, self.min.y, self.max.y),
+            (ray.origin.z, ray.direction.z, self.min.z, self.max.z),
+        ];
+        for (origin, direction, min, max) in axes {
+            let inverse = 1.0 / direction;
+            let mut t0 = (min - origin) * inverse;
+            let mut t1 = (max - origin) * inverse;
+            if inverse < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            // NaN from 0 * inf (a ray in the slab's plane) leaves the bounds
+            // alone.
+            if t0 > t_min {
+                t_min = t0;
+            }
+            if t1 < t_max {
+                t_max = t1;
+            }
+            if t_max < t_min {
+                return None;
+            }
+        }
+        Some((t_min, t_max))
+    }
+
+    /// [`hit`](Aabb::hit) for each ray of the packet, each with its own
+    /// `t_max`. Lanes left out of `lanes` come back false.
+    pub fn hit_packet(
+        &self,
+        packet: &RayPacket,
+        lanes: Lanes<bool>,
+        t_min: Scalar,
+        t_max: &Lanes<Scalar>,
+    ) -> Lanes<bool> {
+        let mut near = [t_min; PACKET_SIZE];
+        let mut far = *t_max;
+        let slabs = [
+            (self.min.x, self.max.x),
+            (self.min.y, self.max.y),
+            (self.min.z, self.max.z),
+        ];
+        for (axis, &(min, max)) in slabs.iter().enumerate() {
+            let (origin, inverse) = (&packet.origin[axis], &packet.inverse[axis]);
+            for i in 0..PACKET_SIZE {
+                let mut t0 = (min - origin[i]) * inverse[i];
+                let mut t1 = (max - origin[i]) * inverse[i];
+                if inverse[i] < 0.0 {
+                    std::mem::swap(&mut t0, &mut t1);
+                }
+                if t0 > near[i] {
+                    near[i] = t0;
+                }
+                if t1 < far[i] {
+                    far[i] = t1;
+                }
+            }
+        }
+        // Once a slab empties the range, later ones can only shrink it, so
+        // there's no need to stop early as `clip` does.
+        std::array::from_fn(|i| lanes[i] && far[i] >= near[i])
+    }
+}
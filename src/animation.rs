@@ -0,0 +1,419 @@
+//! Keyframed values for rendering a scene as a sequence of frames.
+//!
+//! Keyframe times are normalized: 0 is the first frame and 1 the last, so
+//! the same animation plays over any number of frames. Between keyframes
+//! values are interpolated linearly, and rotations along the shorter arc,
+//! either at a steady pace or easing in and out of each key.
+
+use std::ops;
+use std::sync::Arc;
+
+use crate::camera::Camera;
+use crate::light::PointLight;
+use crate::matrix::Matrix4;
+use crate::quaternion::Quaternion;
+use crate::scalar::{consts::PI, Scalar};
+use crate::shapes::Shape;
+use crate::vector::Vector;
+
+/// One frame of `count`, played back at `fps` frames per second.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Frame {
+    pub index: usize,
+    pub count: usize,
+    pub fps: Scalar,
+}
+
+impl Frame {
+    /// The frame's normalized time, from 0 at the first frame to 1 at the
+    /// last. A single frame is at time 0.
+    pub fn time(&self) -> Scalar {
+        if self.count <= 1 {
+            0.0
+        } else {
+            self.index as Scalar / (self.count - 1) as Scalar
+        }
+    }
+
+    /// How far into the sequence the frame is shown.
+    pub fn seconds(&self) -> Scalar {
+        self.index as Scalar / self.fps
+    }
+}
+
+/// A value that can be blended with another, `t` of the way to it.
+pub trait Interpolate: Clone {
+    fn interpolate(&self, other: &Self, t: Scalar) -> Self;
+}
+
+impl Interpolate for Scalar {
+    fn interpolate(&self, other: &Scalar, t: Scalar) -> Scalar {
+        self + t * (other - self)
+    }
+}
+
+impl Interpolate for Vector {
+    fn interpolate(&self, other: &Vector, t: Scalar) -> Vector {
+        *self + t * (*other - *self)
+    }
+}
+
+impl Interpolate for Quaternion {
+    fn interpolate(&self, other: &Quaternion, t: Scalar) -> Quaternion {
+        self.slerp(*other, t)
+    }
+}
+
+impl Interpolate for Camera {
+    fn interpolate(&self, other: &Camera, t: Scalar) -> Camera {
+        Camera {
+            position: self.position.interpolate(&other.position, t),
+            look_at: self.look_at.interpolate(&other.look_at, t),
+            up: self.up.interpolate(&other.up, t),
+            fov: self.fov.interpolate(&other.fov, t),
+        }
+    }
+}
+
+impl Interpolate for PointLight {
+    fn interpolate(&self, other: &PointLight, t: Scalar) -> PointLight {
+        PointLight::new(
+            self.source.interpolate(&other.source, t),
+            self.color.interpolate(&other.color, t),
+            self.intensity.interpolate(&other.intensity, t),
+        )
+    }
+}
+
+/// How a value makes its way from one key to the next.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum Easing {
+    /// At a steady pace.
+    #[default]
+    Linear,
+    /// Starting and stopping gently, along a smoothstep.
+    Smooth,
+}
+
+impl Easing {
+    fn apply(self, t: Scalar) -> Scalar {
+        match self {
+            Easing::Linear => t,
+            Easing::Smooth => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Key<T> {
+    pub time: Scalar,
+    pub value: T,
+    /// How the value moves on to the next key.
+    pub easing: Easing,
+}
+
+/// Values at increasing times, held before the first and after the last.
+#[derive(Debug, Clone)]
+pub struct Keyframes<T> {
+    keys: Vec<Key<T>>,
+}
+
+impl<T: Interpolate> Keyframes<T> {
+    /// Keys moving at a steady pace from one to the next. Panics if there
+    /// are none.
+    pub fn new(keys: Vec<(Scalar, T)>) -> Keyframes<T> {
+        Keyframes::from_keys(
+            keys.into_iter()
+                .map(|(time, value)| Key {
+                    time,
+                    value,
+                    easing: Easing::Linear,
+                })
+                .collect(),
+        )
+    }
+
+    /// Panics if there are no keys.
+    pub fn from_keys(mut keys: Vec<Key<T>>) -> Keyframes<T> {
+        assert!(!keys.is_empty(), "keyframes need at least one key");
+        keys.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Keyframes { keys }
+    }
+
+    pub fn keys(&self) -> &[Key<T>] {
+        &self.keys
+    }
+
+    pub fn at(&self, time: Scalar) -> T {
+        let after = self.keys.partition_point(|key| key.time <= time);
+        if after == 0 {
+            return self.keys[0].value.clone();
+        }
+        let key = &self.keys[after - 1];
+        match self.keys.get(after) {
+            Some(next) => {
+                let t = (time - key.time) / (next.time - key.time);
+                key.value.interpolate(&next.value, key.easing.apply(t))
+            }
+            None => key.value.clone(),
+        }
+    }
+}
+
+/// A scale, rotation and translation, applied in that order. Poses are
+/// interpolated part by part, so that a turning object keeps its shape
+/// instead of shearing the way blended matrices would.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Pose {
+    pub scale: Vector,
+    pub rotate: Quaternion,
+    pub translate: Vector,
+}
+
+impl Pose {
+    pub fn identity() -> Pose {
+        Pose {
+            scale: Vector::new(1.0, 1.0, 1.0),
+            rotate: Quaternion::identity(),
+            translate: Vector::zero(),
+        }
+    }
+
+    pub fn to_matrix(&self) -> Matrix4 {
+        Matrix4::translate(self.translate) * self.rotate.to_matrix() * Matrix4::scale(self.scale)
+    }
+}
+
+impl Interpolate for Pose {
+    fn interpolate(&self, other: &Pose, t: Scalar) -> Pose {
+        Pose {
+            scale: self.scale.interpolate(&other.scale, t),
+            rotate: self.rotate.interpolate(&other.rotate, t),
+            translate: self.translate.interpolate(&other.translate, t),
+        }
+    }
+}
+
+/// A transform made of fixed matrices and keyframed poses, which compose
+/// like matrices: `a * b` applies `b` first. A keyframed transform in a
+/// scene graph makes everything below it move.
+#[derive(Debug, Clone)]
+pub struct Motion {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone)]
+enum Part {
+    Fixed(Matrix4),
+    Keyed(Keyframes<Pose>),
+}
+
+impl Motion {
+    pub fn fixed(matrix: Matrix4) -> Motion {
+        Motion {
+            parts: vec![Part::Fixed(matrix)],
+        }
+    }
+
+    pub fn keyed(poses: Keyframes<Pose>) -> Motion {
+        Motion {
+            parts: vec![Part::Keyed(poses)],
+        }
+    }
+
+    /// The matrix at `time`.
+    pub fn at(&self, time: Scalar) -> Matrix4 {
+        self.parts
+            .iter()
+            .fold(Matrix4::identity(), |matrix, part| match part {
+                Part::Fixed(fixed) => matrix * *fixed,
+                Part::Keyed(poses) => matrix * poses.at(time).to_matrix(),
+            })
+    }
+
+    /// The matrix it's always at, unless some of it is keyframed.
+    pub fn fixed_matrix(&self) -> Option<Matrix4> {
+        match self.parts[..] {
+            [Part::Fixed(matrix)] => Some(matrix),
+            _ => None,
+        }
+    }
+}
+
+impl From<Keyframes<Pose>> for Motion {
+    fn from(poses: Keyframes<Pose>) -> Motion {
+        Motion::keyed(poses)
+    }
+}
+
+impl ops::Mul<Motion> for Motion {
+    type Output = Motion;
+
+    fn mul(self, other: Motion) -> Motion {
+        let mut parts = self.parts;
+        for part in other.parts {
+            match (parts.last_mut(), part) {
+                (Some(Part::Fixed(last)), Part::Fixed(next)) => *last = *last * next,
+                (_, part) => parts.push(part),
+            }
+        }
+        Motion { parts }
+    }
+}
+
+/// A camera flown through keys. Its position follows a Catmull-Rom spline,
+/// passing through each key without a jolt in its velocity; it turns from
+/// facing one key's `look_at` to the next along the shorter arc, and its
+/// field of view and its distance to what it looks at change linearly.
+#[derive(Debug, Clone)]
+pub struct CameraPath {
+    keys: Vec<PathKey>,
+}
+
+#[derive(Debug, Clone)]
+struct PathKey {
+    time: Scalar,
+    camera: Camera,
+    rotation: Quaternion,
+    distance: Scalar,
+}
+
+impl CameraPath {
+    /// Panics if there are no keys.
+    pub fn new(mut keys: Vec<(Scalar, Camera)>) -> CameraPath {
+        assert!(!keys.is_empty(), "a camera path needs at least one key");
+        keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let keys = keys
+            .into_iter()
+            .map(|(time, camera)| {
+                let forward = (camera.look_at - camera.position).normalize();
+                let right = forward.cross(camera.up).normalize();
+                let up = right.cross(forward);
+                // The camera looks down -z with y up, as `Camera::ray` sees it.
+                let basis = Matrix4::new([
+                    [right.x, up.x, -forward.x, 0.0],
+                    [right.y, up.y, -forward.y, 0.0],
+                    [right.z, up.z, -forward.z, 0.0],
+                    [0.0, 0.0, 0.0, 1.0],
+                ]);
+                PathKey {
+                    time,
+                    camera,
+                    rotation: Quaternion::from_matrix(&basis),
+                    distance: (camera.look_at - camera.position).len(),
+                }
+            })
+            .collect();
+        CameraPath { keys }
+    }
+
+    /// The camera at `time`, held at the first and last keys outside them.
+    pub fn at(&self, time: Scalar) -> Camera {
+        let keys = &self.keys;
+        let next = keys.partition_point(|key| key.time <= time);
+        if next == 0 {
+            return keys[0].camera;
+        }
+        if next == keys.len() {
+            return keys[next - 1].camera;
+        }
+        let (a, b) = (&keys[next - 1], &keys[next]);
+        let span = b.time - a.time;
+        let t = (time - a.time) / span;
+        // The cubic Hermite curve from a to b with the velocities through
+        // them, which is what a Catmull-Rom spline is made of.
+        let (t2, t3) = (t * t, t * t * t);
+        let position = (2.0 * t3 - 3.0 * t2 + 1.0) * a.camera.position
+            + (span * (t3 - 2.0 * t2 + t)) * self.velocity(next - 1)
+            + (3.0 * t2 - 2.0 * t3) * b.camera.position
+            + (span * (t3 - t2)) * self.velocity(next);
+        let rotation = a.rotation.slerp(b.rotation, t).to_matrix();
+        let forward = rotation.transform_direction(Vector::new(0.0, 0.0, -1.0));
+        Camera {
+            position,
+            look_at: position + a.distance.interpolate(&b.distance, t) * forward,
+            up: rotation.transform_direction(Vector::new(0.0, 1.0, 0.0)),
+            fov: a.camera.fov.interpolate(&b.camera.fov, t),
+        }
+    }
+
+    /// How fast the camera moves through key `i`: along the line between
+    /// the keys either side of it, or toward its one neighbour at the ends.
+    fn velocity(&self, i: usize) -> Vector {
+        let before = &self.keys[i.saturating_sub(1)];
+        let after = &self.keys[(i + 1).min(self.keys.len() - 1)];
+        (1.0 / (after.time - before.time)) * (after.camera.position - before.camera.position)
+    }
+}
+
+/// A camera orbiting `pivot` once over a sequence, `radius` away and
+/// `elevation` degrees above it, always looking at it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Turntable {
+    pub pivot: Vector,
+    pub radius: Scalar,
+    pub elevation: Scalar,
+}
+
+impl Turntable {
+    /// The orbit `camera` is already on, around the point it looks at.
+    pub fn around(camera: &Camera) -> Turntable {
+        let offset = camera.position - camera.look_at;
+        let radius = offset.len();
+        Turntable {
+            pivot: camera.look_at,
+            radius,
+            elevation: (offset.y / radius).asin().to_degrees(),
+        }
+    }
+
+    /// `camera` moved to where it is at `frame`. The orbit starts on the +z
+    /// side of the pivot and turns by the same angle each frame, so that the
+    /// frame after the last would be the first again and the sequence loops.
+    pub fn camera(&self, camera: &Camera, frame: Frame) -> Camera {
+        let angle = 2.0 * PI * frame.index as Scalar / frame.count.max(1) as Scalar;
+        let elevation = self.elevation.to_radians();
+        let offset = Vector::new(
+            elevation.cos() * angle.sin(),
+            elevation.sin(),
+            elevation.cos() * angle.cos(),
+        );
+        Camera {
+            position: self.pivot + self.radius * offset,
+            look_at: self.pivot,
+            ..*camera
+        }
+    }
+}
+
+/// An object moved on top of the transform it was added with.
+#[derive(Clone)]
+pub struct AnimatedObject {
+    /// The object's index in `Scene::objects`.
+    pub object: usize,
+    /// The object's shape as it was added, which the motion moves.
+    pub shape: Arc<dyn Shape>,
+    pub motion: Motion,
+}
+
+/// Everything in a scene that changes from frame to frame; see
+/// [`Scene::set_frame`](crate::scene::Scene::set_frame).
+#[derive(Clone, Default)]
+pub struct Animation {
+    pub objects: Vec<AnimatedObject>,
+    /// Point lights by their index in `Scene::lights`.
+    pub lights: Vec<(usize, Keyframes<PointLight>)>,
+    pub camera: Option<Keyframes<Camera>>,
+    /// A path the camera flies along, in place of `camera`.
+    pub camera_path: Option<CameraPath>,
+}
+
+impl Animation {
+    /// Whether nothing changes, so every frame is the same.
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+            && self.lights.is_empty()
+            && self.camera.is_none()
+            && self.camera_path.is_none()
+    }
+}
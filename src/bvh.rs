@@ -0,0 +1,229 @@
+use crate::aabb::Aabb;
+use crate::ray::{Lanes, Ray, RayPacket, PACKET_SIZE};
+use crate::scalar::Scalar;
+use crate::stats;
+
+const LEAF_SIZE: usize = 4;
+
+/// A bounding volume hierarchy over a list of primitives, identified by
+/// their index in the list it was built from.
+pub struct Bvh {
+    nodes: Vec<Node>,
+    order: Vec<usize>,
+}
+
+/// The shape of a hierarchy: how many nodes it has, how many of them are
+/// leaves, and how many nodes are on the longest path from the root down.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct BvhStats {
+    pub nodes: usize,
+    pub leaves: usize,
+    pub depth: usize,
+}
+
+struct Node {
+    bounds: Aabb,
+    // For leaves, the range of `order` holding their primitives; interior
+    // nodes have `count` 0, their first child right after them and their
+    // second child at `start`.
+    start: usize,
+    count: usize,
+}
+
+impl Bvh {
+    pub fn new(bounds: &[Aabb]) -> Bvh {
+        let mut bvh = Bvh {
+            nodes: Vec::new(),
+            order: (0..bounds.len()).collect(),
+        };
+        if !bounds.is_empty() {
+            bvh.build(bounds, 0, bounds.len());
+        }
+        bvh
+    }
+
+    fn build(&mut self, bounds: &[Aabb], start: usize, end: usize) -> usize {
+        let items = &mut self.order[start..end];
+        let total = items
+            .iter()
+            .fold(Aabb::empty(), |total, &i| total.union(&bounds[i]));
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            bounds: total,
+            start,
+            count: end - start,
+        });
+        if end - start <= LEAF_SIZE {
+            return index;
+        }
+        let centroids = items.iter().fold(Aabb::empty(), |centroids, &i| {
+            centroids.grow(bounds[i].centroid())
+        });
+        let extent = centroids.extent();
+        let axis = |i: usize| {
+            let c = bounds[i].centroid();
+            if extent.x >= extent.y && extent.x >= extent.z {
+                c.x
+            } else if extent.y >= extent.z {
+                c.y
+            } else {
+                c.z
+            }
+        };
+        let mid = items.len() / 2;
+        items.select_nth_unstable_by(mid, |&a, &b| axis(a).total_cmp(&axis(b)));
+        self.build(bounds, start, start + mid);
+        let second = self.build(bounds, start + mid, end);
+        self.nodes[index].start = second;
+        self.nodes[index].count = 0;
+        index
+    }
+
+    /// The memory the hierarchy holds on the heap.
+    pub fn heap_bytes(&self) -> usize {
+        self.nodes.capacity() * std::mem::size_of::<Node>()
+            + self.order.capacity() * std::mem::size_of::<usize>()
+    }
+
+    pub fn stats(&self) -> BvhStats {
+        let mut stats = BvhStats {
+            nodes: self.nodes.len(),
+            ..BvhStats::default()
+        };
+        let mut stack = match self.nodes.is_empty() {
+            true => vec![],
+            false => vec![(0, 1)],
+        };
+        while let Some((index, depth)) = stack.pop() {
+            let node = &self.nodes[index];
+            stats.depth = stats.depth.max(depth);
+            if node.count > 0 {
+                stats.leaves += 1;
+            } else {
+                stack.push((index + 1, depth + 1));
+                stack.push((node.start, depth + 1));
+            }
+        }
+        stats
+    }
+
+    /// Walks the primitives whose bounds the ray passes through, nearest
+    /// subtrees first. `hit` is given a primitive and the current `t_max`
+    /// and returns the `t` of an intersection closer than it, if any.
+    /// Returns the closest `t` found.
+    pub fn intersect<F>(
+        &self,
+        ray: &Ray,
+        t_min: Scalar,
+        t_max: Scalar,
+        mut hit: F,
+    ) -> Option<Scalar>
+    where
+        F: FnMut(usize, Scalar) -> Option<Scalar>,
+    {
+        if self.nodes.is_empty() {
+            return None;
+        }
+        let mut closest = t_max;
+        let mut found = false;
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !node.bounds.hit(ray, t_min, closest) {
+                continue;
+            }
+            if node.count > 0 {
+                for &primitive in &self.order[node.start..node.start + node.count] {
+                    if let Some(t) = hit(primitive, closest) {
+                        closest = t;
+                        found = true;
+                    }
+                }
+            } else {
+                let (first, second) = (index + 1, node.start);
+                // Visit the child nearer along the ray first.
+                let near_first = self.nodes[first].bounds.centroid() * ray.direction
+                    <= self.nodes[second].bounds.centroid() * ray.direction;
+                if near_first {
+                    stack.push(second);
+                    stack.push(first);
+                } else {
+                    stack.push(first);
+                    stack.push(second);
+                }
+            }
+        }
+        if found {
+            Some(closest)
+        } else {
+            None
+        }
+    }
+
+    /// [`intersect`](Bvh::intersect) for a packet of rays, each with its
+    /// own `t_max`, walking the tree once for all of them. `hit` is given a
+    /// primitive, the lanes whose rays pass through its node's bounds and
+    /// their current `t_max`, and returns the `t` of each closer
+    /// intersection. Children are visited nearest first along the first of
+    /// those rays, which for coherent rays is nearest first for all of them.
+    pub fn intersect_packet<F>(
+        &self,
+        packet: &RayPacket,
+        t_min: Scalar,
+        t_max: Lanes<Scalar>,
+        mut hit: F,
+    ) -> Lanes<Option<Scalar>>
+    where
+        F: FnMut(usize, Lanes<bool>, &Lanes<Scalar>) -> Lanes<Option<Scalar>>,
+    {
+        let mut closest = t_max;
+        let mut found = [false; PACKET_SIZE];
+        // Counted here and recorded once, which costs less than at each test.
+        let (mut tests, mut used) = (0, 0);
+        let mut stack = if self.nodes.is_empty() {
+            Vec::new()
+        } else {
+            vec![0]
+        };
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            let lanes = node
+                .bounds
+                .hit_packet(packet, packet.active, t_min, &closest);
+            let count = lanes.iter().filter(|&&lane| lane).count() as u64;
+            tests += 1;
+            used += count;
+            let first = match lanes.iter().position(|&lane| lane) {
+                Some(first) => first,
+                None => continue,
+            };
+            if node.count > 0 {
+                for &primitive in &self.order[node.start..node.start + node.count] {
+                    tests += 1;
+                    used += count;
+                    let hits = hit(primitive, lanes, &closest);
+                    for i in 0..PACKET_SIZE {
+                        if let Some(t) = hits[i] {
+                            closest[i] = t;
+                            found[i] = true;
+                        }
+                    }
+                }
+            } else {
+                let direction = packet.rays[first].direction;
+                let (first, second) = (index + 1, node.start);
+                let near_first = self.nodes[first].bounds.centroid() * direction
+                    <= self.nodes[second].bounds.centroid() * direction;
+                if near_first {
+                    stack.push(second);
+                    stack.push(first);
+                } else {
+                    stack.push(first);
+                    stack.push(second);
+                }
+            }
+        }
+        stats::record_packet_tests(tests, used);
+        std::array::from_fn(|i| found[i].then_some(closest[i]))
+    }
+}
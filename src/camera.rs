@@ -0,0 +1,102 @@
+use crate::check;
+use crate::matrix::Matrix4;
+use crate::ray::Ray;
+use crate::scalar::{consts::PI, Scalar};
+use crate::vector::Vector;
+
+/// A pinhole camera; `fov` is the horizontal field of view in degrees.
+#[derive(Debug, Copy, Clone)]
+pub struct Camera {
+    pub position: Vector,
+    pub look_at: Vector,
+    pub up: Vector,
+    pub fov: Scalar,
+}
+
+impl Camera {
+    pub fn new(position: Vector, look_at: Vector, fov: Scalar) -> Camera {
+        Camera {
+            position,
+            look_at,
+            up: Vector::new(0.0, 1.0, 0.0),
+            fov,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        check::point("position", self.position)?;
+        check::point("look_at", self.look_at)?;
+        if self.look_at == self.position {
+            return Err("look_at: expected a point away from the position".to_string());
+        }
+        check::direction("up", self.up)?;
+        if !(self.fov > 0.0 && self.fov < 180.0) {
+            return Err(format!(
+                "fov: expected an angle between 0 and 180 degrees, found {}",
+                self.fov
+            ));
+        }
+        Ok(())
+    }
+
+    /// The directions right, up and forward from the camera's point of view.
+    fn basis(&self) -> (Vector, Vector, Vector) {
+        let forward = (self.look_at - self.position).normalize();
+        let right = forward.cross(self.up).normalize();
+        (right, right.cross(forward), forward)
+    }
+
+    /// The camera and its target moved by `offset`, measured to the right,
+    /// up and forward from the camera's point of view.
+    pub fn translated(&self, offset: Vector) -> Camera {
+        let (right, up, forward) = self.basis();
+        let offset = offset.x * right + offset.y * up + offset.z * forward;
+        Camera {
+            position: self.position + offset,
+            look_at: self.look_at + offset,
+            ..*self
+        }
+    }
+
+    /// The camera swung around its target, `yaw` radians about `up` and
+    /// `pitch` radians up over the target, stopping short of looking
+    /// straight down or up.
+    pub fn orbited(&self, yaw: Scalar, pitch: Scalar) -> Camera {
+        let up = self.up.normalize();
+        let offset = Matrix4::rotate(up, yaw).transform_direction(self.position - self.look_at);
+        let distance = offset.len();
+        let along = offset * up;
+        let across = offset - along * up;
+        let across = if across.len() > 1e-12 * distance {
+            across.normalize()
+        } else {
+            up.cross(self.basis().0).normalize()
+        };
+        let limit = 1e-3;
+        let polar = ((along / distance).clamp(-1.0, 1.0).acos() - pitch).clamp(limit, PI - limit);
+        Camera {
+            position: self.look_at + distance * (polar.sin() * across + polar.cos() * up),
+            ..*self
+        }
+    }
+
+    /// The camera moved along its line of sight so that it's `factor` times
+    /// as far from its target.
+    pub fn dollied(&self, factor: Scalar) -> Camera {
+        Camera {
+            position: self.look_at + factor * (self.position - self.look_at),
+            ..*self
+        }
+    }
+
+    /// The ray through image coordinates `(u, v)`, both in `[0, 1]` with `v`
+    /// pointing down the image.
+    pub fn ray(&self, u: Scalar, v: Scalar, aspect: Scalar) -> Ray {
+        let (right, up, forward) = self.basis();
+        let half_width = (self.fov.to_radians() / 2.0).tan();
+        let half_height = half_width / aspect;
+        let direction =
+            forward + ((2.0 * u - 1.0) * half_width) * right + ((1.0 - 2.0 * v) * half_height) * up;
+        Ray::new(self.position, direction.normalize())
+    }
+}
@@ -0,0 +1,90 @@
+//! Checks shared by the `validate` methods of shapes, lights and the camera.
+//!
+//! Each names the field it checks at the start of its message, as in
+//! `radius: expected a positive number, found -1`, so that callers can put
+//! the path to the object in front and get `spheres[0].radius: ...`.
+
+use std::fmt::Display;
+
+use crate::scalar::Scalar;
+use crate::vector::Vector;
+
+fn coordinates(v: Vector) -> String {
+    format!("({}, {}, {})", v.x, v.y, v.z)
+}
+
+fn is_finite(v: Vector) -> bool {
+    v.x.is_finite() && v.y.is_finite() && v.z.is_finite()
+}
+
+/// Puts `path` and a `.` in front of a message from one of these checks.
+pub(crate) fn within(path: impl Display) -> impl Fn(String) -> String {
+    move |message| format!("{}.{}", path, message)
+}
+
+pub(crate) fn point(field: &str, v: Vector) -> Result<(), String> {
+    if is_finite(v) {
+        Ok(())
+    } else {
+        Err(format!(
+            "{}: expected finite coordinates, found {}",
+            field,
+            coordinates(v)
+        ))
+    }
+}
+
+/// A direction or normal, which also can't be zero.
+pub(crate) fn direction(field: &str, v: Vector) -> Result<(), String> {
+    if is_finite(v) && v != Vector::zero() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{}: expected a finite, nonzero direction, found {}",
+            field,
+            coordinates(v)
+        ))
+    }
+}
+
+pub(crate) fn color(field: &str, c: Vector) -> Result<(), String> {
+    if is_finite(c) && c.x >= 0.0 && c.y >= 0.0 && c.z >= 0.0 {
+        Ok(())
+    } else {
+        Err(format!(
+            "{}: expected a finite color with no negative channels, found {}",
+            field,
+            coordinates(c)
+        ))
+    }
+}
+
+pub(crate) fn finite(field: &str, x: Scalar) -> Result<(), String> {
+    if x.is_finite() {
+        Ok(())
+    } else {
+        Err(format!("{}: expected a finite number, found {}", field, x))
+    }
+}
+
+pub(crate) fn positive(field: &str, x: Scalar) -> Result<(), String> {
+    if x.is_finite() && x > 0.0 {
+        Ok(())
+    } else {
+        Err(format!(
+            "{}: expected a positive number, found {}",
+            field, x
+        ))
+    }
+}
+
+pub(crate) fn non_negative(field: &str, x: Scalar) -> Result<(), String> {
+    if x.is_finite() && x >= 0.0 {
+        Ok(())
+    } else {
+        Err(format!(
+            "{}: expected a non-negative number, found {}",
+            field, x
+        ))
+    }
+}
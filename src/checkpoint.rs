@@ -0,0 +1,134 @@
+//! Checkpoints of a render in progress, so that one stopped part way can be
+//! picked up where it left off.
+//!
+//! A checkpoint holds the running mean of every pixel and how many samples
+//! went into it. Nothing else of the sampler needs keeping: each sample is
+//! seeded by its pixel and index, so the render carries on with the sample
+//! after the last one. Every pass samples every pixel, so one count covers
+//! them all.
+
+use std::convert::TryInto;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Write};
+use std::path::Path;
+
+use crate::error::Error;
+use crate::image::Image;
+use crate::render::RenderSettings;
+use crate::scalar::{self, Scalar};
+use crate::scene::Scene;
+use crate::tile::TileOrder;
+use crate::vector::Color;
+
+const MAGIC: &[u8; 8] = b"RTCKPT01";
+
+pub struct Checkpoint {
+    /// Which scene and settings the samples were taken with, from
+    /// [`fingerprint`].
+    pub fingerprint: u64,
+    /// How many samples each pixel has.
+    pub samples: u32,
+    pub image: Image,
+}
+
+/// 64-bit FNV-1a.
+struct Hasher(u64);
+
+impl Hasher {
+    fn add(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x100_0000_01b3);
+        }
+        // Keeps "ab", "c" apart from "a", "bc".
+        self.0 = (self.0 ^ 0xff).wrapping_mul(0x100_0000_01b3);
+    }
+}
+
+/// A hash of what a render's samples depend on: `source`, the text the scene
+/// was read from, what's been made of it, and `settings`. Resuming with any
+/// of them changed would mix samples of two different images.
+pub fn fingerprint(scene: &Scene, source: &[u8], settings: &RenderSettings) -> u64 {
+    let mut hasher = Hasher(0xcbf2_9ce4_8422_2325);
+    hasher.add(source);
+    hasher.add(format!("{:?}", scene.camera).as_bytes());
+    hasher.add(format!("{:?}", scene.medium).as_bytes());
+    hasher.add(&scene.geometry_bytes().to_le_bytes());
+    hasher.add(scene.layer_names().join(",").as_bytes());
+    // Neither the order the tiles go in, how many threads render them, nor
+    // how their rays are traced changes them.
+    let settings = RenderSettings {
+        tile_order: TileOrder::Scanline,
+        packets: true,
+        threads: 0,
+        ..settings.clone()
+    };
+    hasher.add(format!("{:?}", settings).as_bytes());
+    hasher.0
+}
+
+fn io<'a>(path: &'a Path, operation: &'static str) -> impl FnOnce(io::Error) -> Error + 'a {
+    move |source| Error::Io {
+        path: path.to_path_buf(),
+        operation,
+        source,
+    }
+}
+
+impl Checkpoint {
+    /// Writes the checkpoint to a file next to `path` and then renames it
+    /// over `path`, so that a crash while it's written leaves the last
+    /// checkpoint as it was.
+    pub fn write(&self, path: &Path) -> Result<(), Error> {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let temporary = path.with_file_name(format!(".{}.tmp", name));
+        let mut data = Vec::with_capacity(28 + 24 * self.image.pixels.len());
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&self.fingerprint.to_le_bytes());
+        for value in [self.samples, self.image.width, self.image.height] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        for color in &self.image.pixels {
+            // Always kept as `f64`, whatever the math is done in.
+            for channel in [color.x, color.y, color.z] {
+                data.extend_from_slice(&scalar::to_f64(channel).to_le_bytes());
+            }
+        }
+        let mut file = File::create(&temporary).map_err(io(&temporary, "create"))?;
+        file.write_all(&data).map_err(io(&temporary, "write"))?;
+        file.sync_all().map_err(io(&temporary, "write"))?;
+        fs::rename(&temporary, path).map_err(io(path, "write"))
+    }
+
+    pub fn read(path: &Path) -> Result<Checkpoint, Error> {
+        let file = File::open(path).map_err(io(path, "open"))?;
+        let mut data = Vec::new();
+        BufReader::new(file)
+            .read_to_end(&mut data)
+            .map_err(io(path, "read"))?;
+        let invalid =
+            |message: &str| io(path, "read")(io::Error::new(io::ErrorKind::InvalidData, message));
+        if data.len() < 28 || &data[..8] != MAGIC {
+            return Err(invalid("not a checkpoint"));
+        }
+        let u32_at = |at: usize| u32::from_le_bytes(data[at..at + 4].try_into().unwrap());
+        let fingerprint = u64::from_le_bytes(data[8..16].try_into().unwrap());
+        let (samples, width, height) = (u32_at(16), u32_at(20), u32_at(24));
+        let pixels = &data[28..];
+        if pixels.len() as u64 != 24 * width as u64 * height as u64 {
+            return Err(invalid("the checkpoint is cut short"));
+        }
+        let channels: Vec<Scalar> = pixels
+            .chunks(8)
+            .map(|bytes| f64::from_le_bytes(bytes.try_into().unwrap()) as Scalar)
+            .collect();
+        let mut image = Image::new(width, height);
+        for (pixel, rgb) in image.pixels.iter_mut().zip(channels.chunks(3)) {
+            *pixel = Color::new(rgb[0], rgb[1], rgb[2]);
+        }
+        Ok(Checkpoint {
+            fingerprint,
+            samples,
+            image,
+        })
+    }
+}
@@ -0,0 +1,178 @@
+//! How far apart two images are, for telling what a change did to a render.
+//!
+//! Images are compared as the linear light they hold, however they were
+//! stored, and PSNR and SSIM take 1 to be white. SSIM is the usual one of
+//! Wang et al., over an 11-pixel Gaussian window of deviation 1.5 cut off at
+//! the edges of the image, averaged over the three channels.
+
+use std::fmt;
+
+use crate::image::Image;
+use crate::json::Value;
+use crate::scalar::{self, Scalar};
+use crate::vector::Color;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Comparison {
+    /// The mean absolute difference of each channel.
+    pub mean_absolute_error: Color,
+    /// The peak signal-to-noise ratio in decibels, infinite for identical
+    /// images.
+    pub psnr: f64,
+    /// The structural similarity, 1 for identical images.
+    pub ssim: f64,
+}
+
+impl Comparison {
+    /// The mean absolute error over all three channels.
+    pub fn error(&self) -> f64 {
+        let e = self.mean_absolute_error;
+        scalar::to_f64(e.x + e.y + e.z) / 3.0
+    }
+
+    /// The comparison as a JSON object, with a `psnr` of `null` for
+    /// identical images.
+    pub fn to_json(&self) -> Value {
+        let e = self.mean_absolute_error;
+        let channels = [e.x, e.y, e.z]
+            .iter()
+            .map(|&c| Value::Number(scalar::to_f64(c)))
+            .collect();
+        let psnr = match self.psnr.is_finite() {
+            true => Value::Number(self.psnr),
+            false => Value::Null,
+        };
+        Value::Object(vec![
+            ("mean_absolute_error".to_string(), Value::Array(channels)),
+            ("psnr".to_string(), psnr),
+            ("ssim".to_string(), Value::Number(self.ssim)),
+        ])
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let e = self.mean_absolute_error;
+        writeln!(
+            f,
+            "mean absolute error: {:.6} (r {:.6}, g {:.6}, b {:.6})",
+            self.error(),
+            e.x,
+            e.y,
+            e.z
+        )?;
+        writeln!(f, "PSNR: {:.2} dB", self.psnr)?;
+        write!(f, "SSIM: {:.6}", self.ssim)
+    }
+}
+
+fn channel(image: &Image, index: usize) -> Vec<f64> {
+    image
+        .pixels
+        .iter()
+        .map(|color| scalar::to_f64([color.x, color.y, color.z][index]))
+        .collect()
+}
+
+/// `values` blurred by the SSIM window, weighing what's left of it at the
+/// edges as the whole.
+fn blur(values: &[f64], width: usize, height: usize) -> Vec<f64> {
+    let weights: Vec<f64> = (-5..=5)
+        .map(|k: i32| (-f64::from(k * k) / (2.0 * 1.5 * 1.5)).exp())
+        .collect();
+    let pass = |values: &[f64], along: usize, stride: usize, across: usize| {
+        let mut out = vec![0.0; values.len()];
+        for line in 0..across {
+            let start = line * if stride == 1 { along } else { 1 };
+            for i in 0..along {
+                let (mut sum, mut total) = (0.0, 0.0);
+                for (k, weight) in weights.iter().enumerate() {
+                    let j = i as i64 + k as i64 - 5;
+                    if 0 <= j && j < along as i64 {
+                        sum += weight * values[start + j as usize * stride];
+                        total += weight;
+                    }
+                }
+                out[start + i * stride] = sum / total;
+            }
+        }
+        out
+    };
+    let rows = pass(values, width, 1, height);
+    pass(&rows, height, width, width)
+}
+
+fn ssim(a: &[f64], b: &[f64], width: usize, height: usize) -> f64 {
+    let (c1, c2) = (0.01f64.powi(2), 0.03f64.powi(2));
+    let product = |x: &[f64], y: &[f64]| -> Vec<f64> {
+        let product: Vec<f64> = x.iter().zip(y).map(|(x, y)| x * y).collect();
+        blur(&product, width, height)
+    };
+    let (mean_a, mean_b) = (blur(a, width, height), blur(b, width, height));
+    let (aa, bb, ab) = (product(a, a), product(b, b), product(a, b));
+    let mut total = 0.0;
+    for i in 0..a.len() {
+        let (ma, mb) = (mean_a[i], mean_b[i]);
+        let (va, vb, cov) = (aa[i] - ma * ma, bb[i] - mb * mb, ab[i] - ma * mb);
+        total +=
+            (2.0 * ma * mb + c1) * (2.0 * cov + c2) / ((ma * ma + mb * mb + c1) * (va + vb + c2));
+    }
+    total / a.len() as f64
+}
+
+/// Compares `a` with `b`, which have to be the same size.
+pub fn compare(a: &Image, b: &Image) -> Result<Comparison, String> {
+    if (a.width, a.height) != (b.width, b.height) {
+        return Err(format!(
+            "the images are different sizes, {}x{} and {}x{}",
+            a.width, a.height, b.width, b.height
+        ));
+    }
+    if a.pixels.is_empty() {
+        return Err("the images are empty".to_string());
+    }
+    let (width, height) = (a.width as usize, a.height as usize);
+    let mut mean_absolute_error = Color::zero();
+    let (mut squares, mut structure) = (0.0, 0.0);
+    for (index, error) in [
+        &mut mean_absolute_error.x,
+        &mut mean_absolute_error.y,
+        &mut mean_absolute_error.z,
+    ]
+    .iter_mut()
+    .enumerate()
+    {
+        let (a, b) = (channel(a, index), channel(b, index));
+        let mut absolute = 0.0;
+        for (x, y) in a.iter().zip(&b) {
+            absolute += (x - y).abs();
+            squares += (x - y) * (x - y);
+        }
+        **error = (absolute / a.len() as f64) as Scalar;
+        structure += ssim(&a, &b, width, height);
+    }
+    let mean_square = squares / (3 * a.pixels.len()) as f64;
+    Ok(Comparison {
+        mean_absolute_error,
+        psnr: -10.0 * mean_square.log10(),
+        ssim: structure / 3.0,
+    })
+}
+
+/// A heat map of how far apart `a` and `b`, the same size, are at each
+/// pixel: the channels' mean absolute difference times `gain`, from black
+/// through red and yellow to white at 1 and over.
+pub fn heatmap(a: &Image, b: &Image, gain: Scalar) -> Image {
+    let mut map = Image::new(a.width, a.height);
+    for ((out, p), q) in map.pixels.iter_mut().zip(&a.pixels).zip(&b.pixels) {
+        let off = *p - *q;
+        // A third of the way along the ramp for each of red, green and blue.
+        let heat = gain * (off.x.abs() + off.y.abs() + off.z.abs());
+        *out = Color::new(
+            heat.clamp(0.0, 1.0),
+            (heat - 1.0).clamp(0.0, 1.0),
+            (heat - 2.0).clamp(0.0, 1.0),
+        );
+    }
+    map
+}
@@ -0,0 +1,275 @@
+//! Render settings kept in a file, so the flags that go with every render
+//! needn't be typed each time.
+//!
+//! A config file, `raytracer.toml` unless `--config` names another, holds
+//! `key = value` lines for any of:
+//!
+//! - `width`, `height`: the image's size in pixels, at most 2^26 in all
+//! - `spp`, `max_depth`: samples a pixel and bounces a path, at most 2^20
+//!   and 2^10
+//! - `tonemap`: `"clamp"`, `"reinhard"` or `"aces"`; see [`Tonemap`]
+//! - `threads`: how many threads to render with, 0 for one per core
+//! - `output`: where the image goes, with `{layer}` and `#` as in `--output`
+//!
+//! That's the part of TOML the file needs: `#` comments, basic and literal
+//! strings, integers, floats and booleans. Tables, arrays and dotted keys
+//! aren't allowed.
+//!
+//! A scene file can hold the same settings as a `"settings"` object. Each
+//! setting comes from the first of these that has it: the flags, the
+//! scene file, the config file, and the built-in defaults.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::Error;
+use crate::image::Tonemap;
+use crate::json::Value;
+use crate::render::{MAX_DEPTH, MAX_PIXELS, MAX_SPP};
+
+/// The config file read from the working directory without `--config`.
+pub const FILE_NAME: &str = "raytracer.toml";
+
+/// Every setting, in the order [`Settings::to_toml`] writes them.
+pub const KEYS: [&str; 7] = [
+    "width",
+    "height",
+    "spp",
+    "max_depth",
+    "tonemap",
+    "threads",
+    "output",
+];
+
+/// Settings from one layer, `None` where it leaves the setting to the
+/// layers below.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Settings {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub spp: Option<u32>,
+    pub max_depth: Option<u32>,
+    pub tonemap: Option<Tonemap>,
+    pub threads: Option<usize>,
+    pub output: Option<PathBuf>,
+}
+
+/// A non-negative integer no greater than `max`.
+fn integer(value: &Value, max: u32) -> Result<u32, String> {
+    match value.as_f64() {
+        Some(n) if n >= 0.0 && n.fract() == 0.0 && n <= max as f64 => Ok(n as u32),
+        Some(n) if n > max as f64 && n.fract() == 0.0 => {
+            Err(format!("expected at most {}, found {}", max, n))
+        }
+        _ => Err(format!(
+            "expected a non-negative integer, found {}",
+            value.kind()
+        )),
+    }
+}
+
+/// Checks a width and height, either of them just set, against the other.
+fn pixels(width: Option<u32>, height: Option<u32>) -> Result<(), String> {
+    match (width, height) {
+        (Some(width), Some(height)) if width as u64 * height as u64 > MAX_PIXELS => Err(format!(
+            "the {}x{} image is more than {} pixels",
+            width, height, MAX_PIXELS
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn string(value: &Value) -> Result<&str, String> {
+    value
+        .as_str()
+        .ok_or_else(|| format!("expected a string, found {}", value.kind()))
+}
+
+impl Settings {
+    /// These settings, with the ones they leave out taken from `base`.
+    pub fn or(self, base: Settings) -> Settings {
+        Settings {
+            width: self.width.or(base.width),
+            height: self.height.or(base.height),
+            spp: self.spp.or(base.spp),
+            max_depth: self.max_depth.or(base.max_depth),
+            tonemap: self.tonemap.or(base.tonemap),
+            threads: self.threads.or(base.threads),
+            output: self.output.or(base.output),
+        }
+    }
+
+    /// Sets `key`, one of [`KEYS`], to `value`. The error doesn't name the
+    /// key, for the caller to say where it is.
+    pub fn set(&mut self, key: &str, value: &Value) -> Result<(), String> {
+        match key {
+            "width" => {
+                self.width = Some(integer(value, MAX_PIXELS as u32)?);
+                pixels(self.width, self.height)?;
+            }
+            "height" => {
+                self.height = Some(integer(value, MAX_PIXELS as u32)?);
+                pixels(self.width, self.height)?;
+            }
+            "spp" => self.spp = Some(integer(value, MAX_SPP)?),
+            "max_depth" => self.max_depth = Some(integer(value, MAX_DEPTH)?),
+            "tonemap" => {
+                let name = string(value)?;
+                let tonemap = Tonemap::from_name(name).ok_or_else(|| {
+                    format!(
+                        "unknown tonemap {:?}, expected clamp, reinhard or aces",
+                        name
+                    )
+                })?;
+                self.tonemap = Some(tonemap);
+            }
+            "threads" => self.threads = Some(integer(value, u32::MAX)? as usize),
+            "output" => self.output = Some(PathBuf::from(string(value)?)),
+            _ => return Err(format!("unknown key {:?}", key)),
+        }
+        Ok(())
+    }
+
+    /// The settings as a config file that [`parse`] reads back the same.
+    pub fn to_toml(&self) -> String {
+        let number = |n: Option<u64>| n.map(|n| n.to_string());
+        let string = |s: String| Value::String(s).to_string();
+        let values = [
+            number(self.width.map(u64::from)),
+            number(self.height.map(u64::from)),
+            number(self.spp.map(u64::from)),
+            number(self.max_depth.map(u64::from)),
+            self.tonemap.map(|t| string(t.name().to_string())),
+            number(self.threads.map(|n| n as u64)),
+            self.output
+                .as_ref()
+                .map(|path| string(path.to_string_lossy().into_owned())),
+        ];
+        let mut text = String::new();
+        for (key, value) in KEYS.iter().zip(values.iter()) {
+            if let Some(value) = value {
+                text.push_str(&format!("{} = {}\n", key, value));
+            }
+        }
+        text
+    }
+}
+
+/// The `\` escape at the start of `chars` in a basic string.
+fn escape(chars: &mut std::str::Chars) -> Result<char, String> {
+    let digits = match chars.next() {
+        Some('"') => return Ok('"'),
+        Some('\\') => return Ok('\\'),
+        Some('n') => return Ok('\n'),
+        Some('t') => return Ok('\t'),
+        Some('r') => return Ok('\r'),
+        Some('b') => return Ok('\u{8}'),
+        Some('f') => return Ok('\u{c}'),
+        Some('u') => 4,
+        Some('U') => 8,
+        Some(other) => return Err(format!("unknown escape \\{}", other)),
+        None => return Err("unterminated string".to_string()),
+    };
+    let hex: String = chars.take(digits).collect();
+    match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+        Some(c) if hex.len() == digits => Ok(c),
+        _ => Err(format!("invalid escape \\u{}", hex)),
+    }
+}
+
+/// A value at the start of `text`, and what's left of the line after it.
+fn value(text: &str) -> Result<(Value, &str), String> {
+    if text.starts_with("\"\"\"") || text.starts_with("'''") {
+        return Err("multi-line strings aren't supported".to_string());
+    }
+    if let Some(rest) = text.strip_prefix('\'') {
+        let end = rest.find('\'').ok_or("unterminated string")?;
+        return Ok((Value::String(rest[..end].to_string()), &rest[end + 1..]));
+    }
+    if let Some(rest) = text.strip_prefix('"') {
+        let mut string = String::new();
+        let mut chars = rest.chars();
+        loop {
+            match chars.next() {
+                Some('"') => return Ok((Value::String(string), chars.as_str())),
+                Some('\\') => string.push(escape(&mut chars)?),
+                Some(c) => string.push(c),
+                None => return Err("unterminated string".to_string()),
+            }
+        }
+    }
+    if text.starts_with('[') || text.starts_with('{') {
+        return Err("arrays and inline tables aren't supported".to_string());
+    }
+    let end = text.find(['#', ' ', '\t']).unwrap_or(text.len());
+    let (token, rest) = text.split_at(end);
+    let value = match token {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        "" => return Err("missing value".to_string()),
+        // Underscores can only go between digits.
+        _ if token.contains("__") || token.starts_with('_') || token.ends_with('_') => {
+            return Err(format!("invalid value {:?}", token))
+        }
+        _ => match token.replace('_', "").parse::<f64>() {
+            Ok(n) if token.chars().all(|c| "0123456789+-._eE".contains(c)) => Value::Number(n),
+            _ => return Err(format!("invalid value {:?}", token)),
+        },
+    };
+    Ok((value, rest))
+}
+
+/// Reads the settings in a config file; errors say which line they're on.
+pub fn parse(text: &str) -> Result<Settings, String> {
+    let mut settings = Settings::default();
+    let mut seen = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let at = |message: String| format!("line {}: {}", i + 1, message);
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') {
+            return Err(at("tables aren't supported".to_string()));
+        }
+        let (key, rest) = line
+            .split_once('=')
+            .ok_or_else(|| at("expected key = value".to_string()))?;
+        let key = key.trim();
+        let key = match key.strip_prefix('"').and_then(|k| k.strip_suffix('"')) {
+            Some(quoted) => quoted,
+            None if key.contains('.') => {
+                return Err(at("dotted keys aren't supported".to_string()))
+            }
+            None => key,
+        };
+        if !KEYS.contains(&key) {
+            return Err(at(format!("unknown key {:?}", key)));
+        }
+        if seen.contains(&key) {
+            return Err(at(format!("{} is set twice", key)));
+        }
+        seen.push(key);
+        let (value, rest) = value(rest.trim_start()).map_err(at)?;
+        let rest = rest.trim_start();
+        if !(rest.is_empty() || rest.starts_with('#')) {
+            return Err(at(format!("unexpected {:?} after the value", rest)));
+        }
+        settings
+            .set(key, &value)
+            .map_err(|message| at(format!("{}: {}", key, message)))?;
+    }
+    Ok(settings)
+}
+
+pub fn load(path: &Path) -> Result<Settings, Error> {
+    let text = fs::read_to_string(path).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        operation: "read",
+        source,
+    })?;
+    parse(&text).map_err(|message| Error::Config {
+        path: path.to_path_buf(),
+        message,
+    })
+}
@@ -0,0 +1,99 @@
+//! The ways loading a scene, rendering it and writing the image can fail.
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum Error {
+    /// A file couldn't be opened, read or written; `operation` says which.
+    Io {
+        path: PathBuf,
+        operation: &'static str,
+        source: io::Error,
+    },
+    /// An image couldn't be encoded as a PNG.
+    Encode {
+        path: PathBuf,
+        source: png::EncodingError,
+    },
+    /// A scene file was read but isn't one, such as JSON missing a member;
+    /// `message` says where in the file the problem is.
+    SceneParse { path: PathBuf, message: String },
+    /// A config file was read but isn't valid; `message` says which line
+    /// the problem is on.
+    Config { path: PathBuf, message: String },
+    /// A scene with a shape, light or camera whose parameters don't make
+    /// sense, such as a sphere with a negative radius. From a scene file,
+    /// the message starts with the file and the place in it.
+    InvalidScene(String),
+    /// Render settings that can't make an image, such as zero samples per
+    /// pixel.
+    InvalidSettings(String),
+    /// The preview window couldn't be opened.
+    Preview(String),
+    /// The render was stopped before it finished, such as by closing the
+    /// preview window.
+    Cancelled,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io {
+                path,
+                operation,
+                source,
+            } => write!(f, "can't {} {}: {}", operation, path.display(), source),
+            Error::Encode { path, source } => {
+                write!(f, "can't encode {} as a PNG: {}", path.display(), source)
+            }
+            Error::SceneParse { path, message } | Error::Config { path, message } => {
+                write!(f, "{}: {}", path.display(), message)
+            }
+            Error::InvalidScene(message) => write!(f, "invalid scene: {}", message),
+            Error::InvalidSettings(message) => write!(f, "invalid render settings: {}", message),
+            Error::Preview(message) => write!(f, "can't open the preview window: {}", message),
+            Error::Cancelled => write!(f, "the render was cancelled"),
+        }
+    }
+}
+
+impl Error {
+    /// The code the binary exits with for this error. They stay the same
+    /// from release to release, so that scripts can tell failures apart:
+    ///
+    /// - 1: anything not listed here, such as the preview failing to open
+    /// - 2: invalid flags or render settings
+    /// - 3: a scene or config file that can't be parsed
+    /// - 4: a scene that parses but doesn't make sense
+    /// - 5: a file that can't be read or written, or an image that can't be
+    ///   encoded
+    /// - 130: a cancelled render, which is what a shell reports for a
+    ///   process killed by Ctrl-C
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Preview(_) => 1,
+            Error::InvalidSettings(_) => 2,
+            Error::SceneParse { .. } | Error::Config { .. } => 3,
+            Error::InvalidScene(_) => 4,
+            Error::Io { .. } | Error::Encode { .. } => 5,
+            Error::Cancelled => 130,
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Encode { source, .. } => Some(source),
+            Error::SceneParse { .. }
+            | Error::Config { .. }
+            | Error::InvalidScene(_)
+            | Error::InvalidSettings(_)
+            | Error::Preview(_)
+            | Error::Cancelled => None,
+        }
+    }
+}
@@ -0,0 +1,410 @@
+//! OpenEXR images, which keep a render as the linear light it holds rather
+//! than as 8-bit sRGB.
+//!
+//! Only what a renderer needs is handled: single-part scanline images whose
+//! `R`, `G` and `B` channels (or a lone `Y`) hold half, float or uint
+//! samples, uncompressed or ZIP compressed a line or 16 lines at a time.
+//! Images are written as 32-bit floats in 16-line ZIP blocks. Tiled, deep
+//! and multi-part files and the other compressions are refused.
+
+use std::convert::{TryFrom, TryInto};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use deflate::{write::ZlibEncoder, Compression};
+
+use crate::error::Error;
+use crate::image::{Image, MAX_PIXELS};
+use crate::scalar::{self, Scalar};
+
+const MAGIC: u32 = 20_000_630;
+/// The version field's flags for tiled, deep and multi-part files.
+const UNSUPPORTED_FLAGS: u32 = 0x200 | 0x800 | 0x1000;
+
+const NO_COMPRESSION: u8 = 0;
+const ZIPS_COMPRESSION: u8 = 2;
+const ZIP_COMPRESSION: u8 = 3;
+
+const UINT: i32 = 0;
+const HALF: i32 = 1;
+const FLOAT: i32 = 2;
+
+/// Whether `path` names an EXR file.
+pub fn is_exr(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("exr"))
+}
+
+pub fn read(path: &Path) -> Result<Image, String> {
+    let fail = |e: &dyn std::fmt::Display| format!("can't read {}: {}", path.display(), e);
+    let bytes = fs::read(path).map_err(|e| fail(&e))?;
+    decode(&bytes).map_err(|e| fail(&e))
+}
+
+/// Reads the little-endian fields of a file, failing rather than reading
+/// past its end.
+struct Bytes<'a> {
+    data: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Bytes<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .at
+            .checked_add(count)
+            .filter(|&end| end <= self.data.len())
+            .ok_or("the file is cut short")?;
+        let bytes = &self.data[self.at..end];
+        self.at = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        Ok(i32::from_le_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    /// A name ended by a zero byte, which is empty at the end of a list.
+    fn name(&mut self) -> Result<&'a str, String> {
+        let rest = &self.data[self.at..];
+        let length = rest
+            .iter()
+            .take(256)
+            .position(|&b| b == 0)
+            .ok_or("a name isn't ended")?;
+        let name = std::str::from_utf8(&rest[..length]).map_err(|_| "a name isn't UTF-8")?;
+        self.at += length + 1;
+        Ok(name)
+    }
+}
+
+struct Channel<'a> {
+    name: &'a str,
+    kind: i32,
+}
+
+impl Channel<'_> {
+    fn sample_bytes(&self) -> usize {
+        if self.kind == HALF {
+            2
+        } else {
+            4
+        }
+    }
+}
+
+fn channels(value: &[u8]) -> Result<Vec<Channel<'_>>, String> {
+    let mut bytes = Bytes { data: value, at: 0 };
+    let mut channels = Vec::new();
+    loop {
+        let name = bytes.name()?;
+        if name.is_empty() {
+            return Ok(channels);
+        }
+        let kind = bytes.i32()?;
+        if !(UINT..=FLOAT).contains(&kind) {
+            return Err(format!(
+                "channel {:?} has unknown sample type {}",
+                name, kind
+            ));
+        }
+        bytes.take(4)?;
+        if (bytes.i32()?, bytes.i32()?) != (1, 1) {
+            return Err(format!("channel {:?} is subsampled", name));
+        }
+        channels.push(Channel { name, kind });
+    }
+}
+
+fn half_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = i32::from((bits >> 10) & 0x1f);
+    let mantissa = f32::from(bits & 0x3ff);
+    sign * match exponent {
+        0 => mantissa * 2f32.powi(-24),
+        31 if mantissa == 0.0 => f32::INFINITY,
+        31 => f32::NAN,
+        _ => (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
+
+/// Undoes what ZIP compression does to a block before deflating it: bytes
+/// stored as differences from the one before, and the first and second
+/// halves of the block holding the even and odd bytes.
+fn unpredict(mut data: Vec<u8>) -> Vec<u8> {
+    for i in 1..data.len() {
+        data[i] = data[i - 1].wrapping_add(data[i]).wrapping_sub(128);
+    }
+    let (even, odd) = data.split_at(data.len().div_ceil(2));
+    let mut bytes = Vec::with_capacity(data.len());
+    for (i, &a) in even.iter().enumerate() {
+        bytes.push(a);
+        if let Some(&b) = odd.get(i) {
+            bytes.push(b);
+        }
+    }
+    bytes
+}
+
+fn predict(data: &[u8]) -> Vec<u8> {
+    let mut bytes: Vec<u8> = data.iter().step_by(2).copied().collect();
+    bytes.extend(data.iter().skip(1).step_by(2));
+    for i in (1..bytes.len()).rev() {
+        bytes[i] = bytes[i].wrapping_sub(bytes[i - 1]).wrapping_add(128);
+    }
+    bytes
+}
+
+/// Inflates a ZIP block, failing as soon as it's longer than `limit` rather
+/// than inflating all of a block made to be huge.
+fn inflate(packed: &[u8], limit: usize) -> Result<Vec<u8>, String> {
+    let mut stream = inflate::InflateStream::from_zlib();
+    let (mut unpacked, mut read) = (Vec::new(), 0);
+    while read < packed.len() {
+        let (consumed, output) = stream
+            .update(&packed[read..])
+            .map_err(|e| format!("bad ZIP data: {}", e))?;
+        if consumed == 0 && output.is_empty() {
+            break;
+        }
+        unpacked.extend_from_slice(output);
+        if unpacked.len() > limit {
+            break;
+        }
+        read += consumed;
+    }
+    Ok(unpacked)
+}
+
+/// Decodes an EXR file's bytes.
+pub fn decode(data: &[u8]) -> Result<Image, String> {
+    let mut bytes = Bytes { data, at: 0 };
+    if bytes.u32().ok() != Some(MAGIC) {
+        return Err("not an EXR file".to_string());
+    }
+    let version = bytes.u32()?;
+    if version & 0xff != 2 {
+        return Err(format!("unknown EXR version {}", version & 0xff));
+    }
+    if version & UNSUPPORTED_FLAGS != 0 {
+        return Err("only single-part scanline EXR images can be read".to_string());
+    }
+    let (mut channel_list, mut compression, mut window) = (None, None, None);
+    loop {
+        let name = bytes.name()?;
+        if name.is_empty() {
+            break;
+        }
+        bytes.name()?;
+        let size = bytes.u32()? as usize;
+        let value = bytes.take(size)?;
+        let mut value_bytes = Bytes { data: value, at: 0 };
+        match name {
+            "channels" => channel_list = Some(channels(value)?),
+            "compression" => compression = Some(value_bytes.u8()?),
+            "dataWindow" => {
+                let mut corners = [0; 4];
+                for corner in corners.iter_mut() {
+                    *corner = value_bytes.i32()? as i64;
+                }
+                window = Some(corners);
+            }
+            _ => {}
+        }
+    }
+    let channels = channel_list.ok_or("the header has no channels")?;
+    let compression = compression.ok_or("the header has no compression")?;
+    let [x_min, y_min, x_max, y_max] = window.ok_or("the header has no data window")?;
+    let lines = match compression {
+        NO_COMPRESSION | ZIPS_COMPRESSION => 1,
+        ZIP_COMPRESSION => 16,
+        other => {
+            return Err(format!(
+                "compression {} isn't supported, only none or ZIP",
+                other
+            ))
+        }
+    };
+    let (width, height) = (x_max - x_min + 1, y_max - y_min + 1);
+    if width < 1 || height < 1 {
+        return Err("the data window is empty".to_string());
+    }
+    if (width as u64).saturating_mul(height as u64) > MAX_PIXELS {
+        return Err(format!(
+            "{}x{} is more than {} pixels",
+            width, height, MAX_PIXELS
+        ));
+    }
+    let (width, height) = (width as usize, height as usize);
+    // Where each channel's samples go in a pixel: red, green, blue, or all
+    // three for luminance.
+    let targets: Vec<&[usize]> = channels
+        .iter()
+        .map(|channel| match channel.name {
+            "R" => &[0][..],
+            "G" => &[1],
+            "B" => &[2],
+            "Y" => &[0, 1, 2],
+            _ => &[],
+        })
+        .collect();
+    let found = |name| channels.iter().any(|channel| channel.name == name);
+    if !(found("R") && found("G") && found("B") || found("Y")) {
+        return Err("expected R, G and B channels, or Y".to_string());
+    }
+    let line_bytes: usize = channels
+        .iter()
+        .map(|channel| width * channel.sample_bytes())
+        .sum();
+    let blocks = height.div_ceil(lines);
+    let mut offsets = Vec::with_capacity(blocks);
+    for _ in 0..blocks {
+        offsets.push(bytes.u64()?);
+    }
+    let mut image = Image::new(width as u32, height as u32);
+    for offset in offsets {
+        let mut block = Bytes {
+            data,
+            at: usize::try_from(offset).unwrap_or(usize::MAX),
+        };
+        let row = block.i32()? as i64 - y_min;
+        if row < 0 || row as usize >= height || !(row as usize).is_multiple_of(lines) {
+            return Err(format!("a block starts at line {}", row + y_min));
+        }
+        let row = row as usize;
+        let rows = lines.min(height - row);
+        let size = block.u32()? as usize;
+        let packed = block.take(size)?;
+        let expected = rows * line_bytes;
+        // A block that wouldn't get any smaller is kept as it is.
+        let unpacked = if compression == NO_COMPRESSION || size == expected {
+            packed.to_vec()
+        } else {
+            unpredict(inflate(packed, expected)?)
+        };
+        if unpacked.len() != expected {
+            return Err(format!(
+                "the block at line {} is the wrong size",
+                row as i64 + y_min
+            ));
+        }
+        let mut samples = Bytes {
+            data: &unpacked,
+            at: 0,
+        };
+        for y in row..row + rows {
+            for (channel, targets) in channels.iter().zip(&targets) {
+                for x in 0..width {
+                    let value = match channel.kind {
+                        HALF => Scalar::from(half_to_f32(u16::from_le_bytes(samples.array()?))),
+                        FLOAT => Scalar::from(f32::from_le_bytes(samples.array()?)),
+                        _ => samples.u32()? as Scalar,
+                    };
+                    let pixel = &mut image.pixels[x + y * width];
+                    for &target in targets.iter() {
+                        match target {
+                            0 => pixel.x = value,
+                            1 => pixel.y = value,
+                            _ => pixel.z = value,
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(image)
+}
+
+fn attribute(data: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    for text in [name, kind].iter() {
+        data.extend_from_slice(text.as_bytes());
+        data.push(0);
+    }
+    data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    data.extend_from_slice(value);
+}
+
+/// The image as an EXR file.
+pub fn encode(image: &Image) -> Vec<u8> {
+    let (width, height) = (image.width as usize, image.height as usize);
+    let mut data = Vec::new();
+    data.extend_from_slice(&MAGIC.to_le_bytes());
+    data.extend_from_slice(&2u32.to_le_bytes());
+    let mut channels = Vec::new();
+    for name in ["B", "G", "R"].iter() {
+        channels.extend_from_slice(name.as_bytes());
+        channels.push(0);
+        channels.extend_from_slice(&FLOAT.to_le_bytes());
+        channels.extend_from_slice(&[0; 4]);
+        channels.extend_from_slice(&1i32.to_le_bytes());
+        channels.extend_from_slice(&1i32.to_le_bytes());
+    }
+    channels.push(0);
+    let mut window = Vec::new();
+    for corner in [0, 0, image.width as i32 - 1, image.height as i32 - 1].iter() {
+        window.extend_from_slice(&corner.to_le_bytes());
+    }
+    attribute(&mut data, "channels", "chlist", &channels);
+    attribute(&mut data, "compression", "compression", &[ZIP_COMPRESSION]);
+    attribute(&mut data, "dataWindow", "box2i", &window);
+    attribute(&mut data, "displayWindow", "box2i", &window);
+    attribute(&mut data, "lineOrder", "lineOrder", &[0]);
+    attribute(&mut data, "pixelAspectRatio", "float", &1f32.to_le_bytes());
+    attribute(&mut data, "screenWindowCenter", "v2f", &[0; 8]);
+    attribute(&mut data, "screenWindowWidth", "float", &1f32.to_le_bytes());
+    data.push(0);
+    let table = data.len();
+    let blocks = height.div_ceil(16);
+    data.resize(table + 8 * blocks, 0);
+    for block in 0..blocks {
+        let offset = data.len() as u64;
+        data[table + 8 * block..table + 8 * block + 8].copy_from_slice(&offset.to_le_bytes());
+        let mut raw = Vec::new();
+        for y in 16 * block..height.min(16 * block + 16) {
+            let row = &image.pixels[y * width..(y + 1) * width];
+            for channel in [2, 1, 0].iter() {
+                for color in row {
+                    let value = [color.x, color.y, color.z][*channel];
+                    raw.extend_from_slice(&(scalar::to_f64(value) as f32).to_le_bytes());
+                }
+            }
+        }
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::Default);
+        encoder.write_all(&predict(&raw)).unwrap();
+        let packed = encoder.finish().unwrap();
+        let packed = if packed.len() < raw.len() {
+            packed
+        } else {
+            raw
+        };
+        data.extend_from_slice(&(16 * block as i32).to_le_bytes());
+        data.extend_from_slice(&(packed.len() as u32).to_le_bytes());
+        data.extend_from_slice(&packed);
+    }
+    data
+}
+
+pub fn write(image: &Image, path: &Path) -> Result<(), Error> {
+    fs::write(path, encode(image)).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        operation: "write",
+        source,
+    })
+}
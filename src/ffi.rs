@@ -0,0 +1,488 @@
+//! A C API for building scenes and rendering them, declared in
+//! `include/basic_raytracer.h`, which cbindgen generates from this file as
+//! `cbindgen.toml` says.
+//!
+//! Ownership is simple: `rt_scene_new` returns a scene handle the caller owns
+//! and must give back to `rt_scene_free`, once, and nothing else the API
+//! takes or returns is owned by the other side. Parameters passed by pointer
+//! are only read during the call. Materials are added to a scene and then
+//! referred to by the index `rt_scene_add_material` gave them.
+//!
+//! Every function returns one of the `RT_*` status codes, or null for
+//! `rt_scene_new`, rather than let a panic unwind into C. After a failure
+//! `rt_last_error` describes it.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, c_void, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use crate::camera::Camera;
+use crate::error::Error;
+use crate::image::encode_srgb;
+use crate::light::PointLight;
+use crate::material::{Dielectric, Emissive, Glossy, Lambertian, Material};
+use crate::render::{self, IntegratorKind, RenderSettings};
+use crate::scalar::{to_f64, Scalar};
+use crate::scene::Scene;
+use crate::shapes::{Plane, Sphere};
+use crate::vector::Vector;
+
+pub const RT_OK: i32 = 0;
+/// A pointer that mustn't be null was.
+pub const RT_NULL_POINTER: i32 = 1;
+/// A parameter is out of range, such as the index of a material the scene
+/// doesn't have, or a buffer the wrong size.
+pub const RT_INVALID_ARGUMENT: i32 = 2;
+/// The scene or settings can't make an image.
+pub const RT_RENDER_FAILED: i32 = 3;
+/// Something went wrong inside the renderer.
+pub const RT_PANIC: i32 = 4;
+/// The progress callback asked for the render to stop.
+pub const RT_CANCELLED: i32 = 5;
+
+pub const RT_LAMBERTIAN: u32 = 0;
+pub const RT_GLOSSY: u32 = 1;
+pub const RT_DIELECTRIC: u32 = 2;
+pub const RT_EMISSIVE: u32 = 3;
+
+pub const RT_WHITTED: u32 = 0;
+pub const RT_PATH: u32 = 1;
+pub const RT_AMBIENT_OCCLUSION: u32 = 2;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct RtVector {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct RtCamera {
+    pub position: RtVector,
+    pub look_at: RtVector,
+    /// The vertical field of view in degrees.
+    pub fov: f64,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct RtMaterial {
+    /// One of `RT_LAMBERTIAN`, `RT_GLOSSY`, `RT_DIELECTRIC` or
+    /// `RT_EMISSIVE`.
+    pub kind: u32,
+    /// The albedo, or the radiance of an emissive material; a dielectric
+    /// doesn't use it.
+    pub color: RtVector,
+    /// The exponent of a glossy material's lobe.
+    pub exponent: f64,
+    /// A dielectric's index of refraction.
+    pub ior: f64,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct RtSphere {
+    pub center: RtVector,
+    pub radius: f64,
+    pub material: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct RtPlane {
+    pub point: RtVector,
+    pub normal: RtVector,
+    pub material: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct RtPointLight {
+    pub position: RtVector,
+    pub color: RtVector,
+    pub intensity: f64,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct RtSettings {
+    pub width: u32,
+    pub height: u32,
+    pub spp: u32,
+    pub max_depth: u32,
+    /// One of `RT_WHITTED`, `RT_PATH` or `RT_AMBIENT_OCCLUSION`.
+    pub integrator: u32,
+    pub seed: u64,
+}
+
+/// A scene built through the API, with the materials its objects refer to.
+pub struct RtScene {
+    scene: Scene,
+    materials: Vec<Arc<dyn Material>>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+fn fail(status: i32, message: impl Into<String>) -> i32 {
+    // Messages with a NUL in them lose everything from it on.
+    let mut message = message.into().into_bytes();
+    message.truncate(
+        message
+            .iter()
+            .position(|&b| b == 0)
+            .unwrap_or(message.len()),
+    );
+    let message = CString::new(message).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+    status
+}
+
+/// Runs `f`, turning a panic into `RT_PANIC`.
+fn guard(f: impl FnOnce() -> i32) -> i32 {
+    panic::catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|_| fail(RT_PANIC, "the renderer panicked"))
+}
+
+/// Called by `rt_render_with_progress` with how many of the image's rows
+/// are done and how many there are, and the `user_data` it was given.
+/// Returning anything but zero cancels the render.
+pub type RtProgress = Option<unsafe extern "C" fn(u32, u32, *mut c_void) -> i32>;
+
+impl From<RtVector> for Vector {
+    fn from(v: RtVector) -> Vector {
+        Vector::new(v.x as Scalar, v.y as Scalar, v.z as Scalar)
+    }
+}
+
+impl From<Vector> for RtVector {
+    fn from(v: Vector) -> RtVector {
+        RtVector {
+            x: to_f64(v.x),
+            y: to_f64(v.y),
+            z: to_f64(v.z),
+        }
+    }
+}
+
+impl RtScene {
+    fn material(&self, index: u32) -> Result<Arc<dyn Material>, i32> {
+        self.materials.get(index as usize).cloned().ok_or_else(|| {
+            fail(
+                RT_INVALID_ARGUMENT,
+                format!("material {} of the {} added", index, self.materials.len()),
+            )
+        })
+    }
+}
+
+/// Calls `f` with the scene and parameter behind the pointers, unless
+/// either is null.
+///
+/// # Safety
+///
+/// Non-null pointers must be valid for the call.
+unsafe fn with<T: Copy>(
+    scene: *mut RtScene,
+    parameters: *const T,
+    f: impl FnOnce(&mut RtScene, T) -> Result<(), i32>,
+) -> i32 {
+    if scene.is_null() || parameters.is_null() {
+        return fail(RT_NULL_POINTER, "a null pointer was passed");
+    }
+    let (scene, parameters) = (&mut *scene, *parameters);
+    guard(|| match f(scene, parameters) {
+        Ok(()) => RT_OK,
+        Err(status) => status,
+    })
+}
+
+/// A new, empty scene with a camera at the origin looking down -z, and a
+/// black background. Free it with `rt_scene_free`. Returns null only if the
+/// renderer panicked.
+#[no_mangle]
+pub extern "C" fn rt_scene_new() -> *mut RtScene {
+    panic::catch_unwind(|| {
+        let camera = Camera::new(Vector::zero(), Vector::new(0.0, 0.0, -1.0), 45.0);
+        Box::into_raw(Box::new(RtScene {
+            scene: Scene::new(camera),
+            materials: Vec::new(),
+        }))
+    })
+    .unwrap_or_else(|_| {
+        fail(RT_PANIC, "the renderer panicked");
+        ptr::null_mut()
+    })
+}
+
+/// Frees a scene from `rt_scene_new`. Null is ignored.
+///
+/// # Safety
+///
+/// `scene` must be null or a scene from `rt_scene_new` not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn rt_scene_free(scene: *mut RtScene) {
+    if !scene.is_null() {
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(scene))));
+    }
+}
+
+/// # Safety
+///
+/// `scene` must be a live scene, and `camera` valid to read.
+#[no_mangle]
+pub unsafe extern "C" fn rt_scene_set_camera(scene: *mut RtScene, camera: *const RtCamera) -> i32 {
+    with(scene, camera, |scene, camera| {
+        let fov = camera.fov as Scalar;
+        scene.scene.camera = Camera::new(
+            Vector::from(camera.position),
+            Vector::from(camera.look_at),
+            fov,
+        );
+        Ok(())
+    })
+}
+
+/// # Safety
+///
+/// `scene` must be a live scene, and `color` valid to read.
+#[no_mangle]
+pub unsafe extern "C" fn rt_scene_set_background(
+    scene: *mut RtScene,
+    color: *const RtVector,
+) -> i32 {
+    with(scene, color, |scene, color| {
+        scene.scene.background = Vector::from(color);
+        Ok(())
+    })
+}
+
+/// Adds a material for objects to refer to, writing its index to `index`
+/// if that isn't null.
+///
+/// # Safety
+///
+/// `scene` must be a live scene, `material` valid to read, and `index` null
+/// or valid to write.
+#[no_mangle]
+pub unsafe extern "C" fn rt_scene_add_material(
+    scene: *mut RtScene,
+    material: *const RtMaterial,
+    index: *mut u32,
+) -> i32 {
+    with(scene, material, |scene, material| {
+        let color = Vector::from(material.color);
+        let made: Arc<dyn Material> = match material.kind {
+            RT_LAMBERTIAN => Arc::new(Lambertian::new(color)),
+            RT_GLOSSY => Arc::new(Glossy::new(color, material.exponent as Scalar)),
+            RT_DIELECTRIC => Arc::new(Dielectric::new(material.ior as Scalar)),
+            RT_EMISSIVE => Arc::new(Emissive::new(color)),
+            kind => {
+                let message = format!("unknown material kind {}", kind);
+                return Err(fail(RT_INVALID_ARGUMENT, message));
+            }
+        };
+        if !index.is_null() {
+            *index = scene.materials.len() as u32;
+        }
+        scene.materials.push(made);
+        Ok(())
+    })
+}
+
+/// # Safety
+///
+/// `scene` must be a live scene, and `sphere` valid to read.
+#[no_mangle]
+pub unsafe extern "C" fn rt_scene_add_sphere(scene: *mut RtScene, sphere: *const RtSphere) -> i32 {
+    with(scene, sphere, |scene, sphere| {
+        let material = scene.material(sphere.material)?;
+        let shape = Sphere::new(Vector::from(sphere.center), sphere.radius as Scalar);
+        scene.scene.add(shape, material);
+        Ok(())
+    })
+}
+
+/// # Safety
+///
+/// `scene` must be a live scene, and `plane` valid to read.
+#[no_mangle]
+pub unsafe extern "C" fn rt_scene_add_plane(scene: *mut RtScene, plane: *const RtPlane) -> i32 {
+    with(scene, plane, |scene, plane| {
+        let material = scene.material(plane.material)?;
+        let shape = Plane::new(Vector::from(plane.point), Vector::from(plane.normal));
+        scene.scene.add(shape, material);
+        Ok(())
+    })
+}
+
+/// # Safety
+///
+/// `scene` must be a live scene, and `light` valid to read.
+#[no_mangle]
+pub unsafe extern "C" fn rt_scene_add_point_light(
+    scene: *mut RtScene,
+    light: *const RtPointLight,
+) -> i32 {
+    with(scene, light, |scene, light| {
+        let (position, color) = (Vector::from(light.position), Vector::from(light.color));
+        let light = PointLight::new(position, color, light.intensity as Scalar);
+        scene.scene.add_light(light);
+        Ok(())
+    })
+}
+
+/// Fills in the settings the renderer uses by default.
+///
+/// # Safety
+///
+/// `settings` must be null or valid to write.
+#[no_mangle]
+pub unsafe extern "C" fn rt_settings_default(settings: *mut RtSettings) -> i32 {
+    if settings.is_null() {
+        return fail(RT_NULL_POINTER, "a null pointer was passed");
+    }
+    let defaults = RenderSettings::default();
+    *settings = RtSettings {
+        width: defaults.width,
+        height: defaults.height,
+        spp: defaults.spp,
+        max_depth: defaults.max_depth,
+        integrator: RT_WHITTED,
+        seed: defaults.seed,
+    };
+    RT_OK
+}
+
+/// Renders the scene into `buffer`, which must hold exactly `length` bytes,
+/// 4 for each pixel: 8-bit sRGB red, green and blue, then an opaque alpha,
+/// row by row from the top.
+///
+/// # Safety
+///
+/// `scene` must be a live scene, `settings` valid to read, and `buffer`
+/// valid to write `length` bytes to.
+#[no_mangle]
+pub unsafe extern "C" fn rt_render(
+    scene: *const RtScene,
+    settings: *const RtSettings,
+    buffer: *mut u8,
+    length: usize,
+) -> i32 {
+    if scene.is_null() || settings.is_null() || buffer.is_null() {
+        return fail(RT_NULL_POINTER, "a null pointer was passed");
+    }
+    let (scene, settings) = (&*scene, *settings);
+    guard(|| match render_settings(settings, length) {
+        Ok(settings) => match render::render_to_buffer(&scene.scene, &settings) {
+            Ok(pixels) => {
+                std::slice::from_raw_parts_mut(buffer, length).copy_from_slice(&pixels);
+                RT_OK
+            }
+            Err(error) => fail(RT_RENDER_FAILED, error.to_string()),
+        },
+        Err(status) => status,
+    })
+}
+
+/// Renders like `rt_render`, but a row at a time from the top, calling
+/// `progress` after each one is in `buffer` unless it's null. The callback
+/// is made on the calling thread while the workers carry on, so it may take
+/// its time; if it returns nonzero the render stops with `RT_CANCELLED` and
+/// `buffer` holds the rows done so far.
+///
+/// # Safety
+///
+/// As for `rt_render`; `progress` must be safe to call with `user_data`.
+#[no_mangle]
+pub unsafe extern "C" fn rt_render_with_progress(
+    scene: *const RtScene,
+    settings: *const RtSettings,
+    buffer: *mut u8,
+    length: usize,
+    progress: RtProgress,
+    user_data: *mut c_void,
+) -> i32 {
+    if scene.is_null() || settings.is_null() || buffer.is_null() {
+        return fail(RT_NULL_POINTER, "a null pointer was passed");
+    }
+    let (scene, settings) = (&*scene, *settings);
+    guard(|| {
+        let settings = match render_settings(settings, length) {
+            Ok(settings) => settings,
+            Err(status) => return status,
+        };
+        let buffer = std::slice::from_raw_parts_mut(buffer, length);
+        let row_bytes = 4 * settings.width as usize;
+        let mut rows = buffer.chunks_mut(row_bytes);
+        let mut done = 0;
+        let stop = AtomicBool::new(false);
+        let streamed = render::render_streamed(&scene.scene, &settings, &stop, |row| {
+            let pixels = rows.next().unwrap_or_default();
+            for (pixel, color) in pixels.chunks_mut(4).zip(row) {
+                pixel.copy_from_slice(&[
+                    encode_srgb(color.x),
+                    encode_srgb(color.y),
+                    encode_srgb(color.z),
+                    255,
+                ]);
+            }
+            done += 1;
+            match progress {
+                Some(progress) if progress(done, settings.height, user_data) != 0 => {
+                    Err(Error::Cancelled)
+                }
+                _ => Ok(()),
+            }
+        });
+        match streamed {
+            Ok(_) => RT_OK,
+            Err(Error::Cancelled) => fail(RT_CANCELLED, "the render was cancelled"),
+            Err(error) => fail(RT_RENDER_FAILED, error.to_string()),
+        }
+    })
+}
+
+/// The renderer's settings for `settings`, checking that the image fits in
+/// `length` bytes exactly.
+fn render_settings(settings: RtSettings, length: usize) -> Result<RenderSettings, i32> {
+    let integrator = match settings.integrator {
+        RT_WHITTED => IntegratorKind::Whitted,
+        RT_PATH => IntegratorKind::Path,
+        RT_AMBIENT_OCCLUSION => IntegratorKind::AmbientOcclusion,
+        kind => {
+            let message = format!("unknown integrator {}", kind);
+            return Err(fail(RT_INVALID_ARGUMENT, message));
+        }
+    };
+    let needed = 4 * settings.width as u64 * settings.height as u64;
+    if needed != length as u64 {
+        let message = format!(
+            "a {}x{} image needs {} bytes, not {}",
+            settings.width, settings.height, needed, length
+        );
+        return Err(fail(RT_INVALID_ARGUMENT, message));
+    }
+    Ok(RenderSettings {
+        width: settings.width,
+        height: settings.height,
+        spp: settings.spp,
+        max_depth: settings.max_depth,
+        integrator,
+        seed: settings.seed,
+        ..RenderSettings::default()
+    })
+}
+
+/// What went wrong in the last call on this thread that failed, or an
+/// empty string. It stays valid until the next call that fails.
+#[no_mangle]
+pub extern "C" fn rt_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ptr())
+}
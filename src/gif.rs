@@ -0,0 +1,417 @@
+//! Animated GIFs, for quick previews of a frame sequence.
+//!
+//! A GIF has at most 256 colors, so every frame is quantized to one palette
+//! chosen by median cut over all of them, which keeps colors from
+//! flickering from frame to frame. Since the palette isn't known until the
+//! last frame is in, frames are spooled to a file next to the GIF as 8-bit
+//! sRGB while they're added, and encoded one at a time when it's finished.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use log::warn;
+
+use crate::error::Error;
+use crate::image::Image;
+
+/// Colors are counted in buckets of 5 bits per channel.
+const BUCKETS: usize = 1 << 15;
+
+fn bucket(rgb: [u8; 3]) -> usize {
+    (rgb[0] as usize >> 3) << 10 | (rgb[1] as usize >> 3) << 5 | rgb[2] as usize >> 3
+}
+
+/// How often each bucket's colors turn up and what they add up to, for
+/// choosing a palette.
+struct Histogram {
+    counts: Vec<u64>,
+    sums: Vec<[u64; 3]>,
+}
+
+impl Histogram {
+    fn new() -> Histogram {
+        Histogram {
+            counts: vec![0; BUCKETS],
+            sums: vec![[0; 3]; BUCKETS],
+        }
+    }
+
+    fn add(&mut self, rgb: [u8; 3]) {
+        let bucket = bucket(rgb);
+        self.counts[bucket] += 1;
+        for (sum, &channel) in self.sums[bucket].iter_mut().zip(&rgb) {
+            *sum += channel as u64;
+        }
+    }
+}
+
+/// Up to `colors` colors standing in for those in `histogram`: the buckets
+/// are split at the median of their widest channel, box by box, and each
+/// box is represented by the mean of its colors.
+fn median_cut(histogram: &Histogram, colors: usize) -> Vec<[u8; 3]> {
+    let channel = |bucket: usize, c: usize| (bucket >> (10 - 5 * c)) & 31;
+    let used: Vec<usize> = (0..BUCKETS)
+        .filter(|&bucket| histogram.counts[bucket] > 0)
+        .collect();
+    if used.is_empty() {
+        return vec![[0; 3]];
+    }
+    let mut boxes = vec![used];
+    while boxes.len() < colors {
+        // The box spanning the most of any channel, and which channel.
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, buckets)| buckets.len() > 1)
+            .flat_map(|(i, buckets)| {
+                (0..3).map(move |c| {
+                    let values = buckets.iter().map(|&bucket| channel(bucket, c));
+                    let range = values.clone().max().unwrap() - values.min().unwrap();
+                    (range, i, c)
+                })
+            })
+            .max();
+        let (i, c) = match widest {
+            Some((range, i, c)) if range > 0 => (i, c),
+            _ => break,
+        };
+        let mut buckets = boxes.swap_remove(i);
+        buckets.sort_by_key(|&bucket| channel(bucket, c));
+        let total: u64 = buckets.iter().map(|&bucket| histogram.counts[bucket]).sum();
+        let mut seen = 0;
+        let mut split = buckets
+            .iter()
+            .position(|&bucket| {
+                seen += histogram.counts[bucket];
+                2 * seen >= total
+            })
+            .unwrap()
+            + 1;
+        // Never leave either half empty.
+        split = split.clamp(1, buckets.len() - 1);
+        let upper = buckets.split_off(split);
+        boxes.push(buckets);
+        boxes.push(upper);
+    }
+    boxes
+        .iter()
+        .map(|buckets| {
+            let count: u64 = buckets.iter().map(|&bucket| histogram.counts[bucket]).sum();
+            let mut color = [0; 3];
+            for (c, channel) in color.iter_mut().enumerate() {
+                let sum: u64 = buckets
+                    .iter()
+                    .map(|&bucket| histogram.sums[bucket][c])
+                    .sum();
+                *channel = ((sum + count / 2) / count) as u8;
+            }
+            color
+        })
+        .collect()
+}
+
+fn distance(a: [f64; 3], b: [u8; 3]) -> f64 {
+    a.iter()
+        .zip(&b)
+        .map(|(&a, &b)| (a - b as f64) * (a - b as f64))
+        .sum()
+}
+
+/// The palette and, for each bucket, the palette color nearest its middle.
+struct Palette {
+    colors: Vec<[u8; 3]>,
+    nearest: Vec<u8>,
+}
+
+impl Palette {
+    fn new(colors: Vec<[u8; 3]>) -> Palette {
+        let nearest = (0..BUCKETS)
+            .map(|bucket| {
+                let middle = [
+                    ((bucket >> 10) << 3 | 4) as f64,
+                    ((bucket >> 5 & 31) << 3 | 4) as f64,
+                    ((bucket & 31) << 3 | 4) as f64,
+                ];
+                (0..colors.len())
+                    .min_by(|&a, &b| {
+                        distance(middle, colors[a]).total_cmp(&distance(middle, colors[b]))
+                    })
+                    .unwrap() as u8
+            })
+            .collect();
+        Palette { colors, nearest }
+    }
+
+    /// Bits per index in the color table, which GIFs size in powers of two.
+    fn bits(&self) -> u32 {
+        let mut bits = 1;
+        while 1 << bits < self.colors.len() {
+            bits += 1;
+        }
+        bits
+    }
+
+    /// The palette index for each pixel of a frame, spreading each
+    /// pixel's error over its neighbours with Floyd–Steinberg if `dither`.
+    fn quantize(&self, rgb: &[u8], width: usize, dither: bool) -> Vec<u8> {
+        if !dither {
+            return rgb
+                .chunks_exact(3)
+                .map(|pixel| self.nearest[bucket([pixel[0], pixel[1], pixel[2]])])
+                .collect();
+        }
+        let mut indices = Vec::with_capacity(rgb.len() / 3);
+        let mut errors = vec![[0.0; 3]; width + 2];
+        let mut next = vec![[0.0; 3]; width + 2];
+        for row in rgb.chunks_exact(3 * width) {
+            for (x, pixel) in row.chunks_exact(3).enumerate() {
+                let mut wanted = [0; 3];
+                let mut exact = [0.0; 3];
+                for c in 0..3 {
+                    exact[c] = (pixel[c] as f64 + errors[x + 1][c]).clamp(0.0, 255.0);
+                    wanted[c] = exact[c].round() as u8;
+                }
+                let index = self.nearest[bucket(wanted)];
+                let color = self.colors[index as usize];
+                for c in 0..3 {
+                    let error = exact[c] - color[c] as f64;
+                    errors[x + 2][c] += error * 7.0 / 16.0;
+                    next[x][c] += error * 3.0 / 16.0;
+                    next[x + 1][c] += error * 5.0 / 16.0;
+                    next[x + 2][c] += error / 16.0;
+                }
+                indices.push(index);
+            }
+            errors = std::mem::replace(&mut next, vec![[0.0; 3]; width + 2]);
+        }
+        indices
+    }
+}
+
+/// Packs variable-width codes into bytes, least significant bit first,
+/// and those into the sub-blocks of at most 255 bytes that GIFs store.
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: u32,
+    pending: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, width: u32) {
+        self.bits |= (code as u32) << self.pending;
+        self.pending += width;
+        while self.pending >= 8 {
+            self.bytes.push(self.bits as u8);
+            self.bits >>= 8;
+            self.pending -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.pending > 0 {
+            self.bytes.push(self.bits as u8);
+        }
+        let mut blocks = Vec::with_capacity(self.bytes.len() + self.bytes.len() / 255 + 2);
+        for chunk in self.bytes.chunks(255) {
+            blocks.push(chunk.len() as u8);
+            blocks.extend_from_slice(chunk);
+        }
+        blocks.push(0);
+        blocks
+    }
+}
+
+/// Compresses palette indices of `min_width` bits with GIF's flavor of
+/// LZW, starting over once the table fills all 12 bits.
+fn lzw(indices: &[u8], min_width: u32) -> Vec<u8> {
+    let clear = 1u16 << min_width;
+    let end = clear + 1;
+    let mut out = BitWriter {
+        bytes: Vec::new(),
+        bits: 0,
+        pending: 0,
+    };
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = end + 1;
+    let mut width = min_width + 1;
+    out.write(clear, width);
+    let mut indices = indices.iter();
+    let mut prefix = match indices.next() {
+        Some(&first) => first as u16,
+        None => {
+            out.write(end, width);
+            return out.finish();
+        }
+    };
+    for &index in indices {
+        if let Some(&code) = table.get(&(prefix, index)) {
+            prefix = code;
+            continue;
+        }
+        out.write(prefix, width);
+        if next == 4096 {
+            out.write(clear, width);
+            table.clear();
+            next = end + 1;
+            width = min_width + 1;
+        } else {
+            table.insert((prefix, index), next);
+            if next == 1 << width {
+                width += 1;
+            }
+            next += 1;
+        }
+        prefix = index as u16;
+    }
+    out.write(prefix, width);
+    // The decoder grows its table a step behind, which the end code has to
+    // account for too.
+    if next == 1 << width && width < 12 {
+        width += 1;
+    }
+    out.write(end, width);
+    out.finish()
+}
+
+fn io<'a>(path: &'a Path, operation: &'static str) -> impl FnOnce(io::Error) -> Error + 'a {
+    move |source| Error::Io {
+        path: path.to_path_buf(),
+        operation,
+        source,
+    }
+}
+
+/// Collects rendered frames and writes them out as a GIF that loops
+/// forever, when finished.
+pub struct GifWriter {
+    path: PathBuf,
+    spool_path: PathBuf,
+    spool: BufWriter<File>,
+    size: Option<(u32, u32)>,
+    fps: f64,
+    dither: bool,
+    frames: usize,
+    histogram: Histogram,
+}
+
+impl GifWriter {
+    /// A GIF at `path` played back at `fps` frames per second, dithering
+    /// each frame to its palette if `dither`.
+    pub fn create(path: &Path, fps: f64, dither: bool) -> Result<GifWriter, Error> {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let spool_path = path.with_file_name(format!(".{}.frames", name));
+        let spool = File::create(&spool_path).map_err(io(&spool_path, "create"))?;
+        Ok(GifWriter {
+            path: path.to_path_buf(),
+            spool_path,
+            spool: BufWriter::new(spool),
+            size: None,
+            fps,
+            dither,
+            frames: 0,
+            histogram: Histogram::new(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Adds a frame, which has to be the same size as the first.
+    pub fn add_frame(&mut self, image: &Image) -> Result<(), Error> {
+        let size = *self.size.get_or_insert((image.width, image.height));
+        if size != (image.width, image.height) {
+            return Err(Error::InvalidSettings(format!(
+                "every frame of {} has to be {}x{}",
+                self.path.display(),
+                size.0,
+                size.1
+            )));
+        }
+        if size.0 > u16::MAX as u32 || size.1 > u16::MAX as u32 {
+            return Err(Error::InvalidSettings(format!(
+                "a GIF can be at most {} pixels across",
+                u16::MAX
+            )));
+        }
+        let rgba = image.to_rgba8();
+        let mut rgb = Vec::with_capacity(rgba.len() / 4 * 3);
+        for pixel in rgba.chunks_exact(4) {
+            let color = [pixel[0], pixel[1], pixel[2]];
+            self.histogram.add(color);
+            rgb.extend_from_slice(&color);
+        }
+        self.spool
+            .write_all(&rgb)
+            .map_err(io(&self.spool_path, "write"))?;
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// How long frame `index` is shown, in the hundredths of a second GIFs
+    /// count in. The rounding is spread over the frames so that the whole
+    /// sequence keeps to `fps`.
+    fn delay(&self, index: usize) -> u16 {
+        let at = |index: usize| (index as f64 * 100.0 / self.fps).round();
+        (at(index + 1) - at(index)).clamp(1.0, u16::MAX as f64) as u16
+    }
+
+    /// Chooses the palette and writes the GIF.
+    pub fn finish(mut self) -> Result<(), Error> {
+        self.spool.flush().map_err(io(&self.spool_path, "write"))?;
+        let output = |operation| io(&self.path, operation);
+        let file = File::create(&self.path).map_err(output("create"))?;
+        let w = &mut BufWriter::new(file);
+        let (width, height) = self.size.unwrap_or((1, 1));
+        let palette = Palette::new(median_cut(&self.histogram, 256));
+        let bits = palette.bits();
+
+        let mut header = b"GIF89a".to_vec();
+        header.extend_from_slice(&(width as u16).to_le_bytes());
+        header.extend_from_slice(&(height as u16).to_le_bytes());
+        // A global color table of 2^bits colors, with as many bits of color
+        // resolution.
+        header.extend_from_slice(&[0x80 | ((bits - 1) << 4) as u8 | (bits - 1) as u8, 0, 0]);
+        for i in 0..1 << bits {
+            header.extend_from_slice(&palette.colors.get(i).copied().unwrap_or([0; 3]));
+        }
+        // Loop forever.
+        header.extend_from_slice(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00");
+        w.write_all(&header).map_err(output("write"))?;
+
+        let mut frames =
+            BufReader::new(File::open(&self.spool_path).map_err(io(&self.spool_path, "open"))?);
+        let mut rgb = vec![0; width as usize * height as usize * 3];
+        let min_width = bits.max(2);
+        for index in 0..self.frames {
+            frames
+                .read_exact(&mut rgb)
+                .map_err(io(&self.spool_path, "read"))?;
+            let indices = palette.quantize(&rgb, width as usize, self.dither);
+            let mut block = vec![0x21, 0xf9, 4, 0];
+            block.extend_from_slice(&self.delay(index).to_le_bytes());
+            block.extend_from_slice(&[0, 0, 0x2c, 0, 0, 0, 0]);
+            block.extend_from_slice(&(width as u16).to_le_bytes());
+            block.extend_from_slice(&(height as u16).to_le_bytes());
+            block.extend_from_slice(&[0, min_width as u8]);
+            block.extend(lzw(&indices, min_width));
+            w.write_all(&block).map_err(output("write"))?;
+        }
+        w.write_all(&[0x3b]).map_err(output("write"))?;
+        w.flush().map_err(output("write"))
+    }
+}
+
+impl Drop for GifWriter {
+    fn drop(&mut self) {
+        // The spooled frames are only ever needed until the GIF is written.
+        match fs::remove_file(&self.spool_path) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => {
+                warn!("can't remove {}: {}", self.spool_path.display(), error)
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,439 @@
+#[cfg(feature = "files")]
+use deflate::{write::ZlibEncoder, Compression};
+#[cfg(feature = "files")]
+use png::HasParameters;
+#[cfg(feature = "files")]
+use std::fs::File;
+#[cfg(feature = "files")]
+use std::io::{self, BufReader, BufWriter, Write};
+#[cfg(feature = "files")]
+use std::mem;
+#[cfg(feature = "files")]
+use std::path::Path;
+
+#[cfg(feature = "files")]
+use crate::error::Error;
+#[cfg(feature = "files")]
+use crate::exr;
+use crate::scalar::Scalar;
+use crate::vector::Color;
+
+/// A linear-light framebuffer.
+#[derive(Clone)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<Color>,
+}
+
+pub(crate) fn encode_srgb(linear: Scalar) -> u8 {
+    let linear = linear.clamp(0.0, 1.0);
+    let srgb = if linear <= 0.003_130_8 {
+        12.92 * linear
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    };
+    (srgb * 255.0).round() as u8
+}
+
+/// Undoes the sRGB transfer curve of an 8-bit channel.
+pub fn decode_srgb(encoded: u8) -> Scalar {
+    let srgb = encoded as Scalar / 255.0;
+    if srgb <= 0.040_45 {
+        srgb / 12.92
+    } else {
+        ((srgb + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// How light past white is brought into the range an 8-bit image holds.
+/// EXR files are written linear whatever the tonemap, so they keep it all.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Tonemap {
+    /// Clips every channel at 1, as images always were.
+    #[default]
+    Clamp,
+    /// `c / (1 + c)`, which never quite reaches white.
+    Reinhard,
+    /// Narkowicz's fit to the ACES filmic curve, with a toe and a shoulder.
+    Aces,
+}
+
+impl Tonemap {
+    pub const ALL: [Tonemap; 3] = [Tonemap::Clamp, Tonemap::Reinhard, Tonemap::Aces];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Tonemap::Clamp => "clamp",
+            Tonemap::Reinhard => "reinhard",
+            Tonemap::Aces => "aces",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Tonemap> {
+        Tonemap::ALL.iter().copied().find(|t| t.name() == name)
+    }
+
+    pub fn apply(self, color: Color) -> Color {
+        let curve = |c: Scalar| match self {
+            Tonemap::Clamp => c,
+            Tonemap::Reinhard => c.max(0.0) / (1.0 + c.max(0.0)),
+            Tonemap::Aces => {
+                let c = c.max(0.0);
+                (c * (2.51 * c + 0.03) / (c * (2.43 * c + 0.59) + 0.14)).min(1.0)
+            }
+        };
+        Color::new(curve(color.x), curve(color.y), curve(color.z))
+    }
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32) -> Image {
+        Image {
+            width,
+            height,
+            pixels: vec![Color::zero(); (width * height) as usize],
+        }
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> Color {
+        self.pixels[(x + y * self.width) as usize]
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, color: Color) {
+        self.pixels[(x + y * self.width) as usize] = color;
+    }
+
+    /// The `width` by `height` pixels with their top left corner at (x, y).
+    pub fn crop(&self, x: u32, y: u32, width: u32, height: u32) -> Image {
+        let mut crop = Image::new(width, height);
+        for j in 0..height {
+            for i in 0..width {
+                crop.set(i, j, self.get(x + i, y + j));
+            }
+        }
+        crop
+    }
+
+    /// Copies `image` over this one with its top left corner at (x, y).
+    pub fn paste(&mut self, image: &Image, x: u32, y: u32) {
+        for j in 0..image.height {
+            for i in 0..image.width {
+                self.set(x + i, y + j, image.get(i, j));
+            }
+        }
+    }
+
+    /// A copy with `tonemap` applied to every pixel.
+    pub fn tonemapped(&self, tonemap: Tonemap) -> Image {
+        Image {
+            pixels: self.pixels.iter().map(|&c| tonemap.apply(c)).collect(),
+            ..*self
+        }
+    }
+
+    /// Makes every pixel with a NaN or infinite channel black, and says how
+    /// many there were.
+    pub fn flush_non_finite(&mut self) -> usize {
+        let mut flushed = 0;
+        for pixel in &mut self.pixels {
+            if !(pixel.x.is_finite() && pixel.y.is_finite() && pixel.z.is_finite()) {
+                *pixel = Color::zero();
+                flushed += 1;
+            }
+        }
+        flushed
+    }
+
+    /// Clamps to `[0, 1]` and encodes to 8-bit sRGB with an opaque alpha.
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(self.pixels.len() * 4);
+        for color in &self.pixels {
+            data.push(encode_srgb(color.x));
+            data.push(encode_srgb(color.y));
+            data.push(encode_srgb(color.z));
+            data.push(255);
+        }
+        data
+    }
+}
+
+/// The most pixels an image can have to be read or rendered, 8192 by 8192.
+/// A file's header alone says how big the image is, and a scene's settings
+/// alone how big the render is, so a few bytes could otherwise ask for far
+/// more memory than the machine has.
+pub(crate) const MAX_PIXELS: u64 = 1 << 26;
+
+/// Reading and writing PNG files.
+#[cfg(feature = "files")]
+impl Image {
+    /// Reads an EXR file as the linear light it holds, or anything else as
+    /// a PNG with [`Image::read_png`].
+    pub fn read(path: &Path) -> Result<Image, String> {
+        if exr::is_exr(path) {
+            exr::read(path)
+        } else {
+            Image::read_png(path)
+        }
+    }
+
+    /// Reads an 8- or 16-bit PNG of any color type, treating it as sRGB and
+    /// ignoring alpha.
+    pub fn read_png(path: &Path) -> Result<Image, String> {
+        Image::decode_png(path, decode_srgb)
+    }
+
+    /// Reads a PNG that stores linear values rather than colors, such as a
+    /// normal map, mapping each channel straight to `[0, 1]`.
+    pub fn read_linear_png(path: &Path) -> Result<Image, String> {
+        Image::decode_png(path, |encoded| encoded as Scalar / 255.0)
+    }
+
+    fn decode_png(path: &Path, decode: fn(u8) -> Scalar) -> Result<Image, String> {
+        let fail = |e: &dyn std::fmt::Display| format!("can't read {}: {}", path.display(), e);
+        let file = File::open(path).map_err(|e| fail(&e))?;
+        let decoder = png::Decoder::new(BufReader::new(file));
+        let (info, mut reader) = decoder.read_info().map_err(|e| fail(&e))?;
+        if info.width as u64 * info.height as u64 > MAX_PIXELS {
+            return Err(fail(&format_args!(
+                "{}x{} is more than {} pixels",
+                info.width, info.height, MAX_PIXELS
+            )));
+        }
+        let mut data = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut data).map_err(|e| fail(&e))?;
+        let (color_type, _) = reader.output_color_type();
+        let channels = color_type.samples();
+        let mut image = Image::new(info.width, info.height);
+        for (pixel, bytes) in image.pixels.iter_mut().zip(data.chunks_exact(channels)) {
+            *pixel = match color_type {
+                png::ColorType::Grayscale | png::ColorType::GrayscaleAlpha => {
+                    let gray = decode(bytes[0]);
+                    Color::new(gray, gray, gray)
+                }
+                _ => Color::new(decode(bytes[0]), decode(bytes[1]), decode(bytes[2])),
+            };
+        }
+        Ok(image)
+    }
+
+    /// Writes an EXR file if `path` ends in `.exr`, or a PNG otherwise.
+    pub fn write(&self, path: &Path) -> Result<(), Error> {
+        if exr::is_exr(path) {
+            exr::write(self, path)
+        } else {
+            self.write_png(path)
+        }
+    }
+
+    /// Writes the image as [`Image::write`] does, with `tonemap` applied
+    /// unless it's an EXR.
+    pub fn write_tonemapped(&self, path: &Path, tonemap: Tonemap) -> Result<(), Error> {
+        match tonemap {
+            Tonemap::Clamp => self.write(path),
+            _ if exr::is_exr(path) => exr::write(self, path),
+            _ => self.tonemapped(tonemap).write_png(path),
+        }
+    }
+
+    pub fn write_png(&self, path: &Path) -> Result<(), Error> {
+        let io = |operation| {
+            move |source| Error::Io {
+                path: path.to_path_buf(),
+                operation,
+                source,
+            }
+        };
+        let file = File::create(path).map_err(io("create"))?;
+        self.encode_png(BufWriter::new(file), path)
+    }
+
+    /// Writes the image to `out` as a PNG, such as to stdout, calling it
+    /// `path` in errors.
+    pub fn encode_png(&self, mut out: impl Write, path: &Path) -> Result<(), Error> {
+        let mut encoder = png::Encoder::new(&mut out, self.width, self.height);
+        encoder.set(png::ColorType::RGBA).set(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&self.to_rgba8()))
+            .map_err(|error| encoding(path, error))?;
+        // Dropping a buffer would flush it too, but quietly drop any error.
+        out.flush().map_err(|source| Error::Io {
+            path: path.to_path_buf(),
+            operation: "write",
+            source,
+        })
+    }
+}
+
+#[cfg(feature = "files")]
+fn encoding(path: &Path, error: png::EncodingError) -> Error {
+    match error {
+        png::EncodingError::IoError(source) => Error::Io {
+            path: path.to_path_buf(),
+            operation: "write",
+            source,
+        },
+        source => Error::Encode {
+            path: path.to_path_buf(),
+            source,
+        },
+    }
+}
+
+/// The filter type byte a row filtered by `Sub` starts with.
+#[cfg(feature = "files")]
+const SUB_FILTER: u8 = 1;
+
+/// Compressed image data goes out in chunks of about this many bytes.
+#[cfg(feature = "files")]
+const CHUNK_BYTES: usize = 1 << 16;
+
+/// Where the compressed rows of a [`PngRows`] go, a chunk at a time.
+#[cfg(feature = "files")]
+struct Chunks<'a> {
+    writer: png::Writer<&'a mut BufWriter<File>>,
+    data: Vec<u8>,
+}
+
+#[cfg(feature = "files")]
+impl Chunks<'_> {
+    fn emit(&mut self) -> io::Result<()> {
+        let data = mem::take(&mut self.data);
+        self.writer
+            .write_chunk(*b"IDAT", &data)
+            .map_err(|error| match error {
+                png::EncodingError::IoError(error) => error,
+                other => io::Error::other(other.to_string()),
+            })
+    }
+}
+
+#[cfg(feature = "files")]
+impl Write for Chunks<'_> {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.data.extend_from_slice(bytes);
+        if self.data.len() >= CHUNK_BYTES {
+            self.emit()?;
+        }
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A PNG being written a row at a time, by [`stream_png`]. Rows are filtered
+/// and compressed just as [`Image::write_png`] does it, so the image data
+/// comes out the same, only split over several chunks.
+#[cfg(feature = "files")]
+pub struct PngRows<'a> {
+    zlib: ZlibEncoder<Chunks<'a>>,
+    path: &'a Path,
+    row: Vec<u8>,
+    width: u32,
+    written: u32,
+}
+
+#[cfg(feature = "files")]
+impl PngRows<'_> {
+    /// Writes the next row down, clamped and encoded like
+    /// [`Image::to_rgba8`].
+    pub fn write_row(&mut self, row: &[Color]) -> Result<(), Error> {
+        if row.len() != self.width as usize {
+            let message = format!(
+                "a row of {} pixels in a {} wide image",
+                row.len(),
+                self.width
+            );
+            return Err(encoding(
+                self.path,
+                png::EncodingError::Format(message.into()),
+            ));
+        }
+        self.row.clear();
+        for color in row {
+            self.row.extend_from_slice(&[
+                encode_srgb(color.x),
+                encode_srgb(color.y),
+                encode_srgb(color.z),
+                255,
+            ]);
+        }
+        // The sub filter, leaving each byte as the difference from the one a
+        // pixel to its left.
+        for i in (4..self.row.len()).rev() {
+            self.row[i] = self.row[i].wrapping_sub(self.row[i - 4]);
+        }
+        let path = self.path;
+        let fail = |source| Error::Io {
+            path: path.to_path_buf(),
+            operation: "write",
+            source,
+        };
+        self.zlib.write_all(&[SUB_FILTER]).map_err(fail)?;
+        self.zlib.write_all(&self.row).map_err(fail)?;
+        self.written += 1;
+        Ok(())
+    }
+
+    /// About how many bytes of the image are held at once: a row and the
+    /// compressed data waiting to go out.
+    pub fn buffer_bytes(&self) -> usize {
+        self.row.capacity() + CHUNK_BYTES
+    }
+}
+
+/// Writes a `width` by `height` PNG to `path` without holding the image:
+/// `write` is handed the [`PngRows`] to write each row to in turn, and has to
+/// write every one of them.
+#[cfg(feature = "files")]
+pub fn stream_png<T>(
+    path: &Path,
+    width: u32,
+    height: u32,
+    write: impl FnOnce(&mut PngRows) -> Result<T, Error>,
+) -> Result<T, Error> {
+    let file = File::create(path).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        operation: "create",
+        source,
+    })?;
+    let w = &mut BufWriter::new(file);
+    let mut encoder = png::Encoder::new(&mut *w, width, height);
+    encoder.set(png::ColorType::RGBA).set(png::BitDepth::Eight);
+    let writer = encoder
+        .write_header()
+        .map_err(|error| encoding(path, error))?;
+    let fail = |source| Error::Io {
+        path: path.to_path_buf(),
+        operation: "write",
+        source,
+    };
+    // The rows borrow the file until they're done with.
+    let value = {
+        let chunks = Chunks {
+            writer,
+            data: Vec::new(),
+        };
+        let mut rows = PngRows {
+            zlib: ZlibEncoder::new(chunks, Compression::Fast),
+            path,
+            row: Vec::with_capacity(4 * width as usize),
+            width,
+            written: 0,
+        };
+        let value = write(&mut rows)?;
+        if rows.written != height {
+            let message = format!("{} of the {} rows were written", rows.written, height);
+            return Err(encoding(path, png::EncodingError::Format(message.into())));
+        }
+        let mut chunks = rows.zlib.finish().map_err(fail)?;
+        // Dropping the writer ends the file.
+        chunks.emit().map_err(fail)?;
+        value
+    };
+    w.flush().map_err(fail)?;
+    Ok(value)
+}
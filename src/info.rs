@@ -0,0 +1,170 @@
+//! A summary of what's in a scene, as `info` prints it: how many objects,
+//! triangles, lights and materials there are, where they are, and roughly
+//! how much memory they take.
+
+use std::any;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::mem;
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::bvh::{Bvh, BvhStats};
+use crate::scene::{Object, Scene};
+
+/// The name of `T` without its module path or type parameters, such as
+/// `Transformed` for `Transformed<Arc<dyn Shape>>`.
+pub(crate) fn type_name<T: ?Sized>() -> &'static str {
+    let name = any::type_name::<T>();
+    let name = &name[..name.find('<').unwrap_or(name.len())];
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectInfo {
+    pub shape: &'static str,
+    pub material: &'static str,
+    pub triangles: usize,
+    /// `None` for unbounded shapes such as planes.
+    pub bounds: Option<Aabb>,
+    pub bytes: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneInfo {
+    pub objects: Vec<ObjectInfo>,
+    pub animated_objects: usize,
+    pub triangles: usize,
+    /// How many lights there are of each kind, by name.
+    pub lights: Vec<(&'static str, usize)>,
+    /// How many different materials there are of each kind, by name; a
+    /// material shared by several objects counts once.
+    pub materials: Vec<(&'static str, usize)>,
+    /// The box around every bounded object, if there are any.
+    pub bounds: Option<Aabb>,
+    pub layers: Vec<String>,
+    /// Roughly how much memory the scene takes: its shapes, the BVH a
+    /// render builds over them, and the objects that place them.
+    pub geometry_bytes: usize,
+    pub bvh_bytes: usize,
+    pub object_bytes: usize,
+    pub bvh: BvhStats,
+}
+
+fn counts(names: impl Iterator<Item = &'static str>) -> Vec<(&'static str, usize)> {
+    let mut counts = BTreeMap::new();
+    for name in names {
+        *counts.entry(name).or_insert(0) += 1;
+    }
+    counts.into_iter().collect()
+}
+
+impl SceneInfo {
+    pub fn of(scene: &Scene) -> SceneInfo {
+        let objects: Vec<ObjectInfo> = scene
+            .objects
+            .iter()
+            .map(|object| ObjectInfo {
+                shape: object.shape.name(),
+                material: object.material.name(),
+                triangles: object.shape.triangle_count(),
+                bounds: object.shape.bounds(),
+                bytes: object.shape.as_ref().memory_bytes(),
+            })
+            .collect();
+        let boxes: Vec<Aabb> = objects.iter().filter_map(|object| object.bounds).collect();
+        let bvh = Bvh::new(&boxes);
+        let mut seen = HashSet::new();
+        let materials = scene
+            .objects
+            .iter()
+            .filter(|object| seen.insert(Arc::as_ptr(&object.material) as *const ()))
+            .map(|object| object.material.name());
+        SceneInfo {
+            animated_objects: scene.animation.objects.len(),
+            triangles: objects.iter().map(|object| object.triangles).sum(),
+            lights: counts(scene.lights.iter().map(|light| light.name())),
+            materials: counts(materials),
+            bounds: boxes.iter().copied().reduce(|a, b| a.union(&b)),
+            layers: scene
+                .layer_names()
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            geometry_bytes: scene.geometry_bytes(),
+            bvh_bytes: bvh.heap_bytes(),
+            object_bytes: scene.objects.len() * mem::size_of::<Object>(),
+            bvh: bvh.stats(),
+            objects,
+        }
+    }
+
+    pub fn memory_bytes(&self) -> usize {
+        self.geometry_bytes + self.bvh_bytes + self.object_bytes
+    }
+}
+
+fn write_counts(f: &mut fmt::Formatter, counts: &[(&str, usize)]) -> fmt::Result {
+    let total: usize = counts.iter().map(|(_, count)| count).sum();
+    write!(f, "{}", total)?;
+    for (i, (name, count)) in counts.iter().enumerate() {
+        write!(f, "{}{} {}", if i == 0 { " (" } else { ", " }, name, count)?;
+    }
+    if !counts.is_empty() {
+        write!(f, ")")?;
+    }
+    writeln!(f)
+}
+
+fn write_bounds(f: &mut fmt::Formatter, bounds: Option<Aabb>) -> fmt::Result {
+    match bounds {
+        Some(aabb) => write!(f, "{:.3} to {:.3}", aabb.min, aabb.max),
+        None => write!(f, "unbounded"),
+    }
+}
+
+/// The totals, then a line for each object.
+impl fmt::Display for SceneInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let unbounded = self.objects.iter().filter(|o| o.bounds.is_none()).count();
+        writeln!(
+            f,
+            "objects: {} ({} unbounded, {} animated)",
+            self.objects.len(),
+            unbounded,
+            self.animated_objects
+        )?;
+        writeln!(f, "triangles: {}", self.triangles)?;
+        write!(f, "lights: ")?;
+        write_counts(f, &self.lights)?;
+        write!(f, "materials: ")?;
+        write_counts(f, &self.materials)?;
+        write!(f, "bounds: ")?;
+        write_bounds(f, self.bounds)?;
+        writeln!(f)?;
+        writeln!(f, "layers: {}", self.layers.join(", "))?;
+        writeln!(
+            f,
+            "estimated memory: {} bytes ({} geometry, {} BVH, {} objects)",
+            self.memory_bytes(),
+            self.geometry_bytes,
+            self.bvh_bytes,
+            self.object_bytes
+        )?;
+        writeln!(f)?;
+        write!(
+            f,
+            "{:>7}  {:<14} {:<14} {:>9} {:>10}  bounds",
+            "object", "shape", "material", "triangles", "bytes"
+        )?;
+        for (i, object) in self.objects.iter().enumerate() {
+            write!(
+                f,
+                "\n{:>7}  {:<14} {:<14} {:>9} {:>10}  ",
+                i, object.shape, object.material, object.triangles, object.bytes
+            )?;
+            write_bounds(f, object.bounds)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,406 @@
+use crate::light::LightSample;
+use crate::material::Material;
+use crate::photon::PhotonMap;
+use crate::ray::{Ray, EPSILON};
+use crate::sampler::Sampler;
+use crate::sampling::{sample_cosine_hemisphere, OrthonormalBasis};
+use crate::scalar::Scalar;
+use crate::scene::{Hit, Object, RayKind, Scene};
+use crate::shapes::HitRecord;
+use crate::vector::{Color, Vector};
+
+pub trait Integrator: Sync {
+    /// Estimates the radiance arriving at the ray's origin along the camera
+    /// ray.
+    fn radiance(&self, ray: &Ray, scene: &Scene, sampler: &mut Sampler) -> Color {
+        let hit = scene.trace(ray, RayKind::Camera, EPSILON, Scalar::INFINITY);
+        self.radiance_from(ray, hit, scene, sampler)
+    }
+
+    /// [`radiance`](Integrator::radiance) given what the camera ray hits,
+    /// for rays traced beforehand, as in a packet.
+    fn radiance_from<'a>(
+        &self,
+        ray: &Ray,
+        hit: Option<Hit<'a>>,
+        scene: &'a Scene,
+        sampler: &mut Sampler,
+    ) -> Color;
+}
+
+/// The light from one sample of light `index` reflected toward `wo`,
+/// divided by the sample's PDF.
+fn light_contribution(
+    scene: &Scene,
+    hit: &HitRecord,
+    material: &dyn Material,
+    wo: Vector,
+    index: usize,
+    sample: &LightSample,
+) -> Color {
+    let cosine = hit.shading_normal * sample.direction;
+    if cosine <= 0.0
+        || hit.normal * sample.direction <= 0.0
+        || scene.shadowed(index, hit.point, sample.direction, sample.distance)
+    {
+        return Color::zero();
+    }
+    let f = material.eval(hit, wo, sample.direction);
+    let weight = cosine * scene.transmittance(sample.distance) / sample.pdf;
+    weight * f.component_mul(sample.radiance)
+}
+
+fn ray_kind(depth: u32) -> RayKind {
+    if depth == 0 {
+        RayKind::Camera
+    } else {
+        RayKind::Secondary
+    }
+}
+
+/// Adds the medium's contribution along a segment of `distance` and returns
+/// the transmittance over it.
+fn march_medium(
+    scene: &Scene,
+    ray: &Ray,
+    distance: Scalar,
+    sampler: &mut Sampler,
+    color: &mut Color,
+    throughput: Color,
+) -> Scalar {
+    match &scene.medium {
+        Some(medium) => {
+            *color += throughput.component_mul(medium.in_scatter(scene, ray, distance, sampler));
+            medium.transmittance(distance)
+        }
+        None => 1.0,
+    }
+}
+
+/// Direct lighting from one sample of each light at the first diffuse hit,
+/// following mirror and glass surfaces for up to `max_depth` bounces on the
+/// way there. With a `caustics` photon map, its estimate is added at that hit.
+pub struct Whitted {
+    pub max_depth: u32,
+    pub caustics: Option<PhotonMap>,
+}
+
+impl Integrator for Whitted {
+    fn radiance_from<'a>(
+        &self,
+        ray: &Ray,
+        first: Option<Hit<'a>>,
+        scene: &'a Scene,
+        sampler: &mut Sampler,
+    ) -> Color {
+        let mut ray = *ray;
+        let mut color = Color::zero();
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        let mut first = Some(first);
+        for depth in 0..=self.max_depth {
+            let hit = first
+                .take()
+                .unwrap_or_else(|| scene.trace(&ray, ray_kind(depth), EPSILON, Scalar::INFINITY));
+            let distance = hit.as_ref().map_or(Scalar::INFINITY, |hit| hit.record.t);
+            throughput =
+                march_medium(scene, &ray, distance, sampler, &mut color, throughput) * throughput;
+            let hit = match hit {
+                Some(hit) => hit,
+                None => {
+                    color += throughput.component_mul(scene.background);
+                    break;
+                }
+            };
+            let material = hit.object.material.as_ref();
+            color += throughput.component_mul(material.emitted(&hit.record));
+            if material.is_specular() {
+                if depth == self.max_depth {
+                    break;
+                }
+                match material.scatter(&ray, &hit.record, sampler) {
+                    Some(scatter) => {
+                        throughput = throughput.component_mul(scatter.attenuation);
+                        ray = Ray::new(hit.record.point, scatter.direction);
+                        continue;
+                    }
+                    None => break,
+                }
+            }
+            let wo = -ray.direction;
+            let mut surface = Color::zero();
+            for (index, light) in scene.lights.iter().enumerate() {
+                if !scene.illuminates(index, hit.object) {
+                    continue;
+                }
+                if let Some(sample) = light.sample(hit.record.point, sampler) {
+                    surface += light_contribution(scene, &hit.record, material, wo, index, &sample);
+                }
+            }
+            if let Some(caustics) = &self.caustics {
+                surface += caustics.radiance(&hit.record, material, wo);
+            }
+            color += throughput.component_mul(surface);
+            break;
+        }
+        color
+    }
+}
+
+fn power_heuristic(pdf: Scalar, other: Scalar) -> Scalar {
+    if pdf == 0.0 {
+        return 0.0;
+    }
+    pdf * pdf / (pdf * pdf + other * other)
+}
+
+/// Unidirectional path tracing: each hit scatters the path according to its
+/// material until it escapes, is absorbed, or reaches `max_depth` bounces.
+///
+/// Past the `russian_roulette` depth, paths survive each bounce with a
+/// probability given by their throughput and are reweighted to stay unbiased.
+///
+/// With `next_event_estimation`, every bounce also samples one randomly
+/// chosen light directly. Emission from lights found by the following bounce
+/// is then either skipped so it isn't counted twice or, with
+/// `multiple_importance_sampling`, both estimates are kept and weighted by
+/// the power heuristic. Without next event estimation only the point lights,
+/// which can't be hit by chance, are sampled directly.
+///
+/// With a `caustics` photon map, its estimate is added at every diffuse hit,
+/// and emission reached from a diffuse hit through specular surfaces only is
+/// skipped since the photons already carry it.
+///
+/// Each sample's indirect light, which reached the camera off more than one
+/// surface, is scaled down so that no channel exceeds `clamp_indirect`. This
+/// removes fireflies at the cost of bias: clamped images come out darker
+/// than the true solution wherever bright indirect paths matter.
+pub struct PathTracer {
+    pub max_depth: u32,
+    pub russian_roulette: Option<u32>,
+    pub next_event_estimation: bool,
+    pub multiple_importance_sampling: bool,
+    pub caustics: Option<PhotonMap>,
+    pub clamp_indirect: Scalar,
+}
+
+// Where a BSDF-sampled ray came from, for weighting the emission it finds.
+struct Bounce<'a> {
+    point: Vector,
+    pdf: Scalar,
+    specular: bool,
+    object: &'a Object,
+}
+
+impl PathTracer {
+    /// Light arriving at `hit` on `object` straight from the lights. Every
+    /// light is picked as often whether or not it shines on `object`, so that
+    /// the emission weights of scattered rays needn't depend on the links.
+    fn direct_lighting(
+        &self,
+        scene: &Scene,
+        hit: &HitRecord,
+        object: &Object,
+        wo: Vector,
+        sampler: &mut Sampler,
+    ) -> Color {
+        let material = object.material.as_ref();
+        if self.next_event_estimation {
+            let count = scene.light_count();
+            if count == 0 {
+                return Color::zero();
+            }
+            let index = ((sampler.next_scalar() * count as Scalar) as usize).min(count - 1);
+            if !scene.illuminates(index, object) {
+                return Color::zero();
+            }
+            let sample = match scene.sample_light(index, hit.point, sampler) {
+                Some(sample) => sample,
+                None => return Color::zero(),
+            };
+            let delta = scene
+                .lights
+                .get(index)
+                .is_some_and(|light| light.is_delta());
+            let weight = if self.multiple_importance_sampling && !delta {
+                let bsdf_pdf = material.pdf(hit, wo, sample.direction);
+                power_heuristic(sample.pdf / count as Scalar, bsdf_pdf)
+            } else {
+                1.0
+            };
+            (weight * count as Scalar)
+                * light_contribution(scene, hit, material, wo, index, &sample)
+        } else {
+            let mut color = Color::zero();
+            for (index, light) in scene.lights.iter().enumerate() {
+                if !light.is_delta() || !scene.illuminates(index, object) {
+                    continue;
+                }
+                if let Some(sample) = light.sample(hit.point, sampler) {
+                    color += light_contribution(scene, hit, material, wo, index, &sample);
+                }
+            }
+            color
+        }
+    }
+
+    /// The weight of emission from light `index` found along `direction` by
+    /// a BSDF-sampled ray, or by the camera ray when there is no `bounce`.
+    fn emission_weight(
+        &self,
+        scene: &Scene,
+        index: Option<usize>,
+        bounce: &Option<Bounce>,
+        direction: Vector,
+    ) -> Scalar {
+        match (index, bounce) {
+            (Some(index), Some(bounce)) if !scene.illuminates(index, bounce.object) => 0.0,
+            (Some(index), Some(bounce)) if self.next_event_estimation && !bounce.specular => {
+                if !self.multiple_importance_sampling {
+                    return 0.0;
+                }
+                let light_pdf =
+                    scene.light_pdf(index, bounce.point, direction) / scene.light_count() as Scalar;
+                power_heuristic(bounce.pdf, light_pdf)
+            }
+            _ => 1.0,
+        }
+    }
+}
+
+impl Integrator for PathTracer {
+    fn radiance_from<'a>(
+        &self,
+        ray: &Ray,
+        first: Option<Hit<'a>>,
+        scene: &'a Scene,
+        sampler: &mut Sampler,
+    ) -> Color {
+        let mut ray = *ray;
+        let mut color = Color::zero();
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        let mut bounce: Option<Bounce> = None;
+        let mut diffuse_seen = false;
+        // Light that reached the camera off more than one surface, kept apart
+        // so that it can be clamped.
+        let mut indirect = Color::zero();
+        let mut first = Some(first);
+        for depth in 0..=self.max_depth {
+            // Light reached from a diffuse surface through specular ones only
+            // is already in the photon map.
+            let caustic = self.caustics.is_some()
+                && diffuse_seen
+                && bounce.as_ref().is_some_and(|bounce| bounce.specular);
+            let hit = first
+                .take()
+                .unwrap_or_else(|| scene.trace(&ray, ray_kind(depth), EPSILON, Scalar::INFINITY));
+            let distance = hit.as_ref().map_or(Scalar::INFINITY, |hit| hit.record.t);
+            let medium = if depth == 0 {
+                &mut color
+            } else {
+                &mut indirect
+            };
+            throughput =
+                march_medium(scene, &ray, distance, sampler, medium, throughput) * throughput;
+            let emission = if depth <= 1 {
+                &mut color
+            } else {
+                &mut indirect
+            };
+            let hit = match hit {
+                Some(hit) => hit,
+                None => {
+                    if !caustic {
+                        let weight = self.emission_weight(
+                            scene,
+                            scene.environment_light(),
+                            &bounce,
+                            ray.direction,
+                        );
+                        *emission += (weight * throughput).component_mul(scene.background);
+                    }
+                    break;
+                }
+            };
+            let material = hit.object.material.as_ref();
+            if !caustic {
+                let weight = self.emission_weight(scene, hit.object.light, &bounce, ray.direction);
+                *emission += (weight * throughput).component_mul(material.emitted(&hit.record));
+            }
+            let specular = material.is_specular();
+            if !specular {
+                let wo = -ray.direction;
+                let mut direct = self.direct_lighting(scene, &hit.record, hit.object, wo, sampler);
+                if let Some(caustics) = &self.caustics {
+                    direct += caustics.radiance(&hit.record, material, wo);
+                }
+                let target = if depth == 0 {
+                    &mut color
+                } else {
+                    &mut indirect
+                };
+                *target += throughput.component_mul(direct);
+            }
+            diffuse_seen |= !specular;
+            if depth == self.max_depth {
+                break;
+            }
+            let scatter = match material.scatter(&ray, &hit.record, sampler) {
+                Some(scatter) => scatter,
+                None => break,
+            };
+            throughput = throughput.component_mul(scatter.attenuation);
+            if matches!(self.russian_roulette, Some(min_depth) if depth >= min_depth) {
+                let survival = throughput.max_component().clamp(0.05, 0.95);
+                if sampler.next_scalar() >= survival {
+                    break;
+                }
+                throughput = (1.0 / survival) * throughput;
+            }
+            bounce = Some(Bounce {
+                point: hit.record.point,
+                pdf: scatter.pdf,
+                specular,
+                object: hit.object,
+            });
+            ray = Ray::new(hit.record.point, scatter.direction);
+        }
+        let brightest = indirect.max_component();
+        if brightest > self.clamp_indirect {
+            indirect = (self.clamp_indirect / brightest) * indirect;
+        }
+        color + indirect
+    }
+}
+
+/// Ambient occlusion: the fraction of cosine-weighted directions above each
+/// primary hit that travel `max_distance` without hitting anything, as a
+/// grayscale value. Rays escaping the scene read as fully open.
+pub struct AmbientOcclusion {
+    pub samples: u32,
+    pub max_distance: Scalar,
+}
+
+impl Integrator for AmbientOcclusion {
+    fn radiance_from<'a>(
+        &self,
+        _ray: &Ray,
+        first: Option<Hit<'a>>,
+        scene: &'a Scene,
+        sampler: &mut Sampler,
+    ) -> Color {
+        let hit = match first {
+            Some(hit) => hit.record,
+            None => return Color::new(1.0, 1.0, 1.0),
+        };
+        let basis = OrthonormalBasis::from_normal(hit.normal);
+        let mut open = 0;
+        for _ in 0..self.samples {
+            let (local, _) = sample_cosine_hemisphere(sampler.next_scalar(), sampler.next_scalar());
+            if !scene.occluded(hit.point, basis.to_world(local), self.max_distance) {
+                open += 1;
+            }
+        }
+        let visibility = open as Scalar / self.samples as Scalar;
+        Color::new(visibility, visibility, visibility)
+    }
+}
@@ -0,0 +1,54 @@
+pub mod aabb;
+pub mod animation;
+pub mod bvh;
+pub mod camera;
+mod check;
+#[cfg(feature = "files")]
+pub mod checkpoint;
+pub mod compare;
+#[cfg(feature = "files")]
+pub mod config;
+pub mod error;
+#[cfg(feature = "files")]
+pub mod exr;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "files")]
+pub mod gif;
+pub mod image;
+pub mod info;
+pub mod integrator;
+pub mod json;
+pub mod light;
+pub mod material;
+pub mod matrix;
+pub mod medium;
+pub mod noise;
+pub mod obj;
+pub mod overrides;
+#[cfg(feature = "files")]
+pub mod parts;
+pub mod photon;
+#[cfg(feature = "preview")]
+pub mod preview;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod quaternion;
+pub mod ray;
+pub mod render;
+pub mod report;
+pub mod sampler;
+pub mod sampling;
+pub mod scalar;
+pub mod scene;
+#[cfg(feature = "files")]
+pub mod scene_file;
+pub mod shapes;
+#[cfg(feature = "files")]
+pub mod snapshot;
+pub mod stats;
+pub mod texture;
+pub mod tile;
+pub mod vector;
+#[cfg(feature = "files")]
+pub mod watch;
@@ -0,0 +1,240 @@
+use std::sync::Arc;
+
+use crate::check;
+use crate::info;
+use crate::ray::{Ray, EPSILON};
+use crate::sampler::Sampler;
+use crate::sampling::{sample_cosine_hemisphere, sample_uniform_sphere, OrthonormalBasis};
+use crate::scalar::{consts::PI, Scalar};
+use crate::shapes::Shape;
+use crate::vector::{Color, Vector};
+
+/// A photon leaving a light, carrying `power` of its total emitted power.
+#[derive(Debug, Copy, Clone)]
+pub struct PhotonEmission {
+    pub ray: Ray,
+    pub power: Color,
+}
+
+/// Light arriving at a point from one sampled point on a light.
+#[derive(Debug, Copy, Clone)]
+pub struct LightSample {
+    /// Unit direction from the shaded point toward the light.
+    pub direction: Vector,
+    pub distance: Scalar,
+    pub radiance: Color,
+    /// Solid-angle density of `direction`; 1 for delta lights, whose
+    /// `radiance` already includes the falloff.
+    pub pdf: Scalar,
+}
+
+pub trait Light: Send + Sync {
+    fn sample(&self, point: Vector, sampler: &mut Sampler) -> Option<LightSample>;
+
+    /// The solid-angle density with which `sample` picks `direction` from
+    /// `point`; always 0 for delta lights.
+    fn pdf(&self, point: Vector, direction: Vector) -> Scalar;
+
+    /// Samples a ray leaving the light, weighted so that the expected power
+    /// equals the light's total emitted power. Lights at infinity emit none.
+    fn emit_photon(&self, _sampler: &mut Sampler) -> Option<PhotonEmission> {
+        None
+    }
+
+    /// Whether the light occupies no area, so that it can only be reached by
+    /// sampling it explicitly.
+    fn is_delta(&self) -> bool {
+        false
+    }
+
+    /// Checks the light's parameters the way [`Shape::validate`] checks a
+    /// shape's.
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// What kind of light this is, such as `PointLight`.
+    fn name(&self) -> &'static str {
+        info::type_name::<Self>()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PointLight {
+    pub source: Vector,
+    pub color: Color,
+    pub intensity: Scalar,
+}
+
+impl PointLight {
+    pub fn new(source: Vector, color: Color, intensity: Scalar) -> PointLight {
+        PointLight {
+            source,
+            color,
+            intensity,
+        }
+    }
+
+    pub fn illuminate(&self, point: Vector) -> Color {
+        (self.intensity / (point - self.source).len().powi(2)) * self.color
+    }
+}
+
+impl Light for PointLight {
+    fn sample(&self, point: Vector, _sampler: &mut Sampler) -> Option<LightSample> {
+        let offset = self.source - point;
+        let distance = offset.len();
+        Some(LightSample {
+            direction: (1.0 / distance) * offset,
+            distance,
+            radiance: self.illuminate(point),
+            pdf: 1.0,
+        })
+    }
+
+    fn pdf(&self, _point: Vector, _direction: Vector) -> Scalar {
+        0.0
+    }
+
+    fn emit_photon(&self, sampler: &mut Sampler) -> Option<PhotonEmission> {
+        let (direction, pdf) = sample_uniform_sphere(sampler.next_scalar(), sampler.next_scalar());
+        Some(PhotonEmission {
+            ray: Ray::new(self.source, direction),
+            power: (self.intensity / pdf) * self.color,
+        })
+    }
+
+    fn is_delta(&self) -> bool {
+        true
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        check::point("position", self.source)?;
+        check::color("color", self.color)?;
+        check::non_negative("intensity", self.intensity)
+    }
+}
+
+/// Parallel light traveling along `direction`, as from the sun, arriving
+/// with the same strength everywhere.
+pub struct DirectionalLight {
+    pub direction: Vector,
+    pub color: Color,
+    pub intensity: Scalar,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Vector, color: Color, intensity: Scalar) -> DirectionalLight {
+        DirectionalLight {
+            direction: direction.normalize(),
+            color,
+            intensity,
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _point: Vector, _sampler: &mut Sampler) -> Option<LightSample> {
+        Some(LightSample {
+            direction: -self.direction,
+            distance: Scalar::INFINITY,
+            radiance: self.intensity * self.color,
+            pdf: 1.0,
+        })
+    }
+
+    fn pdf(&self, _point: Vector, _direction: Vector) -> Scalar {
+        0.0
+    }
+
+    fn is_delta(&self) -> bool {
+        true
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        check::direction("direction", self.direction)?;
+        check::color("color", self.color)?;
+        check::non_negative("intensity", self.intensity)
+    }
+}
+
+/// A shape emitting `radiance` from its front side; the scene also adds the
+/// shape itself with an emissive material so that rays can hit it.
+pub struct AreaLight {
+    pub shape: Arc<dyn Shape>,
+    pub radiance: Color,
+}
+
+impl Light for AreaLight {
+    fn sample(&self, point: Vector, sampler: &mut Sampler) -> Option<LightSample> {
+        let surface = self
+            .shape
+            .sample_surface(sampler.next_scalar(), sampler.next_scalar())?;
+        let offset = surface.point - point;
+        let distance = offset.len();
+        let direction = (1.0 / distance) * offset;
+        let cosine = -(direction * surface.normal);
+        if cosine <= 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            direction,
+            distance,
+            radiance: self.radiance,
+            pdf: surface.pdf * distance.powi(2) / cosine,
+        })
+    }
+
+    fn pdf(&self, point: Vector, direction: Vector) -> Scalar {
+        let ray = Ray::new(point, direction);
+        match self.shape.intersect(&ray, EPSILON, Scalar::INFINITY) {
+            Some(hit) if hit.front_face => {
+                let cosine = -(direction * hit.normal);
+                self.shape.surface_pdf(hit.point, hit.normal) * hit.t.powi(2) / cosine
+            }
+            _ => 0.0,
+        }
+    }
+
+    fn emit_photon(&self, sampler: &mut Sampler) -> Option<PhotonEmission> {
+        let surface = self
+            .shape
+            .sample_surface(sampler.next_scalar(), sampler.next_scalar())?;
+        let (local, _) = sample_cosine_hemisphere(sampler.next_scalar(), sampler.next_scalar());
+        let direction = OrthonormalBasis::from_normal(surface.normal).to_world(local);
+        Some(PhotonEmission {
+            ray: Ray::new(surface.point, direction),
+            power: (PI / surface.pdf) * self.radiance,
+        })
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        self.shape.validate().map_err(check::within("shape"))?;
+        check::color("radiance", self.radiance)
+    }
+}
+
+/// Constant radiance arriving from every direction at infinity.
+pub struct EnvironmentLight {
+    pub radiance: Color,
+}
+
+impl Light for EnvironmentLight {
+    fn sample(&self, _point: Vector, sampler: &mut Sampler) -> Option<LightSample> {
+        let (direction, pdf) = sample_uniform_sphere(sampler.next_scalar(), sampler.next_scalar());
+        Some(LightSample {
+            direction,
+            distance: Scalar::INFINITY,
+            radiance: self.radiance,
+            pdf,
+        })
+    }
+
+    fn pdf(&self, _point: Vector, _direction: Vector) -> Scalar {
+        1.0 / (4.0 * PI)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        check::color("radiance", self.radiance)
+    }
+}
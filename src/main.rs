@@ -1,222 +1,2023 @@
-use png::HasParameters;
-use std::f64::consts::PI;
-use std::fs::File;
-use std::io::BufWriter;
-use std::path::Path;
+#[cfg(feature = "preview")]
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
 
-#[derive(Debug, Copy, Clone)]
-struct Vector {
-    x: f64,
-    y: f64,
-    z: f64,
+use log::{info, warn, Level, LevelFilter, Log, Metadata, Record};
+
+use basic_raytracer::animation::{Frame, Turntable};
+use basic_raytracer::checkpoint::{self, Checkpoint};
+use basic_raytracer::compare;
+use basic_raytracer::config::{self, Settings};
+use basic_raytracer::error::Error;
+use basic_raytracer::exr;
+use basic_raytracer::gif::GifWriter;
+use basic_raytracer::image::{self, Image, Tonemap};
+use basic_raytracer::info::SceneInfo;
+use basic_raytracer::json::Value;
+use basic_raytracer::medium::Medium;
+use basic_raytracer::overrides::{Override, Sweep};
+use basic_raytracer::parts::Part;
+#[cfg(feature = "preview")]
+use basic_raytracer::preview::{self, Preview};
+use basic_raytracer::render::{self, Crop, IntegratorKind, RenderSettings};
+use basic_raytracer::report::{Output, Report};
+use basic_raytracer::scalar::{self, Scalar};
+use basic_raytracer::scene::Scene;
+use basic_raytracer::scene_file;
+use basic_raytracer::snapshot::Snapshots;
+use basic_raytracer::stats::{plural, BatchScene, BatchStats, RenderStats, SequenceStats};
+use basic_raytracer::tile::TileOrder;
+use basic_raytracer::vector::{Color, Vector};
+use basic_raytracer::watch::{self, Trigger, Watcher};
+
+// Set while the image goes to stdout, which leaves stdout to it.
+static SAY_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+// What `--report` says of the run, gathered as it goes, or `None` without
+// `--report`.
+static REPORT: Mutex<Option<Report>> = Mutex::new(None);
+
+fn record(f: impl FnOnce(&mut Report)) {
+    if let Some(report) = REPORT.lock().unwrap().as_mut() {
+        f(report);
+    }
+}
+
+/// Which messages to show, by the module they're from, as `RUST_LOG` says:
+/// a comma-separated list of levels for the modules whose paths start with a
+/// prefix, such as `basic_raytracer::bvh=debug`, and a level on its own for
+/// everything else. `off` shows nothing.
+struct Filter {
+    default: LevelFilter,
+    targets: Vec<(String, LevelFilter)>,
+}
+
+fn level(name: &str) -> Result<LevelFilter, String> {
+    name.parse()
+        .map_err(|_| format!("unknown log level {:?}", name))
+}
+
+impl Filter {
+    fn new(level: LevelFilter) -> Filter {
+        Filter {
+            default: level,
+            targets: Vec::new(),
+        }
+    }
+
+    fn parse(spec: &str) -> Result<Filter, String> {
+        let mut filter = Filter::new(LevelFilter::Error);
+        for item in spec
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+        {
+            match item.split_once('=') {
+                Some((target, name)) => filter
+                    .targets
+                    .push((target.trim().to_string(), level(name.trim())?)),
+                None => filter.default = level(item)?,
+            }
+        }
+        Ok(filter)
+    }
+
+    /// The most detailed level shown from `target`: that of the longest
+    /// prefix of it listed, or the default. A prefix only counts up to a
+    /// `::`, so `render` isn't a prefix of `rendering`.
+    fn level(&self, target: &str) -> LevelFilter {
+        let within = |prefix: &str| {
+            target.starts_with(prefix)
+                && (target.len() == prefix.len() || target[prefix.len()..].starts_with("::"))
+        };
+        self.targets
+            .iter()
+            .filter(|(prefix, _)| within(prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.default, |&(_, level)| level)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        let levels = self.targets.iter().map(|&(_, level)| level);
+        levels.fold(self.default, Ord::max)
+    }
 }
 
-use std::ops;
+/// Says what's logged: what the program is doing plainly on stdout, or on
+/// stderr while the image goes to stdout, and the rest on stderr with its
+/// level. Warnings are kept for the report too.
+struct Terminal {
+    filter: Filter,
+}
 
-impl Vector {
-    fn new(x: f64, y: f64, z: f64) -> Vector {
-        Vector { x, y, z }
+impl Log for Terminal {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.filter.level(metadata.target())
+    }
+
+    fn log(&self, message: &Record) {
+        if !self.enabled(message.metadata()) {
+            return;
+        }
+        match message.level() {
+            Level::Info if SAY_TO_STDERR.load(Ordering::Relaxed) => eprintln!("{}", message.args()),
+            Level::Info => println!("{}", message.args()),
+            Level::Warn => {
+                let warning = message.args().to_string();
+                eprintln!("warning: {}", warning);
+                record(|report| report.warnings.push(warning));
+            }
+            level => eprintln!(
+                "{}: {}",
+                level.as_str().to_ascii_lowercase(),
+                message.args()
+            ),
+        }
     }
 
-    fn len(&self) -> f64 {
-        let len_squared = self.x.powi(2) + self.y.powi(2) + self.z.powi(2);
-        len_squared.sqrt()
+    fn flush(&self) {}
+}
+
+/// Which messages to show: `-q` leaves only warnings and errors, and `-v`
+/// adds debug messages and `-vv` trace ones too. Without either, `RUST_LOG`
+/// says, or else everything down to info is shown.
+fn log_filter(global: &Global) -> Filter {
+    let level = match (global.quiet, global.verbose) {
+        (true, _) => LevelFilter::Warn,
+        (false, 0) => LevelFilter::Info,
+        (false, 1) => LevelFilter::Debug,
+        (false, _) => LevelFilter::Trace,
+    };
+    let flags = global.quiet || global.verbose > 0;
+    match std::env::var("RUST_LOG") {
+        Ok(spec) if !flags => Filter::parse(&spec).unwrap_or_else(|message| {
+            eprintln!("warning: ignoring RUST_LOG: {}", message);
+            Filter::new(level)
+        }),
+        _ => Filter::new(level),
     }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Command {
+    /// Renders a scene to a file, as the binary does with no command.
+    Render,
+    /// Moves the camera around a scene in a window.
+    Preview,
+    Diff,
+    /// Adds up the parts of a render that was shared out.
+    Merge,
+    /// Prints a summary of a scene.
+    Info,
+}
 
-    fn normalize(&self) -> Vector {
-        let l = self.len();
-        (1.0 / l) * self.clone()
+/// Flags every command takes, wherever they are among its arguments.
+struct Global {
+    /// How many threads to render with, if not as many as the settings
+    /// say.
+    threads: Option<usize>,
+    /// `-q`, and how many times `-v` was given.
+    quiet: bool,
+    verbose: u32,
+}
+
+/// Takes the global flags out of `args`, leaving the rest in order.
+fn parse_global_args(args: &[String]) -> Result<(Global, Vec<String>), String> {
+    let mut global = Global {
+        threads: None,
+        quiet: false,
+        verbose: 0,
+    };
+    let mut rest = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--threads" => {
+                let threads = parse_value(arg, args.next())?;
+                if threads == 0 {
+                    return Err("--threads must be at least 1".to_string());
+                }
+                global.threads = Some(threads);
+            }
+            "-q" | "--quiet" => global.quiet = true,
+            "-v" | "--verbose" => global.verbose += 1,
+            "-vv" => global.verbose += 2,
+            _ => rest.push(arg.clone()),
+        }
     }
+    Ok((global, rest))
 }
 
-impl ops::Add<Vector> for Vector {
-    type Output = Vector;
+#[derive(Clone)]
+struct Options {
+    settings: RenderSettings,
+    scene: Option<PathBuf>,
+    /// The spheres of `--builtin-scene spheres-N`, made from the seed, to
+    /// render instead of a scene file.
+    builtin_scene: Option<usize>,
+    /// Scene files to render one after another, each to a PNG named after
+    /// it in `output_dir`, and how many of them to render at once.
+    batch: Vec<PathBuf>,
+    output_dir: Option<PathBuf>,
+    jobs: Option<usize>,
+    /// Changes to the scene file's values, and ones that sweep across the
+    /// frames of a sequence.
+    set: Vec<Override>,
+    sweeps: Vec<Sweep>,
+    medium: Option<Medium>,
+    /// Where the image goes; with `--all-layers`, `{layer}` in it is replaced
+    /// by each layer's name, and with `--frames`, a run of `#` by the frame
+    /// number. A `.gif` gets every frame instead.
+    output: PathBuf,
+    tonemap: Tonemap,
+    /// The settings given as flags, which go over those of the scene file
+    /// and the config file before they're put in `settings` and `output`.
+    overrides: Settings,
+    config: Option<PathBuf>,
+    /// Whether to print the settings that apply as a config file instead
+    /// of rendering.
+    print_settings: bool,
+    layers: Vec<String>,
+    all_layers: bool,
+    /// How many frames of the scene's animation to render, if it's rendered
+    /// as a sequence.
+    frames: Option<usize>,
+    fps: Scalar,
+    /// Whether to dither frames to a GIF's palette.
+    dither: bool,
+    /// Whether to show the render in a window as it happens.
+    preview: bool,
+    /// Whether to render again whenever the scene file, or a file it refers
+    /// to, changes.
+    watch: bool,
+    /// Whether to move the camera around in a window instead of rendering
+    /// to a file, and how many times smaller to render it while it moves.
+    interactive: bool,
+    interactive_scale: u32,
+    /// Whether to render in passes that refine the whole image, how many,
+    /// and every how many passes to write the image so far.
+    progressive: bool,
+    passes: Option<u32>,
+    save_every: Option<u32>,
+    /// Where to keep a checkpoint of the render, how many seconds apart to
+    /// write it, and which checkpoint to carry on from. A resumed render
+    /// keeps checkpointing to the file it resumed from.
+    checkpoint: Option<PathBuf>,
+    checkpoint_interval: Option<f64>,
+    resume: Option<PathBuf>,
+    /// Whether a crop is put where it belongs in an image the full size,
+    /// rather than written on its own.
+    crop_in_place: bool,
+    /// Whether to write rows into the PNG as they finish rather than hold
+    /// the whole image.
+    low_memory: bool,
+    /// How many times smaller to render a quick draft to the output first,
+    /// and whether to stop at the draft.
+    draft: Option<u32>,
+    draft_only: bool,
+    /// Where to write the sums of the samples taken instead of an image, for
+    /// `merge`, and which share of the samples to take, as the `i`th of `n`.
+    save_samples: Option<PathBuf>,
+    part: Option<(u32, u32)>,
+    /// How many seconds apart to write snapshots of the image so far.
+    snapshot_every: Option<f64>,
+    /// Where to write the JSON report of how the run went, `-` for stderr.
+    report: Option<PathBuf>,
+    /// Whether the camera orbits the scene over the frames; the orbit's
+    /// pivot, radius and elevation default to those of the scene's camera.
+    turntable: bool,
+    pivot: Option<Vector>,
+    radius: Option<Scalar>,
+    elevation: Option<Scalar>,
+}
 
-    fn add(self, other: Vector) -> Vector {
-        Vector::new(self.x + other.x, self.y + other.y, self.z + other.z)
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<&String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} needs a value", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid value {:?} for {}", value, flag))
+}
+
+/// A point written as `x,y,z`.
+fn parse_vector(flag: &str, value: Option<&String>) -> Result<Vector, String> {
+    let value: String = parse_value(flag, value)?;
+    let coordinates: Vec<Scalar> = value.split(',').filter_map(|c| c.parse().ok()).collect();
+    match coordinates[..] {
+        [x, y, z] => Ok(Vector::new(x, y, z)),
+        _ => Err(format!("invalid value {:?} for {}", value, flag)),
     }
 }
 
-impl ops::Sub<Vector> for Vector {
-    type Output = Vector;
+/// Checks the format a scene is in, which only matters for one read from
+/// stdin, since there's no extension to go by. Only JSON is read.
+fn parse_scene_format(flag: &str, value: Option<&String>) -> Result<(), String> {
+    let name: String = parse_value(flag, value)?;
+    match name.as_str() {
+        "json" => Ok(()),
+        _ => Err(format!("unknown scene format {:?}, expected json", name)),
+    }
+}
+
+/// How many spheres the built-in scene `spheres-N` has; see
+/// [`Scene::random_spheres`].
+fn parse_builtin_scene(flag: &str, value: Option<&String>) -> Result<usize, String> {
+    let name: String = parse_value(flag, value)?;
+    match name.strip_prefix("spheres-").map(str::parse) {
+        Some(Ok(count)) => Ok(count),
+        _ => Err(format!(
+            "unknown built-in scene {:?}, expected spheres-N such as spheres-500",
+            name
+        )),
+    }
+}
 
-    fn sub(self, other: Vector) -> Vector {
-        Vector::new(self.x - other.x, self.y - other.y, self.z - other.z)
+/// A share of the samples written as `i/n`.
+fn parse_part(flag: &str, value: Option<&String>) -> Result<(u32, u32), String> {
+    let value: String = parse_value(flag, value)?;
+    let numbers: Vec<u32> = value.split('/').filter_map(|n| n.parse().ok()).collect();
+    match numbers[..] {
+        [part, parts] if (1..=parts).contains(&part) => Ok((part, parts)),
+        _ => Err(format!("invalid value {:?} for {}", value, flag)),
     }
 }
 
-impl ops::Mul<Vector> for Vector {
-    type Output = f64;
+/// A crop written as `x,y,width,height`.
+fn parse_crop(flag: &str, value: Option<&String>) -> Result<Crop, String> {
+    let value: String = parse_value(flag, value)?;
+    let numbers: Vec<u32> = value.split(',').filter_map(|n| n.parse().ok()).collect();
+    match numbers[..] {
+        [x, y, width, height] => Ok(Crop {
+            x,
+            y,
+            width,
+            height,
+        }),
+        _ => Err(format!("invalid value {:?} for {}", value, flag)),
+    }
+}
 
-    fn mul(self, other: Vector) -> f64 {
-        self.x * other.x + self.y * other.y + self.z * other.z
+impl Options {
+    /// How many passes a progressive render takes.
+    fn passes(&self) -> u32 {
+        self.passes.unwrap_or(64)
     }
 }
 
-impl ops::Mul<Vector> for f64 {
-    type Output = Vector;
+fn parse_tonemap(flag: &str, value: Option<&String>) -> Result<Tonemap, String> {
+    let name: String = parse_value(flag, value)?;
+    Tonemap::from_name(&name).ok_or_else(|| {
+        format!(
+            "unknown tonemap {:?}, expected clamp, reinhard or aces",
+            name
+        )
+    })
+}
 
-    fn mul(self, other: Vector) -> Vector {
-        Vector::new(self * other.x, self * other.y, self * other.z)
+/// The options of `render` or `preview`, or of no command at all. The
+/// commands take the scene file on its own as well as with `--scene`, and
+/// `render` takes any number of them with `--output-dir`. They
+/// aren't checked until the settings from files are in them, by
+/// [`check_options`].
+fn parse_args(args: &[String], command: Option<Command>) -> Result<Options, String> {
+    let mut options = Options {
+        settings: RenderSettings::default(),
+        scene: None,
+        builtin_scene: None,
+        batch: Vec::new(),
+        output_dir: None,
+        jobs: None,
+        set: Vec::new(),
+        sweeps: Vec::new(),
+        medium: None,
+        output: PathBuf::from("output.png"),
+        tonemap: Tonemap::Clamp,
+        overrides: Settings::default(),
+        config: None,
+        print_settings: false,
+        layers: Vec::new(),
+        all_layers: false,
+        frames: None,
+        fps: 24.0,
+        dither: false,
+        preview: false,
+        watch: false,
+        interactive: false,
+        interactive_scale: 4,
+        progressive: false,
+        passes: None,
+        save_every: None,
+        checkpoint: None,
+        checkpoint_interval: None,
+        resume: None,
+        crop_in_place: false,
+        low_memory: false,
+        draft: None,
+        draft_only: false,
+        save_samples: None,
+        part: None,
+        snapshot_every: None,
+        report: None,
+        turntable: false,
+        pivot: None,
+        radius: None,
+        elevation: None,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--scene" => options.scene = Some(parse_value(arg, args.next())?),
+            "--scene-format" => parse_scene_format(arg, args.next())?,
+            "--builtin-scene" => {
+                options.builtin_scene = Some(parse_builtin_scene(arg, args.next())?)
+            }
+            "--set" => {
+                let value: String = parse_value(arg, args.next())?;
+                let set = Override::parse(&value)
+                    .map_err(|e| format!("invalid value {:?} for --set: {}", value, e))?;
+                options.set.push(set);
+            }
+            "--set-per-frame" => {
+                let value: String = parse_value(arg, args.next())?;
+                let sweep = Sweep::parse(&value)
+                    .map_err(|e| format!("invalid value {:?} for --set-per-frame: {}", value, e))?;
+                options.sweeps.push(sweep);
+            }
+            "--output" => options.overrides.output = Some(parse_value(arg, args.next())?),
+            "--output-dir" => options.output_dir = Some(parse_value(arg, args.next())?),
+            "--jobs" => options.jobs = Some(parse_value(arg, args.next())?),
+            "--width" => options.overrides.width = Some(parse_value(arg, args.next())?),
+            "--height" => options.overrides.height = Some(parse_value(arg, args.next())?),
+            "--tonemap" => options.overrides.tonemap = Some(parse_tonemap(arg, args.next())?),
+            "--config" => options.config = Some(parse_value(arg, args.next())?),
+            "--print-settings" => options.print_settings = true,
+            "--layer" => options.layers.push(parse_value(arg, args.next())?),
+            "--all-layers" => options.all_layers = true,
+            "--frames" => options.frames = Some(parse_value(arg, args.next())?),
+            "--fps" => options.fps = parse_value(arg, args.next())?,
+            "--dither" => options.dither = true,
+            "--watch" => options.watch = true,
+            "--preview" if cfg!(feature = "preview") => options.preview = true,
+            "--interactive" if cfg!(feature = "preview") => options.interactive = true,
+            "--preview" | "--interactive" => {
+                return Err(format!("{} needs a build with `--features preview`", arg))
+            }
+            "--interactive-scale" => options.interactive_scale = parse_value(arg, args.next())?,
+            "--progressive" => options.progressive = true,
+            "--passes" => options.passes = Some(parse_value(arg, args.next())?),
+            "--save-every" => options.save_every = Some(parse_value(arg, args.next())?),
+            "--checkpoint" => options.checkpoint = Some(parse_value(arg, args.next())?),
+            "--checkpoint-interval" => {
+                options.checkpoint_interval = Some(parse_value(arg, args.next())?)
+            }
+            "--resume" => options.resume = Some(parse_value(arg, args.next())?),
+            "--crop" => options.settings.crop = Some(parse_crop(arg, args.next())?),
+            "--crop-in-place" => options.crop_in_place = true,
+            "--low-memory" => options.low_memory = true,
+            "--draft" => options.draft = Some(parse_value(arg, args.next())?),
+            "--draft-only" => options.draft_only = true,
+            "--save-samples" => options.save_samples = Some(parse_value(arg, args.next())?),
+            "--part" => options.part = Some(parse_part(arg, args.next())?),
+            "--seed" => options.settings.seed = parse_value(arg, args.next())?,
+            "--report" => options.report = Some(parse_value(arg, args.next())?),
+            "--snapshot-every" => options.snapshot_every = Some(parse_value(arg, args.next())?),
+            "--turntable" => options.turntable = true,
+            "--pivot" => options.pivot = Some(parse_vector(arg, args.next())?),
+            "--radius" => options.radius = Some(parse_value(arg, args.next())?),
+            "--elevation" => options.elevation = Some(parse_value(arg, args.next())?),
+            "--integrator" => {
+                options.settings.integrator = match args.next().map(String::as_str) {
+                    Some("whitted") => IntegratorKind::Whitted,
+                    Some("path") => IntegratorKind::Path,
+                    Some("ao") => IntegratorKind::AmbientOcclusion,
+                    other => return Err(format!("unknown integrator {:?}", other.unwrap_or(""))),
+                }
+            }
+            "--tile-order" => {
+                options.settings.tile_order = match args.next().map(String::as_str) {
+                    Some("scanline") => TileOrder::Scanline,
+                    Some("spiral") => TileOrder::Spiral,
+                    Some("hilbert") => TileOrder::Hilbert,
+                    Some("random") => TileOrder::Random,
+                    other => return Err(format!("unknown tile order {:?}", other.unwrap_or(""))),
+                }
+            }
+            "--no-packets" => options.settings.packets = false,
+            "--spp" => options.overrides.spp = Some(parse_value(arg, args.next())?),
+            "--max-depth" => options.overrides.max_depth = Some(parse_value(arg, args.next())?),
+            "--rr-depth" => {
+                options.settings.russian_roulette = Some(parse_value(arg, args.next())?)
+            }
+            "--no-rr" => options.settings.russian_roulette = None,
+            "--no-nee" => options.settings.next_event_estimation = false,
+            "--no-mis" => options.settings.multiple_importance_sampling = false,
+            "--ao-samples" => options.settings.ao_samples = parse_value(arg, args.next())?,
+            "--ao-distance" => options.settings.ao_distance = parse_value(arg, args.next())?,
+            "--caustic-photons" => {
+                options.settings.caustic_photons = parse_value(arg, args.next())?
+            }
+            "--photon-k" => options.settings.photon_k = parse_value(arg, args.next())?,
+            "--photon-radius" => options.settings.photon_radius = parse_value(arg, args.next())?,
+            "--clamp-indirect" => options.settings.clamp_indirect = parse_value(arg, args.next())?,
+            // `--medium absorption,scattering` fills the scene with a
+            // homogeneous medium.
+            "--medium" => {
+                let value: String = parse_value(arg, args.next())?;
+                let coefficients: Vec<Scalar> =
+                    value.split(',').filter_map(|c| c.parse().ok()).collect();
+                options.medium = match coefficients[..] {
+                    [absorption, scattering] => Some(Medium::new(absorption, scattering)),
+                    _ => return Err(format!("invalid value {:?} for --medium", value)),
+                };
+            }
+            _ if command.is_some() && !arg.starts_with('-') && options.scene.is_none() => {
+                options.scene = Some(PathBuf::from(arg))
+            }
+            _ if command == Some(Command::Render) && !arg.starts_with('-') => {
+                options.batch.push(PathBuf::from(arg))
+            }
+            _ => return Err(format!("unknown argument {:?}", arg)),
+        }
     }
+    if command == Some(Command::Preview) {
+        if options.preview {
+            return Err("preview can't be used with --preview, which is for render".to_string());
+        }
+        if !cfg!(feature = "preview") {
+            return Err("preview needs a build with `--features preview`".to_string());
+        }
+        options.interactive = true;
+    }
+    if options.output_dir.is_some() {
+        if let Some(scene) = options.scene.take() {
+            options.batch.insert(0, scene);
+        }
+    }
+    Ok(options)
 }
 
-struct Ray {
-    origin: Vector,
-    direction: Vector,
+/// The settings from every layer: the flags', then the scene file's, then
+/// the config file's, where it's `--config` or `raytracer.toml` in the
+/// working directory.
+fn layered_settings(options: &Options) -> Result<Settings, Error> {
+    let default_config = Path::new(config::FILE_NAME);
+    let config = match &options.config {
+        Some(path) => config::load(path)?,
+        None if default_config.is_file() => config::load(default_config)?,
+        None => Settings::default(),
+    };
+    let scene = match &options.scene {
+        Some(path) => scene_file::load_settings(path, &options.set)?,
+        None => Settings::default(),
+    };
+    Ok(options.overrides.clone().or(scene.or(config)))
 }
 
-impl Ray {
-    fn new(origin: Vector, direction: Vector) -> Ray {
-        Ray { origin, direction }
+/// Puts `layered` over the built-in defaults.
+fn apply_settings(options: &mut Options, layered: Settings) {
+    let settings = &mut options.settings;
+    settings.width = layered.width.unwrap_or(settings.width);
+    settings.height = layered.height.unwrap_or(settings.height);
+    settings.spp = layered.spp.unwrap_or(settings.spp);
+    settings.max_depth = layered.max_depth.unwrap_or(settings.max_depth);
+    settings.threads = layered.threads.unwrap_or(settings.threads);
+    options.tonemap = layered.tonemap.unwrap_or(options.tonemap);
+    if let Some(output) = layered.output {
+        options.output = output;
     }
+}
 
-    fn at(&self, length: f64) -> Vector {
-        self.origin + length * self.direction
+/// Every setting as it applies, for `--print-settings`.
+fn effective_settings(options: &Options) -> Settings {
+    Settings {
+        width: Some(options.settings.width),
+        height: Some(options.settings.height),
+        spp: Some(options.settings.spp),
+        max_depth: Some(options.settings.max_depth),
+        tonemap: Some(options.tonemap),
+        threads: Some(options.settings.threads),
+        output: Some(options.output.clone()),
     }
 }
 
-trait Shape {
-    fn intersect(&self, ray: &Ray) -> Option<f64>;
+/// Rejects options that can't be used together or that make no sense.
+fn check_options(options: &Options) -> Result<(), String> {
+    let scene_file = options.scene.is_some() || !options.batch.is_empty();
+    if options.builtin_scene.is_some() && scene_file {
+        return Err("--builtin-scene can't be used with a scene file".to_string());
+    }
+    if (!options.set.is_empty() || !options.sweeps.is_empty()) && !scene_file {
+        return Err("--set and --set-per-frame need a scene file".to_string());
+    }
+    if !options.sweeps.is_empty() && options.frames.is_none() {
+        return Err("--set-per-frame needs --frames".to_string());
+    }
+    if !options.batch.is_empty() && options.output_dir.is_none() {
+        return Err("rendering more than one scene needs --output-dir".to_string());
+    }
+    if options.batch.iter().any(|scene| is_stdio(scene)) {
+        return Err("stdin can't be one of a batch of scenes".to_string());
+    }
+    if options.output_dir.is_some() && options.batch.is_empty() {
+        return Err("--output-dir needs a scene file".to_string());
+    }
+    if options.jobs.is_some() && options.output_dir.is_none() {
+        return Err("--jobs needs --output-dir".to_string());
+    }
+    if options.jobs == Some(0) {
+        return Err("--jobs must be at least 1".to_string());
+    }
+    if options.output_dir.is_some()
+        && (options.overrides.output.is_some()
+            || options.frames.is_some()
+            || options.all_layers
+            || options.watch
+            || options.interactive
+            || options.preview
+            || options.checkpoint.is_some()
+            || options.resume.is_some())
+    {
+        return Err(
+            "--output-dir can't be used with --output, --frames, --all-layers, --watch, --interactive, --preview, --checkpoint or --resume"
+                .to_string(),
+        );
+    }
+    // Names that differ only in case are one file where file names ignore
+    // it, as they do by default on macOS and Windows.
+    let key = |scene: &Path| {
+        batch_output(options, scene)
+            .to_string_lossy()
+            .to_lowercase()
+    };
+    for (i, scene) in options.batch.iter().enumerate() {
+        let output = batch_output(options, scene);
+        if let Some(other) = options.batch[..i]
+            .iter()
+            .find(|other| key(other) == key(scene))
+        {
+            return Err(format!(
+                "{} and {} would both be rendered to {}",
+                other.display(),
+                scene.display(),
+                output.display()
+            ));
+        }
+    }
+    if options.all_layers && !options.layers.is_empty() {
+        return Err("--layer and --all-layers can't be used together".to_string());
+    }
+    if options.all_layers && is_gif(&options.output) {
+        return Err("--all-layers can't write to a GIF".to_string());
+    }
+    if options.interactive && (options.frames.is_some() || options.all_layers) {
+        return Err("--interactive can't be used with --frames or --all-layers".to_string());
+    }
+    if options.interactive_scale == 0 {
+        return Err("--interactive-scale must be at least 1".to_string());
+    }
+    if (options.passes.is_some() || options.save_every.is_some()) && !options.progressive {
+        return Err("--passes and --save-every need --progressive".to_string());
+    }
+    if options.interactive && options.progressive {
+        return Err("--interactive can't be used with --progressive".to_string());
+    }
+    if options.passes == Some(0) || options.save_every == Some(0) {
+        return Err("--passes and --save-every must be at least 1".to_string());
+    }
+    if options.save_every.is_some() && is_gif(&options.output) {
+        return Err("--save-every can't write to a GIF".to_string());
+    }
+    let checkpointed = options.checkpoint.is_some() || options.resume.is_some();
+    if options.checkpoint_interval.is_some() && !checkpointed {
+        return Err("--checkpoint-interval needs --checkpoint or --resume".to_string());
+    }
+    if matches!(options.checkpoint_interval, Some(interval) if interval.is_nan() || interval < 0.0)
+    {
+        return Err("--checkpoint-interval must not be negative".to_string());
+    }
+    let many_images = options.frames.is_some() || options.all_layers || is_gif(&options.output);
+    if options.watch && options.scene.is_none() {
+        return Err("--watch needs a scene file".to_string());
+    }
+    if options.watch && options.scene.as_deref().is_some_and(is_stdio) {
+        return Err("--watch can't watch stdin".to_string());
+    }
+    if is_stdio(&options.output) {
+        let more_files = options.save_every.is_some()
+            || checkpointed
+            || options.snapshot_every.is_some()
+            || options.low_memory
+            || options.draft.is_some();
+        if many_images || more_files || options.watch || options.save_samples.is_some() {
+            return Err(
+                "--output - writes one image, so it can't be used with --frames, --all-layers, --save-every, --checkpoint, --resume, --snapshot-every, --low-memory, --draft, --watch or --save-samples"
+                    .to_string(),
+            );
+        }
+        if io::stdout().is_terminal() && !options.print_settings {
+            return Err(
+                "--output - won't write an image to a terminal; pipe it somewhere".to_string(),
+            );
+        }
+    }
+    if options.watch && (many_images || checkpointed || options.interactive || options.low_memory) {
+        return Err(
+            "--watch can't be used with --frames, --all-layers, --checkpoint, --resume, --interactive, --low-memory or a GIF"
+                .to_string(),
+        );
+    }
+    if checkpointed && (many_images || options.interactive) {
+        return Err(
+            "--checkpoint and --resume can't be used with --frames, --all-layers, --interactive or a GIF"
+                .to_string(),
+        );
+    }
+    if matches!(options.snapshot_every, Some(every) if !(every.is_finite() && every > 0.0)) {
+        return Err("--snapshot-every must be a positive number of seconds".to_string());
+    }
+    if options.snapshot_every.is_some() && (many_images || options.interactive) {
+        return Err(
+            "--snapshot-every can't be used with --frames, --all-layers, --interactive or a GIF"
+                .to_string(),
+        );
+    }
+    if options.crop_in_place && options.settings.crop.is_none() {
+        return Err("--crop-in-place needs --crop".to_string());
+    }
+    if options.interactive && options.settings.crop.is_some() {
+        return Err("--interactive can't be used with --crop".to_string());
+    }
+    let whole_image = options.progressive
+        || checkpointed
+        || options.snapshot_every.is_some()
+        || options.crop_in_place
+        || options.preview
+        || is_gif(&options.output);
+    if options.low_memory && whole_image {
+        return Err(
+            "--low-memory can't be used with --progressive, --checkpoint, --resume, --snapshot-every, --crop-in-place, --preview or a GIF"
+                .to_string(),
+        );
+    }
+    if options.low_memory && exr::is_exr(&options.output) {
+        return Err("--low-memory writes a PNG, so it can't write an EXR".to_string());
+    }
+    if options.draft == Some(0) {
+        return Err("--draft must be at least 1".to_string());
+    }
+    if options.draft_only && options.draft.is_none() {
+        return Err("--draft-only needs --draft".to_string());
+    }
+    if options.draft.is_some()
+        && (options.interactive || options.settings.crop.is_some() || is_gif(&options.output))
+    {
+        return Err("--draft can't be used with --interactive, --crop or a GIF".to_string());
+    }
+    if options.part.is_some() && options.save_samples.is_none() {
+        return Err("--part needs --save-samples".to_string());
+    }
+    if options.save_samples.is_some()
+        && (many_images
+            || whole_image
+            || options.watch
+            || options.interactive
+            || options.output_dir.is_some()
+            || options.low_memory
+            || options.draft.is_some())
+    {
+        return Err(
+            "--save-samples can't be used with --progressive, --checkpoint, --resume, --snapshot-every, --crop-in-place, --preview, --frames, --all-layers, --watch, --interactive, --output-dir, --low-memory, --draft or a GIF"
+                .to_string(),
+        );
+    }
+    if matches!(options.part, Some((_, parts)) if parts > options.settings.spp) {
+        return Err(
+            "--part can't share out fewer samples a pixel than there are parts".to_string(),
+        );
+    }
+    if options.report.is_some()
+        && (options.output_dir.is_some()
+            || options.watch
+            || options.interactive
+            || options.print_settings)
+    {
+        return Err(
+            "--report can't be used with --output-dir, --watch, --interactive or --print-settings"
+                .to_string(),
+        );
+    }
+    if options.frames == Some(0) {
+        return Err("--frames must be at least 1".to_string());
+    }
+    if !(options.fps.is_finite() && options.fps > 0.0) {
+        return Err("--fps must be a positive number".to_string());
+    }
+    let orbit = options.pivot.is_some() || options.radius.is_some() || options.elevation.is_some();
+    if orbit && !options.turntable {
+        return Err("--pivot, --radius and --elevation need --turntable".to_string());
+    }
+    if options.turntable && options.frames.is_none() {
+        return Err("--turntable needs --frames".to_string());
+    }
+    if matches!(options.radius, Some(radius) if !(radius.is_finite() && radius > 0.0)) {
+        return Err("--radius must be a positive number".to_string());
+    }
+    if matches!(options.elevation, Some(elevation) if elevation.is_nan() || elevation.abs() >= 90.0)
+    {
+        return Err("--elevation must be between -90 and 90 degrees".to_string());
+    }
+    Ok(())
 }
 
-struct Sphere {
-    center: Vector,
-    radius: f64,
+/// What `diff` compares, where it puts the heat map, and the most the
+/// images can differ by before it fails.
+struct DiffOptions {
+    images: Vec<PathBuf>,
+    out: Option<PathBuf>,
+    gain: Scalar,
+    json: bool,
+    threshold: Option<f64>,
+    min_psnr: Option<f64>,
+    min_ssim: Option<f64>,
 }
 
-impl Sphere {
-    fn new(center: Vector, radius: f64) -> Sphere {
-        Sphere { center, radius }
+fn parse_diff_args(args: &[String]) -> Result<DiffOptions, String> {
+    let mut options = DiffOptions {
+        images: Vec::new(),
+        out: None,
+        gain: 10.0,
+        json: false,
+        threshold: None,
+        min_psnr: None,
+        min_ssim: None,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => options.out = Some(parse_value(arg, args.next())?),
+            "--gain" => options.gain = parse_value(arg, args.next())?,
+            "--json" => options.json = true,
+            "--threshold" => options.threshold = Some(parse_value(arg, args.next())?),
+            "--min-psnr" => options.min_psnr = Some(parse_value(arg, args.next())?),
+            "--min-ssim" => options.min_ssim = Some(parse_value(arg, args.next())?),
+            _ if arg.starts_with("--") => return Err(format!("unknown argument {:?}", arg)),
+            _ => options.images.push(PathBuf::from(arg)),
+        }
     }
+    if options.images.len() != 2 {
+        return Err(
+            "usage: diff a.png b.png [--out diff.png] [--gain N] [--json] \
+             [--threshold ERROR] [--min-psnr DB] [--min-ssim SSIM]"
+                .to_string(),
+        );
+    }
+    if !(options.gain.is_finite() && options.gain > 0.0) {
+        return Err("--gain must be a positive number".to_string());
+    }
+    Ok(options)
 }
 
-impl Shape for Sphere {
-    fn intersect(&self, ray: &Ray) -> Option<f64> {
-        let c = self.center;
-        let r = self.radius;
-        let l = ray.direction.normalize();
-        let o = ray.origin;
-        let diff = o - c;
-        let dot = l * diff;
-        let discriminant = dot.powi(2) - (diff.len().powi(2) - r.powi(2));
-        if discriminant < 0.0 {
-            None
-        } else if discriminant == 0.0 {
-            Some(-dot)
-        } else {
-            let a = -dot + discriminant;
-            let b = -dot - discriminant;
-            if a > b {
-                Some(b)
-            } else {
-                Some(a)
+/// Compares two images, and says whether they're within the thresholds.
+fn diff(options: &DiffOptions) -> Result<bool, String> {
+    let (a, b) = (&options.images[0], &options.images[1]);
+    let (first, second) = (Image::read(a)?, Image::read(b)?);
+    let comparison = compare::compare(&first, &second)
+        .map_err(|e| format!("can't compare {} with {}: {}", a.display(), b.display(), e))?;
+    let mut failures = Vec::new();
+    if let Some(threshold) = options.threshold.filter(|&t| comparison.error() > t) {
+        failures.push(format!(
+            "mean absolute error {:.6} is over {}",
+            comparison.error(),
+            threshold
+        ));
+    }
+    if let Some(min) = options.min_psnr.filter(|&min| comparison.psnr < min) {
+        failures.push(format!("PSNR {:.2} dB is under {}", comparison.psnr, min));
+    }
+    if let Some(min) = options.min_ssim.filter(|&min| comparison.ssim < min) {
+        failures.push(format!("SSIM {:.6} is under {}", comparison.ssim, min));
+    }
+    match options.json {
+        true => {
+            let mut json = comparison.to_json();
+            if let Value::Object(members) = &mut json {
+                members.push(("passed".to_string(), Value::Bool(failures.is_empty())));
             }
+            info!("{}", json);
         }
+        false => info!("{}", comparison),
     }
+    if let Some(out) = &options.out {
+        compare::heatmap(&first, &second, options.gain)
+            .write(out)
+            .map_err(|e| e.to_string())?;
+    }
+    for failure in &failures {
+        eprintln!("{}", failure);
+    }
+    Ok(failures.is_empty())
 }
 
-struct PointLight {
-    source: Vector,
-    color: Vector,
-    intensity: f64,
+/// Whether `path` is `-`, which stands for stdin or stdout.
+fn is_stdio(path: &Path) -> bool {
+    path == Path::new(scene_file::STDIN)
 }
 
-impl PointLight {
-    fn new(source: Vector, color: Vector, intensity: f64) -> PointLight {
-        PointLight {
+/// Writes `image` to `path` as [`Image::write_tonemapped`] does, or to stdout
+/// as a PNG for `-`. Stdout takes bytes as they are on every platform,
+/// Windows included, so nothing in the PNG is changed on the way out.
+fn write_image(image: &Image, path: &Path, tonemap: Tonemap) -> Result<(), Error> {
+    if !is_stdio(path) {
+        image.write_tonemapped(path, tonemap)?;
+        return record_output(path);
+    }
+    let stdout = Path::new("stdout");
+    let mut png = Vec::new();
+    image.tonemapped(tonemap).encode_png(&mut png, stdout)?;
+    record(|report| report.outputs.push(Output::of(path.to_path_buf(), &png)));
+    let mut out = io::stdout().lock();
+    out.write_all(&png)
+        .and_then(|()| out.flush())
+        .map_err(|source| Error::Io {
+            path: stdout.to_path_buf(),
+            operation: "write",
             source,
-            color,
-            intensity,
+        })
+}
+
+/// Keeps `path`, which has just been written, for the report.
+fn record_output(path: &Path) -> Result<(), Error> {
+    if REPORT.lock().unwrap().is_none() {
+        return Ok(());
+    }
+    let contents = fs::read(path).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        operation: "read",
+        source,
+    })?;
+    record(|report| {
+        report
+            .outputs
+            .push(Output::of(path.to_path_buf(), &contents))
+    });
+    Ok(())
+}
+
+fn is_gif(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("gif"))
+}
+
+/// `path` with `suffix` added to the file name, before the extension.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{}{}.{}", stem, suffix, extension.to_string_lossy()),
+        None => format!("{}{}", stem, suffix),
+    };
+    path.with_file_name(name)
+}
+
+/// The output path for `layer`: the template with `{layer}` replaced, or
+/// with `-layer` added to the file name if it has no `{layer}`.
+fn layer_path(template: &Path, layer: &str) -> PathBuf {
+    let text = template.to_string_lossy();
+    if text.contains("{layer}") {
+        return PathBuf::from(text.replace("{layer}", layer));
+    }
+    with_suffix(template, &format!("-{}", layer))
+}
+
+/// The output path for frame `index`: the template with its first run of
+/// `#` replaced by the frame number padded to as many digits, or with
+/// `_0000` added to the file name if it has no `#`.
+fn frame_path(template: &Path, index: usize) -> PathBuf {
+    let text = template.to_string_lossy();
+    let start = match text.find('#') {
+        Some(start) => start,
+        None => return with_suffix(template, &format!("_{:04}", index)),
+    };
+    let width = text[start..].chars().take_while(|&c| c == '#').count();
+    PathBuf::from(format!(
+        "{}{:0width$}{}",
+        &text[..start],
+        index,
+        &text[start + width..],
+        width = width
+    ))
+}
+
+// Set by the first Ctrl-C.
+static STOP: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+extern "C" {
+    fn signal(signum: i32, handler: usize) -> usize;
+}
+
+#[cfg(unix)]
+const SIGINT: i32 = 2;
+
+#[cfg(unix)]
+extern "C" fn interrupted(_: i32) {
+    STOP.store(true, Ordering::SeqCst);
+    // A second Ctrl-C kills the process as usual.
+    unsafe {
+        signal(SIGINT, 0);
+    }
+}
+
+/// Makes Ctrl-C stop a sequence once the frame being rendered is done, or a
+/// single image once the tiles being rendered are.
+fn stop_on_interrupt() {
+    #[cfg(unix)]
+    unsafe {
+        signal(SIGINT, interrupted as extern "C" fn(i32) as usize);
+    }
+}
+
+/// What sees each image as it renders, returning false to cancel it.
+type Watch<'a> = &'a mut dyn FnMut(&Image) -> bool;
+
+/// The fingerprint of `scene` as `options` load it, rendered with
+/// `settings`.
+fn fingerprint(scene: &Scene, options: &Options, settings: &RenderSettings) -> Result<u64, Error> {
+    let file = match (&options.scene, options.builtin_scene) {
+        (Some(file), _) => file,
+        (None, Some(count)) => {
+            let name = format!("spheres-{}", count);
+            return Ok(checkpoint::fingerprint(scene, name.as_bytes(), settings));
+        }
+        (None, None) => return Ok(checkpoint::fingerprint(scene, &[], settings)),
+    };
+    let mut source = scene_file::read_source(file)?.into_bytes();
+    // The same file with other overrides is another scene.
+    for set in &options.set {
+        source.extend_from_slice(format!("\n{}", set).as_bytes());
+    }
+    // And so is the same file with a mesh, texture or anything else it
+    // refers to changed, or gone.
+    for reference in scene_file::references(file, &options.set)? {
+        source.extend_from_slice(format!("\n{}\n", reference.display()).as_bytes());
+        match fs::read(&reference) {
+            Ok(contents) => {
+                source.extend_from_slice(format!("{}\n", contents.len()).as_bytes());
+                source.extend_from_slice(&contents);
+            }
+            Err(_) => source.extend_from_slice(b"missing"),
+        }
+    }
+    Ok(checkpoint::fingerprint(scene, &source, settings))
+}
+
+/// Renders `scene` in `options.passes` passes, writing it to `path` after
+/// every `options.save_every` of them, and keeping a checkpoint of it if
+/// asked to. Also says how many samples a pixel the render resumed from.
+fn render_progressive(
+    scene: &Scene,
+    options: &Options,
+    path: &Path,
+    stop: &AtomicBool,
+    watch: Watch,
+) -> Result<(Image, RenderStats, u32), Error> {
+    // Without --progressive, a checkpointed render takes its samples one
+    // pass at a time, which makes the same image.
+    let (settings, passes) = match options.progressive {
+        true => (options.settings.clone(), options.passes()),
+        false => (
+            RenderSettings {
+                spp: 1,
+                ..options.settings.clone()
+            },
+            options.settings.spp,
+        ),
+    };
+    let checkpoint = options.checkpoint.as_ref().or(options.resume.as_ref());
+    let fingerprint = match checkpoint {
+        Some(_) => fingerprint(scene, options, &settings)?,
+        None => 0,
+    };
+    let (done, image) = match &options.resume {
+        Some(resume) => {
+            let saved = Checkpoint::read(resume)?;
+            if saved.fingerprint != fingerprint {
+                return Err(Error::InvalidSettings(format!(
+                    "{} is a checkpoint of a different scene or settings",
+                    resume.display()
+                )));
+            }
+            info!(
+                "Resuming at {} a pixel",
+                plural(saved.samples as usize, "sample")
+            );
+            (saved.samples / settings.spp, saved.image)
+        }
+        None => {
+            let window = settings.window();
+            (0, Image::new(window.width, window.height))
+        }
+    };
+    let resumed = done * settings.spp;
+    let mut checkpointed = Instant::now();
+    // The newest whole pass since the last checkpoint, for checkpointing if
+    // the render is stopped part way through the next.
+    let mut unsaved = None;
+    let mut saved = resumed;
+    let mut pass = |done, image: &Image| {
+        if options.progressive {
+            info!("Pass {} of {}", done, passes);
+        }
+        // The last pass is written along with everything else.
+        if options.save_every.is_some_and(|every| done % every == 0) && done < passes {
+            image.write_tonemapped(path, options.tonemap)?;
+        }
+        let checkpoint = match checkpoint {
+            Some(checkpoint) => checkpoint,
+            None => return Ok(()),
+        };
+        let samples = done * settings.spp;
+        let interval = options.checkpoint_interval.unwrap_or(60.0);
+        let due = checkpointed.elapsed().as_secs_f64() >= interval;
+        match due || done == passes {
+            true => {
+                Checkpoint {
+                    fingerprint,
+                    samples,
+                    image: image.clone(),
+                }
+                .write(checkpoint)?;
+                checkpointed = Instant::now();
+                (saved, unsaved) = (samples, None);
+            }
+            false => unsaved = Some((samples, image.clone())),
+        }
+        Ok(())
+    };
+    let range = done..passes;
+    let (image, stats) =
+        render::render_progressive_from(scene, &settings, range, image, stop, &mut pass, watch)?;
+    match checkpoint {
+        Some(checkpoint) if stop.load(Ordering::SeqCst) => {
+            if let Some((samples, image)) = unsaved {
+                Checkpoint {
+                    fingerprint,
+                    samples,
+                    image,
+                }
+                .write(checkpoint)?;
+                saved = samples;
+            }
+            if saved > 0 {
+                info!(
+                    "Checkpointed {} a pixel; carry on with --resume {}",
+                    plural(saved as usize, "sample"),
+                    checkpoint.display()
+                );
+            }
+        }
+        _ => {}
+    }
+    Ok((image, stats, resumed))
+}
+
+/// Renders `scene` to `path`, or as the next frame of `gif`, stopping a
+/// single image part way if `stop` is set.
+fn render_to(
+    scene: &Scene,
+    options: &Options,
+    path: &Path,
+    gif: Option<&mut GifWriter>,
+    stop: &AtomicBool,
+    watch: Watch,
+) -> Result<RenderStats, Error> {
+    // A sequence only stops between frames.
+    let keep_going = AtomicBool::new(false);
+    let stop = match options.frames.is_some() || options.all_layers || gif.is_some() {
+        true => &keep_going,
+        false => stop,
+    };
+    if let Some(scale) = options.draft {
+        let stats = draft(scene, options, scale, path, stop, watch)?;
+        if options.draft_only {
+            record(|report| report.renders.push(stats));
+            record_output(path)?;
+            info!("{}", stats);
+            return Ok(stats);
+        }
+    }
+    if options.low_memory {
+        return render_streamed(scene, options, path, stop);
+    }
+    let mut snapshots = options.snapshot_every.map(|every| {
+        Snapshots::new(path, Duration::from_secs_f64(every)).tonemapped(options.tonemap)
+    });
+    let watch = &mut |image: &Image| {
+        if let Some(snapshots) = &mut snapshots {
+            // Losing a snapshot isn't worth losing the render over.
+            if let Err(error) = snapshots.update(image) {
+                warn!("{}", error);
+            }
+        }
+        watch(image)
+    };
+    let checkpointed = options.checkpoint.is_some() || options.resume.is_some();
+    let (image, stats, resumed) = match options.progressive || checkpointed {
+        true => render_progressive(scene, options, path, stop, watch)?,
+        false => {
+            let (image, stats) = render::render_stoppable(scene, &options.settings, stop, watch)?;
+            (image, stats, 0)
+        }
+    };
+    record(|report| report.renders.push(stats));
+    let window = options.settings.window();
+    let mut image = match options.crop_in_place {
+        true => {
+            let mut full = Image::new(options.settings.width, options.settings.height);
+            full.paste(&image, window.x, window.y);
+            full
+        }
+        false => image,
+    };
+    let flushed = image.flush_non_finite();
+    if flushed > 0 {
+        warn!(
+            "{} pixels of {} weren't finite numbers and were made black",
+            flushed,
+            path.display()
+        );
+    }
+    if stop.load(Ordering::SeqCst) {
+        // A newer change is on its way, and the last image stays until
+        // it's rendered.
+        if options.watch {
+            return Err(Error::Cancelled);
         }
+        write_image(&image, path, options.tonemap)?;
+        if let Some(snapshots) = snapshots {
+            snapshots.finish()?;
+        }
+        return Err(stopped(options, &stats, resumed, path));
+    }
+    match gif {
+        Some(gif) => gif.add_frame(&image.tonemapped(options.tonemap))?,
+        None => {
+            write_image(&image, path, options.tonemap)?;
+            let path = match is_stdio(path) {
+                true => Path::new("stdout"),
+                false => path,
+            };
+            info!("Raytraced {} successfully!", path.display());
+        }
+    }
+    if let Some(snapshots) = snapshots {
+        snapshots.finish()?;
+    }
+    info!("{}", stats);
+    info!("{} bytes of geometry", scene.geometry_bytes());
+    // The image, and the 8-bit copy of it that's encoded.
+    let bytes = image.pixels.len() * (mem::size_of::<Color>() + 4);
+    info!("{} bytes of image buffers at most", bytes);
+    Ok(stats)
+}
+
+/// Renders `scene` to `path` a row at a time, with `--low-memory`.
+fn render_streamed(
+    scene: &Scene,
+    options: &Options,
+    path: &Path,
+    stop: &AtomicBool,
+) -> Result<RenderStats, Error> {
+    let window = options.settings.window();
+    let (stats, bytes) = image::stream_png(path, window.width, window.height, |png| {
+        let write = |row: &[Color]| match options.tonemap {
+            Tonemap::Clamp => png.write_row(row),
+            tonemap => {
+                let row: Vec<Color> = row.iter().map(|&c| tonemap.apply(c)).collect();
+                png.write_row(&row)
+            }
+        };
+        let (stats, waiting) = render::render_streamed(scene, &options.settings, stop, write)?;
+        Ok((stats, waiting + png.buffer_bytes()))
+    })?;
+    record(|report| report.renders.push(stats));
+    record_output(path)?;
+    if stop.load(Ordering::SeqCst) {
+        return Err(stopped(options, &stats, 0, path));
+    }
+    info!("Raytraced {} successfully!", path.display());
+    info!("{}", stats);
+    info!("{} bytes of geometry", scene.geometry_bytes());
+    info!("{} bytes of image buffers at most", bytes);
+    Ok(stats)
+}
+
+/// Renders `scene` `scale` times smaller than asked and with a sample a
+/// pixel, and writes it to `path` for the full render to replace.
+fn draft(
+    scene: &Scene,
+    options: &Options,
+    scale: u32,
+    path: &Path,
+    stop: &AtomicBool,
+    watch: Watch,
+) -> Result<RenderStats, Error> {
+    let settings = RenderSettings {
+        width: options.settings.width.div_ceil(scale),
+        height: options.settings.height.div_ceil(scale),
+        spp: 1,
+        ..options.settings.clone()
+    };
+    let (image, stats) = render::render_stoppable(scene, &settings, stop, watch)?;
+    if stop.load(Ordering::SeqCst) {
+        info!("Stopped during the draft");
+        return Err(Error::Cancelled);
+    }
+    image.write_tonemapped(path, options.tonemap)?;
+    info!(
+        "Drafted {} at {}x{} in {:.2} s",
+        path.display(),
+        image.width,
+        image.height,
+        stats.elapsed.as_secs_f64()
+    );
+    Ok(stats)
+}
+
+/// Says how far a stopped render got, now that what there is of it has been
+/// written to `path`, and gives the error to stop with.
+fn stopped(options: &Options, stats: &RenderStats, resumed: u32, path: &Path) -> Error {
+    let passes = if options.progressive {
+        options.passes()
+    } else {
+        1
+    };
+    let window = options.settings.window();
+    let pixels = (window.width * window.height) as f64;
+    let samples = resumed as f64 * pixels + stats.camera_rays as f64;
+    let all = (options.settings.spp * passes) as f64 * pixels;
+    info!(
+        "Stopped {:.1}% of the way through; wrote what there is to {}",
+        100.0 * samples / all,
+        path.display()
+    );
+    Error::Cancelled
+}
+
+/// Renders the scene as it is to `output`, or each of the layers asked for.
+fn render_frame(
+    scene: &Scene,
+    options: &Options,
+    output: &Path,
+    gif: Option<&mut GifWriter>,
+    stop: &AtomicBool,
+    watch: Watch,
+) -> Result<RenderStats, Error> {
+    if options.all_layers {
+        let mut stats = RenderStats::default();
+        for layer in scene.layer_names() {
+            let path = layer_path(output, layer);
+            stats += render_to(&scene.layer(&[layer]), options, &path, None, stop, watch)?;
+        }
+        return Ok(stats);
+    }
+    if options.layers.is_empty() {
+        return render_to(scene, options, output, gif, stop, watch);
     }
+    let layers: Vec<&str> = options.layers.iter().map(String::as_str).collect();
+    render_to(&scene.layer(&layers), options, output, gif, stop, watch)
+}
+
+/// The scene to render, with `overrides` made to its file.
+fn load_scene(options: &Options, overrides: &[Override]) -> Result<Scene, Error> {
+    let started = Instant::now();
+    let mut scene = match (&options.scene, options.builtin_scene) {
+        (None, Some(count)) => {
+            let scene = Scene::random_spheres(count, options.settings.seed);
+            info!(
+                "Made the built-in scene spheres-{} from seed {} in {:.2?}",
+                count,
+                options.settings.seed,
+                started.elapsed()
+            );
+            scene
+        }
+        (Some(path), _) => {
+            let scene = scene_file::load_with(path, overrides)?;
+            info!(
+                "Loaded {} in {:.2?}: {} and {}",
+                scene_file::name(path).display(),
+                started.elapsed(),
+                plural(scene.objects.len(), "object"),
+                plural(scene.lights.len(), "light")
+            );
+            scene
+        }
+        (None, None) => {
+            info!("Using the built-in demo scene");
+            Scene::demo()
+        }
+    };
+    if options.medium.is_some() {
+        scene.medium = options.medium;
+    }
+    Ok(scene)
+}
 
-    fn illuminate(&self, point: Vector) -> Vector {
-        (self.intensity / (point - self.source).len().powi(2)) * self.color
+fn check_layers(scene: &Scene, options: &Options) -> Result<(), Error> {
+    let names = scene.layer_names();
+    match options
+        .layers
+        .iter()
+        .find(|layer| !names.contains(&layer.as_str()))
+    {
+        Some(missing) => Err(Error::InvalidSettings(format!(
+            "no object or light is in layer {:?}",
+            missing
+        ))),
+        None => Ok(()),
     }
 }
 
-fn raytrace() -> () {
-    let sphere = Sphere::new(Vector::new(0.0, 0.0, -10.0), 1.0);
-    let light_red = PointLight::new(Vector::new(2.0, 0.0, -9.0), Vector::new(1.0, 0.0, 0.0), 2.0);
-    let light_green = PointLight::new(
-        Vector::new(-2.0, 0.0, -9.0),
-        Vector::new(0.0, 1.0, 0.0),
-        2.0,
+/// Takes the samples of `--part`, or all of them, and writes their sums to
+/// `path` for `merge`.
+fn save_samples(scene: &Scene, options: &Options, path: &Path) -> Result<(), Error> {
+    let spp = options.settings.spp;
+    let samples = match options.part {
+        Some((part, parts)) => (part - 1) * spp / parts..part * spp / parts,
+        None => 0..spp,
+    };
+    let layers: Vec<&str> = options.layers.iter().map(String::as_str).collect();
+    let layered;
+    let scene = match layers.is_empty() {
+        true => scene,
+        false => {
+            layered = scene.layer(&layers);
+            &layered
+        }
+    };
+    stop_on_interrupt();
+    let (sums, stats) =
+        render::render_part(scene, &options.settings, samples.clone(), &STOP, |_| true)?;
+    // Parts of other seeds are as good as any other samples.
+    let unseeded = RenderSettings {
+        seed: 0,
+        ..options.settings.clone()
+    };
+    let fingerprint = fingerprint(scene, options, &unseeded)?;
+    Part::new(fingerprint, options.settings.seed, samples.clone(), &sums).write(path)?;
+    record(|report| report.renders.push(stats));
+    record_output(path)?;
+    info!(
+        "Wrote samples {}..{} of seed {} to {}",
+        samples.start,
+        samples.end,
+        options.settings.seed,
+        path.display()
     );
-    let light_blue = PointLight::new(
-        Vector::new(0.0, -2.0, -9.0),
-        Vector::new(0.0, 0.0, 1.0),
-        2.0,
+    info!("{}", stats);
+    Ok(())
+}
+
+fn run(options: &Options) -> Result<(), Error> {
+    options.settings.validate()?;
+    let mut scene = load_scene(options, &options.set)?;
+    check_layers(&scene, options)?;
+    record(|report| report.scene = Some(SceneInfo::of(&scene)));
+    if let Some(path) = &options.save_samples {
+        return save_samples(&scene, options, path);
+    }
+    #[cfg(feature = "preview")]
+    if options.interactive {
+        if !options.layers.is_empty() {
+            let layers: Vec<&str> = options.layers.iter().map(String::as_str).collect();
+            scene = scene.layer(&layers);
+        }
+        return preview::explore(&mut scene, &options.settings, options.interactive_scale);
+    }
+    let turntable = options.turntable.then(|| {
+        let around = Turntable::around(&scene.camera);
+        Turntable {
+            pivot: options.pivot.unwrap_or(around.pivot),
+            radius: options.radius.unwrap_or(around.radius),
+            elevation: options.elevation.unwrap_or(around.elevation),
+        }
+    });
+    let count = options.frames.unwrap_or(1);
+    stop_on_interrupt();
+    let mut gif = match is_gif(&options.output) {
+        true => Some(GifWriter::create(
+            &options.output,
+            scalar::to_f64(options.fps),
+            options.dither,
+        )?),
+        false => None,
+    };
+    #[cfg(feature = "preview")]
+    let mut preview = match options.preview {
+        true => Some(Preview::open(
+            options.settings.width,
+            options.settings.height,
+        )?),
+        false => None,
+    };
+    let mut watch = |_image: &Image| {
+        #[cfg(feature = "preview")]
+        if let Some(preview) = &mut preview {
+            return preview.show(_image);
+        }
+        true
+    };
+    let mut sequence = SequenceStats::default();
+    for index in 0..count {
+        if STOP.load(Ordering::SeqCst) {
+            info!("Stopped after {} of {} frames", index, count);
+            break;
+        }
+        let frame = Frame {
+            index,
+            count,
+            fps: options.fps,
+        };
+        if !options.sweeps.is_empty() {
+            let time = scalar::to_f64(frame.time());
+            let sweeps = options.sweeps.iter().map(|sweep| sweep.at(time));
+            let overrides: Vec<Override> = options.set.iter().cloned().chain(sweeps).collect();
+            scene = load_scene(options, &overrides)?;
+        }
+        if !scene.animation.is_empty() {
+            scene.set_frame(frame);
+        }
+        if let Some(turntable) = &turntable {
+            scene.camera = turntable.camera(&scene.camera, frame);
+        }
+        let output = match options.frames {
+            Some(_) if gif.is_none() => frame_path(&options.output, index),
+            _ => options.output.clone(),
+        };
+        if options.frames.is_some() {
+            info!("Frame {} at {:.2} s", index, frame.seconds());
+        }
+        sequence.frames.push(render_frame(
+            &scene,
+            options,
+            &output,
+            gif.as_mut(),
+            &STOP,
+            &mut watch,
+        )?);
+    }
+    if let Some(gif) = gif {
+        gif.finish()?;
+        record_output(&options.output)?;
+        info!("Raytraced {} successfully!", options.output.display());
+    }
+    if options.frames.is_some() {
+        info!("{}", sequence);
+    }
+    #[cfg(feature = "preview")]
+    if let Some(preview) = &mut preview {
+        info!("Close the preview window to exit");
+        preview.wait();
+    }
+    Ok(())
+}
+
+/// How long `--watch` waits for a change before looking for Ctrl-C, which
+/// is also how often it polls the files without notifications, and how
+/// long it waits after a change for another before rendering it.
+const POLL: Duration = Duration::from_millis(100);
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// The scene file and the files it refers to, or only the scene file while
+/// it can't be read for them.
+fn watched_files(path: &Path, overrides: &[Override]) -> Vec<PathBuf> {
+    let mut files = vec![path.to_path_buf()];
+    files.extend(scene_file::references(path, overrides).unwrap_or_default());
+    files
+}
+
+/// Renders the scene, then again each time its file or one it refers to
+/// changes, until Ctrl-C or the preview window is closed. An image stays
+/// as it is until a render of a newer scene replaces it, so a scene that
+/// fails to load, or a render cancelled by another change, leaves the last
+/// one there.
+fn watch(options: &Options) -> Result<(), Error> {
+    options.settings.validate()?;
+    let path = options.scene.clone().unwrap_or_default();
+    let trigger = Trigger::new();
+    let mut watcher = Watcher::new(watched_files(&path, &options.set));
+    info!(
+        "Watching {} and {} files it refers to; press Ctrl-C to stop",
+        path.display(),
+        watcher.files().count() - 1
     );
-    let shapes: Vec<&Shape> = vec![&sphere];
-    let lights: Vec<&PointLight> = vec![&light_red, &light_green, &light_blue];
-
-    const width: u32 = 640;
-    const height: u32 = 480;
-    const array_size: usize = (width * height * 4) as usize;
-    let fov = 45.0 * PI / 180.0;
-
-    let path = Path::new(r"output.png");
-    let file = File::create(path).unwrap();
-    let ref mut w = BufWriter::new(file);
-
-    let mut encoder = png::Encoder::new(w, width, height);
-    encoder.set(png::ColorType::RGBA).set(png::BitDepth::Eight);
-    let mut writer = encoder.write_header().unwrap();
-
-    let fov_y = (height as f64 * fov) / (width as f64);
-
-    let mut data = [0; array_size];
-    let origin = Vector::new(0.0, 0.0, 0.0);
-    for x in 0..width {
-        for y in 0..height {
-            let i = ((x + y * width) * 4) as usize;
-            let angle_x = ((x as f64) / (width as f64) - 0.5) * fov;
-            let angle_y = -((y as f64) / (height as f64) - 0.5) * fov_y;
-            let dx = angle_x.tan();
-            let dy = angle_y.tan();
-            let dz = -(1.0 - dx.powi(2) - dy.powi(2)).sqrt();
-            let d = Vector::new(dx, dy, dz);
-            let r = Ray::new(origin, d);
-
-            let mut current_closest: Option<&Shape> = None;
-            let mut current_closest_distance: Option<f64> = None;
-            for &shape in &shapes {
-                let distance = shape.intersect(&r);
-                if let Some(q) = distance {
-                    if current_closest_distance == None || q < current_closest_distance.unwrap() {
-                        current_closest_distance = Some(q);
-                        current_closest = Some(shape);
-                    }
+    if !watcher.is_notified() {
+        info!("Polling them every {:?} for changes", POLL);
+    }
+    stop_on_interrupt();
+    let poller = {
+        let (trigger, overrides) = (trigger.clone(), options.set.clone());
+        thread::spawn(move || {
+            while !trigger.is_closed() {
+                let changed = watcher.wait(POLL);
+                if STOP.load(Ordering::SeqCst) {
+                    trigger.close();
+                } else if changed {
+                    watcher.set_files(watched_files(&path, &overrides));
+                    trigger.fire();
                 }
             }
+        })
+    };
+    #[cfg(feature = "preview")]
+    let preview = RefCell::new(match options.preview {
+        true => Some(Preview::open(
+            options.settings.width,
+            options.settings.height,
+        )?),
+        false => None,
+    });
+    let mut show = |_image: &Image| {
+        #[cfg(feature = "preview")]
+        if let Some(preview) = preview.borrow_mut().as_mut() {
+            if !preview.show(_image) {
+                trigger.close();
+                return false;
+            }
+        }
+        true
+    };
+    let render = |cancel: &AtomicBool| {
+        let result = load_scene(options, &options.set).and_then(|scene| {
+            check_layers(&scene, options)?;
+            render_frame(&scene, options, &options.output, None, cancel, &mut show)
+        });
+        match &result {
+            Err(Error::Cancelled) if !trigger.is_closed() => {
+                info!("The scene changed; rendering again")
+            }
+            Err(Error::Cancelled) | Ok(_) => {}
+            Err(error) => eprintln!("error: {}", error),
+        }
+        result.map(drop)
+    };
+    let idle = || {
+        #[cfg(feature = "preview")]
+        if let Some(preview) = preview.borrow_mut().as_mut() {
+            return preview.pump();
+        }
+        true
+    };
+    let stats = watch::render_on_changes(&trigger, DEBOUNCE, render, idle);
+    trigger.close();
+    let _ = poller.join();
+    info!(
+        "Stopped watching after {} renders, {} cancelled and {} failed",
+        stats.finished, stats.cancelled, stats.failed
+    );
+    Ok(())
+}
+
+/// Where `--output-dir` puts the image of the scene file at `scene`.
+fn batch_output(options: &Options, scene: &Path) -> PathBuf {
+    let mut name = scene.file_stem().unwrap_or_default().to_os_string();
+    name.push(".png");
+    options.output_dir.clone().unwrap_or_default().join(name)
+}
 
-            if let Some(distance) = current_closest_distance {
-                let mut color = Vector::new(0.0, 0.0, 0.0);
-                for &light in &lights {
-                    color = color + light.illuminate(r.at(distance));
-                    color.x = if color.x > 1.0 { 1.0 } else { color.x };
-                    color.y = if color.y > 1.0 { 1.0 } else { color.y };
-                    color.z = if color.z > 1.0 { 1.0 } else { color.z };
+/// Renders one scene of a batch, with the settings of its file under the
+/// flags, and with `threads` threads if not as many as they say.
+fn render_batch_scene(options: &Options, path: &Path, threads: Option<usize>) -> Result<(), Error> {
+    let mut options = options.clone();
+    options.scene = Some(path.to_path_buf());
+    let layered = layered_settings(&options)?;
+    apply_settings(&mut options, layered);
+    if let Some(threads) = threads {
+        options.settings.threads = threads;
+    }
+    options.settings.validate()?;
+    let scene = load_scene(&options, &options.set)?;
+    check_layers(&scene, &options)?;
+    let output = batch_output(&options, path);
+    info!("Rendering {} to {}", path.display(), output.display());
+    render_frame(&scene, &options, &output, None, &STOP, &mut |_| true).map(drop)
+}
+
+/// Renders every scene of `--output-dir`, `--jobs` of them at once, carrying
+/// on past those that fail, and prints how long each took. Returns how many
+/// failed, once they're reported.
+fn batch(options: &Options) -> Result<usize, Error> {
+    let dir = options.output_dir.clone().unwrap_or_default();
+    fs::create_dir_all(&dir).map_err(|source| Error::Io {
+        path: dir.clone(),
+        operation: "create",
+        source,
+    })?;
+    let jobs = options.jobs.unwrap_or(1).min(options.batch.len());
+    // Scenes rendered at once share the threads between them, rather than
+    // each taking as many as the settings say.
+    let threads = (jobs > 1).then(|| {
+        let all = match options.settings.threads {
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            threads => threads,
+        };
+        (all / jobs).max(1)
+    });
+    stop_on_interrupt();
+    let start = Instant::now();
+    let next = AtomicUsize::new(0);
+    let rendered = Mutex::new(vec![None; options.batch.len()]);
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| {
+                while !STOP.load(Ordering::SeqCst) {
+                    let index = next.fetch_add(1, Ordering::SeqCst);
+                    let path = match options.batch.get(index) {
+                        Some(path) => path,
+                        None => break,
+                    };
+                    let began = Instant::now();
+                    let result = render_batch_scene(options, path, threads);
+                    let name = path.display().to_string();
+                    // Saying which scene, for the errors that don't.
+                    let error = result.err().map(|error| match error.to_string() {
+                        message if message.starts_with(&name) => message,
+                        message => format!("{}: {}", name, message),
+                    });
+                    rendered.lock().unwrap()[index] = Some(BatchScene {
+                        name,
+                        elapsed: began.elapsed(),
+                        error,
+                    });
                 }
-                data[i + 0] = (color.x * 255.0) as u8;
-                data[i + 1] = (color.y * 255.0) as u8;
-                data[i + 2] = (color.z * 255.0) as u8;
-                data[i + 3] = 255;
-            } else {
-                data[i] = 0;
-                data[i + 1] = 0;
-                data[i + 2] = 0;
-                data[i + 3] = 255;
-            }
+            });
+        }
+    });
+    let scenes = rendered
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .zip(&options.batch);
+    let stats = BatchStats {
+        scenes: scenes
+            .map(|(scene, path)| {
+                scene.unwrap_or_else(|| BatchScene {
+                    name: path.display().to_string(),
+                    elapsed: Duration::ZERO,
+                    error: Some("stopped before it was rendered".to_string()),
+                })
+            })
+            .collect(),
+        elapsed: start.elapsed(),
+    };
+    info!("{}", stats);
+    for scene in stats.failed() {
+        eprintln!("error: {}", scene.error.as_deref().unwrap_or_default());
+    }
+    if STOP.load(Ordering::SeqCst) {
+        return Err(Error::Cancelled);
+    }
+    Ok(stats.failed().count())
+}
+
+/// Prints the settings that apply as a config file, once they're checked.
+fn print_settings(options: &Options) -> Result<(), Error> {
+    options.settings.validate()?;
+    info!("{}", effective_settings(options).to_toml().trim_end());
+    Ok(())
+}
+
+/// The parts `merge` adds up, and where the image goes.
+struct MergeOptions {
+    parts: Vec<PathBuf>,
+    output: PathBuf,
+    tonemap: Tonemap,
+}
+
+fn parse_merge_args(args: &[String]) -> Result<MergeOptions, String> {
+    let mut options = MergeOptions {
+        parts: Vec::new(),
+        output: PathBuf::from("output.png"),
+        tonemap: Tonemap::Clamp,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => options.output = parse_value(arg, args.next())?,
+            "--tonemap" => options.tonemap = parse_tonemap(arg, args.next())?,
+            _ if arg.starts_with("--") => return Err(format!("unknown argument {:?}", arg)),
+            _ => options.parts.push(PathBuf::from(arg)),
         }
     }
-    writer.write_image_data(&data).unwrap();
+    if options.parts.is_empty() {
+        return Err("usage: merge part.bin... [--output final.png] [--tonemap NAME]".to_string());
+    }
+    Ok(options)
+}
+
+/// Adds up the parts and writes the image of all their samples.
+fn merge(options: &MergeOptions) -> Result<(), Error> {
+    let parts = options
+        .parts
+        .iter()
+        .map(|path| Part::read(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    let (first, mut merged) = (&options.parts[0], parts[0].clone());
+    for (i, (path, part)) in options.parts.iter().zip(&parts).enumerate().skip(1) {
+        let earlier = options.parts.iter().zip(&parts).take(i);
+        if let Some((other, _)) = earlier.into_iter().find(|(_, other)| other.overlaps(part)) {
+            return Err(Error::InvalidSettings(format!(
+                "{} and {} both have samples of seed {}, so they'd be counted twice",
+                other.display(),
+                path.display(),
+                part.seed
+            )));
+        }
+        merged.add(part).map_err(|message| {
+            Error::InvalidSettings(format!(
+                "{} can't be merged with {}: {}",
+                path.display(),
+                first.display(),
+                message
+            ))
+        })?;
+    }
+    merged
+        .image()
+        .write_tonemapped(&options.output, options.tonemap)?;
+    info!(
+        "Merged {} parts of {} samples a pixel in all into {}",
+        parts.len(),
+        merged.counts.iter().max().unwrap_or(&0),
+        options.output.display()
+    );
+    Ok(())
+}
+
+fn parse_info_args(args: &[String]) -> Result<Option<PathBuf>, String> {
+    match args {
+        [] => Ok(None),
+        [path] if !path.starts_with('-') => Ok(Some(PathBuf::from(path))),
+        _ => Err("usage: info [scene.json]".to_string()),
+    }
+}
+
+/// Loads the scene file at `path`, or the demo scene without one, and
+/// prints what's in it.
+fn info(path: Option<&Path>) -> Result<(), Error> {
+    let scene = match path {
+        Some(path) => scene_file::load(path)?,
+        None => Scene::demo(),
+    };
+    match path {
+        Some(path) => info!("scene: {}", path.display()),
+        None => info!("scene: the built-in demo"),
+    }
+    info!("{}", SceneInfo::of(&scene));
+    Ok(())
+}
+
+/// Writes the report of a run that ended in `result` to `path`, or to
+/// stderr for `-`.
+fn write_report(options: &Options, path: &Path, result: Result<(), &Error>) -> Result<(), Error> {
+    let report = REPORT.lock().unwrap().take().unwrap_or_default();
+    let json = report.to_json(&options.settings, options.tonemap, result);
+    if is_stdio(path) {
+        eprintln!("{}", json);
+        return Ok(());
+    }
+    fs::write(path, format!("{}\n", json)).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        operation: "write",
+        source,
+    })
+}
+
+/// Reports how a run ended, with `--report` if it was given, and exits with
+/// the code for `result` if it's an error. `message` is what to say of the
+/// error, if not the error itself.
+fn finish(options: &Options, result: Result<(), Error>, message: Option<String>) {
+    if let Err(error) = &result {
+        eprintln!("error: {}", message.unwrap_or_else(|| error.to_string()));
+    }
+    let mut code = result.as_ref().err().map(Error::exit_code);
+    if let Some(path) = &options.report {
+        if let Err(error) = write_report(options, path, result.as_ref().map(|_| ())) {
+            eprintln!("error: {}", error);
+            code = code.or(Some(error.exit_code()));
+        }
+    }
+    if let Some(code) = code {
+        process::exit(code);
+    }
 }
 
 fn main() {
-    println!("Hello, world!");
-    raytrace();
-    println!("Raytraced successfully!");
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (global, args) = match parse_global_args(&args) {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            process::exit(2);
+        }
+    };
+    let filter = log_filter(&global);
+    log::set_max_level(filter.max_level());
+    log::set_logger(Box::leak(Box::new(Terminal { filter }))).expect("the logger is set once");
+    // Without a command, the flags are render's, as they were before there
+    // were commands.
+    let (command, args) = match args.first().map(String::as_str) {
+        Some("render") => (Some(Command::Render), &args[1..]),
+        Some("preview") => (Some(Command::Preview), &args[1..]),
+        Some("diff") => (Some(Command::Diff), &args[1..]),
+        Some("merge") => (Some(Command::Merge), &args[1..]),
+        Some("info") => (Some(Command::Info), &args[1..]),
+        Some(other) if !other.starts_with('-') => {
+            eprintln!(
+                "error: unknown command {:?}, expected render, preview, diff, merge or info",
+                other
+            );
+            process::exit(2);
+        }
+        _ => (None, &args[..]),
+    };
+    match command {
+        // Exits 1 if the images are further apart than allowed, and 2 if
+        // they can't be compared at all.
+        Some(Command::Diff) => process::exit(match parse_diff_args(args).and_then(|o| diff(&o)) {
+            Ok(passed) => i32::from(!passed),
+            Err(message) => {
+                eprintln!("error: {}", message);
+                2
+            }
+        }),
+        Some(Command::Merge) => {
+            let options = match parse_merge_args(args) {
+                Ok(options) => options,
+                Err(message) => {
+                    eprintln!("error: {}", message);
+                    process::exit(2);
+                }
+            };
+            if let Err(error) = merge(&options) {
+                eprintln!("error: {}", error);
+                process::exit(error.exit_code());
+            }
+        }
+        Some(Command::Info) => {
+            let path = match parse_info_args(args) {
+                Ok(path) => path,
+                Err(message) => {
+                    eprintln!("error: {}", message);
+                    process::exit(2);
+                }
+            };
+            if let Err(error) = info(path.as_deref()) {
+                eprintln!("error: {}", error);
+                process::exit(error.exit_code());
+            }
+        }
+        _ => {
+            let mut options = match parse_args(args, command) {
+                Ok(options) => options,
+                Err(message) => {
+                    eprintln!("error: {}", message);
+                    process::exit(2);
+                }
+            };
+            options.overrides.threads = global.threads;
+            if options.report.is_some() {
+                *REPORT.lock().unwrap() = Some(Report::default());
+            }
+            match layered_settings(&options) {
+                Ok(layered) => apply_settings(&mut options, layered),
+                Err(error) => finish(&options, Err(error), None),
+            }
+            if let Err(message) = check_options(&options) {
+                let error = Error::InvalidSettings(message.clone());
+                finish(&options, Err(error), Some(message));
+            }
+            let to_stdout = is_stdio(&options.output) && !options.print_settings;
+            SAY_TO_STDERR.store(to_stdout, Ordering::Relaxed);
+            let result = match options.print_settings {
+                true => print_settings(&options),
+                false if options.watch => watch(&options),
+                false if options.output_dir.is_some() => match batch(&options) {
+                    Ok(0) => Ok(()),
+                    // The scenes that failed were reported with the rest.
+                    Ok(_) => process::exit(1),
+                    Err(error) => Err(error),
+                },
+                false => run(&options),
+            };
+            finish(&options, result, None);
+        }
+    }
 }
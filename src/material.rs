@@ -0,0 +1,333 @@
+use std::sync::Arc;
+
+use crate::info;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::sampling::{cosine_hemisphere_pdf, sample_cosine_hemisphere, OrthonormalBasis};
+use crate::scalar::{consts::PI, Scalar};
+use crate::shapes::HitRecord;
+use crate::texture::{SolidColor, Texture};
+use crate::vector::{Color, Vector};
+
+pub struct Scatter {
+    pub direction: Vector,
+    /// The BSDF times the cosine term, divided by the sampling PDF.
+    pub attenuation: Color,
+    /// Solid-angle density with which `direction` was chosen; meaningless for
+    /// specular materials.
+    pub pdf: Scalar,
+}
+
+pub trait Material: Send + Sync {
+    /// Samples a direction for the continuation of a path arriving along `ray`.
+    fn scatter(&self, ray: &Ray, hit: &HitRecord, sampler: &mut Sampler) -> Option<Scatter>;
+
+    /// Evaluates the BSDF for light arriving from `wi` and leaving toward `wo`,
+    /// both pointing away from the surface.
+    fn eval(&self, hit: &HitRecord, wo: Vector, wi: Vector) -> Color;
+
+    /// The solid-angle density with which `scatter` picks `wi` for a path
+    /// leaving toward `wo`.
+    fn pdf(&self, hit: &HitRecord, wo: Vector, wi: Vector) -> Scalar;
+
+    /// Whether the material only scatters into discrete directions, so that
+    /// `eval` and `pdf` are always 0 and lights can't be sampled through it.
+    fn is_specular(&self) -> bool {
+        false
+    }
+
+    fn emitted(&self, _hit: &HitRecord) -> Color {
+        Color::zero()
+    }
+
+    /// Adjusts the shading normal of a fresh hit before anything else is
+    /// asked of the material; most materials leave it alone.
+    fn shade(&self, _hit: &mut HitRecord) {}
+
+    /// What kind of material this is, such as `Lambertian`.
+    fn name(&self) -> &'static str {
+        info::type_name::<Self>()
+    }
+}
+
+pub struct Lambertian {
+    pub albedo: Box<dyn Texture>,
+}
+
+impl Lambertian {
+    pub fn new(albedo: Color) -> Lambertian {
+        Lambertian::textured(Box::new(SolidColor::new(albedo)))
+    }
+
+    pub fn textured(albedo: Box<dyn Texture>) -> Lambertian {
+        Lambertian { albedo }
+    }
+
+    fn albedo(&self, hit: &HitRecord) -> Color {
+        self.albedo.value_at(hit)
+    }
+}
+
+impl Material for Lambertian {
+    fn scatter(&self, _ray: &Ray, hit: &HitRecord, sampler: &mut Sampler) -> Option<Scatter> {
+        let (local, pdf) = sample_cosine_hemisphere(sampler.next_scalar(), sampler.next_scalar());
+        let direction = OrthonormalBasis::from_normal(hit.shading_normal).to_world(local);
+        Some(Scatter {
+            direction,
+            attenuation: self.albedo(hit),
+            pdf,
+        })
+    }
+
+    fn eval(&self, hit: &HitRecord, _wo: Vector, wi: Vector) -> Color {
+        if hit.shading_normal * wi <= 0.0 {
+            return Color::zero();
+        }
+        (1.0 / PI) * self.albedo(hit)
+    }
+
+    fn pdf(&self, hit: &HitRecord, _wo: Vector, wi: Vector) -> Scalar {
+        cosine_hemisphere_pdf(hit.shading_normal * wi)
+    }
+}
+
+/// A normalized Phong lobe around the mirror direction; the larger the
+/// exponent, the sharper the reflection.
+pub struct Glossy {
+    pub color: Color,
+    pub exponent: Scalar,
+}
+
+impl Glossy {
+    pub fn new(color: Color, exponent: Scalar) -> Glossy {
+        Glossy { color, exponent }
+    }
+
+    fn lobe(&self, hit: &HitRecord, wo: Vector, wi: Vector) -> Option<Scalar> {
+        if hit.shading_normal * wi <= 0.0 || hit.shading_normal * wo <= 0.0 {
+            return None;
+        }
+        let mirror = (-wo).reflect(hit.shading_normal);
+        Some((mirror * wi).max(0.0).powf(self.exponent))
+    }
+}
+
+impl Material for Glossy {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord, sampler: &mut Sampler) -> Option<Scatter> {
+        let wo = -ray.direction;
+        let mirror = ray.direction.reflect(hit.shading_normal);
+        let cos_alpha = sampler.next_scalar().powf(1.0 / (self.exponent + 1.0));
+        let sin_alpha = (1.0 - cos_alpha * cos_alpha).max(0.0).sqrt();
+        let phi = 2.0 * PI * sampler.next_scalar();
+        let local = Vector::new(sin_alpha * phi.cos(), sin_alpha * phi.sin(), cos_alpha);
+        let direction = OrthonormalBasis::from_normal(mirror).to_world(local);
+        let pdf = self.pdf(hit, wo, direction);
+        if pdf <= 0.0 {
+            return None;
+        }
+        let cosine = hit.shading_normal * direction;
+        Some(Scatter {
+            direction,
+            attenuation: (cosine / pdf) * self.eval(hit, wo, direction),
+            pdf,
+        })
+    }
+
+    fn eval(&self, hit: &HitRecord, wo: Vector, wi: Vector) -> Color {
+        match self.lobe(hit, wo, wi) {
+            Some(lobe) => ((self.exponent + 2.0) / (2.0 * PI) * lobe) * self.color,
+            None => Color::zero(),
+        }
+    }
+
+    fn pdf(&self, hit: &HitRecord, wo: Vector, wi: Vector) -> Scalar {
+        match self.lobe(hit, wo, wi) {
+            Some(lobe) => (self.exponent + 1.0) / (2.0 * PI) * lobe,
+            None => 0.0,
+        }
+    }
+}
+
+/// A surface that glows with a constant radiance and reflects nothing.
+pub struct Emissive {
+    pub radiance: Color,
+}
+
+impl Emissive {
+    pub fn new(radiance: Color) -> Emissive {
+        Emissive { radiance }
+    }
+}
+
+impl Material for Emissive {
+    fn scatter(&self, _ray: &Ray, _hit: &HitRecord, _sampler: &mut Sampler) -> Option<Scatter> {
+        None
+    }
+
+    fn eval(&self, _hit: &HitRecord, _wo: Vector, _wi: Vector) -> Color {
+        Color::zero()
+    }
+
+    fn pdf(&self, _hit: &HitRecord, _wo: Vector, _wi: Vector) -> Scalar {
+        0.0
+    }
+
+    fn emitted(&self, hit: &HitRecord) -> Color {
+        if hit.front_face {
+            self.radiance
+        } else {
+            Color::zero()
+        }
+    }
+}
+
+/// A clear refractive material such as glass, reflecting or refracting in
+/// proportion to the Fresnel reflectance.
+pub struct Dielectric {
+    pub ior: Scalar,
+}
+
+impl Dielectric {
+    pub fn new(ior: Scalar) -> Dielectric {
+        Dielectric { ior }
+    }
+}
+
+fn schlick(cosine: Scalar, ratio: Scalar) -> Scalar {
+    let r0 = ((1.0 - ratio) / (1.0 + ratio)).powi(2);
+    r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
+}
+
+impl Material for Dielectric {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord, sampler: &mut Sampler) -> Option<Scatter> {
+        let ratio = if hit.front_face {
+            1.0 / self.ior
+        } else {
+            self.ior
+        };
+        let d = ray.direction.normalize();
+        let cos_theta = (-(d * hit.shading_normal)).min(1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        let direction = if ratio * sin_theta > 1.0
+            || schlick(cos_theta, ratio) > sampler.next_scalar()
+        {
+            d.reflect(hit.shading_normal)
+        } else {
+            let perpendicular = ratio * (d + cos_theta * hit.shading_normal);
+            let parallel = -(1.0 - perpendicular * perpendicular).abs().sqrt() * hit.shading_normal;
+            perpendicular + parallel
+        };
+        Some(Scatter {
+            direction,
+            attenuation: Color::new(1.0, 1.0, 1.0),
+            pdf: 1.0,
+        })
+    }
+
+    fn eval(&self, _hit: &HitRecord, _wo: Vector, _wi: Vector) -> Color {
+        Color::zero()
+    }
+
+    fn pdf(&self, _hit: &HitRecord, _wo: Vector, _wi: Vector) -> Scalar {
+        0.0
+    }
+
+    fn is_specular(&self) -> bool {
+        true
+    }
+}
+
+/// A material whose shading normal is bent by a tangent-space normal map.
+///
+/// Each texel's red, green and blue map from `[0, 1]` to `[-1, 1]` along the
+/// tangent (+u), the bitangent (up the image, -v) and the outward normal, so
+/// the flat color `(0.5, 0.5, 1.0)` leaves the surface as it was. Shapes
+/// without surface derivatives get an arbitrary tangent.
+///
+/// Directions on opposite sides of the geometric and shading normals are
+/// rejected so that bent normals can't let light through the surface.
+pub struct NormalMapped {
+    pub material: Arc<dyn Material>,
+    pub normal_map: Box<dyn Texture>,
+}
+
+impl NormalMapped {
+    pub fn new(material: Arc<dyn Material>, normal_map: Box<dyn Texture>) -> NormalMapped {
+        NormalMapped {
+            material,
+            normal_map,
+        }
+    }
+}
+
+/// Whether `direction` is on the same side of the shading and geometric
+/// normals, so that reflection and transmission agree between the two.
+fn consistent(hit: &HitRecord, direction: Vector) -> bool {
+    (hit.normal * direction) * (hit.shading_normal * direction) > 0.0
+}
+
+impl Material for NormalMapped {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord, sampler: &mut Sampler) -> Option<Scatter> {
+        self.material
+            .scatter(ray, hit, sampler)
+            .filter(|scatter| consistent(hit, scatter.direction))
+    }
+
+    fn eval(&self, hit: &HitRecord, wo: Vector, wi: Vector) -> Color {
+        if !consistent(hit, wo) || !consistent(hit, wi) {
+            return Color::zero();
+        }
+        self.material.eval(hit, wo, wi)
+    }
+
+    fn pdf(&self, hit: &HitRecord, wo: Vector, wi: Vector) -> Scalar {
+        if !consistent(hit, wo) || !consistent(hit, wi) {
+            return 0.0;
+        }
+        self.material.pdf(hit, wo, wi)
+    }
+
+    fn is_specular(&self) -> bool {
+        self.material.is_specular()
+    }
+
+    fn emitted(&self, hit: &HitRecord) -> Color {
+        self.material.emitted(hit)
+    }
+
+    fn shade(&self, hit: &mut HitRecord) {
+        self.material.shade(hit);
+        let texel = self.normal_map.value_at(hit);
+        let local = 2.0 * texel - Vector::new(1.0, 1.0, 1.0);
+        if local.x == 0.0 && local.y == 0.0 {
+            return;
+        }
+        let outward = if hit.front_face {
+            hit.shading_normal
+        } else {
+            -hit.shading_normal
+        };
+        let tangent = hit.tangent - (hit.tangent * outward) * outward;
+        let (tangent, up) = if tangent.len() > 1e-12 {
+            let tangent = tangent.normalize();
+            let side = outward.cross(tangent);
+            // Face the bitangent up the image whichever way the shape's
+            // coordinates turn.
+            let up = if side * hit.bitangent > 0.0 {
+                -side
+            } else {
+                side
+            };
+            (tangent, up)
+        } else {
+            let basis = OrthonormalBasis::from_normal(outward);
+            (basis.u, basis.v)
+        };
+        let bent = local.x * tangent + local.y * up + local.z * outward;
+        if bent.len() == 0.0 {
+            return;
+        }
+        let bent = bent.normalize();
+        hit.shading_normal = if hit.front_face { bent } else { -bent };
+    }
+}
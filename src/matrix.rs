@@ -0,0 +1,158 @@
+use std::ops;
+
+use crate::scalar::Scalar;
+use crate::vector::Vector;
+
+/// A 4×4 matrix for affine transforms of column vectors, stored by rows.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Matrix4 {
+    pub rows: [[Scalar; 4]; 4],
+}
+
+impl Matrix4 {
+    pub fn new(rows: [[Scalar; 4]; 4]) -> Matrix4 {
+        Matrix4 { rows }
+    }
+
+    pub fn identity() -> Matrix4 {
+        Matrix4::scale(Vector::new(1.0, 1.0, 1.0))
+    }
+
+    pub fn translate(offset: Vector) -> Matrix4 {
+        Matrix4::new([
+            [1.0, 0.0, 0.0, offset.x],
+            [0.0, 1.0, 0.0, offset.y],
+            [0.0, 0.0, 1.0, offset.z],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn scale(factors: Vector) -> Matrix4 {
+        Matrix4::new([
+            [factors.x, 0.0, 0.0, 0.0],
+            [0.0, factors.y, 0.0, 0.0],
+            [0.0, 0.0, factors.z, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// A rotation by `angle` radians about the unit `axis`, counterclockwise
+    /// when looking down the axis toward the origin.
+    pub fn rotate(axis: Vector, angle: Scalar) -> Matrix4 {
+        let Vector { x, y, z } = axis;
+        let (sin, cos) = angle.sin_cos();
+        let t = 1.0 - cos;
+        Matrix4::new([
+            [
+                t * x * x + cos,
+                t * x * y - sin * z,
+                t * x * z + sin * y,
+                0.0,
+            ],
+            [
+                t * x * y + sin * z,
+                t * y * y + cos,
+                t * y * z - sin * x,
+                0.0,
+            ],
+            [
+                t * x * z - sin * y,
+                t * y * z + sin * x,
+                t * z * z + cos,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn rotate_x(angle: Scalar) -> Matrix4 {
+        Matrix4::rotate(Vector::new(1.0, 0.0, 0.0), angle)
+    }
+
+    pub fn rotate_y(angle: Scalar) -> Matrix4 {
+        Matrix4::rotate(Vector::new(0.0, 1.0, 0.0), angle)
+    }
+
+    pub fn rotate_z(angle: Scalar) -> Matrix4 {
+        Matrix4::rotate(Vector::new(0.0, 0.0, 1.0), angle)
+    }
+
+    pub fn transpose(&self) -> Matrix4 {
+        let mut rows = [[0.0; 4]; 4];
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, entry) in row.iter_mut().enumerate() {
+                *entry = self.rows[j][i];
+            }
+        }
+        Matrix4::new(rows)
+    }
+
+    /// The inverse by Gauss-Jordan elimination with partial pivoting, or
+    /// `None` if the matrix is singular.
+    pub fn inverse(&self) -> Option<Matrix4> {
+        let mut a = self.rows;
+        let mut inverse = Matrix4::identity().rows;
+        for column in 0..4 {
+            let pivot = (column..4)
+                .max_by(|&i, &j| a[i][column].abs().total_cmp(&a[j][column].abs()))
+                .unwrap();
+            if a[pivot][column].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(column, pivot);
+            inverse.swap(column, pivot);
+            let scale = 1.0 / a[column][column];
+            for j in 0..4 {
+                a[column][j] *= scale;
+                inverse[column][j] *= scale;
+            }
+            for row in 0..4 {
+                let factor = a[row][column];
+                if row == column || factor == 0.0 {
+                    continue;
+                }
+                for j in 0..4 {
+                    a[row][j] -= factor * a[column][j];
+                    inverse[row][j] -= factor * inverse[column][j];
+                }
+            }
+        }
+        Some(Matrix4::new(inverse))
+    }
+
+    /// The determinant of the upper-left 3×3 block, by how much the
+    /// transform scales volumes.
+    pub fn determinant3(&self) -> Scalar {
+        let m = &self.rows;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    /// Transforms a position, including the translation.
+    pub fn transform_point(&self, point: Vector) -> Vector {
+        self.transform_direction(point)
+            + Vector::new(self.rows[0][3], self.rows[1][3], self.rows[2][3])
+    }
+
+    /// Transforms a direction or offset, ignoring the translation.
+    pub fn transform_direction(&self, direction: Vector) -> Vector {
+        let row = |r: [Scalar; 4]| r[0] * direction.x + r[1] * direction.y + r[2] * direction.z;
+        Vector::new(row(self.rows[0]), row(self.rows[1]), row(self.rows[2]))
+    }
+}
+
+impl ops::Mul<Matrix4> for Matrix4 {
+    type Output = Matrix4;
+
+    /// The transform applying `other` first, then `self`.
+    fn mul(self, other: Matrix4) -> Matrix4 {
+        let mut rows = [[0.0; 4]; 4];
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, entry) in row.iter_mut().enumerate() {
+                *entry = (0..4).map(|k| self.rows[i][k] * other.rows[k][j]).sum();
+            }
+        }
+        Matrix4::new(rows)
+    }
+}
@@ -0,0 +1,79 @@
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::scalar::{consts::PI, Scalar};
+use crate::scene::Scene;
+use crate::vector::Color;
+
+/// A homogeneous participating medium filling the whole scene.
+///
+/// Light travelling through it is attenuated by both absorption and
+/// scattering; the scattered part is redirected isotropically, which is what
+/// produces visible light shafts around the point lights.
+#[derive(Debug, Copy, Clone)]
+pub struct Medium {
+    pub absorption: Scalar,
+    pub scattering: Scalar,
+    pub steps: u32,
+    pub max_distance: Scalar,
+}
+
+impl Medium {
+    pub fn new(absorption: Scalar, scattering: Scalar) -> Medium {
+        Medium {
+            absorption,
+            scattering,
+            steps: 128,
+            max_distance: 50.0,
+        }
+    }
+
+    pub fn extinction(&self) -> Scalar {
+        self.absorption + self.scattering
+    }
+
+    pub fn transmittance(&self, distance: Scalar) -> Scalar {
+        (-self.extinction() * distance).exp()
+    }
+
+    pub fn phase(&self) -> Scalar {
+        1.0 / (4.0 * PI)
+    }
+
+    /// Single scattering along a ray with a unit direction, integrated by
+    /// marching from its origin up to `distance` and gathering one sample of
+    /// every unshadowed light at each step.
+    pub fn in_scatter(
+        &self,
+        scene: &Scene,
+        ray: &Ray,
+        distance: Scalar,
+        sampler: &mut Sampler,
+    ) -> Color {
+        let distance = distance.min(self.max_distance);
+        let step = distance / self.steps as Scalar;
+        let mut color = Color::zero();
+        for i in 0..self.steps {
+            let t = (i as Scalar + 0.5) * step;
+            let point = ray.at(t);
+            let camera_transmittance = self.transmittance(t);
+            for light in &scene.lights {
+                let sample = match light.sample(point, sampler) {
+                    Some(sample) => sample,
+                    None => continue,
+                };
+                if scene.occluded(point, sample.direction, sample.distance) {
+                    continue;
+                }
+                let light_transmittance = self.transmittance(sample.distance);
+                let weight = self.scattering
+                    * self.phase()
+                    * camera_transmittance
+                    * light_transmittance
+                    * step
+                    / sample.pdf;
+                color += weight * sample.radiance;
+            }
+        }
+        color
+    }
+}
@@ -0,0 +1,119 @@
+//! Gradient noise after Perlin's "Improving Noise" (2002).
+
+use crate::sampler::Sampler;
+use crate::scalar::Scalar;
+use crate::vector::Vector;
+
+/// Perlin noise with a permutation table shuffled from a seed, so the same
+/// seed always gives the same field.
+#[derive(Debug, Clone)]
+pub struct Perlin {
+    // The permutation twice over, so lookups can skip wrapping.
+    permutation: [u8; 512],
+}
+
+/// Smootherstep, whose first and second derivatives vanish at 0 and 1 so
+/// cells join without visible creases.
+fn fade(t: Scalar) -> Scalar {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: Scalar, a: Scalar, b: Scalar) -> Scalar {
+    a + t * (b - a)
+}
+
+/// The dot product of one of twelve edge-of-cube gradients, picked by
+/// `hash`, with the offset `(x, y, z)` from the lattice point.
+fn gradient(hash: u8, x: Scalar, y: Scalar, z: Scalar) -> Scalar {
+    match hash & 15 {
+        0 | 12 => x + y,
+        1 | 14 => y - x,
+        2 => x - y,
+        3 => -x - y,
+        4 => x + z,
+        5 => z - x,
+        6 => x - z,
+        7 => -x - z,
+        8 => y + z,
+        9 | 13 => z - y,
+        10 => y - z,
+        _ => -y - z,
+    }
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Perlin {
+        let mut table: [u8; 256] = [0; 256];
+        for (i, entry) in table.iter_mut().enumerate() {
+            *entry = i as u8;
+        }
+        let mut sampler = Sampler::new(seed, 0, 0);
+        for i in (1..256).rev() {
+            let j = ((sampler.next_f64() * (i + 1) as f64) as usize).min(i);
+            table.swap(i, j);
+        }
+        let mut permutation = [0; 512];
+        for (i, entry) in permutation.iter_mut().enumerate() {
+            *entry = table[i & 255];
+        }
+        Perlin { permutation }
+    }
+
+    /// Noise at `point`, roughly in `[-1, 1]` and 0 at every lattice point.
+    pub fn noise(&self, point: Vector) -> Scalar {
+        let p = &self.permutation;
+        let cell = |c: Scalar| (c.floor() as i64 & 255) as usize;
+        let (xi, yi, zi) = (cell(point.x), cell(point.y), cell(point.z));
+        let (x, y, z) = (
+            point.x - point.x.floor(),
+            point.y - point.y.floor(),
+            point.z - point.z.floor(),
+        );
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        let a = p[xi] as usize + yi;
+        let (aa, ab) = (p[a] as usize + zi, p[a + 1] as usize + zi);
+        let b = p[xi + 1] as usize + yi;
+        let (ba, bb) = (p[b] as usize + zi, p[b + 1] as usize + zi);
+
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, gradient(p[aa], x, y, z), gradient(p[ba], x - 1.0, y, z)),
+                lerp(
+                    u,
+                    gradient(p[ab], x, y - 1.0, z),
+                    gradient(p[bb], x - 1.0, y - 1.0, z),
+                ),
+            ),
+            lerp(
+                v,
+                lerp(
+                    u,
+                    gradient(p[aa + 1], x, y, z - 1.0),
+                    gradient(p[ba + 1], x - 1.0, y, z - 1.0),
+                ),
+                lerp(
+                    u,
+                    gradient(p[ab + 1], x, y - 1.0, z - 1.0),
+                    gradient(p[bb + 1], x - 1.0, y - 1.0, z - 1.0),
+                ),
+            ),
+        )
+    }
+
+    /// The sum of `octaves` layers of the absolute noise, each at twice the
+    /// frequency and half the weight of the one before; between 0 and 2.
+    pub fn turbulence(&self, point: Vector, octaves: u32) -> Scalar {
+        let mut sum = 0.0;
+        let mut frequency = 1.0;
+        let mut weight = 1.0;
+        for _ in 0..octaves {
+            sum += weight * self.noise(frequency * point).abs();
+            frequency *= 2.0;
+            weight *= 0.5;
+        }
+        sum
+    }
+}
@@ -0,0 +1,127 @@
+//! Reading triangle meshes from Wavefront OBJ files.
+//!
+//! Only vertex positions (`v`), texture coordinates (`vt`) and faces (`f`)
+//! are used; polygons are split into fans of triangles, leaving out those
+//! with no area, which nothing can hit. OBJ puts `v` = 0 at the bottom of a
+//! texture, so it is flipped to match [`ImageTexture`]'s top-down rows.
+//!
+//! [`ImageTexture`]: crate::texture::ImageTexture
+
+#[cfg(feature = "files")]
+use std::fs;
+#[cfg(feature = "files")]
+use std::path::Path;
+
+#[cfg(feature = "files")]
+use log::warn;
+
+use crate::scalar::Scalar;
+use crate::shapes::{MeshTriangle, TriangleMesh};
+use crate::vector::Vector;
+
+/// Reads the mesh at `path`, warning of the triangles with no area that are
+/// left out of it.
+#[cfg(feature = "files")]
+pub fn load(path: &Path) -> Result<TriangleMesh, String> {
+    let text =
+        fs::read_to_string(path).map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+    let (mesh, degenerate) = read(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+    if degenerate > 0 {
+        warn!(
+            "{}: left out {} degenerate triangle{}",
+            path.display(),
+            degenerate,
+            if degenerate == 1 { "" } else { "s" }
+        );
+    }
+    Ok(mesh)
+}
+
+pub fn parse(text: &str) -> Result<TriangleMesh, String> {
+    read(text).map(|(mesh, _)| mesh)
+}
+
+/// The mesh in `text`, and how many triangles with no area were left out.
+fn read(text: &str) -> Result<(TriangleMesh, usize), String> {
+    let mut positions = Vec::new();
+    let mut uvs = Vec::new();
+    let mut triangles = Vec::new();
+    let mut degenerate = 0;
+    for (number, line) in text.lines().enumerate() {
+        let fail = |message: &str| format!("line {}: {}", number + 1, message);
+        let line = line.split('#').next().unwrap_or("");
+        let mut words = line.split_whitespace();
+        let numbers = |words: std::str::SplitWhitespace| -> Result<Vec<Scalar>, String> {
+            words
+                .map(|word| {
+                    word.parse()
+                        .map_err(|_| fail(&format!("invalid number {:?}", word)))
+                })
+                .collect()
+        };
+        match words.next() {
+            Some("v") => match numbers(words)?[..] {
+                [x, y, z] | [x, y, z, _] => positions.push(Vector::new(x, y, z)),
+                _ => return Err(fail("a vertex needs three coordinates")),
+            },
+            Some("vt") => match numbers(words)?[..] {
+                [u] => uvs.push((u, 1.0)),
+                [u, v] | [u, v, _] => uvs.push((u, 1.0 - v)),
+                _ => return Err(fail("a texture coordinate needs one to three numbers")),
+            },
+            Some("f") => {
+                let mut corners = Vec::new();
+                for word in words {
+                    let mut indices = word.split('/');
+                    let position = index(indices.next(), positions.len())
+                        .ok_or_else(|| fail(&format!("invalid vertex {:?}", word)))?;
+                    let uv = match indices.next() {
+                        None | Some("") => None,
+                        texture => Some(
+                            index(texture, uvs.len())
+                                .ok_or_else(|| fail(&format!("invalid vertex {:?}", word)))?,
+                        ),
+                    };
+                    corners.push((position, uv));
+                }
+                if corners.len() < 3 {
+                    return Err(fail("a face needs at least three vertices"));
+                }
+                for i in 1..corners.len() - 1 {
+                    let [a, b, c] = [corners[0], corners[i], corners[i + 1]];
+                    let [pa, pb, pc] = [a.0, b.0, c.0].map(|i| positions[i]);
+                    let area = (pb - pa).cross(pc - pa).len();
+                    if area.is_nan() || area <= 0.0 {
+                        degenerate += 1;
+                        continue;
+                    }
+                    triangles.push(MeshTriangle {
+                        positions: [a.0, b.0, c.0],
+                        uvs: match (a.1, b.1, c.1) {
+                            (Some(a), Some(b), Some(c)) => Some([a, b, c]),
+                            _ => None,
+                        },
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok((TriangleMesh::new(positions, uvs, triangles), degenerate))
+}
+
+/// Resolves a 1-based OBJ index, or a negative one counting back from the
+/// last of `count` elements read so far.
+fn index(word: Option<&str>, count: usize) -> Option<usize> {
+    let index: i64 = word?.parse().ok()?;
+    let resolved = if index < 0 {
+        count as i64 + index
+    } else {
+        index - 1
+    };
+    if resolved >= 0 && (resolved as usize) < count {
+        Some(resolved as usize)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,283 @@
+//! Changes to a scene file's values, made after it's parsed and before the
+//! scene is built from it, for trying one value after another without
+//! editing the file.
+//!
+//! An override is `path=value`. The path names a value the way errors in
+//! scene files do, with member names joined by `.` and `[i]` for an array's
+//! `i`th item, such as `spheres[1].material.albedo`. The value is JSON, such
+//! as `5`, `[1, 0, 0]` or `{"type": "glossy", "color": [1, 1, 1], "exponent":
+//! 10}`, or a string if it isn't JSON, so `lights[0].type=point` needs no
+//! quotes. A number given for an array of numbers sets all of them, and
+//! anything given for a string is taken as the string it's written as. The
+//! last member of a path can be one the file leaves out, which adds it, but
+//! the rest have to be there.
+//!
+//! A [`Sweep`] is `path=from..to`, moving a number, or each of an array of
+//! them, linearly from `from` at the first frame to `to` at the last.
+
+use std::fmt;
+
+use crate::json::{self, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    Member(String),
+    Index(usize),
+}
+
+/// Where in a scene file a problem is, written as its errors are.
+struct Location<'a>(&'a [Step]);
+
+impl Location<'_> {
+    fn error(&self, message: &str) -> String {
+        if self.0.is_empty() {
+            message.to_string()
+        } else {
+            format!("{}: {}", self, message)
+        }
+    }
+}
+
+impl fmt::Display for Location<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, step) in self.0.iter().enumerate() {
+            match step {
+                Step::Member(name) if i == 0 => write!(f, "{}", name)?,
+                Step::Member(name) => write!(f, ".{}", name)?,
+                Step::Index(index) => write!(f, "[{}]", index)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_path(text: &str) -> Result<Vec<Step>, String> {
+    let invalid = || format!("invalid path {:?}", text);
+    let mut path = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        if let Some(index) = rest.strip_prefix('[') {
+            let end = index.find(']').ok_or_else(invalid)?;
+            path.push(Step::Index(index[..end].parse().map_err(|_| invalid())?));
+            rest = &index[end + 1..];
+            continue;
+        }
+        let name = match rest.strip_prefix('.') {
+            Some(name) if !path.is_empty() => name,
+            None if path.is_empty() => rest,
+            _ => return Err(invalid()),
+        };
+        let end = name.find(['.', '[']).unwrap_or(name.len());
+        if end == 0 {
+            return Err(invalid());
+        }
+        path.push(Step::Member(name[..end].to_string()));
+        rest = &name[end..];
+    }
+    match path.first() {
+        Some(Step::Member(_)) => Ok(path),
+        _ => Err(invalid()),
+    }
+}
+
+/// Splits `path=value`.
+fn split(text: &str) -> Result<(Vec<Step>, &str), String> {
+    let (path, value) = text.split_once('=').ok_or("expected path=value")?;
+    Ok((parse_path(path.trim())?, value.trim()))
+}
+
+/// A value as JSON, or as a string if it isn't JSON.
+fn parse_value(text: &str) -> Value {
+    json::parse(text).unwrap_or_else(|_| Value::String(text.to_string()))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Override {
+    /// How it was written, to say which override a problem is with.
+    text: String,
+    path: Vec<Step>,
+    value: Value,
+}
+
+impl Override {
+    pub fn parse(text: &str) -> Result<Override, String> {
+        let (path, value) = split(text)?;
+        Ok(Override {
+            text: text.to_string(),
+            path,
+            value: parse_value(value),
+        })
+    }
+
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// Puts the value at the path in `root`, or says which part of the path
+    /// isn't there.
+    pub fn apply(&self, root: &mut Value) -> Result<(), String> {
+        let (last, parents) = self.path.split_last().unwrap();
+        let mut value = root;
+        for (i, step) in parents.iter().enumerate() {
+            let at = Location(&self.path[..i]);
+            value = match step {
+                Step::Member(name) => {
+                    let members = members(value, &at)?;
+                    match members.iter().position(|(key, _)| key == name) {
+                        Some(found) => &mut members[found].1,
+                        None => return Err(at.error(&format!("missing member {:?}", name))),
+                    }
+                }
+                Step::Index(index) => item(value, *index, &at)?,
+            };
+        }
+        let at = Location(parents);
+        let slot = match last {
+            Step::Member(name) => {
+                let members = members(value, &at)?;
+                match members.iter().position(|(key, _)| key == name) {
+                    Some(found) => &mut members[found].1,
+                    None => {
+                        members.push((name.clone(), self.value.clone()));
+                        return Ok(());
+                    }
+                }
+            }
+            Step::Index(index) => item(value, *index, &at)?,
+        };
+        *slot = self.coerced(slot);
+        Ok(())
+    }
+
+    /// The value as it goes in place of `old`.
+    fn coerced(&self, old: &Value) -> Value {
+        match (old, &self.value) {
+            (Value::Array(items), Value::Number(_))
+                if !items.is_empty() && items.iter().all(|item| item.as_f64().is_some()) =>
+            {
+                Value::Array(vec![self.value.clone(); items.len()])
+            }
+            (Value::String(_), Value::String(_)) => self.value.clone(),
+            (Value::String(_), value) => Value::String(value.to_string()),
+            _ => self.value.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Override {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+fn members<'a>(
+    value: &'a mut Value,
+    at: &Location,
+) -> Result<&'a mut Vec<(String, Value)>, String> {
+    match value {
+        Value::Object(members) => Ok(members),
+        other => Err(at.error(&format!("expected an object, found {}", other.kind()))),
+    }
+}
+
+fn item<'a>(value: &'a mut Value, index: usize, at: &Location) -> Result<&'a mut Value, String> {
+    match value {
+        Value::Array(items) => {
+            let count = items.len();
+            items
+                .get_mut(index)
+                .ok_or_else(|| at.error(&format!("no item [{}] in an array of {}", index, count)))
+        }
+        other => Err(at.error(&format!("expected an array, found {}", other.kind()))),
+    }
+}
+
+/// Makes `overrides` in order, saying which one can't be made.
+pub fn apply_all(root: &mut Value, overrides: &[Override]) -> Result<(), String> {
+    for o in overrides {
+        o.apply(root)
+            .map_err(|message| format!("can't set {}: {}", o, message))?;
+    }
+    Ok(())
+}
+
+/// Says which of `overrides` is to blame for `message`, an error from
+/// building a scene after they were made, if it's about the value one of
+/// them set, something inside it, or the member it added.
+pub fn blame(message: String, overrides: &[Override]) -> String {
+    let culprit = overrides.iter().rev().find(|o| {
+        let path = Location(&o.path).to_string();
+        let within = message
+            .strip_prefix(&path)
+            .is_some_and(|rest| rest.starts_with([':', '.', '[']));
+        let (last, parents) = o.path.split_last().unwrap();
+        let added = match last {
+            Step::Member(name) => {
+                message == Location(parents).error(&format!("unknown member {:?}", name))
+            }
+            Step::Index(_) => false,
+        };
+        within || added
+    });
+    match culprit {
+        Some(o) => format!("can't set {}: {}", o, message),
+        None => message,
+    }
+}
+
+/// An override that changes over a sequence of frames.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sweep {
+    text: String,
+    path: Vec<Step>,
+    from: Value,
+    to: Value,
+}
+
+fn numbers(value: &Value) -> Option<Vec<f64>> {
+    match value {
+        Value::Number(n) => Some(vec![*n]),
+        Value::Array(items) => items.iter().map(Value::as_f64).collect(),
+        _ => None,
+    }
+}
+
+impl Sweep {
+    pub fn parse(text: &str) -> Result<Sweep, String> {
+        let (path, range) = split(text)?;
+        let (from, to) = range.split_once("..").ok_or("expected path=from..to")?;
+        let (from, to) = (parse_value(from.trim()), parse_value(to.trim()));
+        let same_shape = match (&from, &to) {
+            (Value::Number(_), Value::Number(_)) => true,
+            (Value::Array(a), Value::Array(b)) => a.len() == b.len() && !a.is_empty(),
+            _ => false,
+        };
+        if !(same_shape && numbers(&from).is_some() && numbers(&to).is_some()) {
+            return Err(format!(
+                "{:?} doesn't run between two numbers or two arrays of as many numbers",
+                range
+            ));
+        }
+        Ok(Sweep {
+            text: text.to_string(),
+            path,
+            from,
+            to,
+        })
+    }
+
+    /// The override `t` of the way from the first frame to the last.
+    pub fn at(&self, t: f64) -> Override {
+        let lerp = |a: f64, b: f64| Value::Number(a + t * (b - a));
+        let (from, to) = (numbers(&self.from).unwrap(), numbers(&self.to).unwrap());
+        let value = match self.from {
+            Value::Number(_) => lerp(from[0], to[0]),
+            _ => Value::Array(from.iter().zip(&to).map(|(&a, &b)| lerp(a, b)).collect()),
+        };
+        Override {
+            text: self.text.clone(),
+            path: self.path.clone(),
+            value,
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! Parts of a render split across machines, and merging them into the image
+//! they add up to.
+//!
+//! Each part takes its own samples of every pixel: the same samples of
+//! another seed, or another share of the samples of the same seed. A part
+//! holds the sum of each pixel's samples rather than their mean, with how
+//! many there are, so that merging parts is adding them up. The samples of
+//! a seed are the same wherever they're taken, so parts that share out the
+//! samples of one seed merge into exactly the image one render of them all
+//! makes.
+//!
+//! A part file starts with `RTPART` and the version of the format it's in,
+//! then the fingerprint of the scene and settings, the seed and the samples
+//! of it taken, and the size, all little-endian. The pixels follow row by
+//! row, each as three `f64` sums and a `u32` count.
+
+use std::convert::TryInto;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::ops::Range;
+use std::path::Path;
+
+use crate::error::Error;
+use crate::image::Image;
+use crate::scalar::{self, Scalar};
+use crate::vector::Color;
+
+const MAGIC: &[u8; 6] = b"RTPART";
+/// The version of the format parts are written in, which is the only one
+/// read.
+pub const VERSION: u16 = 1;
+const HEADER: usize = 40;
+const PIXEL: usize = 28;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Part {
+    /// Which scene and settings the samples were taken of, leaving out the
+    /// seed, which parts may differ in.
+    pub fingerprint: u64,
+    pub seed: u64,
+    pub samples: Range<u32>,
+    pub width: u32,
+    pub height: u32,
+    /// The sum of each pixel's samples, row by row, and how many there are.
+    pub sums: Vec<[f64; 3]>,
+    pub counts: Vec<u32>,
+}
+
+fn io<'a>(path: &'a Path, operation: &'static str) -> impl FnOnce(io::Error) -> Error + 'a {
+    move |source| Error::Io {
+        path: path.to_path_buf(),
+        operation,
+        source,
+    }
+}
+
+impl Part {
+    /// The part made of `samples` of every pixel of `sums`, which adds them
+    /// up.
+    pub fn new(fingerprint: u64, seed: u64, samples: Range<u32>, sums: &Image) -> Part {
+        let count = samples.len() as u32;
+        Part {
+            fingerprint,
+            seed,
+            samples,
+            width: sums.width,
+            height: sums.height,
+            sums: sums
+                .pixels
+                .iter()
+                .map(|sum| [sum.x, sum.y, sum.z].map(scalar::to_f64))
+                .collect(),
+            counts: vec![count; sums.pixels.len()],
+        }
+    }
+
+    /// The mean of each pixel's samples, black for a pixel with none.
+    pub fn image(&self) -> Image {
+        let mut image = Image::new(self.width, self.height);
+        for ((pixel, sum), &count) in image.pixels.iter_mut().zip(&self.sums).zip(&self.counts) {
+            if count > 0 {
+                let [r, g, b] = sum.map(|channel| (channel / count as f64) as Scalar);
+                *pixel = Color::new(r, g, b);
+            }
+        }
+        image
+    }
+
+    /// Whether `other` has some of the same samples as this part, which
+    /// would count them twice.
+    pub fn overlaps(&self, other: &Part) -> bool {
+        other.seed == self.seed
+            && other.samples.start < self.samples.end
+            && self.samples.start < other.samples.end
+    }
+
+    /// Adds `other`'s samples to this part's, if it's of the same scene and
+    /// settings.
+    pub fn add(&mut self, other: &Part) -> Result<(), String> {
+        if other.fingerprint != self.fingerprint {
+            return Err("it's of a different scene or settings".to_string());
+        }
+        if (other.width, other.height) != (self.width, self.height) {
+            return Err(format!(
+                "it's {}x{}, not {}x{}",
+                other.width, other.height, self.width, self.height
+            ));
+        }
+        for (sum, more) in self.sums.iter_mut().zip(&other.sums) {
+            for (channel, more) in sum.iter_mut().zip(more) {
+                *channel += more;
+            }
+        }
+        for (count, more) in self.counts.iter_mut().zip(&other.counts) {
+            *count = count
+                .checked_add(*more)
+                .ok_or("too many samples a pixel in all")?;
+        }
+        Ok(())
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), Error> {
+        let file = File::create(path).map_err(io(path, "create"))?;
+        let mut out = BufWriter::new(file);
+        let mut header = Vec::with_capacity(HEADER);
+        header.extend_from_slice(MAGIC);
+        header.extend_from_slice(&VERSION.to_le_bytes());
+        header.extend_from_slice(&self.fingerprint.to_le_bytes());
+        header.extend_from_slice(&self.seed.to_le_bytes());
+        for value in [
+            self.samples.start,
+            self.samples.end,
+            self.width,
+            self.height,
+        ] {
+            header.extend_from_slice(&value.to_le_bytes());
+        }
+        out.write_all(&header).map_err(io(path, "write"))?;
+        for (sum, count) in self.sums.iter().zip(&self.counts) {
+            let mut pixel = [0; PIXEL];
+            for (bytes, channel) in pixel.chunks_mut(8).zip(sum) {
+                bytes.copy_from_slice(&channel.to_le_bytes());
+            }
+            pixel[24..].copy_from_slice(&count.to_le_bytes());
+            out.write_all(&pixel).map_err(io(path, "write"))?;
+        }
+        out.flush().map_err(io(path, "write"))
+    }
+
+    pub fn read(path: &Path) -> Result<Part, Error> {
+        let file = File::open(path).map_err(io(path, "open"))?;
+        let mut data = Vec::new();
+        BufReader::new(file)
+            .read_to_end(&mut data)
+            .map_err(io(path, "read"))?;
+        let invalid =
+            |message: &str| io(path, "read")(io::Error::new(io::ErrorKind::InvalidData, message));
+        if data.len() < 8 || &data[..6] != MAGIC {
+            return Err(invalid("not a render part"));
+        }
+        let version = u16::from_le_bytes([data[6], data[7]]);
+        if version != VERSION {
+            return Err(invalid(&format!(
+                "the part is in version {} of the format, and only version {} is supported",
+                version, VERSION
+            )));
+        }
+        if data.len() < HEADER {
+            return Err(invalid("the part is cut short"));
+        }
+        let u32_at = |at: usize| u32::from_le_bytes(data[at..at + 4].try_into().unwrap());
+        let u64_at = |at: usize| u64::from_le_bytes(data[at..at + 8].try_into().unwrap());
+        let (width, height) = (u32_at(32), u32_at(36));
+        let pixels = &data[HEADER..];
+        if pixels.len() as u64 != PIXEL as u64 * width as u64 * height as u64 {
+            return Err(invalid("the part is cut short"));
+        }
+        let pixels = pixels.chunks(PIXEL);
+        Ok(Part {
+            fingerprint: u64_at(8),
+            seed: u64_at(16),
+            samples: u32_at(24)..u32_at(28),
+            width,
+            height,
+            sums: pixels
+                .clone()
+                .map(|pixel| {
+                    let channel =
+                        |at: usize| f64::from_le_bytes(pixel[at..at + 8].try_into().unwrap());
+                    [channel(0), channel(8), channel(16)]
+                })
+                .collect(),
+            counts: pixels
+                .map(|pixel| u32::from_le_bytes(pixel[24..].try_into().unwrap()))
+                .collect(),
+        })
+    }
+}
@@ -0,0 +1,228 @@
+//! Photon mapping for caustics.
+//!
+//! Photons are traced from the lights before rendering; those reaching a
+//! diffuse surface after at least one specular bounce are kept in a
+//! kd-tree, and the radiance they deposit is estimated at render time from
+//! the photons nearest to each shaded point.
+
+use crate::material::Material;
+use crate::ray::{Ray, EPSILON};
+use crate::sampler::Sampler;
+use crate::scalar::{consts::PI, Scalar};
+use crate::scene::Scene;
+use crate::shapes::HitRecord;
+use crate::vector::{Color, Vector};
+
+// Keeps the photon pass's random streams apart from the camera samples'.
+const PHOTON_SEED: u64 = 0x7068_6f74_6f6e;
+
+#[derive(Debug, Copy, Clone)]
+pub struct Photon {
+    pub position: Vector,
+    /// The direction the photon was travelling in when it landed.
+    pub direction: Vector,
+    pub power: Color,
+}
+
+/// Photons in a balanced, implicit kd-tree: each slice's median is its node,
+/// split along the axis stored for it in `axes`.
+pub struct PhotonMap {
+    photons: Vec<Photon>,
+    axes: Vec<u8>,
+    /// How many neighbors the radiance estimate gathers.
+    pub k: usize,
+    /// The largest distance photons are gathered from.
+    pub max_radius: Scalar,
+}
+
+fn coordinate(point: Vector, axis: u8) -> Scalar {
+    match axis {
+        0 => point.x,
+        1 => point.y,
+        _ => point.z,
+    }
+}
+
+fn build(photons: &mut [Photon], axes: &mut [u8]) {
+    if photons.is_empty() {
+        return;
+    }
+    let mut min = photons[0].position;
+    let mut max = min;
+    for photon in photons.iter() {
+        let p = photon.position;
+        min = Vector::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z));
+        max = Vector::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z));
+    }
+    let extent = max - min;
+    let axis = if extent.x >= extent.y && extent.x >= extent.z {
+        0
+    } else if extent.y >= extent.z {
+        1
+    } else {
+        2
+    };
+    let mid = photons.len() / 2;
+    photons.select_nth_unstable_by(mid, |a, b| {
+        coordinate(a.position, axis).total_cmp(&coordinate(b.position, axis))
+    });
+    axes[mid] = axis;
+    let (left, right) = photons.split_at_mut(mid);
+    let (left_axes, right_axes) = axes.split_at_mut(mid);
+    build(left, left_axes);
+    build(&mut right[1..], &mut right_axes[1..]);
+}
+
+impl PhotonMap {
+    pub fn new(mut photons: Vec<Photon>, k: usize, max_radius: Scalar) -> PhotonMap {
+        let mut axes = vec![0; photons.len()];
+        build(&mut photons, &mut axes);
+        PhotonMap {
+            photons,
+            axes,
+            k,
+            max_radius,
+        }
+    }
+
+    /// Traces `count` photons from the scene's lights, following them through
+    /// specular surfaces for up to `max_depth` bounces, and keeps the ones
+    /// that land on a diffuse surface after at least one specular bounce.
+    pub fn caustics(
+        scene: &Scene,
+        count: u32,
+        max_depth: u32,
+        seed: u64,
+        k: usize,
+        max_radius: Scalar,
+    ) -> PhotonMap {
+        let mut photons = Vec::new();
+        let lights = scene.lights.len();
+        for i in 0..count {
+            if lights == 0 {
+                break;
+            }
+            let mut sampler = Sampler::new(seed ^ PHOTON_SEED, i as u64, 0);
+            let index = ((sampler.next_scalar() * lights as Scalar) as usize).min(lights - 1);
+            let emission = match scene.lights[index].emit_photon(&mut sampler) {
+                Some(emission) => emission,
+                None => continue,
+            };
+            let mut ray = emission.ray;
+            let mut power = (lights as Scalar / count as Scalar) * emission.power;
+            let mut specular = false;
+            for _ in 0..max_depth {
+                let hit = match scene.intersect(&ray, EPSILON, Scalar::INFINITY) {
+                    Some(hit) => hit,
+                    None => break,
+                };
+                let material = hit.object.material.as_ref();
+                if !material.is_specular() {
+                    if specular {
+                        photons.push(Photon {
+                            position: hit.record.point,
+                            direction: ray.direction,
+                            power,
+                        });
+                    }
+                    break;
+                }
+                let scatter = match material.scatter(&ray, &hit.record, &mut sampler) {
+                    Some(scatter) => scatter,
+                    None => break,
+                };
+                power = power.component_mul(scatter.attenuation);
+                specular = true;
+                ray = Ray::new(hit.record.point, scatter.direction);
+            }
+        }
+        PhotonMap::new(photons, k, max_radius)
+    }
+
+    pub fn len(&self) -> usize {
+        self.photons.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.photons.is_empty()
+    }
+
+    /// The up to `k` photons closest to `point` within `max_radius`, nearest
+    /// first, with their squared distances.
+    pub fn nearest(&self, point: Vector, k: usize, max_radius: Scalar) -> Vec<(Scalar, &Photon)> {
+        let mut found = Vec::with_capacity(k + 1);
+        self.search(
+            0,
+            self.photons.len(),
+            point,
+            k,
+            max_radius * max_radius,
+            &mut found,
+        );
+        found
+            .into_iter()
+            .map(|(distance, index)| (distance, &self.photons[index]))
+            .collect()
+    }
+
+    fn search(
+        &self,
+        start: usize,
+        end: usize,
+        point: Vector,
+        k: usize,
+        max_squared: Scalar,
+        found: &mut Vec<(Scalar, usize)>,
+    ) {
+        if start >= end || k == 0 {
+            return;
+        }
+        let mid = start + (end - start) / 2;
+        let photon = &self.photons[mid];
+        let axis = self.axes[mid];
+        let offset = coordinate(point, axis) - coordinate(photon.position, axis);
+        let (near, far) = if offset < 0.0 {
+            ((start, mid), (mid + 1, end))
+        } else {
+            ((mid + 1, end), (start, mid))
+        };
+        self.search(near.0, near.1, point, k, max_squared, found);
+
+        let distance = (photon.position - point) * (photon.position - point);
+        let bound = |found: &Vec<(Scalar, usize)>| {
+            if found.len() == k {
+                found[k - 1].0
+            } else {
+                max_squared
+            }
+        };
+        if distance <= bound(found) {
+            let at = found.partition_point(|&(d, _)| d <= distance);
+            found.insert(at, (distance, mid));
+            found.truncate(k);
+        }
+        if offset * offset <= bound(found) {
+            self.search(far.0, far.1, point, k, max_squared, found);
+        }
+    }
+
+    /// Estimates the caustic radiance leaving a diffuse hit toward `wo` from
+    /// the density of nearby photons.
+    pub fn radiance(&self, hit: &HitRecord, material: &dyn Material, wo: Vector) -> Color {
+        let nearest = self.nearest(hit.point, self.k, self.max_radius);
+        if nearest.is_empty() {
+            return Color::zero();
+        }
+        let radius_squared = if nearest.len() == self.k {
+            nearest[nearest.len() - 1].0
+        } else {
+            self.max_radius * self.max_radius
+        };
+        let mut color = Color::zero();
+        for (_, photon) in nearest {
+            let f = material.eval(hit, wo, -photon.direction);
+            color += f.component_mul(photon.power);
+        }
+        (1.0 / (PI * radius_squared)) * color
+    }
+}
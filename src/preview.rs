@@ -0,0 +1,199 @@
+//! A window showing a render as it happens, for `--preview`, and for
+//! moving the camera around a scene with `--interactive`.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, ScaleMode, Window, WindowOptions};
+
+use crate::camera::Camera;
+use crate::error::Error;
+use crate::image::Image;
+use crate::render::{render_watched, RenderSettings};
+use crate::scalar::Scalar;
+use crate::scene::Scene;
+use crate::scene_file;
+use crate::vector::Vector;
+
+/// How long the camera has to stay still before it's rendered at full
+/// resolution again.
+const SETTLE: Duration = Duration::from_millis(300);
+
+pub struct Preview {
+    window: Window,
+    buffer: Vec<u32>,
+}
+
+impl Preview {
+    /// Opens a window the size of the image. It can be resized, in which case
+    /// the image is scaled to fit with nearest-neighbor sampling; the render
+    /// stays the same size.
+    pub fn open(width: u32, height: u32) -> Result<Preview, Error> {
+        let options = WindowOptions {
+            resize: true,
+            scale_mode: ScaleMode::AspectRatioStretch,
+            ..WindowOptions::default()
+        };
+        let window = Window::new("basic-raytracer", width as usize, height as usize, options)
+            .map_err(|e| Error::Preview(e.to_string()))?;
+        Ok(Preview {
+            window,
+            buffer: Vec::new(),
+        })
+    }
+
+    /// Shows `image`, returning false once the window has been closed or
+    /// Escape pressed.
+    pub fn show(&mut self, image: &Image) -> bool {
+        if !self.is_open() {
+            return false;
+        }
+        self.buffer.clear();
+        self.buffer.extend(
+            image
+                .to_rgba8()
+                .chunks_exact(4)
+                .map(|p| (p[0] as u32) << 16 | (p[1] as u32) << 8 | p[2] as u32),
+        );
+        let shown = self.window.update_with_buffer(
+            &self.buffer,
+            image.width as usize,
+            image.height as usize,
+        );
+        shown.is_ok() && self.is_open()
+    }
+
+    /// Keeps the last image up until the window is closed.
+    pub fn wait(&mut self) {
+        while self.pump() {
+            thread::sleep(Duration::from_millis(30));
+        }
+    }
+
+    /// Keeps the window responding while nothing new is shown in it, and
+    /// says whether it's still open.
+    pub fn pump(&mut self) -> bool {
+        self.window.update();
+        self.is_open()
+    }
+
+    fn is_open(&self) -> bool {
+        self.window.is_open() && !self.window.is_key_down(Key::Escape)
+    }
+}
+
+/// Turns what's held down in the window into camera moves.
+struct Controls {
+    /// Where the mouse was while the left button is held.
+    mouse: Option<(f32, f32)>,
+    last: Instant,
+}
+
+impl Controls {
+    /// `camera` after whatever moves were made since the last call, or
+    /// `None` if it didn't move. Ctrl+S prints the camera.
+    fn steer(&mut self, window: &Window, camera: &Camera) -> Option<Camera> {
+        // Moves are by time, not by call, so the speed doesn't depend on how
+        // often the window is looked at.
+        let seconds = self.last.elapsed().as_secs_f64().min(0.1);
+        self.last = Instant::now();
+        let ctrl = window.is_key_down(Key::LeftCtrl) || window.is_key_down(Key::RightCtrl);
+        if ctrl && window.is_key_pressed(Key::S, KeyRepeat::No) {
+            println!("\"camera\": {}", scene_file::camera_value(camera));
+        }
+        let mut moved = *camera;
+
+        // A second to move as far as the target is.
+        let step = seconds * (camera.look_at - camera.position).len();
+        let mut offset = Vector::zero();
+        for key in window.get_keys() {
+            match key {
+                _ if ctrl => {}
+                Key::W | Key::Up => offset.z += step,
+                Key::S | Key::Down => offset.z -= step,
+                Key::D | Key::Right => offset.x += step,
+                Key::A | Key::Left => offset.x -= step,
+                Key::E => offset.y += step,
+                Key::Q => offset.y -= step,
+                _ => {}
+            }
+        }
+        if offset != Vector::zero() {
+            moved = moved.translated(offset);
+        }
+
+        let mouse = window.get_mouse_pos(MouseMode::Pass);
+        if window.get_mouse_down(MouseButton::Left) {
+            if let (Some((x0, y0)), Some((x, y))) = (self.mouse, mouse) {
+                // Dragging across the window turns the camera once around.
+                let turn = 2.0 * crate::scalar::consts::PI / window.get_size().0.max(1) as Scalar;
+                moved = moved.orbited(-(x - x0) as Scalar * turn, (y - y0) as Scalar * turn);
+            }
+            self.mouse = mouse;
+        } else {
+            self.mouse = None;
+        }
+
+        if let Some((_, scroll)) = window.get_scroll_wheel() {
+            moved = moved.dollied(Scalar::powf(0.9, scroll as Scalar));
+        }
+        (moved.position != camera.position || moved.look_at != camera.look_at).then_some(moved)
+    }
+}
+
+/// Renders `scene` in a window over and over as the camera is moved: W, A,
+/// S and D or the arrow keys move it, Q and E move it down and up, dragging
+/// with the left mouse button orbits its target and scrolling moves it
+/// closer or farther. While it moves, the scene renders at `1 / scale` of
+/// the resolution. Ctrl+S prints the camera in scene-file form.
+pub fn explore(scene: &mut Scene, settings: &RenderSettings, scale: u32) -> Result<(), Error> {
+    let mut preview = Preview::open(settings.width, settings.height)?;
+    let reduced = RenderSettings {
+        width: (settings.width / scale).max(1),
+        height: (settings.height / scale).max(1),
+        ..settings.clone()
+    };
+    let mut controls = Controls {
+        mouse: None,
+        last: Instant::now(),
+    };
+    let mut moved = Instant::now() - SETTLE;
+    while preview.is_open() {
+        let full = moved.elapsed() >= SETTLE;
+        let mut steered: Option<Camera> = None;
+        let rendered = render_watched(scene, if full { settings } else { &reduced }, |image| {
+            if !preview.show(image) {
+                return false;
+            }
+            let camera = steered.unwrap_or(scene.camera);
+            if let Some(camera) = controls.steer(&preview.window, &camera) {
+                steered = Some(camera);
+            }
+            // Small renders are quick enough to finish before moving on.
+            steered.is_none() || !full
+        });
+        match rendered {
+            Ok(_) | Err(Error::Cancelled) => {}
+            Err(error) => return Err(error),
+        }
+        if let Some(camera) = steered {
+            scene.camera = camera;
+            moved = Instant::now();
+            continue;
+        }
+        // Wait for a move, or for the camera to settle.
+        while preview.is_open() {
+            if let Some(camera) = controls.steer(&preview.window, &scene.camera) {
+                scene.camera = camera;
+                moved = Instant::now();
+                break;
+            }
+            if !full && moved.elapsed() >= SETTLE {
+                break;
+            }
+            preview.window.update();
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,557 @@
+//! A Python module, `basic_raytracer`, for building scenes and rendering
+//! them from scripts, with the `python` feature. Build it to import with the
+//! `extension-module` feature, which leaves libpython for the interpreter
+//! loading the module to provide:
+//!
+//! ```text
+//! cargo build --release --features extension-module
+//! cp target/release/libbasic_raytracer.so examples/python/basic_raytracer.so
+//! ```
+//!
+//! The classes hold plain values, checked as they're made, and a scene is
+//! built in the renderer afresh for each render, so it can be changed in
+//! between. Renders let go of the GIL, so other Python threads carry on
+//! while one runs.
+
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyList};
+
+use crate::camera::Camera as RtCamera;
+use crate::error::Error;
+use crate::image::encode_srgb;
+use crate::light::PointLight as RtPointLight;
+use crate::material::{Dielectric, Emissive, Glossy, Lambertian, Material};
+use crate::render::{render_streamed, IntegratorKind, RenderSettings};
+use crate::scalar::{to_f64, Scalar};
+use crate::scene::Scene as RtScene;
+use crate::shapes::{Plane as RtPlane, Sphere as RtSphere};
+use crate::vector::Vector;
+
+create_exception!(
+    basic_raytracer,
+    RenderError,
+    PyException,
+    "A render failed; the message is the renderer's own."
+);
+
+const MATERIALS: [&str; 4] = ["lambertian", "glossy", "dielectric", "emissive"];
+const INTEGRATORS: [(&str, IntegratorKind); 3] = [
+    ("whitted", IntegratorKind::Whitted),
+    ("path", IntegratorKind::Path),
+    ("ambient_occlusion", IntegratorKind::AmbientOcclusion),
+];
+
+/// The vector in `value`, any sequence of three numbers, such as a tuple or
+/// a list. `name` is the argument it was passed as, for the error.
+pub fn vector(value: &Bound<'_, PyAny>, name: &str) -> PyResult<Vector> {
+    let mistake = || {
+        let repr = value
+            .repr()
+            .map_or_else(|_| "?".to_string(), |r| r.to_string());
+        PyTypeError::new_err(format!("{} must be three numbers, not {}", name, repr))
+    };
+    let components = value
+        .try_iter()
+        .map_err(|_| mistake())?
+        .map(|component| component.and_then(|c| c.extract::<f64>()))
+        .collect::<PyResult<Vec<f64>>>()
+        .map_err(|_| mistake())?;
+    match components[..] {
+        [x, y, z] => Ok(Vector::new(x as Scalar, y as Scalar, z as Scalar)),
+        _ => Err(mistake()),
+    }
+}
+
+/// `vector` as the tuple Python sees.
+pub fn tuple(vector: Vector) -> (f64, f64, f64) {
+    (to_f64(vector.x), to_f64(vector.y), to_f64(vector.z))
+}
+
+/// `x` as Python's `%g` writes it: six significant digits, with no zeros
+/// after the last of them.
+fn general(x: f64) -> String {
+    if !x.is_finite() {
+        return match x {
+            x if x.is_nan() => "nan".to_string(),
+            x if x > 0.0 => "inf".to_string(),
+            _ => "-inf".to_string(),
+        };
+    }
+    let trim = |digits: &str| match digits.contains('.') {
+        true => digits
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string(),
+        false => digits.to_string(),
+    };
+    let scientific = format!("{:.5e}", x);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    if (-4..6).contains(&exponent) {
+        trim(&format!("{:.*}", (5 - exponent) as usize, x))
+    } else {
+        let sign = if exponent < 0 { '-' } else { '+' };
+        format!("{}e{}{:02}", trim(mantissa), sign, exponent.abs())
+    }
+}
+
+fn format(vector: (f64, f64, f64)) -> String {
+    let (x, y, z) = vector;
+    format!("({}, {}, {})", general(x), general(y), general(z))
+}
+
+/// A pinhole camera at `position` looking at `look_at`, with a horizontal
+/// field of view of `fov` degrees.
+#[pyclass(module = "basic_raytracer", frozen)]
+#[derive(Clone)]
+pub struct Camera {
+    #[pyo3(get)]
+    position: (f64, f64, f64),
+    #[pyo3(get)]
+    look_at: (f64, f64, f64),
+    #[pyo3(get)]
+    fov: f64,
+}
+
+#[pymethods]
+impl Camera {
+    #[new]
+    #[pyo3(signature = (position = None, look_at = None, fov = 45.0))]
+    fn new(
+        position: Option<&Bound<'_, PyAny>>,
+        look_at: Option<&Bound<'_, PyAny>>,
+        fov: f64,
+    ) -> PyResult<Camera> {
+        let or = |value: Option<&Bound<'_, PyAny>>, name, default| match value {
+            Some(value) => vector(value, name).map(tuple),
+            None => Ok(default),
+        };
+        Ok(Camera {
+            position: or(position, "position", (0.0, 0.0, 0.0))?,
+            look_at: or(look_at, "look_at", (0.0, 0.0, -1.0))?,
+            fov,
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Camera(position={}, look_at={}, fov={})",
+            format(self.position),
+            format(self.look_at),
+            general(self.fov)
+        )
+    }
+}
+
+/// What an object is made of: `material` is one of "lambertian", "glossy"
+/// (with `exponent`), "dielectric" (with `ior`) or "emissive", whose
+/// `color` is the light it gives off.
+#[derive(Clone)]
+struct Surface {
+    color: (f64, f64, f64),
+    material: String,
+    exponent: f64,
+    ior: f64,
+}
+
+impl Surface {
+    fn new(
+        color: Option<&Bound<'_, PyAny>>,
+        material: &str,
+        exponent: f64,
+        ior: f64,
+    ) -> PyResult<Surface> {
+        if !MATERIALS.contains(&material) {
+            return Err(PyValueError::new_err(format!(
+                "material must be one of {}, not '{}'",
+                MATERIALS.join(", "),
+                material
+            )));
+        }
+        Ok(Surface {
+            color: match color {
+                Some(color) => tuple(vector(color, "color")?),
+                None => (0.8, 0.8, 0.8),
+            },
+            material: material.to_string(),
+            exponent,
+            ior,
+        })
+    }
+
+    fn material(&self) -> Arc<dyn Material> {
+        let (r, g, b) = self.color;
+        let color = Vector::new(r as Scalar, g as Scalar, b as Scalar);
+        match self.material.as_str() {
+            "glossy" => Arc::new(Glossy::new(color, self.exponent as Scalar)),
+            "dielectric" => Arc::new(Dielectric::new(self.ior as Scalar)),
+            "emissive" => Arc::new(Emissive::new(color)),
+            _ => Arc::new(Lambertian::new(color)),
+        }
+    }
+
+    fn repr(&self) -> String {
+        match self.material.as_str() {
+            "dielectric" => format!("material='dielectric', ior={}", general(self.ior)),
+            material => {
+                let exponent = match material {
+                    "glossy" => format!(", exponent={}", general(self.exponent)),
+                    _ => String::new(),
+                };
+                format!(
+                    "color={}, material='{}'{}",
+                    format(self.color),
+                    material,
+                    exponent
+                )
+            }
+        }
+    }
+}
+
+fn point(value: (f64, f64, f64)) -> Vector {
+    Vector::new(value.0 as Scalar, value.1 as Scalar, value.2 as Scalar)
+}
+
+#[pyclass(module = "basic_raytracer", frozen)]
+#[derive(Clone)]
+pub struct Sphere {
+    #[pyo3(get)]
+    center: (f64, f64, f64),
+    #[pyo3(get)]
+    radius: f64,
+    surface: Surface,
+}
+
+#[pymethods]
+impl Sphere {
+    #[new]
+    #[pyo3(signature = (center = None, radius = 1.0, *, color = None, material = "lambertian", exponent = 32.0, ior = 1.5))]
+    fn new(
+        center: Option<&Bound<'_, PyAny>>,
+        radius: f64,
+        color: Option<&Bound<'_, PyAny>>,
+        material: &str,
+        exponent: f64,
+        ior: f64,
+    ) -> PyResult<Sphere> {
+        Ok(Sphere {
+            center: match center {
+                Some(center) => tuple(vector(center, "center")?),
+                None => (0.0, 0.0, 0.0),
+            },
+            radius,
+            surface: Surface::new(color, material, exponent, ior)?,
+        })
+    }
+
+    #[getter]
+    fn color(&self) -> (f64, f64, f64) {
+        self.surface.color
+    }
+
+    #[getter]
+    fn material(&self) -> &str {
+        &self.surface.material
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Sphere(center={}, radius={}, {})",
+            format(self.center),
+            general(self.radius),
+            self.surface.repr()
+        )
+    }
+}
+
+/// The infinite plane through `point` facing `normal`.
+#[pyclass(module = "basic_raytracer", frozen)]
+#[derive(Clone)]
+pub struct Plane {
+    #[pyo3(get)]
+    point: (f64, f64, f64),
+    #[pyo3(get)]
+    normal: (f64, f64, f64),
+    surface: Surface,
+}
+
+#[pymethods]
+impl Plane {
+    #[new]
+    #[pyo3(signature = (point = None, normal = None, *, color = None, material = "lambertian", exponent = 32.0, ior = 1.5))]
+    fn new(
+        point: Option<&Bound<'_, PyAny>>,
+        normal: Option<&Bound<'_, PyAny>>,
+        color: Option<&Bound<'_, PyAny>>,
+        material: &str,
+        exponent: f64,
+        ior: f64,
+    ) -> PyResult<Plane> {
+        let or = |value: Option<&Bound<'_, PyAny>>, name, default| match value {
+            Some(value) => vector(value, name).map(tuple),
+            None => Ok(default),
+        };
+        Ok(Plane {
+            point: or(point, "point", (0.0, 0.0, 0.0))?,
+            normal: or(normal, "normal", (0.0, 1.0, 0.0))?,
+            surface: Surface::new(color, material, exponent, ior)?,
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Plane(point={}, normal={}, {})",
+            format(self.point),
+            format(self.normal),
+            self.surface.repr()
+        )
+    }
+}
+
+#[pyclass(module = "basic_raytracer", frozen)]
+#[derive(Clone)]
+pub struct PointLight {
+    #[pyo3(get)]
+    position: (f64, f64, f64),
+    #[pyo3(get)]
+    color: (f64, f64, f64),
+    #[pyo3(get)]
+    intensity: f64,
+}
+
+#[pymethods]
+impl PointLight {
+    #[new]
+    #[pyo3(signature = (position = None, *, color = None, intensity = 1.0))]
+    fn new(
+        position: Option<&Bound<'_, PyAny>>,
+        color: Option<&Bound<'_, PyAny>>,
+        intensity: f64,
+    ) -> PyResult<PointLight> {
+        let or = |value: Option<&Bound<'_, PyAny>>, name, default| match value {
+            Some(value) => vector(value, name).map(tuple),
+            None => Ok(default),
+        };
+        Ok(PointLight {
+            position: or(position, "position", (0.0, 0.0, 0.0))?,
+            color: or(color, "color", (1.0, 1.0, 1.0))?,
+            intensity,
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "PointLight(position={}, color={}, intensity={})",
+            format(self.position),
+            format(self.color),
+            general(self.intensity)
+        )
+    }
+}
+
+/// Objects and lights seen through a camera.
+#[pyclass(module = "basic_raytracer")]
+pub struct Scene {
+    #[pyo3(get, set)]
+    camera: Py<Camera>,
+    #[pyo3(get)]
+    background: (f64, f64, f64),
+    /// The spheres and planes, in the order they were added.
+    #[pyo3(get)]
+    objects: Py<PyList>,
+    #[pyo3(get)]
+    lights: Py<PyList>,
+}
+
+#[pymethods]
+impl Scene {
+    #[new]
+    #[pyo3(signature = (camera = None, *, background = None))]
+    fn new(
+        py: Python<'_>,
+        camera: Option<Py<Camera>>,
+        background: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<Scene> {
+        let camera = match camera {
+            Some(camera) => camera,
+            None => Py::new(py, Camera::new(None, None, 45.0)?)?,
+        };
+        Ok(Scene {
+            camera,
+            background: match background {
+                Some(background) => tuple(vector(background, "background")?),
+                None => (0.0, 0.0, 0.0),
+            },
+            objects: PyList::empty(py).unbind(),
+            lights: PyList::empty(py).unbind(),
+        })
+    }
+
+    /// Adds a `Sphere`, `Plane` or `PointLight`, and returns it.
+    fn add<'py>(&self, thing: Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+        let py = thing.py();
+        if thing.is_instance_of::<PointLight>() {
+            self.lights.bind(py).append(&thing)?;
+        } else if thing.is_instance_of::<Sphere>() || thing.is_instance_of::<Plane>() {
+            self.objects.bind(py).append(&thing)?;
+        } else {
+            return Err(PyTypeError::new_err(format!(
+                "can't add {} to a scene",
+                thing.repr()?
+            )));
+        }
+        Ok(thing)
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        Ok(format!(
+            "<Scene with {} objects and {} lights, seen by {}>",
+            self.objects.bind(py).len(),
+            self.lights.bind(py).len(),
+            self.camera.bind(py).repr()?
+        ))
+    }
+}
+
+impl Scene {
+    /// The scene in the renderer.
+    fn build(&self, py: Python<'_>) -> PyResult<RtScene> {
+        let camera = self.camera.get();
+        let mut scene = RtScene::new(RtCamera::new(
+            point(camera.position),
+            point(camera.look_at),
+            camera.fov as Scalar,
+        ));
+        scene.background = point(self.background);
+        for object in self.objects.bind(py).iter() {
+            if let Ok(sphere) = object.downcast::<Sphere>() {
+                let sphere = sphere.get();
+                let shape = RtSphere::new(point(sphere.center), sphere.radius as Scalar);
+                scene.add(shape, sphere.surface.material());
+            } else if let Ok(plane) = object.downcast::<Plane>() {
+                let plane = plane.get();
+                let shape = RtPlane::new(point(plane.point), point(plane.normal));
+                scene.add(shape, plane.surface.material());
+            }
+        }
+        for light in self.lights.bind(py).iter() {
+            let light = light.downcast::<PointLight>()?.get();
+            scene.add_light(RtPointLight::new(
+                point(light.position),
+                point(light.color),
+                light.intensity as Scalar,
+            ));
+        }
+        Ok(scene)
+    }
+}
+
+fn integrator(name: &str) -> PyResult<IntegratorKind> {
+    match INTEGRATORS.iter().find(|(known, _)| *known == name) {
+        Some(&(_, kind)) => Ok(kind),
+        None => {
+            let names: Vec<&str> = INTEGRATORS.iter().map(|(name, _)| *name).collect();
+            Err(PyValueError::new_err(format!(
+                "integrator must be one of {}, not '{}'",
+                names.join(", "),
+                name
+            )))
+        }
+    }
+}
+
+/// Renders `scene` to `width * height * 4` bytes of RGBA, 8-bit sRGB with an
+/// opaque alpha, row by row from the top, as numpy's `frombuffer` takes
+/// them. It also takes, by keyword, `max_depth`, `seed` and `integrator`,
+/// which is "whitted", "path" or "ambient_occlusion".
+///
+/// If `progress` is given it's called with the rows done and the rows in all
+/// after each row; returning True from it cancels the render with a
+/// `RenderError`, and an exception raised in it is raised from here once the
+/// render has stopped.
+#[pyfunction]
+#[pyo3(signature = (scene, width = 640, height = 480, spp = 1, **options))]
+fn render<'py>(
+    py: Python<'py>,
+    scene: PyRef<'py, Scene>,
+    width: u32,
+    height: u32,
+    spp: u32,
+    options: Option<&Bound<'py, PyDict>>,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let mut settings = RenderSettings {
+        width,
+        height,
+        spp,
+        ..RenderSettings::default()
+    };
+    let mut progress: Option<PyObject> = None;
+    for (key, value) in options.into_iter().flatten() {
+        match key.extract::<String>()?.as_str() {
+            "max_depth" => settings.max_depth = value.extract()?,
+            "seed" => settings.seed = value.extract()?,
+            "integrator" => settings.integrator = integrator(&value.extract::<String>()?)?,
+            "progress" if value.is_none() => progress = None,
+            "progress" => progress = Some(value.unbind()),
+            key => {
+                return Err(PyTypeError::new_err(format!(
+                    "render() got an unexpected keyword argument '{}'",
+                    key
+                )))
+            }
+        }
+    }
+    let built = scene.build(py)?;
+    let mut pixels = Vec::with_capacity(4 * width as usize * height as usize);
+    let mut raised = None;
+    let stop = AtomicBool::new(false);
+    let streamed = py.allow_threads(|| {
+        let mut done = 0;
+        render_streamed(&built, &settings, &stop, |row| {
+            for color in row {
+                let channels = [color.x, color.y, color.z].map(encode_srgb);
+                pixels.extend_from_slice(&channels);
+                pixels.push(255);
+            }
+            done += 1;
+            let progress = match &progress {
+                Some(progress) => progress,
+                None => return Ok(()),
+            };
+            Python::with_gil(|py| match progress.call1(py, (done, height)) {
+                Ok(cancel) if !cancel.is_truthy(py).unwrap_or(false) => Ok(()),
+                Ok(_) => Err(Error::Cancelled),
+                Err(error) => {
+                    raised = Some(error);
+                    Err(Error::Cancelled)
+                }
+            })
+        })
+    });
+    if let Some(error) = raised {
+        return Err(error);
+    }
+    match streamed {
+        Ok(_) => Ok(PyBytes::new(py, &pixels)),
+        Err(Error::Cancelled) => Err(RenderError::new_err("the render was cancelled")),
+        Err(error) => Err(RenderError::new_err(error.to_string())),
+    }
+}
+
+#[pymodule]
+pub fn basic_raytracer(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<Camera>()?;
+    module.add_class::<Plane>()?;
+    module.add_class::<PointLight>()?;
+    module.add_class::<Scene>()?;
+    module.add_class::<Sphere>()?;
+    module.add("RenderError", module.py().get_type::<RenderError>())?;
+    module.add_function(wrap_pyfunction!(render, module)?)?;
+    Ok(())
+}
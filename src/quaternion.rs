@@ -0,0 +1,177 @@
+use std::ops;
+
+use crate::matrix::Matrix4;
+use crate::scalar::Scalar;
+use crate::vector::Vector;
+
+/// A quaternion `w + xi + yj + zk`; the unit ones represent rotations.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Quaternion {
+    pub x: Scalar,
+    pub y: Scalar,
+    pub z: Scalar,
+    pub w: Scalar,
+}
+
+impl Quaternion {
+    pub fn new(x: Scalar, y: Scalar, z: Scalar, w: Scalar) -> Quaternion {
+        Quaternion { x, y, z, w }
+    }
+
+    pub fn identity() -> Quaternion {
+        Quaternion::new(0.0, 0.0, 0.0, 1.0)
+    }
+
+    /// A rotation by `angle` radians about `axis`, counterclockwise when
+    /// looking down the axis toward the origin like [`Matrix4::rotate`].
+    pub fn from_axis_angle(axis: Vector, angle: Scalar) -> Quaternion {
+        let (sin, cos) = (0.5 * angle).sin_cos();
+        let axis = sin * axis.normalize();
+        Quaternion::new(axis.x, axis.y, axis.z, cos)
+    }
+
+    /// Rotations by `angles.x`, `angles.y` and `angles.z` radians about the
+    /// x, y and z axes, in that order.
+    pub fn from_euler(angles: Vector) -> Quaternion {
+        Quaternion::from_axis_angle(Vector::new(0.0, 0.0, 1.0), angles.z)
+            * Quaternion::from_axis_angle(Vector::new(0.0, 1.0, 0.0), angles.y)
+            * Quaternion::from_axis_angle(Vector::new(1.0, 0.0, 0.0), angles.x)
+    }
+
+    /// The rotation that the upper 3×3 of a rotation matrix makes.
+    pub fn from_matrix(matrix: &Matrix4) -> Quaternion {
+        let m = &matrix.rows;
+        let trace = m[0][0] + m[1][1] + m[2][2];
+        // Dividing by the largest of the four components keeps it stable.
+        let q = if trace > 0.0 {
+            let s = 2.0 * (1.0 + trace).sqrt();
+            Quaternion::new(
+                (m[2][1] - m[1][2]) / s,
+                (m[0][2] - m[2][0]) / s,
+                (m[1][0] - m[0][1]) / s,
+                0.25 * s,
+            )
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = 2.0 * (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt();
+            Quaternion::new(
+                0.25 * s,
+                (m[0][1] + m[1][0]) / s,
+                (m[0][2] + m[2][0]) / s,
+                (m[2][1] - m[1][2]) / s,
+            )
+        } else if m[1][1] > m[2][2] {
+            let s = 2.0 * (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt();
+            Quaternion::new(
+                (m[0][1] + m[1][0]) / s,
+                0.25 * s,
+                (m[1][2] + m[2][1]) / s,
+                (m[0][2] - m[2][0]) / s,
+            )
+        } else {
+            let s = 2.0 * (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt();
+            Quaternion::new(
+                (m[0][2] + m[2][0]) / s,
+                (m[1][2] + m[2][1]) / s,
+                0.25 * s,
+                (m[1][0] - m[0][1]) / s,
+            )
+        };
+        q.normalize()
+    }
+
+    pub fn dot(&self, other: Quaternion) -> Scalar {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    pub fn len(&self) -> Scalar {
+        self.dot(*self).sqrt()
+    }
+
+    pub fn normalize(&self) -> Quaternion {
+        self.scale(1.0 / self.len())
+    }
+
+    fn scale(&self, factor: Scalar) -> Quaternion {
+        Quaternion::new(
+            factor * self.x,
+            factor * self.y,
+            factor * self.z,
+            factor * self.w,
+        )
+    }
+
+    /// The rotation matrix of a unit quaternion.
+    pub fn to_matrix(&self) -> Matrix4 {
+        let Quaternion { x, y, z, w } = *self;
+        Matrix4::new([
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - w * z),
+                2.0 * (x * z + w * y),
+                0.0,
+            ],
+            [
+                2.0 * (x * y + w * z),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - w * x),
+                0.0,
+            ],
+            [
+                2.0 * (x * z - w * y),
+                2.0 * (y * z + w * x),
+                1.0 - 2.0 * (x * x + y * y),
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Interpolates between unit quaternions at a constant angular speed
+    /// along the shorter way around, from `self` at `t` = 0 to `other` at 1.
+    pub fn slerp(&self, other: Quaternion, t: Scalar) -> Quaternion {
+        // q and -q are the same rotation; taking the one on this side of
+        // the sphere keeps the arc under 180°.
+        let (other, cosine) = match self.dot(other) {
+            d if d < 0.0 => (other.scale(-1.0), -d),
+            d => (other, d),
+        };
+        // Nearly equal rotations leave sin θ too small to divide by, but
+        // there the arc is straight enough to interpolate linearly.
+        if cosine > 0.9995 {
+            return (self.scale(1.0 - t) + other.scale(t)).normalize();
+        }
+        let angle = cosine.acos();
+        let sin = angle.sin();
+        let a = ((1.0 - t) * angle).sin() / sin;
+        let b = (t * angle).sin() / sin;
+        (self.scale(a) + other.scale(b)).normalize()
+    }
+}
+
+impl ops::Add<Quaternion> for Quaternion {
+    type Output = Quaternion;
+
+    fn add(self, other: Quaternion) -> Quaternion {
+        Quaternion::new(
+            self.x + other.x,
+            self.y + other.y,
+            self.z + other.z,
+            self.w + other.w,
+        )
+    }
+}
+
+impl ops::Mul<Quaternion> for Quaternion {
+    type Output = Quaternion;
+
+    /// The Hamilton product, which rotates by `other` first, then `self`.
+    fn mul(self, other: Quaternion) -> Quaternion {
+        let (a, b) = (self, other);
+        Quaternion::new(
+            a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+            a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+            a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w,
+            a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z,
+        )
+    }
+}
@@ -0,0 +1,806 @@
+use std::collections::BTreeMap;
+use std::mem;
+use std::ops::Range;
+#[cfg(feature = "threads")]
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "threads")]
+use std::sync::mpsc::{self, RecvTimeoutError};
+#[cfg(feature = "threads")]
+use std::sync::{Mutex, MutexGuard, PoisonError};
+#[cfg(feature = "threads")]
+use std::thread;
+#[cfg(feature = "threads")]
+use std::time::Duration;
+
+use log::{debug, info};
+
+use crate::error::Error;
+use crate::image::{self, Image};
+use crate::integrator::{AmbientOcclusion, Integrator, PathTracer, Whitted};
+use crate::photon::PhotonMap;
+use crate::ray::{Ray, RayPacket, EPSILON, PACKET_SIZE};
+use crate::sampler::Sampler;
+use crate::scalar::Scalar;
+use crate::scene::{RayKind, Scene};
+use crate::stats::{self, plural, RenderStats, Timer};
+use crate::tile::{self, TileOrder};
+use crate::vector::Color;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum IntegratorKind {
+    Whitted,
+    Path,
+    AmbientOcclusion,
+}
+
+impl IntegratorKind {
+    /// The name `--integrator` takes it by.
+    pub fn name(self) -> &'static str {
+        match self {
+            IntegratorKind::Whitted => "whitted",
+            IntegratorKind::Path => "path",
+            IntegratorKind::AmbientOcclusion => "ao",
+        }
+    }
+}
+
+/// A rectangle of an image's pixels, with `x` and `y` its top left corner.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Crop {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// The most pixels a render can have, as many as an image read from a file.
+pub const MAX_PIXELS: u64 = image::MAX_PIXELS;
+/// The most samples a pixel, which keeps counts of samples in a `u32`.
+pub const MAX_SPP: u32 = 1 << 20;
+/// The most bounces a path, past which a hall of mirrors would take hours a
+/// pixel without showing anything more.
+pub const MAX_DEPTH: u32 = 1 << 10;
+
+#[derive(Debug, Clone)]
+pub struct RenderSettings {
+    pub width: u32,
+    pub height: u32,
+    pub integrator: IntegratorKind,
+    pub spp: u32,
+    pub max_depth: u32,
+    /// Depth after which the path tracer starts terminating paths by
+    /// Russian roulette, or `None` to always trace to `max_depth`.
+    pub russian_roulette: Option<u32>,
+    /// Whether the path tracer samples a light directly at every bounce.
+    pub next_event_estimation: bool,
+    /// Whether light and BSDF samples are combined by multiple importance
+    /// sampling rather than by skipping emission after each light sample.
+    pub multiple_importance_sampling: bool,
+    /// Occlusion rays per primary hit in ambient occlusion mode.
+    pub ao_samples: u32,
+    /// How far away geometry still counts as occluding in ambient occlusion
+    /// mode.
+    pub ao_distance: Scalar,
+    /// Photons traced for the caustic photon map, or 0 to render without one.
+    pub caustic_photons: u32,
+    /// Photons gathered for each caustic radiance estimate.
+    pub photon_k: usize,
+    /// The largest distance caustic photons are gathered from.
+    pub photon_radius: Scalar,
+    /// The brightest indirect contribution a path tracer sample may make, or
+    /// infinity to leave samples unclamped. Anything lower biases the image.
+    pub clamp_indirect: Scalar,
+    pub seed: u64,
+    /// The part of the image to render, or `None` for all of it. Its pixels
+    /// come out exactly as they would in the whole image.
+    pub crop: Option<Crop>,
+    pub tile_order: TileOrder,
+    /// Whether camera rays are traced [`PACKET_SIZE`] at a time. Every ray
+    /// hits what it would alone, so the image is the same either way.
+    pub packets: bool,
+    /// How many threads render tiles, or 0 for one per available core. It
+    /// doesn't change the image.
+    pub threads: usize,
+}
+
+impl Default for RenderSettings {
+    fn default() -> RenderSettings {
+        RenderSettings {
+            width: 640,
+            height: 480,
+            integrator: IntegratorKind::Whitted,
+            spp: 1,
+            max_depth: 8,
+            russian_roulette: Some(3),
+            next_event_estimation: true,
+            multiple_importance_sampling: true,
+            ao_samples: 64,
+            ao_distance: Scalar::INFINITY,
+            caustic_photons: 0,
+            photon_k: 64,
+            photon_radius: 0.1,
+            clamp_indirect: Scalar::INFINITY,
+            seed: 0,
+            crop: None,
+            tile_order: TileOrder::Scanline,
+            packets: true,
+            threads: 0,
+        }
+    }
+}
+
+impl RenderSettings {
+    /// Checks for settings that can't make an image.
+    pub fn validate(&self) -> Result<(), Error> {
+        let invalid = |message: &str| Err(Error::InvalidSettings(message.to_string()));
+        if self.width == 0 || self.height == 0 {
+            return invalid("the image must be at least one pixel wide and high");
+        }
+        if self.width as u64 * self.height as u64 > MAX_PIXELS {
+            return Err(Error::InvalidSettings(format!(
+                "the {}x{} image is more than {} pixels",
+                self.width, self.height, MAX_PIXELS
+            )));
+        }
+        if self.spp == 0 {
+            return invalid("spp must be at least 1");
+        }
+        if self.spp > MAX_SPP {
+            return Err(Error::InvalidSettings(format!(
+                "spp must be at most {}",
+                MAX_SPP
+            )));
+        }
+        if self.max_depth > MAX_DEPTH {
+            return Err(Error::InvalidSettings(format!(
+                "max_depth must be at most {}",
+                MAX_DEPTH
+            )));
+        }
+        if self.clamp_indirect.is_nan() || self.clamp_indirect < 0.0 {
+            return invalid("clamp_indirect must not be negative");
+        }
+        if self.photon_k == 0 {
+            return invalid("photon_k must be at least 1");
+        }
+        if self.ao_samples == 0 {
+            return invalid("ao_samples must be at least 1");
+        }
+        if let Some(crop) = self.crop {
+            let right = crop.x as u64 + crop.width as u64;
+            let bottom = crop.y as u64 + crop.height as u64;
+            if crop.width == 0 || crop.height == 0 {
+                return invalid("the crop must be at least one pixel wide and high");
+            }
+            if right > self.width as u64 || bottom > self.height as u64 {
+                return Err(Error::InvalidSettings(format!(
+                    "the {}x{} crop at {},{} doesn't fit in the {}x{} image",
+                    crop.width, crop.height, crop.x, crop.y, self.width, self.height
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// The pixels that get rendered: the crop, or else the whole image.
+    pub fn window(&self) -> Crop {
+        self.crop.unwrap_or(Crop {
+            x: 0,
+            y: 0,
+            width: self.width,
+            height: self.height,
+        })
+    }
+
+    /// Builds the configured integrator, tracing its caustic photon map
+    /// through `scene` if it uses one.
+    pub fn integrator(&self, scene: &Scene) -> Box<dyn Integrator> {
+        let caustics = || {
+            if self.caustic_photons == 0 {
+                return None;
+            }
+            let timer = Timer::start();
+            let map = PhotonMap::caustics(
+                scene,
+                self.caustic_photons,
+                self.max_depth,
+                self.seed,
+                self.photon_k,
+                self.photon_radius,
+            );
+            info!(
+                "Traced {} caustic photons in {:.2?}",
+                self.caustic_photons,
+                timer.elapsed()
+            );
+            Some(map)
+        };
+        match self.integrator {
+            IntegratorKind::Whitted => Box::new(Whitted {
+                max_depth: self.max_depth,
+                caustics: caustics(),
+            }),
+            IntegratorKind::Path => Box::new(PathTracer {
+                max_depth: self.max_depth,
+                russian_roulette: self.russian_roulette,
+                next_event_estimation: self.next_event_estimation,
+                multiple_importance_sampling: self.multiple_importance_sampling,
+                caustics: caustics(),
+                clamp_indirect: self.clamp_indirect,
+            }),
+            IntegratorKind::AmbientOcclusion => Box::new(AmbientOcclusion {
+                samples: self.ao_samples,
+                max_distance: self.ao_distance,
+            }),
+        }
+    }
+}
+
+/// Says that the settings' window is about to have `samples` rendered.
+fn starting(settings: &RenderSettings, samples: &str) {
+    let window = settings.window();
+    info!(
+        "Rendering {}x{} with {} and the {} integrator",
+        window.width,
+        window.height,
+        samples,
+        settings.integrator.name()
+    );
+}
+
+/// The camera ray through pixel (x, y) for one of its samples, and the
+/// sampler the rest of the sample draws from. A pixel given only one sample
+/// in all takes it through its center.
+fn camera_ray(
+    scene: &Scene,
+    settings: &RenderSettings,
+    x: u32,
+    y: u32,
+    sample: u32,
+) -> (Ray, Sampler) {
+    let aspect = settings.width as Scalar / settings.height as Scalar;
+    let pixel = (x + y * settings.width) as u64;
+    let mut sampler = Sampler::new(settings.seed, pixel, sample as u64);
+    let (dx, dy) = if settings.spp == 1 {
+        (0.5, 0.5)
+    } else {
+        (sampler.next_scalar(), sampler.next_scalar())
+    };
+    let u = (x as Scalar + dx) / settings.width as Scalar;
+    let v = (y as Scalar + dy) / settings.height as Scalar;
+    stats::record_camera_ray();
+    (scene.camera.ray(u, v, aspect), sampler)
+}
+
+/// Adds `samples` to `mean`, the running mean of the samples of pixel (x, y)
+/// before them.
+fn render_pixel(
+    scene: &Scene,
+    settings: &RenderSettings,
+    integrator: &dyn Integrator,
+    x: u32,
+    y: u32,
+    samples: Range<u32>,
+    mut mean: Color,
+) -> Color {
+    for sample in samples {
+        let (ray, mut sampler) = camera_ray(scene, settings, x, y, sample);
+        let color = integrator.radiance(&ray, scene, &mut sampler);
+        mean += (1.0 / (sample + 1) as Scalar) * (color - mean);
+    }
+    mean
+}
+
+/// [`render_pixel`] for each of up to [`PACKET_SIZE`] pixels, with the
+/// running mean of each in `means`, tracing the camera rays of each sample
+/// together.
+fn render_packet(
+    scene: &Scene,
+    settings: &RenderSettings,
+    integrator: &dyn Integrator,
+    pixels: &[(u32, u32)],
+    samples: Range<u32>,
+    means: &mut [Color],
+) {
+    for sample in samples {
+        let (rays, mut samplers): (Vec<Ray>, Vec<Sampler>) = pixels
+            .iter()
+            .map(|&(x, y)| camera_ray(scene, settings, x, y, sample))
+            .unzip();
+        let packet = RayPacket::new(&rays);
+        let hits = scene.trace_packet(&packet, RayKind::Camera, EPSILON, Scalar::INFINITY);
+        let lanes = rays
+            .iter()
+            .zip(hits)
+            .zip(&mut samplers)
+            .zip(means.iter_mut());
+        for (((ray, hit), sampler), mean) in lanes {
+            let color = integrator.radiance_from(ray, hit, scene, sampler);
+            *mean += (1.0 / (sample + 1) as Scalar) * (color - *mean);
+        }
+    }
+}
+
+/// Renders the scene, handing out tiles to one worker per available core.
+pub fn render(scene: &Scene, settings: &RenderSettings) -> Result<(Image, RenderStats), Error> {
+    render_watched(scene, settings, |_| true)
+}
+
+/// Renders the scene like [`render`] into 8-bit sRGB, four bytes to a pixel
+/// with an opaque alpha, row by row: the data of the PNG it would be written
+/// to, without touching any file.
+pub fn render_to_buffer(scene: &Scene, settings: &RenderSettings) -> Result<Vec<u8>, Error> {
+    let (image, _) = render(scene, settings)?;
+    Ok(image.to_rgba8())
+}
+
+/// Renders the scene like [`render`], showing `watch` the image so far
+/// whenever tiles have been finished, and every so often in between. The
+/// tiles not yet rendered are black. Once `watch` returns false the workers
+/// stop after the tiles they're on, and the render fails with
+/// [`Error::Cancelled`].
+///
+/// `watch` is called on the calling thread, so it can drive a window that
+/// has to stay on the thread that opened it.
+pub fn render_watched(
+    scene: &Scene,
+    settings: &RenderSettings,
+    watch: impl FnMut(&Image) -> bool,
+) -> Result<(Image, RenderStats), Error> {
+    render_stoppable(scene, settings, &AtomicBool::new(false), watch)
+}
+
+/// Renders the scene like [`render_watched`] until `stop` is set, when the
+/// workers finish the tiles they're on and take no more. Unlike a cancelled
+/// render, a stopped one still makes an image, with the tiles it didn't get
+/// to left black; its camera rays say how far it got.
+pub fn render_stoppable(
+    scene: &Scene,
+    settings: &RenderSettings,
+    stop: &AtomicBool,
+    watch: impl FnMut(&Image) -> bool,
+) -> Result<(Image, RenderStats), Error> {
+    settings.validate()?;
+    scene.validate()?;
+    starting(
+        settings,
+        &format!("{} a pixel", plural(settings.spp as usize, "sample")),
+    );
+    let timer = Timer::start();
+    let integrator = settings.integrator(scene);
+    let window = settings.window();
+    let black = Image::new(window.width, window.height);
+    let samples = 0..settings.spp;
+    let integrator = integrator.as_ref();
+    let (image, mut stats, _) =
+        render_samples(scene, settings, integrator, samples, &black, stop, watch)?;
+    stats.elapsed = timer.elapsed();
+    Ok((image, stats))
+}
+
+/// Renders the scene in `passes` passes of `settings.spp` samples a pixel,
+/// each one folded into the running mean of those before, so that the image
+/// after the last pass is the one [`render`] makes with all of the samples at
+/// once. `pass` is handed the image after each pass along with how many are
+/// done; `watch` sees it in between, as with [`render_watched`], with the tiles
+/// not yet refined left as they were after the pass before.
+pub fn render_progressive(
+    scene: &Scene,
+    settings: &RenderSettings,
+    passes: u32,
+    pass: impl FnMut(u32, &Image) -> Result<(), Error>,
+    watch: impl FnMut(&Image) -> bool,
+) -> Result<(Image, RenderStats), Error> {
+    let window = settings.window();
+    let black = Image::new(window.width, window.height);
+    let stop = AtomicBool::new(false);
+    render_progressive_from(scene, settings, 0..passes, black, &stop, pass, watch)
+}
+
+/// Carries on a progressive render whose first `passes.start` passes made
+/// `image`, up to `passes.end` passes in all. Once `stop` is set the render
+/// ends as [`render_stoppable`] does, with the tiles of the pass it was on
+/// that got done refined and the rest left as they were; `pass` is only
+/// handed whole passes.
+pub fn render_progressive_from(
+    scene: &Scene,
+    settings: &RenderSettings,
+    passes: Range<u32>,
+    mut image: Image,
+    stop: &AtomicBool,
+    mut pass: impl FnMut(u32, &Image) -> Result<(), Error>,
+    mut watch: impl FnMut(&Image) -> bool,
+) -> Result<(Image, RenderStats), Error> {
+    let (done, passes) = (passes.start, passes.end);
+    if passes == 0 {
+        return Err(Error::InvalidSettings(
+            "passes must be at least 1".to_string(),
+        ));
+    }
+    if done > passes {
+        return Err(Error::InvalidSettings(format!(
+            "{} passes are already done, more than the {} asked for",
+            done, passes
+        )));
+    }
+    let window = settings.window();
+    if (image.width, image.height) != (window.width, window.height) {
+        return Err(Error::InvalidSettings(format!(
+            "the image so far is {}x{}, not {}x{}",
+            image.width, image.height, window.width, window.height
+        )));
+    }
+    // The sampler is seeded by sample index, so pass `n` takes the samples
+    // after those of the passes before it.
+    let spp = settings.spp;
+    let all = RenderSettings {
+        spp: spp
+            .checked_mul(passes)
+            .ok_or_else(|| Error::InvalidSettings("too many samples a pixel in all".to_string()))?,
+        ..settings.clone()
+    };
+    all.validate()?;
+    scene.validate()?;
+    starting(
+        settings,
+        &format!(
+            "passes {} to {} of {} a pixel",
+            done + 1,
+            passes,
+            plural(spp as usize, "sample")
+        ),
+    );
+    let timer = Timer::start();
+    let integrator = all.integrator(scene);
+    let mut stats = RenderStats::default();
+    let integrator = integrator.as_ref();
+    for done in done + 1..=passes {
+        let samples = (done - 1) * spp..done * spp;
+        let (refined, pass_stats, whole) =
+            render_samples(scene, &all, integrator, samples, &image, stop, &mut watch)?;
+        image = refined;
+        stats += pass_stats;
+        if !whole {
+            break;
+        }
+        debug!("Pass {} of {} done", done, passes);
+        pass(done, &image)?;
+    }
+    stats.elapsed = timer.elapsed();
+    Ok((image, stats))
+}
+
+/// Takes only `samples` of every pixel of the `settings.spp` a whole render
+/// would, for one part of a render shared out, and sums them rather than
+/// averaging them. A part stopped before every tile is done is cancelled,
+/// since the tiles it didn't get to would count as black.
+pub fn render_part(
+    scene: &Scene,
+    settings: &RenderSettings,
+    samples: Range<u32>,
+    stop: &AtomicBool,
+    watch: impl FnMut(&Image) -> bool,
+) -> Result<(Image, RenderStats), Error> {
+    settings.validate()?;
+    if samples.is_empty() || samples.end > settings.spp {
+        return Err(Error::InvalidSettings(format!(
+            "samples {}..{} aren't among the {} a pixel",
+            samples.start, samples.end, settings.spp
+        )));
+    }
+    scene.validate()?;
+    starting(
+        settings,
+        &format!(
+            "samples {} to {} of the {} a pixel",
+            samples.start + 1,
+            samples.end,
+            settings.spp
+        ),
+    );
+    let timer = Timer::start();
+    let integrator = settings.integrator(scene);
+    let window = settings.window();
+    let black = Image::new(window.width, window.height);
+    let (mut image, mut stats, whole) = render_samples(
+        scene,
+        settings,
+        integrator.as_ref(),
+        samples.clone(),
+        &black,
+        stop,
+        watch,
+    )?;
+    if !whole {
+        return Err(Error::Cancelled);
+    }
+    // Starting from black, the running means are as though the samples
+    // before these were black too, so they're the sums over all of them.
+    let all = samples.end as Scalar;
+    for pixel in &mut image.pixels {
+        *pixel = all * *pixel;
+    }
+    stats.elapsed = timer.elapsed();
+    Ok((image, stats))
+}
+
+/// Adds `samples` to every pixel of `previous`, and says whether every tile
+/// got them before `stop` was set.
+fn render_samples(
+    scene: &Scene,
+    settings: &RenderSettings,
+    integrator: &dyn Integrator,
+    samples: Range<u32>,
+    previous: &Image,
+    stop: &AtomicBool,
+    mut watch: impl FnMut(&Image) -> bool,
+) -> Result<(Image, RenderStats, bool), Error> {
+    let mut image = previous.clone();
+    let mut pixels = 0;
+    let stats = render_tiles(
+        scene,
+        settings,
+        integrator,
+        samples,
+        Some(previous),
+        stop,
+        |arrived| {
+            for (tile, colors) in arrived {
+                let tile_pixels =
+                    (0..tile.height).flat_map(|y| (0..tile.width).map(move |x| (x, y)));
+                for ((x, y), color) in tile_pixels.zip(colors) {
+                    image.set(tile.x + x, tile.y + y, color);
+                }
+                pixels += tile.width * tile.height;
+            }
+            watch(&image)
+        },
+    )?;
+    let window = settings.window();
+    Ok((image, stats, pixels == window.width * window.height))
+}
+
+/// Renders the scene like [`render_stoppable`] without ever holding the whole
+/// image: `row` is handed each row of the window in turn, top to bottom, as
+/// soon as every tile across it and those above it are done, and only the
+/// bands of tiles finished out of order are kept until then. A stopped render
+/// still hands over every row, with what it didn't get to black.
+///
+/// Also says how many bytes the bands waiting their turn came to at most,
+/// which the tile order can make many more than the workers are on at once.
+pub fn render_streamed(
+    scene: &Scene,
+    settings: &RenderSettings,
+    stop: &AtomicBool,
+    mut row: impl FnMut(&[Color]) -> Result<(), Error>,
+) -> Result<(RenderStats, usize), Error> {
+    settings.validate()?;
+    scene.validate()?;
+    starting(
+        settings,
+        &format!(
+            "{} a pixel, a row at a time",
+            plural(settings.spp as usize, "sample")
+        ),
+    );
+    let timer = Timer::start();
+    let integrator = settings.integrator(scene);
+    let integrator = integrator.as_ref();
+    let window = settings.window();
+    let width = window.width as usize;
+    // Each band of tiles by the row it starts on, with its pixels so far and
+    // how many of them are done.
+    let mut waiting: BTreeMap<u32, (Vec<Color>, u32)> = BTreeMap::new();
+    let (mut next, mut most) = (0, 0);
+    let mut failed = None;
+    let samples = 0..settings.spp;
+    let rendered = render_tiles(
+        scene,
+        settings,
+        integrator,
+        samples,
+        None,
+        stop,
+        |arrived| {
+            for (tile, colors) in arrived {
+                let band_height = tile.height as usize;
+                let (band, done) = waiting
+                    .entry(tile.y)
+                    .or_insert_with(|| (vec![Color::zero(); band_height * width], 0));
+                for (y, tile_row) in colors.chunks(tile.width as usize).enumerate() {
+                    let at = y * width + tile.x as usize;
+                    band[at..at + tile_row.len()].copy_from_slice(tile_row);
+                }
+                *done += tile.width * tile.height;
+            }
+            most = most.max(waiting.values().map(|(band, _)| band.len()).sum());
+            while let Some((band, done)) = waiting.get(&next) {
+                if *done as usize != band.len() {
+                    break;
+                }
+                let (band, _) = waiting.remove(&next).unwrap_or_default();
+                for band_row in band.chunks(width) {
+                    if let Err(error) = row(band_row) {
+                        failed = Some(error);
+                        return false;
+                    }
+                    next += 1;
+                }
+            }
+            true
+        },
+    );
+    if let Some(error) = failed {
+        return Err(error);
+    }
+    let mut stats = rendered?;
+    let black = vec![Color::zero(); width];
+    while next < window.height {
+        match waiting.remove(&next) {
+            Some((band, _)) => {
+                for band_row in band.chunks(width) {
+                    row(band_row)?;
+                    next += 1;
+                }
+            }
+            None => {
+                row(&black)?;
+                next += 1;
+            }
+        }
+    }
+    stats.elapsed = timer.elapsed();
+    Ok((stats, most * mem::size_of::<Color>()))
+}
+
+/// The means of the pixels of `tile`, placed in the window, row by row,
+/// after `samples` more of each added to their means in `previous` if there
+/// are any.
+fn render_tile(
+    scene: &Scene,
+    settings: &RenderSettings,
+    integrator: &dyn Integrator,
+    samples: &Range<u32>,
+    previous: Option<&Image>,
+    tile: Crop,
+) -> Vec<Color> {
+    let window = settings.window();
+    let pixels: Vec<(u32, u32)> = (tile.y..tile.y + tile.height)
+        .flat_map(|y| (tile.x..tile.x + tile.width).map(move |x| (x, y)))
+        .collect();
+    let mut colors: Vec<Color> = pixels
+        .iter()
+        .map(|&(x, y)| previous.map_or(Color::zero(), |image| image.get(x, y)))
+        .collect();
+    let pixels: Vec<_> = pixels
+        .into_iter()
+        .map(|(x, y)| (window.x + x, window.y + y))
+        .collect();
+    let size = if settings.packets { PACKET_SIZE } else { 1 };
+    for (pixels, means) in pixels.chunks(size).zip(colors.chunks_mut(size)) {
+        if let [(x, y)] = *pixels {
+            means[0] = render_pixel(scene, settings, integrator, x, y, samples.clone(), means[0]);
+        } else {
+            render_packet(scene, settings, integrator, pixels, samples.clone(), means);
+        }
+    }
+    colors
+}
+
+fn finished_tile(tile: Crop, timer: &Timer) {
+    debug!(
+        "Tile {}x{} at ({}, {}) took {:.2?}",
+        tile.width,
+        tile.height,
+        tile.x,
+        tile.y,
+        timer.elapsed()
+    );
+}
+
+/// Renders `samples` of every pixel in the settings' window, added to the
+/// means in `previous` if there are any, handing out tiles in the settings'
+/// order to the settings' workers, or one per available core, until they're
+/// all done or `stop` is set. Finished tiles are sent back to this thread,
+/// placed in the window, with their pixels row by row; `arrived` is handed
+/// those finished since it was last called, whenever there are some and
+/// every so often in between. Once it returns false the render is
+/// cancelled. The workers never wait on it.
+#[cfg(feature = "threads")]
+fn render_tiles(
+    scene: &Scene,
+    settings: &RenderSettings,
+    integrator: &dyn Integrator,
+    samples: Range<u32>,
+    previous: Option<&Image>,
+    stop: &AtomicBool,
+    mut arrived: impl FnMut(Vec<(Crop, Vec<Color>)>) -> bool,
+) -> Result<RenderStats, Error> {
+    let tiles = tile::tiles(settings.window(), settings.tile_order, settings.seed);
+    let stats = Mutex::new(RenderStats::default());
+    let next_tile = AtomicUsize::new(0);
+    let cancelled = AtomicBool::new(false);
+    let threads = match settings.threads {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        threads => threads,
+    };
+    debug!("Rendering {} tiles on {} threads", tiles.len(), threads);
+    let (stats_ref, samples, tiles) = (&stats, &samples, &tiles);
+    let (next_tile, cancelled) = (&next_tile, &cancelled);
+    thread::scope(|scope| {
+        let (done, finished) = mpsc::channel();
+        for _ in 0..threads {
+            let done = done.clone();
+            scope.spawn(move || loop {
+                let next = tiles.get(next_tile.fetch_add(1, Ordering::Relaxed));
+                let stopped = cancelled.load(Ordering::Relaxed) || stop.load(Ordering::Relaxed);
+                let tile = match next {
+                    Some(&tile) if !stopped => tile,
+                    _ => {
+                        *lock(stats_ref) += stats::take_thread_counters();
+                        break;
+                    }
+                };
+                let timer = Timer::start();
+                let colors = render_tile(scene, settings, integrator, samples, previous, tile);
+                finished_tile(tile, &timer);
+                // Nobody's listening only if the watcher panicked.
+                let _ = done.send((tile, colors));
+            });
+        }
+        drop(done);
+        // The channel closes once every worker is done.
+        let tick = Duration::from_millis(50);
+        loop {
+            let mut tiles = match finished.recv_timeout(tick) {
+                Ok(tile) => vec![tile],
+                Err(RecvTimeoutError::Timeout) => Vec::new(),
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+            tiles.extend(finished.try_iter());
+            if !cancelled.load(Ordering::Relaxed) && !arrived(tiles) {
+                cancelled.store(true, Ordering::Relaxed);
+            }
+        }
+    });
+    if cancelled.load(Ordering::Relaxed) {
+        return Err(Error::Cancelled);
+    }
+    Ok(stats.into_inner().unwrap_or_else(PoisonError::into_inner))
+}
+
+/// [`render_tiles`] on the calling thread alone, for platforms without
+/// threads: each tile goes to `arrived` as soon as it's done.
+#[cfg(not(feature = "threads"))]
+fn render_tiles(
+    scene: &Scene,
+    settings: &RenderSettings,
+    integrator: &dyn Integrator,
+    samples: Range<u32>,
+    previous: Option<&Image>,
+    stop: &AtomicBool,
+    mut arrived: impl FnMut(Vec<(Crop, Vec<Color>)>) -> bool,
+) -> Result<RenderStats, Error> {
+    // Whatever this thread counted before isn't part of the render.
+    stats::take_thread_counters();
+    for tile in tile::tiles(settings.window(), settings.tile_order, settings.seed) {
+        if stop.load(Ordering::Relaxed) {
+            break;
+        }
+        let timer = Timer::start();
+        let colors = render_tile(scene, settings, integrator, &samples, previous, tile);
+        finished_tile(tile, &timer);
+        if !arrived(vec![(tile, colors)]) {
+            return Err(Error::Cancelled);
+        }
+    }
+    Ok(stats::take_thread_counters())
+}
+
+/// A panicking worker makes the whole render panic once the scope ends, so the
+/// others may as well carry on past a lock it poisoned.
+#[cfg(feature = "threads")]
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
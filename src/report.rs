@@ -0,0 +1,249 @@
+//! The report `--report` writes of how a run went, as JSON, for the scripts
+//! that run renders to read instead of what's printed along the way.
+
+use std::path::PathBuf;
+
+use crate::aabb::Aabb;
+use crate::error::Error;
+use crate::image::Tonemap;
+use crate::info::SceneInfo;
+use crate::json::Value;
+use crate::render::RenderSettings;
+use crate::scalar::{self, Scalar};
+use crate::stats::RenderStats;
+use crate::vector::Vector;
+
+/// The version of the report's layout. Members may be added without a new
+/// version, but none are moved, renamed or taken out.
+pub const VERSION: u32 = 1;
+
+/// A file the run wrote, with how long it is and a hash of what's in it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Output {
+    pub path: PathBuf,
+    pub bytes: u64,
+    /// The 64-bit FNV-1a hash of the file.
+    pub hash: u64,
+}
+
+impl Output {
+    pub fn of(path: PathBuf, contents: &[u8]) -> Output {
+        Output {
+            path,
+            bytes: contents.len() as u64,
+            hash: fnv1a(contents),
+        }
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100_0000_01b3)
+    })
+}
+
+/// What's gathered of a run as it goes.
+#[derive(Debug, Default, Clone)]
+pub struct Report {
+    pub scene: Option<SceneInfo>,
+    /// The stats of each image rendered, in the order they were.
+    pub renders: Vec<RenderStats>,
+    pub outputs: Vec<Output>,
+    pub warnings: Vec<String>,
+}
+
+fn number(value: f64) -> Value {
+    match value.is_finite() {
+        true => Value::Number(value),
+        // JSON has no infinity, which is what the settings without a limit
+        // hold.
+        false => Value::Null,
+    }
+}
+
+fn scalar(value: Scalar) -> Value {
+    number(scalar::to_f64(value))
+}
+
+fn object(members: Vec<(&str, Value)>) -> Value {
+    Value::Object(
+        members
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect(),
+    )
+}
+
+fn counts(counts: &[(&str, usize)]) -> Value {
+    object(
+        counts
+            .iter()
+            .map(|&(name, count)| (name, Value::Number(count as f64)))
+            .collect(),
+    )
+}
+
+fn bounds(aabb: &Aabb) -> Value {
+    let point = |p: Vector| Value::Array(vec![scalar(p.x), scalar(p.y), scalar(p.z)]);
+    object(vec![("min", point(aabb.min)), ("max", point(aabb.max))])
+}
+
+fn settings_json(settings: &RenderSettings, tonemap: Tonemap) -> Value {
+    let count = |n: u32| Value::Number(n as f64);
+    let crop = settings.crop.map_or(Value::Null, |crop| {
+        object(vec![
+            ("x", count(crop.x)),
+            ("y", count(crop.y)),
+            ("width", count(crop.width)),
+            ("height", count(crop.height)),
+        ])
+    });
+    object(vec![
+        ("width", count(settings.width)),
+        ("height", count(settings.height)),
+        (
+            "integrator",
+            Value::String(settings.integrator.name().to_string()),
+        ),
+        ("spp", count(settings.spp)),
+        ("max_depth", count(settings.max_depth)),
+        (
+            "russian_roulette",
+            settings.russian_roulette.map_or(Value::Null, count),
+        ),
+        (
+            "next_event_estimation",
+            Value::Bool(settings.next_event_estimation),
+        ),
+        (
+            "multiple_importance_sampling",
+            Value::Bool(settings.multiple_importance_sampling),
+        ),
+        ("ao_samples", count(settings.ao_samples)),
+        ("ao_distance", scalar(settings.ao_distance)),
+        ("caustic_photons", count(settings.caustic_photons)),
+        ("photon_k", Value::Number(settings.photon_k as f64)),
+        ("photon_radius", scalar(settings.photon_radius)),
+        ("clamp_indirect", scalar(settings.clamp_indirect)),
+        ("seed", Value::Number(settings.seed as f64)),
+        ("crop", crop),
+        (
+            "tile_order",
+            Value::String(settings.tile_order.name().to_string()),
+        ),
+        ("packets", Value::Bool(settings.packets)),
+        ("threads", Value::Number(settings.threads as f64)),
+        ("tonemap", Value::String(tonemap.name().to_string())),
+    ])
+}
+
+fn scene_json(info: &SceneInfo) -> Value {
+    let size = |n: usize| Value::Number(n as f64);
+    let unbounded = info.objects.iter().filter(|o| o.bounds.is_none()).count();
+    object(vec![
+        ("objects", size(info.objects.len())),
+        ("unbounded_objects", size(unbounded)),
+        ("animated_objects", size(info.animated_objects)),
+        ("triangles", size(info.triangles)),
+        ("lights", counts(&info.lights)),
+        ("materials", counts(&info.materials)),
+        ("bounds", info.bounds.as_ref().map_or(Value::Null, bounds)),
+        (
+            "layers",
+            Value::Array(info.layers.iter().cloned().map(Value::String).collect()),
+        ),
+        (
+            "bvh",
+            object(vec![
+                ("nodes", size(info.bvh.nodes)),
+                ("leaves", size(info.bvh.leaves)),
+                ("depth", size(info.bvh.depth)),
+            ]),
+        ),
+        (
+            "memory_bytes",
+            object(vec![
+                ("geometry", size(info.geometry_bytes)),
+                ("bvh", size(info.bvh_bytes)),
+                ("objects", size(info.object_bytes)),
+            ]),
+        ),
+    ])
+}
+
+fn stats_json(stats: &RenderStats) -> Value {
+    let count = |n: u64| Value::Number(n as f64);
+    object(vec![
+        ("camera_rays", count(stats.camera_rays)),
+        ("rays", count(stats.rays)),
+        ("shadow_rays", count(stats.shadow_rays)),
+        ("packet_tests", count(stats.packet_tests)),
+        ("packet_lanes", count(stats.packet_lanes)),
+        (
+            "packet_utilization",
+            stats.packet_utilization().map_or(Value::Null, number),
+        ),
+        ("seconds", number(stats.elapsed.as_secs_f64())),
+    ])
+}
+
+impl Report {
+    /// The report of a run with `settings` that ended in `result`.
+    pub fn to_json(
+        &self,
+        settings: &RenderSettings,
+        tonemap: Tonemap,
+        result: Result<(), &Error>,
+    ) -> Value {
+        let status = match result {
+            Ok(()) => "ok",
+            Err(Error::Cancelled) => "cancelled",
+            Err(_) => "failed",
+        };
+        let mut total = RenderStats::default();
+        for &render in &self.renders {
+            total += render;
+        }
+        let outputs = self.outputs.iter().map(|output| {
+            object(vec![
+                ("path", Value::String(output.path.display().to_string())),
+                ("bytes", Value::Number(output.bytes as f64)),
+                (
+                    "hash",
+                    Value::String(format!("fnv1a64:{:016x}", output.hash)),
+                ),
+            ])
+        });
+        object(vec![
+            ("version", Value::Number(VERSION as f64)),
+            ("status", Value::String(status.to_string())),
+            (
+                "exit_code",
+                Value::Number(result.map_or_else(Error::exit_code, |()| 0) as f64),
+            ),
+            (
+                "error",
+                result
+                    .err()
+                    .map_or(Value::Null, |error| Value::String(error.to_string())),
+            ),
+            ("settings", settings_json(settings, tonemap)),
+            ("scene", self.scene.as_ref().map_or(Value::Null, scene_json)),
+            (
+                "stats",
+                object(vec![
+                    (
+                        "renders",
+                        Value::Array(self.renders.iter().map(stats_json).collect()),
+                    ),
+                    ("total", stats_json(&total)),
+                ]),
+            ),
+            ("outputs", Value::Array(outputs.collect())),
+            (
+                "warnings",
+                Value::Array(self.warnings.iter().cloned().map(Value::String).collect()),
+            ),
+        ])
+    }
+}
@@ -0,0 +1,39 @@
+use crate::scalar::Scalar;
+
+/// A small deterministic random number generator (SplitMix64).
+///
+/// Every pixel sample gets its own stream derived from the render seed, the
+/// pixel index and the sample index, so images don't depend on how the work
+/// was split between threads.
+#[derive(Debug, Clone)]
+pub struct Sampler {
+    state: u64,
+}
+
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+impl Sampler {
+    pub fn new(seed: u64, pixel: u64, sample: u64) -> Sampler {
+        Sampler {
+            state: mix(seed ^ mix(pixel ^ mix(sample))),
+        }
+    }
+
+    pub fn next_f64(&mut self) -> f64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        (mix(self.state) >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    /// Like [`Sampler::next_f64`], but as a [`Scalar`] in [0, 1), which
+    /// rounding a `f64` just under 1 to a `f32` wouldn't be. With `f64` math
+    /// it's the same number.
+    pub fn next_scalar(&mut self) -> Scalar {
+        let bits = Scalar::MANTISSA_DIGITS;
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        (mix(self.state) >> (64 - bits)) as Scalar * (1.0 / (1u64 << bits) as Scalar)
+    }
+}
@@ -0,0 +1,75 @@
+//! Warping functions from pairs of uniform numbers in `[0, 1)` to
+//! directions and points, each returned together with its PDF.
+
+use crate::scalar::{consts::PI, Scalar};
+use crate::vector::Vector;
+
+/// A right-handed frame whose `w` axis is a given unit normal.
+#[derive(Debug, Copy, Clone)]
+pub struct OrthonormalBasis {
+    pub u: Vector,
+    pub v: Vector,
+    pub w: Vector,
+}
+
+impl OrthonormalBasis {
+    /// Builds a frame around `n` without branching on which axis `n` is
+    /// closest to (Duff et al. 2017), so normals along an axis are fine.
+    pub fn from_normal(n: Vector) -> OrthonormalBasis {
+        let sign = Scalar::copysign(1.0, n.z);
+        let a = -1.0 / (sign + n.z);
+        let b = n.x * n.y * a;
+        OrthonormalBasis {
+            u: Vector::new(1.0 + sign * n.x * n.x * a, sign * b, -sign * n.x),
+            v: Vector::new(b, sign + n.y * n.y * a, -n.y),
+            w: n,
+        }
+    }
+
+    pub fn to_world(&self, local: Vector) -> Vector {
+        local.x * self.u + local.y * self.v + local.z * self.w
+    }
+
+    pub fn to_local(&self, world: Vector) -> Vector {
+        Vector::new(world * self.u, world * self.v, world * self.w)
+    }
+}
+
+/// A point in the unit disk (in the xy plane) with area density `1 / pi`,
+/// using the concentric mapping so strata stay compact.
+pub fn sample_unit_disk(u1: Scalar, u2: Scalar) -> (Vector, Scalar) {
+    let a = 2.0 * u1 - 1.0;
+    let b = 2.0 * u2 - 1.0;
+    if a == 0.0 && b == 0.0 {
+        return (Vector::zero(), 1.0 / PI);
+    }
+    let (r, theta) = if a.abs() > b.abs() {
+        (a, (PI / 4.0) * (b / a))
+    } else {
+        (b, PI / 2.0 - (PI / 4.0) * (a / b))
+    };
+    (Vector::new(r * theta.cos(), r * theta.sin(), 0.0), 1.0 / PI)
+}
+
+/// A direction in the `+z` hemisphere with solid-angle density
+/// `cos(theta) / pi`.
+pub fn sample_cosine_hemisphere(u1: Scalar, u2: Scalar) -> (Vector, Scalar) {
+    let (disk, _) = sample_unit_disk(u1, u2);
+    let z = (1.0 - disk.x * disk.x - disk.y * disk.y).max(0.0).sqrt();
+    (Vector::new(disk.x, disk.y, z), z / PI)
+}
+
+pub fn cosine_hemisphere_pdf(cos_theta: Scalar) -> Scalar {
+    cos_theta.max(0.0) / PI
+}
+
+/// A direction on the whole sphere with solid-angle density `1 / (4 pi)`.
+pub fn sample_uniform_sphere(u1: Scalar, u2: Scalar) -> (Vector, Scalar) {
+    let z = 1.0 - 2.0 * u1;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * u2;
+    (
+        Vector::new(r * phi.cos(), r * phi.sin(), z),
+        1.0 / (4.0 * PI),
+    )
+}
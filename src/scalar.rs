@@ -0,0 +1,25 @@
+//! The floating-point type all of the geometry and color math is done in.
+//! It's `f64` unless built with the `f32` feature, which renders large
+//! scenes faster and keeps their geometry in half the memory, at the cost of
+//! the precision the tolerances around the renderer are set for.
+
+#[cfg(not(feature = "f32"))]
+pub type Scalar = f64;
+#[cfg(feature = "f32")]
+pub type Scalar = f32;
+
+#[cfg(feature = "f32")]
+pub use std::f32::consts;
+#[cfg(not(feature = "f32"))]
+pub use std::f64::consts;
+
+/// `x` as an `f64`, for what's kept at double precision whatever the math is
+/// done in: numbers written back out to JSON, checkpoints and GIF timing.
+#[cfg(not(feature = "f32"))]
+pub fn to_f64(x: Scalar) -> f64 {
+    x
+}
+#[cfg(feature = "f32")]
+pub fn to_f64(x: Scalar) -> f64 {
+    x.into()
+}
@@ -0,0 +1,906 @@
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+
+use log::info;
+
+use crate::animation::{AnimatedObject, Animation, CameraPath, Frame, Keyframes, Motion};
+use crate::bvh::Bvh;
+use crate::camera::Camera;
+use crate::check;
+use crate::error::Error;
+use crate::light::{AreaLight, EnvironmentLight, Light, LightSample, PointLight};
+use crate::material::{Dielectric, Emissive, Glossy, Lambertian, Material};
+use crate::matrix::Matrix4;
+use crate::medium::Medium;
+use crate::ray::{Lanes, Ray, RayPacket, EPSILON, PACKET_SIZE};
+use crate::sampler::Sampler;
+use crate::scalar::Scalar;
+use crate::shapes::{HitRecord, Plane, Shape, Sphere, Transformed};
+use crate::stats::{self, Timer};
+use crate::vector::{Color, Vector};
+
+/// The layer objects and lights are in unless they're put in others.
+pub const DEFAULT_LAYER: &str = "default";
+
+#[derive(Clone)]
+pub struct Object {
+    pub shape: Arc<dyn Shape>,
+    pub material: Arc<dyn Material>,
+    /// The index of the light in `Scene::lights` this object is the surface
+    /// of, if any.
+    pub light: Option<usize>,
+    pub light_links: LightLinks,
+    /// Whether the object blocks light on its way to other objects.
+    pub cast_shadows: bool,
+    /// Whether camera rays can hit the object.
+    pub visible_to_camera: bool,
+    /// Whether rays reflected, refracted or scattered off other surfaces can
+    /// hit the object.
+    pub visible_in_reflections: bool,
+    /// The render layers the object is in; see [`Scene::layer`].
+    pub layers: Vec<String>,
+}
+
+impl Object {
+    /// Whether a `kind` of ray can hit the object.
+    pub fn visible_to(&self, kind: RayKind) -> bool {
+        match kind {
+            RayKind::Camera => self.visible_to_camera,
+            RayKind::Secondary => self.visible_in_reflections,
+        }
+    }
+}
+
+/// Where a ray comes from, for [`Scene::trace`] to decide which objects it
+/// can hit.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum RayKind {
+    Camera,
+    /// Any ray leaving a surface, besides shadow rays.
+    Secondary,
+}
+
+/// Which of the scene's lights shine on an object, by their indices in
+/// `Scene::lights`. By default every light does; the light from the
+/// background always does.
+///
+/// A light that doesn't shine on an object adds nothing to it, whether it's
+/// sampled directly or found by a ray scattered off the object. Light that it
+/// sends elsewhere still reaches the object indirectly, and so does light in
+/// the caustic photon map or scattered by a medium.
+#[derive(Debug, Clone)]
+pub struct LightLinks {
+    /// The only lights that shine on the object, or `None` for all of them.
+    pub include: Option<Vec<usize>>,
+    /// Lights that don't shine on the object, even if they're included.
+    pub exclude: Vec<usize>,
+    /// Whether the object still casts shadows from the lights that don't
+    /// shine on it. It does by default, so that unlinking a light changes
+    /// only how the object itself looks.
+    pub shadows_unlinked: bool,
+}
+
+impl Default for LightLinks {
+    fn default() -> LightLinks {
+        LightLinks {
+            include: None,
+            exclude: Vec::new(),
+            shadows_unlinked: true,
+        }
+    }
+}
+
+impl LightLinks {
+    /// Just `lights` shine on the object.
+    pub fn only(lights: Vec<usize>) -> LightLinks {
+        LightLinks {
+            include: Some(lights),
+            ..LightLinks::default()
+        }
+    }
+
+    /// Every light but `lights` shines on the object.
+    pub fn except(lights: Vec<usize>) -> LightLinks {
+        LightLinks {
+            exclude: lights,
+            ..LightLinks::default()
+        }
+    }
+
+    pub fn lit_by(&self, light: usize) -> bool {
+        self.include
+            .as_ref()
+            .is_none_or(|include| include.contains(&light))
+            && !self.exclude.contains(&light)
+    }
+
+    /// Whether the object blocks `light` on its way to other objects.
+    pub fn shadows(&self, light: usize) -> bool {
+        self.shadows_unlinked || self.lit_by(light)
+    }
+}
+
+pub struct Hit<'a> {
+    pub record: HitRecord,
+    pub object: &'a Object,
+}
+
+pub struct Scene {
+    pub camera: Camera,
+    pub objects: Vec<Object>,
+    /// Shapes kept once and placed any number of times by `add_instance`.
+    pub geometry: Vec<Arc<dyn Shape>>,
+    pub lights: Vec<Arc<dyn Light>>,
+    /// The render layers of each light in `lights`. A light without an entry
+    /// is in the default layer.
+    pub light_layers: Vec<Vec<String>>,
+    pub medium: Option<Medium>,
+    /// Radiance of rays escaping the scene; when it isn't black it also acts
+    /// as an environment light for next event estimation.
+    pub background: Color,
+    pub animation: Animation,
+    // Built on the first intersection and dropped when objects are added:
+    // one over the objects that stay put, and one over the animated ones,
+    // which is also dropped whenever they move.
+    accelerator: OnceLock<Accelerator>,
+    moving: OnceLock<Accelerator>,
+}
+
+/// A BVH over some of the objects with bounds, and the unbounded ones that
+/// every ray is tested against.
+struct Accelerator {
+    bvh: Bvh,
+    bounded: Vec<usize>,
+    unbounded: Vec<usize>,
+    // How many objects the scene had when this was built.
+    objects: usize,
+}
+
+impl Accelerator {
+    fn new(objects: &[Object], included: impl Fn(usize) -> bool) -> Accelerator {
+        let timer = Timer::start();
+        let mut bounds = Vec::new();
+        let mut bounded = Vec::new();
+        let mut unbounded = Vec::new();
+        for (index, object) in objects.iter().enumerate() {
+            if !included(index) {
+                continue;
+            }
+            match object.shape.bounds() {
+                Some(aabb) => {
+                    bounds.push(aabb);
+                    bounded.push(index);
+                }
+                None => unbounded.push(index),
+            }
+        }
+        let bvh = Bvh::new(&bounds);
+        if !bounded.is_empty() {
+            let shape = bvh.stats();
+            info!(
+                "Built a BVH of {} nodes, {} deep, over {} objects in {:.2?}",
+                shape.nodes,
+                shape.depth,
+                bounded.len(),
+                timer.elapsed()
+            );
+        }
+        Accelerator {
+            bvh,
+            bounded,
+            unbounded,
+            objects: objects.len(),
+        }
+    }
+}
+
+fn default_layers() -> Vec<String> {
+    vec![DEFAULT_LAYER.to_string()]
+}
+
+fn in_layers(names: &[String], layers: &[&str]) -> bool {
+    names.iter().any(|name| layers.contains(&name.as_str()))
+}
+
+impl Scene {
+    pub fn new(camera: Camera) -> Scene {
+        Scene {
+            camera,
+            objects: Vec::new(),
+            geometry: Vec::new(),
+            lights: Vec::new(),
+            light_layers: Vec::new(),
+            medium: None,
+            background: Color::zero(),
+            animation: Animation::default(),
+            accelerator: OnceLock::new(),
+            moving: OnceLock::new(),
+        }
+    }
+
+    /// The scene the binary renders when it isn't given one: a white sphere
+    /// lit red, green and blue from three sides.
+    pub fn demo() -> Scene {
+        let mut scene = Scene::new(Camera::new(
+            Vector::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 0.0, -1.0),
+            45.0,
+        ));
+        let white = Arc::new(Lambertian::new(Color::new(1.0, 1.0, 1.0)));
+        scene.add(Sphere::new(Vector::new(0.0, 0.0, -10.0), 1.0), white);
+        scene.add_light(PointLight::new(
+            Vector::new(2.0, 0.0, -9.0),
+            Color::new(1.0, 0.0, 0.0),
+            2.0,
+        ));
+        scene.add_light(PointLight::new(
+            Vector::new(-2.0, 0.0, -9.0),
+            Color::new(0.0, 1.0, 0.0),
+            2.0,
+        ));
+        scene.add_light(PointLight::new(
+            Vector::new(0.0, -2.0, -9.0),
+            Color::new(0.0, 0.0, 1.0),
+            2.0,
+        ));
+        scene
+    }
+
+    /// `count` spheres strewn in front of the camera, lit by two lights, for
+    /// benchmarks to time. Where they go and what they're made of comes only
+    /// from `seed`, so the same scene is timed on every machine and commit.
+    pub fn benchmark(count: usize, seed: u64) -> Scene {
+        let mut scene = Scene::new(Camera::new(
+            Vector::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 0.0, -1.0),
+            45.0,
+        ));
+        let mut sampler = Sampler::new(seed, 0, 0);
+        let mut next = |low: Scalar, high: Scalar| low + (high - low) * sampler.next_scalar();
+        // Dense enough that many rays pass several spheres on the way to the
+        // one they hit, and some miss them all.
+        let spread = (count as Scalar).cbrt();
+        for _ in 0..count {
+            let center = Vector::new(
+                next(-1.0, 1.0) * spread,
+                next(-0.75, 0.75) * spread,
+                next(-6.0, -2.0) * spread,
+            );
+            let radius = next(0.3, 0.8);
+            let color = Color::new(next(0.2, 1.0), next(0.2, 1.0), next(0.2, 1.0));
+            let material: Arc<dyn Material> = if next(0.0, 1.0) < 0.25 {
+                Arc::new(Glossy::new(color, 40.0))
+            } else {
+                Arc::new(Lambertian::new(color))
+            };
+            scene.add(Sphere::new(center, radius), material);
+        }
+        for (position, intensity) in [
+            (Vector::new(3.0, 4.0, 1.0), 60.0),
+            (Vector::new(-4.0, 2.0, -1.0), 30.0),
+        ] {
+            let light = PointLight::new(
+                spread * position,
+                Color::new(1.0, 1.0, 1.0),
+                intensity * spread * spread,
+            );
+            scene.add_light(light);
+        }
+        scene
+    }
+
+    /// The classic field of `count` small spheres on a ground plane, around
+    /// three large ones of glass, diffuse and metal, under a blue sky. Each
+    /// small sphere rests on the ground clear of every other, at a place, of
+    /// a size and of a material drawn from `seed` alone; its material is
+    /// diffuse four times in five, and otherwise metal or, less often, glass.
+    pub fn random_spheres(count: usize, seed: u64) -> Scene {
+        // The furthest apart two small spheres' centers can be and still
+        // touch, with the gap always left between them, fits in a cell.
+        const GAP: Scalar = 0.02;
+        const CELL: Scalar = 0.6;
+        let mut scene = Scene::new(Camera::new(
+            Vector::new(13.0, 2.0, 3.0),
+            Vector::zero(),
+            35.0,
+        ));
+        scene.background = Color::new(0.5, 0.7, 1.0);
+        scene.add(
+            Plane::new(Vector::zero(), Vector::new(0.0, 1.0, 0.0)),
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        );
+        let features: [(Vector, Arc<dyn Material>); 3] = [
+            (Vector::new(0.0, 1.0, 0.0), Arc::new(Dielectric::new(1.5))),
+            (
+                Vector::new(-4.0, 1.0, 0.0),
+                Arc::new(Lambertian::new(Color::new(0.4, 0.2, 0.1))),
+            ),
+            (
+                Vector::new(4.0, 1.0, 0.0),
+                Arc::new(Glossy::new(Color::new(0.7, 0.6, 0.5), 100.0)),
+            ),
+        ];
+        for (center, material) in features.iter() {
+            scene.add(Sphere::new(*center, 1.0), material.clone());
+        }
+        scene.add_light(PointLight::new(
+            Vector::new(10.0, 20.0, 8.0),
+            Color::new(1.0, 1.0, 1.0),
+            1200.0,
+        ));
+
+        let mut sampler = Sampler::new(seed, 0, 0);
+        let mut next = |low: Scalar, high: Scalar| low + (high - low) * sampler.next_scalar();
+        // About one sphere a square unit, as in the classic scene, on a square
+        // no smaller than it takes to leave room around the large ones.
+        let half = (count as Scalar).sqrt().max(12.0) / 2.0;
+        let cell = |p: Vector| ((p.x / CELL).floor() as i64, (p.z / CELL).floor() as i64);
+        let apart = |a: (Vector, Scalar), b: (Vector, Scalar)| (a.0 - b.0).len() >= a.1 + b.1 + GAP;
+        let mut cells: HashMap<(i64, i64), Vec<(Vector, Scalar)>> = HashMap::new();
+        let mut placed = 0;
+        while placed < count {
+            let radius = next(0.15, 0.25);
+            let center = Vector::new(next(-half, half), radius, next(-half, half));
+            let (i, j) = cell(center);
+            let clear = features
+                .iter()
+                .all(|(feature, _)| apart((center, radius), (*feature, 1.0)))
+                && (i - 1..=i + 1)
+                    .flat_map(|i| (j - 1..=j + 1).map(move |j| (i, j)))
+                    .filter_map(|key| cells.get(&key))
+                    .flatten()
+                    .all(|&other| apart((center, radius), other));
+            if !clear {
+                continue;
+            }
+            let choice = next(0.0, 1.0);
+            let material: Arc<dyn Material> = if choice < 0.8 {
+                let (r, g, b) = (next(0.0, 1.0), next(0.0, 1.0), next(0.0, 1.0));
+                Arc::new(Lambertian::new(Color::new(r * r, g * g, b * b)))
+            } else if choice < 0.95 {
+                let color = Color::new(next(0.5, 1.0), next(0.5, 1.0), next(0.5, 1.0));
+                Arc::new(Glossy::new(color, next(20.0, 200.0)))
+            } else {
+                Arc::new(Dielectric::new(1.5))
+            };
+            scene.add(Sphere::new(center, radius), material);
+            cells.entry((i, j)).or_default().push((center, radius));
+            placed += 1;
+        }
+        scene
+    }
+
+    /// Adds an object lit by every light and seen by every ray, returning its
+    /// index in `objects` for changing its links and flags.
+    pub fn add<S: Shape + 'static>(&mut self, shape: S, material: Arc<dyn Material>) -> usize {
+        self.push_object(Object {
+            shape: Arc::new(shape),
+            material,
+            light: None,
+            light_links: LightLinks::default(),
+            cast_shadows: true,
+            visible_to_camera: true,
+            visible_in_reflections: true,
+            layers: default_layers(),
+        })
+    }
+
+    fn push_object(&mut self, object: Object) -> usize {
+        self.objects.push(object);
+        self.accelerator = OnceLock::new();
+        self.moving = OnceLock::new();
+        self.objects.len() - 1
+    }
+
+    /// Adds a shape to `geometry` without placing it, returning its index.
+    pub fn add_geometry<S: Shape + 'static>(&mut self, shape: S) -> usize {
+        self.geometry.push(Arc::new(shape));
+        self.geometry.len() - 1
+    }
+
+    /// Places `geometry[index]` in the scene, sharing it with every other
+    /// instance of it. Panics if there is no such geometry or the transform
+    /// can't be inverted.
+    pub fn add_instance(
+        &mut self,
+        index: usize,
+        object_to_world: Matrix4,
+        material: Arc<dyn Material>,
+    ) -> usize {
+        let shape = Transformed::new(self.geometry[index].clone(), object_to_world);
+        self.add(shape, material)
+    }
+
+    /// Roughly how much memory the objects' shapes and the shared geometry
+    /// take, each shared shape counted once.
+    pub fn geometry_bytes(&self) -> usize {
+        let objects: usize = self
+            .objects
+            .iter()
+            .map(|object| object.shape.as_ref().memory_bytes())
+            .sum();
+        let shared: usize = self
+            .geometry
+            .iter()
+            .map(|shape| shape.as_ref().memory_bytes())
+            .sum();
+        objects + shared
+    }
+
+    /// Checks the camera, every object and light, and the medium, naming the
+    /// first thing that doesn't make sense, such as `objects[2].radius` for a
+    /// sphere with a negative radius. Rendering does this first.
+    pub fn validate(&self) -> Result<(), Error> {
+        let check = || -> Result<(), String> {
+            self.camera.validate().map_err(check::within("camera"))?;
+            for (i, object) in self.objects.iter().enumerate() {
+                let within = check::within(format!("objects[{}]", i));
+                object.shape.validate().map_err(within)?;
+            }
+            for (i, light) in self.lights.iter().enumerate() {
+                let within = check::within(format!("lights[{}]", i));
+                light.validate().map_err(within)?;
+            }
+            check::color("background", self.background)?;
+            if let Some(medium) = &self.medium {
+                let within = check::within("medium");
+                check::non_negative("absorption", medium.absorption).map_err(&within)?;
+                check::non_negative("scattering", medium.scattering).map_err(&within)?;
+            }
+            Ok(())
+        };
+        check().map_err(Error::InvalidScene)
+    }
+
+    /// Moves `objects[index]` by `motion` from now on, on top of where it
+    /// was added, starting with where the motion has it at time 0. Panics if
+    /// there is no such object or it's the surface of an area light, which
+    /// can't follow it.
+    pub fn animate_object(&mut self, index: usize, motion: impl Into<Motion>) {
+        let object = &self.objects[index];
+        assert!(object.light.is_none(), "area lights can't be animated");
+        let motion = motion.into();
+        let animated = match self
+            .animation
+            .objects
+            .iter_mut()
+            .find(|animated| animated.object == index)
+        {
+            Some(animated) => {
+                animated.motion = motion;
+                animated
+            }
+            None => {
+                self.animation.objects.push(AnimatedObject {
+                    object: index,
+                    shape: object.shape.clone(),
+                    motion,
+                });
+                self.animation.objects.last_mut().unwrap()
+            }
+        };
+        let matrix = animated.motion.at(0.0);
+        self.objects[index].shape = Arc::new(Transformed::new(animated.shape.clone(), matrix));
+        self.accelerator = OnceLock::new();
+        self.moving = OnceLock::new();
+    }
+
+    /// Replaces `lights[index]` with the point light `keys` gives each
+    /// frame, starting with time 0.
+    pub fn animate_light(&mut self, index: usize, keys: Keyframes<PointLight>) {
+        self.lights[index] = Arc::new(keys.at(0.0));
+        self.animation.lights.retain(|(light, _)| *light != index);
+        self.animation.lights.push((index, keys));
+    }
+
+    pub fn animate_camera(&mut self, keys: Keyframes<Camera>) {
+        self.camera = keys.at(0.0);
+        self.animation.camera = Some(keys);
+        self.animation.camera_path = None;
+    }
+
+    /// Flies the camera along `path` instead of any keyframes it had.
+    pub fn fly_camera(&mut self, path: CameraPath) {
+        self.camera = path.at(0.0);
+        self.animation.camera_path = Some(path);
+        self.animation.camera = None;
+    }
+
+    /// Moves everything animated to where it is at `frame`. Only the
+    /// accelerator over the animated objects is rebuilt.
+    pub fn set_frame(&mut self, frame: Frame) {
+        let time = frame.time();
+        for animated in &self.animation.objects {
+            let matrix = animated.motion.at(time);
+            self.objects[animated.object].shape =
+                Arc::new(Transformed::new(animated.shape.clone(), matrix));
+        }
+        if !self.animation.objects.is_empty() {
+            self.moving = OnceLock::new();
+        }
+        for (index, keys) in &self.animation.lights {
+            self.lights[*index] = Arc::new(keys.at(time));
+        }
+        if let Some(keys) = &self.animation.camera {
+            self.camera = keys.at(time);
+        }
+        if let Some(path) = &self.animation.camera_path {
+            self.camera = path.at(time);
+        }
+    }
+
+    /// Adds a light, returning its index in `lights` for objects to link to.
+    pub fn add_light<L: Light + 'static>(&mut self, light: L) -> usize {
+        self.push_light(Arc::new(light))
+    }
+
+    /// Adds a shape that glows with `radiance` on its front side, both as an
+    /// object rays can hit and as a light that can be sampled.
+    pub fn add_area_light<S: Shape + 'static>(&mut self, shape: S, radiance: Color) {
+        let shape: Arc<dyn Shape> = Arc::new(shape);
+        self.push_object(Object {
+            shape: shape.clone(),
+            material: Arc::new(Emissive::new(radiance)),
+            light: Some(self.lights.len()),
+            light_links: LightLinks::default(),
+            cast_shadows: true,
+            visible_to_camera: true,
+            visible_in_reflections: true,
+            layers: default_layers(),
+        });
+        self.push_light(Arc::new(AreaLight { shape, radiance }));
+    }
+
+    fn push_light(&mut self, light: Arc<dyn Light>) -> usize {
+        self.light_layers
+            .resize(self.lights.len(), default_layers());
+        self.light_layers.push(default_layers());
+        self.lights.push(light);
+        self.lights.len() - 1
+    }
+
+    fn light_in(&self, index: usize, layers: &[&str]) -> bool {
+        match self.light_layers.get(index) {
+            Some(names) => in_layers(names, layers),
+            None => layers.contains(&DEFAULT_LAYER),
+        }
+    }
+
+    /// Every layer an object or light is in, sorted by name.
+    pub fn layer_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .objects
+            .iter()
+            .flat_map(|object| &object.layers)
+            .chain(self.light_layers.iter().flatten())
+            .map(String::as_str)
+            .collect();
+        if self.light_layers.len() < self.lights.len() {
+            names.push(DEFAULT_LAYER);
+        }
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    /// A copy of the scene with just the objects and lights in any of
+    /// `layers`, sharing their shapes, materials and lights with this one.
+    /// Light links and area lights are renumbered to match, and the
+    /// background still lights everything. The copy stays as it is at the
+    /// scene's current frame.
+    pub fn layer(&self, layers: &[&str]) -> Scene {
+        let mut kept = Vec::new();
+        let renumbered: Vec<Option<usize>> = (0..self.lights.len())
+            .map(|index| {
+                self.light_in(index, layers).then(|| {
+                    kept.push(index);
+                    kept.len() - 1
+                })
+            })
+            .collect();
+        let renumber = |lights: &[usize]| -> Vec<usize> {
+            lights
+                .iter()
+                .filter_map(|&index| renumbered.get(index).copied().flatten())
+                .collect()
+        };
+        let objects = self
+            .objects
+            .iter()
+            .filter(|object| in_layers(&object.layers, layers))
+            .map(|object| {
+                let mut object = object.clone();
+                object.light = object.light.and_then(|index| renumbered[index]);
+                object.light_links.include = object.light_links.include.as_deref().map(renumber);
+                object.light_links.exclude = renumber(&object.light_links.exclude);
+                object
+            })
+            .collect();
+        Scene {
+            camera: self.camera,
+            objects,
+            geometry: self.geometry.clone(),
+            lights: kept
+                .iter()
+                .map(|&index| self.lights[index].clone())
+                .collect(),
+            light_layers: kept
+                .iter()
+                .map(|&index| {
+                    self.light_layers
+                        .get(index)
+                        .cloned()
+                        .unwrap_or_else(default_layers)
+                })
+                .collect(),
+            medium: self.medium,
+            background: self.background,
+            animation: Animation::default(),
+            accelerator: OnceLock::new(),
+            moving: OnceLock::new(),
+        }
+    }
+
+    fn has_environment(&self) -> bool {
+        self.background != Color::zero()
+    }
+
+    /// The number of lights next event estimation chooses between.
+    pub fn light_count(&self) -> usize {
+        self.lights.len() + self.has_environment() as usize
+    }
+
+    /// Samples light `index` of `light_count()`, the last one being the
+    /// environment if there is one.
+    pub fn sample_light(
+        &self,
+        index: usize,
+        point: Vector,
+        sampler: &mut Sampler,
+    ) -> Option<LightSample> {
+        match self.lights.get(index) {
+            Some(light) => light.sample(point, sampler),
+            None if self.has_environment() => EnvironmentLight {
+                radiance: self.background,
+            }
+            .sample(point, sampler),
+            None => None,
+        }
+    }
+
+    /// The density with which `sample_light(index, ...)` picks `direction`.
+    pub fn light_pdf(&self, index: usize, point: Vector, direction: Vector) -> Scalar {
+        match self.lights.get(index) {
+            Some(light) => light.pdf(point, direction),
+            None if self.has_environment() => EnvironmentLight {
+                radiance: self.background,
+            }
+            .pdf(point, direction),
+            None => 0.0,
+        }
+    }
+
+    /// Whether light `index` of `light_count()` shines on `object`.
+    pub fn illuminates(&self, index: usize, object: &Object) -> bool {
+        index >= self.lights.len() || object.light_links.lit_by(index)
+    }
+
+    /// The index `light_pdf` and `sample_light` use for the environment.
+    pub fn environment_light(&self) -> Option<usize> {
+        if self.has_environment() {
+            Some(self.lights.len())
+        } else {
+            None
+        }
+    }
+
+    /// Offers `hit` each object the ray might meet along with the current
+    /// `t_max`, like [`Bvh::intersect`]. Returning negative infinity stops
+    /// the search.
+    fn visit_objects<'a, F>(&'a self, ray: &Ray, t_min: Scalar, t_max: Scalar, mut hit: F)
+    where
+        F: FnMut(&'a Object, Scalar) -> Option<Scalar>,
+    {
+        let mut closest = t_max;
+        let accelerators = match self.accelerators() {
+            Some(accelerators) => accelerators,
+            None => {
+                for object in &self.objects {
+                    if let Some(t) = hit(object, closest) {
+                        closest = t;
+                    }
+                }
+                return;
+            }
+        };
+        for accelerator in accelerators {
+            for &index in &accelerator.unbounded {
+                if let Some(t) = hit(&self.objects[index], closest) {
+                    closest = t;
+                }
+            }
+            if let Some(t) = accelerator
+                .bvh
+                .intersect(ray, t_min, closest, |primitive, t_max| {
+                    hit(&self.objects[accelerator.bounded[primitive]], t_max)
+                })
+            {
+                closest = t;
+            }
+        }
+    }
+
+    /// The accelerators over the objects that stay put and over the moving
+    /// ones, built if they haven't been. Objects pushed straight onto
+    /// `objects` since they were built aren't in them, and then there are
+    /// none, so that everything is tested.
+    fn accelerators(&self) -> Option<[&Accelerator; 2]> {
+        let animated = || {
+            let mut animated = vec![false; self.objects.len()];
+            for object in &self.animation.objects {
+                animated[object.object] = true;
+            }
+            animated
+        };
+        let accelerator = self.accelerator.get_or_init(|| {
+            let animated = animated();
+            Accelerator::new(&self.objects, |index| !animated[index])
+        });
+        let moving = self.moving.get_or_init(|| {
+            let animated = animated();
+            Accelerator::new(&self.objects, |index| animated[index])
+        });
+        if accelerator.objects != self.objects.len() {
+            return None;
+        }
+        Some([accelerator, moving])
+    }
+
+    /// The nearest hit on any object, whatever rays it's visible to.
+    pub fn intersect(&self, ray: &Ray, t_min: Scalar, t_max: Scalar) -> Option<Hit<'_>> {
+        self.nearest(ray, t_min, t_max, |_| true)
+    }
+
+    /// The nearest hit on an object visible to a `kind` of ray.
+    pub fn trace(&self, ray: &Ray, kind: RayKind, t_min: Scalar, t_max: Scalar) -> Option<Hit<'_>> {
+        self.nearest(ray, t_min, t_max, |object| object.visible_to(kind))
+    }
+
+    fn nearest<F>(&self, ray: &Ray, t_min: Scalar, t_max: Scalar, visible: F) -> Option<Hit<'_>>
+    where
+        F: Fn(&Object) -> bool,
+    {
+        stats::record_ray();
+        let mut closest: Option<Hit> = None;
+        self.visit_objects(ray, t_min, t_max, |object, t_max| {
+            if !visible(object) {
+                return None;
+            }
+            let record = object.shape.intersect(ray, t_min, t_max)?;
+            closest = Some(Hit { record, object });
+            Some(record.t)
+        });
+        if let Some(hit) = &mut closest {
+            hit.object.material.shade(&mut hit.record);
+        }
+        closest
+    }
+
+    /// [`trace`](Scene::trace) for each ray of the packet, walking the BVH
+    /// once for all of them. Each ray hits just what it would alone.
+    pub fn trace_packet(
+        &self,
+        packet: &RayPacket,
+        kind: RayKind,
+        t_min: Scalar,
+        t_max: Scalar,
+    ) -> Lanes<Option<Hit<'_>>> {
+        let accelerators = match self.accelerators() {
+            Some(accelerators) => accelerators,
+            None => {
+                return std::array::from_fn(|i| {
+                    let ray = &packet.rays[i];
+                    packet.active[i].then(|| self.trace(ray, kind, t_min, t_max))?
+                })
+            }
+        };
+        let mut closest: Lanes<Option<Hit>> = std::array::from_fn(|_| None);
+        let mut offer = |index: usize, lanes: Lanes<bool>, t_max: &Lanes<Scalar>| {
+            let object = &self.objects[index];
+            let mut ts = [None; PACKET_SIZE];
+            if !object.visible_to(kind) {
+                return ts;
+            }
+            let records = object.shape.intersect_packet(packet, lanes, t_min, t_max);
+            for (i, record) in records.iter().enumerate() {
+                if let Some(record) = *record {
+                    closest[i] = Some(Hit { record, object });
+                    ts[i] = Some(record.t);
+                }
+            }
+            ts
+        };
+        let mut nearest = [t_max; PACKET_SIZE];
+        let narrow = |nearest: &mut Lanes<Scalar>, ts: Lanes<Option<Scalar>>| {
+            for (t_max, t) in nearest.iter_mut().zip(ts) {
+                if let Some(t) = t {
+                    *t_max = t;
+                }
+            }
+        };
+        for accelerator in accelerators {
+            for &index in &accelerator.unbounded {
+                let lanes = packet.active.iter().filter(|&&lane| lane).count();
+                stats::record_packet_tests(1, lanes as u64);
+                let ts = offer(index, packet.active, &nearest);
+                narrow(&mut nearest, ts);
+            }
+            let ts = accelerator.bvh.intersect_packet(
+                packet,
+                t_min,
+                nearest,
+                |primitive, lanes, t_max| offer(accelerator.bounded[primitive], lanes, t_max),
+            );
+            narrow(&mut nearest, ts);
+        }
+        for (&active, hit) in packet.active.iter().zip(&mut closest) {
+            if !active {
+                continue;
+            }
+            stats::record_ray();
+            if let Some(hit) = hit {
+                hit.object.material.shade(&mut hit.record);
+            }
+        }
+        closest
+    }
+
+    /// Whether anything casting shadows blocks the first `distance` along a
+    /// unit direction.
+    pub fn occluded(&self, from: Vector, direction: Vector, distance: Scalar) -> bool {
+        self.blocked(from, direction, distance, |_| true)
+    }
+
+    /// Whether anything casting shadows from light `index` of
+    /// `light_count()` blocks the first `distance` along a unit direction.
+    pub fn shadowed(
+        &self,
+        index: usize,
+        from: Vector,
+        direction: Vector,
+        distance: Scalar,
+    ) -> bool {
+        if index >= self.lights.len() {
+            return self.occluded(from, direction, distance);
+        }
+        self.blocked(from, direction, distance, |object| {
+            object.light_links.shadows(index)
+        })
+    }
+
+    fn blocked<F>(&self, from: Vector, direction: Vector, distance: Scalar, blocks: F) -> bool
+    where
+        F: Fn(&Object) -> bool,
+    {
+        stats::record_shadow_ray();
+        let ray = Ray::new(from, direction);
+        let mut blocked = false;
+        self.visit_objects(&ray, EPSILON, distance - EPSILON, |object, t_max| {
+            if blocked || !object.cast_shadows || !blocks(object) {
+                return None;
+            }
+            object.shape.intersect(&ray, EPSILON, t_max)?;
+            blocked = true;
+            Some(Scalar::NEG_INFINITY)
+        });
+        blocked
+    }
+
+    /// The fraction of light surviving the medium over `distance`.
+    pub fn transmittance(&self, distance: Scalar) -> Scalar {
+        match &self.medium {
+            Some(medium) => medium.transmittance(distance),
+            None => 1.0,
+        }
+    }
+}
@@ -0,0 +1,1848 @@
+//! Loading scenes from JSON files.
+//!
+//! A scene file is an object with these members, all optional:
+//!
+//! - `camera`: `{"position": [x, y, z], "look_at": [x, y, z], "up": [x, y, z],
+//!   "fov": degrees}`
+//! - `background`: a color, `[r, g, b]`
+//! - `medium`: `{"absorption": a, "scattering": s}`
+//! - `spheres`: `[{"center", "radius", "material"}]`
+//! - `planes`: `[{"point", "normal", "material"}]`
+//! - `quads`: `[{"corner", "u", "v", "material"}]`
+//! - `boxes`: `[{"min", "max", "material"}]`
+//! - `cylinders`, `cones`: `[{"base", "radius", "height", "material"}]`
+//! - `triangles`: `[{"vertices": [a, b, c], "material"}]`
+//! - `meshes`: `[{"path", "material"}]`, loading an OBJ file
+//! - `csg`: `[{"operation", "a", "b", "material"}]`, combining two solids
+//! - `heightfields`: `[{"path" or "heights", "corner", "size", "material"}]`,
+//!   terrain from a grayscale PNG or an array of rows of heights
+//! - `metaballs`: `[{"blobs": [{"center", "radius", "weight"}], "threshold",
+//!   "material"}]`, a blobby surface where the blobs' fields sum to the
+//!   threshold (0.5 by default); each weight defaults to 1
+//! - `sdfs`: `[{"field", "max_steps", "epsilon", "max_distance",
+//!   "material"}]`, sphere tracing a distance field
+//! - `geometry`: `[{"name", "path", "material"}]`, loading an OBJ file once
+//!   to be placed by `instances`; the material is optional
+//! - `instances`: `[{"geometry": name, "transform", "material"}]`, sharing
+//!   the named geometry's triangles; the material overrides the geometry's
+//! - `groups`: `[{"name", ...}]`, scene graph nodes placed only by name
+//! - `nodes`: `[{"transform", "group"}]` or `[{"transform", ...}]`
+//! - `lights`: `[{"type": "point", "position", "color", "intensity"}]` or
+//!   `[{"type": "directional", "direction", "color", "intensity"}]`, each with
+//!   an optional `"name"` and `"layers"`
+//! - `settings`: render settings for the scene, with the keys of a config
+//!   file; see [`crate::config`]
+//!
+//! Every shape can also take `"transform": {"scale", "rotate_deg",
+//! "translate"}`, which scales it about the origin (by a number or `[x, y,
+//! z]`), rotates it by `[x, y, z]` degrees about the x, y and z axes in that
+//! order, then moves it. The rotation can instead be given as a quaternion,
+//! `"rotate_quat": [x, y, z, w]`, or as `"rotate_axis": {"axis", "angle_deg"}`.
+//!
+//! Any object, instances included, can be put in render layers other than
+//! `"default"` with `"layers": ["foreground", ...]`, for rendering parts of
+//! the scene on their own with [`Scene::layer`]. It can be hidden from some
+//! rays with the flags `"cast_shadows"`, `"visible_to_camera"` and
+//! `"visible_in_reflections"`, all true by default; the last covers every ray
+//! leaving a surface other than shadow rays. It can also limit the lights that
+//! shine on it with `"light_links": {"include", "exclude", "shadows"}`. `include` and
+//! `exclude` list lights by name or by index in `lights`; without `include`
+//! every light is included. `shadows` (true by default) says whether the
+//! object still casts shadows from the lights that don't shine on it. See
+//! [`LightLinks`].
+//!
+//! Any `"transform"` can instead be an array of keyframes, each a transform
+//! with a `"time"` from 0 at the first frame to 1 at the last; see
+//! [`crate::animation`]. Translation and scale are interpolated linearly and
+//! rotation along the shorter arc, holding the first and last keys outside
+//! them. A key's `"interpolation"` is `"linear"` (the default) or
+//! `"smooth"`, easing out of it and into the next one. Area lights can't
+//! follow a keyframed transform. Point lights and the camera take keyframes
+//! in `"animation"`, with any of their own members; the rest stay as they
+//! are outside it.
+//!
+//! The camera can instead fly along a `"path"` of keys like those, smoothly
+//! through each one as [`CameraPath`] describes, either listed in place or
+//! in a JSON file of its own holding just the array.
+//!
+//! A scene graph node holds the same object lists as the scene itself, from
+//! `spheres` to `instances`, and `"children"`, an array of nested nodes. Its
+//! `"transform"` moves everything under it, composing down the tree.
+//! Instead of objects, a node can hold `"group"`: the name of a group to
+//! place; groups can place each other but not themselves. The graph is
+//! flattened into plain objects as the file loads.
+//!
+//! A CSG `operation` is `"union"`, `"intersection"` or `"difference"`, which
+//! keeps what is in `a` but not in `b`. Both are closed solids, each one of
+//! `{"type": "sphere", "center", "radius"}`, `{"type": "box", "min", "max"}`,
+//! `{"type": "cylinder" or "cone", "base", "radius", "height"}`, `{"type":
+//! "mesh", "path"}` or `{"type": "csg", "operation", "a", "b"}`, and each can
+//! take a `"transform"`.
+//!
+//! A distance field is one of the presets `{"type": "sphere", "center",
+//! "radius"}`, `{"type": "rounded_box", "center", "half_size", "radius"}`,
+//! `{"type": "torus", "center", "major_radius", "minor_radius"}`, `{"type":
+//! "smooth_union", "a", "b", "smoothness"}`, blending two fields, or `{"type":
+//! "mandelbulb", "center", "power", "iterations"}`; the power defaults to 8
+//! and the iterations to 10. The tracing settings default to [`SdfShape`]'s.
+//!
+//! A material is one of `{"type": "lambertian", "albedo": texture}`,
+//! `{"type": "glossy", "color", "exponent"}`, `{"type": "dielectric", "ior"}`
+//! or `{"type": "emissive", "radiance"}`. Emissive spheres and quads are also
+//! sampled as area lights. Any material can take a `"normal_map"` texture in
+//! tangent space.
+//!
+//! A texture is either a color or one of `{"type": "solid", "color"}`,
+//! `{"type": "checker", "scale", "odd": texture, "even": texture}`,
+//! `{"type": "uv_checker", "columns", "rows", "odd", "even"}`,
+//! `{"type": "noise", "scale", "seed"}`,
+//! `{"type": "marble", "scale", "axis", "turbulence", "seed", "light", "dark"}`,
+//! `{"type": "wood", "scale", "center", "axis", "turbulence", "seed", "light",
+//! "dark"}`, `{"type": "image", "path", "wrap", "filter", "linear"}` and
+//! `{"type": "triplanar", "texture", "scale", "sharpness"}`, which projects
+//! another texture along the world axes for surfaces without coordinates.
+//! Noise, marble and wood work in world space, so they need no surface
+//! coordinates; only `scale` is required and the wood rings default to the
+//! vertical axis through the origin. An image's `wrap` is `"repeat"` (the
+//! default), `"clamp"` or `"mirror"`, or a pair of them for u and v, and its
+//! `filter` is `"bilinear"` (the default) or `"nearest"`; `"linear": true`
+//! reads it without undoing sRGB, as normal maps need. An image that isn't
+//! there is warned of and made magenta, so that it stands out in the render
+//! rather than failing it. Each shape documents how it maps surface
+//! coordinates. All paths are relative to the scene file.
+//!
+//! Any texture object can also transform the surface coordinates it sees
+//! with `"uv_scale"` (a number or `[u, v]`), `"uv_rotate_deg"`, `"uv_pivot"`
+//! (`[0.5, 0.5]` by default) and `"uv_offset"`, applied in that order as
+//! [`UvTransform`] describes.
+//!
+//! Each shape, light and the camera is validated as it's read, so a negative
+//! radius or a NaN color is an error at its place in the file, such as
+//! `spheres[0].radius: expected a positive number, found -1`.
+//!
+//! Files needn't be trusted: loading one fails rather than running out of
+//! stack or memory. Scene graph nodes and CSG combinations nest at most 64
+//! deep, a file makes at most 2^20 objects with its groups expanded, images
+//! have at most 2^26 pixels, and `max_steps` and a mandelbulb's `iterations`
+//! are capped at 100000 and 1000. So are the settings: a render of at most
+//! 2^26 pixels, 2^20 samples a pixel and 2^10 bounces.
+//! Transforms, multiplied out through their groups, must be invertible, so
+//! scales too close to zero are refused along with zero.
+//!
+//! [`CameraPath`]: crate::animation::CameraPath
+//! [`LightLinks`]: crate::scene::LightLinks
+//! [`SdfShape`]: crate::shapes::SdfShape
+//! [`UvTransform`]: crate::texture::UvTransform
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+use log::warn;
+
+use crate::animation::{CameraPath, Easing, Interpolate, Key, Keyframes, Motion, Pose};
+use crate::camera::Camera;
+use crate::check;
+use crate::config::{self, Settings};
+use crate::error::Error;
+use crate::image::Image;
+use crate::json::{self, Value};
+use crate::light::{DirectionalLight, Light, PointLight};
+use crate::material::{Dielectric, Emissive, Glossy, Lambertian, Material, NormalMapped};
+use crate::matrix::Matrix4;
+use crate::medium::Medium;
+use crate::obj;
+use crate::overrides::{self, Override};
+use crate::quaternion::Quaternion;
+use crate::scalar::{self, Scalar};
+use crate::scene::Scene;
+use crate::shapes::{
+    Blob, Cone, Csg, Cuboid, Cylinder, DistanceField, Heightfield, Mandelbulb, Metaballs,
+    Operation, Plane, Quad, RoundedBox, SdfShape, Shape, SmoothUnion, Sphere, Torus, Transformed,
+    Triangle,
+};
+use crate::texture::{
+    Checker, Filter, ImageTexture, Marble, NoiseTexture, SolidColor, Texture, Triplanar, UvChecker,
+    UvTransform, UvTransformed, Wood, Wrap,
+};
+use crate::vector::{Color, Vector};
+
+/// The path that stands for stdin, which a scene can be read from as it can
+/// from a file. The relative paths in it start from the working directory.
+pub const STDIN: &str = "-";
+
+/// What errors call the file at `path`.
+pub fn name(path: &Path) -> PathBuf {
+    match path == Path::new(STDIN) {
+        true => PathBuf::from("stdin"),
+        false => path.to_path_buf(),
+    }
+}
+
+/// The text of the scene file at `path`, or of stdin for [`STDIN`]. Stdin
+/// can only be read once, so what came from it is kept for the next time.
+pub fn read_source(path: &Path) -> Result<String, Error> {
+    static STDIN_TEXT: OnceLock<Result<String, (io::ErrorKind, String)>> = OnceLock::new();
+    let text = match path == Path::new(STDIN) {
+        true => STDIN_TEXT
+            .get_or_init(|| {
+                let mut text = String::new();
+                io::stdin()
+                    .read_to_string(&mut text)
+                    .map(|_| text)
+                    .map_err(|error| (error.kind(), error.to_string()))
+            })
+            .clone()
+            .map_err(|(kind, message)| io::Error::new(kind, message)),
+        false => fs::read_to_string(path),
+    };
+    text.map_err(|source| Error::Io {
+        path: name(path),
+        operation: "read",
+        source,
+    })
+}
+
+pub fn load(path: &Path) -> Result<Scene, Error> {
+    load_with(path, &[])
+}
+
+/// Loads the scene file at `path` with `overrides` made to its values
+/// first. Errors with a value an override set say which override it was.
+pub fn load_with(path: &Path, overrides: &[Override]) -> Result<Scene, Error> {
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+    read_with(path, overrides, |value| build(value, base))
+}
+
+/// Why a scene file didn't load.
+enum Failure {
+    /// It isn't a scene file: it isn't JSON, or a member is missing, of the
+    /// wrong type or unknown.
+    Parse(String),
+    /// It is one, but a shape, light or the camera in it doesn't make sense,
+    /// such as a negative radius.
+    Invalid(String),
+}
+
+impl From<String> for Failure {
+    fn from(message: String) -> Failure {
+        Failure::Parse(message)
+    }
+}
+
+impl From<Failure> for String {
+    fn from(failure: Failure) -> String {
+        match failure {
+            Failure::Parse(message) | Failure::Invalid(message) => message,
+        }
+    }
+}
+
+/// Reads the scene file at `path`, makes `overrides` to it, and hands it to
+/// `build`.
+fn read_with<T, E: Into<Failure>>(
+    path: &Path,
+    overrides: &[Override],
+    build: impl FnOnce(&Value) -> Result<T, E>,
+) -> Result<T, Error> {
+    let text = read_source(path)?;
+    let built = json::parse(&text)
+        .map_err(|e| Failure::Parse(e.to_string()))
+        .and_then(|mut value| {
+            overrides::apply_all(&mut value, overrides)?;
+            build(&value).map_err(E::into)
+        });
+    match built {
+        Ok(built) => Ok(built),
+        Err(Failure::Parse(message)) => Err(Error::SceneParse {
+            path: name(path),
+            message: overrides::blame(message, overrides),
+        }),
+        Err(Failure::Invalid(message)) => Err(Error::InvalidScene(format!(
+            "{}: {}",
+            name(path).display(),
+            overrides::blame(message, overrides)
+        ))),
+    }
+}
+
+/// Reads just the `settings` of the scene file at `path`, with `overrides`
+/// made to it, without loading anything it refers to.
+pub fn load_settings(path: &Path, overrides: &[Override]) -> Result<Settings, Error> {
+    read_with(path, overrides, settings)
+}
+
+/// The files the scene file at `path` refers to, with `overrides` made to
+/// it: meshes, heightmaps, textures and camera paths, all given by a
+/// `"path"` member.
+pub fn references(path: &Path, overrides: &[Override]) -> Result<Vec<PathBuf>, Error> {
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+    read_with(path, overrides, |value| {
+        let mut files = Vec::new();
+        add_references(value, base, &mut files);
+        Ok::<_, String>(files)
+    })
+}
+
+fn add_references(value: &Value, base: &Path, files: &mut Vec<PathBuf>) {
+    match value {
+        Value::Object(members) => {
+            for (name, value) in members {
+                match value {
+                    Value::String(file) if name == "path" => {
+                        let file = base.join(file);
+                        if !files.contains(&file) {
+                            files.push(file);
+                        }
+                    }
+                    _ => add_references(value, base, files),
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                add_references(item, base, files);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The `settings` member of a parsed scene file, empty if it has none.
+pub fn settings(value: &Value) -> Result<Settings, String> {
+    let root = Node {
+        value,
+        path: String::new(),
+    };
+    let mut settings = Settings::default();
+    let node = match root.optional("settings") {
+        Some(node) => node,
+        None => return Ok(settings),
+    };
+    node.check_members(&config::KEYS)?;
+    for key in config::KEYS.iter() {
+        if let Some(member) = node.optional(key) {
+            settings
+                .set(key, member.value)
+                .map_err(|message| member.error(&message))?;
+        }
+    }
+    Ok(settings)
+}
+
+/// Parses a scene file whose relative paths start from `base`.
+pub fn parse(text: &str, base: &Path) -> Result<Scene, String> {
+    let value = json::parse(text).map_err(|e| e.to_string())?;
+    from_value(&value, base)
+}
+
+/// Builds a scene from an already parsed scene file.
+pub fn from_value(value: &Value, base: &Path) -> Result<Scene, String> {
+    build(value, base).map_err(String::from)
+}
+
+fn build(value: &Value, base: &Path) -> Result<Scene, Failure> {
+    let root = Node {
+        value,
+        path: String::new(),
+    };
+    root.check_members(&[
+        "camera",
+        "background",
+        "medium",
+        "spheres",
+        "planes",
+        "quads",
+        "boxes",
+        "cylinders",
+        "cones",
+        "triangles",
+        "meshes",
+        "csg",
+        "heightfields",
+        "metaballs",
+        "sdfs",
+        "geometry",
+        "instances",
+        "groups",
+        "nodes",
+        "lights",
+        "settings",
+    ])?;
+    settings(value)?;
+    let camera_node = root.optional("camera");
+    let camera = match &camera_node {
+        Some(node) => camera(node)?,
+        None => Camera::new(Vector::zero(), Vector::new(0.0, 0.0, -1.0), 45.0),
+    };
+    let mut scene = Scene::new(camera);
+    if let Some(path) = camera_node.as_ref().and_then(|node| node.optional("path")) {
+        if camera_node
+            .as_ref()
+            .unwrap()
+            .optional("animation")
+            .is_some()
+        {
+            return Err(path.error("can't be used along with \"animation\"").into());
+        }
+        scene.fly_camera(camera_path(&path, &camera, base)?);
+    }
+    if let Some(keys) = camera_node.and_then(|node| node.optional("animation")) {
+        let members = ["position", "look_at", "up", "fov"];
+        scene.animate_camera(keyframes(&keys, &members, |key| -> Result<_, Failure> {
+            let camera = Camera {
+                position: key.vector_or("position", camera.position)?,
+                look_at: key.vector_or("look_at", camera.look_at)?,
+                up: key.vector_or("up", camera.up)?,
+                fov: key.number_or("fov", camera.fov)?,
+            };
+            camera.validate().map_err(|field| key.invalid(&field))?;
+            Ok(camera)
+        })?);
+    }
+    if let Some(node) = root.optional("background") {
+        scene.background = node.vector()?;
+        check::color("background", scene.background).map_err(|field| root.invalid(&field))?;
+    }
+    if let Some(node) = root.optional("medium") {
+        node.check_members(&["absorption", "scattering"])?;
+        let medium = Medium::new(
+            node.member("absorption")?.number()?,
+            node.member("scattering")?.number()?,
+        );
+        check::non_negative("absorption", medium.absorption)
+            .map_err(|field| node.invalid(&field))?;
+        check::non_negative("scattering", medium.scattering)
+            .map_err(|field| node.invalid(&field))?;
+        scene.medium = Some(medium);
+    }
+    // Lights come first, so that objects can link to them by their place in
+    // the file.
+    let mut light_names = Vec::new();
+    for node in root.list("lights")? {
+        let name = match node.optional("name") {
+            Some(name) => Some(name.string()?),
+            None => None,
+        };
+        if name.is_some() && light_names.contains(&name) {
+            let name = node.member("name")?;
+            return Err(name
+                .error(&format!("light {:?} is already defined", name.string()?))
+                .into());
+        }
+        light_names.push(name);
+        match node.kind()? {
+            "point" => {
+                node.check_members(&[
+                    "type",
+                    "name",
+                    "layers",
+                    "position",
+                    "color",
+                    "intensity",
+                    "animation",
+                ])?;
+                let light = PointLight::new(
+                    node.member("position")?.vector()?,
+                    node.member("color")?.vector()?,
+                    node.member("intensity")?.number()?,
+                );
+                let keys = match node.optional("animation") {
+                    Some(keys) => Some(keyframes(
+                        &keys,
+                        &["position", "color", "intensity"],
+                        |key| -> Result<_, Failure> {
+                            let light = PointLight::new(
+                                key.vector_or("position", light.source)?,
+                                key.vector_or("color", light.color)?,
+                                key.number_or("intensity", light.intensity)?,
+                            );
+                            light.validate().map_err(|field| key.invalid(&field))?;
+                            Ok(light)
+                        },
+                    )?),
+                    None => None,
+                };
+                let index = add_light(&mut scene, &node, light)?;
+                if let Some(keys) = keys {
+                    scene.animate_light(index, keys);
+                }
+            }
+            "directional" => {
+                node.check_members(&["type", "name", "layers", "direction", "color", "intensity"])?;
+                let direction = node.member("direction")?;
+                let vector = direction.vector()?;
+                if vector == Vector::zero() {
+                    return Err(direction.error("a direction can't be zero").into());
+                }
+                let light = DirectionalLight::new(
+                    vector,
+                    node.member("color")?.vector()?,
+                    node.member("intensity")?.number()?,
+                );
+                add_light(&mut scene, &node, light)?;
+            }
+            other => {
+                return Err(node
+                    .error(&format!("unknown light type {:?}", other))
+                    .into())
+            }
+        }
+    }
+    let mut definitions = Definitions {
+        base,
+        geometry: Vec::new(),
+        default_materials: Vec::new(),
+        groups: Vec::new(),
+        group_sizes: Vec::new(),
+        lights: light_names,
+    };
+    // Each named mesh is loaded once into `scene.geometry`, along with the
+    // material its instances use unless they give their own.
+    for node in root.list("geometry")? {
+        node.check_members(&["name", "path", "material"])?;
+        let name = node.member("name")?;
+        let key = name.string()?;
+        if definitions.geometry.contains(&key) {
+            return Err(name
+                .error(&format!("geometry {:?} is already defined", key))
+                .into());
+        }
+        let path = node.member("path")?;
+        let mesh = obj::load(&base.join(path.string()?)).map_err(|e| path.error(&e))?;
+        mesh.validate()
+            .map_err(|e| Failure::Invalid(path.error(&e)))?;
+        definitions
+            .default_materials
+            .push(match node.optional("material") {
+                Some(material_node) => Some(material(&material_node, base)?),
+                None => None,
+            });
+        definitions.geometry.push(key);
+        scene.add_geometry(mesh);
+    }
+    for node in root.list("groups")? {
+        node.check_members(&[&["name"], &OBJECT_LISTS[..]].concat())?;
+        let name = node.member("name")?;
+        let key = name.string()?;
+        if definitions.groups.iter().any(|(other, _)| *other == key) {
+            return Err(name
+                .error(&format!("group {:?} is already defined", key))
+                .into());
+        }
+        definitions.groups.push((key, node));
+    }
+    definitions.group_sizes = group_sizes(&definitions.groups);
+    let world = Motion::fixed(Matrix4::identity());
+    add_objects(&mut scene, &root, &definitions, &world, &mut Vec::new(), 0)?;
+    for node in root.list("nodes")? {
+        add_node(&mut scene, &node, &definitions, &world, &mut Vec::new(), 1)?;
+    }
+    Ok(scene)
+}
+
+fn add_light<L: Light + 'static>(
+    scene: &mut Scene,
+    node: &Node,
+    light: L,
+) -> Result<usize, Failure> {
+    light.validate().map_err(|field| node.invalid(&field))?;
+    let index = scene.add_light(light);
+    if let Some(layers) = node.optional("layers") {
+        scene.light_layers[index] = layers.layers()?;
+    }
+    Ok(index)
+}
+
+/// The keyframes in the array `node`, each an object with a `"time"`, an
+/// optional `"interpolation"`, and `members` that `value` reads.
+fn keyframes<T, E, F>(node: &Node, members: &[&str], value: F) -> Result<Keyframes<T>, E>
+where
+    T: Interpolate,
+    E: From<String>,
+    F: Fn(&Node) -> Result<T, E>,
+{
+    let keys = node
+        .items()?
+        .iter()
+        .map(|item| {
+            item.check_members(&[&["time", "interpolation"], members].concat())?;
+            let easing = match item.optional("interpolation") {
+                Some(mode) => match mode.string()? {
+                    "linear" => Easing::Linear,
+                    "smooth" => Easing::Smooth,
+                    other => {
+                        return Err(mode
+                            .error(&format!("unknown interpolation {:?}", other))
+                            .into())
+                    }
+                },
+                None => Easing::Linear,
+            };
+            Ok(Key {
+                time: item.member("time")?.number()?,
+                value: value(item)?,
+                easing,
+            })
+        })
+        .collect::<Result<Vec<_>, E>>()?;
+    if keys.is_empty() {
+        return Err(node.error("expected at least one keyframe").into());
+    }
+    Ok(Keyframes::from_keys(keys))
+}
+
+/// The camera path in `node`, an array of keys or the name of a file
+/// holding one. Keys leave out whichever of `camera`'s members they keep.
+fn camera_path(node: &Node, camera: &Camera, base: &Path) -> Result<CameraPath, Failure> {
+    let file;
+    let keys = match node.value {
+        Value::String(name) => {
+            let path = base.join(name);
+            let read = |e: &dyn std::fmt::Display| {
+                node.error(&format!("can't read {}: {}", path.display(), e))
+            };
+            let text = fs::read_to_string(&path).map_err(|e| read(&e))?;
+            file = json::parse(&text).map_err(|e| read(&e))?;
+            Node {
+                value: &file,
+                path: name.clone(),
+            }
+        }
+        _ => Node {
+            value: node.value,
+            path: node.path.clone(),
+        },
+    };
+    let keys = keys
+        .items()?
+        .iter()
+        .map(|key| {
+            key.check_members(&["time", "position", "look_at", "up", "fov"])?;
+            let camera = Camera {
+                position: key.vector_or("position", camera.position)?,
+                look_at: key.vector_or("look_at", camera.look_at)?,
+                up: key.vector_or("up", camera.up)?,
+                fov: key.number_or("fov", camera.fov)?,
+            };
+            camera.validate().map_err(|field| key.invalid(&field))?;
+            Ok((key.member("time")?.number()?, camera))
+        })
+        .collect::<Result<Vec<_>, Failure>>()?;
+    if keys.is_empty() {
+        return Err(node.error("expected at least one keyframe").into());
+    }
+    Ok(CameraPath::new(keys))
+}
+
+// A value along with where it is in the file, for error messages.
+struct Node<'a> {
+    value: &'a Value,
+    path: String,
+}
+
+impl<'a> Node<'a> {
+    fn error(&self, message: &str) -> String {
+        if self.path.is_empty() {
+            message.to_string()
+        } else {
+            format!("{}: {}", self.path, message)
+        }
+    }
+
+    /// A failed check of `field`, one of this node's members.
+    fn invalid(&self, field: &str) -> Failure {
+        Failure::Invalid(self.child(field))
+    }
+
+    fn expected(&self, what: &str) -> String {
+        self.error(&format!("expected {}, found {}", what, self.value.kind()))
+    }
+
+    fn child(&self, key: &str) -> String {
+        if self.path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", self.path, key)
+        }
+    }
+
+    fn optional(&self, key: &str) -> Option<Node<'a>> {
+        self.value.get(key).map(|value| Node {
+            value,
+            path: self.child(key),
+        })
+    }
+
+    fn member(&self, key: &str) -> Result<Node<'a>, String> {
+        if self.value.as_object().is_none() {
+            return Err(self.expected("an object"));
+        }
+        self.optional(key)
+            .ok_or_else(|| self.error(&format!("missing member {:?}", key)))
+    }
+
+    /// Rejects members other than `allowed`, which are most likely typos.
+    fn check_members(&self, allowed: &[&str]) -> Result<(), String> {
+        let members = self
+            .value
+            .as_object()
+            .ok_or_else(|| self.expected("an object"))?;
+        match members
+            .iter()
+            .find(|(name, _)| !allowed.contains(&name.as_str()))
+        {
+            Some((name, _)) => Err(self.error(&format!("unknown member {:?}", name))),
+            None => Ok(()),
+        }
+    }
+
+    fn items(&self) -> Result<Vec<Node<'a>>, String> {
+        let items = self
+            .value
+            .as_array()
+            .ok_or_else(|| self.expected("an array"))?;
+        Ok(items
+            .iter()
+            .enumerate()
+            .map(|(i, value)| Node {
+                value,
+                path: format!("{}[{}]", self.path, i),
+            })
+            .collect())
+    }
+
+    /// The items of the array member `key`, or none if it's missing.
+    fn list(&self, key: &str) -> Result<Vec<Node<'a>>, String> {
+        match self.optional(key) {
+            Some(node) => node.items(),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// The `type` member of an object.
+    fn kind(&self) -> Result<&'a str, String> {
+        self.member("type")?.string()
+    }
+
+    fn string(&self) -> Result<&'a str, String> {
+        self.value.as_str().ok_or_else(|| self.expected("a string"))
+    }
+
+    /// An array of layer names.
+    fn layers(&self) -> Result<Vec<String>, String> {
+        self.items()?
+            .iter()
+            .map(|item| item.string().map(str::to_string))
+            .collect()
+    }
+
+    fn boolean(&self) -> Result<bool, String> {
+        self.value
+            .as_bool()
+            .ok_or_else(|| self.expected("a boolean"))
+    }
+
+    fn number(&self) -> Result<Scalar, String> {
+        scalar(self.value).ok_or_else(|| self.expected("a number"))
+    }
+
+    /// The number member `key`, or `default` if it's missing.
+    fn number_or(&self, key: &str, default: Scalar) -> Result<Scalar, String> {
+        match self.optional(key) {
+            Some(value) => value.number(),
+            None => Ok(default),
+        }
+    }
+
+    /// The vector member `key`, or `default` if it's missing.
+    fn vector_or(&self, key: &str, default: Vector) -> Result<Vector, String> {
+        match self.optional(key) {
+            Some(value) => value.vector(),
+            None => Ok(default),
+        }
+    }
+
+    fn wrap(&self) -> Result<Wrap, String> {
+        match self.string()? {
+            "repeat" => Ok(Wrap::Repeat),
+            "clamp" => Ok(Wrap::Clamp),
+            "mirror" => Ok(Wrap::Mirror),
+            other => Err(self.error(&format!("unknown wrap mode {:?}", other))),
+        }
+    }
+
+    /// The optional `seed` member, 0 if it's missing.
+    fn seed(&self) -> Result<u64, String> {
+        match self.optional("seed") {
+            Some(seed) => seed.integer(),
+            None => Ok(0),
+        }
+    }
+
+    /// A non-negative integer no greater than `max`.
+    fn at_most(&self, max: u64) -> Result<u64, String> {
+        match self.integer()? {
+            n if n > max => Err(self.error(&format!("expected at most {}, found {}", max, n))),
+            n => Ok(n),
+        }
+    }
+
+    fn integer(&self) -> Result<u64, String> {
+        match self.value.as_f64() {
+            Some(n) if n >= 0.0 && n.fract() == 0.0 && n < u64::MAX as f64 => Ok(n as u64),
+            _ => Err(self.expected("a non-negative integer")),
+        }
+    }
+
+    fn pair(&self) -> Result<(Scalar, Scalar), String> {
+        match self.value.as_array() {
+            Some([a, b]) => match (scalar(a), scalar(b)) {
+                (Some(a), Some(b)) => Ok((a, b)),
+                _ => Err(self.error("expected two numbers")),
+            },
+            _ => Err(self.expected("an array of two numbers")),
+        }
+    }
+
+    fn vector(&self) -> Result<Vector, String> {
+        match self.value.as_array() {
+            Some([x, y, z]) => match (scalar(x), scalar(y), scalar(z)) {
+                (Some(x), Some(y), Some(z)) => Ok(Vector::new(x, y, z)),
+                _ => Err(self.error("expected three numbers")),
+            },
+            _ => Err(self.expected("an array of three numbers")),
+        }
+    }
+}
+
+/// A JSON number as a [`Scalar`].
+fn scalar(value: &Value) -> Option<Scalar> {
+    value.as_f64().map(|n| n as Scalar)
+}
+
+/// `camera` as the `"camera"` member of a scene file.
+pub fn camera_value(camera: &Camera) -> Value {
+    let vector = |v: Vector| {
+        Value::Array(vec![
+            Value::Number(scalar::to_f64(v.x)),
+            Value::Number(scalar::to_f64(v.y)),
+            Value::Number(scalar::to_f64(v.z)),
+        ])
+    };
+    Value::Object(vec![
+        ("position".to_string(), vector(camera.position)),
+        ("look_at".to_string(), vector(camera.look_at)),
+        ("up".to_string(), vector(camera.up)),
+        ("fov".to_string(), Value::Number(scalar::to_f64(camera.fov))),
+    ])
+}
+
+fn camera(node: &Node) -> Result<Camera, Failure> {
+    node.check_members(&["position", "look_at", "up", "fov", "animation", "path"])?;
+    let mut camera = Camera::new(
+        node.member("position")?.vector()?,
+        node.member("look_at")?.vector()?,
+        node.member("fov")?.number()?,
+    );
+    if let Some(up) = node.optional("up") {
+        camera.up = up.vector()?;
+    }
+    camera.validate().map_err(|field| node.invalid(&field))?;
+    Ok(camera)
+}
+
+/// What objects anywhere in the file can refer to by name.
+struct Definitions<'a> {
+    base: &'a Path,
+    // The names of `scene.geometry` in order, and the materials their
+    // instances use by default.
+    geometry: Vec<&'a str>,
+    default_materials: Vec<Option<Arc<dyn Material>>>,
+    groups: Vec<(&'a str, Node<'a>)>,
+    // How many objects placing each group makes.
+    group_sizes: Vec<u64>,
+    // The names of the file's lights, which are first in `scene.lights`.
+    lights: Vec<Option<&'a str>>,
+}
+
+/// How deeply scene graph nodes, counting the groups they place, and CSG
+/// combinations can nest. Loading recurses for each level, and a chain of
+/// groups that each place the next could otherwise overflow the stack.
+const MAX_NESTING: usize = 64;
+
+/// How many objects a file can make, counting those of a group once for
+/// each place it's placed, so that a small file can't ask for billions.
+const MAX_OBJECTS: usize = 1 << 20;
+
+/// The lists of objects that both the root and every scene graph node can
+/// hold.
+const OBJECT_LISTS: [&str; 14] = [
+    "spheres",
+    "planes",
+    "quads",
+    "boxes",
+    "cylinders",
+    "cones",
+    "triangles",
+    "meshes",
+    "csg",
+    "heightfields",
+    "metaballs",
+    "sdfs",
+    "instances",
+    "children",
+];
+
+/// Adds the objects in `node`'s lists, with `parent` taking them into the
+/// world, and recurses into its children. `open` is the path of groups
+/// being expanded, to catch groups that contain themselves, and `depth` how
+/// many nodes deep `node` is.
+fn add_objects<'a>(
+    scene: &mut Scene,
+    node: &Node<'a>,
+    definitions: &Definitions<'a>,
+    parent: &Motion,
+    open: &mut Vec<&'a str>,
+    depth: usize,
+) -> Result<(), Failure> {
+    for item in node.list("spheres")? {
+        check_object_members(&item, &["center", "radius"])?;
+        let sphere = Sphere::new(
+            item.member("center")?.vector()?,
+            item.member("radius")?.number()?,
+        );
+        add_shape(scene, sphere, &item, definitions, parent, true)?;
+    }
+    for item in node.list("planes")? {
+        check_object_members(&item, &["point", "normal"])?;
+        let plane = Plane::new(
+            item.member("point")?.vector()?,
+            item.member("normal")?.vector()?,
+        );
+        add_shape(scene, plane, &item, definitions, parent, false)?;
+    }
+    for item in node.list("quads")? {
+        check_object_members(&item, &["corner", "u", "v"])?;
+        let quad = Quad::new(
+            item.member("corner")?.vector()?,
+            item.member("u")?.vector()?,
+            item.member("v")?.vector()?,
+        );
+        add_shape(scene, quad, &item, definitions, parent, true)?;
+    }
+    for item in node.list("boxes")? {
+        check_object_members(&item, &["min", "max"])?;
+        let cuboid = Cuboid::new(item.member("min")?.vector()?, item.member("max")?.vector()?);
+        add_shape(scene, cuboid, &item, definitions, parent, false)?;
+    }
+    for item in node.list("cylinders")? {
+        item.check_members(&[
+            "base",
+            "radius",
+            "height",
+            "material",
+            "transform",
+            "light_links",
+        ])?;
+        let cylinder = Cylinder::new(
+            item.member("base")?.vector()?,
+            item.member("radius")?.number()?,
+            item.member("height")?.number()?,
+        );
+        add_shape(scene, cylinder, &item, definitions, parent, false)?;
+    }
+    for item in node.list("cones")? {
+        item.check_members(&[
+            "base",
+            "radius",
+            "height",
+            "material",
+            "transform",
+            "light_links",
+        ])?;
+        let cone = Cone::new(
+            item.member("base")?.vector()?,
+            item.member("radius")?.number()?,
+            item.member("height")?.number()?,
+        );
+        add_shape(scene, cone, &item, definitions, parent, false)?;
+    }
+    for item in node.list("triangles")? {
+        check_object_members(&item, &["vertices"])?;
+        let vertices = item.member("vertices")?;
+        let triangle = match &vertices.items()?[..] {
+            [a, b, c] => Triangle::new(a.vector()?, b.vector()?, c.vector()?),
+            _ => return Err(vertices.error("expected three vertices").into()),
+        };
+        add_shape(scene, triangle, &item, definitions, parent, false)?;
+    }
+    for item in node.list("meshes")? {
+        check_object_members(&item, &["path"])?;
+        let path = item.member("path")?;
+        let mesh = obj::load(&definitions.base.join(path.string()?)).map_err(|e| path.error(&e))?;
+        add_shape(scene, mesh, &item, definitions, parent, false)?;
+    }
+    for item in node.list("csg")? {
+        item.check_members(&[
+            "operation",
+            "a",
+            "b",
+            "material",
+            "transform",
+            "light_links",
+        ])?;
+        let csg = csg(&item, definitions.base, 1)?;
+        add_shape(scene, csg, &item, definitions, parent, false)?;
+    }
+    for item in node.list("heightfields")? {
+        item.check_members(&[
+            "path",
+            "heights",
+            "corner",
+            "size",
+            "material",
+            "transform",
+            "light_links",
+        ])?;
+        let heightfield = heightfield(&item, definitions.base)?;
+        add_shape(scene, heightfield, &item, definitions, parent, false)?;
+    }
+    for item in node.list("metaballs")? {
+        check_object_members(&item, &["blobs", "threshold"])?;
+        let mut blobs = Vec::new();
+        for blob in item.member("blobs")?.items()? {
+            blob.check_members(&["center", "radius", "weight"])?;
+            blobs.push(Blob::new(
+                blob.member("center")?.vector()?,
+                blob.member("radius")?.number()?,
+                blob.number_or("weight", 1.0)?,
+            ));
+        }
+        let metaballs = Metaballs::new(blobs, positive(&item, "threshold", 0.5)?);
+        add_shape(scene, metaballs, &item, definitions, parent, false)?;
+    }
+    for item in node.list("sdfs")? {
+        check_object_members(&item, &["field", "max_steps", "epsilon", "max_distance"])?;
+        let mut sdf = SdfShape::new(distance_field(&item.member("field")?)?);
+        if let Some(steps) = item.optional("max_steps") {
+            sdf.max_steps = steps.at_most(MAX_STEPS)? as usize;
+        }
+        sdf.epsilon = positive(&item, "epsilon", sdf.epsilon)?;
+        sdf.max_distance = positive(&item, "max_distance", sdf.max_distance)?;
+        add_shape(scene, sdf, &item, definitions, parent, false)?;
+    }
+    for item in node.list("instances")? {
+        check_object_members(&item, &["geometry"])?;
+        room_for_object(scene, &item)?;
+        let name = item.member("geometry")?;
+        let key = name.string()?;
+        let index = definitions
+            .geometry
+            .iter()
+            .position(|&other| other == key)
+            .ok_or_else(|| name.error(&format!("no geometry named {:?}", key)))?;
+        let object_to_world = parent.clone() * local_transform(&item)?;
+        let material = match (
+            item.optional("material"),
+            &definitions.default_materials[index],
+        ) {
+            (Some(material_node), _) => material(&material_node, definitions.base)?,
+            (None, Some(default)) => default.clone(),
+            (None, None) => return Err(item.error("missing member \"material\"").into()),
+        };
+        let object = match object_to_world.fixed_matrix() {
+            Some(matrix) => scene.add_instance(index, invertible(&item, matrix)?, material),
+            None => {
+                invertible(&item, object_to_world.at(0.0))?;
+                invertible(&item, object_to_world.at(1.0))?;
+                let object = scene.add_instance(index, Matrix4::identity(), material);
+                scene.animate_object(object, object_to_world);
+                object
+            }
+        };
+        configure_object(scene, object, &item, definitions)?;
+    }
+    for child in node.list("children")? {
+        add_node(scene, &child, definitions, parent, open, depth + 1)?;
+    }
+    Ok(())
+}
+
+/// Adds a scene graph node, which either holds objects of its own or places
+/// a named group, moved by its `transform` within `parent`.
+fn add_node<'a>(
+    scene: &mut Scene,
+    node: &Node<'a>,
+    definitions: &Definitions<'a>,
+    parent: &Motion,
+    open: &mut Vec<&'a str>,
+    depth: usize,
+) -> Result<(), Failure> {
+    if depth > MAX_NESTING {
+        return Err(node
+            .error(&format!(
+                "scene graph nodes can't nest more than {} deep",
+                MAX_NESTING
+            ))
+            .into());
+    }
+    let world = parent.clone() * local_transform(node)?;
+    let group = match node.optional("group") {
+        Some(group) => group,
+        None => {
+            node.check_members(&[&["transform"], &OBJECT_LISTS[..]].concat())?;
+            return add_objects(scene, node, definitions, &world, open, depth);
+        }
+    };
+    node.check_members(&["transform", "group"])?;
+    let key = group.string()?;
+    let index = definitions
+        .groups
+        .iter()
+        .position(|(other, _)| *other == key)
+        .ok_or_else(|| group.error(&format!("no group named {:?}", key)))?;
+    let contents = &definitions.groups[index].1;
+    if open.contains(&key) {
+        let cycle: Vec<String> = open
+            .iter()
+            .skip_while(|&&name| name != key)
+            .chain([key].iter())
+            .map(|name| format!("{:?}", name))
+            .collect();
+        return Err(group
+            .error(&format!(
+                "group {:?} contains itself: {}",
+                key,
+                cycle.join(" -> ")
+            ))
+            .into());
+    }
+    let size = definitions.group_sizes[index];
+    if (scene.objects.len() as u64).saturating_add(size) > MAX_OBJECTS as u64 {
+        return Err(group
+            .error(&format!(
+                "placing group {:?} would make more than {} objects",
+                key, MAX_OBJECTS
+            ))
+            .into());
+    }
+    open.push(key);
+    add_objects(scene, contents, definitions, &world, open, depth)?;
+    open.pop();
+    Ok(())
+}
+
+/// How many objects placing each of `groups` makes, counted before any is
+/// expanded. A group placing another twice doubles its count, so a few dozen
+/// groups could make more objects than [`MAX_OBJECTS`] allows, and take
+/// ages to be found out one object at a time.
+fn group_sizes(groups: &[(&str, Node)]) -> Vec<u64> {
+    let mut sizes = vec![None; groups.len()];
+    for index in 0..groups.len() {
+        group_size(index, groups, &mut sizes, 1);
+    }
+    sizes.into_iter().map(Option::unwrap_or_default).collect()
+}
+
+/// Counts the objects in `groups[index]` once, remembering it in `sizes`.
+/// Groups that contain themselves or nest too deeply count as smaller than
+/// they are, but they fail to expand anyway.
+fn group_size(
+    index: usize,
+    groups: &[(&str, Node)],
+    sizes: &mut [Option<u64>],
+    depth: usize,
+) -> u64 {
+    if let Some(size) = sizes[index] {
+        return size;
+    }
+    if depth > MAX_NESTING {
+        return 0;
+    }
+    sizes[index] = Some(0);
+    let size = objects_in(groups[index].1.value, groups, sizes, depth);
+    sizes[index] = Some(size);
+    size
+}
+
+fn objects_in(
+    value: &Value,
+    groups: &[(&str, Node)],
+    sizes: &mut [Option<u64>],
+    depth: usize,
+) -> u64 {
+    if let Some(key) = value.get("group").and_then(Value::as_str) {
+        return match groups.iter().position(|(name, _)| *name == key) {
+            Some(index) => group_size(index, groups, sizes, depth),
+            None => 0,
+        };
+    }
+    let mut count: u64 = 0;
+    for list in OBJECT_LISTS.iter() {
+        for item in value
+            .get(list)
+            .and_then(Value::as_array)
+            .unwrap_or_default()
+        {
+            count = count.saturating_add(match *list {
+                "children" => objects_in(item, groups, sizes, depth + 1),
+                _ => 1,
+            });
+        }
+    }
+    count
+}
+
+/// A node's own `transform`, or the identity if it has none. An array of
+/// transforms is a keyframed one.
+fn local_transform(node: &Node) -> Result<Motion, String> {
+    match node.optional("transform") {
+        Some(transform) if transform.value.as_array().is_some() => {
+            let poses = keyframes(&transform, &TRANSFORM_MEMBERS, pose)?;
+            // A scale going from positive to negative passes through zero.
+            for pair in poses.keys().windows(2) {
+                let (a, b) = (pair[0].value.scale, pair[1].value.scale);
+                if a.x * b.x < 0.0 || a.y * b.y < 0.0 || a.z * b.z < 0.0 {
+                    return Err(transform.error("a keyframed scale can't change sign"));
+                }
+            }
+            Ok(Motion::keyed(poses))
+        }
+        Some(transform) => Ok(Motion::fixed(object_to_world(&transform)?)),
+        None => Ok(Motion::fixed(Matrix4::identity())),
+    }
+}
+
+/// The combination of the solids `node` gives as `a` and `b`, `depth`
+/// combinations deep.
+fn csg(node: &Node, base: &Path, depth: usize) -> Result<Csg, String> {
+    if depth > MAX_NESTING {
+        return Err(node.error(&format!(
+            "CSG combinations can't nest more than {} deep",
+            MAX_NESTING
+        )));
+    }
+    let operation = node.member("operation")?;
+    let operation = match operation.string()? {
+        "union" => Operation::Union,
+        "intersection" => Operation::Intersection,
+        "difference" => Operation::Difference,
+        other => return Err(operation.error(&format!("unknown operation {:?}", other))),
+    };
+    Ok(Csg::new(
+        operation,
+        solid(&node.member("a")?, base, depth)?,
+        solid(&node.member("b")?, base, depth)?,
+    ))
+}
+
+/// A closed shape inside a CSG combination `depth` deep, moved by its
+/// `transform`.
+fn solid(node: &Node, base: &Path, depth: usize) -> Result<Box<dyn Shape>, String> {
+    let shape: Box<dyn Shape> = match node.kind()? {
+        "sphere" => {
+            node.check_members(&["type", "center", "radius", "transform"])?;
+            Box::new(Sphere::new(
+                node.member("center")?.vector()?,
+                node.member("radius")?.number()?,
+            ))
+        }
+        "box" => {
+            node.check_members(&["type", "min", "max", "transform"])?;
+            Box::new(Cuboid::new(
+                node.member("min")?.vector()?,
+                node.member("max")?.vector()?,
+            ))
+        }
+        kind @ ("cylinder" | "cone") => {
+            node.check_members(&["type", "base", "radius", "height", "transform"])?;
+            let (center, radius, height) = (
+                node.member("base")?.vector()?,
+                node.member("radius")?.number()?,
+                node.member("height")?.number()?,
+            );
+            if kind == "cylinder" {
+                Box::new(Cylinder::new(center, radius, height))
+            } else {
+                Box::new(Cone::new(center, radius, height))
+            }
+        }
+        "mesh" => {
+            node.check_members(&["type", "path", "transform"])?;
+            let path = node.member("path")?;
+            Box::new(obj::load(&base.join(path.string()?)).map_err(|e| path.error(&e))?)
+        }
+        "csg" => {
+            node.check_members(&["type", "operation", "a", "b", "transform"])?;
+            Box::new(csg(node, base, depth + 1)?)
+        }
+        other => return Err(node.error(&format!("unknown solid type {:?}", other))),
+    };
+    match node.optional("transform") {
+        Some(transform) => Ok(Box::new(Transformed::new(
+            shape,
+            object_to_world(&transform)?,
+        ))),
+        None => Ok(shape),
+    }
+}
+
+/// A heightfield from either an image or rows of numbers.
+fn heightfield(node: &Node, base: &Path) -> Result<Heightfield, String> {
+    let corner = node.member("corner")?.vector()?;
+    let size = node.member("size")?.vector()?;
+    match (node.optional("path"), node.optional("heights")) {
+        (Some(path), None) => {
+            let image =
+                Image::read_linear_png(&base.join(path.string()?)).map_err(|e| path.error(&e))?;
+            if image.width < 2 || image.height < 2 {
+                return Err(path.error("a heightfield needs at least two rows and columns"));
+            }
+            Ok(Heightfield::from_image(&image, corner, size))
+        }
+        (None, Some(grid)) => {
+            let mut heights = Vec::new();
+            let mut columns = None;
+            let rows = grid.items()?;
+            for row in &rows {
+                let row_heights = row
+                    .items()?
+                    .iter()
+                    .map(Node::number)
+                    .collect::<Result<Vec<Scalar>, String>>()?;
+                match columns {
+                    None => columns = Some(row_heights.len()),
+                    Some(columns) if row_heights.len() != columns => {
+                        return Err(row.error(&format!("expected {} heights", columns)));
+                    }
+                    Some(_) => {}
+                }
+                heights.extend(row_heights);
+            }
+            let columns = columns.unwrap_or(0);
+            if rows.len() < 2 || columns < 2 {
+                return Err(grid.error("a heightfield needs at least two rows and columns"));
+            }
+            Ok(Heightfield::new(&heights, columns, corner, size))
+        }
+        _ => Err(node.error("expected either \"path\" or \"heights\"")),
+    }
+}
+
+/// One of the preset distance fields.
+fn distance_field(node: &Node) -> Result<Box<dyn DistanceField>, String> {
+    Ok(match node.kind()? {
+        "sphere" => {
+            node.check_members(&["type", "center", "radius"])?;
+            Box::new(Sphere::new(
+                node.member("center")?.vector()?,
+                node.member("radius")?.number()?,
+            ))
+        }
+        "rounded_box" => {
+            node.check_members(&["type", "center", "half_size", "radius"])?;
+            Box::new(RoundedBox::new(
+                node.member("center")?.vector()?,
+                node.member("half_size")?.vector()?,
+                node.member("radius")?.number()?,
+            ))
+        }
+        "torus" => {
+            node.check_members(&["type", "center", "major_radius", "minor_radius"])?;
+            Box::new(Torus::new(
+                node.member("center")?.vector()?,
+                node.member("major_radius")?.number()?,
+                node.member("minor_radius")?.number()?,
+            ))
+        }
+        "smooth_union" => {
+            node.check_members(&["type", "a", "b", "smoothness"])?;
+            Box::new(SmoothUnion::new(
+                distance_field(&node.member("a")?)?,
+                distance_field(&node.member("b")?)?,
+                node.member("smoothness")?.number()?,
+            ))
+        }
+        "mandelbulb" => {
+            node.check_members(&["type", "center", "power", "iterations"])?;
+            let iterations = match node.optional("iterations") {
+                Some(iterations) => iterations.at_most(MAX_ITERATIONS)? as usize,
+                None => 10,
+            };
+            Box::new(Mandelbulb::new(
+                node.member("center")?.vector()?,
+                node.number_or("power", 8.0)?,
+                iterations,
+            ))
+        }
+        other => return Err(node.error(&format!("unknown distance field type {:?}", other))),
+    })
+}
+
+/// The most steps a ray can take through a distance field, and the most
+/// times a fractal field can iterate, so that a file can't ask for renders
+/// that never finish.
+const MAX_STEPS: u64 = 100_000;
+const MAX_ITERATIONS: u64 = 1000;
+
+/// The number member `key`, which has to be positive, or `default` if it's
+/// missing.
+fn positive(node: &Node, key: &str, default: Scalar) -> Result<Scalar, String> {
+    match node.optional(key) {
+        Some(value) if value.number()? <= 0.0 => Err(value.error("expected a positive number")),
+        Some(value) => value.number(),
+        None => Ok(default),
+    }
+}
+
+/// Adds the shape `node` describes, moved by its `transform` and then by
+/// `parent`.
+fn add_shape<S: Shape + 'static>(
+    scene: &mut Scene,
+    shape: S,
+    node: &Node,
+    definitions: &Definitions,
+    parent: &Motion,
+    sampled: bool,
+) -> Result<(), Failure> {
+    // The checks' messages start with the field, which goes on the node's
+    // path.
+    shape.validate().map_err(|field| node.invalid(&field))?;
+    room_for_object(scene, node)?;
+    let material = node.member("material")?;
+    let object_to_world = parent.clone() * local_transform(node)?;
+    let base = definitions.base;
+    let object = match object_to_world.fixed_matrix() {
+        Some(matrix) if matrix == Matrix4::identity() => {
+            add_with_material(scene, shape, &material, base, sampled)?
+        }
+        Some(matrix) => {
+            let shape = Transformed::new(shape, invertible(node, matrix)?);
+            add_with_material(scene, shape, &material, base, sampled)?
+        }
+        None => {
+            invertible(node, object_to_world.at(0.0))?;
+            invertible(node, object_to_world.at(1.0))?;
+            let object = add_with_material(scene, shape, &material, base, sampled)?;
+            if scene.objects[object].light.is_some() {
+                return Err(node
+                    .error("area lights can't follow a keyframed transform")
+                    .into());
+            }
+            scene.animate_object(object, object_to_world);
+            object
+        }
+    };
+    configure_object(scene, object, node, definitions).map_err(Failure::from)
+}
+
+/// The members every object can have, besides those of its shape.
+const OBJECT_MEMBERS: [&str; 7] = [
+    "material",
+    "transform",
+    "layers",
+    "light_links",
+    "cast_shadows",
+    "visible_to_camera",
+    "visible_in_reflections",
+];
+
+fn check_object_members(node: &Node, shape: &[&str]) -> Result<(), String> {
+    node.check_members(&[shape, &OBJECT_MEMBERS[..]].concat())
+}
+
+/// Fails at `node` if the scene already has as many objects as a file can
+/// make.
+fn room_for_object(scene: &Scene, node: &Node) -> Result<(), String> {
+    if scene.objects.len() >= MAX_OBJECTS {
+        return Err(node.error(&format!(
+            "a scene can't have more than {} objects",
+            MAX_OBJECTS
+        )));
+    }
+    Ok(())
+}
+
+/// Sets the layers and flags of `scene.objects[object]` and which lights
+/// shine on it from the node's members, where it has them. An area light is
+/// in the same layers as its surface.
+fn configure_object(
+    scene: &mut Scene,
+    object: usize,
+    node: &Node,
+    definitions: &Definitions,
+) -> Result<(), String> {
+    if let Some(layers) = node.optional("layers") {
+        let layers = layers.layers()?;
+        if let Some(light) = scene.objects[object].light {
+            scene.light_layers[light] = layers.clone();
+        }
+        scene.objects[object].layers = layers;
+    }
+    let object = &mut scene.objects[object];
+    for (key, flag) in [
+        ("cast_shadows", &mut object.cast_shadows),
+        ("visible_to_camera", &mut object.visible_to_camera),
+        ("visible_in_reflections", &mut object.visible_in_reflections),
+    ] {
+        if let Some(value) = node.optional(key) {
+            *flag = value.boolean()?;
+        }
+    }
+    let node = match node.optional("light_links") {
+        Some(node) => node,
+        None => return Ok(()),
+    };
+    node.check_members(&["include", "exclude", "shadows"])?;
+    let lights = |key: &str| -> Result<Vec<usize>, String> {
+        node.list(key)?
+            .iter()
+            .map(|light| light_index(light, &definitions.lights))
+            .collect()
+    };
+    let links = &mut object.light_links;
+    if node.optional("include").is_some() {
+        links.include = Some(lights("include")?);
+    }
+    links.exclude = lights("exclude")?;
+    if let Some(shadows) = node.optional("shadows") {
+        links.shadows_unlinked = shadows.boolean()?;
+    }
+    Ok(())
+}
+
+/// The index of a light given by its name or its place in `lights`.
+fn light_index(node: &Node, names: &[Option<&str>]) -> Result<usize, String> {
+    if let Some(name) = node.value.as_str() {
+        return names
+            .iter()
+            .position(|&other| other == Some(name))
+            .ok_or_else(|| node.error(&format!("no light named {:?}", name)));
+    }
+    let index = node.integer()? as usize;
+    if index >= names.len() {
+        return Err(node.error(&format!(
+            "there are only {} lights in \"lights\"",
+            names.len()
+        )));
+    }
+    Ok(index)
+}
+
+/// Adds a shape with the material `node`, as an area light if it's emissive
+/// and can be `sampled`, returning the object's index.
+fn add_with_material<S: Shape + 'static>(
+    scene: &mut Scene,
+    shape: S,
+    node: &Node,
+    base: &Path,
+    sampled: bool,
+) -> Result<usize, String> {
+    if sampled && node.kind()? == "emissive" {
+        node.check_members(&["type", "radiance"])?;
+        let radiance = node.member("radiance")?.vector()?;
+        check::color("radiance", radiance).map_err(|field| node.child(&field))?;
+        scene.add_area_light(shape, radiance);
+        Ok(scene.objects.len() - 1)
+    } else {
+        Ok(scene.add(shape, material(node, base)?))
+    }
+}
+
+const TRANSFORM_MEMBERS: [&str; 5] = [
+    "translate",
+    "rotate_deg",
+    "rotate_quat",
+    "rotate_axis",
+    "scale",
+];
+
+/// Scales, then rotates, then translates.
+fn object_to_world(node: &Node) -> Result<Matrix4, String> {
+    node.check_members(&TRANSFORM_MEMBERS)?;
+    let mut matrix = Matrix4::identity();
+    if let Some(scale) = node.optional("scale") {
+        matrix = Matrix4::scale(scale_factors(&scale)?);
+    }
+    if let Some(rotation) = rotation(node)? {
+        matrix = rotation.to_matrix() * matrix;
+    }
+    if let Some(offset) = node.optional("translate") {
+        matrix = Matrix4::translate(offset.vector()?) * matrix;
+    }
+    invertible(node, matrix)
+}
+
+/// `matrix`, the transform of `node` and the groups above it, if rays can
+/// be carried back through it. A scale of 1e-13, or a few small ones that
+/// multiply out to it, is as good as zero.
+fn invertible(node: &Node, matrix: Matrix4) -> Result<Matrix4, String> {
+    match matrix.inverse() {
+        Some(_) => Ok(matrix),
+        None => Err(node.error("the transform can't be inverted; is a scale too close to zero?")),
+    }
+}
+
+/// The pose of a keyframed transform, whose members have been checked.
+fn pose(node: &Node) -> Result<Pose, String> {
+    let mut pose = Pose::identity();
+    if let Some(scale) = node.optional("scale") {
+        pose.scale = scale_factors(&scale)?;
+    }
+    if let Some(rotation) = rotation(node)? {
+        pose.rotate = rotation;
+    }
+    pose.translate = node.vector_or("translate", pose.translate)?;
+    invertible(node, pose.to_matrix())?;
+    Ok(pose)
+}
+
+/// A scale by a number or by `[x, y, z]`.
+fn scale_factors(node: &Node) -> Result<Vector, String> {
+    let factors = match node.value {
+        Value::Number(s) => Vector::new(*s as Scalar, *s as Scalar, *s as Scalar),
+        _ => node.vector()?,
+    };
+    if factors.x == 0.0 || factors.y == 0.0 || factors.z == 0.0 {
+        return Err(node.error("a scale can't be zero"));
+    }
+    Ok(factors)
+}
+
+/// The rotation a transform gives in any of its three forms, if it has one.
+fn rotation(node: &Node) -> Result<Option<Quaternion>, String> {
+    let forms = ["rotate_deg", "rotate_quat", "rotate_axis"];
+    if forms
+        .iter()
+        .filter(|&&key| node.optional(key).is_some())
+        .count()
+        > 1
+    {
+        return Err(node.error(
+            "only one of \"rotate_deg\", \"rotate_quat\" and \"rotate_axis\" can be given",
+        ));
+    }
+    if let Some(degrees) = node.optional("rotate_deg") {
+        let degrees = degrees.vector()?;
+        let radians = Vector::new(
+            degrees.x.to_radians(),
+            degrees.y.to_radians(),
+            degrees.z.to_radians(),
+        );
+        return Ok(Some(Quaternion::from_euler(radians)));
+    }
+    if let Some(quaternion) = node.optional("rotate_quat") {
+        let q = match quaternion.value.as_array() {
+            Some([x, y, z, w]) => match (scalar(x), scalar(y), scalar(z), scalar(w)) {
+                (Some(x), Some(y), Some(z), Some(w)) => Quaternion::new(x, y, z, w),
+                _ => return Err(quaternion.error("expected four numbers")),
+            },
+            _ => return Err(quaternion.expected("an array of four numbers")),
+        };
+        if q.len() == 0.0 {
+            return Err(quaternion.error("a rotation can't be zero"));
+        }
+        return Ok(Some(q.normalize()));
+    }
+    if let Some(rotation) = node.optional("rotate_axis") {
+        rotation.check_members(&["axis", "angle_deg"])?;
+        let axis = rotation.member("axis")?;
+        let direction = axis.vector()?;
+        if direction.len() == 0.0 {
+            return Err(axis.error("an axis can't be zero"));
+        }
+        let angle = rotation.member("angle_deg")?.number()?.to_radians();
+        return Ok(Some(Quaternion::from_axis_angle(direction, angle)));
+    }
+    Ok(None)
+}
+
+fn material(node: &Node, base: &Path) -> Result<Arc<dyn Material>, String> {
+    let material: Arc<dyn Material> = match node.kind()? {
+        "lambertian" => {
+            node.check_members(&["type", "albedo", "normal_map"])?;
+            Arc::new(Lambertian::textured(texture(
+                &node.member("albedo")?,
+                base,
+            )?))
+        }
+        "glossy" => {
+            node.check_members(&["type", "color", "exponent", "normal_map"])?;
+            Arc::new(Glossy::new(
+                node.member("color")?.vector()?,
+                node.member("exponent")?.number()?,
+            ))
+        }
+        "dielectric" => {
+            node.check_members(&["type", "ior", "normal_map"])?;
+            Arc::new(Dielectric::new(node.member("ior")?.number()?))
+        }
+        "emissive" => {
+            node.check_members(&["type", "radiance", "normal_map"])?;
+            Arc::new(Emissive::new(node.member("radiance")?.vector()?))
+        }
+        other => return Err(node.error(&format!("unknown material type {:?}", other))),
+    };
+    Ok(match node.optional("normal_map") {
+        Some(map) => Arc::new(NormalMapped::new(material, texture(&map, base)?)),
+        None => material,
+    })
+}
+
+fn texture(node: &Node, base: &Path) -> Result<Box<dyn Texture>, String> {
+    if node.value.as_array().is_some() {
+        return Ok(Box::new(SolidColor::new(node.vector()?)));
+    }
+    // Any texture object may also transform its coordinates.
+    let check = |allowed: &[&str]| node.check_members(&[allowed, &UV_TRANSFORM[..]].concat());
+    let texture: Box<dyn Texture> = match node.kind()? {
+        "solid" => {
+            check(&["type", "color"])?;
+            Box::new(SolidColor::new(node.member("color")?.vector()?))
+        }
+        "checker" => {
+            check(&["type", "scale", "odd", "even"])?;
+            Box::new(Checker::new(
+                node.member("scale")?.number()?,
+                texture(&node.member("odd")?, base)?,
+                texture(&node.member("even")?, base)?,
+            ))
+        }
+        "uv_checker" => {
+            check(&["type", "columns", "rows", "odd", "even"])?;
+            Box::new(UvChecker::new(
+                node.member("columns")?.number()?,
+                node.member("rows")?.number()?,
+                texture(&node.member("odd")?, base)?,
+                texture(&node.member("even")?, base)?,
+            ))
+        }
+        "noise" => {
+            check(&["type", "scale", "seed"])?;
+            Box::new(NoiseTexture::new(
+                node.member("scale")?.number()?,
+                node.seed()?,
+            ))
+        }
+        "marble" => {
+            check(&[
+                "type",
+                "scale",
+                "axis",
+                "turbulence",
+                "seed",
+                "light",
+                "dark",
+            ])?;
+            Box::new(Marble::new(
+                node.member("scale")?.number()?,
+                node.vector_or("axis", Vector::new(1.0, 0.0, 0.0))?,
+                node.number_or("turbulence", 5.0)?,
+                node.seed()?,
+                node.vector_or("light", Color::new(0.9, 0.9, 0.88))?,
+                node.vector_or("dark", Color::new(0.25, 0.25, 0.3))?,
+            ))
+        }
+        "wood" => {
+            check(&[
+                "type",
+                "scale",
+                "center",
+                "axis",
+                "turbulence",
+                "seed",
+                "light",
+                "dark",
+            ])?;
+            Box::new(Wood::new(
+                node.member("scale")?.number()?,
+                node.vector_or("center", Vector::zero())?,
+                node.vector_or("axis", Vector::new(0.0, 1.0, 0.0))?,
+                node.number_or("turbulence", 0.4)?,
+                node.seed()?,
+                node.vector_or("light", Color::new(0.75, 0.52, 0.3))?,
+                node.vector_or("dark", Color::new(0.4, 0.22, 0.1))?,
+            ))
+        }
+        "triplanar" => {
+            check(&["type", "texture", "scale", "sharpness"])?;
+            Box::new(Triplanar::new(
+                texture(&node.member("texture")?, base)?,
+                node.number_or("scale", 1.0)?,
+                node.number_or("sharpness", 4.0)?,
+            ))
+        }
+        "image" => {
+            check(&["type", "path", "wrap", "filter", "linear"])?;
+            let path = node.member("path")?;
+            let linear = match node.optional("linear") {
+                Some(linear) => linear.boolean()?,
+                None => false,
+            };
+            let path_on_disk = base.join(path.string()?);
+            let mut texture = if !path_on_disk.exists() {
+                warn!(
+                    "{}: no such texture, so it's magenta instead",
+                    path_on_disk.display()
+                );
+                let mut magenta = Image::new(1, 1);
+                magenta.set(0, 0, Color::new(1.0, 0.0, 1.0));
+                Ok(ImageTexture::new(magenta))
+            } else if linear {
+                ImageTexture::load_linear(&path_on_disk)
+            } else {
+                ImageTexture::load(&path_on_disk)
+            }
+            .map_err(|e| path.error(&e))?;
+            if let Some(wrap) = node.optional("wrap") {
+                (texture.wrap_u, texture.wrap_v) = match wrap.value {
+                    Value::Array(_) => match &wrap.items()?[..] {
+                        [u, v] => (u.wrap()?, v.wrap()?),
+                        _ => return Err(wrap.expected("a wrap mode or an array of two")),
+                    },
+                    _ => (wrap.wrap()?, wrap.wrap()?),
+                };
+            }
+            if let Some(filter) = node.optional("filter") {
+                texture.filter = match filter.string()? {
+                    "nearest" => Filter::Nearest,
+                    "bilinear" => Filter::Bilinear,
+                    other => return Err(filter.error(&format!("unknown filter {:?}", other))),
+                };
+            }
+            Box::new(texture)
+        }
+        other => return Err(node.error(&format!("unknown texture type {:?}", other))),
+    };
+    let transform = uv_transform(node)?;
+    Ok(if transform == UvTransform::default() {
+        texture
+    } else {
+        Box::new(UvTransformed::new(texture, transform))
+    })
+}
+
+/// The members any texture object may have to transform its coordinates.
+const UV_TRANSFORM: [&str; 4] = ["uv_scale", "uv_rotate_deg", "uv_pivot", "uv_offset"];
+
+fn uv_transform(node: &Node) -> Result<UvTransform, String> {
+    let mut transform = UvTransform::default();
+    if let Some(scale) = node.optional("uv_scale") {
+        transform.scale = match scale.value {
+            Value::Number(s) => (*s as Scalar, *s as Scalar),
+            _ => scale.pair()?,
+        };
+    }
+    if let Some(degrees) = node.optional("uv_rotate_deg") {
+        transform.rotation = degrees.number()?.to_radians();
+    }
+    if let Some(pivot) = node.optional("uv_pivot") {
+        transform.pivot = pivot.pair()?;
+    }
+    if let Some(offset) = node.optional("uv_offset") {
+        transform.offset = offset.pair()?;
+    }
+    Ok(transform)
+}
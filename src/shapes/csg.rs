@@ -0,0 +1,174 @@
+use super::{HitRecord, Shape};
+use crate::aabb::Aabb;
+use crate::check;
+use crate::ray::Ray;
+use crate::scalar::Scalar;
+
+/// How a [`Csg`] combines its two shapes.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Operation {
+    /// Everything inside either shape.
+    Union,
+    /// Only what is inside both shapes.
+    Intersection,
+    /// What is inside the first shape but not the second.
+    Difference,
+}
+
+impl Operation {
+    fn contains(self, in_a: bool, in_b: bool) -> bool {
+        match self {
+            Operation::Union => in_a || in_b,
+            Operation::Intersection => in_a && in_b,
+            Operation::Difference => in_a && !in_b,
+        }
+    }
+}
+
+/// Hits closer together along a ray than this are taken to be at the same
+/// place, so that faces the two shapes share don't show. The hits on a shared
+/// face land further apart the less precise the math.
+#[cfg(not(feature = "f32"))]
+const COINCIDENT: Scalar = 1e-9;
+#[cfg(feature = "f32")]
+const COINCIDENT: Scalar = 1e-5;
+
+/// A solid made by combining two closed shapes with an [`Operation`].
+///
+/// A ray is followed through every intersection with both shapes, tracking
+/// whether it's inside each, and the combination's surface is wherever being
+/// inside it changes. The inside of the second shape of a difference shows
+/// through as a concave surface, with its normals turned to face out of the
+/// result.
+pub struct Csg {
+    pub operation: Operation,
+    pub a: Box<dyn Shape>,
+    pub b: Box<dyn Shape>,
+}
+
+impl Csg {
+    pub fn new<A, B>(operation: Operation, a: A, b: B) -> Csg
+    where
+        A: Shape + 'static,
+        B: Shape + 'static,
+    {
+        Csg {
+            operation,
+            a: Box::new(a),
+            b: Box::new(b),
+        }
+    }
+
+    pub fn union<A: Shape + 'static, B: Shape + 'static>(a: A, b: B) -> Csg {
+        Csg::new(Operation::Union, a, b)
+    }
+
+    pub fn intersection<A: Shape + 'static, B: Shape + 'static>(a: A, b: B) -> Csg {
+        Csg::new(Operation::Intersection, a, b)
+    }
+
+    pub fn difference<A: Shape + 'static, B: Shape + 'static>(a: A, b: B) -> Csg {
+        Csg::new(Operation::Difference, a, b)
+    }
+
+    /// The hit on the combination at `hit`, a hit on shape `side` (0 for
+    /// `a`, 1 for `b`).
+    fn boundary(&self, ray: &Ray, side: usize, hit: &HitRecord) -> HitRecord {
+        let mut outward = if hit.front_face {
+            hit.normal
+        } else {
+            -hit.normal
+        };
+        if self.operation == Operation::Difference && side == 1 {
+            outward = -outward;
+        }
+        let mut boundary = HitRecord::new(ray, hit.t, outward);
+        boundary.set_uv(hit.uv, hit.tangent, hit.bitangent);
+        boundary
+    }
+}
+
+/// Whether a ray starts out inside a shape, given all its hits along it. A
+/// ray from inside meets the surface first on the way out.
+fn starts_inside(hits: &[HitRecord]) -> bool {
+    hits.first().is_some_and(|hit| !hit.front_face)
+}
+
+impl Shape for Csg {
+    fn intersect(&self, ray: &Ray, t_min: Scalar, t_max: Scalar) -> Option<HitRecord> {
+        self.intersect_all(ray)
+            .into_iter()
+            .find(|hit| hit.t > t_min && hit.t < t_max)
+    }
+
+    fn intersect_all(&self, ray: &Ray) -> Vec<HitRecord> {
+        let a = self.a.intersect_all(ray);
+        let b = self.b.intersect_all(ray);
+        let mut inside = [starts_inside(&a), starts_inside(&b)];
+        let mut events: Vec<(usize, HitRecord)> = a
+            .into_iter()
+            .map(|hit| (0, hit))
+            .chain(b.into_iter().map(|hit| (1, hit)))
+            .collect();
+        events.sort_by(|x, y| x.1.t.total_cmp(&y.1.t));
+
+        let mut boundaries = Vec::new();
+        let mut start = 0;
+        while start < events.len() {
+            // Take every hit at this place before judging what changed, so
+            // one shape's surface can hide where the other one's continues.
+            let end = start
+                + events[start..]
+                    .iter()
+                    .take_while(|(_, hit)| hit.t - events[start].1.t < COINCIDENT)
+                    .count();
+            let before = inside;
+            for (side, hit) in &events[start..end] {
+                inside[*side] = hit.front_face;
+            }
+            let was_inside = self.operation.contains(before[0], before[1]);
+            let is_inside = self.operation.contains(inside[0], inside[1]);
+            if was_inside != is_inside {
+                // The surface is that of a shape that alone makes the change.
+                let (side, hit) = events[start..end]
+                    .iter()
+                    .find(|(side, hit)| {
+                        let mut alone = before;
+                        alone[*side] = hit.front_face;
+                        self.operation.contains(alone[0], alone[1]) == is_inside
+                    })
+                    .unwrap_or(&events[start]);
+                boundaries.push(self.boundary(ray, *side, hit));
+            }
+            start = end;
+        }
+        boundaries
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let (a, b) = (self.a.bounds(), self.b.bounds());
+        match self.operation {
+            Operation::Union => Some(a?.union(&b?)),
+            Operation::Intersection => match (a, b) {
+                (Some(a), Some(b)) => Some(a.intersection(&b)),
+                (a, b) => a.or(b),
+            },
+            Operation::Difference => a,
+        }
+    }
+
+    fn memory_bytes(&self) -> usize {
+        std::mem::size_of_val(self)
+            + self.a.as_ref().memory_bytes()
+            + self.b.as_ref().memory_bytes()
+    }
+
+    fn triangle_count(&self) -> usize {
+        self.a.triangle_count() + self.b.triangle_count()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        self.a.validate().map_err(check::within("a"))?;
+        self.b.validate().map_err(check::within("b"))
+    }
+}
@@ -0,0 +1,138 @@
+use super::{HitRecord, Shape};
+use crate::aabb::Aabb;
+use crate::check;
+use crate::ray::Ray;
+use crate::scalar::Scalar;
+use crate::vector::Vector;
+
+/// The distance along a ray to a face of a box, with the face's axis and
+/// whether it's on the positive side.
+type Crossing = (Scalar, usize, bool);
+
+/// An axis-aligned box between the corners `min` and `max`.
+///
+/// Each face is mapped to `[0, 1]²` as seen from outside the box with `u`
+/// growing to the right and `v` growing down, like the rows of an image.
+/// The side faces are seen upright (+y up); the top face is seen from above
+/// with -z up and the bottom face from below with +z up. On the +x face, for
+/// example, `(0, 0)` is the corner at max y and max z and `(1, 1)` the corner
+/// at min y and min z.
+pub struct Cuboid {
+    pub min: Vector,
+    pub max: Vector,
+}
+
+impl Cuboid {
+    pub fn new(min: Vector, max: Vector) -> Cuboid {
+        Cuboid { min, max }
+    }
+
+    /// The outward normal, surface coordinates and their derivatives at
+    /// `point` on the face perpendicular to `axis` on the `positive` side.
+    fn face(
+        &self,
+        point: Vector,
+        axis: usize,
+        positive: bool,
+    ) -> (Vector, (Scalar, Scalar), Vector, Vector) {
+        let size = self.max - self.min;
+        let from_min = point - self.min;
+        let to_max = self.max - point;
+        let (x, z) = (from_min.x / size.x, from_min.z / size.z);
+        let (rx, ry, rz) = (to_max.x / size.x, to_max.y / size.y, to_max.z / size.z);
+        let (dx, dy, dz) = (
+            Vector::new(size.x, 0.0, 0.0),
+            Vector::new(0.0, size.y, 0.0),
+            Vector::new(0.0, 0.0, size.z),
+        );
+        match (axis, positive) {
+            (0, true) => (Vector::new(1.0, 0.0, 0.0), (rz, ry), -dz, -dy),
+            (0, false) => (Vector::new(-1.0, 0.0, 0.0), (z, ry), dz, -dy),
+            (1, true) => (Vector::new(0.0, 1.0, 0.0), (x, z), dx, dz),
+            (1, false) => (Vector::new(0.0, -1.0, 0.0), (x, rz), dx, -dz),
+            (_, true) => (Vector::new(0.0, 0.0, 1.0), (x, ry), dx, -dy),
+            (_, false) => (Vector::new(0.0, 0.0, -1.0), (rx, ry), -dx, -dy),
+        }
+    }
+
+    /// Where the ray enters and leaves the box, each with the axis and side
+    /// of the face it crosses, unless it misses.
+    fn crossings(&self, ray: &Ray) -> Option<(Crossing, Crossing)> {
+        let origin = [ray.origin.x, ray.origin.y, ray.origin.z];
+        let direction = [ray.direction.x, ray.direction.y, ray.direction.z];
+        let min = [self.min.x, self.min.y, self.min.z];
+        let max = [self.max.x, self.max.y, self.max.z];
+        // The latest entry and earliest exit over the three slabs, with the
+        // face each happens through.
+        let mut enter = (Scalar::NEG_INFINITY, 0, false);
+        let mut exit = (Scalar::INFINITY, 0, false);
+        for axis in 0..3 {
+            if direction[axis] == 0.0 {
+                if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                    return None;
+                }
+                continue;
+            }
+            let t0 = (min[axis] - origin[axis]) / direction[axis];
+            let t1 = (max[axis] - origin[axis]) / direction[axis];
+            let positive = direction[axis] < 0.0;
+            let (near, far) = if positive { (t1, t0) } else { (t0, t1) };
+            if near > enter.0 {
+                enter = (near, axis, positive);
+            }
+            if far < exit.0 {
+                exit = (far, axis, !positive);
+            }
+        }
+        if enter.0 > exit.0 {
+            return None;
+        }
+        Some((enter, exit))
+    }
+
+    fn hit_at(&self, ray: &Ray, (t, axis, positive): Crossing) -> HitRecord {
+        let (normal, uv, tangent, bitangent) = self.face(ray.at(t), axis, positive);
+        let mut hit = HitRecord::new(ray, t, normal);
+        hit.set_uv(uv, tangent, bitangent);
+        hit
+    }
+}
+
+impl Shape for Cuboid {
+    fn intersect(&self, ray: &Ray, t_min: Scalar, t_max: Scalar) -> Option<HitRecord> {
+        let (enter, exit) = self.crossings(ray)?;
+        let crossing = if enter.0 > t_min && enter.0 < t_max {
+            enter
+        } else if exit.0 > t_min && exit.0 < t_max {
+            exit
+        } else {
+            return None;
+        };
+        Some(self.hit_at(ray, crossing))
+    }
+
+    fn intersect_all(&self, ray: &Ray) -> Vec<HitRecord> {
+        match self.crossings(ray) {
+            // A ray only touching an edge or skimming a face never gets
+            // inside.
+            Some((enter, exit)) if enter.0 < exit.0 => {
+                vec![self.hit_at(ray, enter), self.hit_at(ray, exit)]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        Some(Aabb::new(self.min, self.max))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        check::point("min", self.min)?;
+        check::point("max", self.max)?;
+        let size = self.max - self.min;
+        if size.x <= 0.0 || size.y <= 0.0 || size.z <= 0.0 {
+            return Err("max: expected a corner above min on every axis".to_string());
+        }
+        Ok(())
+    }
+}
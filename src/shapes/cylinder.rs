@@ -0,0 +1,221 @@
+use super::{HitRecord, Shape};
+use crate::aabb::Aabb;
+use crate::check;
+use crate::ray::Ray;
+use crate::scalar::{consts::PI, Scalar};
+use crate::vector::Vector;
+
+/// Surface coordinates with their derivatives ∂p/∂u and ∂p/∂v.
+type Surface = ((Scalar, Scalar), Vector, Vector);
+
+/// The surface coordinates at `offset` from the center of a cap facing
+/// `normal_y` (1 for up, -1 for down), as on a [`super::Cuboid`]'s top and
+/// bottom faces.
+fn cap_uv(offset: Vector, radius: Scalar, normal_y: Scalar) -> Surface {
+    (
+        (
+            0.5 + offset.x / (2.0 * radius),
+            0.5 + normal_y * offset.z / (2.0 * radius),
+        ),
+        Vector::new(2.0 * radius, 0.0, 0.0),
+        Vector::new(0.0, 0.0, normal_y * 2.0 * radius),
+    )
+}
+
+/// The angle around the y axis as `u`, as for [`super::spherical_uv`].
+fn angle_u(offset: Vector) -> Scalar {
+    0.5 + offset.x.atan2(offset.z) / (2.0 * PI)
+}
+
+/// How a point at `offset` from the axis moves with the angle `u`.
+fn angle_tangent(offset: Vector) -> Vector {
+    (2.0 * PI) * Vector::new(offset.z, 0.0, -offset.x)
+}
+
+/// A closed cylinder standing on the disk of `radius` around `base`,
+/// extending `height` up the y axis.
+///
+/// The side maps the angle around the axis to `u` like a sphere's longitude
+/// and the height to `v`, from 0 at the top to 1 at the bottom. The caps are
+/// mapped as their enclosing squares seen from outside, the top one with -z
+/// up and the bottom one with +z up.
+pub struct Cylinder {
+    pub base: Vector,
+    pub radius: Scalar,
+    pub height: Scalar,
+}
+
+impl Cylinder {
+    pub fn new(base: Vector, radius: Scalar, height: Scalar) -> Cylinder {
+        Cylinder {
+            base,
+            radius,
+            height,
+        }
+    }
+}
+
+impl Shape for Cylinder {
+    fn intersect(&self, ray: &Ray, t_min: Scalar, mut t_max: Scalar) -> Option<HitRecord> {
+        let o = ray.origin - self.base;
+        let d = ray.direction;
+        let mut nearest: Option<(Scalar, Vector, Surface)> = None;
+
+        let a = d.x * d.x + d.z * d.z;
+        if a > 0.0 {
+            let half_b = o.x * d.x + o.z * d.z;
+            let c = o.x * o.x + o.z * o.z - self.radius * self.radius;
+            let discriminant = half_b * half_b - a * c;
+            if discriminant >= 0.0 {
+                let root = discriminant.sqrt();
+                for t in [(-half_b - root) / a, (-half_b + root) / a] {
+                    let y = o.y + t * d.y;
+                    if t > t_min && t < t_max && (0.0..=self.height).contains(&y) {
+                        let p = o + t * d;
+                        let normal = (1.0 / self.radius) * Vector::new(p.x, 0.0, p.z);
+                        let surface = (
+                            (angle_u(p), 1.0 - y / self.height),
+                            angle_tangent(p),
+                            Vector::new(0.0, -self.height, 0.0),
+                        );
+                        nearest = Some((t, normal, surface));
+                        t_max = t;
+                        break;
+                    }
+                }
+            }
+        }
+        if d.y != 0.0 {
+            for (y, up) in [(0.0, -1.0), (self.height, 1.0)] {
+                let t = (y - o.y) / d.y;
+                let p = o + t * d;
+                if t > t_min && t < t_max && p.x * p.x + p.z * p.z <= self.radius * self.radius {
+                    let surface = cap_uv(p, self.radius, up);
+                    nearest = Some((t, Vector::new(0.0, up, 0.0), surface));
+                    t_max = t;
+                }
+            }
+        }
+        let (t, normal, (uv, tangent, bitangent)) = nearest?;
+        let mut hit = HitRecord::new(ray, t, normal);
+        hit.set_uv(uv, tangent, bitangent);
+        Some(hit)
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let r = self.radius;
+        Some(Aabb::new(
+            self.base - Vector::new(r, 0.0, r),
+            self.base + Vector::new(r, self.height, r),
+        ))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        check::point("base", self.base)?;
+        check::positive("radius", self.radius)?;
+        check::positive("height", self.height)
+    }
+}
+
+/// A closed cone on the disk of `radius` around `base`, with its apex
+/// `height` up the y axis.
+///
+/// The side maps the angle around the axis to `u` and the height to `v`,
+/// from 0 at the apex to 1 at the base; the base is mapped like a
+/// [`Cylinder`]'s bottom cap.
+pub struct Cone {
+    pub base: Vector,
+    pub radius: Scalar,
+    pub height: Scalar,
+}
+
+impl Cone {
+    pub fn new(base: Vector, radius: Scalar, height: Scalar) -> Cone {
+        Cone {
+            base,
+            radius,
+            height,
+        }
+    }
+}
+
+impl Shape for Cone {
+    fn intersect(&self, ray: &Ray, t_min: Scalar, mut t_max: Scalar) -> Option<HitRecord> {
+        let o = ray.origin - self.base;
+        let d = ray.direction;
+        let k2 = (self.radius / self.height).powi(2);
+        // Distance below the apex along the axis.
+        let below = self.height - o.y;
+        let mut nearest: Option<(Scalar, Vector, Surface)> = None;
+
+        let a = d.x * d.x + d.z * d.z - k2 * d.y * d.y;
+        let half_b = o.x * d.x + o.z * d.z + k2 * below * d.y;
+        let c = o.x * o.x + o.z * o.z - k2 * below * below;
+        let mut roots = Vec::with_capacity(2);
+        if a.abs() < 1e-12 {
+            if half_b != 0.0 {
+                roots.push(-c / (2.0 * half_b));
+            }
+        } else {
+            let discriminant = half_b * half_b - a * c;
+            if discriminant >= 0.0 {
+                let root = discriminant.sqrt();
+                let (t0, t1) = ((-half_b - root) / a, (-half_b + root) / a);
+                roots.push(t0.min(t1));
+                roots.push(t0.max(t1));
+            }
+        }
+        for t in roots {
+            let p = o + t * d;
+            if t > t_min && t < t_max && (0.0..=self.height).contains(&p.y) {
+                let normal = Vector::new(p.x, k2 * (self.height - p.y), p.z);
+                let normal = if normal == Vector::zero() {
+                    Vector::new(0.0, 1.0, 0.0)
+                } else {
+                    normal.normalize()
+                };
+                // Down the slant toward the base, which widens by `radius`.
+                let ring = (p.x * p.x + p.z * p.z).sqrt();
+                let outward = if ring > 0.0 {
+                    (self.radius / ring) * Vector::new(p.x, 0.0, p.z)
+                } else {
+                    Vector::zero()
+                };
+                let surface = (
+                    (angle_u(p), 1.0 - p.y / self.height),
+                    angle_tangent(p),
+                    outward - Vector::new(0.0, self.height, 0.0),
+                );
+                nearest = Some((t, normal, surface));
+                t_max = t;
+                break;
+            }
+        }
+        if d.y != 0.0 {
+            let t = -o.y / d.y;
+            let p = o + t * d;
+            if t > t_min && t < t_max && p.x * p.x + p.z * p.z <= self.radius * self.radius {
+                let surface = cap_uv(p, self.radius, -1.0);
+                nearest = Some((t, Vector::new(0.0, -1.0, 0.0), surface));
+            }
+        }
+        let (t, normal, (uv, tangent, bitangent)) = nearest?;
+        let mut hit = HitRecord::new(ray, t, normal);
+        hit.set_uv(uv, tangent, bitangent);
+        Some(hit)
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let r = self.radius;
+        Some(Aabb::new(
+            self.base - Vector::new(r, 0.0, r),
+            self.base + Vector::new(r, self.height, r),
+        ))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        check::point("base", self.base)?;
+        check::positive("radius", self.radius)?;
+        check::positive("height", self.height)
+    }
+}
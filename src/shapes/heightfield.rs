@@ -0,0 +1,257 @@
+use std::mem;
+
+use super::{HitRecord, Shape};
+use crate::aabb::Aabb;
+use crate::check;
+use crate::image::Image;
+use crate::ray::Ray;
+use crate::scalar::Scalar;
+use crate::vector::Vector;
+
+/// A terrain surface over a grid of height samples in the xz plane.
+///
+/// The samples are `columns` across x and as many rows as fill out
+/// `heights`, spread evenly from `corner` over `size.x` and `size.z`. Each
+/// is raised `size.y` times its height above `corner.y`. Within a cell the
+/// surface interpolates the four samples bilinearly, and it's shaded with
+/// normals found from central differences of the heights at the samples.
+///
+/// Rays walk the cells they pass over in order, so only a line of cells is
+/// tested rather than the whole grid. Surface coordinates run over the whole
+/// grid, `u` along x and `v` along z.
+pub struct Heightfield {
+    columns: usize,
+    rows: usize,
+    corner: Vector,
+    /// The width and depth of a cell.
+    spacing: (Scalar, Scalar),
+    /// The height of every sample in world units, row by row.
+    heights: Vec<Scalar>,
+    normals: Vec<Vector>,
+    bounds: Aabb,
+}
+
+impl Heightfield {
+    /// Panics unless there are at least two columns and two rows.
+    pub fn new(heights: &[Scalar], columns: usize, corner: Vector, size: Vector) -> Heightfield {
+        let rows = heights.len().checked_div(columns).unwrap_or(0);
+        assert!(
+            columns >= 2 && rows >= 2 && heights.len() == columns * rows,
+            "a heightfield needs a full grid of at least two by two samples"
+        );
+        let spacing = (
+            size.x / (columns - 1) as Scalar,
+            size.z / (rows - 1) as Scalar,
+        );
+        let heights: Vec<Scalar> = heights.iter().map(|h| corner.y + size.y * h).collect();
+        let (low, high) = heights.iter().fold(
+            (Scalar::INFINITY, Scalar::NEG_INFINITY),
+            |(low, high), &h| (low.min(h), high.max(h)),
+        );
+        let mut field = Heightfield {
+            columns,
+            rows,
+            corner,
+            spacing,
+            heights,
+            normals: Vec::new(),
+            bounds: Aabb::new(
+                Vector::new(corner.x, low, corner.z),
+                Vector::new(corner.x + size.x, high, corner.z + size.z),
+            ),
+        };
+        field.normals = (0..rows)
+            .flat_map(|j| (0..columns).map(move |i| (i, j)))
+            .map(|(i, j)| field.sample_normal(i, j))
+            .collect();
+        field
+    }
+
+    /// Heights from an image's brightness, its top row at the back (-z) when
+    /// seen from above with x to the right.
+    pub fn from_image(image: &Image, corner: Vector, size: Vector) -> Heightfield {
+        let heights: Vec<Scalar> = image
+            .pixels
+            .iter()
+            .map(|pixel| (pixel.x + pixel.y + pixel.z) / 3.0)
+            .collect();
+        Heightfield::new(&heights, image.width as usize, corner, size)
+    }
+
+    fn height(&self, i: usize, j: usize) -> Scalar {
+        self.heights[i + j * self.columns]
+    }
+
+    /// The normal at sample `(i, j)` from the slopes to its neighbors,
+    /// one-sided at the edges.
+    fn sample_normal(&self, i: usize, j: usize) -> Vector {
+        let (left, right) = (i.saturating_sub(1), (i + 1).min(self.columns - 1));
+        let (back, front) = (j.saturating_sub(1), (j + 1).min(self.rows - 1));
+        let dx = (self.height(right, j) - self.height(left, j))
+            / ((right - left) as Scalar * self.spacing.0);
+        let dz = (self.height(i, front) - self.height(i, back))
+            / ((front - back) as Scalar * self.spacing.1);
+        Vector::new(-dx, 1.0, -dz).normalize()
+    }
+
+    /// The nearest hit on cell `(i, j)` between `t_enter` and `t_exit`,
+    /// with `grid` the ray in grid coordinates.
+    fn intersect_cell(
+        &self,
+        ray: &Ray,
+        grid: &Ray,
+        (i, j): (usize, usize),
+        t_enter: Scalar,
+        t_exit: Scalar,
+    ) -> Option<HitRecord> {
+        let h00 = self.height(i, j);
+        let h10 = self.height(i + 1, j);
+        let h01 = self.height(i, j + 1);
+        let h11 = self.height(i + 1, j + 1);
+        // Skip cells the ray passes entirely above or below.
+        let (y0, y1) = (ray.at(t_enter).y, ray.at(t_exit).y);
+        let (low, high) = (
+            h00.min(h10).min(h01.min(h11)),
+            h00.max(h10).max(h01.max(h11)),
+        );
+        if y0.min(y1) > high || y0.max(y1) < low {
+            return None;
+        }
+        // The patch is h00 + a u + b v + c u v, with u and v running across
+        // the cell; along the ray they're linear in t, so the height the ray
+        // meets is a quadratic in t.
+        let (a, b, c) = (h10 - h00, h01 - h00, h00 - h10 - h01 + h11);
+        let (u0, v0) = (grid.origin.x - i as Scalar, grid.origin.z - j as Scalar);
+        let (du, dv) = (grid.direction.x, grid.direction.z);
+        let quadratic = -c * du * dv;
+        let linear = ray.direction.y - (a * du + b * dv + c * (u0 * dv + v0 * du));
+        let constant = ray.origin.y - (h00 + a * u0 + b * v0 + c * u0 * v0);
+        let t = first_root(quadratic, linear, constant, t_enter, t_exit)?;
+
+        let (u, v) = (u0 + t * du, v0 + t * dv);
+        let (slope_u, slope_v) = (a + c * v, b + c * u);
+        let (width, depth) = self.spacing;
+        let outward = Vector::new(-slope_u / width, 1.0, -slope_v / depth).normalize();
+        let mut hit = HitRecord::new(ray, t, outward);
+        let lerp = |p: Vector, q: Vector, s: Scalar| p + s * (q - p);
+        let shading = lerp(
+            lerp(self.normal(i, j), self.normal(i + 1, j), u),
+            lerp(self.normal(i, j + 1), self.normal(i + 1, j + 1), u),
+            v,
+        )
+        .normalize();
+        hit.shading_normal = if hit.front_face { shading } else { -shading };
+        let (across, down) = ((self.columns - 1) as Scalar, (self.rows - 1) as Scalar);
+        hit.set_uv(
+            ((i as Scalar + u) / across, (j as Scalar + v) / down),
+            across * Vector::new(width, slope_u, 0.0),
+            down * Vector::new(0.0, slope_v, depth),
+        );
+        Some(hit)
+    }
+
+    fn normal(&self, i: usize, j: usize) -> Vector {
+        self.normals[i + j * self.columns]
+    }
+}
+
+/// The smallest root of `a t² + b t + c` between `low` and `high`.
+fn first_root(a: Scalar, b: Scalar, c: Scalar, low: Scalar, high: Scalar) -> Option<Scalar> {
+    let within = |t: Scalar| t >= low && t <= high;
+    if a.abs() < 1e-12 {
+        let t = -c / b;
+        return Some(t).filter(|&t| within(t));
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    // Avoids cancellation between b and the root.
+    let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+    let (t0, t1) = (q / a, c / q);
+    let (near, far) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+    [near, far].iter().copied().find(|&t| within(t))
+}
+
+impl Shape for Heightfield {
+    fn intersect(&self, ray: &Ray, t_min: Scalar, t_max: Scalar) -> Option<HitRecord> {
+        let (t_start, t_end) = self.bounds.clip(ray, t_min, t_max)?;
+        let (width, depth) = self.spacing;
+        // The ray in grid coordinates, where sample (i, j) is at x = i and
+        // z = j.
+        let grid = Ray::new(
+            Vector::new(
+                (ray.origin.x - self.corner.x) / width,
+                0.0,
+                (ray.origin.z - self.corner.z) / depth,
+            ),
+            Vector::new(ray.direction.x / width, 0.0, ray.direction.z / depth),
+        );
+        let start = grid.at(t_start);
+        let last = (self.columns - 2, self.rows - 2);
+        let clamp = |x: Scalar, last: usize| (x.floor().max(0.0) as usize).min(last);
+        let (mut i, mut j) = (clamp(start.x, last.0), clamp(start.z, last.1));
+
+        // Where the ray next crosses a cell boundary on each axis, and how
+        // far apart those crossings are.
+        let axis = |origin: Scalar, direction: Scalar, cell: usize| {
+            if direction > 0.0 {
+                (
+                    (cell as Scalar + 1.0 - origin) / direction,
+                    1.0 / direction,
+                    1,
+                )
+            } else if direction < 0.0 {
+                ((cell as Scalar - origin) / direction, -1.0 / direction, -1)
+            } else {
+                (Scalar::INFINITY, Scalar::INFINITY, 0)
+            }
+        };
+        let (mut next_x, delta_x, step_x) = axis(grid.origin.x, grid.direction.x, i);
+        let (mut next_z, delta_z, step_z) = axis(grid.origin.z, grid.direction.z, j);
+
+        let mut t_enter = t_start;
+        loop {
+            let t_exit = next_x.min(next_z).min(t_end);
+            let hit = self.intersect_cell(ray, &grid, (i, j), t_enter, t_exit);
+            if let Some(hit) = hit.filter(|hit| hit.t > t_min && hit.t < t_max) {
+                return Some(hit);
+            }
+            if t_exit >= t_end {
+                return None;
+            }
+            t_enter = t_exit;
+            let (cell, step, last) = if next_x < next_z {
+                next_x += delta_x;
+                (&mut i, step_x, last.0)
+            } else {
+                next_z += delta_z;
+                (&mut j, step_z, last.1)
+            };
+            match cell.checked_add_signed(step) {
+                Some(moved) if moved <= last => *cell = moved,
+                _ => return None,
+            }
+        }
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        Some(self.bounds)
+    }
+
+    fn memory_bytes(&self) -> usize {
+        mem::size_of_val(self)
+            + self.heights.capacity() * mem::size_of::<Scalar>()
+            + self.normals.capacity() * mem::size_of::<Vector>()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        check::point("corner", self.corner)?;
+        check::positive("size.x", self.spacing.0)?;
+        check::positive("size.z", self.spacing.1)?;
+        for (i, &height) in self.heights.iter().enumerate() {
+            check::finite(&format!("heights[{}]", i), height)?;
+        }
+        Ok(())
+    }
+}
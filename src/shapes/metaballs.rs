@@ -0,0 +1,267 @@
+use std::mem;
+
+use super::{HitRecord, Shape};
+use crate::aabb::Aabb;
+use crate::check;
+use crate::ray::Ray;
+use crate::scalar::Scalar;
+use crate::vector::Vector;
+
+/// One point of a [`Metaballs`] field, contributing `weight` at its center
+/// and falling smoothly to nothing at `radius` from it.
+#[derive(Debug, Copy, Clone)]
+pub struct Blob {
+    pub center: Vector,
+    pub radius: Scalar,
+    pub weight: Scalar,
+}
+
+impl Blob {
+    pub fn new(center: Vector, radius: Scalar, weight: Scalar) -> Blob {
+        Blob {
+            center,
+            radius,
+            weight,
+        }
+    }
+
+    /// The gradient at `point` of the blob's field, `weight · (1 - r²/R²)³`
+    /// within its radius. The cube makes the field's curvature fade out at
+    /// the radius too, so the surface doesn't change shape abruptly where
+    /// another blob's reach ends.
+    fn gradient(&self, point: Vector) -> Vector {
+        let offset = point - self.center;
+        let r2 = self.radius * self.radius;
+        let s = (offset * offset) / r2;
+        if s < 1.0 {
+            (-6.0 * self.weight * (1.0 - s) * (1.0 - s) / r2) * offset
+        } else {
+            Vector::zero()
+        }
+    }
+
+    /// The radius of the sphere this blob makes on its own at `threshold`,
+    /// or `None` if its weight never reaches it.
+    pub fn surface_radius(&self, threshold: Scalar) -> Option<Scalar> {
+        if threshold >= self.weight || threshold <= 0.0 {
+            return None;
+        }
+        Some(self.radius * (1.0 - (threshold / self.weight).cbrt()).sqrt())
+    }
+}
+
+/// A blobby surface where the summed fields of several [`Blob`]s reach
+/// `threshold`. Blobs far apart are round; brought together, their fields add
+/// up between them and they merge smoothly into one.
+///
+/// Along a ray each blob's field is a polynomial in `t` where the ray passes
+/// within its radius, so between the places where the ray enters and leaves
+/// blobs the sum is one polynomial, whose roots are found exactly. Normals are
+/// the field's gradient. There are no surface coordinates.
+pub struct Metaballs {
+    pub blobs: Vec<Blob>,
+    pub threshold: Scalar,
+}
+
+/// Coefficients of a polynomial of degree six at most, lowest first.
+type Polynomial = [Scalar; 7];
+
+impl Metaballs {
+    pub fn new(blobs: Vec<Blob>, threshold: Scalar) -> Metaballs {
+        Metaballs { blobs, threshold }
+    }
+
+    /// Every `t` where the field along the ray crosses the threshold between
+    /// `t_min` and `t_max`, nearest first.
+    fn crossings(&self, ray: &Ray, t_min: Scalar, t_max: Scalar) -> Vec<Scalar> {
+        // The polynomials' coefficients grow with the sixth power of how far
+        // the ray starts from the blobs, and cancel out near the surface, so
+        // measure `t` from where the ray passes the blobs' middle instead.
+        let middle = self
+            .blobs
+            .iter()
+            .fold(Vector::zero(), |sum, blob| sum + blob.center);
+        let middle = (1.0 / self.blobs.len().max(1) as Scalar) * middle;
+        let shift = ((middle - ray.origin) * ray.direction) / (ray.direction * ray.direction);
+        if !shift.is_finite() {
+            return self.crossings_from(ray, t_min, t_max);
+        }
+        let near = Ray::new(ray.at(shift), ray.direction);
+        self.crossings_from(&near, t_min - shift, t_max - shift)
+            .into_iter()
+            .map(|t| t + shift)
+            .filter(|&t| t > t_min && t < t_max)
+            .collect()
+    }
+
+    /// [`Metaballs::crossings`], with `t` measured from the ray's origin.
+    fn crossings_from(&self, ray: &Ray, t_min: Scalar, t_max: Scalar) -> Vec<Scalar> {
+        // Each blob's field along the ray, and where the ray is within it.
+        let mut spans: Vec<(Scalar, Scalar, Polynomial)> = Vec::new();
+        for blob in &self.blobs {
+            let offset = ray.origin - blob.center;
+            let r2 = blob.radius * blob.radius;
+            // s = r²/R² along the ray, a quadratic in t.
+            let (a, b, c) = (
+                (ray.direction * ray.direction) / r2,
+                2.0 * (ray.direction * offset) / r2,
+                (offset * offset) / r2,
+            );
+            let discriminant = b * b - 4.0 * a * (c - 1.0);
+            if discriminant <= 0.0 {
+                continue;
+            }
+            let root = discriminant.sqrt();
+            let (enter, exit) = ((-b - root) / (2.0 * a), (-b + root) / (2.0 * a));
+            let mut field = [blob.weight, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+            for _ in 0..3 {
+                field = times(&field, [1.0 - c, -b, -a]);
+            }
+            spans.push((enter, exit, field));
+        }
+        let mut breaks: Vec<Scalar> = spans
+            .iter()
+            .flat_map(|&(enter, exit, _)| [enter, exit])
+            .map(|t| t.clamp(t_min, t_max))
+            .collect();
+        breaks.sort_by(|a, b| a.total_cmp(b));
+        breaks.dedup();
+
+        let mut crossings = Vec::new();
+        for pair in breaks.windows(2) {
+            let (start, end) = (pair[0], pair[1]);
+            let middle = 0.5 * (start + end);
+            let mut sum = [-self.threshold, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+            for (_, _, field) in spans
+                .iter()
+                .filter(|&&(enter, exit, _)| enter < middle && middle < exit)
+            {
+                for (total, coefficient) in sum.iter_mut().zip(field) {
+                    *total += coefficient;
+                }
+            }
+            for t in roots(&sum, start, end) {
+                if t > t_min && t < t_max && crossings.last().is_none_or(|&last| t > last) {
+                    crossings.push(t);
+                }
+            }
+        }
+        crossings
+    }
+
+    fn hit_at(&self, ray: &Ray, t: Scalar) -> HitRecord {
+        let point = ray.at(t);
+        let gradient: Vector = self
+            .blobs
+            .iter()
+            .fold(Vector::zero(), |sum, blob| sum + blob.gradient(point));
+        // The field grows inward, so the outward normal is against it.
+        HitRecord::new(ray, t, -gradient.normalize())
+    }
+}
+
+/// `p · q`, for products that stay within degree six, so that the terms past
+/// the end would all be zero.
+fn times(p: &Polynomial, q: [Scalar; 3]) -> Polynomial {
+    let mut product = [0.0; 7];
+    for (i, &a) in p.iter().enumerate() {
+        for (j, &b) in q.iter().enumerate() {
+            if let Some(term) = product.get_mut(i + j) {
+                *term += a * b;
+            }
+        }
+    }
+    product
+}
+
+fn evaluate(coefficients: &[Scalar], t: Scalar) -> Scalar {
+    coefficients.iter().rev().fold(0.0, |sum, &c| sum * t + c)
+}
+
+/// The real roots of a polynomial between `low` and `high`, in order, found
+/// by bisecting between the roots of its derivative, where it's monotonic.
+fn roots(coefficients: &[Scalar], low: Scalar, high: Scalar) -> Vec<Scalar> {
+    let degree = match coefficients.iter().rposition(|&c| c != 0.0) {
+        Some(degree) if degree > 0 => degree,
+        _ => return Vec::new(),
+    };
+    let coefficients = &coefficients[..=degree];
+    let mut points = vec![low];
+    if degree > 1 {
+        let derivative: Vec<Scalar> = coefficients
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(power, &c)| power as Scalar * c)
+            .collect();
+        points.extend(roots(&derivative, low, high));
+    }
+    points.push(high);
+
+    let mut found = Vec::new();
+    for pair in points.windows(2) {
+        let (mut a, mut b) = (pair[0], pair[1]);
+        let (mut fa, fb) = (evaluate(coefficients, a), evaluate(coefficients, b));
+        if fa == 0.0 {
+            found.push(a);
+            continue;
+        }
+        if fa * fb > 0.0 || fb == 0.0 && pair[1] < high {
+            // The next interval starts at a root here, if there is one.
+            continue;
+        }
+        for _ in 0..100 {
+            let middle = 0.5 * (a + b);
+            if middle <= a || middle >= b {
+                break;
+            }
+            let fm = evaluate(coefficients, middle);
+            if (fm < 0.0) == (fa < 0.0) {
+                a = middle;
+                fa = fm;
+            } else {
+                b = middle;
+            }
+        }
+        found.push(0.5 * (a + b));
+    }
+    found
+}
+
+impl Shape for Metaballs {
+    fn intersect(&self, ray: &Ray, t_min: Scalar, t_max: Scalar) -> Option<HitRecord> {
+        let t = *self.crossings(ray, t_min, t_max).first()?;
+        Some(self.hit_at(ray, t))
+    }
+
+    fn intersect_all(&self, ray: &Ray) -> Vec<HitRecord> {
+        self.crossings(ray, Scalar::NEG_INFINITY, Scalar::INFINITY)
+            .into_iter()
+            .map(|t| self.hit_at(ray, t))
+            .collect()
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        self.blobs
+            .iter()
+            .map(|blob| {
+                let r = Vector::new(blob.radius, blob.radius, blob.radius);
+                Aabb::new(blob.center - r, blob.center + r)
+            })
+            .reduce(|a, b| a.union(&b))
+    }
+
+    fn memory_bytes(&self) -> usize {
+        mem::size_of_val(self) + self.blobs.capacity() * mem::size_of::<Blob>()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for (i, blob) in self.blobs.iter().enumerate() {
+            let within = check::within(format!("blobs[{}]", i));
+            check::point("center", blob.center).map_err(&within)?;
+            check::positive("radius", blob.radius).map_err(&within)?;
+            check::finite("weight", blob.weight).map_err(&within)?;
+        }
+        check::positive("threshold", self.threshold)
+    }
+}
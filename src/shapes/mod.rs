@@ -0,0 +1,294 @@
+use std::fmt;
+use std::mem;
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::info;
+use crate::ray::{Lanes, Ray, RayPacket, PACKET_SIZE};
+use crate::scalar::Scalar;
+use crate::vector::Vector;
+
+mod csg;
+mod cuboid;
+mod cylinder;
+mod heightfield;
+mod metaballs;
+mod plane;
+mod quad;
+mod sdf;
+mod sphere;
+mod transformed;
+mod triangle;
+
+pub use self::csg::{Csg, Operation};
+pub use self::cuboid::Cuboid;
+pub use self::cylinder::{Cone, Cylinder};
+pub use self::heightfield::Heightfield;
+pub use self::metaballs::{Blob, Metaballs};
+pub use self::plane::Plane;
+pub use self::quad::Quad;
+pub use self::sdf::{DistanceField, Mandelbulb, RoundedBox, SdfShape, SmoothUnion, Torus};
+pub use self::sphere::{spherical_uv, Sphere};
+pub use self::transformed::Transformed;
+pub use self::triangle::{MeshTriangle, Triangle, TriangleMesh};
+
+#[derive(Debug, Copy, Clone)]
+pub struct HitRecord {
+    pub t: Scalar,
+    pub point: Vector,
+    /// Unit geometric normal, always facing against the incoming ray. Rays
+    /// leaving the surface are checked against it to tell reflection from
+    /// transmission.
+    pub normal: Vector,
+    /// The unit normal materials shade with, on the same side as `normal`.
+    /// The two only differ where a material's normal map bends it.
+    pub shading_normal: Vector,
+    /// Whether the ray hit the side the shape's outward normal points to.
+    pub front_face: bool,
+    /// Surface coordinates for texturing; `(0, 0)` on shapes without a
+    /// parameterization.
+    pub uv: (Scalar, Scalar),
+    /// How the hit point moves with `u` and `v`, ∂p/∂u and ∂p/∂v; zero on
+    /// shapes without a parameterization.
+    pub tangent: Vector,
+    pub bitangent: Vector,
+}
+
+impl HitRecord {
+    pub fn new(ray: &Ray, t: Scalar, outward_normal: Vector) -> HitRecord {
+        let front_face = ray.direction * outward_normal < 0.0;
+        let normal = if front_face {
+            outward_normal
+        } else {
+            -outward_normal
+        };
+        HitRecord {
+            t,
+            point: ray.at(t),
+            normal,
+            shading_normal: normal,
+            front_face,
+            uv: (0.0, 0.0),
+            tangent: Vector::zero(),
+            bitangent: Vector::zero(),
+        }
+    }
+
+    /// Sets the surface coordinates along with their derivatives.
+    pub fn set_uv(&mut self, uv: (Scalar, Scalar), tangent: Vector, bitangent: Vector) {
+        self.uv = uv;
+        self.tangent = tangent;
+        self.bitangent = bitangent;
+    }
+}
+
+/// Where and at what `t` the ray hit, which way the surface faces, and the
+/// surface coordinates if the shape has them.
+impl fmt::Display for HitRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Any precision given is for every number, as it is for `Vector`.
+        let number = |f: &mut fmt::Formatter, x: Scalar| match f.precision() {
+            Some(precision) => write!(f, "{:.*}", precision, x),
+            None => write!(f, "{}", x),
+        };
+        let side = if self.front_face { "front" } else { "back" };
+        f.write_str("hit at t = ")?;
+        number(f, self.t)?;
+        f.write_str(" at ")?;
+        fmt::Display::fmt(&self.point, f)?;
+        write!(f, ", {} face, normal ", side)?;
+        fmt::Display::fmt(&self.normal, f)?;
+        if self.uv != (0.0, 0.0) {
+            f.write_str(", uv (")?;
+            number(f, self.uv.0)?;
+            f.write_str(", ")?;
+            number(f, self.uv.1)?;
+            f.write_str(")")?;
+        }
+        Ok(())
+    }
+}
+
+/// A point picked on a shape's surface, with its density per unit area.
+#[derive(Debug, Copy, Clone)]
+pub struct SurfaceSample {
+    pub point: Vector,
+    /// The outward unit normal at `point`.
+    pub normal: Vector,
+    pub pdf: Scalar,
+}
+
+pub trait Shape: Send + Sync {
+    /// Finds the nearest intersection with `t` strictly between `t_min` and
+    /// `t_max`.
+    fn intersect(&self, ray: &Ray, t_min: Scalar, t_max: Scalar) -> Option<HitRecord>;
+
+    /// Every intersection along the whole line of the ray, behind its origin
+    /// too, nearest first. A [`Csg`] uses these to tell where the ray is
+    /// inside its shapes. By default they're found one after another with
+    /// `intersect`.
+    fn intersect_all(&self, ray: &Ray) -> Vec<HitRecord> {
+        let mut hits = Vec::new();
+        let mut t = Scalar::NEG_INFINITY;
+        while let Some(hit) = self.intersect(ray, t, Scalar::INFINITY) {
+            t = hit.t;
+            hits.push(hit);
+        }
+        hits
+    }
+
+    /// [`intersect`](Shape::intersect) for each ray of the packet in
+    /// `lanes`, each with its own `t_max`. By default the rays are
+    /// intersected one at a time.
+    fn intersect_packet(
+        &self,
+        packet: &RayPacket,
+        lanes: Lanes<bool>,
+        t_min: Scalar,
+        t_max: &Lanes<Scalar>,
+    ) -> Lanes<Option<HitRecord>> {
+        let mut hits = [None; PACKET_SIZE];
+        for (i, hit) in hits.iter_mut().enumerate() {
+            if lanes[i] {
+                *hit = self.intersect(&packet.rays[i], t_min, t_max[i]);
+            }
+        }
+        hits
+    }
+
+    /// Picks a point uniformly by area, for shapes that can be area lights.
+    fn sample_surface(&self, _u1: Scalar, _u2: Scalar) -> Option<SurfaceSample> {
+        None
+    }
+
+    /// The density per unit area of `sample_surface` at a point on the shape
+    /// with the unit surface normal `normal`, facing either way.
+    fn surface_pdf(&self, _point: Vector, _normal: Vector) -> Scalar {
+        0.0
+    }
+
+    /// A box enclosing the shape, or `None` if it is unbounded.
+    fn bounds(&self) -> Option<Aabb> {
+        None
+    }
+
+    /// Roughly how much memory the shape holds, including what it owns on
+    /// the heap.
+    fn memory_bytes(&self) -> usize {
+        mem::size_of_val(self)
+    }
+
+    /// What kind of shape this is, such as `Sphere`, for describing a scene.
+    fn name(&self) -> &'static str {
+        info::type_name::<Self>()
+    }
+
+    /// How many triangles the shape is made of, 0 for shapes that aren't.
+    fn triangle_count(&self) -> usize {
+        0
+    }
+
+    /// Checks that the shape's parameters make sense, such as a positive
+    /// radius, with an error that starts with the field that doesn't.
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Shared geometry, so that many objects can refer to one shape. Its memory
+/// is counted wherever the shape itself is kept, not in each handle.
+impl<S: Shape + ?Sized> Shape for Arc<S> {
+    fn intersect(&self, ray: &Ray, t_min: Scalar, t_max: Scalar) -> Option<HitRecord> {
+        (**self).intersect(ray, t_min, t_max)
+    }
+
+    fn intersect_all(&self, ray: &Ray) -> Vec<HitRecord> {
+        (**self).intersect_all(ray)
+    }
+
+    fn intersect_packet(
+        &self,
+        packet: &RayPacket,
+        lanes: Lanes<bool>,
+        t_min: Scalar,
+        t_max: &Lanes<Scalar>,
+    ) -> Lanes<Option<HitRecord>> {
+        (**self).intersect_packet(packet, lanes, t_min, t_max)
+    }
+
+    fn sample_surface(&self, u1: Scalar, u2: Scalar) -> Option<SurfaceSample> {
+        (**self).sample_surface(u1, u2)
+    }
+
+    fn surface_pdf(&self, point: Vector, normal: Vector) -> Scalar {
+        (**self).surface_pdf(point, normal)
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        (**self).bounds()
+    }
+
+    fn memory_bytes(&self) -> usize {
+        mem::size_of::<Self>()
+    }
+
+    fn name(&self) -> &'static str {
+        (**self).name()
+    }
+
+    fn triangle_count(&self) -> usize {
+        (**self).triangle_count()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        (**self).validate()
+    }
+}
+
+impl<S: Shape + ?Sized> Shape for Box<S> {
+    fn intersect(&self, ray: &Ray, t_min: Scalar, t_max: Scalar) -> Option<HitRecord> {
+        (**self).intersect(ray, t_min, t_max)
+    }
+
+    fn intersect_all(&self, ray: &Ray) -> Vec<HitRecord> {
+        (**self).intersect_all(ray)
+    }
+
+    fn intersect_packet(
+        &self,
+        packet: &RayPacket,
+        lanes: Lanes<bool>,
+        t_min: Scalar,
+        t_max: &Lanes<Scalar>,
+    ) -> Lanes<Option<HitRecord>> {
+        (**self).intersect_packet(packet, lanes, t_min, t_max)
+    }
+
+    fn sample_surface(&self, u1: Scalar, u2: Scalar) -> Option<SurfaceSample> {
+        (**self).sample_surface(u1, u2)
+    }
+
+    fn surface_pdf(&self, point: Vector, normal: Vector) -> Scalar {
+        (**self).surface_pdf(point, normal)
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        (**self).bounds()
+    }
+
+    fn memory_bytes(&self) -> usize {
+        mem::size_of::<Self>() + (**self).memory_bytes()
+    }
+
+    fn name(&self) -> &'static str {
+        (**self).name()
+    }
+
+    fn triangle_count(&self) -> usize {
+        (**self).triangle_count()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        (**self).validate()
+    }
+}
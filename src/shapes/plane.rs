@@ -0,0 +1,56 @@
+use super::{HitRecord, Shape};
+use crate::check;
+use crate::ray::Ray;
+use crate::sampling::OrthonormalBasis;
+use crate::scalar::Scalar;
+use crate::vector::Vector;
+
+/// An infinite plane through `point`.
+///
+/// Its surface coordinates are distances from `point` along two tangents
+/// picked from the normal; for a floor facing +y they run along +x and +z.
+pub struct Plane {
+    pub point: Vector,
+    pub normal: Vector,
+    tangent: Vector,
+    bitangent: Vector,
+}
+
+impl Plane {
+    pub fn new(point: Vector, normal: Vector) -> Plane {
+        let normal = normal.normalize();
+        let basis = OrthonormalBasis::from_normal(normal);
+        Plane {
+            point,
+            normal,
+            tangent: basis.u,
+            bitangent: -basis.v,
+        }
+    }
+}
+
+impl Shape for Plane {
+    fn intersect(&self, ray: &Ray, t_min: Scalar, t_max: Scalar) -> Option<HitRecord> {
+        let denominator = self.normal * ray.direction;
+        if denominator.abs() < 1e-12 {
+            return None;
+        }
+        let t = (self.normal * (self.point - ray.origin)) / denominator;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+        let mut hit = HitRecord::new(ray, t, self.normal);
+        let offset = hit.point - self.point;
+        hit.set_uv(
+            (offset * self.tangent, offset * self.bitangent),
+            self.tangent,
+            self.bitangent,
+        );
+        Some(hit)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        check::point("point", self.point)?;
+        check::direction("normal", self.normal)
+    }
+}
@@ -0,0 +1,89 @@
+use super::{HitRecord, Shape, SurfaceSample};
+use crate::aabb::Aabb;
+use crate::check;
+use crate::ray::Ray;
+use crate::scalar::Scalar;
+use crate::vector::Vector;
+
+/// The parallelogram spanned by `u` and `v` from `corner`, facing `u × v`.
+/// Its surface coordinates are the fractions of `u` and `v` to a point, so
+/// `corner` is at `(0, 0)` and `corner + u + v` at `(1, 1)`.
+pub struct Quad {
+    pub corner: Vector,
+    pub u: Vector,
+    pub v: Vector,
+    normal: Vector,
+    // Scaled normal used to project hit points onto (u, v) coordinates.
+    w: Vector,
+}
+
+impl Quad {
+    pub fn new(corner: Vector, u: Vector, v: Vector) -> Quad {
+        let n = u.cross(v);
+        Quad {
+            corner,
+            u,
+            v,
+            normal: n.normalize(),
+            w: (1.0 / (n * n)) * n,
+        }
+    }
+
+    pub fn area(&self) -> Scalar {
+        self.u.cross(self.v).len()
+    }
+}
+
+impl Shape for Quad {
+    fn intersect(&self, ray: &Ray, t_min: Scalar, t_max: Scalar) -> Option<HitRecord> {
+        let denominator = self.normal * ray.direction;
+        if denominator.abs() < 1e-12 {
+            return None;
+        }
+        let t = (self.normal * (self.corner - ray.origin)) / denominator;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+        let planar = ray.at(t) - self.corner;
+        let alpha = self.w * planar.cross(self.v);
+        let beta = self.w * self.u.cross(planar);
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+        let mut hit = HitRecord::new(ray, t, self.normal);
+        hit.set_uv((alpha, beta), self.u, self.v);
+        Some(hit)
+    }
+
+    fn sample_surface(&self, u1: Scalar, u2: Scalar) -> Option<SurfaceSample> {
+        Some(SurfaceSample {
+            point: self.corner + u1 * self.u + u2 * self.v,
+            normal: self.normal,
+            pdf: 1.0 / self.area(),
+        })
+    }
+
+    fn surface_pdf(&self, _point: Vector, _normal: Vector) -> Scalar {
+        1.0 / self.area()
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let c = self.corner;
+        Some(Aabb::from_points(&[
+            c,
+            c + self.u,
+            c + self.v,
+            c + self.u + self.v,
+        ]))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        check::point("corner", self.corner)?;
+        check::direction("u", self.u)?;
+        check::direction("v", self.v)?;
+        if self.area() == 0.0 {
+            return Err("v: expected an edge that isn't parallel to u".to_string());
+        }
+        Ok(())
+    }
+}
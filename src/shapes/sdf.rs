@@ -0,0 +1,342 @@
+use super::{HitRecord, Shape, Sphere};
+use crate::aabb::Aabb;
+use crate::check;
+use crate::ray::Ray;
+use crate::scalar::Scalar;
+use crate::vector::Vector;
+
+/// A signed distance function: how far a point is from the nearest surface,
+/// negative inside. It may underestimate but never overestimate, so that a
+/// ray can safely step that far.
+pub trait DistanceField: Send + Sync {
+    fn distance(&self, point: Vector) -> Scalar;
+
+    /// A box the surface lies within, or `None` if it isn't known.
+    fn bounds(&self) -> Option<Aabb> {
+        None
+    }
+}
+
+impl<F: Fn(Vector) -> Scalar + Send + Sync> DistanceField for F {
+    fn distance(&self, point: Vector) -> Scalar {
+        self(point)
+    }
+}
+
+impl DistanceField for Box<dyn DistanceField> {
+    fn distance(&self, point: Vector) -> Scalar {
+        (**self).distance(point)
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        (**self).bounds()
+    }
+}
+
+impl DistanceField for Sphere {
+    fn distance(&self, point: Vector) -> Scalar {
+        (point - self.center).len() - self.radius
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        Shape::bounds(self)
+    }
+}
+
+/// A box centered on `center` reaching `half_size` out along each axis, with
+/// its edges and corners rounded off by `radius`.
+pub struct RoundedBox {
+    pub center: Vector,
+    pub half_size: Vector,
+    pub radius: Scalar,
+}
+
+impl RoundedBox {
+    pub fn new(center: Vector, half_size: Vector, radius: Scalar) -> RoundedBox {
+        RoundedBox {
+            center,
+            half_size,
+            radius,
+        }
+    }
+}
+
+impl DistanceField for RoundedBox {
+    fn distance(&self, point: Vector) -> Scalar {
+        let p = point - self.center;
+        let r = self.radius;
+        // How far outside the box shrunk by the radius, along each axis.
+        let q = Vector::new(
+            p.x.abs() - (self.half_size.x - r),
+            p.y.abs() - (self.half_size.y - r),
+            p.z.abs() - (self.half_size.z - r),
+        );
+        let outside = Vector::new(q.x.max(0.0), q.y.max(0.0), q.z.max(0.0)).len();
+        outside + q.max_component().min(0.0) - r
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        Some(Aabb::new(
+            self.center - self.half_size,
+            self.center + self.half_size,
+        ))
+    }
+}
+
+/// A ring around the y axis through `center`: the points within
+/// `minor_radius` of the circle of `major_radius`.
+pub struct Torus {
+    pub center: Vector,
+    pub major_radius: Scalar,
+    pub minor_radius: Scalar,
+}
+
+impl Torus {
+    pub fn new(center: Vector, major_radius: Scalar, minor_radius: Scalar) -> Torus {
+        Torus {
+            center,
+            major_radius,
+            minor_radius,
+        }
+    }
+}
+
+impl DistanceField for Torus {
+    fn distance(&self, point: Vector) -> Scalar {
+        let p = point - self.center;
+        let ring = (p.x * p.x + p.z * p.z).sqrt() - self.major_radius;
+        (ring * ring + p.y * p.y).sqrt() - self.minor_radius
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let (outer, r) = (self.major_radius + self.minor_radius, self.minor_radius);
+        let reach = Vector::new(outer, r, outer);
+        Some(Aabb::new(self.center - reach, self.center + reach))
+    }
+}
+
+/// The union of two fields with the crease where they meet filled in over
+/// about `smoothness`, so that they blend into one surface.
+pub struct SmoothUnion {
+    pub a: Box<dyn DistanceField>,
+    pub b: Box<dyn DistanceField>,
+    pub smoothness: Scalar,
+}
+
+impl SmoothUnion {
+    pub fn new<A, B>(a: A, b: B, smoothness: Scalar) -> SmoothUnion
+    where
+        A: DistanceField + 'static,
+        B: DistanceField + 'static,
+    {
+        SmoothUnion {
+            a: Box::new(a),
+            b: Box::new(b),
+            smoothness,
+        }
+    }
+}
+
+impl DistanceField for SmoothUnion {
+    fn distance(&self, point: Vector) -> Scalar {
+        let (a, b) = (self.a.distance(point), self.b.distance(point));
+        let k = self.smoothness;
+        if k <= 0.0 {
+            return a.min(b);
+        }
+        // A polynomial smooth minimum, which equals the plain one wherever
+        // the two differ by more than `k`.
+        let h = (0.5 + 0.5 * (b - a) / k).clamp(0.0, 1.0);
+        b + h * (a - b) - k * h * (1.0 - h)
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        // The blend bulges out of both by at most a quarter of `smoothness`.
+        let bounds = self.a.bounds()?.union(&self.b.bounds()?);
+        let bulge = 0.25 * self.smoothness.max(0.0);
+        let bulge = Vector::new(bulge, bulge, bulge);
+        Some(Aabb::new(bounds.min - bulge, bounds.max + bulge))
+    }
+}
+
+/// The Mandelbulb fractal centered on `center`, about a unit in radius,
+/// iterating `z ↦ zⁿ + p` in spherical coordinates with `n` the `power`.
+pub struct Mandelbulb {
+    pub center: Vector,
+    pub power: Scalar,
+    pub iterations: usize,
+}
+
+impl Mandelbulb {
+    pub fn new(center: Vector, power: Scalar, iterations: usize) -> Mandelbulb {
+        Mandelbulb {
+            center,
+            power,
+            iterations,
+        }
+    }
+}
+
+impl DistanceField for Mandelbulb {
+    fn distance(&self, point: Vector) -> Scalar {
+        let c = point - self.center;
+        let n = self.power;
+        let (mut z, mut r) = (c, c.len());
+        // The running derivative |dz/dc|, for the distance estimate.
+        let mut dr = 1.0;
+        for _ in 0..self.iterations {
+            if r > 2.0 || r == 0.0 {
+                break;
+            }
+            let theta = n * (z.z / r).acos();
+            let phi = n * z.y.atan2(z.x);
+            dr = n * r.powf(n - 1.0) * dr + 1.0;
+            z = r.powf(n)
+                * Vector::new(
+                    theta.sin() * phi.cos(),
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                )
+                + c;
+            r = z.len();
+        }
+        if r == 0.0 {
+            return 0.0;
+        }
+        0.5 * r.ln() * r / dr
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let reach = Vector::new(1.2, 1.2, 1.2);
+        Some(Aabb::new(self.center - reach, self.center + reach))
+    }
+}
+
+/// The surface where a [`DistanceField`] is zero, found by sphere tracing:
+/// stepping along a ray by the distance to the nearest surface, which can't
+/// overshoot, until that's within `epsilon`.
+///
+/// Rays give up after `max_steps` steps or `max_distance` along the ray,
+/// starting where they enter the field's bounds if it has any. Normals are
+/// the field's gradient, by central differences. There are no surface
+/// coordinates.
+pub struct SdfShape {
+    pub field: Box<dyn DistanceField>,
+    pub max_steps: usize,
+    pub epsilon: Scalar,
+    pub max_distance: Scalar,
+}
+
+impl SdfShape {
+    pub fn new<F: DistanceField + 'static>(field: F) -> SdfShape {
+        SdfShape {
+            field: Box::new(field),
+            max_steps: 256,
+            epsilon: 1e-5,
+            max_distance: 100.0,
+        }
+    }
+
+    fn normal(&self, point: Vector) -> Vector {
+        // Central differences are off by about h² for the field's curvature
+        // and by ε / h for rounding, which balance at h = ∛ε; in `f32` that's
+        // well above `epsilon`, and steps below it only add noise.
+        let h = self
+            .epsilon
+            .max(Scalar::EPSILON.cbrt() * point.len().max(1.0));
+        let along = |offset: Vector| {
+            self.field.distance(point + offset) - self.field.distance(point - offset)
+        };
+        Vector::new(
+            along(Vector::new(h, 0.0, 0.0)),
+            along(Vector::new(0.0, h, 0.0)),
+            along(Vector::new(0.0, 0.0, h)),
+        )
+        .normalize()
+    }
+
+    /// Marches along the ray from `t_min` toward `t_max`, passing each
+    /// surface crossed to `found` until it returns false.
+    fn march(
+        &self,
+        ray: &Ray,
+        t_min: Scalar,
+        t_max: Scalar,
+        mut found: impl FnMut(HitRecord) -> bool,
+    ) {
+        let (mut t, end) = match self.field.bounds() {
+            Some(bounds) => {
+                // Padded so that the ray is clear of the surface it's bounding
+                // when it starts.
+                let pad = (2.0 * self.epsilon) * Vector::new(1.0, 1.0, 1.0);
+                let padded = Aabb::new(bounds.min - pad, bounds.max + pad);
+                match padded.clip(ray, t_min, t_max) {
+                    Some(range) => range,
+                    None => return,
+                }
+            }
+            None => (t_min, t_max),
+        };
+        // Distances are in the field's units, but steps are in `t`.
+        let speed = ray.direction.len();
+        let reach = self.max_distance / speed;
+        if !t.is_finite() {
+            t = -reach;
+        }
+        let end = end.min(t + reach);
+        // A crossing only counts once the ray has been clear of the surface,
+        // so that one leaving it doesn't find it again straight away.
+        let mut clear = false;
+        for _ in 0..self.max_steps {
+            if t >= end {
+                break;
+            }
+            let point = ray.at(t);
+            let distance = self.field.distance(point);
+            if distance.abs() < self.epsilon {
+                if clear && t > t_min {
+                    let hit = HitRecord::new(ray, t, self.normal(point));
+                    if !found(hit) {
+                        return;
+                    }
+                }
+                clear = false;
+            } else {
+                clear = true;
+            }
+            t += distance.abs().max(self.epsilon) / speed;
+        }
+    }
+}
+
+impl Shape for SdfShape {
+    fn intersect(&self, ray: &Ray, t_min: Scalar, t_max: Scalar) -> Option<HitRecord> {
+        let mut nearest = None;
+        self.march(ray, t_min, t_max, |hit| {
+            nearest = Some(hit);
+            false
+        });
+        nearest
+    }
+
+    fn intersect_all(&self, ray: &Ray) -> Vec<HitRecord> {
+        let mut hits = Vec::new();
+        self.march(ray, Scalar::NEG_INFINITY, Scalar::INFINITY, |hit| {
+            hits.push(hit);
+            true
+        });
+        hits
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        self.field.bounds()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.max_steps == 0 {
+            return Err("max_steps: expected at least one step".to_string());
+        }
+        check::positive("epsilon", self.epsilon)?;
+        check::positive("max_distance", self.max_distance)
+    }
+}
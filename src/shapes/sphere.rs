@@ -0,0 +1,151 @@
+use super::{HitRecord, Shape, SurfaceSample};
+use crate::aabb::Aabb;
+use crate::check;
+use crate::ray::{Lanes, Ray, RayPacket, PACKET_SIZE};
+use crate::sampling::sample_uniform_sphere;
+use crate::scalar::{consts::PI, Scalar};
+use crate::vector::Vector;
+
+/// Longitude and latitude of a unit `direction` as `(u, v)` in `[0, 1]`, the
+/// equirectangular mapping also used for environment maps.
+///
+/// `v` runs from 0 at the north pole (+y) to 1 at the south pole, so it grows
+/// down the rows of a map image. `u` is 0.5 on the prime meridian, which
+/// faces +z, and grows eastward toward +x; the seam at `u` = 0 and 1 faces -z.
+pub fn spherical_uv(direction: Vector) -> (Scalar, Scalar) {
+    let u = 0.5 + direction.x.atan2(direction.z) / (2.0 * PI);
+    let v = direction.y.clamp(-1.0, 1.0).acos() / PI;
+    (u, v)
+}
+
+pub struct Sphere {
+    pub center: Vector,
+    pub radius: Scalar,
+}
+
+impl Sphere {
+    pub fn new(center: Vector, radius: Scalar) -> Sphere {
+        Sphere { center, radius }
+    }
+
+    /// Where the ray crosses the sphere, nearest first, unless it misses.
+    fn roots(&self, ray: &Ray) -> Option<(Scalar, Scalar)> {
+        let l = ray.direction;
+        let diff = ray.origin - self.center;
+        let a = l * l;
+        let half_b = l * diff;
+        let discriminant = half_b.powi(2) - a * (diff * diff - self.radius.powi(2));
+        if discriminant < 0.0 {
+            return None;
+        }
+        let root = discriminant.sqrt();
+        Some(((-half_b - root) / a, (-half_b + root) / a))
+    }
+
+    fn hit_at(&self, ray: &Ray, t: Scalar) -> HitRecord {
+        let r = self.radius;
+        let outward_normal = (1.0 / r) * (ray.at(t) - self.center);
+        let mut hit = HitRecord::new(ray, t, outward_normal);
+        let n = outward_normal;
+        // Eastward and southward along the surface; both vanish at the poles.
+        let east = Vector::new(n.z, 0.0, -n.x);
+        let ring = (n.x * n.x + n.z * n.z).sqrt();
+        let south = if ring > 0.0 {
+            Vector::new(n.y * n.x / ring, -ring, n.y * n.z / ring)
+        } else {
+            Vector::zero()
+        };
+        hit.set_uv(spherical_uv(n), (2.0 * PI * r) * east, (PI * r) * south);
+        hit
+    }
+}
+
+impl Shape for Sphere {
+    fn intersect(&self, ray: &Ray, t_min: Scalar, t_max: Scalar) -> Option<HitRecord> {
+        let (near, far) = self.roots(ray)?;
+        let t = if near > t_min && near < t_max {
+            near
+        } else if far > t_min && far < t_max {
+            far
+        } else {
+            return None;
+        };
+        Some(self.hit_at(ray, t))
+    }
+
+    fn intersect_packet(
+        &self,
+        packet: &RayPacket,
+        lanes: Lanes<bool>,
+        t_min: Scalar,
+        t_max: &Lanes<Scalar>,
+    ) -> Lanes<Option<HitRecord>> {
+        // `roots` a lane at a time, with the dot products written out in the
+        // order `Vector` sums them.
+        let [ox, oy, oz] = packet.origin;
+        let [lx, ly, lz] = packet.direction;
+        let mut near = [Scalar::NAN; PACKET_SIZE];
+        let mut far = [Scalar::NAN; PACKET_SIZE];
+        for i in 0..PACKET_SIZE {
+            let diff = (
+                ox[i] - self.center.x,
+                oy[i] - self.center.y,
+                oz[i] - self.center.z,
+            );
+            let a = lx[i] * lx[i] + ly[i] * ly[i] + lz[i] * lz[i];
+            let half_b = lx[i] * diff.0 + ly[i] * diff.1 + lz[i] * diff.2;
+            let c = diff.0 * diff.0 + diff.1 * diff.1 + diff.2 * diff.2;
+            let discriminant = half_b.powi(2) - a * (c - self.radius.powi(2));
+            // A miss leaves NaN, which no range contains.
+            let root = discriminant.sqrt();
+            near[i] = (-half_b - root) / a;
+            far[i] = (-half_b + root) / a;
+        }
+        let mut hits = [None; PACKET_SIZE];
+        for (i, hit) in hits.iter_mut().enumerate() {
+            if !lanes[i] {
+                continue;
+            }
+            let t = if near[i] > t_min && near[i] < t_max[i] {
+                near[i]
+            } else if far[i] > t_min && far[i] < t_max[i] {
+                far[i]
+            } else {
+                continue;
+            };
+            *hit = Some(self.hit_at(&packet.rays[i], t));
+        }
+        hits
+    }
+
+    fn intersect_all(&self, ray: &Ray) -> Vec<HitRecord> {
+        match self.roots(ray) {
+            // A ray only touching the sphere never gets inside.
+            Some((near, far)) if near < far => vec![self.hit_at(ray, near), self.hit_at(ray, far)],
+            _ => Vec::new(),
+        }
+    }
+
+    fn sample_surface(&self, u1: Scalar, u2: Scalar) -> Option<SurfaceSample> {
+        let (normal, _) = sample_uniform_sphere(u1, u2);
+        Some(SurfaceSample {
+            point: self.center + self.radius * normal,
+            normal,
+            pdf: 1.0 / (4.0 * PI * self.radius.powi(2)),
+        })
+    }
+
+    fn surface_pdf(&self, _point: Vector, _normal: Vector) -> Scalar {
+        1.0 / (4.0 * PI * self.radius.powi(2))
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let r = Vector::new(self.radius, self.radius, self.radius);
+        Some(Aabb::new(self.center - r, self.center + r))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        check::point("center", self.center)?;
+        check::positive("radius", self.radius)
+    }
+}
@@ -0,0 +1,148 @@
+use std::mem;
+
+use super::{HitRecord, Shape, SurfaceSample};
+use crate::aabb::Aabb;
+use crate::matrix::Matrix4;
+use crate::ray::Ray;
+use crate::scalar::Scalar;
+use crate::vector::Vector;
+
+/// A shape placed in the world by an affine transform of its own space.
+///
+/// Rays are carried into object space without renormalizing, so distances
+/// along them agree in both spaces. Normals go back through the inverse
+/// transpose, which keeps them perpendicular under non-uniform scaling.
+pub struct Transformed<S: Shape> {
+    pub shape: S,
+    object_to_world: Matrix4,
+    world_to_object: Matrix4,
+    // The inverse transpose, for carrying normals into the world.
+    normal_to_world: Matrix4,
+}
+
+impl<S: Shape> Transformed<S> {
+    /// Panics if `object_to_world` can't be inverted.
+    pub fn new(shape: S, object_to_world: Matrix4) -> Transformed<S> {
+        let world_to_object = object_to_world
+            .inverse()
+            .expect("transform can't be inverted");
+        Transformed {
+            shape,
+            object_to_world,
+            world_to_object,
+            normal_to_world: world_to_object.transpose(),
+        }
+    }
+
+    pub fn object_to_world(&self) -> Matrix4 {
+        self.object_to_world
+    }
+
+    pub fn world_to_object(&self) -> Matrix4 {
+        self.world_to_object
+    }
+
+    fn to_object(&self, ray: &Ray) -> Ray {
+        Ray::new(
+            self.world_to_object.transform_point(ray.origin),
+            self.world_to_object.transform_direction(ray.direction),
+        )
+    }
+
+    /// The world ray's hit matching `inner`, a hit on the shape by the ray
+    /// in object space.
+    fn to_world(&self, ray: &Ray, inner: &HitRecord) -> HitRecord {
+        let outward = if inner.front_face {
+            inner.normal
+        } else {
+            -inner.normal
+        };
+        let normal = self
+            .normal_to_world
+            .transform_direction(outward)
+            .normalize();
+        let mut hit = HitRecord::new(ray, inner.t, normal);
+        hit.set_uv(
+            inner.uv,
+            self.object_to_world.transform_direction(inner.tangent),
+            self.object_to_world.transform_direction(inner.bitangent),
+        );
+        hit
+    }
+
+    /// How much the transform stretches area around a point with the unit
+    /// object-space normal `normal`.
+    fn area_scale(&self, normal: Vector) -> Scalar {
+        self.object_to_world.determinant3().abs()
+            * self.normal_to_world.transform_direction(normal).len()
+    }
+}
+
+impl<S: Shape> Shape for Transformed<S> {
+    fn intersect(&self, ray: &Ray, t_min: Scalar, t_max: Scalar) -> Option<HitRecord> {
+        let inner = self.shape.intersect(&self.to_object(ray), t_min, t_max)?;
+        Some(self.to_world(ray, &inner))
+    }
+
+    fn intersect_all(&self, ray: &Ray) -> Vec<HitRecord> {
+        let hits = self.shape.intersect_all(&self.to_object(ray));
+        hits.iter().map(|inner| self.to_world(ray, inner)).collect()
+    }
+
+    fn sample_surface(&self, u1: Scalar, u2: Scalar) -> Option<SurfaceSample> {
+        let sample = self.shape.sample_surface(u1, u2)?;
+        Some(SurfaceSample {
+            point: self.object_to_world.transform_point(sample.point),
+            normal: self
+                .normal_to_world
+                .transform_direction(sample.normal)
+                .normalize(),
+            pdf: sample.pdf / self.area_scale(sample.normal),
+        })
+    }
+
+    fn surface_pdf(&self, point: Vector, normal: Vector) -> Scalar {
+        // Normals come back to object space through the transpose.
+        let normal = self
+            .object_to_world
+            .transpose()
+            .transform_direction(normal)
+            .normalize();
+        let point = self.world_to_object.transform_point(point);
+        self.shape.surface_pdf(point, normal) / self.area_scale(normal)
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let Aabb { min, max } = self.shape.bounds()?;
+        let corners: Vec<Vector> = (0..8)
+            .map(|i| {
+                let pick =
+                    |bit: usize, low: Scalar, high: Scalar| if i & bit == 0 { low } else { high };
+                let corner = Vector::new(
+                    pick(1, min.x, max.x),
+                    pick(2, min.y, max.y),
+                    pick(4, min.z, max.z),
+                );
+                self.object_to_world.transform_point(corner)
+            })
+            .collect();
+        Some(Aabb::from_points(&corners))
+    }
+
+    fn memory_bytes(&self) -> usize {
+        mem::size_of_val(self) - mem::size_of_val(&self.shape) + self.shape.memory_bytes()
+    }
+
+    /// The name of the shape that's moved.
+    fn name(&self) -> &'static str {
+        self.shape.name()
+    }
+
+    fn triangle_count(&self) -> usize {
+        self.shape.triangle_count()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        self.shape.validate()
+    }
+}
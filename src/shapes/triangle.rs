@@ -0,0 +1,204 @@
+use std::mem;
+
+use log::debug;
+
+use super::{HitRecord, Shape};
+use crate::aabb::Aabb;
+use crate::bvh::Bvh;
+use crate::check;
+use crate::ray::Ray;
+use crate::scalar::Scalar;
+use crate::vector::Vector;
+
+/// The distance and barycentric coordinates `(b1, b2)` of the ray's
+/// intersection with triangle `abc`, by Möller–Trumbore.
+fn intersect_triangle(
+    ray: &Ray,
+    [a, b, c]: [Vector; 3],
+    t_min: Scalar,
+    t_max: Scalar,
+) -> Option<(Scalar, Scalar, Scalar)> {
+    let edge1 = b - a;
+    let edge2 = c - a;
+    let p = ray.direction.cross(edge2);
+    let determinant = edge1 * p;
+    if determinant.abs() < 1e-12 {
+        return None;
+    }
+    let inverse = 1.0 / determinant;
+    let s = ray.origin - a;
+    let b1 = inverse * (s * p);
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+    let q = s.cross(edge1);
+    let b2 = inverse * (ray.direction * q);
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+    let t = inverse * (edge2 * q);
+    if t <= t_min || t >= t_max {
+        return None;
+    }
+    Some((t, b1, b2))
+}
+
+/// A single triangle facing `(b - a) × (c - a)`, so counterclockwise when
+/// seen from the front. Its surface coordinates are the barycentric weights
+/// of `b` and `c`.
+pub struct Triangle {
+    pub vertices: [Vector; 3],
+}
+
+impl Triangle {
+    pub fn new(a: Vector, b: Vector, c: Vector) -> Triangle {
+        Triangle {
+            vertices: [a, b, c],
+        }
+    }
+}
+
+impl Shape for Triangle {
+    fn intersect(&self, ray: &Ray, t_min: Scalar, t_max: Scalar) -> Option<HitRecord> {
+        let (t, b1, b2) = intersect_triangle(ray, self.vertices, t_min, t_max)?;
+        let [a, b, c] = self.vertices;
+        let mut hit = HitRecord::new(ray, t, (b - a).cross(c - a).normalize());
+        hit.set_uv((b1, b2), b - a, c - a);
+        Some(hit)
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        Some(Aabb::from_points(&self.vertices))
+    }
+
+    fn triangle_count(&self) -> usize {
+        1
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for (i, &vertex) in self.vertices.iter().enumerate() {
+            check::point(&format!("vertices[{}]", i), vertex)?;
+        }
+        Ok(())
+    }
+}
+
+/// One face of a [`TriangleMesh`], as indices into its vertex lists.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MeshTriangle {
+    pub positions: [usize; 3],
+    pub uvs: Option<[usize; 3]>,
+}
+
+/// Triangles sharing a list of vertices, with a BVH over them.
+///
+/// Faces with texture coordinates interpolate them across the face; the
+/// others fall back to barycentric coordinates like a [`Triangle`].
+pub struct TriangleMesh {
+    pub positions: Vec<Vector>,
+    pub uvs: Vec<(Scalar, Scalar)>,
+    pub triangles: Vec<MeshTriangle>,
+    bvh: Bvh,
+}
+
+impl TriangleMesh {
+    /// Panics if a triangle refers to a vertex that doesn't exist.
+    pub fn new(
+        positions: Vec<Vector>,
+        uvs: Vec<(Scalar, Scalar)>,
+        triangles: Vec<MeshTriangle>,
+    ) -> TriangleMesh {
+        let bounds: Vec<Aabb> = triangles
+            .iter()
+            .map(|triangle| {
+                let uvs_exist = triangle.uvs.iter().flatten().all(|&i| i < uvs.len());
+                assert!(uvs_exist, "triangle refers to a missing uv");
+                Aabb::from_points(&triangle.positions.map(|i| positions[i]))
+            })
+            .collect();
+        let bvh = Bvh::new(&bounds);
+        let shape = bvh.stats();
+        debug!(
+            "Built a BVH of {} nodes, {} deep, over {} triangles",
+            shape.nodes,
+            shape.depth,
+            triangles.len()
+        );
+        TriangleMesh {
+            positions,
+            uvs,
+            triangles,
+            bvh,
+        }
+    }
+
+    fn vertices(&self, triangle: &MeshTriangle) -> [Vector; 3] {
+        triangle.positions.map(|i| self.positions[i])
+    }
+}
+
+impl Shape for TriangleMesh {
+    fn intersect(&self, ray: &Ray, t_min: Scalar, t_max: Scalar) -> Option<HitRecord> {
+        let mut nearest = None;
+        self.bvh.intersect(ray, t_min, t_max, |index, t_max| {
+            let triangle = &self.triangles[index];
+            let (t, b1, b2) = intersect_triangle(ray, self.vertices(triangle), t_min, t_max)?;
+            nearest = Some((index, t, b1, b2));
+            Some(t)
+        })?;
+        let (index, t, b1, b2) = nearest?;
+        let triangle = &self.triangles[index];
+        let [a, b, c] = self.vertices(triangle);
+        let mut hit = HitRecord::new(ray, t, (b - a).cross(c - a).normalize());
+        match triangle.uvs {
+            Some([ua, ub, uc]) => {
+                let b0 = 1.0 - b1 - b2;
+                let (ua, ub, uc) = (self.uvs[ua], self.uvs[ub], self.uvs[uc]);
+                let uv = (
+                    b0 * ua.0 + b1 * ub.0 + b2 * uc.0,
+                    b0 * ua.1 + b1 * ub.1 + b2 * uc.1,
+                );
+                // Solve the edges for ∂p/∂u and ∂p/∂v, which are zero when
+                // the texture coordinates are degenerate.
+                let (du1, dv1) = (ub.0 - ua.0, ub.1 - ua.1);
+                let (du2, dv2) = (uc.0 - ua.0, uc.1 - ua.1);
+                let determinant = du1 * dv2 - dv1 * du2;
+                let (tangent, bitangent) = if determinant.abs() > 1e-12 {
+                    let inverse = 1.0 / determinant;
+                    (
+                        inverse * (dv2 * (b - a) - dv1 * (c - a)),
+                        inverse * (du1 * (c - a) - du2 * (b - a)),
+                    )
+                } else {
+                    (Vector::zero(), Vector::zero())
+                };
+                hit.set_uv(uv, tangent, bitangent);
+            }
+            None => hit.set_uv((b1, b2), b - a, c - a),
+        }
+        Some(hit)
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        Some(Aabb::from_points(&self.positions))
+    }
+
+    fn memory_bytes(&self) -> usize {
+        mem::size_of_val(self)
+            + self.positions.capacity() * mem::size_of::<Vector>()
+            + self.uvs.capacity() * mem::size_of::<(Scalar, Scalar)>()
+            + self.triangles.capacity() * mem::size_of::<MeshTriangle>()
+            + self.bvh.heap_bytes()
+    }
+
+    fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for (i, &position) in self.positions.iter().enumerate() {
+            check::point(&format!("positions[{}]", i), position)?;
+        }
+        Ok(())
+    }
+}
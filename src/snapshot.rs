@@ -0,0 +1,95 @@
+//! Snapshots of a long render as it goes, written beside the output so that
+//! progress can be looked in on without waiting for the end.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::error::Error;
+use crate::image::{Image, Tonemap};
+
+/// Where the snapshots of `output` go: `output.partial.png` for
+/// `output.png`.
+pub fn partial_path(output: &Path) -> PathBuf {
+    let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match output.extension() {
+        Some(extension) => format!("{}.partial.{}", stem, extension.to_string_lossy()),
+        None => format!("{}.partial", stem),
+    };
+    output.with_file_name(name)
+}
+
+pub struct Snapshots {
+    path: PathBuf,
+    every: Duration,
+    /// How long the render has been going.
+    clock: Box<dyn Fn() -> Duration>,
+    last: Duration,
+    tonemap: Tonemap,
+}
+
+impl Snapshots {
+    /// Snapshots of the render to `output`, taken `every` so often.
+    pub fn new(output: &Path, every: Duration) -> Snapshots {
+        let start = Instant::now();
+        Snapshots::with_clock(output, every, move || start.elapsed())
+    }
+
+    /// Snapshots timed by `clock` rather than the time of day.
+    pub fn with_clock(
+        output: &Path,
+        every: Duration,
+        clock: impl Fn() -> Duration + 'static,
+    ) -> Snapshots {
+        Snapshots {
+            path: partial_path(output),
+            every,
+            clock: Box::new(clock),
+            last: Duration::ZERO,
+            tonemap: Tonemap::Clamp,
+        }
+    }
+
+    /// Snapshots tonemapped as the output is.
+    pub fn tonemapped(self, tonemap: Tonemap) -> Snapshots {
+        Snapshots { tonemap, ..self }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes `image` as the latest snapshot if the last one, or the start
+    /// of the render, is `every` ago, and says whether it did. It's written
+    /// as the output will be, then moved into place, so that whatever's
+    /// looking at the snapshot never sees half of one.
+    pub fn update(&mut self, image: &Image) -> Result<bool, Error> {
+        let now = (self.clock)();
+        if now < self.last + self.every {
+            return Ok(false);
+        }
+        self.last = now;
+        let name = self.path.file_name().unwrap_or_default().to_string_lossy();
+        let temporary = self.path.with_file_name(format!(".{}", name));
+        image.write_tonemapped(&temporary, self.tonemap)?;
+        fs::rename(&temporary, &self.path).map_err(|source| Error::Io {
+            path: self.path.clone(),
+            operation: "write",
+            source,
+        })?;
+        Ok(true)
+    }
+
+    /// Removes the last snapshot, once the output it stood in for is written.
+    pub fn finish(self) -> Result<(), Error> {
+        match fs::remove_file(&self.path) {
+            Err(source) if source.kind() != io::ErrorKind::NotFound => Err(Error::Io {
+                path: self.path,
+                operation: "remove",
+                source,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
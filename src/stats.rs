@@ -0,0 +1,204 @@
+use std::cell::Cell;
+use std::fmt;
+use std::ops;
+use std::time::{Duration, Instant};
+
+use crate::ray::PACKET_SIZE;
+
+/// `count` of `noun`, such as "1 object" or "3 lights".
+pub fn plural(count: usize, noun: &str) -> String {
+    format!("{} {}{}", count, noun, if count == 1 { "" } else { "s" })
+}
+
+/// Counters gathered over a render.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct RenderStats {
+    pub camera_rays: u64,
+    /// Closest-hit queries, including the camera rays.
+    pub rays: u64,
+    pub shadow_rays: u64,
+    /// Box and shape tests done for packets of camera rays, and the lanes
+    /// of them that held a ray still able to hit what was tested. Their
+    /// ratio to [`PACKET_SIZE`] is how well packets were used.
+    pub packet_tests: u64,
+    pub packet_lanes: u64,
+    pub elapsed: Duration,
+}
+
+impl RenderStats {
+    /// The share of packet lanes that did useful work, or `None` without
+    /// packets.
+    pub fn packet_utilization(&self) -> Option<f64> {
+        if self.packet_tests == 0 {
+            return None;
+        }
+        Some(self.packet_lanes as f64 / (self.packet_tests * PACKET_SIZE as u64) as f64)
+    }
+}
+
+impl ops::AddAssign<RenderStats> for RenderStats {
+    fn add_assign(&mut self, other: RenderStats) {
+        self.camera_rays += other.camera_rays;
+        self.rays += other.rays;
+        self.shadow_rays += other.shadow_rays;
+        self.packet_tests += other.packet_tests;
+        self.packet_lanes += other.packet_lanes;
+        self.elapsed += other.elapsed;
+    }
+}
+
+impl fmt::Display for RenderStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} camera rays, {} rays, {} shadow rays in {:.2?}",
+            self.camera_rays, self.rays, self.shadow_rays, self.elapsed
+        )?;
+        if let Some(utilization) = self.packet_utilization() {
+            write!(f, ", {:.0}% of packet lanes used", 100.0 * utilization)?;
+        }
+        Ok(())
+    }
+}
+
+/// The stats of each frame of an animation, summarized by `Display`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SequenceStats {
+    pub frames: Vec<RenderStats>,
+}
+
+impl SequenceStats {
+    pub fn total(&self) -> RenderStats {
+        let mut total = RenderStats::default();
+        for &frame in &self.frames {
+            total += frame;
+        }
+        total
+    }
+}
+
+impl fmt::Display for SequenceStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} frames: {}", self.frames.len(), self.total())?;
+        let times = self.frames.iter().map(|frame| frame.elapsed);
+        if let (Some(fastest), Some(slowest)) = (times.clone().min(), times.max()) {
+            write!(
+                f,
+                "; each took {:.2?} to {:.2?}, {:.2?} on average",
+                fastest,
+                slowest,
+                self.total().elapsed / self.frames.len() as u32
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// How one scene of a batch went.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchScene {
+    pub name: String,
+    pub elapsed: Duration,
+    /// Why it wasn't rendered, if it wasn't.
+    pub error: Option<String>,
+}
+
+/// The scenes of a batch, tabulated by `Display` with how long each took
+/// from loading to writing, and how long they all took together.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BatchStats {
+    pub scenes: Vec<BatchScene>,
+    pub elapsed: Duration,
+}
+
+impl BatchStats {
+    pub fn failed(&self) -> impl Iterator<Item = &BatchScene> {
+        self.scenes.iter().filter(|scene| scene.error.is_some())
+    }
+}
+
+impl fmt::Display for BatchStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names = self.scenes.iter().map(|scene| scene.name.len());
+        let width = names.chain(Some("total".len())).max().unwrap_or(0);
+        for scene in &self.scenes {
+            let result = match scene.error {
+                Some(_) => "failed",
+                None => "rendered",
+            };
+            let elapsed = format!("{:.2?}", scene.elapsed);
+            writeln!(
+                f,
+                "{:w$}  {:>10}  {}",
+                scene.name,
+                elapsed,
+                result,
+                w = width
+            )?;
+        }
+        let rendered = self.scenes.len() - self.failed().count();
+        let elapsed = format!("{:.2?}", self.elapsed);
+        write!(
+            f,
+            "{:w$}  {:>10}  {} of {} rendered",
+            "total",
+            elapsed,
+            rendered,
+            self.scenes.len(),
+            w = width
+        )
+    }
+}
+
+// Workers count into thread-locals so the hot paths never contend, and hand
+// their totals over with `take_thread_counters` when they finish.
+thread_local! {
+    static COUNTERS: Cell<RenderStats> = Cell::new(RenderStats::default());
+}
+
+fn update(f: impl FnOnce(&mut RenderStats)) {
+    COUNTERS.with(|counters| {
+        let mut stats = counters.get();
+        f(&mut stats);
+        counters.set(stats);
+    });
+}
+
+pub(crate) fn record_camera_ray() {
+    update(|stats| stats.camera_rays += 1);
+}
+
+pub(crate) fn record_ray() {
+    update(|stats| stats.rays += 1);
+}
+
+pub(crate) fn record_shadow_ray() {
+    update(|stats| stats.shadow_rays += 1);
+}
+
+/// Counts `tests` packet tests that had `lanes` lanes in use between them.
+pub(crate) fn record_packet_tests(tests: u64, lanes: u64) {
+    update(|stats| {
+        stats.packet_tests += tests;
+        stats.packet_lanes += lanes;
+    });
+}
+
+/// Times a render, unless it's on wasm32-unknown-unknown, which has no
+/// clock for `Instant::now` to read, and where renders take no time at all.
+pub(crate) struct Timer(Option<Instant>);
+
+impl Timer {
+    pub(crate) fn start() -> Timer {
+        let clock = !cfg!(all(target_arch = "wasm32", target_os = "unknown"));
+        Timer(clock.then(Instant::now))
+    }
+
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.0.map_or(Duration::ZERO, |start| start.elapsed())
+    }
+}
+
+pub(crate) fn take_thread_counters() -> RenderStats {
+    COUNTERS.with(|counters| counters.replace(RenderStats::default()))
+}
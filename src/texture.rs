@@ -0,0 +1,470 @@
+#[cfg(feature = "files")]
+use std::path::Path;
+
+use crate::image::Image;
+use crate::noise::Perlin;
+use crate::scalar::{consts::PI, Scalar};
+use crate::shapes::HitRecord;
+use crate::vector::{Color, Vector};
+
+pub trait Texture: Send + Sync {
+    /// The color at surface coordinates `(u, v)` and world position `point`.
+    fn value(&self, u: Scalar, v: Scalar, point: Vector) -> Color;
+
+    /// The color at a hit, for textures that need more of it than `value`
+    /// gets, such as the normal [`Triplanar`] blends by.
+    fn value_at(&self, hit: &HitRecord) -> Color {
+        self.value(hit.uv.0, hit.uv.1, hit.point)
+    }
+}
+
+pub struct SolidColor {
+    pub color: Color,
+}
+
+impl SolidColor {
+    pub fn new(color: Color) -> SolidColor {
+        SolidColor { color }
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: Scalar, _v: Scalar, _point: Vector) -> Color {
+        self.color
+    }
+}
+
+/// A 3D checkerboard of cubes with sides `scale` long in world space,
+/// alternating between `even` (the cube with a corner at the origin) and
+/// `odd`. Each cube includes its faces toward negative coordinates, so a
+/// surface lying exactly on a face flickers between the two cubes.
+pub struct Checker {
+    pub scale: Scalar,
+    pub odd: Box<dyn Texture>,
+    pub even: Box<dyn Texture>,
+}
+
+impl Checker {
+    pub fn new(scale: Scalar, odd: Box<dyn Texture>, even: Box<dyn Texture>) -> Checker {
+        Checker { scale, odd, even }
+    }
+
+    /// Whether `point` lies in one of the `even` cubes.
+    pub fn is_even(&self, point: Vector) -> bool {
+        let cell = |c: Scalar| (c / self.scale).floor() as i64;
+        (cell(point.x) + cell(point.y) + cell(point.z)).rem_euclid(2) == 0
+    }
+}
+
+impl Checker {
+    fn square(&self, point: Vector) -> &dyn Texture {
+        if self.is_even(point) {
+            self.even.as_ref()
+        } else {
+            self.odd.as_ref()
+        }
+    }
+}
+
+impl Texture for Checker {
+    fn value(&self, u: Scalar, v: Scalar, point: Vector) -> Color {
+        self.square(point).value(u, v, point)
+    }
+
+    fn value_at(&self, hit: &HitRecord) -> Color {
+        self.square(hit.point).value_at(hit)
+    }
+}
+
+/// A checkerboard in surface coordinates with `columns` by `rows` squares
+/// across `[0, 1]²`, starting with an `even` square at `(0, 0)`.
+pub struct UvChecker {
+    pub columns: Scalar,
+    pub rows: Scalar,
+    pub odd: Box<dyn Texture>,
+    pub even: Box<dyn Texture>,
+}
+
+impl UvChecker {
+    pub fn new(
+        columns: Scalar,
+        rows: Scalar,
+        odd: Box<dyn Texture>,
+        even: Box<dyn Texture>,
+    ) -> UvChecker {
+        UvChecker {
+            columns,
+            rows,
+            odd,
+            even,
+        }
+    }
+}
+
+impl UvChecker {
+    fn square(&self, u: Scalar, v: Scalar) -> &dyn Texture {
+        let column = (u * self.columns).floor() as i64;
+        let row = (v * self.rows).floor() as i64;
+        if (column + row).rem_euclid(2) == 0 {
+            self.even.as_ref()
+        } else {
+            self.odd.as_ref()
+        }
+    }
+}
+
+impl Texture for UvChecker {
+    fn value(&self, u: Scalar, v: Scalar, point: Vector) -> Color {
+        self.square(u, v).value(u, v, point)
+    }
+
+    fn value_at(&self, hit: &HitRecord) -> Color {
+        self.square(hit.uv.0, hit.uv.1).value_at(hit)
+    }
+}
+
+/// A 2D transform of surface coordinates, applied in a fixed order: `scale`
+/// about `(0, 0)`, then `rotation` about `pivot` (given in the scaled
+/// coordinates), then `offset`. The rotation is in radians from +u toward +v,
+/// which turns the texture clockwise on screen since v grows down.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct UvTransform {
+    pub scale: (Scalar, Scalar),
+    pub rotation: Scalar,
+    pub pivot: (Scalar, Scalar),
+    pub offset: (Scalar, Scalar),
+}
+
+impl Default for UvTransform {
+    fn default() -> UvTransform {
+        UvTransform {
+            scale: (1.0, 1.0),
+            rotation: 0.0,
+            pivot: (0.5, 0.5),
+            offset: (0.0, 0.0),
+        }
+    }
+}
+
+impl UvTransform {
+    pub fn apply(&self, (u, v): (Scalar, Scalar)) -> (Scalar, Scalar) {
+        let (u, v) = (
+            u * self.scale.0 - self.pivot.0,
+            v * self.scale.1 - self.pivot.1,
+        );
+        let (sin, cos) = self.rotation.sin_cos();
+        (
+            u * cos - v * sin + self.pivot.0 + self.offset.0,
+            u * sin + v * cos + self.pivot.1 + self.offset.1,
+        )
+    }
+}
+
+/// Looks `inner` up at transformed surface coordinates, so that one texture
+/// can be tiled differently on different objects. Anything `inner` does with
+/// coordinates, such as wrapping, happens after the transform.
+pub struct UvTransformed {
+    pub inner: Box<dyn Texture>,
+    pub transform: UvTransform,
+}
+
+impl UvTransformed {
+    pub fn new(inner: Box<dyn Texture>, transform: UvTransform) -> UvTransformed {
+        UvTransformed { inner, transform }
+    }
+}
+
+impl Texture for UvTransformed {
+    fn value(&self, u: Scalar, v: Scalar, point: Vector) -> Color {
+        let (u, v) = self.transform.apply((u, v));
+        self.inner.value(u, v, point)
+    }
+
+    fn value_at(&self, hit: &HitRecord) -> Color {
+        let mut hit = *hit;
+        hit.uv = self.transform.apply(hit.uv);
+        self.inner.value_at(&hit)
+    }
+}
+
+/// Grayscale Perlin noise over world space with features about `1 / scale`
+/// apart, remapped from `[-1, 1]` to `[0, 1]`.
+pub struct NoiseTexture {
+    pub scale: Scalar,
+    pub seed: u64,
+    perlin: Perlin,
+}
+
+impl NoiseTexture {
+    pub fn new(scale: Scalar, seed: u64) -> NoiseTexture {
+        NoiseTexture {
+            scale,
+            seed,
+            perlin: Perlin::new(seed),
+        }
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: Scalar, _v: Scalar, point: Vector) -> Color {
+        let value = (0.5 * (1.0 + self.perlin.noise(self.scale * point))).clamp(0.0, 1.0);
+        Color::new(value, value, value)
+    }
+}
+
+/// Marble veins: a sine wave along `axis` with `scale` stripes per unit,
+/// its phase pushed around by `turbulence` radians of turbulence, blending
+/// from `light` at the crests to `dark` in the troughs.
+pub struct Marble {
+    pub scale: Scalar,
+    pub axis: Vector,
+    pub turbulence: Scalar,
+    pub light: Color,
+    pub dark: Color,
+    perlin: Perlin,
+}
+
+impl Marble {
+    pub fn new(
+        scale: Scalar,
+        axis: Vector,
+        turbulence: Scalar,
+        seed: u64,
+        light: Color,
+        dark: Color,
+    ) -> Marble {
+        Marble {
+            scale,
+            axis: axis.normalize(),
+            turbulence,
+            light,
+            dark,
+            perlin: Perlin::new(seed),
+        }
+    }
+}
+
+impl Texture for Marble {
+    fn value(&self, _u: Scalar, _v: Scalar, point: Vector) -> Color {
+        let phase = 2.0 * PI * self.scale * (point * self.axis)
+            + self.turbulence * self.perlin.turbulence(self.scale * point, 7);
+        let t = 0.5 * (1.0 + phase.sin());
+        t * self.light + (1.0 - t) * self.dark
+    }
+}
+
+/// Growth rings around the line through `center` along `axis`, `scale` rings
+/// per unit of distance from it. Each ring fades from `light` to `dark` and
+/// then starts over; `turbulence` shifts the rings by up to that many ring
+/// widths so they aren't perfect circles.
+pub struct Wood {
+    pub scale: Scalar,
+    pub center: Vector,
+    pub axis: Vector,
+    pub turbulence: Scalar,
+    pub light: Color,
+    pub dark: Color,
+    perlin: Perlin,
+}
+
+impl Wood {
+    pub fn new(
+        scale: Scalar,
+        center: Vector,
+        axis: Vector,
+        turbulence: Scalar,
+        seed: u64,
+        light: Color,
+        dark: Color,
+    ) -> Wood {
+        Wood {
+            scale,
+            center,
+            axis: axis.normalize(),
+            turbulence,
+            light,
+            dark,
+            perlin: Perlin::new(seed),
+        }
+    }
+
+    /// How many rings out from the axis `point` is, before turbulence.
+    pub fn rings(&self, point: Vector) -> Scalar {
+        let offset = point - self.center;
+        let radial = offset - (offset * self.axis) * self.axis;
+        self.scale * radial.len()
+    }
+}
+
+impl Texture for Wood {
+    fn value(&self, _u: Scalar, _v: Scalar, point: Vector) -> Color {
+        let rings = self.rings(point) + self.turbulence * self.perlin.turbulence(point, 4);
+        let t = rings - rings.floor();
+        (1.0 - t) * self.light + t * self.dark
+    }
+}
+
+/// How an image texture handles coordinates outside `[0, 1]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wrap {
+    /// Tiles the image.
+    Repeat,
+    /// Extends the edge texels.
+    Clamp,
+    /// Tiles the image, flipping every other copy so the edges meet.
+    Mirror,
+}
+
+impl Wrap {
+    /// The texel that stands in for column or row `index` of an image `size`
+    /// texels across.
+    fn texel(self, index: i64, size: u32) -> u32 {
+        let size = size as i64;
+        let index = match self {
+            Wrap::Repeat => index.rem_euclid(size),
+            Wrap::Clamp => index.clamp(0, size - 1),
+            Wrap::Mirror => {
+                let index = index.rem_euclid(2 * size);
+                if index < size {
+                    index
+                } else {
+                    2 * size - 1 - index
+                }
+            }
+        };
+        index as u32
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    /// The texel under the lookup.
+    Nearest,
+    /// A blend of the four texels whose centers surround the lookup.
+    Bilinear,
+}
+
+/// An image looked up by `(u, v)`, with `(0, 0)` the top left corner of the
+/// image and `(1, 1)` the bottom right. Each axis wraps on its own, and the
+/// four texels of a bilinear lookup are wrapped before they're blended, so
+/// under [`Wrap::Repeat`] the seam blends with the far edge.
+pub struct ImageTexture {
+    pub image: Image,
+    pub filter: Filter,
+    pub wrap_u: Wrap,
+    pub wrap_v: Wrap,
+}
+
+impl ImageTexture {
+    /// A bilinearly filtered, repeating texture of linear-light `image`.
+    pub fn new(image: Image) -> ImageTexture {
+        ImageTexture {
+            image,
+            filter: Filter::Bilinear,
+            wrap_u: Wrap::Repeat,
+            wrap_v: Wrap::Repeat,
+        }
+    }
+
+    /// Loads an sRGB-encoded PNG, converting it to linear light.
+    #[cfg(feature = "files")]
+    pub fn load(path: &Path) -> Result<ImageTexture, String> {
+        Ok(ImageTexture::new(Image::read_png(path)?))
+    }
+
+    /// Loads a PNG of linear values, such as a normal map.
+    #[cfg(feature = "files")]
+    pub fn load_linear(path: &Path) -> Result<ImageTexture, String> {
+        Ok(ImageTexture::new(Image::read_linear_png(path)?))
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Color {
+        self.image.get(
+            self.wrap_u.texel(x, self.image.width),
+            self.wrap_v.texel(y, self.image.height),
+        )
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: Scalar, v: Scalar, _point: Vector) -> Color {
+        if self.image.width == 0 || self.image.height == 0 {
+            return Color::zero();
+        }
+        let x = u * self.image.width as Scalar;
+        let y = v * self.image.height as Scalar;
+        match self.filter {
+            Filter::Nearest => self.texel(x.floor() as i64, y.floor() as i64),
+            Filter::Bilinear => {
+                // Texel centers sit at half-integer coordinates.
+                let (x, y) = (x - 0.5, y - 0.5);
+                let (x0, y0) = (x.floor(), y.floor());
+                let (fx, fy) = (x - x0, y - y0);
+                let (x0, y0) = (x0 as i64, y0 as i64);
+                let top = (1.0 - fx) * self.texel(x0, y0) + fx * self.texel(x0 + 1, y0);
+                let bottom = (1.0 - fx) * self.texel(x0, y0 + 1) + fx * self.texel(x0 + 1, y0 + 1);
+                (1.0 - fy) * top + fy * bottom
+            }
+        }
+    }
+}
+
+/// Projects `inner` onto the surface along the three world axes instead of
+/// using surface coordinates, for shapes and meshes without them.
+///
+/// Each projection maps world distances times `scale` to texture
+/// coordinates: x and z to `u` on the XY and YZ planes and x to `u`, z to `v`
+/// on the XZ plane, with `v` running down the y axis on the side planes. The
+/// three lookups are blended by the absolute components of the normal raised
+/// to `sharpness`, normalized so that they sum to 1; the higher the
+/// sharpness, the narrower the seams where projections mix. Looked up by
+/// `value` alone, without a normal, the three are weighted equally.
+pub struct Triplanar {
+    pub inner: Box<dyn Texture>,
+    pub scale: Scalar,
+    pub sharpness: Scalar,
+}
+
+impl Triplanar {
+    pub fn new(inner: Box<dyn Texture>, scale: Scalar, sharpness: Scalar) -> Triplanar {
+        Triplanar {
+            inner,
+            scale,
+            sharpness,
+        }
+    }
+
+    /// How much the YZ, XZ and XY projections count for at a surface with
+    /// `normal`.
+    pub fn weights(&self, normal: Vector) -> [Scalar; 3] {
+        let weights = [normal.x, normal.y, normal.z].map(|c| c.abs().powf(self.sharpness));
+        let total: Scalar = weights.iter().sum();
+        if total > 0.0 {
+            weights.map(|w| w / total)
+        } else {
+            [1.0 / 3.0; 3]
+        }
+    }
+
+    fn blend(&self, weights: [Scalar; 3], point: Vector) -> Color {
+        let p = self.scale * point;
+        let projections = [(p.z, -p.y), (p.x, p.z), (p.x, -p.y)];
+        let mut color = Color::zero();
+        for (&weight, &(u, v)) in weights.iter().zip(projections.iter()) {
+            if weight > 0.0 {
+                color += weight * self.inner.value(u, v, point);
+            }
+        }
+        color
+    }
+}
+
+impl Texture for Triplanar {
+    fn value(&self, _u: Scalar, _v: Scalar, point: Vector) -> Color {
+        self.blend([1.0 / 3.0; 3], point)
+    }
+
+    fn value_at(&self, hit: &HitRecord) -> Color {
+        self.blend(self.weights(hit.normal), hit.point)
+    }
+}
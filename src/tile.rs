@@ -0,0 +1,133 @@
+//! The tiles an image is rendered in, and the order they're handed out in.
+//! Every pixel comes out the same whatever the order; it only changes which
+//! part of the image shows up first.
+
+use std::mem;
+
+use crate::render::Crop;
+use crate::sampler::Sampler;
+
+/// How many pixels wide and high a tile is, but for those cut short by the
+/// right and bottom edges.
+pub const TILE_SIZE: u32 = 16;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TileOrder {
+    /// Left to right, top to bottom.
+    Scanline,
+    /// Outward from the tile at the center of the image.
+    Spiral,
+    /// Along a Hilbert curve, so that each tile is next to the one before
+    /// and the rays of one tile mostly meet the same geometry as the last.
+    Hilbert,
+    /// Shuffled by the render seed.
+    Random,
+}
+
+impl TileOrder {
+    pub fn name(self) -> &'static str {
+        match self {
+            TileOrder::Scanline => "scanline",
+            TileOrder::Spiral => "spiral",
+            TileOrder::Hilbert => "hilbert",
+            TileOrder::Random => "random",
+        }
+    }
+}
+
+/// The tiles covering `window`, placed relative to its top left corner, in
+/// the order they're rendered.
+pub fn tiles(window: Crop, order: TileOrder, seed: u64) -> Vec<Crop> {
+    let columns = window.width.div_ceil(TILE_SIZE);
+    let rows = window.height.div_ceil(TILE_SIZE);
+    let scanline = || (0..rows).flat_map(move |row| (0..columns).map(move |column| (column, row)));
+    let cells: Vec<(u32, u32)> = match order {
+        TileOrder::Scanline => scanline().collect(),
+        TileOrder::Spiral => {
+            let center = (window.width / 2 / TILE_SIZE, window.height / 2 / TILE_SIZE);
+            spiral(columns, rows, center)
+        }
+        TileOrder::Hilbert => hilbert(columns, rows),
+        TileOrder::Random => {
+            let mut cells: Vec<_> = scanline().collect();
+            let mut sampler = Sampler::new(seed, 0, 0);
+            for i in (1..cells.len()).rev() {
+                let j = (sampler.next_f64() * (i + 1) as f64) as usize;
+                cells.swap(i, j.min(i));
+            }
+            cells
+        }
+    };
+    cells
+        .into_iter()
+        .map(|(column, row)| {
+            let (x, y) = (column * TILE_SIZE, row * TILE_SIZE);
+            Crop {
+                x,
+                y,
+                width: TILE_SIZE.min(window.width - x),
+                height: TILE_SIZE.min(window.height - y),
+            }
+        })
+        .collect()
+}
+
+/// Walks a square spiral out from `center`, right, down, left and up in
+/// ever longer runs, keeping the cells of the grid it passes over. Past the
+/// shorter side of the grid the runs go out of it and back in again.
+fn spiral(columns: u32, rows: u32, center: (u32, u32)) -> Vec<(u32, u32)> {
+    let total = columns as usize * rows as usize;
+    let mut cells = Vec::with_capacity(total);
+    let (mut x, mut y) = (center.0 as i64, center.1 as i64);
+    let (mut dx, mut dy) = (1, 0);
+    let mut run = 1;
+    while cells.len() < total {
+        for _ in 0..2 {
+            for _ in 0..run {
+                if (0..columns as i64).contains(&x) && (0..rows as i64).contains(&y) {
+                    cells.push((x as u32, y as u32));
+                }
+                x += dx;
+                y += dy;
+            }
+            (dx, dy) = (-dy, dx);
+        }
+        run += 1;
+    }
+    cells
+}
+
+/// The cells of the grid in the order a Hilbert curve through the smallest
+/// power-of-two square around it visits them.
+fn hilbert(columns: u32, rows: u32) -> Vec<(u32, u32)> {
+    let side = columns.max(rows).next_power_of_two() as u64;
+    (0..side * side)
+        .map(|d| hilbert_point(side, d))
+        .filter(|&(x, y)| x < columns && y < rows)
+        .collect()
+}
+
+/// The cell `d` steps along the Hilbert curve through a `side` by `side`
+/// square.
+fn hilbert_point(side: u64, d: u64) -> (u32, u32) {
+    let (mut x, mut y, mut rest) = (0, 0, d);
+    let mut size = 1;
+    while size < side {
+        let rx = 1 & (rest / 2);
+        let ry = 1 & (rest ^ rx);
+        // Each quadrant is a copy of the whole curve, turned so that it
+        // starts next to where the one before it ended.
+        if ry == 0 {
+            if rx == 1 {
+                x = size - 1 - x;
+                y = size - 1 - y;
+            }
+            mem::swap(&mut x, &mut y);
+        }
+        x += size * rx;
+        y += size * ry;
+        rest /= 4;
+        size *= 2;
+    }
+    (x as u32, y as u32)
+}
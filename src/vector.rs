@@ -0,0 +1,186 @@
+use std::fmt;
+use std::ops;
+
+use crate::scalar::Scalar;
+
+/// How far apart vectors [`assert_vec_eq!`] takes as equal may be by
+/// default, in each component: about a thousand rounding errors.
+#[cfg(not(feature = "f32"))]
+pub const DEFAULT_EPSILON: Scalar = 1e-12;
+#[cfg(feature = "f32")]
+pub const DEFAULT_EPSILON: Scalar = 1e-4;
+
+#[derive(Copy, Clone, PartialEq)]
+pub struct Vector {
+    pub x: Scalar,
+    pub y: Scalar,
+    pub z: Scalar,
+}
+
+pub type Color = Vector;
+
+impl Vector {
+    pub fn new(x: Scalar, y: Scalar, z: Scalar) -> Vector {
+        Vector { x, y, z }
+    }
+
+    pub fn zero() -> Vector {
+        Vector::new(0.0, 0.0, 0.0)
+    }
+
+    pub fn len(&self) -> Scalar {
+        let len_squared = self.x.powi(2) + self.y.powi(2) + self.z.powi(2);
+        len_squared.sqrt()
+    }
+
+    /// The unit vector in the same direction. The zero vector has none, and
+    /// comes out NaN in every component.
+    pub fn normalize(&self) -> Vector {
+        let l = self.len();
+        (1.0 / l) * *self
+    }
+
+    pub fn cross(&self, other: Vector) -> Vector {
+        Vector::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    /// Mirrors the vector about a unit normal.
+    pub fn reflect(&self, normal: Vector) -> Vector {
+        *self - (2.0 * (*self * normal)) * normal
+    }
+
+    pub fn component_mul(&self, other: Vector) -> Vector {
+        Vector::new(self.x * other.x, self.y * other.y, self.z * other.z)
+    }
+
+    pub fn max_component(&self) -> Scalar {
+        self.x.max(self.y).max(self.z)
+    }
+
+    /// Whether every component is within `epsilon` of `other`'s.
+    pub fn abs_diff_eq(&self, other: Vector, epsilon: Scalar) -> bool {
+        (self.x - other.x).abs() <= epsilon
+            && (self.y - other.y).abs() <= epsilon
+            && (self.z - other.z).abs() <= epsilon
+    }
+
+    /// Whether every component is within `epsilon` of `other`'s, or within
+    /// `max_relative` times the larger of the two, for vectors whose size
+    /// makes a fixed epsilon too tight or too loose.
+    pub fn relative_eq(&self, other: Vector, epsilon: Scalar, max_relative: Scalar) -> bool {
+        let close = |a: Scalar, b: Scalar| {
+            let difference = (a - b).abs();
+            difference <= epsilon || difference <= max_relative * a.abs().max(b.abs())
+        };
+        close(self.x, other.x) && close(self.y, other.y) && close(self.z, other.z)
+    }
+}
+
+/// Asserts that two vectors are equal to within [`vector::DEFAULT_EPSILON`] in each
+/// component, or to within a given epsilon, showing both if they aren't.
+///
+/// [`vector::DEFAULT_EPSILON`]: crate::vector::DEFAULT_EPSILON
+#[macro_export]
+macro_rules! assert_vec_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::assert_vec_eq!($left, $right, $crate::vector::DEFAULT_EPSILON)
+    };
+    ($left:expr, $right:expr, $epsilon:expr $(,)?) => {{
+        let (left, right): ($crate::vector::Vector, $crate::vector::Vector) =
+            ($left.into(), $right.into());
+        let epsilon = $epsilon;
+        assert!(
+            left.abs_diff_eq(right, epsilon),
+            "assertion `left == right` failed (epsilon {:e})\n  left: {}\n right: {}\n  diff: {}",
+            epsilon,
+            left,
+            right,
+            left - right
+        );
+    }};
+}
+
+/// `(x, y, z)`, each component as `Display` writes it, with any precision
+/// given applied to all three.
+impl fmt::Display for Vector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match f.precision() {
+            Some(precision) => write!(
+                f,
+                "({:.*}, {:.*}, {:.*})",
+                precision, self.x, precision, self.y, precision, self.z
+            ),
+            None => write!(f, "({}, {}, {})", self.x, self.y, self.z),
+        }
+    }
+}
+
+// `Vector(x, y, z)` rather than the derived form with the field names, which
+// makes anything holding a few vectors hard to read.
+impl fmt::Debug for Vector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Vector({:?}, {:?}, {:?})", self.x, self.y, self.z)
+    }
+}
+
+impl From<[Scalar; 3]> for Vector {
+    fn from([x, y, z]: [Scalar; 3]) -> Vector {
+        Vector::new(x, y, z)
+    }
+}
+
+impl From<(Scalar, Scalar, Scalar)> for Vector {
+    fn from((x, y, z): (Scalar, Scalar, Scalar)) -> Vector {
+        Vector::new(x, y, z)
+    }
+}
+
+impl ops::Add<Vector> for Vector {
+    type Output = Vector;
+
+    fn add(self, other: Vector) -> Vector {
+        Vector::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl ops::AddAssign<Vector> for Vector {
+    fn add_assign(&mut self, other: Vector) {
+        *self = *self + other;
+    }
+}
+
+impl ops::Sub<Vector> for Vector {
+    type Output = Vector;
+
+    fn sub(self, other: Vector) -> Vector {
+        Vector::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl ops::Neg for Vector {
+    type Output = Vector;
+
+    fn neg(self) -> Vector {
+        Vector::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl ops::Mul<Vector> for Vector {
+    type Output = Scalar;
+
+    fn mul(self, other: Vector) -> Scalar {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+}
+
+impl ops::Mul<Vector> for Scalar {
+    type Output = Vector;
+
+    fn mul(self, other: Vector) -> Vector {
+        Vector::new(self * other.x, self * other.y, self * other.z)
+    }
+}
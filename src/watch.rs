@@ -0,0 +1,351 @@
+//! Rendering again whenever a scene changes, for `--watch`.
+//!
+//! Changes come from the file system's notifications through `notify`, for
+//! the directories the files are in, since editors often save by renaming a
+//! new file over the old one. Where there are none to be had, or without the
+//! `files` feature, the files are polled for changes to their size or
+//! modification time instead. Each change goes to a [`Trigger`],
+//! which cancels the render in flight and starts another once the changes
+//! stop coming for a moment, so that an editor saving several times in a
+//! row causes one render rather than a render for each save. Anything else
+//! can pull the trigger too.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "files")]
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+#[cfg(feature = "files")]
+use log::warn;
+#[cfg(feature = "files")]
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode};
+
+use crate::error::Error;
+
+/// When a file was last seen to change, as far as polling can tell.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Stamp {
+    modified: SystemTime,
+    len: u64,
+}
+
+/// `None` for a file that isn't there, which counts as a change when it
+/// comes back.
+fn stamp(path: &Path) -> Option<Stamp> {
+    let metadata = fs::metadata(path).ok()?;
+    Some(Stamp {
+        modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+        len: metadata.len(),
+    })
+}
+
+/// The file system's notifications of changes in some directories.
+#[cfg(feature = "files")]
+struct Notifications {
+    watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    dirs: Vec<PathBuf>,
+}
+
+#[cfg(feature = "files")]
+impl Notifications {
+    fn new() -> notify::Result<Notifications> {
+        let (sender, events) = mpsc::channel();
+        Ok(Notifications {
+            watcher: notify::recommended_watcher(sender)?,
+            events,
+            dirs: Vec::new(),
+        })
+    }
+
+    /// Watches the directories `files` are in, and no others.
+    fn watch_dirs_of(&mut self, files: &[(PathBuf, Option<Stamp>)]) -> notify::Result<()> {
+        use notify::Watcher as _;
+        let mut dirs: Vec<PathBuf> = Vec::new();
+        for (file, _) in files {
+            let dir = absolute(file.parent().unwrap_or_else(|| Path::new("")));
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+        for dir in &self.dirs {
+            if !dirs.contains(dir) {
+                // It may have gone, taking its watch with it.
+                let _ = self.watcher.unwatch(dir);
+            }
+        }
+        for dir in &dirs {
+            if !self.dirs.contains(dir) {
+                self.watcher.watch(dir, RecursiveMode::NonRecursive)?;
+            }
+        }
+        self.dirs = dirs;
+        Ok(())
+    }
+}
+
+/// `path` from the working directory, as notifications name the files in
+/// a directory watched by its absolute path.
+#[cfg(feature = "files")]
+fn absolute(path: &Path) -> PathBuf {
+    std::env::current_dir().unwrap_or_default().join(path)
+}
+
+/// Files being watched for changes, by notifications where possible and by
+/// polling where not.
+pub struct Watcher {
+    files: Vec<(PathBuf, Option<Stamp>)>,
+    #[cfg(feature = "files")]
+    notifications: Option<Notifications>,
+}
+
+impl Watcher {
+    pub fn new(files: Vec<PathBuf>) -> Watcher {
+        let mut watcher = Watcher {
+            files: Vec::new(),
+            #[cfg(feature = "files")]
+            notifications: Notifications::new()
+                .map_err(|error| warn!("can't have file system notifications: {}", error))
+                .ok(),
+        };
+        watcher.set_files(files);
+        watcher
+    }
+
+    /// Watches `files` from now on. Those already watched are compared
+    /// with how they were when last looked at, so a change to them between
+    /// then and now isn't missed.
+    pub fn set_files(&mut self, files: Vec<PathBuf>) {
+        let old = std::mem::take(&mut self.files);
+        self.files = files
+            .into_iter()
+            .map(|path| {
+                let seen = old.iter().find(|(old, _)| *old == path);
+                let stamp = seen.map_or_else(|| stamp(&path), |(_, seen)| *seen);
+                (path, stamp)
+            })
+            .collect();
+        #[cfg(feature = "files")]
+        if let Some(notifications) = &mut self.notifications {
+            if let Err(error) = notifications.watch_dirs_of(&self.files) {
+                warn!("can't watch for changes: {}; polling instead", error);
+                self.notifications = None;
+            }
+        }
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.files.iter().map(|(path, _)| path.as_path())
+    }
+
+    /// Whether changes come from notifications rather than polling.
+    pub fn is_notified(&self) -> bool {
+        #[cfg(feature = "files")]
+        return self.notifications.is_some();
+        #[cfg(not(feature = "files"))]
+        false
+    }
+
+    /// Waits up to `timeout` for any of the files to change, appear or go
+    /// away, and says whether one did.
+    pub fn wait(&mut self, timeout: Duration) -> bool {
+        #[cfg(feature = "files")]
+        if let Some(notifications) = &self.notifications {
+            let files: Vec<PathBuf> = self.files().map(absolute).collect();
+            let deadline = Instant::now() + timeout;
+            loop {
+                let left = deadline.saturating_duration_since(Instant::now());
+                match notifications.events.recv_timeout(left) {
+                    // Reading the files to render them doesn't count.
+                    Ok(Ok(event)) if matches!(event.kind, EventKind::Access(_)) => {}
+                    Ok(Ok(event)) if event.paths.iter().any(|path| files.contains(path)) => {
+                        // Keeps the stamps up to date, should polling take over.
+                        self.changed();
+                        return true;
+                    }
+                    Ok(Ok(_)) => {}
+                    Err(RecvTimeoutError::Timeout) => return false,
+                    Ok(Err(error)) => {
+                        warn!("lost file system notifications: {}; polling instead", error);
+                        break;
+                    }
+                    Err(RecvTimeoutError::Disconnected) => {
+                        warn!("lost file system notifications; polling instead");
+                        break;
+                    }
+                }
+            }
+            self.notifications = None;
+            return self.changed();
+        }
+        thread::sleep(timeout);
+        self.changed()
+    }
+
+    /// Whether any of the files has changed, appeared or gone away since
+    /// the last look.
+    pub fn changed(&mut self) -> bool {
+        let mut changed = false;
+        for (path, seen) in &mut self.files {
+            let now = stamp(path);
+            changed |= now != *seen;
+            *seen = now;
+        }
+        changed
+    }
+}
+
+struct State {
+    /// When the latest change not yet rendered came.
+    changed: Option<Instant>,
+    closed: bool,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    wake: Condvar,
+    cancel: AtomicBool,
+}
+
+/// What says the scene has changed, from whichever thread notices. Clones
+/// share the same changes.
+#[derive(Clone)]
+pub struct Trigger {
+    shared: Arc<Shared>,
+}
+
+/// What [`Trigger::settled`] found.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Settled {
+    /// A change came and no other has come since, for long enough.
+    Changed,
+    /// Nothing to render yet.
+    Waiting,
+    Closed,
+}
+
+impl Default for Trigger {
+    fn default() -> Trigger {
+        Trigger::new()
+    }
+}
+
+impl Trigger {
+    pub fn new() -> Trigger {
+        Trigger {
+            shared: Arc::new(Shared {
+                state: Mutex::new(State {
+                    changed: None,
+                    closed: false,
+                }),
+                wake: Condvar::new(),
+                cancel: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    /// Says something changed, which cancels the render in flight.
+    pub fn fire(&self) {
+        let mut state = self.shared.state.lock().unwrap();
+        if !state.closed {
+            state.changed = Some(Instant::now());
+            self.shared.cancel.store(true, Ordering::SeqCst);
+            self.shared.wake.notify_all();
+        }
+    }
+
+    /// Stops watching, cancelling the render in flight and any change not
+    /// yet rendered.
+    pub fn close(&self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.closed = true;
+        state.changed = None;
+        self.shared.cancel.store(true, Ordering::SeqCst);
+        self.shared.wake.notify_all();
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.shared.state.lock().unwrap().closed
+    }
+
+    /// Set while the render in flight has been overtaken by a change, for
+    /// it to stop.
+    pub fn cancelled(&self) -> &AtomicBool {
+        &self.shared.cancel
+    }
+
+    /// Waits up to `timeout` for a change to go `debounce` without another
+    /// after it. Once one has, the cancellation is cleared for the render
+    /// of it.
+    pub fn settled(&self, debounce: Duration, timeout: Duration) -> Settled {
+        let mut state = self.shared.state.lock().unwrap();
+        let deadline = Instant::now() + timeout;
+        loop {
+            if state.closed {
+                return Settled::Closed;
+            }
+            let now = Instant::now();
+            let wait = match state.changed {
+                Some(at) if now >= at + debounce => {
+                    state.changed = None;
+                    self.shared.cancel.store(false, Ordering::SeqCst);
+                    return Settled::Changed;
+                }
+                Some(at) => (at + debounce).min(deadline),
+                None => deadline,
+            };
+            if now >= deadline {
+                return Settled::Waiting;
+            }
+            state = self.shared.wake.wait_timeout(state, wait - now).unwrap().0;
+        }
+    }
+}
+
+/// How the renders of a watch went.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct WatchStats {
+    pub finished: usize,
+    /// Renders stopped part way, by a newer change or by closing.
+    pub cancelled: usize,
+    pub failed: usize,
+}
+
+/// How often [`render_on_changes`] calls `idle` while there's no change.
+const IDLE: Duration = Duration::from_millis(30);
+
+/// Renders with `render` now, and again after each change to `trigger` has
+/// settled for `debounce`, until it's closed or `idle`, called every so
+/// often between renders, returns false. `render` is given the flag that
+/// says to stop, and a failed render is only counted, for `render` to say
+/// what went wrong; the watch carries on.
+pub fn render_on_changes(
+    trigger: &Trigger,
+    debounce: Duration,
+    mut render: impl FnMut(&AtomicBool) -> Result<(), Error>,
+    mut idle: impl FnMut() -> bool,
+) -> WatchStats {
+    let mut stats = WatchStats::default();
+    loop {
+        match render(trigger.cancelled()) {
+            Ok(()) => stats.finished += 1,
+            Err(Error::Cancelled) => stats.cancelled += 1,
+            Err(_) => stats.failed += 1,
+        }
+        loop {
+            match trigger.settled(debounce, IDLE) {
+                Settled::Changed => break,
+                Settled::Closed => return stats,
+                Settled::Waiting if !idle() => {
+                    trigger.close();
+                    return stats;
+                }
+                Settled::Waiting => {}
+            }
+        }
+    }
+}
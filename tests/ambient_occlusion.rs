@@ -0,0 +1,63 @@
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::integrator::{AmbientOcclusion, Integrator};
+use basic_raytracer::material::Lambertian;
+use basic_raytracer::ray::Ray;
+use basic_raytracer::sampler::Sampler;
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene::Scene;
+use basic_raytracer::shapes::Plane;
+use basic_raytracer::vector::{Color, Vector};
+
+fn floor() -> Scene {
+    let mut scene = Scene::new(Camera::new(
+        Vector::new(0.0, 1.0, 0.0),
+        Vector::new(0.0, 0.0, 0.0),
+        45.0,
+    ));
+    scene.add(
+        Plane::new(Vector::zero(), Vector::new(0.0, 1.0, 0.0)),
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    );
+    scene
+}
+
+fn visibility(scene: &Scene, target: Vector) -> Scalar {
+    let integrator = AmbientOcclusion {
+        samples: 20_000,
+        max_distance: 100.0,
+    };
+    let origin = target + Vector::new(0.5, 1.0, 0.0);
+    let ray = Ray::new(origin, (target - origin).normalize());
+    let mut sampler = Sampler::new(0, 0, 0);
+    integrator.radiance(&ray, scene, &mut sampler).x
+}
+
+#[test]
+fn open_plane_is_unoccluded() {
+    let value = visibility(&floor(), Vector::zero());
+    assert!((value - 1.0).abs() < 1e-9, "visibility {}", value);
+}
+
+#[test]
+fn inside_corner_is_half_occluded() {
+    let mut scene = floor();
+    scene.add(
+        Plane::new(Vector::zero(), Vector::new(1.0, 0.0, 0.0)),
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    );
+    let value = visibility(&scene, Vector::new(1e-3, 0.0, 0.0));
+    assert!((value - 0.5).abs() < 0.02, "visibility {}", value);
+}
+
+#[test]
+fn geometry_beyond_max_distance_does_not_occlude() {
+    let mut scene = floor();
+    scene.add(
+        Plane::new(Vector::new(0.0, 200.0, 0.0), Vector::new(0.0, -1.0, 0.0)),
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    );
+    let value = visibility(&scene, Vector::zero());
+    assert!((value - 1.0).abs() < 1e-9, "visibility {}", value);
+}
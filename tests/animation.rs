@@ -0,0 +1,299 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::sync::Arc;
+
+use basic_raytracer::animation::{Frame, Keyframes, Pose, Turntable};
+use basic_raytracer::camera::Camera;
+use basic_raytracer::image::Image;
+use basic_raytracer::light::PointLight;
+use basic_raytracer::material::Lambertian;
+use basic_raytracer::quaternion::Quaternion;
+use basic_raytracer::render::{render, IntegratorKind, RenderSettings};
+use basic_raytracer::sampler::Sampler;
+use basic_raytracer::scalar::{self, Scalar};
+use basic_raytracer::scene::Scene;
+use basic_raytracer::scene_file;
+use basic_raytracer::shapes::Sphere;
+use basic_raytracer::vector::{Color, Vector};
+
+/// How near an interpolated value must be to the one expected.
+#[cfg(not(feature = "f32"))]
+const TOLERANCE: Scalar = 1e-9;
+#[cfg(feature = "f32")]
+const TOLERANCE: Scalar = 1e-5;
+
+fn frame(index: usize, count: usize) -> Frame {
+    Frame {
+        index,
+        count,
+        fps: 24.0,
+    }
+}
+
+/// The mean x coordinate of the pixels that aren't black.
+fn centroid(image: &Image) -> Scalar {
+    let (mut sum, mut count) = (0.0, 0);
+    for y in 0..image.height {
+        for x in 0..image.width {
+            if image.get(x, y) != Color::zero() {
+                sum += x as Scalar;
+                count += 1;
+            }
+        }
+    }
+    assert!(count > 0);
+    sum / count as Scalar
+}
+
+#[test]
+fn keyframes_hold_their_ends_and_interpolate_between() {
+    let keys = Keyframes::new(vec![(1.0, 10.0), (0.0, 0.0), (0.5, 2.0)]);
+    assert_eq!(keys.keys()[0].time, 0.0);
+    assert_eq!(keys.at(-1.0), 0.0);
+    assert_eq!(keys.at(0.25), 1.0);
+    assert_eq!(keys.at(0.75), 6.0);
+    assert_eq!(keys.at(2.0), 10.0);
+    assert_eq!(Keyframes::new(vec![(0.3, 4.0)]).at(0.9), 4.0);
+
+    assert_eq!(frame(0, 5).time(), 0.0);
+    assert_eq!(frame(2, 5).time(), 0.5);
+    assert_eq!(frame(4, 5).time(), 1.0);
+    assert_eq!(frame(0, 1).time(), 0.0);
+    assert_eq!(frame(12, 24).seconds(), 0.5);
+
+    // Halfway through a quarter turn is an eighth of a turn, not a squashed
+    // blend of the two matrices.
+    let turn = Quaternion::from_axis_angle(Vector::new(0.0, 0.0, 1.0), scalar::consts::FRAC_PI_2);
+    let poses = Keyframes::new(vec![
+        (0.0, Pose::identity()),
+        (
+            1.0,
+            Pose {
+                rotate: turn,
+                ..Pose::identity()
+            },
+        ),
+    ]);
+    let point = poses
+        .at(0.5)
+        .to_matrix()
+        .transform_point(Vector::new(1.0, 0.0, 0.0));
+    assert!((point.len() - 1.0).abs() < TOLERANCE);
+    assert!((point.x - point.y).abs() < TOLERANCE);
+}
+
+#[test]
+fn a_moving_sphere_moves_across_the_frames() {
+    let mut scene = Scene::new(Camera::new(
+        Vector::new(0.0, 0.0, 6.0),
+        Vector::zero(),
+        50.0,
+    ));
+    let white = Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.8)));
+    // One sphere that stays put in the top corner, out of the way, and one
+    // that moves from left to right.
+    scene.add(Sphere::new(Vector::new(-2.2, 1.5, 0.0), 0.3), white.clone());
+    let sphere = scene.add(Sphere::new(Vector::zero(), 0.5), white);
+    let at = |x: Scalar| Pose {
+        translate: Vector::new(x, 0.0, 0.0),
+        ..Pose::identity()
+    };
+    scene.animate_object(
+        sphere,
+        Keyframes::new(vec![(0.0, at(-1.5)), (1.0, at(1.5))]),
+    );
+    scene.add_light(PointLight::new(
+        Vector::new(0.0, 0.0, 6.0),
+        Color::new(1.0, 1.0, 1.0),
+        40.0,
+    ));
+    let settings = RenderSettings {
+        width: 48,
+        height: 32,
+        integrator: IntegratorKind::Whitted,
+        ..RenderSettings::default()
+    };
+
+    let mut centroids = Vec::new();
+    for index in 0..3 {
+        scene.set_frame(frame(index, 3));
+        let (image, _) = render(&scene, &settings).unwrap();
+        // The still sphere is where it always was.
+        let corner = scene.camera.ray(0.1, 0.1, 1.5);
+        assert!(scene.intersect(&corner, 1e-6, Scalar::INFINITY).is_some());
+        centroids.push(centroid(&image));
+    }
+    assert!(
+        centroids[0] < centroids[1] && centroids[1] < centroids[2],
+        "{:?}",
+        centroids
+    );
+}
+
+#[test]
+fn scene_files_animate_objects_lights_and_the_camera() {
+    let mut scene = scene_file::parse(
+        r#"{"camera": {"position": [0, 0, 5], "look_at": [0, 0, 0], "fov": 40,
+              "animation": [{"time": 0}, {"time": 1, "position": [0, 0, 9], "fov": 60}]},
+            "spheres": [{"center": [0, 0, 0], "radius": 1,
+              "material": {"type": "lambertian", "albedo": [1, 1, 1]},
+              "transform": [{"time": 0, "translate": [-1, 0, 0]},
+                            {"time": 1, "translate": [1, 0, 0], "scale": 2}]}],
+            "lights": [{"type": "point", "position": [0, 5, 0], "color": [1, 1, 1],
+              "intensity": 10,
+              "animation": [{"time": 0}, {"time": 1, "intensity": 20}]}]}"#,
+        Path::new(""),
+    )
+    .unwrap();
+    scene.set_frame(frame(1, 3));
+    assert_eq!(scene.camera.position, Vector::new(0.0, 0.0, 7.0));
+    assert_eq!(scene.camera.fov, 50.0);
+    let bounds = scene.objects[0].shape.bounds().unwrap();
+    assert!((bounds.min.x + 1.5).abs() < TOLERANCE && (bounds.max.x - 1.5).abs() < TOLERANCE);
+    let mut sampler = Sampler::new(0, 0, 0);
+    let light = scene.lights[0]
+        .sample(Vector::zero(), &mut sampler)
+        .unwrap();
+    assert!((light.radiance.x - 15.0 / 25.0).abs() < TOLERANCE);
+
+    let parse = |text: &str| scene_file::parse(text, Path::new("")).err().unwrap();
+    assert_eq!(
+        parse(
+            r#"{"quads": [{"corner": [0, 5, 0], "u": [1, 0, 0], "v": [0, 0, 1],
+                "material": {"type": "emissive", "radiance": [1, 1, 1]},
+                "transform": [{"time": 0}]}]}"#
+        ),
+        "quads[0]: area lights can't follow a keyframed transform"
+    );
+    assert_eq!(
+        parse(
+            r#"{"spheres": [{"center": [0, 0, 0], "radius": 1,
+                "material": {"type": "lambertian", "albedo": [1, 1, 1]},
+                "transform": [{"translate": [1, 0, 0]}]}]}"#
+        ),
+        "spheres[0].transform[0]: missing member \"time\""
+    );
+    assert_eq!(
+        parse(
+            r#"{"camera": {"position": [0, 0, 5], "look_at": [0, 0, 0], "fov": 40, "animation": []}}"#
+        ),
+        "camera.animation: expected at least one keyframe"
+    );
+}
+
+#[test]
+fn a_turntable_orbits_the_pivot_and_loops() {
+    let camera = Camera::new(Vector::zero(), Vector::new(0.0, 0.0, -1.0), 45.0);
+    let turntable = Turntable {
+        pivot: Vector::new(0.0, 0.0, -10.0),
+        radius: 6.0,
+        elevation: 30.0,
+    };
+    let (across, up) = (6.0 * Scalar::to_radians(30.0).cos(), 3.0);
+    let at = |index: usize| turntable.camera(&camera, frame(index, 8));
+    let close = |a: Vector, b: Vector| (a - b).len() < TOLERANCE;
+    assert!(close(at(0).position, Vector::new(0.0, up, -10.0 + across)));
+    assert!(close(at(2).position, Vector::new(across, up, -10.0)));
+    assert!(close(at(4).position, Vector::new(0.0, up, -10.0 - across)));
+    assert!(close(at(8).position, at(0).position));
+    assert_eq!(at(3).look_at, turntable.pivot);
+    assert_eq!(at(3).fov, 45.0);
+
+    // Without a pivot, radius or elevation, the orbit goes through the
+    // camera as it is.
+    let above = Camera::new(Vector::new(0.0, 4.0, 3.0), Vector::zero(), 45.0);
+    let around = Turntable::around(&above);
+    assert!(close(
+        around.camera(&above, frame(0, 8)).position,
+        above.position
+    ));
+    assert!((around.radius - 5.0).abs() < TOLERANCE);
+}
+
+#[test]
+fn the_binary_numbers_each_frame() {
+    let dir = std::env::temp_dir().join(format!("animation-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("scene.json"),
+        r#"{"camera": {"position": [0, 0, 5], "look_at": [0, 0, 0], "fov": 40},
+            "spheres": [{"center": [0, 0, 0], "radius": 1,
+              "material": {"type": "lambertian", "albedo": [1, 1, 1]},
+              "transform": [{"time": 0, "translate": [-1, 0, 0]},
+                            {"time": 1, "translate": [1, 0, 0]}]}],
+            "lights": [{"type": "point", "position": [0, 5, 5], "color": [1, 1, 1],
+              "intensity": 10}]}"#,
+    )
+    .unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_basic-raytracer"))
+        .args(["--scene", "scene.json", "--frames", "3"])
+        .args(["--fps", "12", "--output", "frame_###.png"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    for name in ["frame_000.png", "frame_001.png", "frame_002.png"] {
+        assert!(dir.join(name).exists(), "{}", name);
+    }
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Frame 2 at 0.17 s"), "{}", stdout);
+    assert!(stdout.contains("\n3 frames: "), "{}", stdout);
+
+    for args in [
+        &["--frames", "0"][..],
+        &["--turntable"],
+        &["--frames", "2", "--radius", "3"],
+        &["--frames", "2", "--turntable", "--elevation", "90"],
+    ] {
+        let output = Command::new(env!("CARGO_BIN_EXE_basic-raytracer"))
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_basic-raytracer"))
+        .args(["--frames", "2", "--turntable", "--pivot", "0,0,-10"])
+        .args(["--radius", "6", "--elevation", "15", "--spp", "1"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(dir.join("output_0001.png").exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn ctrl_c_stops_after_the_current_frame() {
+    use std::process::Stdio;
+    use std::thread;
+    use std::time::Duration;
+
+    let dir = std::env::temp_dir().join(format!("animation-stop-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let child = Command::new(env!("CARGO_BIN_EXE_basic-raytracer"))
+        .args(["--frames", "1000"])
+        .current_dir(&dir)
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    while !dir.join("output_0000.png").exists() {
+        thread::sleep(Duration::from_millis(10));
+    }
+    let killed = Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(killed.success());
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Stopped after "), "{}", stdout);
+    assert!(!dir.join("output_0999.png").exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
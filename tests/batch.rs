@@ -0,0 +1,134 @@
+pub mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use basic_raytracer::image::Image;
+use basic_raytracer::stats::{BatchScene, BatchStats};
+use common::run;
+
+/// A scratch directory with three copies of a scene and a broken scene in
+/// its `scenes` directory.
+fn scratch(name: &str) -> PathBuf {
+    let dir = common::scratch(name);
+    fs::create_dir_all(dir.join("scenes")).unwrap();
+    let checker = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes/checker.json");
+    for name in ["first", "second", "third"].iter() {
+        fs::copy(&checker, dir.join("scenes").join(format!("{}.json", name))).unwrap();
+    }
+    fs::write(dir.join("scenes/broken.json"), "{\"spheres\": [").unwrap();
+    dir
+}
+
+/// Runs `render` in `dir`, small and quick, for its exit code, stdout and
+/// stderr.
+fn render(dir: &Path, args: &[&str]) -> (Option<i32>, String, String) {
+    let small = ["render", "--width", "16", "--height", "12", "--spp", "1"];
+    run(dir, &[&small[..], args].concat())
+}
+
+#[test]
+fn a_broken_scene_is_reported_and_the_rest_are_rendered() {
+    let dir = scratch("broken");
+    let scenes = [
+        "scenes/first.json",
+        "scenes/broken.json",
+        "scenes/second.json",
+        "scenes/third.json",
+    ];
+    for jobs in ["1", "2"].iter() {
+        let renders = format!("renders-{}", jobs);
+        let flags = ["--output-dir", &renders, "--jobs", jobs];
+        let (code, stdout, stderr) = render(&dir, &[&scenes[..], &flags[..]].concat());
+        assert_eq!(code, Some(1), "{}", stderr);
+        for name in ["first", "second", "third"].iter() {
+            let image = Image::read_png(&dir.join(&renders).join(format!("{}.png", name)));
+            assert_eq!(image.unwrap().width, 16);
+        }
+        assert!(!dir.join(&renders).join("broken.png").exists());
+        assert!(
+            stdout.contains("scenes/broken.json") && stdout.contains("3 of 4 rendered"),
+            "{}",
+            stdout
+        );
+        assert!(
+            stderr.starts_with("error: scenes/broken.json: line 1"),
+            "{}",
+            stderr
+        );
+    }
+
+    let (code, _, stderr) = render(&dir, &["scenes/first.json", "--output-dir", "renders"]);
+    assert_eq!(code, Some(0), "{}", stderr);
+}
+
+#[test]
+fn batches_need_somewhere_for_each_scene_to_go() {
+    let dir = scratch("options");
+    fs::create_dir_all(dir.join("copy")).unwrap();
+    fs::copy(dir.join("scenes/first.json"), dir.join("copy/first.json")).unwrap();
+    fs::copy(dir.join("scenes/first.json"), dir.join("copy/First.json")).unwrap();
+    for (args, message) in [
+        (
+            &["scenes/first.json", "scenes/second.json"][..],
+            "rendering more than one scene needs --output-dir",
+        ),
+        (
+            &["scenes/first.json", "--jobs", "2"],
+            "--jobs needs --output-dir",
+        ),
+        (
+            &[
+                "scenes/first.json",
+                "copy/first.json",
+                "--output-dir",
+                "out",
+            ],
+            "scenes/first.json and copy/first.json would both be rendered to out/first.png",
+        ),
+        (
+            &[
+                "scenes/first.json",
+                "copy/First.json",
+                "--output-dir",
+                "out",
+            ],
+            "scenes/first.json and copy/First.json would both be rendered to out/First.png",
+        ),
+    ]
+    .iter()
+    {
+        let (code, _, stderr) = render(&dir, args);
+        assert_eq!(
+            (code, stderr.trim()),
+            (Some(2), format!("error: {}", message).as_str())
+        );
+    }
+}
+
+#[test]
+fn batch_stats_tabulate_each_scene_and_the_total() {
+    let stats = BatchStats {
+        scenes: vec![
+            BatchScene {
+                name: "a.json".to_string(),
+                elapsed: Duration::from_millis(1500),
+                error: None,
+            },
+            BatchScene {
+                name: "broken.json".to_string(),
+                elapsed: Duration::from_millis(2),
+                error: Some("broken.json: unexpected end of input".to_string()),
+            },
+        ],
+        elapsed: Duration::from_millis(1502),
+    };
+    assert_eq!(stats.failed().count(), 1);
+    assert_eq!(
+        stats.to_string(),
+        "a.json            1.50s  rendered\n\
+         broken.json      2.00ms  failed\n\
+         total             1.50s  1 of 2 rendered"
+    );
+}
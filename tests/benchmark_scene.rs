@@ -0,0 +1,173 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+use basic_raytracer::aabb::Aabb;
+use basic_raytracer::json;
+use basic_raytracer::ray::EPSILON;
+use basic_raytracer::render::{render, render_to_buffer, IntegratorKind, RenderSettings};
+use basic_raytracer::scalar::{to_f64, Scalar};
+use basic_raytracer::scene::Scene;
+
+fn bounds(scene: &Scene) -> Vec<[u64; 6]> {
+    scene
+        .objects
+        .iter()
+        .map(|object| {
+            let Aabb { min, max } = object.shape.bounds().unwrap();
+            [min.x, min.y, min.z, max.x, max.y, max.z].map(|x| to_f64(x).to_bits())
+        })
+        .collect()
+}
+
+#[test]
+fn the_benchmark_scene_depends_only_on_its_seed() {
+    let (a, b) = (Scene::benchmark(300, 9), Scene::benchmark(300, 9));
+    assert_eq!(a.objects.len(), 300);
+    assert_eq!(a.lights.len(), 2);
+    assert_eq!(bounds(&a), bounds(&b));
+    let settings = RenderSettings {
+        width: 48,
+        height: 36,
+        integrator: IntegratorKind::Whitted,
+        ..RenderSettings::default()
+    };
+    let image = render_to_buffer(&a, &settings).unwrap();
+    assert!(image == render_to_buffer(&b, &settings).unwrap());
+    // Most camera rays hit a sphere, but not all of them.
+    let hits = (0..36 * 48)
+        .filter(|i| {
+            let (u, v) = ((i % 48) as Scalar / 48.0, (i / 48) as Scalar / 36.0);
+            let ray = a.camera.ray(u, v, 4.0 / 3.0);
+            a.intersect(&ray, EPSILON, Scalar::INFINITY).is_some()
+        })
+        .count();
+    assert!(hits > 36 * 48 / 2 && hits < 36 * 48, "{}", hits);
+
+    let other = Scene::benchmark(300, 10);
+    assert_ne!(bounds(&a), bounds(&other));
+    // Every sphere is in front of the camera.
+    for object in &other.objects {
+        assert!(object.shape.bounds().unwrap().max.z < 0.0);
+    }
+}
+
+/// The center and radius of each sphere with bounds.
+fn spheres(scene: &Scene) -> Vec<([f64; 3], f64)> {
+    scene
+        .objects
+        .iter()
+        .filter_map(|object| object.shape.bounds())
+        .map(|Aabb { min, max }| {
+            let center = 0.5 * (min + max);
+            let center = [center.x, center.y, center.z].map(to_f64);
+            (center, to_f64(max.x - min.x) / 2.0)
+        })
+        .collect()
+}
+
+#[test]
+fn random_spheres_rest_on_the_ground_clear_of_each_other() {
+    let scene = Scene::random_spheres(400, 7);
+    // The ground is the one object without bounds.
+    assert_eq!(scene.objects.len(), 1 + 3 + 400);
+    let all = spheres(&scene);
+    assert_eq!(all.len(), 3 + 400);
+    for (i, &(a, r)) in all.iter().enumerate() {
+        assert!(a[1] - r >= -1e-6, "sphere {} is in the ground", i);
+        for (j, &(b, s)) in all[..i].iter().enumerate() {
+            let distance = (0..3).map(|k| (a[k] - b[k]).powi(2)).sum::<f64>().sqrt();
+            assert!(distance > r + s, "spheres {} and {} overlap", j, i);
+        }
+    }
+    assert_eq!(all, spheres(&Scene::random_spheres(400, 7)));
+    assert_ne!(all, spheres(&Scene::random_spheres(400, 8)));
+    // A scene of fewer spheres is laid out afresh, not cut short.
+    assert_eq!(Scene::random_spheres(50, 7).objects.len(), 54);
+}
+
+/// Keeps the warnings it's handed.
+struct Warnings(Arc<Mutex<Vec<String>>>);
+
+impl Log for Warnings {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Warn
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            self.0.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+#[test]
+fn random_spheres_render_the_same_without_warnings() {
+    let warnings = Arc::new(Mutex::new(Vec::new()));
+    log::set_logger(Box::leak(Box::new(Warnings(warnings.clone())))).unwrap();
+    log::set_max_level(LevelFilter::Warn);
+    for integrator in [IntegratorKind::Whitted, IntegratorKind::Path].iter() {
+        let settings = RenderSettings {
+            width: 48,
+            height: 27,
+            spp: 2,
+            integrator: *integrator,
+            seed: 7,
+            ..RenderSettings::default()
+        };
+        let (image, _) = render(&Scene::random_spheres(100, 7), &settings).unwrap();
+        assert!(image
+            .pixels
+            .iter()
+            .all(|p| p.x.is_finite() && p.y.is_finite() && p.z.is_finite()));
+        let (again, _) = render(&Scene::random_spheres(100, 7), &settings).unwrap();
+        assert!(image.pixels == again.pixels);
+    }
+    assert_eq!(*warnings.lock().unwrap(), Vec::<String>::new());
+
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("builtin_scene");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_basic-raytracer"))
+            .args(["render", "--width", "32", "--height", "18", "--spp", "1"])
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        (output.status.code(), stdout, stderr)
+    };
+    let args = ["--builtin-scene", "spheres-60", "--seed", "7"];
+    let (code, stdout, stderr) = run(&[&args[..], &["--report", "report.json"]].concat());
+    assert_eq!(code, Some(0), "{}", stderr);
+    assert!(stdout.starts_with("Made the built-in scene spheres-60 from seed 7"));
+    let report = json::parse(&fs::read_to_string(dir.join("report.json")).unwrap()).unwrap();
+    assert_eq!(report.get("warnings").unwrap().as_array(), Some(&[][..]));
+    let objects = report.get("scene").and_then(|scene| scene.get("objects"));
+    assert_eq!(objects.and_then(|n| n.as_f64()), Some(64.0));
+
+    let (code, _, stderr) = run(&["--builtin-scene", "cubes-9"]);
+    assert_eq!(
+        (code, stderr.trim()),
+        (
+            Some(2),
+            "error: unknown built-in scene \"cubes-9\", expected spheres-N such as spheres-500"
+        )
+    );
+    let scene = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes/checker.json");
+    let (code, _, stderr) = run(&[&args[..], &["--scene", scene.to_str().unwrap()]].concat());
+    assert_eq!(
+        (code, stderr.trim()),
+        (
+            Some(2),
+            "error: --builtin-scene can't be used with a scene file"
+        )
+    );
+}
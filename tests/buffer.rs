@@ -0,0 +1,49 @@
+use std::fs::{self, File};
+
+use basic_raytracer::render::{render, render_to_buffer, Crop, RenderSettings};
+use basic_raytracer::scene::Scene;
+
+#[test]
+fn the_buffer_holds_what_the_png_would() {
+    let dir = std::env::temp_dir().join(format!("buffer-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let scene = Scene::demo();
+    for crop in [
+        None,
+        Some(Crop {
+            x: 21,
+            y: 40,
+            width: 33,
+            height: 7,
+        }),
+    ] {
+        let settings = RenderSettings {
+            width: 96,
+            height: 72,
+            crop,
+            ..RenderSettings::default()
+        };
+        let buffer = render_to_buffer(&scene, &settings).unwrap();
+        let (image, _) = render(&scene, &settings).unwrap();
+        let path = dir.join("image.png");
+        image.write_png(&path).unwrap();
+
+        let decoder = png::Decoder::new(File::open(&path).unwrap());
+        let (info, mut reader) = decoder.read_info().unwrap();
+        let mut data = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut data).unwrap();
+        assert_eq!((info.width, info.height), (image.width, image.height));
+        assert_eq!(buffer.len(), 4 * (image.width * image.height) as usize);
+        assert!(buffer == data);
+        // Lit, not just black.
+        assert!(buffer.chunks(4).any(|pixel| pixel[..3] != [0, 0, 0]));
+    }
+    fs::remove_dir_all(&dir).unwrap();
+
+    let empty = RenderSettings {
+        width: 0,
+        ..RenderSettings::default()
+    };
+    assert!(render_to_buffer(&scene, &empty).is_err());
+}
@@ -0,0 +1,91 @@
+use std::path::Path;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::json;
+use basic_raytracer::scalar::{self, Scalar};
+use basic_raytracer::scene_file;
+use basic_raytracer::vector::Vector;
+
+/// How far off a moved camera may be: `f64` rounding, or the much coarser
+/// rounding of `f32` with the `f32` feature.
+#[cfg(not(feature = "f32"))]
+const TOLERANCE: Scalar = 1e-9;
+#[cfg(feature = "f32")]
+const TOLERANCE: Scalar = 1e-5;
+
+fn close(a: Vector, b: Vector) -> bool {
+    (a - b).len() < TOLERANCE
+}
+
+fn camera() -> Camera {
+    Camera::new(
+        Vector::new(0.0, 2.0, 6.0),
+        Vector::new(0.0, 0.0, -2.0),
+        45.0,
+    )
+}
+
+fn forward(camera: &Camera) -> Vector {
+    (camera.look_at - camera.position).normalize()
+}
+
+#[test]
+fn moving_keeps_the_camera_pointed_the_same_way() {
+    let camera = camera();
+    let moved = camera.translated(Vector::new(1.0, 0.0, 2.0));
+    assert!(close(forward(&moved), forward(&camera)));
+    // Forward is along the line of sight, right is level.
+    assert!(close(
+        moved.position - camera.position - 2.0 * forward(&camera),
+        Vector::new(1.0, 0.0, 0.0)
+    ));
+    let raised = camera.translated(Vector::new(0.0, 1.0, 0.0));
+    assert!(((raised.position - camera.position) * forward(&camera)).abs() < TOLERANCE);
+    assert!(raised.position.y > camera.position.y);
+}
+
+#[test]
+fn orbiting_circles_the_target_at_the_same_distance() {
+    let camera = camera();
+    let distance = (camera.position - camera.look_at).len();
+    let around = camera.orbited(scalar::consts::PI, 0.0);
+    assert_eq!(around.look_at, camera.look_at);
+    assert!(close(around.position, Vector::new(0.0, 2.0, -10.0)));
+
+    let over = camera.orbited(0.3, 0.4);
+    assert!(((over.position - over.look_at).len() - distance).abs() < TOLERANCE);
+    assert!(over.position.y > camera.position.y);
+    // However far it's dragged, it stops short of the pole, where the view
+    // would flip over.
+    let top = camera.orbited(0.0, 10.0);
+    let offset = (top.position - top.look_at).normalize();
+    assert!(offset.y < 1.0 && offset.y > 0.999);
+    assert!(top.orbited(0.0, -0.5).position.y < top.position.y);
+}
+
+#[test]
+fn dollying_moves_along_the_line_of_sight() {
+    let camera = camera();
+    let closer = camera.dollied(0.5);
+    assert!(close(forward(&closer), forward(&camera)));
+    assert!(close(closer.position, Vector::new(0.0, 1.0, 2.0)));
+}
+
+#[test]
+fn a_snapshot_is_a_camera_a_scene_file_reads_back() {
+    let camera = camera().orbited(0.7, 0.2).dollied(0.8);
+    let snapshot = scene_file::camera_value(&camera);
+    let text = format!("{{\"camera\": {}}}", snapshot);
+    let scene = scene_file::parse(&text, Path::new("")).unwrap();
+    assert_eq!(scene.camera.position, camera.position);
+    assert_eq!(scene.camera.look_at, camera.look_at);
+    assert_eq!(scene.camera.up, camera.up);
+    assert_eq!(scene.camera.fov, camera.fov);
+
+    let value = json::parse(r#"{"a": [1, -2.5, true, null], "b\"\n": {}, "c": "é"}"#).unwrap();
+    assert_eq!(json::parse(&value.to_string()).unwrap(), value);
+    assert_eq!(
+        value.to_string(),
+        r#"{"a": [1, -2.5, true, null], "b\"\n": {}, "c": "é"}"#
+    );
+}
@@ -0,0 +1,169 @@
+use std::fs;
+
+use basic_raytracer::animation::{CameraPath, Frame};
+use basic_raytracer::camera::Camera;
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene_file;
+use basic_raytracer::vector::Vector;
+
+/// How near the camera must be to each key it passes through.
+#[cfg(not(feature = "f32"))]
+const TOLERANCE: Scalar = 1e-9;
+#[cfg(feature = "f32")]
+const TOLERANCE: Scalar = 1e-4;
+/// The step velocities are taken over, and how much they may change over
+/// one. A step short enough to see no curvature in the `f64` path rounds to
+/// noise in `f32`, so a longer one is taken there and a little curvature
+/// allowed for; a kink is still off by far more.
+#[cfg(not(feature = "f32"))]
+const STEP: (Scalar, Scalar) = (1e-6, 1e-3);
+#[cfg(feature = "f32")]
+const STEP: (Scalar, Scalar) = (1e-4, 0.1);
+
+fn close(a: Vector, b: Vector, tolerance: Scalar) -> bool {
+    (a - b).len() < tolerance
+}
+
+/// A path through keys at uneven times, turning to look at two targets
+/// while it zooms in.
+fn path() -> (Vec<(Scalar, Camera)>, CameraPath) {
+    let key = |time, position, look_at, fov| (time, Camera::new(position, look_at, fov));
+    let keys = vec![
+        key(0.0, Vector::new(0.0, 1.0, 8.0), Vector::zero(), 40.0),
+        key(0.2, Vector::new(4.0, 2.0, 5.0), Vector::zero(), 45.0),
+        key(
+            0.7,
+            Vector::new(5.0, 1.0, -3.0),
+            Vector::new(0.0, 0.0, -4.0),
+            70.0,
+        ),
+        key(
+            1.0,
+            Vector::new(0.0, 3.0, -7.0),
+            Vector::new(0.0, 0.0, -4.0),
+            30.0,
+        ),
+    ];
+    (keys.clone(), CameraPath::new(keys))
+}
+
+fn velocity(path: &CameraPath, time: Scalar, step: Scalar) -> Vector {
+    (1.0 / step) * (path.at(time + step).position - path.at(time).position)
+}
+
+#[test]
+fn the_path_passes_through_every_key() {
+    let (keys, path) = path();
+    for (time, key) in &keys {
+        let camera = path.at(*time);
+        assert_eq!(camera.position, key.position);
+        assert_eq!(camera.fov, key.fov);
+        let forward = (key.look_at - key.position).normalize();
+        let looking = (camera.look_at - camera.position).normalize();
+        assert!(close(looking, forward, TOLERANCE), "{:?}", time);
+        assert!(close(camera.look_at, key.look_at, TOLERANCE), "{:?}", time);
+    }
+    // Outside the keys the camera holds still.
+    assert_eq!(path.at(-1.0).position, keys[0].1.position);
+    assert_eq!(path.at(2.0).position, keys[3].1.position);
+}
+
+#[test]
+fn the_velocity_is_continuous_along_the_path() {
+    let (keys, path) = path();
+    let (step, smooth) = STEP;
+    // Through each inner key, the velocity just before matches the velocity
+    // just after, where a polyline or separate curves would kink.
+    for (time, _) in &keys[1..keys.len() - 1] {
+        let before = velocity(&path, time - step, step);
+        let after = velocity(&path, *time, step);
+        assert!(
+            close(before, after, smooth),
+            "{}: {:?} {:?}",
+            time,
+            before,
+            after
+        );
+        assert!(before.len() > 1.0);
+    }
+    // And it changes smoothly between the keys too.
+    for pair in keys.windows(2) {
+        let middle = 0.5 * (pair[0].0 + pair[1].0);
+        let before = velocity(&path, middle - step, step);
+        let after = velocity(&path, middle, step);
+        assert!(close(before, after, smooth), "{}", middle);
+    }
+    // The field of view changes linearly from key to key, so a dolly zoom
+    // widens it while the camera moves in.
+    assert!((path.at(0.45).fov - 57.5).abs() < TOLERANCE);
+}
+
+#[test]
+fn the_camera_turns_between_targets_along_the_shorter_arc() {
+    let (_, path) = path();
+    let camera = path.at(0.45);
+    let forward = (camera.look_at - camera.position).normalize();
+    let from = (Vector::zero() - Vector::new(4.0, 2.0, 5.0)).normalize();
+    let to = (Vector::new(0.0, 0.0, -4.0) - Vector::new(5.0, 1.0, -3.0)).normalize();
+    // Halfway in time is halfway through the turn.
+    let angle = |a: Vector, b: Vector| (a * b).clamp(-1.0, 1.0).acos();
+    assert!((angle(from, forward) - angle(forward, to)).abs() < TOLERANCE);
+    assert!(angle(from, forward) < angle(from, to));
+    // The camera stays upright.
+    assert!((camera.up * forward).abs() < TOLERANCE);
+    assert!(camera.up.y > 0.9);
+}
+
+#[test]
+fn scene_files_fly_the_camera_along_a_path() {
+    let dir = std::env::temp_dir().join(format!("camera-path-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("paths")).unwrap();
+    let keys = r#"[{"time": 1, "position": [4, 0, 0], "fov": 60},
+                   {"time": 0, "position": [0, 0, 4]},
+                   {"time": 0.5, "position": [3, 0, 3]}]"#;
+    fs::write(dir.join("paths/fly.json"), keys).unwrap();
+    let camera = r#"{"position": [0, 0, 5], "look_at": [0, 0, 0], "fov": 40, "path": "#;
+    let inline = format!(r#"{{"camera": {}{}}}}}"#, camera, keys);
+    let from_file = format!(r#"{{"camera": {}"paths/fly.json"}}}}"#, camera);
+    for text in [&inline, &from_file] {
+        let mut scene = scene_file::parse(text, &dir).unwrap();
+        assert_eq!(scene.camera.position, Vector::new(0.0, 0.0, 4.0));
+        scene.set_frame(Frame {
+            index: 2,
+            count: 5,
+            fps: 24.0,
+        });
+        assert_eq!(scene.camera.position, Vector::new(3.0, 0.0, 3.0));
+        assert_eq!(scene.camera.fov, 40.0);
+        assert!(close(scene.camera.look_at, Vector::zero(), TOLERANCE));
+    }
+
+    let parse = |text: &str| scene_file::parse(text, &dir).err().unwrap();
+    assert_eq!(
+        parse(&format!(
+            r#"{{"camera": {}"paths/missing.json"}}}}"#,
+            camera
+        ))
+        .split(':')
+        .next(),
+        Some("camera.path")
+    );
+    fs::write(dir.join("paths/bad.json"), r#"[{"time": 0, "fov": 200}]"#).unwrap();
+    assert_eq!(
+        parse(&format!(r#"{{"camera": {}"paths/bad.json"}}}}"#, camera)),
+        "paths/bad.json[0].fov: expected an angle between 0 and 180 degrees, found 200"
+    );
+    assert_eq!(
+        parse(&format!(
+            r#"{{"camera": {}[{{"time": 0}}], "animation": [{{"time": 0}}]}}}}"#,
+            camera
+        )),
+        "camera.path: can't be used along with \"animation\""
+    );
+    assert_eq!(
+        parse(&format!(r#"{{"camera": {}[]}}}}"#, camera)),
+        "camera.path: expected at least one keyframe"
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
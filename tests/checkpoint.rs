@@ -0,0 +1,230 @@
+use std::fs;
+use std::process::Command;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::checkpoint::{fingerprint, Checkpoint};
+use basic_raytracer::error::Error;
+use basic_raytracer::image::Image;
+use basic_raytracer::light::PointLight;
+use basic_raytracer::material::Lambertian;
+use basic_raytracer::render::{
+    render, render_progressive, render_progressive_from, IntegratorKind, RenderSettings,
+};
+use basic_raytracer::scene::Scene;
+use basic_raytracer::shapes::{Plane, Sphere};
+use basic_raytracer::vector::{Color, Vector};
+
+fn scene() -> Scene {
+    let mut scene = Scene::new(Camera::new(
+        Vector::new(0.0, 1.0, 4.0),
+        Vector::zero(),
+        45.0,
+    ));
+    scene.add(
+        Plane::new(Vector::new(0.0, -1.0, 0.0), Vector::new(0.0, 1.0, 0.0)),
+        Arc::new(Lambertian::new(Color::new(0.2, 0.6, 0.3))),
+    );
+    scene.add(
+        Sphere::new(Vector::zero(), 0.8),
+        Arc::new(Lambertian::new(Color::new(0.9, 0.3, 0.1))),
+    );
+    scene.add_light(PointLight::new(
+        Vector::new(2.0, 4.0, 3.0),
+        Color::new(1.0, 1.0, 1.0),
+        40.0,
+    ));
+    scene
+}
+
+fn settings(spp: u32) -> RenderSettings {
+    RenderSettings {
+        width: 32,
+        height: 24,
+        spp,
+        integrator: IntegratorKind::Path,
+        seed: 3,
+        ..RenderSettings::default()
+    }
+}
+
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn a_resumed_render_ends_where_an_uninterrupted_one_does() {
+    let dir = temp_dir("checkpoint");
+    let path = dir.join("half.ckpt");
+    let scene = scene();
+    let per_pass = settings(1);
+    let print = fingerprint(&scene, b"", &per_pass);
+    // Dies half way, just after checkpointing.
+    let result = render_progressive(
+        &scene,
+        &per_pass,
+        8,
+        |done, image| {
+            if done == 4 {
+                let checkpoint = Checkpoint {
+                    fingerprint: print,
+                    samples: done,
+                    image: image.clone(),
+                };
+                checkpoint.write(&path)?;
+                return Err(Error::Cancelled);
+            }
+            Ok(())
+        },
+        |_| true,
+    );
+    assert!(matches!(result, Err(Error::Cancelled)));
+    // Only the checkpoint is left, not the file it was written to first.
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+    let saved = Checkpoint::read(&path).unwrap();
+    assert_eq!((saved.fingerprint, saved.samples), (print, 4));
+    let (image, stats) = render_progressive_from(
+        &scene,
+        &per_pass,
+        4..8,
+        saved.image,
+        &AtomicBool::new(false),
+        |_, _| Ok(()),
+        |_| true,
+    )
+    .unwrap();
+    let (uninterrupted, _) = render(&scene, &settings(8)).unwrap();
+    assert!(image.pixels == uninterrupted.pixels);
+    assert_eq!(stats.camera_rays, 4 * 32 * 24);
+
+    // Anything that changes the samples changes the fingerprint.
+    let reseeded = RenderSettings {
+        seed: 4,
+        ..per_pass.clone()
+    };
+    assert_ne!(fingerprint(&scene, b"", &reseeded), print);
+    assert_ne!(fingerprint(&scene, b"{}", &per_pass), print);
+    let mut moved = self::scene();
+    assert_eq!(fingerprint(&moved, b"", &per_pass), print);
+    moved.camera.position.x += 1.0;
+    assert_ne!(fingerprint(&moved, b"", &per_pass), print);
+
+    let bytes = fs::read(&path).unwrap();
+    fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+    let error = Checkpoint::read(&path).err().unwrap().to_string();
+    assert!(error.ends_with("the checkpoint is cut short"), "{}", error);
+    fs::write(&path, "not one").unwrap();
+    let error = Checkpoint::read(&path).err().unwrap().to_string();
+    assert!(error.ends_with("not a checkpoint"), "{}", error);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn the_binary_resumes_from_a_checkpoint() {
+    let dir = temp_dir("checkpoint-binary");
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_basic-raytracer"))
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .unwrap()
+    };
+    let half = run(&[
+        "--spp",
+        "2",
+        "--checkpoint",
+        "demo.ckpt",
+        "--output",
+        "half.png",
+    ]);
+    assert!(half.status.success());
+    assert_eq!(Checkpoint::read(&dir.join("demo.ckpt")).unwrap().samples, 2);
+    let resumed = run(&[
+        "--spp",
+        "4",
+        "--resume",
+        "demo.ckpt",
+        "--output",
+        "resumed.png",
+    ]);
+    assert!(String::from_utf8(resumed.stdout)
+        .unwrap()
+        .contains("Resuming at 2 samples a pixel"));
+    assert!(run(&["--spp", "4", "--output", "whole.png"])
+        .status
+        .success());
+    assert_eq!(
+        fs::read(dir.join("resumed.png")).unwrap(),
+        fs::read(dir.join("whole.png")).unwrap()
+    );
+    assert_eq!(Checkpoint::read(&dir.join("demo.ckpt")).unwrap().samples, 4);
+
+    for args in [
+        &["--spp", "8", "--max-depth", "2", "--resume", "demo.ckpt"][..],
+        &["--checkpoint-interval", "5"],
+        &["--checkpoint", "demo.ckpt", "--checkpoint-interval", "-1"],
+        &["--checkpoint", "demo.ckpt", "--frames", "2"],
+        &["--resume", "demo.ckpt", "--output", "demo.gif"],
+    ] {
+        assert_eq!(run(args).status.code(), Some(2), "{:?}", args);
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn a_checkpoint_is_refused_once_a_file_the_scene_refers_to_changes() {
+    let dir = temp_dir("checkpoint-references");
+    let texture = |color: Color| {
+        let mut image = Image::new(2, 2);
+        for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)].iter() {
+            image.set(*x, *y, color);
+        }
+        image.write_png(&dir.join("texture.png")).unwrap();
+    };
+    texture(Color::new(0.8, 0.2, 0.2));
+    fs::write(
+        dir.join("scene.json"),
+        r#"{"spheres": [{"center": [0, 0, -3], "radius": 1, "material":
+            {"type": "lambertian", "albedo": {"type": "image", "path": "texture.png"}}}],
+            "lights": [{"type": "point", "position": [2, 2, 0], "color": [1, 1, 1],
+            "intensity": 20}]}"#,
+    )
+    .unwrap();
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_basic-raytracer"))
+            .args(["--scene", "scene.json", "--width", "16", "--height", "12"])
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        (
+            output.status.code(),
+            String::from_utf8(output.stderr).unwrap(),
+        )
+    };
+    let (code, stderr) = run(&["--spp", "1", "--checkpoint", "scene.ckpt"]);
+    assert_eq!(code, Some(0), "{}", stderr);
+
+    // The texture's the same size, so nothing but its pixels differ.
+    texture(Color::new(0.2, 0.2, 0.8));
+    let (code, stderr) = run(&["--spp", "2", "--resume", "scene.ckpt"]);
+    assert_eq!(
+        (code, stderr.trim()),
+        (
+            Some(2),
+            "error: invalid render settings: scene.ckpt is a checkpoint of a different scene or settings"
+        )
+    );
+    fs::remove_file(dir.join("texture.png")).unwrap();
+    assert_eq!(run(&["--spp", "2", "--resume", "scene.ckpt"]).0, Some(2));
+
+    texture(Color::new(0.8, 0.2, 0.2));
+    let (code, stderr) = run(&["--spp", "2", "--resume", "scene.ckpt"]);
+    assert_eq!(code, Some(0), "{}", stderr);
+    fs::remove_dir_all(&dir).unwrap();
+}
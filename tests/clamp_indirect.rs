@@ -0,0 +1,102 @@
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::image::Image;
+use basic_raytracer::material::{Dielectric, Lambertian};
+use basic_raytracer::render::{render, IntegratorKind, RenderSettings};
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene::Scene;
+use basic_raytracer::shapes::{Plane, Quad, Sphere};
+use basic_raytracer::vector::{Color, Vector};
+
+// A glass sphere under a small, bright light, seen from just above the floor
+// so that the sphere itself is out of view. Paths from the floor that find
+// the light through the glass are rare and very bright.
+fn glass_sphere() -> Scene {
+    let mut camera = Camera::new(
+        Vector::new(0.0, 0.5, 0.0),
+        Vector::new(0.0, 0.0, 0.0),
+        120.0,
+    );
+    camera.up = Vector::new(0.0, 0.0, -1.0);
+    let mut scene = Scene::new(camera);
+    scene.add(
+        Plane::new(Vector::zero(), Vector::new(0.0, 1.0, 0.0)),
+        Arc::new(Lambertian::new(Color::new(0.75, 0.75, 0.75))),
+    );
+    scene.add(
+        Sphere::new(Vector::new(0.0, 1.2, 0.0), 0.6),
+        Arc::new(Dielectric::new(1.5)),
+    );
+    scene.add_area_light(
+        Quad::new(
+            Vector::new(-0.15, 3.0, -0.15),
+            Vector::new(0.3, 0.0, 0.0),
+            Vector::new(0.0, 0.0, 0.3),
+        ),
+        Color::new(200.0, 200.0, 200.0),
+    );
+    scene
+}
+
+fn percentile(image: &Image, fraction: Scalar) -> Scalar {
+    let mut values: Vec<Scalar> = image.pixels.iter().map(|p| p.max_component()).collect();
+    values.sort_by(Scalar::total_cmp);
+    values[((values.len() - 1) as Scalar * fraction) as usize]
+}
+
+#[test]
+fn clamping_bounds_fireflies() {
+    let scene = glass_sphere();
+    let settings = RenderSettings {
+        width: 48,
+        height: 48,
+        integrator: IntegratorKind::Path,
+        spp: 16,
+        ..RenderSettings::default()
+    };
+    let (unclamped, _) = render(&scene, &settings).unwrap();
+    let (clamped, _) = render(
+        &scene,
+        &RenderSettings {
+            clamp_indirect: 1.0,
+            ..settings.clone()
+        },
+    )
+    .unwrap();
+    let unclamped_peak = percentile(&unclamped, 0.999);
+    let clamped_peak = percentile(&clamped, 0.999);
+    assert!(unclamped_peak > 5.0, "unclamped peak {}", unclamped_peak);
+    assert!(clamped_peak < 1.5, "clamped peak {}", clamped_peak);
+
+    // The corners lie outside the sphere's shadow and are lit directly, so
+    // clamping should leave their mean alone up to the sampling noise.
+    let corners = |image: &Image| -> Vec<Scalar> {
+        let mut values = Vec::new();
+        for y in 0..48 {
+            for x in 0..48 {
+                let (dx, dy) = (x as Scalar - 23.5, y as Scalar - 23.5);
+                if dx * dx + dy * dy > 20.0 * 20.0 {
+                    values.push(image.get(x, y).x);
+                }
+            }
+        }
+        values
+    };
+    let mean = |values: &[Scalar]| values.iter().sum::<Scalar>() / values.len() as Scalar;
+    let before = corners(&unclamped);
+    let after = corners(&clamped);
+    let variance = before
+        .iter()
+        .map(|v| (v - mean(&before)).powi(2))
+        .sum::<Scalar>()
+        / (before.len() - 1) as Scalar;
+    let standard_error = (variance / before.len() as Scalar).sqrt();
+    assert!(mean(&after) > 0.03, "corner mean {}", mean(&after));
+    assert!(
+        (mean(&before) - mean(&after)).abs() < 3.0 * standard_error,
+        "corner mean {} unclamped vs {} clamped",
+        mean(&before),
+        mean(&after)
+    );
+}
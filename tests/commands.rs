@@ -0,0 +1,192 @@
+pub mod common;
+
+use std::path::Path;
+
+use basic_raytracer::image::Image;
+use common::{run, scratch};
+
+fn scene_path(name: &str) -> String {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("scenes")
+        .join(name)
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[test]
+fn without_a_command_the_demo_is_rendered_as_before() {
+    let dir = scratch("none");
+    let (code, stdout, _) = run(&dir, &["--draft", "8", "--draft-only"]);
+    assert_eq!(code, Some(0));
+    assert!(stdout.contains("Drafted output.png at 80x60"), "{}", stdout);
+    assert!(dir.join("output.png").exists());
+
+    let (code, _, stderr) = run(&dir, &["render.json"]);
+    assert_eq!(code, Some(2));
+    assert_eq!(
+        stderr.trim(),
+        "error: unknown command \"render.json\", expected render, preview, diff, merge or info"
+    );
+}
+
+#[test]
+fn render_takes_the_scene_on_its_own_or_with_scene() {
+    let dir = scratch("render");
+    let checker = scene_path("checker.json");
+    let args = ["--draft", "8", "--draft-only", "--output"];
+    let (code, _, stderr) = run(
+        &dir,
+        &[&["render", &checker], &args[..], &["alone.png"]].concat(),
+    );
+    assert_eq!(code, Some(0), "{}", stderr);
+    let flag = [&["render", "--scene", &checker], &args[..], &["flag.png"]].concat();
+    assert_eq!(run(&dir, &flag).0, Some(0));
+    let alone = Image::read_png(&dir.join("alone.png")).unwrap();
+    assert!(alone.pixels == Image::read_png(&dir.join("flag.png")).unwrap().pixels);
+
+    let (code, _, stderr) = run(&dir, &["render", &checker, "other.json"]);
+    assert_eq!(code, Some(2));
+    assert_eq!(
+        stderr.trim(),
+        "error: rendering more than one scene needs --output-dir"
+    );
+    let (code, _, stderr) = run(&dir, &["render", "--spp"]);
+    assert_eq!(
+        (code, stderr.trim()),
+        (Some(2), "error: --spp needs a value")
+    );
+}
+
+#[test]
+fn preview_refuses_what_only_a_render_to_a_file_can_do() {
+    let dir = scratch("preview");
+    let (code, _, stderr) = run(&dir, &["preview", "--frames", "2"]);
+    assert_eq!(code, Some(2));
+    if cfg!(feature = "preview") {
+        assert_eq!(
+            stderr.trim(),
+            "error: --interactive can't be used with --frames or --all-layers"
+        );
+        let (code, _, stderr) = run(&dir, &["preview", "--preview"]);
+        assert_eq!(code, Some(2));
+        assert!(stderr.contains("which is for render"), "{}", stderr);
+    } else {
+        assert_eq!(
+            stderr.trim(),
+            "error: preview needs a build with `--features preview`"
+        );
+    }
+}
+
+#[test]
+fn diff_checks_its_arguments() {
+    let dir = scratch("diff");
+    let (code, _, stderr) = run(&dir, &["diff", "a.png", "b.png", "c.png"]);
+    assert_eq!(code, Some(2));
+    assert!(
+        stderr.starts_with("error: usage: diff a.png b.png"),
+        "{}",
+        stderr
+    );
+    let (code, _, stderr) = run(&dir, &["diff", "a.png", "b.png", "--gain", "0"]);
+    assert_eq!(code, Some(2));
+    assert_eq!(stderr.trim(), "error: --gain must be a positive number");
+    let (code, _, stderr) = run(&dir, &["diff", "a.png", "b.png", "--spp", "4"]);
+    assert_eq!(code, Some(2));
+    assert_eq!(stderr.trim(), "error: unknown argument \"--spp\"");
+}
+
+#[test]
+fn info_prints_a_summary_of_the_scene() {
+    let dir = scratch("info");
+    let shapes = scene_path("shapes.json");
+    let (code, stdout, stderr) = run(&dir, &["info", &shapes]);
+    assert_eq!(code, Some(0), "{}", stderr);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], format!("scene: {}", shapes));
+    assert_eq!(lines[1], "objects: 5 (1 unbounded, 0 animated)");
+    assert_eq!(lines[2], "triangles: 12");
+    assert_eq!(lines[3], "lights: 1 (PointLight 1)");
+    assert_eq!(lines[4], "materials: 5 (Lambertian 5)");
+    assert!(lines[5].starts_with("bounds: ("), "{}", lines[5]);
+    assert!(lines[7].starts_with("estimated memory: "), "{}", lines[7]);
+    let mesh = lines
+        .iter()
+        .find(|line| line.contains("TriangleMesh"))
+        .unwrap();
+    assert!(
+        mesh.starts_with("      4  TriangleMesh   Lambertian            12"),
+        "{}",
+        mesh
+    );
+    assert_eq!(lines.len(), 10 + 5);
+
+    let (code, stdout, _) = run(&dir, &["info"]);
+    assert_eq!(code, Some(0));
+    assert!(
+        stdout.starts_with("scene: the built-in demo\nobjects: 1 "),
+        "{}",
+        stdout
+    );
+
+    let (code, _, stderr) = run(&dir, &["info", &shapes, "--spp", "4"]);
+    assert_eq!(
+        (code, stderr.trim()),
+        (Some(2), "error: usage: info [scene.json]")
+    );
+    let (code, _, stderr) = run(&dir, &["info", "missing.json"]);
+    assert_eq!(code, Some(5));
+    assert!(
+        stderr.starts_with("error: can't read missing.json"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn global_flags_go_anywhere_and_apply_to_every_command() {
+    let dir = scratch("global");
+    let shapes = scene_path("shapes.json");
+    // Quiet, info only says by its exit code whether the scene loads.
+    let (code, stdout, _) = run(&dir, &["--quiet", "info", &shapes]);
+    assert_eq!((code, stdout.as_str()), (Some(0), ""));
+    let (code, stdout, _) = run(&dir, &["info", "missing.json", "--quiet"]);
+    assert_eq!((code, stdout.as_str()), (Some(5), ""));
+
+    let draft = ["--draft", "8", "--draft-only", "--output"];
+    let (code, stdout, _) = run(
+        &dir,
+        &[
+            &["render", "--threads", "1", "--quiet"],
+            &draft[..],
+            &["one.png"],
+        ]
+        .concat(),
+    );
+    assert_eq!((code, stdout.as_str()), (Some(0), ""));
+    let (code, _, _) = run(
+        &dir,
+        &[&["--threads", "3"], &draft[..], &["three.png"]].concat(),
+    );
+    assert_eq!(code, Some(0));
+    // However many threads render it, the image is the same.
+    let one = Image::read_png(&dir.join("one.png")).unwrap();
+    assert!(one.pixels == Image::read_png(&dir.join("three.png")).unwrap().pixels);
+
+    for (args, message) in [
+        (
+            &["--threads", "0"][..],
+            "error: --threads must be at least 1",
+        ),
+        (&["info", "--threads"], "error: --threads needs a value"),
+        (
+            &["diff", "--threads", "many"],
+            "error: invalid value \"many\" for --threads",
+        ),
+    ]
+    .iter()
+    {
+        let (code, _, stderr) = run(&dir, args);
+        assert_eq!((code, stderr.trim()), (Some(2), *message));
+    }
+}
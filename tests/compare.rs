@@ -0,0 +1,170 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use basic_raytracer::compare::{compare, heatmap};
+use basic_raytracer::image::Image;
+use basic_raytracer::json;
+use basic_raytracer::scalar;
+use basic_raytracer::vector::Color;
+
+fn filled(width: u32, height: u32, color: Color) -> Image {
+    let mut image = Image::new(width, height);
+    for pixel in image.pixels.iter_mut() {
+        *pixel = color;
+    }
+    image
+}
+
+fn checkerboard(size: u32) -> Image {
+    let mut image = Image::new(size, size);
+    for y in 0..size {
+        for x in 0..size {
+            let bright = (x / 4 + y / 4) % 2 == 0;
+            let value = if bright { 0.8 } else { 0.2 };
+            image.set(x, y, Color::new(value, value, value));
+        }
+    }
+    image
+}
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-5
+}
+
+#[test]
+fn identical_images_are_a_perfect_match() {
+    let image = checkerboard(32);
+    let comparison = compare(&image, &image).unwrap();
+    assert_eq!(comparison.mean_absolute_error, Color::zero());
+    assert_eq!(comparison.psnr, f64::INFINITY);
+    assert!(close(comparison.ssim, 1.0), "{}", comparison.ssim);
+    assert_eq!(
+        comparison.to_json().to_string(),
+        r#"{"mean_absolute_error": [0, 0, 0], "psnr": null, "ssim": 1}"#
+    );
+}
+
+#[test]
+fn errors_are_measured_per_channel() {
+    let a = filled(20, 10, Color::new(0.5, 0.5, 0.5));
+    let b = filled(20, 10, Color::new(0.6, 0.5, 0.2));
+    let comparison = compare(&a, &b).unwrap();
+    let error = comparison.mean_absolute_error;
+    assert!(
+        close(scalar::to_f64(error.x), 0.1)
+            && error.y == 0.0
+            && close(scalar::to_f64(error.z), 0.3)
+    );
+    assert!(close(comparison.error(), 0.4 / 3.0));
+    // The mean square error is (0.01 + 0.09) / 3.
+    assert!(close(comparison.psnr, -10.0 * (0.1f64 / 3.0).log10()));
+}
+
+#[test]
+fn ssim_falls_as_structure_is_lost() {
+    let image = checkerboard(32);
+    let gray = filled(32, 32, Color::new(0.5, 0.5, 0.5));
+    let mut brighter = image.clone();
+    for pixel in brighter.pixels.iter_mut() {
+        *pixel += Color::new(0.05, 0.05, 0.05);
+    }
+    let shifted = compare(&image, &brighter).unwrap().ssim;
+    let flattened = compare(&image, &gray).unwrap().ssim;
+    assert!(0.9 < shifted && shifted < 1.0, "{}", shifted);
+    assert!(flattened < 0.1, "{}", flattened);
+}
+
+#[test]
+fn images_of_different_sizes_cant_be_compared() {
+    let error = compare(&Image::new(4, 3), &Image::new(3, 4)).unwrap_err();
+    assert_eq!(error, "the images are different sizes, 4x3 and 3x4");
+}
+
+#[test]
+fn the_heatmap_runs_from_black_through_red_and_yellow_to_white() {
+    let black = filled(4, 1, Color::zero());
+    let mut other = black.clone();
+    for (x, value) in [0.0, 0.125, 0.25, 1.0].iter().enumerate() {
+        other.set(x as u32, 0, Color::new(*value, *value, *value));
+    }
+    // With a gain of 4, a difference of 0.125 is halfway along.
+    let map = heatmap(&black, &other, 4.0);
+    assert_eq!(map.get(0, 0), Color::zero());
+    assert_eq!(map.get(1, 0), Color::new(1.0, 0.5, 0.0));
+    assert_eq!(map.get(2, 0), Color::new(1.0, 1.0, 1.0));
+    assert_eq!(map.get(3, 0), Color::new(1.0, 1.0, 1.0));
+}
+
+#[test]
+fn the_diff_subcommand_exits_by_what_it_finds() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("compare");
+    fs::create_dir_all(&dir).unwrap();
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_basic-raytracer"))
+            .arg("diff")
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        (output.status.code(), stdout, stderr)
+    };
+    let image = checkerboard(32);
+    let mut lighter = image.clone();
+    lighter.set(0, 0, Color::new(0.85, 0.85, 0.85));
+    image.write(&dir.join("a.png")).unwrap();
+    lighter.write(&dir.join("b.png")).unwrap();
+    // Past white, which only an EXR keeps.
+    lighter.set(0, 0, Color::new(4.0, 4.0, 4.0));
+    lighter.write(&dir.join("b.exr")).unwrap();
+    Image::new(16, 32).write(&dir.join("small.png")).unwrap();
+
+    let (code, stdout, _) = run(&["a.png", "b.png", "--out", "heat.png"]);
+    assert_eq!(code, Some(0));
+    assert!(
+        stdout.starts_with("mean absolute error: 0.000"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("PSNR: ") && stdout.contains("SSIM: 0.9"));
+    let heat = Image::read(&dir.join("heat.png")).unwrap();
+    // Halfway along with the default gain of 10, give or take the PNG's
+    // rounding.
+    let hot = heat.get(0, 0);
+    assert!(
+        hot.x == 1.0 && 0.4 < hot.y && hot.y < 0.6 && hot.z == 0.0,
+        "{:?}",
+        hot
+    );
+    assert_eq!(heat.get(31, 31), Color::zero());
+
+    let (code, _, stderr) = run(&["a.png", "b.png", "--threshold", "0.00001"]);
+    assert_eq!(code, Some(1));
+    assert!(
+        stderr.starts_with("mean absolute error 0.000"),
+        "{}",
+        stderr
+    );
+    assert_eq!(run(&["a.png", "b.png", "--min-ssim", "0.99999"]).0, Some(1));
+    assert_eq!(run(&["a.png", "a.png", "--min-psnr", "100"]).0, Some(0));
+
+    let (code, stdout, _) = run(&["a.png", "b.exr", "--json", "--threshold", "0.001"]);
+    assert_eq!(code, Some(1));
+    let json = json::parse(stdout.trim()).unwrap();
+    let error = json.get("mean_absolute_error").unwrap().as_array().unwrap();
+    // The 3.2 past the PNG's white counts, spread over 1024 pixels.
+    let red = error[0].as_f64().unwrap();
+    assert!((red - 3.2 / 1024.0).abs() < 2e-3, "{}", red);
+    assert_eq!(json.get("passed").unwrap().as_bool(), Some(false));
+
+    let (code, _, stderr) = run(&["a.png", "small.png"]);
+    assert_eq!(code, Some(2));
+    assert_eq!(
+        stderr.trim(),
+        "error: can't compare a.png with small.png: the images are different sizes, 32x32 and 16x32"
+    );
+    assert_eq!(run(&["a.png", "missing.png"]).0, Some(2));
+    assert_eq!(run(&["a.png"]).0, Some(2));
+}
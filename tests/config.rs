@@ -0,0 +1,219 @@
+pub mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use basic_raytracer::config::{self, Settings};
+use basic_raytracer::image::{Image, Tonemap};
+use basic_raytracer::scene_file;
+use basic_raytracer::vector::Color;
+use common::{run, scratch};
+
+#[test]
+fn config_files_take_the_toml_they_need() {
+    let settings = config::parse(
+        "# Defaults for every render\n\
+         width = 1_920\n\
+         height = 1080 # HD\n\
+         \n\
+         spp = 1e2\n\
+         tonemap = \"aces\"\n\
+         output = 'renders\\frame-###.png'\n",
+    )
+    .unwrap();
+    assert_eq!(
+        settings,
+        Settings {
+            width: Some(1920),
+            height: Some(1080),
+            spp: Some(100),
+            tonemap: Some(Tonemap::Aces),
+            output: Some(PathBuf::from("renders\\frame-###.png")),
+            ..Settings::default()
+        }
+    );
+    // What it writes, it reads back the same.
+    assert_eq!(config::parse(&settings.to_toml()).unwrap(), settings);
+    assert_eq!(
+        config::parse("output = \"a \\\"b\\\" \\u00e9.png\"")
+            .unwrap()
+            .output,
+        Some(PathBuf::from("a \"b\" \u{e9}.png"))
+    );
+}
+
+#[test]
+fn what_isnt_a_setting_is_refused_by_name() {
+    for (text, message) in [
+        ("spp = 4\nsamples = 16", "line 2: unknown key \"samples\""),
+        ("[render]\nspp = 4", "line 1: tables aren't supported"),
+        ("render.spp = 4", "line 1: dotted keys aren't supported"),
+        ("spp = 4\nspp = 8", "line 2: spp is set twice"),
+        (
+            "spp = -1",
+            "line 1: spp: expected a non-negative integer, found a number",
+        ),
+        (
+            "tonemap = \"filmic\"",
+            "line 1: tonemap: unknown tonemap \"filmic\", expected clamp, reinhard or aces",
+        ),
+        ("width = 1__0", "line 1: invalid value \"1__0\""),
+        ("output = \"out.png", "line 1: unterminated string"),
+        ("width = 10 20", "line 1: unexpected \"20\" after the value"),
+    ]
+    .iter()
+    {
+        assert_eq!(config::parse(text).unwrap_err(), *message);
+    }
+}
+
+#[test]
+fn tonemaps_bring_bright_light_under_white() {
+    let bright = Color::new(0.0, 1.0, 3.0);
+    assert_eq!(Tonemap::Clamp.apply(bright), bright);
+    assert_eq!(Tonemap::Reinhard.apply(bright), Color::new(0.0, 0.5, 0.75));
+    let aces = Tonemap::Aces.apply(bright);
+    assert!(aces.x.abs() < 1e-6 && aces.y < aces.z && aces.z <= 1.0);
+    for tonemap in Tonemap::ALL.iter() {
+        assert_eq!(Tonemap::from_name(tonemap.name()), Some(*tonemap));
+    }
+
+    let dir = scratch("tonemap");
+    let mut image = Image::new(2, 1);
+    image.set(1, 0, Color::new(3.0, 3.0, 3.0));
+    let (png, exr) = (dir.join("mapped.png"), dir.join("linear.exr"));
+    image.write_tonemapped(&png, Tonemap::Reinhard).unwrap();
+    image.write_tonemapped(&exr, Tonemap::Reinhard).unwrap();
+    let mapped = Image::read(&png).unwrap().get(1, 0);
+    assert!((mapped.x - 0.75).abs() < 0.01, "{:?}", mapped);
+    // EXR keeps the light linear, however it's tonemapped.
+    assert_eq!(
+        Image::read(&exr).unwrap().get(1, 0),
+        Color::new(3.0, 3.0, 3.0)
+    );
+}
+
+#[test]
+fn scene_files_can_hold_settings() {
+    let value = r#"{"settings": {"width": 64, "tonemap": "reinhard"}}"#;
+    let settings = scene_file::settings(&basic_raytracer::json::parse(value).unwrap()).unwrap();
+    assert_eq!(settings.width, Some(64));
+    assert_eq!(settings.tonemap, Some(Tonemap::Reinhard));
+
+    for (text, message) in [
+        (
+            r#"{"settings": {"sp": 4}}"#,
+            "settings: unknown member \"sp\"",
+        ),
+        (
+            r#"{"settings": {"spp": "many"}}"#,
+            "settings.spp: expected a non-negative integer, found a string",
+        ),
+    ]
+    .iter()
+    {
+        let error = scene_file::parse(text, Path::new("")).err().unwrap();
+        assert_eq!(error, *message);
+    }
+}
+
+/// The value of `key` in a config file, as `--print-settings` writes it.
+fn setting<'a>(toml: &'a str, key: &str) -> &'a str {
+    let prefix = format!("{} = ", key);
+    toml.lines()
+        .find_map(|line| line.strip_prefix(&prefix))
+        .unwrap()
+}
+
+#[test]
+fn flags_go_over_the_scene_file_which_goes_over_the_config_file() {
+    let dir = scratch("layers");
+    fs::write(
+        dir.join("raytracer.toml"),
+        "width = 40\nheight = 20\nspp = 4\nmax_depth = 3\ntonemap = \"reinhard\"\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("scene.json"),
+        r#"{"settings": {"height": 30, "spp": 8, "max_depth": 5}}"#,
+    )
+    .unwrap();
+
+    let (code, toml, stderr) = run(&dir, &["--print-settings"]);
+    assert_eq!(code, Some(0), "{}", stderr);
+    assert_eq!(
+        toml,
+        "width = 40\nheight = 20\nspp = 4\nmax_depth = 3\n\
+         tonemap = \"reinhard\"\nthreads = 0\noutput = \"output.png\"\n"
+    );
+
+    let (_, toml, _) = run(&dir, &["render", "scene.json", "--print-settings"]);
+    assert_eq!(setting(&toml, "width"), "40");
+    assert_eq!(setting(&toml, "height"), "30");
+    assert_eq!(setting(&toml, "spp"), "8");
+    assert_eq!(setting(&toml, "max_depth"), "5");
+
+    let flags = ["--spp", "2", "--max-depth", "5", "--tonemap", "aces"];
+    let (_, toml, _) = run(
+        &dir,
+        &[&["render", "scene.json", "--print-settings"], &flags[..]].concat(),
+    );
+    assert_eq!(setting(&toml, "width"), "40");
+    assert_eq!(setting(&toml, "height"), "30");
+    assert_eq!(setting(&toml, "spp"), "2");
+    assert_eq!(setting(&toml, "tonemap"), "\"aces\"");
+    // Without a value for it anywhere, a setting is the built-in default.
+    assert_eq!(setting(&toml, "threads"), "0");
+
+    // And the settings are the ones the render uses.
+    let (code, _, stderr) = run(&dir, &["render", "scene.json", "--output", "out.png"]);
+    assert_eq!(code, Some(0), "{}", stderr);
+    let image = Image::read_png(&dir.join("out.png")).unwrap();
+    assert_eq!((image.width, image.height), (40, 30));
+}
+
+#[test]
+fn printed_settings_can_be_saved_as_a_config_file() {
+    let dir = scratch("print");
+    let args = [
+        "--print-settings",
+        "--width",
+        "64",
+        "--output",
+        "renders/frame-###.png",
+        "--threads",
+        "2",
+    ];
+    let (code, toml, _) = run(&dir, &args);
+    assert_eq!(code, Some(0));
+    fs::write(dir.join("saved.toml"), &toml).unwrap();
+    let (code, again, stderr) = run(&dir, &["--config", "saved.toml", "--print-settings"]);
+    assert_eq!(code, Some(0), "{}", stderr);
+    assert_eq!(again, toml);
+    assert_eq!(setting(&again, "output"), "\"renders/frame-###.png\"");
+
+    // Settings from files are checked like flags are.
+    fs::write(dir.join("zero.toml"), "spp = 0\n").unwrap();
+    let (code, _, stderr) = run(&dir, &["--config", "zero.toml", "--print-settings"]);
+    assert_eq!(
+        (code, stderr.trim()),
+        (
+            Some(2),
+            "error: invalid render settings: spp must be at least 1"
+        )
+    );
+    fs::write(dir.join("typo.toml"), "# spp = 4\nsamples = 4\n").unwrap();
+    let (code, _, stderr) = run(&dir, &["--config", "typo.toml"]);
+    assert_eq!(code, Some(3));
+    assert_eq!(
+        stderr.trim(),
+        "error: typo.toml: line 2: unknown key \"samples\""
+    );
+    let (code, _, stderr) = run(&dir, &["--config", "missing.toml"]);
+    assert_eq!(code, Some(5));
+    assert!(
+        stderr.starts_with("error: can't read missing.toml"),
+        "{}",
+        stderr
+    );
+}
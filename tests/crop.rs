@@ -0,0 +1,137 @@
+use std::fs;
+use std::process::Command;
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::image::Image;
+use basic_raytracer::light::PointLight;
+use basic_raytracer::material::{Dielectric, Lambertian};
+use basic_raytracer::render::{render, render_progressive, Crop, IntegratorKind, RenderSettings};
+use basic_raytracer::scene::Scene;
+use basic_raytracer::shapes::{Plane, Sphere};
+use basic_raytracer::vector::{Color, Vector};
+
+fn scene() -> Scene {
+    let mut scene = Scene::new(Camera::new(
+        Vector::new(0.0, 1.0, 4.0),
+        Vector::zero(),
+        45.0,
+    ));
+    scene.add(
+        Plane::new(Vector::new(0.0, -1.0, 0.0), Vector::new(0.0, 1.0, 0.0)),
+        Arc::new(Lambertian::new(Color::new(0.6, 0.6, 0.6))),
+    );
+    scene.add(
+        Sphere::new(Vector::zero(), 0.8),
+        Arc::new(Dielectric::new(1.5)),
+    );
+    scene.add_light(PointLight::new(
+        Vector::new(2.0, 4.0, 3.0),
+        Color::new(1.0, 1.0, 1.0),
+        40.0,
+    ));
+    scene
+}
+
+const CROP: Crop = Crop {
+    x: 13,
+    y: 7,
+    width: 20,
+    height: 11,
+};
+
+fn settings(crop: Option<Crop>) -> RenderSettings {
+    RenderSettings {
+        width: 48,
+        height: 32,
+        spp: 4,
+        integrator: IntegratorKind::Path,
+        crop,
+        ..RenderSettings::default()
+    }
+}
+
+#[test]
+fn a_crop_is_exactly_that_part_of_the_whole_image() {
+    let (whole, _) = render(&scene(), &settings(None)).unwrap();
+    let expected = whole.crop(CROP.x, CROP.y, CROP.width, CROP.height);
+    let (crop, stats) = render(&scene(), &settings(Some(CROP))).unwrap();
+    assert_eq!((crop.width, crop.height), (20, 11));
+    assert!(crop.pixels == expected.pixels);
+    assert_eq!(stats.camera_rays, 4 * 20 * 11);
+
+    let (passes, _) = render_progressive(
+        &scene(),
+        &RenderSettings {
+            spp: 2,
+            ..settings(Some(CROP))
+        },
+        2,
+        |_, _| Ok(()),
+        |_| true,
+    )
+    .unwrap();
+    assert!(passes.pixels == expected.pixels);
+}
+
+#[test]
+fn a_crop_has_to_fit_in_the_image() {
+    let crop = |x, y, width, height| {
+        let settings = settings(Some(Crop {
+            x,
+            y,
+            width,
+            height,
+        }));
+        settings.validate().err().map(|error| error.to_string())
+    };
+    assert_eq!(crop(0, 0, 48, 32), None);
+    assert_eq!(
+        crop(40, 0, 9, 32).unwrap(),
+        "invalid render settings: the 9x32 crop at 40,0 doesn't fit in the 48x32 image"
+    );
+    assert!(crop(0, 32, 1, 1).is_some());
+    assert!(crop(0, 0, 0, 10).is_some());
+    assert!(crop(u32::MAX, 0, 2, 2).is_some());
+}
+
+#[test]
+fn the_binary_writes_the_crop_alone_or_in_place() {
+    let dir = std::env::temp_dir().join(format!("crop-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_basic-raytracer"))
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .unwrap()
+    };
+    let crop = ["--crop", "280,200,80,60"];
+    assert!(run(&[&crop[..], &["--output", "alone.png"]].concat())
+        .status
+        .success());
+    assert!(
+        run(&[&crop[..], &["--crop-in-place", "--output", "placed.png"]].concat())
+            .status
+            .success()
+    );
+    let alone = Image::read_png(&dir.join("alone.png")).unwrap();
+    let placed = Image::read_png(&dir.join("placed.png")).unwrap();
+    assert_eq!((alone.width, alone.height), (80, 60));
+    assert_eq!((placed.width, placed.height), (640, 480));
+    assert!(placed.crop(280, 200, 80, 60).pixels == alone.pixels);
+    assert!(alone.pixels.iter().any(|&color| color != Color::zero()));
+    let mut outside = placed;
+    outside.paste(&Image::new(80, 60), 280, 200);
+    assert!(outside.pixels.iter().all(|&color| color == Color::zero()));
+
+    let output = run(&["--crop", "600,0,50,10"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("doesn't fit in the 640x480 image"));
+    assert_eq!(run(&["--crop-in-place"]).status.code(), Some(2));
+    assert_eq!(run(&["--crop", "1,2,3"]).status.code(), Some(2));
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,209 @@
+use std::path::Path;
+
+use basic_raytracer::matrix::Matrix4;
+use basic_raytracer::ray::Ray;
+use basic_raytracer::scalar::{self, Scalar};
+use basic_raytracer::scene_file;
+use basic_raytracer::shapes::{Csg, Cuboid, Cylinder, HitRecord, Shape, Sphere, Transformed};
+use basic_raytracer::vector::Vector;
+
+/// How far a hit may be from where it's worked out to be by hand.
+#[cfg(not(feature = "f32"))]
+const TOLERANCE: Scalar = 1e-9;
+#[cfg(feature = "f32")]
+const TOLERANCE: Scalar = 1e-5;
+
+fn assert_close(a: Vector, b: Vector) {
+    assert!((a - b).len() < TOLERANCE, "{:?} != {:?}", a, b);
+}
+
+fn hit(shape: &dyn Shape, origin: Vector, direction: Vector) -> Option<HitRecord> {
+    shape.intersect(&Ray::new(origin, direction), 1e-4, Scalar::INFINITY)
+}
+
+/// A unit sphere with a box cut out of its front.
+fn bitten_sphere() -> Csg {
+    Csg::difference(
+        Sphere::new(Vector::zero(), 1.0),
+        Cuboid::new(Vector::new(-0.5, -0.5, 0.2), Vector::new(0.5, 0.5, 2.0)),
+    )
+}
+
+#[test]
+fn differences_show_the_cut_from_inside() {
+    let shape = bitten_sphere();
+    let back = Vector::new(0.0, 0.0, -1.0);
+    // Into the bite, the first surface is the floor of the cut, facing out.
+    let floor = hit(&shape, Vector::new(0.0, 0.0, 5.0), back).unwrap();
+    assert!((floor.t - 4.8).abs() < TOLERANCE);
+    assert!(floor.front_face);
+    assert_close(floor.normal, Vector::new(0.0, 0.0, 1.0));
+    // Beside it, the sphere itself.
+    let side = hit(&shape, Vector::new(0.0, 0.7, 5.0), back).unwrap();
+    assert_close(side.point, Vector::new(0.0, 0.7, Scalar::sqrt(0.51)));
+    assert_close(side.normal, side.point);
+    // A wall of the cut seen from across it faces into the cut.
+    let wall = hit(
+        &shape,
+        Vector::new(-0.2, 0.0, 0.5),
+        Vector::new(1.0, 0.0, 0.0),
+    )
+    .unwrap();
+    assert_close(wall.point, Vector::new(0.5, 0.0, 0.5));
+    assert_close(wall.normal, Vector::new(-1.0, 0.0, 0.0));
+    assert!(wall.front_face);
+    // Nothing is left where only the cut was.
+    assert!(hit(
+        &shape,
+        Vector::new(0.0, 0.0, 0.5),
+        Vector::new(0.0, 0.0, 1.0)
+    )
+    .is_none());
+}
+
+#[test]
+fn intersections_keep_only_the_overlap() {
+    let lens = Csg::intersection(
+        Sphere::new(Vector::new(-0.5, 0.0, 0.0), 1.0),
+        Sphere::new(Vector::new(0.5, 0.0, 0.0), 1.0),
+    );
+    let right = Vector::new(1.0, 0.0, 0.0);
+    let hits = lens.intersect_all(&Ray::new(Vector::new(-5.0, 0.0, 0.0), right));
+    let ts: Vec<Scalar> = hits.iter().map(|hit| hit.t).collect();
+    assert_eq!(hits.len(), 2, "{:?}", ts);
+    assert!((ts[0] - 4.5).abs() < TOLERANCE && (ts[1] - 5.5).abs() < TOLERANCE);
+    assert!(hits[0].front_face && !hits[1].front_face);
+    let bounds = lens.bounds().unwrap();
+    assert_close(bounds.min, Vector::new(-0.5, -1.0, -1.0));
+    assert_close(bounds.max, Vector::new(0.5, 1.0, 1.0));
+    // Well outside the overlap the ray misses, though it hits each sphere.
+    assert!(hit(&lens, Vector::new(-5.0, 0.9, 0.0), right).is_none());
+}
+
+#[test]
+fn shared_faces_do_not_show() {
+    let left = Cuboid::new(Vector::new(-1.0, 0.0, 0.0), Vector::new(0.0, 1.0, 1.0));
+    let right = Cuboid::new(Vector::new(0.0, 0.0, 0.0), Vector::new(1.0, 1.0, 1.0));
+    let union = Csg::union(left, right);
+    let across = Ray::new(Vector::new(-5.0, 0.5, 0.5), Vector::new(1.0, 0.0, 0.0));
+    let ts: Vec<Scalar> = union
+        .intersect_all(&across)
+        .iter()
+        .map(|hit| hit.t)
+        .collect();
+    assert_eq!(ts, vec![4.0, 6.0]);
+
+    // Cutting a box with one that shares its outer face leaves only the
+    // cut's inner face, facing out.
+    let block = Cuboid::new(Vector::new(0.0, 0.0, 0.0), Vector::new(1.0, 1.0, 1.0));
+    let slab = Cuboid::new(Vector::new(0.5, 0.0, 0.0), Vector::new(1.0, 1.0, 1.0));
+    let cut = Csg::difference(block, slab);
+    let leftward = Vector::new(-1.0, 0.0, 0.0);
+    let face = hit(&cut, Vector::new(5.0, 0.5, 0.5), leftward).unwrap();
+    assert!((face.t - 4.5).abs() < TOLERANCE);
+    assert_close(face.normal, Vector::new(1.0, 0.0, 0.0));
+    assert!(face.front_face);
+}
+
+#[test]
+fn grazing_rays_find_no_phantom_surfaces() {
+    // A ray just touching the sphere at the top of the bite never enters it.
+    let shape = bitten_sphere();
+    let tangent = Ray::new(Vector::new(-3.0, 1.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+    assert!(shape.intersect_all(&tangent).is_empty());
+    // Nor does one skimming just under the floor of the bite hit anything but
+    // the sphere beyond it.
+    let skim = Ray::new(
+        Vector::new(0.0, 0.0, 0.2 - 1e-7),
+        Vector::new(0.0, 1.0, 0.0),
+    );
+    let beyond = hit(&shape, skim.origin, skim.direction).unwrap();
+    assert!(((beyond.point - skim.origin).len() - Scalar::sqrt(0.96)).abs() < 1e-6);
+    assert!(!beyond.front_face);
+}
+
+#[test]
+fn csg_nests_and_transforms() {
+    // A tube: a cylinder with a thinner one taken out, turned onto its side.
+    let tube = Transformed::new(
+        Csg::difference(
+            Cylinder::new(Vector::new(0.0, -1.0, 0.0), 1.0, 2.0),
+            Cylinder::new(Vector::new(0.0, -2.0, 0.0), 0.5, 4.0),
+        ),
+        Matrix4::rotate_z(scalar::consts::FRAC_PI_2),
+    );
+    let down = Vector::new(0.0, -1.0, 0.0);
+    let ts: Vec<Scalar> = tube
+        .intersect_all(&Ray::new(Vector::new(0.0, 5.0, 0.0), down))
+        .iter()
+        .map(|hit| hit.t)
+        .collect();
+    assert_eq!(ts.len(), 4, "{:?}", ts);
+    for (t, expected) in ts.iter().zip([4.0, 4.5, 5.5, 6.0].iter()) {
+        assert!((t - expected).abs() < TOLERANCE, "{:?}", ts);
+    }
+    // Down the bore nothing is in the way.
+    assert!(hit(
+        &tube,
+        Vector::new(-5.0, 0.0, 0.0),
+        Vector::new(1.0, 0.0, 0.0)
+    )
+    .is_none());
+
+    let cored = Csg::intersection(tube, Sphere::new(Vector::zero(), 0.8));
+    let inner = hit(&cored, Vector::new(0.0, 5.0, 0.0), down).unwrap();
+    assert!((inner.t - 4.2).abs() < TOLERANCE);
+    assert_close(inner.normal, Vector::new(0.0, 1.0, 0.0));
+}
+
+#[test]
+fn scene_files_combine_solids() {
+    let scene = scene_file::parse(
+        r#"{"csg": [{"operation": "difference",
+            "a": {"type": "sphere", "center": [0, 0, 0], "radius": 1},
+            "b": {"type": "csg", "operation": "union",
+                  "a": {"type": "box", "min": [-0.5, -0.5, 0.2], "max": [0.5, 0.5, 2]},
+                  "b": {"type": "cylinder", "base": [0, -2, 0], "radius": 0.1, "height": 4,
+                        "transform": {"translate": [0.7, 0, 0]}}},
+            "material": {"type": "lambertian", "albedo": [1, 1, 1]}}]}"#,
+        Path::new(""),
+    )
+    .unwrap();
+    let shape = scene.objects[0].shape.as_ref();
+    let floor = hit(
+        shape,
+        Vector::new(0.0, 0.0, 5.0),
+        Vector::new(0.0, 0.0, -1.0),
+    )
+    .unwrap();
+    assert!((floor.t - 4.8).abs() < TOLERANCE);
+    // The drilled hole goes right through.
+    assert!(hit(
+        shape,
+        Vector::new(0.7, 5.0, 0.0),
+        Vector::new(0.0, -1.0, 0.0)
+    )
+    .is_none());
+
+    let error = |a: &str| {
+        scene_file::parse(
+            &format!(
+                r#"{{"csg": [{{"operation": "union", "a": {},
+                    "b": {{"type": "sphere", "center": [0, 0, 0], "radius": 1}},
+                    "material": {{"type": "dielectric", "ior": 1.5}}}}]}}"#,
+                a
+            ),
+            Path::new(""),
+        )
+        .err()
+        .unwrap()
+    };
+    assert_eq!(
+        error(r#"{"type": "plane", "point": [0, 0, 0]}"#),
+        "csg[0].a: unknown solid type \"plane\""
+    );
+    assert_eq!(
+        error(r#"{"type": "sphere", "center": [0, 0, 0], "radius": 1, "material": {}}"#),
+        "csg[0].a: unknown member \"material\""
+    );
+}
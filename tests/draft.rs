@@ -0,0 +1,65 @@
+use std::fs;
+use std::process::Command;
+
+use basic_raytracer::image::Image;
+
+#[test]
+fn the_draft_is_written_first_and_then_replaced() {
+    let dir = std::env::temp_dir().join(format!("draft-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_basic-raytracer"))
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .unwrap()
+    };
+    let output = run(&["--draft", "8", "--draft-only", "--output", "draft.png"]);
+    assert!(output.status.success());
+    let draft = Image::read_png(&dir.join("draft.png")).unwrap();
+    assert_eq!((draft.width, draft.height), (80, 60));
+    assert!(!String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("successfully"));
+
+    // A size that doesn't divide evenly rounds up rather than losing a pixel.
+    assert!(
+        run(&["--draft", "7", "--draft-only", "--output", "odd.png"])
+            .status
+            .success()
+    );
+    let odd = Image::read_png(&dir.join("odd.png")).unwrap();
+    assert_eq!((odd.width, odd.height), (92, 69));
+
+    let output = run(&["--draft", "8", "--output", "full.png"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let drafted = stdout.find("Drafted full.png at 80x60").unwrap();
+    let finished = stdout.find("Raytraced full.png successfully!").unwrap();
+    assert!(drafted < finished);
+    let full = Image::read_png(&dir.join("full.png")).unwrap();
+    assert_eq!((full.width, full.height), (640, 480));
+
+    // At full size it's the render with one sample a pixel, through the
+    // same camera.
+    assert!(
+        run(&["--draft", "1", "--draft-only", "--output", "same.png"])
+            .status
+            .success()
+    );
+    assert!(run(&["--spp", "1", "--output", "one.png"]).status.success());
+    assert!(
+        Image::read_png(&dir.join("same.png")).unwrap().pixels
+            == Image::read_png(&dir.join("one.png")).unwrap().pixels
+    );
+
+    for args in [
+        &["--draft-only"][..],
+        &["--draft", "0"],
+        &["--draft", "2", "--output", "a.gif"],
+    ] {
+        assert_eq!(run(args).status.code(), Some(2));
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,144 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::error::Error;
+use basic_raytracer::image::Image;
+use basic_raytracer::render::{render, RenderSettings};
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene::Scene;
+use basic_raytracer::scene_file;
+use basic_raytracer::vector::Vector;
+
+/// A fresh directory for one test to write into.
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("errors-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn unwritable_output_is_an_io_error() {
+    let dir = scratch("output");
+    // Even with every permission, nothing can be created inside a file.
+    let file = dir.join("file");
+    fs::write(&file, "").unwrap();
+    let path = file.join("image.png");
+    let error = Image::new(2, 2).write_png(&path).unwrap_err();
+    match &error {
+        Error::Io {
+            path: failed,
+            operation,
+            ..
+        } => assert_eq!((failed, *operation), (&path, "create")),
+        other => panic!("expected an I/O error, found {:?}", other),
+    }
+    assert!(error
+        .to_string()
+        .starts_with(&format!("can't create {}: ", path.display())));
+    assert!(std::error::Error::source(&error).is_some());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn broken_scene_files_say_where() {
+    let dir = scratch("scene");
+    let path = dir.join("broken.json");
+    fs::write(
+        &path,
+        r#"{"spheres": [{"center": [0, 0, 0], "radius": "big"}]}"#,
+    )
+    .unwrap();
+    let error = scene_file::load(&path).err().unwrap();
+    assert!(matches!(error, Error::SceneParse { .. }));
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "{}: spheres[0].radius: expected a number, found a string",
+            path.display()
+        )
+    );
+
+    let missing = dir.join("missing.json");
+    match scene_file::load(&missing).err().unwrap() {
+        Error::Io { operation, .. } => assert_eq!(operation, "read"),
+        other => panic!("expected an I/O error, found {:?}", other),
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn renders_refuse_impossible_settings() {
+    let scene = Scene::new(Camera::new(
+        Vector::zero(),
+        Vector::new(0.0, 0.0, -1.0),
+        45.0,
+    ));
+    let settings = RenderSettings {
+        width: 4,
+        height: 4,
+        ..RenderSettings::default()
+    };
+    assert!(render(&scene, &settings).is_ok());
+    for broken in [
+        RenderSettings {
+            spp: 0,
+            ..settings.clone()
+        },
+        RenderSettings {
+            height: 0,
+            ..settings.clone()
+        },
+        RenderSettings {
+            clamp_indirect: Scalar::NAN,
+            ..settings.clone()
+        },
+    ] {
+        match render(&scene, &broken).err().unwrap() {
+            Error::InvalidSettings(_) => {}
+            other => panic!("expected invalid settings, found {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn the_binary_reports_errors_without_panicking() {
+    let dir = scratch("binary");
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_basic-raytracer"))
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        (
+            output.status.code(),
+            String::from_utf8(output.stderr).unwrap(),
+        )
+    };
+
+    fs::write(dir.join("broken.json"), "{\"spheres\": [").unwrap();
+    let (code, stderr) = run(&["--scene", "broken.json"]);
+    assert_eq!(code, Some(3));
+    assert!(stderr.starts_with("error: broken.json: "), "{}", stderr);
+
+    // The image goes to output.png, which can't be written over a directory.
+    fs::create_dir(dir.join("output.png")).unwrap();
+    let (code, stderr) = run(&[]);
+    assert_eq!(code, Some(5));
+    assert!(
+        stderr.starts_with("error: can't create output.png: "),
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("panicked"));
+
+    let (code, stderr) = run(&["--spp", "0"]);
+    assert_eq!(code, Some(2));
+    assert_eq!(
+        stderr,
+        "error: invalid render settings: spp must be at least 1\n"
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,133 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use basic_raytracer::exr;
+use basic_raytracer::image::Image;
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::vector::Color;
+
+/// A gradient with values past white and below black, which an 8-bit image
+/// would clamp.
+fn gradient(width: u32, height: u32) -> Image {
+    let mut image = Image::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let (u, v) = (x as f32 / width as f32, y as f32 / height as f32);
+            image.set(
+                x,
+                y,
+                Color::new((4.0 * u) as Scalar, (v - 0.5) as Scalar, (u * v) as Scalar),
+            );
+        }
+    }
+    image
+}
+
+#[test]
+fn images_round_trip_through_exr_exactly() {
+    // More than one 16-line block, with a short one at the end.
+    let image = gradient(37, 41);
+    let decoded = exr::decode(&exr::encode(&image)).unwrap();
+    assert_eq!((decoded.width, decoded.height), (37, 41));
+    assert!(decoded.pixels == image.pixels);
+
+    // Noise doesn't compress, so it's stored raw.
+    let mut noise = Image::new(8, 3);
+    let mut state = 12345u32;
+    for pixel in noise.pixels.iter_mut() {
+        let mut next = || {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            f32::from_bits(0x3f80_0000 | (state >> 9)) as Scalar
+        };
+        *pixel = Color::new(next(), next(), next());
+    }
+    assert!(exr::decode(&exr::encode(&noise)).unwrap().pixels == noise.pixels);
+}
+
+fn attribute(data: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    for text in [name, kind].iter() {
+        data.extend_from_slice(text.as_bytes());
+        data.push(0);
+    }
+    data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    data.extend_from_slice(value);
+}
+
+/// An uncompressed 2x1 image with a single half-float `Y` channel.
+fn luminance(version: u32) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&20_000_630u32.to_le_bytes());
+    data.extend_from_slice(&version.to_le_bytes());
+    let mut channels = b"Y\0".to_vec();
+    for field in [1i32, 0, 1, 1].iter() {
+        channels.extend_from_slice(&field.to_le_bytes());
+    }
+    channels.push(0);
+    let mut window = Vec::new();
+    for corner in [0i32, 0, 1, 0].iter() {
+        window.extend_from_slice(&corner.to_le_bytes());
+    }
+    attribute(&mut data, "channels", "chlist", &channels);
+    attribute(&mut data, "compression", "compression", &[0]);
+    attribute(&mut data, "dataWindow", "box2i", &window);
+    data.push(0);
+    let offset = data.len() as u64 + 8;
+    data.extend_from_slice(&offset.to_le_bytes());
+    data.extend_from_slice(&0i32.to_le_bytes());
+    data.extend_from_slice(&4u32.to_le_bytes());
+    // 0.5 and 2 as halves.
+    data.extend_from_slice(&[0x00, 0x38, 0x00, 0x40]);
+    data
+}
+
+#[test]
+fn half_float_luminance_is_read_as_gray() {
+    let image = exr::decode(&luminance(2)).unwrap();
+    assert_eq!((image.width, image.height), (2, 1));
+    assert_eq!(image.get(0, 0), Color::new(0.5, 0.5, 0.5));
+    assert_eq!(image.get(1, 0), Color::new(2.0, 2.0, 2.0));
+}
+
+#[test]
+fn what_cant_be_read_is_refused() {
+    assert_eq!(exr::decode(b"\x89PNG").err().unwrap(), "not an EXR file");
+    assert_eq!(
+        exr::decode(&luminance(2 | 0x200)).err().unwrap(),
+        "only single-part scanline EXR images can be read"
+    );
+    let whole = luminance(2);
+    assert_eq!(
+        exr::decode(&whole[..whole.len() - 1]).err().unwrap(),
+        "the file is cut short"
+    );
+    let error = exr::read(Path::new("missing.exr")).err().unwrap();
+    assert!(error.starts_with("can't read missing.exr: "), "{}", error);
+}
+
+#[test]
+fn renders_can_be_written_as_exr() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("exr");
+    fs::create_dir_all(&dir).unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_basic-raytracer"))
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .unwrap()
+    };
+    let args = ["--draft", "8", "--draft-only", "--output"];
+    assert!(run(&[&args[..], &["draft.exr"]].concat()).status.success());
+    assert!(run(&[&args[..], &["draft.png"]].concat()).status.success());
+    let exr = Image::read(&dir.join("draft.exr")).unwrap();
+    let png = Image::read(&dir.join("draft.png")).unwrap();
+    assert_eq!((exr.width, exr.height), (80, 60));
+    // The PNG is the EXR rounded to 8 bits of sRGB.
+    for (a, b) in exr.pixels.iter().zip(&png.pixels) {
+        let a = Color::new(a.x.min(1.0), a.y.min(1.0), a.z.min(1.0));
+        assert!((a - *b).len() < 0.01, "{:?} and {:?}", a, b);
+    }
+
+    let output = run(&["--low-memory", "--output", "streamed.exr"]);
+    assert_eq!(output.status.code(), Some(2));
+}
@@ -0,0 +1,262 @@
+//! Compiles `tests/ffi/render_sphere.c` against the library's C API and
+//! checks the pixel it prints against a render of the same scene in Rust.
+
+use std::ffi::c_void;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::ffi::{
+    rt_render_with_progress, rt_scene_add_material, rt_scene_add_point_light, rt_scene_add_sphere,
+    rt_scene_free, rt_scene_new, rt_settings_default, RtMaterial, RtPointLight, RtSettings,
+    RtSphere, RtVector, RT_CANCELLED, RT_LAMBERTIAN, RT_OK,
+};
+use basic_raytracer::light::PointLight;
+use basic_raytracer::material::Lambertian;
+use basic_raytracer::render::{render_to_buffer, RenderSettings};
+use basic_raytracer::scalar::{to_f64, Scalar};
+use basic_raytracer::scene::Scene;
+use basic_raytracer::shapes::Sphere;
+use basic_raytracer::vector::{Color, Vector};
+
+/// The triple rustc builds for by default, which the tests were built for.
+fn host() -> String {
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let output = Command::new(rustc).arg("-vV").output().unwrap();
+    let version = String::from_utf8(output.stdout).unwrap();
+    let host = version.lines().find_map(|line| line.strip_prefix("host: "));
+    host.unwrap().to_string()
+}
+
+/// Builds the cdylib, returning the directory it's in. The one beside the
+/// tests is shared by every build, whatever its features, so this builds one
+/// that surely has the API.
+fn library() -> PathBuf {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let build = Path::new(env!("CARGO_TARGET_TMPDIR")).join("ffi");
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let status = Command::new(cargo)
+        .args([
+            "build",
+            "--quiet",
+            "--lib",
+            "--features",
+            "ffi",
+            "--target-dir",
+        ])
+        .arg(&build)
+        .current_dir(root)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    build.join("debug")
+}
+
+fn sphere_scene() -> Scene {
+    let mut scene = Scene::new(Camera::new(
+        Vector::zero(),
+        Vector::new(0.0, 0.0, -1.0),
+        45.0,
+    ));
+    scene.add(
+        Sphere::new(Vector::new(0.0, 0.0, -5.0), 1.0),
+        Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.8))),
+    );
+    scene.add_light(PointLight::new(
+        Vector::new(0.0, 2.0, 0.0),
+        Color::new(1.0, 1.0, 1.0),
+        20.0,
+    ));
+    scene
+}
+
+#[test]
+#[cfg_attr(not(unix), ignore = "links with -l and -rpath")]
+fn a_c_program_renders_a_sphere() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let libraries = library();
+    let dir = std::env::temp_dir().join(format!("ffi-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let program = dir.join("render_sphere");
+
+    let host = host();
+    let compiler = cc::Build::new()
+        .target(&host)
+        .host(&host)
+        .opt_level(0)
+        .cargo_metadata(false)
+        .get_compiler();
+    let status = compiler
+        .to_command()
+        .arg("-I")
+        .arg(root.join("include"))
+        .arg(root.join("tests/ffi/render_sphere.c"))
+        .arg("-o")
+        .arg(&program)
+        .arg("-L")
+        .arg(&libraries)
+        .arg("-lbasic_raytracer")
+        .arg(format!("-Wl,-rpath,{}", libraries.display()))
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    // Cargo points the loader at the shared cdylib first.
+    let output = Command::new(&program)
+        .env("LD_LIBRARY_PATH", &libraries)
+        .env("DYLD_LIBRARY_PATH", &libraries)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let printed: Vec<u8> = String::from_utf8(output.stdout)
+        .unwrap()
+        .split_whitespace()
+        .map(|byte| byte.parse().unwrap())
+        .collect();
+
+    let settings = RenderSettings {
+        width: 64,
+        height: 48,
+        ..RenderSettings::default()
+    };
+    let pixels = render_to_buffer(&sphere_scene(), &settings).unwrap();
+    let center = 4 * (24 * 64 + 32);
+    assert_eq!(printed, pixels[center..center + 4]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// The header is written by hand, so check it declares every function and
+/// defines every constant `src/ffi.rs` does, with the same values.
+#[test]
+fn vectors_cross_the_api_unchanged() {
+    for v in [
+        Vector::zero(),
+        Vector::new(1.0, -2.5, 1e-30),
+        Vector::new(Scalar::MAX, Scalar::MIN_POSITIVE, -0.0),
+        Vector::new(0.1, 1.0 / 3.0, Scalar::INFINITY),
+    ] {
+        let across = RtVector::from(v);
+        assert_eq!(
+            (across.x, across.y, across.z),
+            (to_f64(v.x), to_f64(v.y), to_f64(v.z))
+        );
+        let back = Vector::from(across);
+        let bits = |v: Vector| [v.x.to_bits(), v.y.to_bits(), v.z.to_bits()];
+        assert_eq!(bits(back), bits(v));
+    }
+}
+
+unsafe extern "C" fn count_rows(done: u32, rows: u32, user_data: *mut c_void) -> i32 {
+    let seen = &mut *(user_data as *mut Vec<(u32, u32)>);
+    seen.push((done, rows));
+    // Stops the render after row 5, if asked to by passing a first entry.
+    (seen[0] == (0, 5) && done == 5) as i32
+}
+
+#[test]
+fn progress_comes_a_row_at_a_time_and_can_cancel() {
+    let rgb = |x, y, z| RtVector { x, y, z };
+    let mut settings = RtSettings {
+        width: 0,
+        height: 0,
+        spp: 0,
+        max_depth: 0,
+        integrator: 0,
+        seed: 0,
+    };
+    let (width, height) = (40, 30);
+    let mut pixels = vec![0u8; 4 * width * height];
+    let mut seen: Vec<(u32, u32)> = Vec::new();
+    unsafe {
+        let scene = rt_scene_new();
+        let material = RtMaterial {
+            kind: RT_LAMBERTIAN,
+            color: rgb(0.8, 0.8, 0.8),
+            exponent: 0.0,
+            ior: 0.0,
+        };
+        let mut index = 0;
+        assert_eq!(rt_scene_add_material(scene, &material, &mut index), RT_OK);
+        let sphere = RtSphere {
+            center: rgb(0.0, 0.0, -5.0),
+            radius: 1.0,
+            material: index,
+        };
+        assert_eq!(rt_scene_add_sphere(scene, &sphere), RT_OK);
+        let light = RtPointLight {
+            position: rgb(0.0, 2.0, 0.0),
+            color: rgb(1.0, 1.0, 1.0),
+            intensity: 20.0,
+        };
+        assert_eq!(rt_scene_add_point_light(scene, &light), RT_OK);
+        assert_eq!(rt_settings_default(&mut settings), RT_OK);
+        settings.width = width as u32;
+        settings.height = height as u32;
+        let render = |pixels: &mut [u8], seen: &mut Vec<(u32, u32)>| {
+            let user_data = seen as *mut Vec<(u32, u32)> as *mut c_void;
+            let (buffer, length) = (pixels.as_mut_ptr(), pixels.len());
+            rt_render_with_progress(
+                scene,
+                &settings,
+                buffer,
+                length,
+                Some(count_rows),
+                user_data,
+            )
+        };
+        assert_eq!(render(&mut pixels, &mut seen), RT_OK);
+        let expected: Vec<_> = (1..=height as u32)
+            .map(|done| (done, height as u32))
+            .collect();
+        assert_eq!(seen, expected);
+
+        seen = vec![(0, 5)];
+        let mut cancelled = vec![0u8; pixels.len()];
+        assert_eq!(render(&mut cancelled, &mut seen), RT_CANCELLED);
+        assert_eq!(seen.len(), 6);
+        // The rows done before it was cancelled are there.
+        assert!(cancelled[..4 * width * 5] == pixels[..4 * width * 5]);
+        rt_scene_free(scene);
+    }
+    let settings = RenderSettings {
+        width: width as u32,
+        height: height as u32,
+        ..RenderSettings::default()
+    };
+    assert!(pixels == render_to_buffer(&sphere_scene(), &settings).unwrap());
+}
+
+/// Checks the header against the one cbindgen generates from `src/ffi.rs`,
+/// or writes it there when updating.
+#[test]
+fn the_header_matches_the_api() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let config = cbindgen::Config::from_file(root.join("cbindgen.toml")).unwrap();
+    let bindings = cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(root.join("src/ffi.rs"))
+        .generate()
+        .unwrap();
+    let header = root.join("include/basic_raytracer.h");
+    if std::env::var_os("UPDATE_HEADER").is_some() {
+        bindings.write_to_file(&header);
+        return;
+    }
+    let mut generated = Vec::new();
+    bindings.write(&mut generated);
+    let generated = String::from_utf8(generated).unwrap();
+    assert!(
+        std::fs::read_to_string(&header).unwrap() == generated,
+        "include/basic_raytracer.h is out of date with src/ffi.rs; \
+         regenerate it with UPDATE_HEADER=1 cargo test --features ffi --test ffi"
+    );
+    for name in ["rt_scene_new", "rt_render_with_progress", "rt_last_error"].iter() {
+        assert!(generated.contains(&format!("{}(", name)), "{}", name);
+    }
+}
@@ -0,0 +1,72 @@
+/* Renders a lit sphere through the C API, checks what it can on its own,
+ * and prints the pixel at the center for the test that runs it to compare
+ * with a render of the same scene in Rust. */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "basic_raytracer.h"
+
+#define WIDTH 64
+#define HEIGHT 48
+
+static int check(int32_t status, int32_t expected, const char *what) {
+    if (status != expected) {
+        fprintf(stderr, "%s: got status %d, not %d (%s)\n", what, status, expected, rt_last_error());
+        exit(1);
+    }
+    return status;
+}
+
+int main(void) {
+    RtScene *scene = rt_scene_new();
+    if (scene == NULL) {
+        fprintf(stderr, "rt_scene_new failed\n");
+        return 1;
+    }
+    RtCamera camera = {{0.0, 0.0, 0.0}, {0.0, 0.0, -1.0}, 45.0};
+    check(rt_scene_set_camera(scene, &camera), RT_OK, "set the camera");
+
+    uint32_t white;
+    RtMaterial lambertian = {RT_LAMBERTIAN, {0.8, 0.8, 0.8}, 0.0, 0.0};
+    check(rt_scene_add_material(scene, &lambertian, &white), RT_OK, "add a material");
+    RtSphere sphere = {{0.0, 0.0, -5.0}, 1.0, white};
+    check(rt_scene_add_sphere(scene, &sphere), RT_OK, "add a sphere");
+    RtPointLight light = {{0.0, 2.0, 0.0}, {1.0, 1.0, 1.0}, 20.0};
+    check(rt_scene_add_point_light(scene, &light), RT_OK, "add a light");
+
+    /* Mistakes come back as statuses. */
+    RtSphere stray = {{0.0, 0.0, -5.0}, 1.0, white + 1};
+    check(rt_scene_add_sphere(scene, &stray), RT_INVALID_ARGUMENT, "use a missing material");
+    if (strstr(rt_last_error(), "material") == NULL) {
+        fprintf(stderr, "unhelpful error: %s\n", rt_last_error());
+        return 1;
+    }
+    check(rt_scene_add_sphere(NULL, &sphere), RT_NULL_POINTER, "add to no scene");
+
+    RtSettings settings;
+    check(rt_settings_default(&settings), RT_OK, "get the default settings");
+    settings.width = WIDTH;
+    settings.height = HEIGHT;
+    size_t length = 4 * WIDTH * HEIGHT;
+    uint8_t *pixels = malloc(length);
+    check(rt_render(scene, &settings, pixels, length - 1), RT_INVALID_ARGUMENT, "render short");
+    settings.spp = 0;
+    check(rt_render(scene, &settings, pixels, length), RT_RENDER_FAILED, "render no samples");
+    settings.spp = 1;
+    check(rt_render(scene, &settings, pixels, length), RT_OK, "render");
+
+    const uint8_t *corner = pixels;
+    const uint8_t *center = pixels + 4 * (HEIGHT / 2 * WIDTH + WIDTH / 2);
+    if (corner[0] != 0 || corner[3] != 255 || center[0] == 0) {
+        fprintf(stderr, "the sphere isn't lit against a black background\n");
+        return 1;
+    }
+    printf("%d %d %d %d\n", center[0], center[1], center[2], center[3]);
+
+    free(pixels);
+    rt_scene_free(scene);
+    rt_scene_free(NULL);
+    return 0;
+}
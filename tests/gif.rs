@@ -0,0 +1,294 @@
+use std::fs;
+use std::process::Command;
+use std::sync::Arc;
+
+use basic_raytracer::animation::{Frame, Turntable};
+use basic_raytracer::camera::Camera;
+use basic_raytracer::gif::GifWriter;
+use basic_raytracer::image::Image;
+use basic_raytracer::light::PointLight;
+use basic_raytracer::material::Lambertian;
+use basic_raytracer::render::{render, IntegratorKind, RenderSettings};
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene::Scene;
+use basic_raytracer::shapes::{Plane, Sphere};
+use basic_raytracer::vector::{Color, Vector};
+
+/// What a GIF decodes to.
+struct Decoded {
+    width: usize,
+    height: usize,
+    palette: Vec<[u8; 3]>,
+    loops: Option<u16>,
+    delays: Vec<u16>,
+    frames: Vec<Vec<u8>>,
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+/// The data of the sub-blocks starting at `*at`, moving past them.
+fn sub_blocks(bytes: &[u8], at: &mut usize) -> Vec<u8> {
+    let mut data = Vec::new();
+    loop {
+        let len = bytes[*at] as usize;
+        *at += 1;
+        if len == 0 {
+            return data;
+        }
+        data.extend_from_slice(&bytes[*at..*at + len]);
+        *at += len;
+    }
+}
+
+fn unlzw(data: &[u8], min_width: u32) -> Vec<u8> {
+    let clear = 1usize << min_width;
+    let reset = || -> Vec<Vec<u8>> {
+        let mut table: Vec<Vec<u8>> = (0..clear).map(|i| vec![i as u8]).collect();
+        table.push(Vec::new());
+        table.push(Vec::new());
+        table
+    };
+    let mut table = reset();
+    let mut width = min_width + 1;
+    let mut previous: Option<Vec<u8>> = None;
+    let mut out = Vec::new();
+    let (mut bits, mut pending, mut bytes) = (0u32, 0, data.iter());
+    loop {
+        while pending < width {
+            bits |= (*bytes.next().expect("ran out of data before the end code") as u32) << pending;
+            pending += 8;
+        }
+        let code = (bits & ((1 << width) - 1)) as usize;
+        bits >>= width;
+        pending -= width;
+        if code == clear {
+            table = reset();
+            width = min_width + 1;
+            previous = None;
+            continue;
+        }
+        if code == clear + 1 {
+            return out;
+        }
+        let entry = match (table.get(code), &previous) {
+            (Some(entry), _) => entry.clone(),
+            (None, Some(previous)) if code == table.len() => {
+                let mut entry = previous.clone();
+                entry.push(previous[0]);
+                entry
+            }
+            _ => panic!("code {} isn't in the table", code),
+        };
+        if let Some(mut previous) = previous {
+            if table.len() < 4096 {
+                previous.push(entry[0]);
+                table.push(previous);
+            }
+        }
+        out.extend_from_slice(&entry);
+        if table.len() == 1 << width && width < 12 {
+            width += 1;
+        }
+        previous = Some(entry);
+    }
+}
+
+fn decode(bytes: &[u8]) -> Decoded {
+    assert_eq!(&bytes[..6], b"GIF89a");
+    let packed = bytes[10];
+    assert!(packed & 0x80 != 0, "no global color table");
+    let colors = 2 << (packed & 7);
+    let mut gif = Decoded {
+        width: u16_at(bytes, 6) as usize,
+        height: u16_at(bytes, 8) as usize,
+        palette: bytes[13..13 + 3 * colors]
+            .chunks(3)
+            .map(|c| [c[0], c[1], c[2]])
+            .collect(),
+        loops: None,
+        delays: Vec::new(),
+        frames: Vec::new(),
+    };
+    let mut at = 13 + 3 * colors;
+    loop {
+        at += 1;
+        match bytes[at - 1] {
+            0x21 => {
+                let label = bytes[at];
+                at += 1;
+                let data = sub_blocks(bytes, &mut at);
+                match label {
+                    0xf9 => gif.delays.push(u16_at(&data, 1)),
+                    0xff if data.starts_with(b"NETSCAPE2.0\x01") => {
+                        gif.loops = Some(u16_at(&data, 12))
+                    }
+                    _ => {}
+                }
+            }
+            0x2c => {
+                assert_eq!(u16_at(bytes, at + 4) as usize, gif.width);
+                assert_eq!(u16_at(bytes, at + 6) as usize, gif.height);
+                assert_eq!(bytes[at + 8], 0, "unexpected local color table");
+                let min_width = bytes[at + 9] as u32;
+                at += 10;
+                gif.frames
+                    .push(unlzw(&sub_blocks(bytes, &mut at), min_width));
+            }
+            0x3b => return gif,
+            other => panic!("unexpected block {:#x}", other),
+        }
+    }
+}
+
+fn turntable(frames: usize) -> Vec<Image> {
+    let mut scene = Scene::new(Camera::new(
+        Vector::new(0.0, 1.0, 4.0),
+        Vector::zero(),
+        45.0,
+    ));
+    scene.add(
+        Plane::new(Vector::new(0.0, -1.0, 0.0), Vector::new(0.0, 1.0, 0.0)),
+        Arc::new(Lambertian::new(Color::new(0.2, 0.6, 0.3))),
+    );
+    scene.add(
+        Sphere::new(Vector::new(0.5, 0.0, 0.0), 0.8),
+        Arc::new(Lambertian::new(Color::new(0.9, 0.3, 0.1))),
+    );
+    scene.add_light(PointLight::new(
+        Vector::new(2.0, 4.0, 3.0),
+        Color::new(1.0, 1.0, 1.0),
+        40.0,
+    ));
+    let orbit = Turntable::around(&scene.camera);
+    let settings = RenderSettings {
+        width: 96,
+        height: 64,
+        integrator: IntegratorKind::Whitted,
+        ..RenderSettings::default()
+    };
+    (0..frames)
+        .map(|index| {
+            let frame = Frame {
+                index,
+                count: frames,
+                fps: 24.0,
+            };
+            scene.camera = orbit.camera(&scene.camera, frame);
+            render(&scene, &settings).unwrap().0
+        })
+        .collect()
+}
+
+#[test]
+fn a_turntable_becomes_a_looping_gif() {
+    let dir = std::env::temp_dir().join(format!("gif-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let images = turntable(24);
+    for dither in [false, true] {
+        let path = dir.join(format!("spin-{}.gif", dither));
+        let mut gif = GifWriter::create(&path, 24.0, dither).unwrap();
+        for image in &images {
+            gif.add_frame(image).unwrap();
+        }
+        gif.finish().unwrap();
+        // Nothing but the GIF is left behind.
+        assert_eq!(
+            fs::read_dir(&dir).unwrap().count(),
+            if dither { 2 } else { 1 }
+        );
+
+        let decoded = decode(&fs::read(&path).unwrap());
+        assert_eq!((decoded.width, decoded.height), (96, 64));
+        assert_eq!(decoded.loops, Some(0));
+        assert_eq!(decoded.frames.len(), 24);
+        // 100 / 24 hundredths of a second a frame, rounded so that the
+        // whole turn takes a second.
+        assert!(decoded.delays.iter().all(|&delay| delay == 4 || delay == 5));
+        assert_eq!(decoded.delays.iter().map(|&d| d as u32).sum::<u32>(), 100);
+
+        for (frame, image) in decoded.frames.iter().zip(&images) {
+            assert_eq!(frame.len(), 96 * 64);
+            let original = image.to_rgba8();
+            let mut error = 0;
+            for (&index, rgba) in frame.iter().zip(original.chunks(4)) {
+                let color = decoded.palette[index as usize];
+                for c in 0..3 {
+                    error += (color[c] as i32 - rgba[c] as i32).abs();
+                }
+            }
+            let mean = error as Scalar / (3 * frame.len()) as Scalar;
+            assert!(mean < 6.0, "{}", mean);
+        }
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn noise_fills_the_code_table_and_starts_over() {
+    let dir = std::env::temp_dir().join(format!("gif-noise-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let mut image = Image::new(160, 120);
+    let mut state = 1u32;
+    for y in 0..image.height {
+        for x in 0..image.width {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            let [r, g, b, _] = state.to_le_bytes();
+            let channel = |c: u8| c as Scalar / 255.0;
+            image.set(x, y, Color::new(channel(r), channel(g), channel(b)));
+        }
+    }
+    let path = dir.join("noise.gif");
+    let mut gif = GifWriter::create(&path, 10.0, false).unwrap();
+    gif.add_frame(&image).unwrap();
+    gif.add_frame(&Image::new(160, 120)).unwrap();
+    assert!(gif.add_frame(&Image::new(16, 12)).is_err());
+    gif.finish().unwrap();
+
+    let decoded = decode(&fs::read(&path).unwrap());
+    assert_eq!(decoded.palette.len(), 256);
+    assert_eq!(decoded.delays, vec![10, 10]);
+    assert_eq!(decoded.frames[0].len(), 160 * 120);
+    // The black frame is all one color.
+    assert_eq!(decoded.frames[1].len(), 160 * 120);
+    let black = decoded.frames[1][0];
+    assert!(decoded.frames[1].iter().all(|&index| index == black));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn the_binary_collects_a_sequence_into_one_gif() {
+    let dir = std::env::temp_dir().join(format!("gif-binary-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_basic-raytracer"))
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .unwrap()
+    };
+    let output = run(&[
+        "--frames",
+        "3",
+        "--turntable",
+        "--fps",
+        "20",
+        "--output",
+        "spin.gif",
+    ]);
+    assert!(output.status.success());
+    let decoded = decode(&fs::read(dir.join("spin.gif")).unwrap());
+    assert_eq!(decoded.delays, vec![5, 5, 5]);
+    assert!(!dir.join("spin_0000.gif").exists());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("Raytraced spin.gif successfully!"));
+
+    let output = run(&["--all-layers", "--output", "layers.gif"]);
+    assert_eq!(output.status.code(), Some(2));
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,293 @@
+//! Renders of the scenes in `scenes/`, and of small reference scenes made
+//! here, against images of them kept in `tests/golden/`, made with `f64`
+//! math. Run `cargo run --example regen-goldens` to write them afresh after
+//! a change meant to alter the renders.
+//!
+//! The settings are spelled out rather than left to the defaults, seed
+//! included. Every pixel sample draws from its own stream, so the thread
+//! count doesn't change the image, and the renders are the same on any
+//! machine up to how its libm rounds.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::image::Image;
+use basic_raytracer::light::PointLight;
+use basic_raytracer::material::{Dielectric, Emissive, Glossy, Lambertian, Material};
+use basic_raytracer::render::{render, IntegratorKind, RenderSettings};
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene::Scene;
+use basic_raytracer::scene_file;
+use basic_raytracer::shapes::{
+    Blob, Cone, Cuboid, Cylinder, Metaballs, Plane, Quad, SdfShape, Shape, Sphere, Torus, Triangle,
+};
+use basic_raytracer::tile::TileOrder;
+use basic_raytracer::vector::{Color, Vector};
+
+/// How far off a render may be from its golden image: the mean difference
+/// of a channel, and the share of pixels off by more than `FAR` in any of
+/// them. `f64` renders should only differ by how libm rounds on another
+/// machine; `f32` ones are expected to be off a little all over, but a
+/// pixel far off is shadow acne or light leaking through a seam.
+#[cfg(not(feature = "f32"))]
+const TOLERANCE: (f64, f64) = (0.2 / 255.0, 0.002);
+#[cfg(feature = "f32")]
+const TOLERANCE: (f64, f64) = (1.0 / 255.0, 0.01);
+const FAR: f64 = 16.0 / 255.0;
+
+fn settings(width: u32, height: u32) -> RenderSettings {
+    RenderSettings {
+        width,
+        height,
+        spp: 1,
+        integrator: IntegratorKind::Whitted,
+        seed: 0,
+        tile_order: TileOrder::Scanline,
+        ..RenderSettings::default()
+    }
+}
+
+/// The mean difference of a channel between the two images as they're
+/// written, and the share of pixels more than `FAR` off.
+fn difference(a: &Image, b: &Image) -> (f64, f64) {
+    let (a, b) = (a.to_rgba8(), b.to_rgba8());
+    let channels = |pixel: &[u8]| pixel[..3].to_vec();
+    let (mut total, mut far) = (0.0, 0);
+    for (p, q) in a.chunks(4).zip(b.chunks(4)) {
+        let off: Vec<f64> = channels(p)
+            .iter()
+            .zip(channels(q))
+            .map(|(&x, y)| (x as f64 - y as f64).abs() / 255.0)
+            .collect();
+        total += off.iter().sum::<f64>();
+        if off.iter().any(|&d| d > FAR) {
+            far += 1;
+        }
+    }
+    let pixels = (a.len() / 4) as f64;
+    (total / (3.0 * pixels), far as f64 / pixels)
+}
+
+/// The difference of two images, eight times over so that small ones show.
+fn difference_image(a: &Image, b: &Image) -> Image {
+    let mut diff = Image::new(a.width, a.height);
+    for ((out, p), q) in diff.pixels.iter_mut().zip(&a.pixels).zip(&b.pixels) {
+        let off = *p - *q;
+        *out = 8.0 * Vector::new(off.x.abs(), off.y.abs(), off.z.abs());
+    }
+    diff
+}
+
+/// Checks `image` against the golden image `name`, or writes it there when
+/// updating. On a mismatch the render and its difference from the golden
+/// image are written beside the test binaries, and the failure says where.
+fn check(name: &str, image: &Image) {
+    let golden = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let reference = golden.join(format!("{}.png", name));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(reference.parent().unwrap()).unwrap();
+        image.write_png(&reference).unwrap();
+        return;
+    }
+    let expected = Image::read_png(&reference).unwrap();
+    assert_eq!(
+        (image.width, image.height),
+        (expected.width, expected.height),
+        "{}",
+        name
+    );
+    let (mean, far) = difference(image, &expected);
+    if mean <= TOLERANCE.0 && far <= TOLERANCE.1 {
+        return;
+    }
+    let out = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    let (actual, diff) = (
+        out.join(format!("{}.png", name)),
+        out.join(format!("{}-diff.png", name)),
+    );
+    fs::create_dir_all(actual.parent().unwrap()).unwrap();
+    image.write_png(&actual).unwrap();
+    difference_image(image, &expected).write_png(&diff).unwrap();
+    panic!(
+        "{}: off by {:.5} on average, with {:.2}% of pixels far off; see {} and {}",
+        name,
+        mean,
+        100.0 * far,
+        actual.display(),
+        diff.display()
+    );
+}
+
+#[test]
+fn the_scenes_render_as_they_did() {
+    let scenes_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes");
+    let mut scenes: Vec<PathBuf> = fs::read_dir(scenes_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .collect();
+    scenes.sort();
+    assert!(!scenes.is_empty());
+    for path in scenes {
+        let scene = scene_file::load(&path).unwrap();
+        let (image, _) = render(&scene, &settings(128, 96)).unwrap();
+        check(&path.file_stem().unwrap().to_string_lossy(), &image);
+    }
+}
+
+/// A stage for one thing at the origin: a camera above and in front of it,
+/// a light over its shoulder and a grey sky.
+fn stage() -> Scene {
+    let mut scene = Scene::new(Camera::new(
+        Vector::new(0.0, 1.5, 4.5),
+        Vector::new(0.0, 0.2, 0.0),
+        40.0,
+    ));
+    scene.background = Color::new(0.2, 0.2, 0.25);
+    scene.add_light(PointLight::new(
+        Vector::new(3.0, 5.0, 4.0),
+        Color::new(1.0, 1.0, 1.0),
+        50.0,
+    ));
+    scene
+}
+
+fn shape_scene(shape: impl Shape + 'static) -> Scene {
+    let mut scene = stage();
+    scene.add(shape, Arc::new(Lambertian::new(Color::new(0.8, 0.4, 0.2))));
+    scene
+}
+
+/// A sphere of `material` in front of a checkerboard of spheres, for
+/// reflections and refractions to show, on a floor.
+fn material_scene(material: Arc<dyn Material>) -> Scene {
+    let mut scene = stage();
+    scene.add(Sphere::new(Vector::zero(), 0.8), material);
+    for i in 0..5 {
+        let color = if i % 2 == 0 {
+            Color::new(0.2, 0.3, 0.9)
+        } else {
+            Color::new(0.9, 0.9, 0.2)
+        };
+        scene.add(
+            Sphere::new(Vector::new(i as Scalar * 0.8 - 1.6, 0.0, -2.0), 0.35),
+            Arc::new(Lambertian::new(color)),
+        );
+    }
+    scene.add(
+        Plane::new(Vector::new(0.0, -0.8, 0.0), Vector::new(0.0, 1.0, 0.0)),
+        Arc::new(Lambertian::new(Color::new(0.7, 0.7, 0.7))),
+    );
+    scene
+}
+
+/// Spheres and a box casting shadows onto a floor from two lights, one of
+/// them colored so that each shadow shows which light it hides.
+fn shadow_scene() -> Scene {
+    let mut scene = stage();
+    let white = Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.8)));
+    scene.add(Sphere::new(Vector::new(-0.7, 0.0, 0.0), 0.5), white.clone());
+    scene.add(Sphere::new(Vector::new(0.6, -0.1, 0.6), 0.4), white.clone());
+    scene.add(
+        Cuboid::new(Vector::new(0.2, -0.5, -1.0), Vector::new(0.9, 0.6, -0.4)),
+        white.clone(),
+    );
+    scene.add(
+        Plane::new(Vector::new(0.0, -0.5, 0.0), Vector::new(0.0, 1.0, 0.0)),
+        white,
+    );
+    scene.add_light(PointLight::new(
+        Vector::new(-4.0, 3.0, 1.0),
+        Color::new(0.3, 0.5, 1.0),
+        30.0,
+    ));
+    scene
+}
+
+/// The small scenes with golden images under `tests/golden/reference/`.
+fn reference_scenes() -> Vec<(&'static str, Scene)> {
+    vec![
+        ("demo", Scene::demo()),
+        ("sphere", shape_scene(Sphere::new(Vector::zero(), 0.8))),
+        (
+            "plane",
+            shape_scene(Plane::new(
+                Vector::new(0.0, -0.5, 0.0),
+                Vector::new(0.0, 1.0, 0.2),
+            )),
+        ),
+        (
+            "cuboid",
+            shape_scene(Cuboid::new(
+                Vector::new(-0.6, -0.6, -0.6),
+                Vector::new(0.6, 0.6, 0.6),
+            )),
+        ),
+        (
+            "cylinder",
+            shape_scene(Cylinder::new(Vector::new(0.0, -0.7, 0.0), 0.6, 1.4)),
+        ),
+        (
+            "cone",
+            shape_scene(Cone::new(Vector::new(0.0, -0.7, 0.0), 0.7, 1.5)),
+        ),
+        (
+            "quad",
+            shape_scene(Quad::new(
+                Vector::new(-0.8, -0.6, 0.0),
+                Vector::new(1.6, 0.0, -0.4),
+                Vector::new(0.0, 1.3, 0.0),
+            )),
+        ),
+        (
+            "triangle",
+            shape_scene(Triangle::new(
+                Vector::new(-0.9, -0.6, 0.0),
+                Vector::new(0.9, -0.6, 0.0),
+                Vector::new(0.0, 0.9, -0.3),
+            )),
+        ),
+        (
+            "torus",
+            shape_scene(SdfShape::new(Torus::new(Vector::zero(), 0.7, 0.25))),
+        ),
+        (
+            "metaballs",
+            shape_scene(Metaballs::new(
+                vec![
+                    Blob::new(Vector::new(-0.35, 0.0, 0.0), 0.7, 1.0),
+                    Blob::new(Vector::new(0.35, 0.1, 0.0), 0.6, 1.0),
+                ],
+                0.5,
+            )),
+        ),
+        (
+            "lambertian",
+            material_scene(Arc::new(Lambertian::new(Color::new(0.8, 0.3, 0.3)))),
+        ),
+        (
+            "glossy",
+            material_scene(Arc::new(Glossy::new(Color::new(0.9, 0.9, 0.9), 200.0))),
+        ),
+        ("dielectric", material_scene(Arc::new(Dielectric::new(1.5)))),
+        (
+            "emissive",
+            material_scene(Arc::new(Emissive::new(Color::new(2.0, 1.5, 0.5)))),
+        ),
+        ("shadows", shadow_scene()),
+        ("random_spheres", Scene::random_spheres(60, 7)),
+    ]
+}
+
+#[test]
+fn the_reference_scenes_render_as_they_did() {
+    for (name, scene) in reference_scenes() {
+        let (image, _) = render(&scene, &settings(160, 120)).unwrap();
+        check(&format!("reference/{}", name), &image);
+    }
+}
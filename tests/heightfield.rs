@@ -0,0 +1,237 @@
+use std::path::Path;
+
+use basic_raytracer::image::Image;
+use basic_raytracer::light::{DirectionalLight, Light};
+use basic_raytracer::ray::Ray;
+use basic_raytracer::sampler::Sampler;
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene::Scene;
+use basic_raytracer::scene_file;
+use basic_raytracer::shapes::{Heightfield, Shape};
+use basic_raytracer::vector::Vector;
+
+/// How near a hit must be to the height the image gives, and how near a
+/// direction that's only normalized to the one expected.
+#[cfg(not(feature = "f32"))]
+const TOLERANCE: Scalar = 1e-9;
+#[cfg(feature = "f32")]
+const TOLERANCE: Scalar = 1e-5;
+#[cfg(not(feature = "f32"))]
+const EXACT: Scalar = 1e-12;
+#[cfg(feature = "f32")]
+const EXACT: Scalar = 1e-6;
+
+fn scenes() -> std::path::PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes")
+}
+
+/// The terrain image, spread over [-2, 2]² and a unit high.
+fn terrain() -> Scene {
+    scene_file::parse(
+        r#"{"heightfields": [{"path": "textures/terrain.png",
+            "corner": [-2, 0, -2], "size": [4, 1, 4],
+            "material": {"type": "lambertian", "albedo": [1, 1, 1]}}]}"#,
+        &scenes(),
+    )
+    .unwrap()
+}
+
+/// The height of the terrain image at `(x, z)`, read straight from the
+/// pixels.
+fn image_height(image: &Image, x: Scalar, z: Scalar) -> Scalar {
+    let gray = |i: u32, j: u32| image.get(i, j).x;
+    let (gx, gz) = (
+        (x + 2.0) / 4.0 * (image.width - 1) as Scalar,
+        (z + 2.0) / 4.0 * (image.height - 1) as Scalar,
+    );
+    let (i, j) = (gx.floor() as u32, gz.floor() as u32);
+    let (u, v) = (gx - i as Scalar, gz - j as Scalar);
+    let top = (1.0 - u) * gray(i, j) + u * gray(i + 1, j);
+    let bottom = (1.0 - u) * gray(i, j + 1) + u * gray(i + 1, j + 1);
+    (1.0 - v) * top + v * bottom
+}
+
+#[test]
+fn hits_follow_the_image() {
+    let scene = terrain();
+    let image = Image::read_linear_png(&scenes().join("textures/terrain.png")).unwrap();
+    let down = Vector::new(0.0, -1.0, 0.0);
+    let mut sampler = Sampler::new(5, 0, 0);
+    // At a sample itself, then at random places within cells.
+    let mut places = vec![(-2.0 + 4.0 * 30.0 / 95.0, -2.0 + 4.0 * 40.0 / 95.0)];
+    for _ in 0..50 {
+        places.push((
+            3.9 * sampler.next_scalar() - 1.95,
+            3.9 * sampler.next_scalar() - 1.95,
+        ));
+    }
+    for &(x, z) in &places {
+        let hit = scene
+            .intersect(
+                &Ray::new(Vector::new(x, 5.0, z), down),
+                1e-4,
+                Scalar::INFINITY,
+            )
+            .unwrap();
+        let expected = image_height(&image, x, z);
+        assert!(
+            (hit.record.point.y - expected).abs() < TOLERANCE,
+            "at ({}, {}): {} != {}",
+            x,
+            z,
+            hit.record.point.y,
+            expected
+        );
+    }
+    let bounds = scene.objects[0].shape.bounds().unwrap();
+    let heights: Vec<Scalar> = image.pixels.iter().map(|pixel| pixel.x).collect();
+    let lowest = heights.iter().copied().fold(Scalar::INFINITY, Scalar::min);
+    let highest = heights
+        .iter()
+        .copied()
+        .fold(Scalar::NEG_INFINITY, Scalar::max);
+    assert_eq!((bounds.min.y, bounds.max.y), (lowest, highest));
+}
+
+#[test]
+fn walking_the_grid_finds_the_first_crossing() {
+    let scene = terrain();
+    let shape = scene.objects[0].shape.as_ref();
+    let image = Image::read_linear_png(&scenes().join("textures/terrain.png")).unwrap();
+    let mut sampler = Sampler::new(9, 0, 0);
+    let mut hits = 0;
+    for _ in 0..300 {
+        let origin = Vector::new(
+            8.0 * sampler.next_scalar() - 4.0,
+            1.0 + sampler.next_scalar(),
+            8.0 * sampler.next_scalar() - 4.0,
+        );
+        let target = Vector::new(
+            3.0 * sampler.next_scalar() - 1.5,
+            0.0,
+            3.0 * sampler.next_scalar() - 1.5,
+        );
+        let ray = Ray::new(origin, target - origin);
+        let above = |t: Scalar| {
+            let p = ray.at(t);
+            p.x.abs() > 2.0 || p.z.abs() > 2.0 || p.y > image_height(&image, p.x, p.z)
+        };
+        match shape.intersect(&ray, 1e-4, Scalar::INFINITY) {
+            Some(hit) => {
+                hits += 1;
+                let p = hit.point;
+                assert!((p.y - image_height(&image, p.x, p.z)).abs() < TOLERANCE);
+                assert!((0..200).all(|k| above(hit.t * k as Scalar / 200.0)));
+                assert!(hit.front_face);
+            }
+            None => assert!((0..=400).all(|k| above(k as Scalar / 200.0))),
+        }
+    }
+    assert!(hits > 200, "{}", hits);
+}
+
+#[test]
+fn shading_normals_are_smooth() {
+    // A gable roof: its two slopes meet in a ridge along z.
+    let heights = [0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0];
+    let roof = Heightfield::new(&heights, 3, Vector::zero(), Vector::new(2.0, 1.0, 2.0));
+    let down = Vector::new(0.0, -1.0, 0.0);
+    let hit_at = |x: Scalar| {
+        roof.intersect(&Ray::new(Vector::new(x, 5.0, 1.0), down), 1e-4, 10.0)
+            .unwrap()
+    };
+    // The geometric normals follow the flat slopes, but the shading normals
+    // turn smoothly over the ridge, straight up at its top.
+    let left = hit_at(0.5);
+    assert!((left.normal - Vector::new(-1.0, 1.0, 0.0).normalize()).len() < TOLERANCE);
+    assert!((hit_at(1.0).shading_normal - Vector::new(0.0, 1.0, 0.0)).len() < TOLERANCE);
+    let (before, after) = (hit_at(0.999).shading_normal, hit_at(1.001).shading_normal);
+    assert!((before - after).len() < 0.01);
+    assert!(left.shading_normal.y > left.normal.y);
+    // Seen from below, the normals face down.
+    let under = roof
+        .intersect(
+            &Ray::new(Vector::new(0.5, -5.0, 1.0), -down),
+            1e-4,
+            Scalar::INFINITY,
+        )
+        .unwrap();
+    assert!(!under.front_face && under.shading_normal.y < 0.0);
+}
+
+#[test]
+fn terrain_shadows_itself_under_a_low_sun() {
+    let scene = terrain();
+    let sun = DirectionalLight::new(Vector::new(1.0, -0.2, 0.0), Vector::new(1.0, 1.0, 1.0), 2.0);
+    let sample = sun
+        .sample(Vector::zero(), &mut Sampler::new(0, 0, 0))
+        .unwrap();
+    assert_eq!(sample.distance, Scalar::INFINITY);
+    assert!((sample.direction - Vector::new(-1.0, 0.2, 0.0).normalize()).len() < EXACT);
+    assert!(sun.is_delta());
+    // The valley just downwind of the tallest hill is in its shadow, and the
+    // hilltop isn't.
+    let down = Vector::new(0.0, -1.0, 0.0);
+    let ground = |x: Scalar, z: Scalar| {
+        scene
+            .intersect(
+                &Ray::new(Vector::new(x, 5.0, z), down),
+                1e-4,
+                Scalar::INFINITY,
+            )
+            .unwrap()
+            .record
+            .point
+    };
+    let valley = ground(-0.3, -0.6);
+    let peak = ground(-0.8, -0.6);
+    assert!(scene.occluded(valley, sample.direction, sample.distance));
+    assert!(!scene.occluded(peak, sample.direction, sample.distance));
+}
+
+#[test]
+fn scene_files_give_heights_or_an_image() {
+    let parse = |members: &str| {
+        scene_file::parse(
+            &format!(
+                r#"{{"heightfields": [{{"corner": [0, 0, 0], "size": [1, 1, 1], {},
+                    "material": {{"type": "lambertian", "albedo": [1, 1, 1]}}}}]}}"#,
+                members
+            ),
+            &scenes(),
+        )
+    };
+    let scene = parse(r#""heights": [[0, 0.5], [0.5, 1]]"#).unwrap();
+    let hit = scene
+        .intersect(
+            &Ray::new(Vector::new(0.5, 5.0, 0.5), Vector::new(0.0, -1.0, 0.0)),
+            1e-4,
+            Scalar::INFINITY,
+        )
+        .unwrap();
+    assert!((hit.record.point.y - 0.5).abs() < EXACT);
+
+    assert_eq!(
+        parse(r#""heights": [[0, 0.5], [0.5]]"#).err().unwrap(),
+        "heightfields[0].heights[1]: expected 2 heights"
+    );
+    assert_eq!(
+        parse(r#""heights": [[0, 0.5]]"#).err().unwrap(),
+        "heightfields[0].heights: a heightfield needs at least two rows and columns"
+    );
+    assert_eq!(
+        parse(r#""heights": [[0, 0.5], [0.5, 1]], "path": "textures/terrain.png""#)
+            .err()
+            .unwrap(),
+        "heightfields[0]: expected either \"path\" or \"heights\""
+    );
+    let error = scene_file::parse(
+        r#"{"lights": [{"type": "directional", "direction": [0, 0, 0],
+            "color": [1, 1, 1], "intensity": 1}]}"#,
+        Path::new(""),
+    );
+    assert_eq!(
+        error.err().unwrap(),
+        "lights[0].direction: a direction can't be zero"
+    );
+}
@@ -0,0 +1,129 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::info::SceneInfo;
+use basic_raytracer::light::{DirectionalLight, PointLight};
+use basic_raytracer::material::{Glossy, Lambertian};
+use basic_raytracer::matrix::Matrix4;
+use basic_raytracer::scene::Scene;
+use basic_raytracer::scene_file;
+use basic_raytracer::shapes::{MeshTriangle, Plane, Sphere, TriangleMesh};
+use basic_raytracer::vector::{Color, Vector};
+
+/// A tetrahedron's four faces.
+fn tetrahedron() -> TriangleMesh {
+    let positions = vec![
+        Vector::new(0.0, 0.0, 0.0),
+        Vector::new(1.0, 0.0, 0.0),
+        Vector::new(0.0, 1.0, 0.0),
+        Vector::new(0.0, 0.0, 1.0),
+    ];
+    let faces = [[0, 2, 1], [0, 1, 3], [0, 3, 2], [1, 2, 3]];
+    let triangles = faces
+        .iter()
+        .map(|&positions| MeshTriangle {
+            positions,
+            uvs: None,
+        })
+        .collect();
+    TriangleMesh::new(positions, Vec::new(), triangles)
+}
+
+fn scene() -> Scene {
+    let origin = Vector::new(0.0, 0.0, 0.0);
+    let mut scene = Scene::new(Camera::new(Vector::new(0.0, 1.0, 5.0), origin, 45.0));
+    let gray = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    scene.add(Plane::new(origin, Vector::new(0.0, 1.0, 0.0)), gray.clone());
+    scene.add(Sphere::new(Vector::new(-2.0, 1.0, 0.0), 1.0), gray.clone());
+    let mesh = scene.add_geometry(tetrahedron());
+    let shiny = Arc::new(Glossy::new(Color::new(0.9, 0.9, 0.9), 50.0));
+    for x in [1.0, 3.0].iter() {
+        let place = Matrix4::translate(Vector::new(*x, 0.0, 0.0));
+        scene.add_instance(mesh, place, shiny.clone());
+    }
+    scene.add(Sphere::new(Vector::new(0.0, 3.0, 0.0), 0.5), gray);
+    scene.add_light(PointLight::new(
+        Vector::new(0.0, 5.0, 0.0),
+        Color::new(1.0, 1.0, 1.0),
+        10.0,
+    ));
+    scene.add_light(DirectionalLight::new(
+        Vector::new(0.0, -1.0, 0.0),
+        Color::new(1.0, 1.0, 1.0),
+        1.0,
+    ));
+    scene
+}
+
+#[test]
+fn objects_are_described_by_what_they_are() {
+    let info = SceneInfo::of(&scene());
+    let shapes: Vec<&str> = info.objects.iter().map(|object| object.shape).collect();
+    // An instance is named for the shape it places.
+    assert_eq!(
+        shapes,
+        ["Plane", "Sphere", "TriangleMesh", "TriangleMesh", "Sphere"]
+    );
+    assert_eq!(info.objects[2].material, "Glossy");
+    assert_eq!(info.objects[2].triangles, 4);
+    assert_eq!(info.triangles, 8);
+    assert!(info.objects[0].bounds.is_none());
+    let placed = info.objects[3].bounds.unwrap();
+    assert_eq!(placed.min, Vector::new(3.0, 0.0, 0.0));
+    assert!(info.objects.iter().all(|object| object.bytes > 0));
+}
+
+#[test]
+fn lights_and_shared_materials_are_counted_by_kind() {
+    let info = SceneInfo::of(&scene());
+    assert_eq!(info.lights, [("DirectionalLight", 1), ("PointLight", 1)]);
+    // Three objects share the gray material, and two the shiny one.
+    assert_eq!(info.materials, [("Glossy", 1), ("Lambertian", 1)]);
+    let bounds = info.bounds.unwrap();
+    assert_eq!(bounds.min, Vector::new(-3.0, 0.0, -1.0));
+    assert_eq!(bounds.max, Vector::new(4.0, 3.5, 1.0));
+    assert_eq!(info.layers, ["default"]);
+    assert_eq!(
+        info.memory_bytes(),
+        info.geometry_bytes + info.bvh_bytes + info.object_bytes
+    );
+}
+
+#[test]
+fn the_summary_comes_before_a_line_for_each_object() {
+    let text = SceneInfo::of(&scene()).to_string();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], "objects: 5 (1 unbounded, 0 animated)");
+    assert_eq!(lines[1], "triangles: 8");
+    assert_eq!(lines[2], "lights: 2 (DirectionalLight 1, PointLight 1)");
+    assert_eq!(lines[3], "materials: 2 (Glossy 1, Lambertian 1)");
+    assert_eq!(
+        lines[4],
+        "bounds: (-3.000, 0.000, -1.000) to (4.000, 3.500, 1.000)"
+    );
+    assert_eq!(lines[5], "layers: default");
+    assert!(lines[6].starts_with("estimated memory: "), "{}", lines[6]);
+    assert_eq!(lines[7], "");
+    assert_eq!(
+        lines[8],
+        " object  shape          material       triangles      bytes  bounds"
+    );
+    assert_eq!(lines.len(), 9 + 5);
+    assert!(lines[9].starts_with("      0  Plane          Lambertian             0 "));
+    assert!(lines[9].ends_with("  unbounded"));
+    assert!(lines[12].starts_with("      3  TriangleMesh   Glossy                 4 "));
+    assert!(lines[12].ends_with("  (3.000, 0.000, 0.000) to (4.000, 1.000, 1.000)"));
+}
+
+#[test]
+fn meshes_in_scene_files_are_loaded_to_be_counted() {
+    let scene = scene_file::load(Path::new("scenes/shapes.json")).unwrap();
+    let info = SceneInfo::of(&scene);
+    let mesh = info
+        .objects
+        .iter()
+        .find(|object| object.shape == "TriangleMesh");
+    assert_eq!(mesh.unwrap().triangles, 12);
+    assert_eq!(info.triangles, 12);
+}
@@ -0,0 +1,156 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::material::Lambertian;
+use basic_raytracer::matrix::Matrix4;
+use basic_raytracer::ray::Ray;
+use basic_raytracer::sampler::Sampler;
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene::Scene;
+use basic_raytracer::scene_file;
+use basic_raytracer::shapes::{MeshTriangle, Plane, Shape, TriangleMesh};
+use basic_raytracer::vector::Vector;
+
+/// A bumpy unit square in the xz plane, split into `n` × `n` cells.
+fn terrain(n: usize) -> TriangleMesh {
+    let mut positions = Vec::new();
+    for i in 0..=n {
+        for j in 0..=n {
+            let (x, z) = (i as Scalar / n as Scalar, j as Scalar / n as Scalar);
+            positions.push(Vector::new(x, 0.1 * (7.0 * x).sin() * (5.0 * z).cos(), z));
+        }
+    }
+    let mut triangles = Vec::new();
+    let vertex = |i: usize, j: usize| i * (n + 1) + j;
+    for i in 0..n {
+        for j in 0..n {
+            let (a, b) = (vertex(i, j), vertex(i + 1, j));
+            let (c, d) = (vertex(i + 1, j + 1), vertex(i, j + 1));
+            for positions in [[a, d, c], [a, c, b]] {
+                triangles.push(MeshTriangle {
+                    positions,
+                    uvs: None,
+                });
+            }
+        }
+    }
+    TriangleMesh::new(positions, Vec::new(), triangles)
+}
+
+/// A scene with `count` copies of one terrain tile in a row along x.
+fn forest(count: usize) -> Scene {
+    let mut scene = Scene::new(Camera::new(
+        Vector::new(0.0, 2.0, 5.0),
+        Vector::zero(),
+        40.0,
+    ));
+    let tile = scene.add_geometry(terrain(32));
+    let material = Arc::new(Lambertian::new(Vector::new(0.5, 0.5, 0.5)));
+    for i in 0..count {
+        let place = Matrix4::translate(Vector::new(1.5 * i as Scalar, 0.0, 0.0))
+            * Matrix4::rotate_y(0.3 * i as Scalar);
+        scene.add_instance(tile, place, material.clone());
+    }
+    scene
+}
+
+#[test]
+fn instances_share_their_geometry() {
+    let one = forest(1).geometry_bytes();
+    let hundred = forest(100).geometry_bytes();
+    let mesh = terrain(32).memory_bytes();
+    assert!(mesh > 100_000, "{}", mesh);
+    assert!(one >= mesh);
+    // Each further instance costs a handle and its matrices, not triangles.
+    assert!(hundred - one < 99 * 1024, "{} vs {}", hundred, one);
+}
+
+#[test]
+fn the_scene_bvh_finds_the_nearest_hit() {
+    let mut scene = forest(20);
+    scene.add(
+        Plane::new(Vector::new(0.0, -0.05, 0.0), Vector::new(0.0, 1.0, 0.0)),
+        Arc::new(Lambertian::new(Vector::new(0.5, 0.5, 0.5))),
+    );
+    let mut sampler = Sampler::new(7, 0, 0);
+    for _ in 0..500 {
+        let origin = Vector::new(
+            30.0 * sampler.next_scalar() - 1.0,
+            1.0 + sampler.next_scalar(),
+            3.0 * sampler.next_scalar() - 1.0,
+        );
+        let direction = Vector::new(
+            sampler.next_scalar() - 0.5,
+            -sampler.next_scalar(),
+            sampler.next_scalar() - 0.5,
+        )
+        .normalize();
+        let ray = Ray::new(origin, direction);
+        let brute_force = scene
+            .objects
+            .iter()
+            .filter_map(|object| object.shape.intersect(&ray, 1e-4, Scalar::INFINITY))
+            .map(|hit| hit.t)
+            .fold(Scalar::INFINITY, Scalar::min);
+        let found = scene
+            .intersect(&ray, 1e-4, Scalar::INFINITY)
+            .map_or(Scalar::INFINITY, |hit| hit.record.t);
+        assert_eq!(found, brute_force);
+        if brute_force.is_finite() {
+            assert!(scene.occluded(origin, direction, brute_force + 1e-3));
+            assert!(!scene.occluded(origin, direction, brute_force - 1e-3));
+        }
+    }
+}
+
+#[test]
+fn scene_files_instance_named_geometry() {
+    let scenes = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes");
+    let parse = |members: &str| {
+        scene_file::parse(
+            &format!(
+                r#"{{"geometry": [{{"name": "cube", "path": "models/tilted_cube.obj",
+                    "material": {{"type": "lambertian", "albedo": [1, 1, 1]}}}}],
+                    {}}}"#,
+                members
+            ),
+            &scenes,
+        )
+    };
+    let scene = parse(
+        r#""instances": [{"geometry": "cube"},
+            {"geometry": "cube", "transform": {"translate": [3, 0, 0]},
+             "material": {"type": "dielectric", "ior": 1.5}}]"#,
+    )
+    .unwrap();
+    assert_eq!(scene.geometry.len(), 1);
+    assert_eq!(scene.objects.len(), 2);
+    assert_eq!(Arc::strong_count(&scene.geometry[0]), 3);
+
+    assert_eq!(
+        parse(r#""instances": [{"geometry": "sphere"}]"#)
+            .err()
+            .unwrap(),
+        "instances[0].geometry: no geometry named \"sphere\""
+    );
+    let error = scene_file::parse(
+        r#"{"geometry": [{"name": "cube", "path": "models/tilted_cube.obj"}],
+            "instances": [{"geometry": "cube"}]}"#,
+        &scenes,
+    )
+    .err()
+    .unwrap();
+    assert_eq!(error, "instances[0]: missing member \"material\"");
+    let error = scene_file::parse(
+        r#"{"geometry": [{"name": "cube", "path": "models/tilted_cube.obj"},
+            {"name": "cube", "path": "models/cube.obj"}]}"#,
+        &scenes,
+    )
+    .err()
+    .unwrap();
+    assert_eq!(
+        error,
+        "geometry[1].name: geometry \"cube\" is already defined"
+    );
+}
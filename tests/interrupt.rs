@@ -0,0 +1,170 @@
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::image::Image;
+use basic_raytracer::light::PointLight;
+use basic_raytracer::material::Lambertian;
+use basic_raytracer::render::{
+    render_progressive_from, render_stoppable, IntegratorKind, RenderSettings,
+};
+use basic_raytracer::scene::Scene;
+use basic_raytracer::shapes::Plane;
+use basic_raytracer::tile::{tiles, TileOrder};
+use basic_raytracer::vector::{Color, Vector};
+
+/// A wall filling the view, so that every rendered row is lit.
+fn wall() -> Scene {
+    let mut scene = Scene::new(Camera::new(
+        Vector::new(0.0, 0.0, 5.0),
+        Vector::zero(),
+        60.0,
+    ));
+    scene.add(
+        Plane::new(Vector::zero(), Vector::new(0.0, 0.0, 1.0)),
+        Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.8))),
+    );
+    scene.add_light(PointLight::new(
+        Vector::new(0.0, 0.0, 4.0),
+        Color::new(1.0, 1.0, 1.0),
+        20.0,
+    ));
+    scene
+}
+
+fn settings() -> RenderSettings {
+    RenderSettings {
+        width: 64,
+        height: 4096,
+        spp: 2,
+        integrator: IntegratorKind::Whitted,
+        ..RenderSettings::default()
+    }
+}
+
+/// The rows that are all lit, and whether every other row is all black.
+fn rows_done(image: &Image) -> (u32, bool) {
+    let row = |y| (0..image.width).map(move |x| image.get(x, y));
+    let lit = (0..image.height)
+        .filter(|&y| row(y).all(|color| color != Color::zero()))
+        .count() as u32;
+    let black = (0..image.height)
+        .filter(|&y| row(y).all(|color| color == Color::zero()))
+        .count() as u32;
+    (lit, lit + black == image.height)
+}
+
+#[test]
+fn a_stopped_render_keeps_the_tiles_it_finished() {
+    let stop = AtomicBool::new(false);
+    let (image, stats) = render_stoppable(&wall(), &settings(), &stop, |_| {
+        stop.store(true, Ordering::SeqCst);
+        true
+    })
+    .unwrap();
+    let window = settings().window();
+    let mut lit = 0;
+    for tile in tiles(window, TileOrder::Scanline, 0) {
+        let colors: Vec<Color> = (tile.y..tile.y + tile.height)
+            .flat_map(|y| (tile.x..tile.x + tile.width).map(move |x| (x, y)))
+            .map(|(x, y)| image.get(x, y))
+            .collect();
+        if colors.iter().all(|&color| color != Color::zero()) {
+            lit += colors.len() as u64;
+        } else {
+            assert!(colors.iter().all(|&color| color == Color::zero()));
+        }
+    }
+    assert!(lit > 0 && lit < 64 * 4096, "{}", lit);
+    // Every ray went into a tile that was finished.
+    assert_eq!(stats.camera_rays, 2 * lit);
+
+    let dir = std::env::temp_dir().join(format!("interrupt-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("partial.png");
+    image.write_png(&path).unwrap();
+    let read = Image::read_png(&path).unwrap();
+    assert_eq!((read.width, read.height), (64, 4096));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn a_stopped_progressive_render_only_hands_over_whole_passes() {
+    let stop = AtomicBool::new(false);
+    let settings = RenderSettings {
+        spp: 1,
+        ..settings()
+    };
+    let black = Image::new(settings.width, settings.height);
+    let mut passes = Vec::new();
+    let first_done = AtomicBool::new(false);
+    let mut looks = 0;
+    let (image, _) = render_progressive_from(
+        &wall(),
+        &settings,
+        0..3,
+        black,
+        &stop,
+        |done, image| {
+            assert_eq!(rows_done(image), (4096, true));
+            passes.push(done);
+            first_done.store(true, Ordering::SeqCst);
+            Ok(())
+        },
+        |_| {
+            looks += 1;
+            // Stops part way into the second pass.
+            if first_done.load(Ordering::SeqCst) {
+                stop.store(true, Ordering::SeqCst);
+            }
+            true
+        },
+    )
+    .unwrap();
+    assert_eq!(passes, vec![1]);
+    assert!(looks > 1);
+    assert_eq!(rows_done(&image).0, 4096);
+}
+
+#[cfg(unix)]
+#[test]
+fn ctrl_c_writes_out_the_partial_image() {
+    use std::io::{BufRead, BufReader, Read};
+    use std::process::{Command, Stdio};
+    use std::thread;
+    use std::time::Duration;
+
+    let dir = std::env::temp_dir().join(format!("interrupt-binary-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_basic-raytracer"))
+        .args(["--spp", "4096", "--output", "partial.png"])
+        .current_dir(&dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    assert_eq!(line, "Using the built-in demo scene\n");
+    // Gives it time to start on the rows.
+    thread::sleep(Duration::from_millis(500));
+    let kill = Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(kill.success());
+    let mut rest = String::new();
+    stdout.read_to_string(&mut rest).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(130));
+    assert!(rest.contains("% of the way through"), "{}", rest);
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("cancelled"));
+    let image = Image::read_png(&dir.join("partial.png")).unwrap();
+    assert_eq!((image.width, image.height), (640, 480));
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,192 @@
+use std::path::Path;
+
+use basic_raytracer::animation::{Easing, Frame, Key, Keyframes, Pose};
+use basic_raytracer::quaternion::Quaternion;
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene::Scene;
+use basic_raytracer::scene_file;
+use basic_raytracer::vector::Vector;
+
+/// How near a pose must be to the one expected; headings are in degrees, so
+/// `f32` math is off by more than its epsilon.
+#[cfg(not(feature = "f32"))]
+const TOLERANCE: Scalar = 1e-9;
+#[cfg(feature = "f32")]
+const TOLERANCE: Scalar = 1e-4;
+
+fn at_time(scene: &mut Scene, index: usize, count: usize) {
+    scene.set_frame(Frame {
+        index,
+        count,
+        fps: 24.0,
+    });
+}
+
+/// The middle of `scene.objects[index]`'s bounds.
+fn center(scene: &Scene, index: usize) -> Vector {
+    let bounds = scene.objects[index].shape.bounds().unwrap();
+    0.5 * (bounds.min + bounds.max)
+}
+
+fn close(a: Vector, b: Vector) -> bool {
+    (a - b).len() < TOLERANCE
+}
+
+fn turn(degrees: Scalar) -> Pose {
+    Pose {
+        rotate: Quaternion::from_axis_angle(Vector::new(0.0, 1.0, 0.0), degrees.to_radians()),
+        ..Pose::identity()
+    }
+}
+
+/// Where `poses` take the point one unit along x at `time`, as an angle in
+/// degrees about the y axis.
+fn heading(poses: &Keyframes<Pose>, time: Scalar) -> Scalar {
+    let point = poses
+        .at(time)
+        .to_matrix()
+        .transform_point(Vector::new(1.0, 0.0, 0.0));
+    (-point.z).atan2(point.x).to_degrees()
+}
+
+#[test]
+fn linear_keys_land_exactly_halfway() {
+    let poses = Keyframes::new(vec![
+        (
+            0.0,
+            Pose {
+                translate: Vector::new(-1.0, 2.0, 0.0),
+                ..Pose::identity()
+            },
+        ),
+        (
+            1.0,
+            Pose {
+                translate: Vector::new(3.0, 2.0, -4.0),
+                scale: Vector::new(3.0, 3.0, 3.0),
+                ..Pose::identity()
+            },
+        ),
+    ]);
+    let halfway = poses.at(0.5);
+    assert_eq!(halfway.translate, Vector::new(1.0, 2.0, -2.0));
+    assert_eq!(halfway.scale, Vector::new(2.0, 2.0, 2.0));
+    assert_eq!(poses.at(-3.0), poses.keys()[0].value);
+    assert_eq!(poses.at(7.0), poses.keys()[1].value);
+
+    let mut scene = scene_file::parse(
+        r#"{"spheres": [{"center": [0, 0, 0], "radius": 1,
+              "material": {"type": "lambertian", "albedo": [1, 1, 1]},
+              "transform": [{"time": 0, "translate": [-1, 0, 0]},
+                            {"time": 1, "translate": [3, 0, 0]}]}]}"#,
+        Path::new(""),
+    )
+    .unwrap();
+    // Before any frame is set, the scene is at the first key.
+    assert!(close(center(&scene, 0), Vector::new(-1.0, 0.0, 0.0)));
+    at_time(&mut scene, 1, 3);
+    assert!(close(center(&scene, 0), Vector::new(1.0, 0.0, 0.0)));
+    at_time(&mut scene, 2, 3);
+    assert!(close(center(&scene, 0), Vector::new(3.0, 0.0, 0.0)));
+}
+
+#[test]
+fn rotations_take_the_shorter_arc() {
+    // Three quarters of a turn one way is a quarter turn the other.
+    let poses = Keyframes::new(vec![(0.0, turn(0.0)), (1.0, turn(270.0))]);
+    assert!((heading(&poses, 0.5) + 45.0).abs() < TOLERANCE);
+    assert!((heading(&poses, 1.0) + 90.0).abs() < TOLERANCE);
+
+    // Just past a half turn, it swings back through -90°, not on through 90°.
+    let poses = Keyframes::new(vec![(0.0, turn(0.0)), (1.0, turn(190.0))]);
+    assert!((heading(&poses, 0.5) + 85.0).abs() < TOLERANCE);
+    let poses = Keyframes::new(vec![(0.0, turn(0.0)), (1.0, turn(170.0))]);
+    assert!((heading(&poses, 0.5) - 85.0).abs() < TOLERANCE);
+
+    // Each step of the way turns by the same angle.
+    let poses = Keyframes::new(vec![(0.0, turn(10.0)), (1.0, turn(130.0))]);
+    for step in 0..=4 {
+        let expected = 10.0 + 30.0 * step as Scalar;
+        assert!((heading(&poses, step as Scalar / 4.0) - expected).abs() < TOLERANCE);
+    }
+}
+
+#[test]
+fn smooth_keys_ease_in_and_out() {
+    let keys = Keyframes::from_keys(vec![
+        Key {
+            time: 0.0,
+            value: 0.0,
+            easing: Easing::Smooth,
+        },
+        Key {
+            time: 1.0,
+            value: 1.0,
+            easing: Easing::Linear,
+        },
+        Key {
+            time: 2.0,
+            value: 2.0,
+            easing: Easing::Linear,
+        },
+    ]);
+    assert_eq!(keys.at(0.25), 0.15625);
+    assert_eq!(keys.at(0.5), 0.5);
+    assert_eq!(keys.at(0.75), 0.84375);
+    // The easing belongs to the key a stretch starts from.
+    assert_eq!(keys.at(1.25), 1.25);
+
+    let scene = scene_file::parse(
+        r#"{"camera": {"position": [0, 0, 5], "look_at": [0, 0, 0], "fov": 40,
+              "animation": [{"time": 0, "interpolation": "smooth"},
+                            {"time": 1, "fov": 60}]}}"#,
+        Path::new(""),
+    )
+    .unwrap();
+    let camera = scene.animation.camera.as_ref().unwrap();
+    assert_eq!(camera.keys()[0].easing, Easing::Smooth);
+    assert_eq!(camera.at(0.25).fov, 40.0 + 20.0 * 0.15625);
+
+    let error = scene_file::parse(
+        r#"{"camera": {"position": [0, 0, 5], "look_at": [0, 0, 0], "fov": 40,
+              "animation": [{"time": 0, "interpolation": "cubic"}]}}"#,
+        Path::new(""),
+    );
+    assert_eq!(
+        error.err().unwrap(),
+        "camera.animation[0].interpolation: unknown interpolation \"cubic\""
+    );
+}
+
+#[test]
+fn keyframed_nodes_move_everything_under_them() {
+    let mut scene = scene_file::parse(
+        r#"{
+            "nodes": [{
+              "transform": [{"time": 0}, {"time": 1, "rotate_deg": [0, 90, 0]}],
+              "spheres": [{"center": [0, 0, 0], "radius": 0.5,
+                "material": {"type": "lambertian", "albedo": [1, 1, 1]},
+                "transform": {"translate": [2, 0, 0]}}],
+              "children": [{
+                "transform": {"translate": [0, 1, 0]},
+                "boxes": [{"min": [-0.5, -0.5, -0.5], "max": [0.5, 0.5, 0.5],
+                  "material": {"type": "lambertian", "albedo": [1, 1, 1]},
+                  "transform": [{"time": 0, "translate": [2, 0, 0]},
+                                {"time": 1, "translate": [4, 0, 0]}]}]
+              }]
+            }],
+            "spheres": [{"center": [0, 0, -3], "radius": 1,
+              "material": {"type": "lambertian", "albedo": [1, 1, 1]}}]}"#,
+        Path::new(""),
+    )
+    .unwrap();
+    // Only the objects under a keyframed transform move.
+    assert_eq!(scene.animation.objects.len(), 2);
+    at_time(&mut scene, 1, 2);
+    assert!(close(center(&scene, 0), Vector::new(0.0, 0.0, -3.0)));
+    assert!(close(center(&scene, 1), Vector::new(0.0, 0.0, -2.0)));
+    assert!(close(center(&scene, 2), Vector::new(0.0, 1.0, -4.0)));
+    at_time(&mut scene, 0, 2);
+    assert!(close(center(&scene, 1), Vector::new(2.0, 0.0, 0.0)));
+    assert!(close(center(&scene, 2), Vector::new(2.0, 1.0, 0.0)));
+}
@@ -0,0 +1,166 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::light::PointLight;
+use basic_raytracer::material::Lambertian;
+use basic_raytracer::render::{render, IntegratorKind, RenderSettings};
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene::{LightLinks, Scene};
+use basic_raytracer::scene_file;
+use basic_raytracer::shapes::{Plane, Sphere};
+use basic_raytracer::vector::{Color, Vector};
+
+const SPHERES: [(&str, Scalar); 2] = [("foreground", -1.0), ("background", 1.0)];
+
+/// A floor lit from above, with those of the foreground and background
+/// spheres in `layers`, each in its own layer with a light of its own. The
+/// floor isn't lit by the spheres' lights.
+fn spheres(layers: &[&str]) -> Scene {
+    let mut scene = Scene::new(Camera::new(
+        Vector::new(0.0, 3.0, 5.0),
+        Vector::new(0.0, 0.5, 0.0),
+        45.0,
+    ));
+    let white = Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.8)));
+    let floor = scene.add(
+        Plane::new(Vector::zero(), Vector::new(0.0, 1.0, 0.0)),
+        white.clone(),
+    );
+    scene.add_light(PointLight::new(
+        Vector::new(0.0, 6.0, 0.0),
+        Color::new(1.0, 1.0, 1.0),
+        40.0,
+    ));
+    for &(layer, x) in SPHERES.iter().filter(|(layer, _)| layers.contains(layer)) {
+        let sphere = scene.add(Sphere::new(Vector::new(x, 1.0, 0.0), 0.5), white.clone());
+        let light = scene.add_light(PointLight::new(
+            Vector::new(3.0 * x, 2.0, 2.0),
+            Color::new(1.0, 1.0, 1.0),
+            5.0,
+        ));
+        scene.objects[sphere].layers = vec![layer.to_string()];
+        scene.objects[sphere].light_links = LightLinks::only(vec![0, light]);
+        scene.light_layers[light] = vec![layer.to_string()];
+        scene.objects[floor].light_links.exclude.push(light);
+    }
+    scene
+}
+
+#[test]
+fn a_layer_renders_as_if_the_rest_were_never_there() {
+    let scene = spheres(&["foreground", "background"]);
+    assert_eq!(
+        scene.layer_names(),
+        vec!["background", "default", "foreground"]
+    );
+    let settings = RenderSettings {
+        width: 40,
+        height: 30,
+        integrator: IntegratorKind::Whitted,
+        ..RenderSettings::default()
+    };
+    let (everything, _) = render(&scene, &settings).unwrap();
+    for &(layer, x) in &SPHERES {
+        let layered = scene.layer(&["default", layer]);
+        assert_eq!(layered.objects.len(), 2);
+        assert_eq!(layered.lights.len(), 2);
+        let (image, _) = render(&layered, &settings).unwrap();
+        let (alone, _) = render(&spheres(&[layer]), &settings).unwrap();
+        assert!(image.pixels == alone.pixels, "{}", layer);
+        assert!(image.pixels != everything.pixels, "{}", layer);
+
+        // Nor does the other sphere shadow the floor.
+        let under = Vector::new(-x, 0.0, 0.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+        assert!(scene.occluded(under, up, 6.0));
+        assert!(!layered.occluded(under, up, 6.0));
+    }
+
+    // The floor's links to the spheres' lights go with them.
+    let floor = scene.layer(&["default"]);
+    assert_eq!((floor.objects.len(), floor.lights.len()), (1, 1));
+    assert_eq!(floor.objects[0].light_links.exclude, Vec::<usize>::new());
+    assert!(scene.layer(&[]).objects.is_empty());
+}
+
+#[test]
+fn scene_files_put_objects_and_lights_in_layers() {
+    let scene = scene_file::parse(
+        r#"{"spheres": [
+              {"center": [0, 0, 0], "radius": 1, "layers": ["foreground", "hero"],
+               "material": {"type": "lambertian", "albedo": [1, 1, 1]}},
+              {"center": [0, 0, -5], "radius": 1,
+               "material": {"type": "lambertian", "albedo": [1, 1, 1]}}],
+            "quads": [{"corner": [0, 5, 0], "u": [1, 0, 0], "v": [0, 0, 1],
+               "layers": ["lights"],
+               "material": {"type": "emissive", "radiance": [1, 1, 1]}}],
+            "lights": [{"type": "point", "position": [0, 5, 0], "color": [1, 1, 1],
+               "intensity": 1, "layers": ["background"]}]}"#,
+        Path::new(""),
+    )
+    .unwrap();
+    assert_eq!(scene.objects[0].layers, vec!["foreground", "hero"]);
+    assert_eq!(scene.objects[1].layers, vec!["default"]);
+    // The quad's area light goes with it.
+    assert_eq!(scene.light_layers, vec![vec!["background"], vec!["lights"]]);
+    let lights = scene.layer(&["lights"]);
+    assert_eq!(lights.objects[0].light, Some(0));
+    assert_eq!(lights.lights.len(), 1);
+
+    let error = scene_file::parse(
+        r#"{"lights": [{"type": "point", "position": [0, 5, 0], "color": [1, 1, 1],
+               "intensity": 1, "layers": "background"}]}"#,
+        Path::new(""),
+    );
+    assert_eq!(
+        error.err().unwrap(),
+        "lights[0].layers: expected an array, found a string"
+    );
+}
+
+#[test]
+fn the_binary_renders_every_layer_to_its_own_file() {
+    let dir = std::env::temp_dir().join(format!("layers-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("scene.json"),
+        r#"{"camera": {"position": [0, 0, 5], "look_at": [0, 0, 0], "fov": 40},
+            "spheres": [{"center": [0, 0, 0], "radius": 1, "layers": ["hero"],
+              "material": {"type": "lambertian", "albedo": [1, 1, 1]}}],
+            "lights": [{"type": "point", "position": [0, 5, 5], "color": [1, 1, 1],
+              "intensity": 10}]}"#,
+    )
+    .unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_basic-raytracer"))
+            .args(["--scene", "scene.json", "--spp", "1"])
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .unwrap()
+    };
+
+    assert!(run(&["--all-layers", "--output", "out.png"])
+        .status
+        .success());
+    assert!(dir.join("out-default.png").exists());
+    assert!(dir.join("out-hero.png").exists());
+    assert!(run(&["--all-layers", "--output", "{layer}.png"])
+        .status
+        .success());
+    assert!(dir.join("hero.png").exists());
+
+    let output = run(&["--layer", "villain"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "error: invalid render settings: no object or light is in layer \"villain\"\n"
+    );
+    let output = run(&["--layer", "hero", "--all-layers"]);
+    assert_eq!(output.status.code(), Some(2));
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,177 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::light::PointLight;
+use basic_raytracer::material::Lambertian;
+use basic_raytracer::ray::EPSILON;
+use basic_raytracer::render::{render, IntegratorKind, RenderSettings};
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene::{LightLinks, Scene};
+use basic_raytracer::scene_file;
+use basic_raytracer::shapes::{Plane, Sphere};
+use basic_raytracer::vector::{Color, Vector};
+
+/// Two spheres side by side on a floor, with a warm light over the left one
+/// and a cool light over the right, at the given intensities.
+fn two_spheres(left: Scalar, right: Scalar) -> Scene {
+    let mut scene = Scene::new(Camera::new(
+        Vector::new(0.0, 1.0, 5.0),
+        Vector::new(0.0, 0.5, 0.0),
+        45.0,
+    ));
+    let white = Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.8)));
+    scene.add(Sphere::new(Vector::new(-1.1, 0.5, 0.0), 0.5), white.clone());
+    scene.add(Sphere::new(Vector::new(1.1, 0.5, 0.0), 0.5), white.clone());
+    scene.add(
+        Plane::new(Vector::zero(), Vector::new(0.0, 1.0, 0.0)),
+        white,
+    );
+    scene.add_light(PointLight::new(
+        Vector::new(-2.0, 3.0, 2.0),
+        Color::new(1.0, 0.6, 0.3),
+        left,
+    ));
+    scene.add_light(PointLight::new(
+        Vector::new(2.0, 3.0, 2.0),
+        Color::new(0.3, 0.6, 1.0),
+        right,
+    ));
+    scene
+}
+
+#[test]
+fn each_sphere_is_shaded_by_just_its_own_light() {
+    let mut linked = two_spheres(20.0, 20.0);
+    linked.objects[0].light_links = LightLinks::only(vec![0]);
+    linked.objects[1].light_links = LightLinks::except(vec![0]);
+    // Turning a light off rather than removing it keeps the path tracer's
+    // choice of light, and so its random numbers, the same.
+    let (left_only, right_only) = (two_spheres(20.0, 0.0), two_spheres(0.0, 20.0));
+    for &integrator in &[IntegratorKind::Whitted, IntegratorKind::Path] {
+        let settings = RenderSettings {
+            width: 48,
+            height: 32,
+            integrator,
+            max_depth: 0,
+            russian_roulette: None,
+            ..RenderSettings::default()
+        };
+        let (image, _) = render(&linked, &settings).unwrap();
+        let (left, _) = render(&left_only, &settings).unwrap();
+        let (right, _) = render(&right_only, &settings).unwrap();
+        let aspect = settings.width as Scalar / settings.height as Scalar;
+        let mut seen = [0, 0];
+        for y in 0..settings.height {
+            for x in 0..settings.width {
+                let ray = linked.camera.ray(
+                    (x as Scalar + 0.5) / settings.width as Scalar,
+                    (y as Scalar + 0.5) / settings.height as Scalar,
+                    aspect,
+                );
+                let hit = match linked.intersect(&ray, EPSILON, Scalar::INFINITY) {
+                    Some(hit) => hit,
+                    None => continue,
+                };
+                let sphere = linked
+                    .objects
+                    .iter()
+                    .take(2)
+                    .position(|object| std::ptr::eq(object, hit.object));
+                let expected = match sphere {
+                    Some(0) => left.get(x, y),
+                    Some(_) => right.get(x, y),
+                    // The floor is lit by both.
+                    None => continue,
+                };
+                assert_eq!(
+                    image.get(x, y),
+                    expected,
+                    "{:?} at ({}, {})",
+                    integrator,
+                    x,
+                    y
+                );
+                if expected != Color::zero() {
+                    seen[sphere.unwrap()] += 1;
+                }
+            }
+        }
+        assert!(seen[0] > 20 && seen[1] > 20, "{:?}", seen);
+    }
+}
+
+#[test]
+fn unlinked_objects_can_stop_casting_shadows() {
+    let mut scene = two_spheres(20.0, 20.0);
+    scene.objects[0].light_links = LightLinks::except(vec![1]);
+    // A point on the floor behind the left sphere as seen from the right
+    // light.
+    let light = Vector::new(2.0, 3.0, 2.0);
+    let sphere = Vector::new(-1.1, 0.5, 0.0);
+    let floor = light + (light.y / (light.y - sphere.y)) * (sphere - light);
+    let offset = light - floor;
+    let direction = (1.0 / offset.len()) * offset;
+    assert!(scene.shadowed(1, floor, direction, offset.len()));
+    scene.objects[0].light_links.shadows_unlinked = false;
+    assert!(!scene.shadowed(1, floor, direction, offset.len()));
+    // Other rays still see it.
+    assert!(scene.occluded(floor, direction, offset.len()));
+    assert!(LightLinks::default().lit_by(7));
+}
+
+#[test]
+fn scene_files_link_lights_by_name_or_index() {
+    let parse = |links: &str| {
+        scene_file::parse(
+            &format!(
+                r#"{{"spheres": [{{"center": [0, 0, 0], "radius": 1,
+                    "material": {{"type": "lambertian", "albedo": [1, 1, 1]}},
+                    "light_links": {}}}],
+                  "lights": [
+                    {{"type": "point", "name": "key", "position": [0, 5, 0],
+                      "color": [1, 1, 1], "intensity": 1}},
+                    {{"type": "point", "name": "rim", "position": [0, 5, -5],
+                      "color": [1, 1, 1], "intensity": 1}},
+                    {{"type": "point", "position": [5, 5, 0],
+                      "color": [1, 1, 1], "intensity": 1}}]}}"#,
+                links
+            ),
+            Path::new(""),
+        )
+    };
+    let scene = parse(r#"{"include": ["rim", 2], "exclude": [2], "shadows": false}"#).unwrap();
+    let links = &scene.objects[0].light_links;
+    assert_eq!(links.include, Some(vec![1, 2]));
+    assert_eq!(links.exclude, vec![2]);
+    assert!(!links.shadows_unlinked);
+    assert!(links.lit_by(1) && !links.lit_by(0) && !links.lit_by(2));
+
+    let scene = parse(r#"{"exclude": ["key"]}"#).unwrap();
+    let links = &scene.objects[0].light_links;
+    assert_eq!(links.include, None);
+    assert!(links.shadows_unlinked);
+
+    assert_eq!(
+        parse(r#"{"include": ["fill"]}"#).err().unwrap(),
+        "spheres[0].light_links.include[0]: no light named \"fill\""
+    );
+    assert_eq!(
+        parse(r#"{"exclude": [3]}"#).err().unwrap(),
+        "spheres[0].light_links.exclude[0]: there are only 3 lights in \"lights\""
+    );
+    assert_eq!(
+        parse(r#"{"only": [0]}"#).err().unwrap(),
+        "spheres[0].light_links: unknown member \"only\""
+    );
+    let error = scene_file::parse(
+        r#"{"lights": [
+            {"type": "point", "name": "key", "position": [0, 5, 0], "color": [1, 1, 1], "intensity": 1},
+            {"type": "point", "name": "key", "position": [0, 5, 0], "color": [1, 1, 1], "intensity": 1}]}"#,
+        Path::new(""),
+    );
+    assert_eq!(
+        error.err().unwrap(),
+        "lights[1].name: light \"key\" is already defined"
+    );
+}
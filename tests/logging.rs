@@ -0,0 +1,159 @@
+pub mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+use basic_raytracer::json;
+use basic_raytracer::scene_file;
+use common::scratch;
+
+/// A square, and a triangle with two corners in the same place.
+const MESH: &str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\nf 1 2 2\n";
+
+fn scene(dir: &Path) -> PathBuf {
+    fs::write(dir.join("mesh.obj"), MESH).unwrap();
+    let scene = dir.join("scene.json");
+    fs::write(
+        &scene,
+        r#"{"meshes": [{"path": "mesh.obj", "material": {"type": "lambertian",
+            "albedo": {"type": "image", "path": "missing.png"}}}]}"#,
+    )
+    .unwrap();
+    scene
+}
+
+/// Keeps every message it's handed, with its level.
+struct Capture(Arc<Mutex<Vec<(Level, String)>>>);
+
+impl Log for Capture {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let message = (record.level(), record.args().to_string());
+        self.0.lock().unwrap().push(message);
+    }
+
+    fn flush(&self) {}
+}
+
+#[test]
+fn loading_a_mesh_warns_once_of_its_degenerate_triangles() {
+    let dir = scratch("capture");
+    let scene = scene(&dir);
+    let messages = Arc::new(Mutex::new(Vec::new()));
+    log::set_logger(Box::leak(Box::new(Capture(messages.clone())))).unwrap();
+    log::set_max_level(LevelFilter::Trace);
+    let loaded = scene_file::load(&scene).unwrap();
+    assert_eq!(loaded.objects.len(), 1);
+
+    let messages = messages.lock().unwrap();
+    let mesh = dir.join("mesh.obj").display().to_string();
+    let warnings: Vec<&String> = messages
+        .iter()
+        .filter(|(level, message)| *level == Level::Warn && message.contains(&mesh))
+        .map(|(_, message)| message)
+        .collect();
+    assert_eq!(
+        warnings,
+        [&format!("{}: left out 1 degenerate triangle", mesh)]
+    );
+    let texture = dir.join("missing.png").display().to_string();
+    assert!(
+        messages.iter().any(|(level, message)| *level == Level::Warn
+            && message == &format!("{}: no such texture, so it's magenta instead", texture)),
+        "{:?}",
+        messages
+    );
+}
+
+#[test]
+fn verbosity_flags_and_rust_log_say_how_much_the_binary_says() {
+    let dir = scratch("binary");
+    let scene = scene(&dir);
+    let scene = scene.to_str().unwrap();
+    let run = |args: &[&str], rust_log: Option<&str>| {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_basic-raytracer"));
+        command
+            .args([
+                "--width", "16", "--height", "12", "--spp", "1", "--scene", scene,
+            ])
+            .args(args)
+            .current_dir(&dir)
+            .env_remove("RUST_LOG");
+        if let Some(spec) = rust_log {
+            command.env("RUST_LOG", spec);
+        }
+        let output = command.output().unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        (
+            String::from_utf8(output.stdout).unwrap(),
+            String::from_utf8(output.stderr).unwrap(),
+        )
+    };
+    let tiles = |stderr: &str| {
+        stderr
+            .lines()
+            .filter(|l| l.starts_with("debug: Tile "))
+            .count()
+    };
+
+    let (stdout, stderr) = run(&[], None);
+    assert!(stdout.contains(": 1 object and 0 lights"), "{}", stdout);
+    assert!(
+        stdout.contains("Rendering 16x12 with 1 sample a pixel"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("Raytraced output.png successfully!"));
+    assert_eq!(tiles(&stderr), 0);
+    assert_eq!(stderr.matches("warning: ").count(), 2, "{}", stderr);
+
+    let (stdout, stderr) = run(&["-q"], None);
+    assert_eq!(stdout, "");
+    assert_eq!(stderr.matches("warning: ").count(), 2, "{}", stderr);
+
+    let (_, stderr) = run(&["-v"], None);
+    assert!(tiles(&stderr) > 0, "{}", stderr);
+    assert!(!stderr.contains("trace: "));
+    let (_, stderr) = run(&[], Some("basic_raytracer::render=debug"));
+    assert!(tiles(&stderr) > 0, "{}", stderr);
+    // The longest prefix listed says, and a prefix only counts up to a `::`.
+    let (_, stderr) = run(&[], Some("DEBUG,basic_raytracer::render=warn"));
+    assert_eq!(tiles(&stderr), 0, "{}", stderr);
+    let (_, stderr) = run(&[], Some("basic_raytracer::rend=debug"));
+    assert_eq!(tiles(&stderr), 0, "{}", stderr);
+    // Without a level of its own, everything else shows errors alone.
+    let (stdout, stderr) = run(&[], Some("basic_raytracer::bvh=debug"));
+    assert_eq!(
+        (stdout.as_str(), stderr.matches("warning: ").count()),
+        ("", 0)
+    );
+    let (stdout, stderr) = run(&[], Some("off"));
+    assert_eq!((stdout.as_str(), stderr.as_str()), ("", ""));
+    let (stdout, stderr) = run(&[], Some("warn,render=loud"));
+    assert!(
+        stderr.starts_with("warning: ignoring RUST_LOG: unknown log level \"loud\"\n"),
+        "{}",
+        stderr
+    );
+    assert!(stdout.contains("Raytraced output.png successfully!"));
+    // The flags go over RUST_LOG.
+    let (stdout, stderr) = run(&["-q"], Some("debug"));
+    assert_eq!((stdout.as_str(), tiles(&stderr)), ("", 0));
+
+    // Warnings are counted into the report.
+    run(&["-q", "--report", "report.json"], None);
+    let report = json::parse(&fs::read_to_string(dir.join("report.json")).unwrap()).unwrap();
+    let warnings = report.get("warnings").unwrap().as_array().unwrap();
+    assert_eq!(warnings.len(), 2);
+    assert!(warnings[0]
+        .as_str()
+        .unwrap()
+        .ends_with("left out 1 degenerate triangle"));
+}
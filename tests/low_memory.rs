@@ -0,0 +1,149 @@
+use std::convert::TryInto;
+use std::fs;
+use std::mem;
+use std::process::Command;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::image::{self, Image};
+use basic_raytracer::light::PointLight;
+use basic_raytracer::material::Lambertian;
+use basic_raytracer::render::{render, render_streamed, IntegratorKind, RenderSettings};
+use basic_raytracer::scene::Scene;
+use basic_raytracer::shapes::{Plane, Sphere};
+use basic_raytracer::vector::{Color, Vector};
+
+fn scene() -> Scene {
+    let mut scene = Scene::new(Camera::new(
+        Vector::new(0.0, 0.5, 6.0),
+        Vector::zero(),
+        50.0,
+    ));
+    scene.add(
+        Plane::new(Vector::new(0.0, -1.0, 0.0), Vector::new(0.0, 1.0, 0.0)),
+        Arc::new(Lambertian::new(Color::new(0.6, 0.6, 0.6))),
+    );
+    scene.add(
+        Sphere::new(Vector::zero(), 1.0),
+        Arc::new(Lambertian::new(Color::new(0.8, 0.3, 0.2))),
+    );
+    scene.add_light(PointLight::new(
+        Vector::new(2.0, 4.0, 3.0),
+        Color::new(1.0, 1.0, 1.0),
+        40.0,
+    ));
+    scene
+}
+
+/// The chunks of a PNG file, by type, in the order they come.
+fn chunks(bytes: &[u8]) -> Vec<(String, &[u8])> {
+    let mut chunks = Vec::new();
+    let mut rest = &bytes[8..];
+    while !rest.is_empty() {
+        let length = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+        let kind = String::from_utf8_lossy(&rest[4..8]).into_owned();
+        chunks.push((kind, &rest[8..8 + length]));
+        rest = &rest[12 + length..];
+    }
+    chunks
+}
+
+#[test]
+fn streamed_rows_make_the_same_png() {
+    let dir = std::env::temp_dir().join(format!("low-memory-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let settings = RenderSettings {
+        width: 16,
+        height: 2048,
+        spp: 1,
+        integrator: IntegratorKind::Whitted,
+        ..RenderSettings::default()
+    };
+    let (whole, _) = render(&scene(), &settings).unwrap();
+    whole.write_png(&dir.join("whole.png")).unwrap();
+    let streamed = dir.join("streamed.png");
+    let (_, peak) = image::stream_png(&streamed, 16, 2048, |png| {
+        let stop = AtomicBool::new(false);
+        let (stats, waiting) =
+            render_streamed(&scene(), &settings, &stop, |row| png.write_row(row))?;
+        assert_eq!(stats.camera_rays, 16 * 2048);
+        Ok((stats, waiting + png.buffer_bytes()))
+    })
+    .unwrap();
+    // Far less than the whole image in floats, let alone its 8-bit copy.
+    assert!(peak < 16 * 2048 * 24, "{}", peak);
+
+    let (whole_bytes, streamed_bytes) = (
+        fs::read(dir.join("whole.png")).unwrap(),
+        fs::read(&streamed).unwrap(),
+    );
+    let (whole_chunks, streamed_chunks) = (chunks(&whole_bytes), chunks(&streamed_bytes));
+    let data = |chunks: &[(String, &[u8])]| -> Vec<u8> {
+        chunks
+            .iter()
+            .filter(|(kind, _)| kind == "IDAT")
+            .flat_map(|(_, data)| data.iter().copied())
+            .collect()
+    };
+    // The same compressed stream, however it's cut into chunks.
+    assert!(data(&whole_chunks) == data(&streamed_chunks));
+    assert_eq!(whole_chunks[0], streamed_chunks[0]);
+    assert_eq!(streamed_chunks.last().unwrap().0, "IEND");
+    assert!(
+        Image::read_png(&streamed).unwrap().pixels
+            == Image::read_png(&dir.join("whole.png")).unwrap().pixels
+    );
+
+    let result = image::stream_png(&dir.join("short.png"), 16, 2, |png| {
+        png.write_row(&[Color::zero(); 16])
+    });
+    assert!(result.is_err());
+    let result = image::stream_png(&dir.join("narrow.png"), 16, 1, |png| {
+        png.write_row(&[Color::zero(); 15])
+    });
+    assert!(result.is_err());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn the_binary_reports_the_buffers_it_held() {
+    let dir = std::env::temp_dir().join(format!("low-memory-binary-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_basic-raytracer"))
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .unwrap()
+    };
+    let buffers = |output: &std::process::Output| -> usize {
+        let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+        let line = stdout
+            .lines()
+            .find(|line| line.ends_with("bytes of image buffers at most"))
+            .unwrap()
+            .to_string();
+        line.split(' ').next().unwrap().parse().unwrap()
+    };
+    let normal = run(&["--output", "normal.png"]);
+    let low = run(&["--low-memory", "--output", "low.png"]);
+    assert!(normal.status.success() && low.status.success());
+    assert_eq!(buffers(&normal), 640 * 480 * (mem::size_of::<Color>() + 4));
+    assert!(buffers(&low) < buffers(&normal) / 4);
+    assert!(
+        Image::read_png(&dir.join("low.png")).unwrap().pixels
+            == Image::read_png(&dir.join("normal.png")).unwrap().pixels
+    );
+
+    for flags in [&["--progressive"][..], &["--output", "out.gif"]] {
+        let output = run(&[&["--low-memory"][..], flags].concat());
+        assert_eq!(output.status.code(), Some(2));
+        assert!(String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("--low-memory can't be used"));
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
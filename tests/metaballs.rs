@@ -0,0 +1,137 @@
+use std::path::Path;
+
+use basic_raytracer::ray::Ray;
+use basic_raytracer::sampler::Sampler;
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene_file;
+use basic_raytracer::shapes::{Blob, Metaballs, Shape, Sphere};
+use basic_raytracer::vector::Vector;
+
+/// How near a root the march must find, how near the answers worked out by
+/// hand must be, and how near the few that are exact in `f64`.
+#[cfg(not(feature = "f32"))]
+const ROOT: Scalar = 1e-6;
+#[cfg(feature = "f32")]
+const ROOT: Scalar = 1e-3;
+#[cfg(not(feature = "f32"))]
+const TOLERANCE: Scalar = 1e-9;
+#[cfg(feature = "f32")]
+const TOLERANCE: Scalar = 1e-4;
+#[cfg(not(feature = "f32"))]
+const EXACT: Scalar = 1e-12;
+#[cfg(feature = "f32")]
+const EXACT: Scalar = 1e-5;
+
+#[test]
+fn a_single_blob_is_a_sphere() {
+    let center = Vector::new(0.2, -0.1, 0.3);
+    let blob = Blob::new(center, 2.0, 1.5);
+    let radius = blob.surface_radius(0.6).unwrap();
+    let metaballs = Metaballs::new(vec![blob], 0.6);
+    let sphere = Sphere::new(center, radius);
+    let mut sampler = Sampler::new(1, 0, 0);
+    for _ in 0..200 {
+        let origin = Vector::new(
+            4.0 * sampler.next_scalar() - 2.0,
+            4.0 * sampler.next_scalar() - 2.0,
+            5.0,
+        );
+        let ray = Ray::new(origin, Vector::new(0.0, 0.0, -1.0));
+        let (expected, found) = (
+            sphere.intersect(&ray, 1e-4, Scalar::INFINITY),
+            metaballs.intersect(&ray, 1e-4, Scalar::INFINITY),
+        );
+        assert_eq!(expected.is_some(), found.is_some());
+        if let (Some(expected), Some(found)) = (expected, found) {
+            assert!((expected.t - found.t).abs() < ROOT);
+            assert!((expected.normal - found.normal).len() < ROOT);
+        }
+    }
+    let down = Vector::new(0.0, -1.0, 0.0);
+    let top = metaballs
+        .intersect(
+            &Ray::new(center + Vector::new(0.0, 5.0, 0.0), down),
+            1e-4,
+            Scalar::INFINITY,
+        )
+        .unwrap();
+    assert!((top.normal - Vector::new(0.0, 1.0, 0.0)).len() < EXACT);
+    assert!(top.front_face);
+    let ts: Vec<Scalar> = metaballs
+        .intersect_all(&Ray::new(center, down))
+        .iter()
+        .map(|hit| hit.t)
+        .collect();
+    assert_eq!(ts.len(), 2);
+    assert!((ts[0] + radius).abs() < TOLERANCE && (ts[1] - radius).abs() < TOLERANCE);
+}
+
+#[test]
+fn blobs_merge_as_they_approach() {
+    let pair = |gap: Scalar| {
+        Metaballs::new(
+            vec![
+                Blob::new(Vector::new(-gap / 2.0, 0.0, 0.0), 1.0, 1.0),
+                Blob::new(Vector::new(gap / 2.0, 0.0, 0.0), 1.0, 1.0),
+            ],
+            0.5,
+        )
+    };
+    let along = Ray::new(Vector::new(-5.0, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+    let down = Ray::new(Vector::new(0.0, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+    // Far apart, there are two balls with nothing between them.
+    let apart = pair(2.5);
+    assert_eq!(apart.intersect_all(&along).len(), 4);
+    assert!(apart.intersect(&down, 1e-4, Scalar::INFINITY).is_none());
+    // Closer together, their fields join them with a neck, which thickens
+    // as they come closer still.
+    let (near, nearer) = (pair(1.2), pair(0.8));
+    assert_eq!(near.intersect_all(&along).len(), 2);
+    let (neck, thicker) = (
+        near.intersect(&down, 1e-4, Scalar::INFINITY).unwrap(),
+        nearer.intersect(&down, 1e-4, Scalar::INFINITY).unwrap(),
+    );
+    assert!(neck.point.y > 0.0 && thicker.point.y > neck.point.y);
+    assert!((neck.normal - Vector::new(0.0, 1.0, 0.0)).len() < EXACT);
+    let bounds = near.bounds().unwrap();
+    assert!((bounds.min - Vector::new(-1.6, -1.0, -1.0)).len() < EXACT);
+    assert!((bounds.max - Vector::new(1.6, 1.0, 1.0)).len() < EXACT);
+}
+
+#[test]
+fn scene_files_list_blobs() {
+    let parse = |members: &str| {
+        scene_file::parse(
+            &format!(
+                r#"{{"metaballs": [{{{}, "material": {{"type": "dielectric", "ior": 1.5}}}}]}}"#,
+                members
+            ),
+            Path::new(""),
+        )
+    };
+    let scene = parse(
+        r#""blobs": [{"center": [0, 0, 0], "radius": 1}, {"center": [3, 0, 0], "radius": 1, "weight": 2}],
+            "threshold": 0.25"#,
+    )
+    .unwrap();
+    let right = Vector::new(1.0, 0.0, 0.0);
+    let hit = scene
+        .intersect(
+            &Ray::new(Vector::new(-5.0, 0.0, 0.0), right),
+            1e-4,
+            Scalar::INFINITY,
+        )
+        .unwrap();
+    assert!((hit.record.t - (5.0 - (1.0 - Scalar::cbrt(0.25)).sqrt())).abs() < TOLERANCE);
+
+    assert_eq!(
+        parse(r#""threshold": 0.5"#).err().unwrap(),
+        "metaballs[0]: missing member \"blobs\""
+    );
+    assert_eq!(
+        parse(r#""blobs": [{"center": [0, 0, 0], "radius": 1, "size": 2}]"#)
+            .err()
+            .unwrap(),
+        "metaballs[0].blobs[0]: unknown member \"size\""
+    );
+}
@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::image::Image;
+use basic_raytracer::material::{Glossy, Lambertian};
+use basic_raytracer::render::{render, IntegratorKind, RenderSettings};
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene::Scene;
+use basic_raytracer::shapes::{Plane, Quad, Sphere};
+use basic_raytracer::vector::{Color, Vector};
+
+// The scene from examples/veach_mis.rs.
+fn veach_scene() -> Scene {
+    let camera = Vector::new(0.0, 2.0, 9.0);
+    let mut scene = Scene::new(Camera::new(camera, Vector::new(0.0, 1.2, 0.0), 38.0));
+    scene.add(
+        Plane::new(Vector::new(0.0, 0.0, -4.0), Vector::new(0.0, 0.0, 1.0)),
+        Arc::new(Lambertian::new(Color::new(0.1, 0.1, 0.1))),
+    );
+    for &(x, radius) in &[(-3.0, 0.05), (-1.0, 0.15), (1.0, 0.4), (3.0, 0.9)] {
+        let power = 0.8 / (radius * radius);
+        scene.add_area_light(
+            Sphere::new(Vector::new(x, 3.5, -2.5), radius),
+            Color::new(power, power, power),
+        );
+    }
+    let aim = Vector::new(0.0, 3.5, -2.5);
+    for &(y, z, exponent) in &[
+        (0.0, 0.0, 8.0),
+        (0.7, 0.5, 40.0),
+        (1.3, 1.0, 200.0),
+        (1.8, 1.5, 2000.0),
+    ] {
+        let center = Vector::new(0.0, y, z);
+        let normal = ((camera - center).normalize() + (aim - center).normalize()).normalize();
+        let across = Vector::new(1.0, 0.0, 0.0);
+        let along = normal.cross(across).normalize();
+        let u = 8.0 * across;
+        let v = 0.8 * along;
+        scene.add(
+            Quad::new(center - 0.5 * u - 0.5 * v, u, v),
+            Arc::new(Glossy::new(Color::new(0.7, 0.7, 0.7), exponent)),
+        );
+    }
+    scene
+}
+
+fn rmse(a: &Image, b: &Image) -> Scalar {
+    let clamp = |c: &Color| Color::new(c.x.min(1.0), c.y.min(1.0), c.z.min(1.0));
+    let total: Scalar = a
+        .pixels
+        .iter()
+        .zip(&b.pixels)
+        .map(|(a, b)| {
+            let d = clamp(a) - clamp(b);
+            d * d
+        })
+        .sum();
+    (total / (3.0 * a.pixels.len() as Scalar)).sqrt()
+}
+
+#[test]
+fn mis_is_no_worse_than_either_strategy_alone() {
+    let scene = veach_scene();
+    let settings = RenderSettings {
+        width: 36,
+        height: 24,
+        integrator: IntegratorKind::Path,
+        spp: 16,
+        max_depth: 2,
+        ..RenderSettings::default()
+    };
+    let (reference, _) = render(
+        &scene,
+        &RenderSettings {
+            spp: 1024,
+            seed: 1,
+            ..settings.clone()
+        },
+    )
+    .unwrap();
+    let error = |next_event_estimation, multiple_importance_sampling| {
+        let (image, _) = render(
+            &scene,
+            &RenderSettings {
+                next_event_estimation,
+                multiple_importance_sampling,
+                ..settings.clone()
+            },
+        )
+        .unwrap();
+        rmse(&image, &reference)
+    };
+    let mis = error(true, true);
+    let light = error(true, false);
+    let bsdf = error(false, false);
+    assert!(
+        mis <= light.min(bsdf),
+        "RMSE {} with MIS, {} light sampling only, {} BSDF sampling only",
+        mis,
+        light,
+        bsdf
+    );
+}
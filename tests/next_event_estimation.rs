@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::image::Image;
+use basic_raytracer::material::Lambertian;
+use basic_raytracer::render::{render, IntegratorKind, RenderSettings};
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene::Scene;
+use basic_raytracer::shapes::{Plane, Quad, Sphere};
+use basic_raytracer::vector::{Color, Vector};
+
+fn cornell_box() -> Scene {
+    let mut scene = Scene::new(Camera::new(
+        Vector::new(0.0, 1.0, 3.4),
+        Vector::new(0.0, 1.0, 0.0),
+        45.0,
+    ));
+    let white = Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+    let red = Arc::new(Lambertian::new(Color::new(0.65, 0.05, 0.05)));
+    let green = Arc::new(Lambertian::new(Color::new(0.12, 0.45, 0.15)));
+    let walls = [
+        (Vector::new(0.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0)),
+        (Vector::new(0.0, 2.0, 0.0), Vector::new(0.0, -1.0, 0.0)),
+        (Vector::new(0.0, 0.0, -1.0), Vector::new(0.0, 0.0, 1.0)),
+    ];
+    for &(point, normal) in &walls {
+        scene.add(Plane::new(point, normal), white.clone());
+    }
+    scene.add(
+        Plane::new(Vector::new(-1.0, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0)),
+        red,
+    );
+    scene.add(
+        Plane::new(Vector::new(1.0, 0.0, 0.0), Vector::new(-1.0, 0.0, 0.0)),
+        green,
+    );
+    scene.add(Sphere::new(Vector::new(-0.45, 0.4, -0.3), 0.4), white);
+    scene.add_area_light(
+        Quad::new(
+            Vector::new(-0.15, 1.999, -0.35),
+            Vector::new(0.3, 0.0, 0.0),
+            Vector::new(0.0, 0.0, 0.3),
+        ),
+        Color::new(40.0, 40.0, 40.0),
+    );
+    scene
+}
+
+// Compared on displayable values, so that pixels partially covering the light
+// don't dominate the error.
+fn rmse(a: &Image, b: &Image) -> Scalar {
+    let clamp = |c: &Color| Color::new(c.x.min(1.0), c.y.min(1.0), c.z.min(1.0));
+    let total: Scalar = a
+        .pixels
+        .iter()
+        .zip(&b.pixels)
+        .map(|(a, b)| {
+            let d = clamp(a) - clamp(b);
+            d * d
+        })
+        .sum();
+    (total / (3.0 * a.pixels.len() as Scalar)).sqrt()
+}
+
+#[test]
+fn next_event_estimation_reduces_error_against_reference() {
+    let scene = cornell_box();
+    let settings = RenderSettings {
+        width: 12,
+        height: 12,
+        integrator: IntegratorKind::Path,
+        spp: 64,
+        max_depth: 4,
+        ..RenderSettings::default()
+    };
+    let (reference, _) = render(
+        &scene,
+        &RenderSettings {
+            spp: 2048,
+            seed: 1,
+            ..settings.clone()
+        },
+    )
+    .unwrap();
+    let (with_nee, _) = render(&scene, &settings).unwrap();
+    let (without_nee, _) = render(
+        &scene,
+        &RenderSettings {
+            next_event_estimation: false,
+            ..settings
+        },
+    )
+    .unwrap();
+
+    let error_with = rmse(&with_nee, &reference);
+    let error_without = rmse(&without_nee, &reference);
+    assert!(
+        error_with < 0.3 * error_without,
+        "RMSE {} with NEE, {} without",
+        error_with,
+        error_without
+    );
+}
@@ -0,0 +1,209 @@
+use basic_raytracer::noise::Perlin;
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::texture::{Marble, NoiseTexture, Texture, Wood};
+use basic_raytracer::vector::{Color, Vector};
+
+/// The step across a cell's face, and how much the noise and its slope may
+/// change over it. `f32` noise rounds to about 1e-7, so its step is longer
+/// and the slopes taken over it rougher.
+#[cfg(not(feature = "f32"))]
+const STEP: (Scalar, Scalar, Scalar) = (1e-6, 1e-5, 1e-3);
+#[cfg(feature = "f32")]
+const STEP: (Scalar, Scalar, Scalar) = (1e-3, 1e-2, 5e-2);
+#[cfg(not(feature = "f32"))]
+const TOLERANCE: Scalar = 1e-9;
+#[cfg(feature = "f32")]
+const TOLERANCE: Scalar = 1e-5;
+
+// Points scattered through a few lattice cells, avoiding integer coordinates.
+fn points() -> Vec<Vector> {
+    (0..200)
+        .map(|i| {
+            let i = i as Scalar;
+            Vector::new(
+                (i * 0.731).sin() * 4.3,
+                (i * 1.173).cos() * 3.7,
+                (i * 0.419).sin() * 5.1 + 0.25,
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn continuous_across_cell_boundaries() {
+    let perlin = Perlin::new(7);
+    let (h, jump, kink) = STEP;
+    for point in points() {
+        for axis in 0..3 {
+            let mut boundary = point;
+            let step = match axis {
+                0 => {
+                    boundary.x = boundary.x.round();
+                    Vector::new(h, 0.0, 0.0)
+                }
+                1 => {
+                    boundary.y = boundary.y.round();
+                    Vector::new(0.0, h, 0.0)
+                }
+                _ => {
+                    boundary.z = boundary.z.round();
+                    Vector::new(0.0, 0.0, h)
+                }
+            };
+            let below = perlin.noise(boundary - step);
+            let at = perlin.noise(boundary);
+            let above = perlin.noise(boundary + step);
+            assert!((below - at).abs() < jump, "{:?}", boundary);
+            assert!((above - at).abs() < jump, "{:?}", boundary);
+            // The slope on either side of the face agrees too.
+            let slope_below = (at - perlin.noise(boundary - 2.0 * step)) / h;
+            let slope_above = (perlin.noise(boundary + 2.0 * step) - at) / h;
+            assert!(
+                (slope_below - slope_above).abs() < kink,
+                "{:?}: {} vs {}",
+                boundary,
+                slope_below,
+                slope_above
+            );
+        }
+    }
+}
+
+#[test]
+fn zero_at_lattice_points() {
+    let perlin = Perlin::new(3);
+    for x in -2..3 {
+        for z in -2..3 {
+            let point = Vector::new(x as Scalar, 1.0, z as Scalar);
+            assert_eq!(perlin.noise(point), 0.0);
+        }
+    }
+}
+
+#[test]
+fn same_seed_gives_the_same_field() {
+    let (a, b) = (Perlin::new(11), Perlin::new(11));
+    for point in points() {
+        assert_eq!(a.noise(point), b.noise(point));
+    }
+}
+
+#[test]
+fn different_seeds_give_different_fields() {
+    let (a, b) = (Perlin::new(1), Perlin::new(2));
+    let differences = points()
+        .into_iter()
+        .filter(|&point| (a.noise(point) - b.noise(point)).abs() > 1e-3)
+        .count();
+    assert!(differences > 150, "{} of 200 differ", differences);
+}
+
+#[test]
+fn turbulence_is_bounded() {
+    let perlin = Perlin::new(5);
+    for point in points() {
+        let t = perlin.turbulence(point, 6);
+        assert!((0.0..2.0).contains(&t), "{}", t);
+    }
+}
+
+#[test]
+fn noise_texture_is_gray_in_unit_range() {
+    let texture = NoiseTexture::new(4.0, 9);
+    let mut spread = (Scalar::INFINITY, Scalar::NEG_INFINITY);
+    for point in points() {
+        let color = texture.value(0.0, 0.0, point);
+        assert_eq!(color.x, color.y);
+        assert_eq!(color.y, color.z);
+        assert!((0.0..=1.0).contains(&color.x));
+        spread = (spread.0.min(color.x), spread.1.max(color.x));
+    }
+    assert!(spread.1 - spread.0 > 0.5, "{:?}", spread);
+}
+
+fn wood(scale: Scalar, turbulence: Scalar) -> Wood {
+    Wood::new(
+        scale,
+        Vector::new(0.0, 0.0, 1.0),
+        Vector::new(0.0, 1.0, 0.0),
+        turbulence,
+        4,
+        Color::new(1.0, 1.0, 1.0),
+        Color::zero(),
+    )
+}
+
+/// Distances along `direction` from `start` where the texture's red channel
+/// jumps from dark back to light, that is, where each new ring starts.
+fn ring_starts(
+    texture: &dyn Texture,
+    start: Vector,
+    direction: Vector,
+    length: Scalar,
+) -> Vec<Scalar> {
+    let steps = 20_000;
+    let step = length / steps as Scalar;
+    let mut previous = texture.value(0.0, 0.0, start).x;
+    let mut starts = Vec::new();
+    for i in 1..=steps {
+        let distance = i as Scalar * step;
+        let value = texture.value(0.0, 0.0, start + distance * direction).x;
+        if value - previous > 0.5 {
+            starts.push(distance);
+        }
+        previous = value;
+    }
+    starts
+}
+
+#[test]
+fn wood_ring_period_matches_scale() {
+    let origin = Vector::new(0.0, 0.3, 1.0);
+    let outward = Vector::new(0.6, 0.0, 0.8);
+    for &scale in &[2.0, 5.0] {
+        let starts = ring_starts(&wood(scale, 0.0), origin, outward, 4.1);
+        assert_eq!(starts.len(), (4.1 * scale) as usize, "{:?}", starts);
+        for pair in starts.windows(2) {
+            assert!((pair[1] - pair[0] - 1.0 / scale).abs() < 1e-3, "{:?}", pair);
+        }
+    }
+}
+
+#[test]
+fn turbulent_wood_keeps_the_average_period() {
+    // Turbulence moves each ring by less than a ring width, so crossing 40
+    // rings still finds one new ring per period, give or take the ends.
+    let starts = ring_starts(
+        &wood(4.0, 0.4),
+        Vector::new(0.0, -1.7, 1.0),
+        Vector::new(0.0, 0.0, 1.0),
+        10.0,
+    );
+    assert!((38..=41).contains(&starts.len()), "{}", starts.len());
+}
+
+#[test]
+fn wood_rings_follow_the_axis() {
+    let texture = wood(3.0, 0.0);
+    let along = |y: Scalar| texture.value(0.0, 0.0, Vector::new(0.25, y, 1.4));
+    assert_eq!(along(-3.0), along(8.0));
+}
+
+#[test]
+fn marble_stripes_repeat_along_the_axis() {
+    let marble = Marble::new(
+        2.0,
+        Vector::new(0.0, 0.0, 3.0),
+        0.0,
+        1,
+        Color::new(1.0, 1.0, 1.0),
+        Color::zero(),
+    );
+    let at = |z: Scalar| marble.value(0.0, 0.0, Vector::new(0.4, -0.2, z)).x;
+    for &z in &[0.0, 0.1, 0.37] {
+        assert!((at(z) - at(z + 0.5)).abs() < TOLERANCE);
+    }
+    // Crests are light and troughs dark.
+    assert!((at(0.125) - 1.0).abs() < TOLERANCE);
+    assert!(at(0.375) < TOLERANCE);
+}
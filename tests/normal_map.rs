@@ -0,0 +1,282 @@
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::light::PointLight;
+use basic_raytracer::material::{Lambertian, Material, NormalMapped};
+use basic_raytracer::ray::Ray;
+use basic_raytracer::render::{render, IntegratorKind, RenderSettings};
+use basic_raytracer::sampler::Sampler;
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene::Scene;
+use basic_raytracer::shapes::{
+    Cone, Cuboid, Cylinder, HitRecord, MeshTriangle, Plane, Quad, Shape, Sphere, TriangleMesh,
+};
+use basic_raytracer::texture::SolidColor;
+use basic_raytracer::vector::{Color, Vector};
+
+/// The step across a surface its derivatives are checked over, and how far
+/// off, as a share of it, the surface may be from what they predict.
+#[cfg(not(feature = "f32"))]
+const STEP: (Scalar, Scalar) = (1e-5, 1e-3);
+#[cfg(feature = "f32")]
+const STEP: (Scalar, Scalar) = (1e-2, 1e-2);
+#[cfg(not(feature = "f32"))]
+const TOLERANCE: Scalar = 1e-9;
+#[cfg(feature = "f32")]
+const TOLERANCE: Scalar = 1e-5;
+
+fn flat() -> Box<SolidColor> {
+    Box::new(SolidColor::new(Color::new(0.5, 0.5, 1.0)))
+}
+
+fn scene(map: bool) -> Scene {
+    let mut scene = Scene::new(Camera::new(
+        Vector::new(0.0, 1.0, 4.0),
+        Vector::new(0.0, 0.5, 0.0),
+        45.0,
+    ));
+    let material = |color: Color| -> Arc<dyn Material> {
+        let lambertian = Arc::new(Lambertian::new(color));
+        if map {
+            Arc::new(NormalMapped::new(lambertian, flat()))
+        } else {
+            lambertian
+        }
+    };
+    scene.add(
+        Plane::new(Vector::zero(), Vector::new(0.0, 1.0, 0.0)),
+        material(Color::new(0.5, 0.5, 0.5)),
+    );
+    scene.add(
+        Sphere::new(Vector::new(-0.6, 0.6, 0.0), 0.6),
+        material(Color::new(0.8, 0.3, 0.2)),
+    );
+    scene.add(
+        Cuboid::new(Vector::new(0.3, 0.0, -0.5), Vector::new(1.1, 0.8, 0.3)),
+        material(Color::new(0.2, 0.6, 0.3)),
+    );
+    scene.add_area_light(
+        Quad::new(
+            Vector::new(-0.5, 2.5, -0.5),
+            Vector::new(1.0, 0.0, 0.0),
+            Vector::new(0.0, 0.0, 1.0),
+        ),
+        Color::new(8.0, 8.0, 8.0),
+    );
+    scene.add_light(PointLight::new(
+        Vector::new(2.0, 4.0, 3.0),
+        Color::new(1.0, 1.0, 1.0),
+        20.0,
+    ));
+    scene.background = Color::new(0.2, 0.3, 0.5);
+    scene
+}
+
+#[test]
+fn flat_normal_map_changes_nothing() {
+    for integrator in [IntegratorKind::Path, IntegratorKind::Whitted] {
+        let settings = RenderSettings {
+            width: 32,
+            height: 24,
+            integrator,
+            spp: 4,
+            ..RenderSettings::default()
+        };
+        let (plain, _) = render(&scene(false), &settings).unwrap();
+        let (mapped, _) = render(&scene(true), &settings).unwrap();
+        assert!(plain.pixels == mapped.pixels);
+    }
+}
+
+/// Checks that moving a tiny step across the surface agrees with the hit's
+/// surface derivatives.
+fn assert_derivatives(shape: &dyn Shape, origin: Vector, target: Vector) {
+    let hit_at = |target: Vector| {
+        let ray = Ray::new(origin, (target - origin).normalize());
+        shape.intersect(&ray, 1e-9, Scalar::INFINITY).unwrap()
+    };
+    let hit = hit_at(target);
+    assert!(hit.tangent.len() > 0.0 && hit.bitangent.len() > 0.0);
+    let (h, share) = STEP;
+    for step in [
+        Vector::new(h, 0.0, 0.0),
+        Vector::new(0.0, h, 0.0),
+        Vector::new(0.0, 0.0, h),
+    ] {
+        let other = hit_at(target + step);
+        let moved = other.point - hit.point;
+        let predicted =
+            (other.uv.0 - hit.uv.0) * hit.tangent + (other.uv.1 - hit.uv.1) * hit.bitangent;
+        assert!(
+            (moved - predicted).len() < share * h,
+            "moved {:?}, predicted {:?}",
+            moved,
+            predicted
+        );
+    }
+}
+
+#[test]
+fn surface_derivatives_match_the_uv_mapping() {
+    let eye = Vector::new(0.3, 2.0, 5.0);
+    assert_derivatives(
+        &Sphere::new(Vector::zero(), 1.5),
+        eye,
+        Vector::new(0.2, 0.4, 0.0),
+    );
+    assert_derivatives(
+        &Quad::new(
+            Vector::new(-1.0, -1.0, 0.0),
+            Vector::new(2.0, 0.3, 0.0),
+            Vector::new(0.0, 1.5, 0.5),
+        ),
+        eye,
+        Vector::new(0.1, 0.2, 0.1),
+    );
+    assert_derivatives(
+        &Plane::new(Vector::zero(), Vector::new(0.0, 1.0, 0.2)),
+        eye,
+        Vector::new(0.4, 0.0, -0.3),
+    );
+    let cuboid = Cuboid::new(Vector::new(-1.0, -1.0, -1.0), Vector::new(1.0, 0.5, 2.0));
+    for origin in [
+        Vector::new(5.0, 0.1, 0.2),
+        Vector::new(-5.0, 0.1, 0.2),
+        Vector::new(0.2, 5.0, 0.3),
+        Vector::new(0.2, -5.0, 0.3),
+        Vector::new(0.1, -0.2, 6.0),
+        Vector::new(0.1, -0.2, -6.0),
+    ] {
+        assert_derivatives(&cuboid, origin, Vector::new(0.1, -0.1, 0.4));
+    }
+    let cylinder = Cylinder::new(Vector::new(0.0, -1.0, 0.0), 0.8, 2.0);
+    assert_derivatives(&cylinder, eye, Vector::new(0.1, 0.0, 0.0));
+    assert_derivatives(
+        &cylinder,
+        Vector::new(0.1, 5.0, 0.2),
+        Vector::new(0.2, 0.0, 0.1),
+    );
+    assert_derivatives(
+        &cylinder,
+        Vector::new(0.1, -5.0, 0.2),
+        Vector::new(0.2, 0.0, 0.1),
+    );
+    let cone = Cone::new(Vector::new(0.0, -1.0, 0.0), 1.0, 2.0);
+    assert_derivatives(&cone, eye, Vector::new(0.1, -0.2, 0.0));
+    assert_derivatives(
+        &cone,
+        Vector::new(0.1, -5.0, 0.2),
+        Vector::new(0.2, 0.0, 0.1),
+    );
+}
+
+#[test]
+fn mesh_derivatives_come_from_its_uvs() {
+    // A square split in two, with its texture rotated a quarter turn and
+    // stretched so ∂p/∂u and ∂p/∂v differ from the edges.
+    let mesh = TriangleMesh::new(
+        vec![
+            Vector::new(0.0, 0.0, 0.0),
+            Vector::new(2.0, 0.0, 0.0),
+            Vector::new(2.0, 1.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ],
+        vec![(0.0, 0.0), (0.0, 1.0), (0.5, 1.0), (0.5, 0.0)],
+        vec![
+            MeshTriangle {
+                positions: [0, 1, 2],
+                uvs: Some([0, 1, 2]),
+            },
+            MeshTriangle {
+                positions: [0, 2, 3],
+                uvs: Some([0, 2, 3]),
+            },
+        ],
+    );
+    let ray = Ray::new(Vector::new(1.2, 0.3, 3.0), Vector::new(0.0, 0.0, -1.0));
+    let hit = mesh.intersect(&ray, 1e-9, Scalar::INFINITY).unwrap();
+    assert!((hit.tangent - Vector::new(0.0, 2.0, 0.0)).len() < TOLERANCE);
+    assert!((hit.bitangent - Vector::new(2.0, 0.0, 0.0)).len() < TOLERANCE);
+    assert_derivatives(
+        &mesh,
+        Vector::new(0.5, 0.2, 3.0),
+        Vector::new(1.4, 0.7, 0.0),
+    );
+}
+
+fn tilted_quad() -> (Quad, NormalMapped) {
+    let quad = Quad::new(
+        Vector::new(-1.0, 0.0, 1.0),
+        Vector::new(2.0, 0.0, 0.0),
+        Vector::new(0.0, 0.0, -2.0),
+    );
+    // Bent 60 degrees toward +u.
+    let (sin, cos) = (
+        Scalar::to_radians(60.0).sin(),
+        Scalar::to_radians(60.0).cos(),
+    );
+    let map = SolidColor::new(Color::new(0.5 + 0.5 * sin, 0.5, 0.5 + 0.5 * cos));
+    let material = NormalMapped::new(
+        Arc::new(Lambertian::new(Color::new(1.0, 1.0, 1.0))),
+        Box::new(map),
+    );
+    (quad, material)
+}
+
+fn shaded_hit(quad: &Quad, material: &NormalMapped, ray: &Ray) -> HitRecord {
+    let mut hit = quad.intersect(ray, 1e-9, Scalar::INFINITY).unwrap();
+    material.shade(&mut hit);
+    hit
+}
+
+#[test]
+fn normal_maps_bend_the_shading_normal_only() {
+    let (quad, material) = tilted_quad();
+    let down = Ray::new(Vector::new(0.0, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+    let hit = shaded_hit(&quad, &material, &down);
+    assert_eq!(hit.normal, Vector::new(0.0, 1.0, 0.0));
+    let expected = Vector::new(
+        Scalar::to_radians(60.0).sin(),
+        Scalar::to_radians(60.0).cos(),
+        0.0,
+    );
+    assert!((hit.shading_normal - expected).len() < TOLERANCE);
+
+    // From below, both normals flip together.
+    let up = Ray::new(Vector::new(0.0, -1.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+    let hit = shaded_hit(&quad, &material, &up);
+    assert_eq!(hit.normal, Vector::new(0.0, -1.0, 0.0));
+    assert!((hit.shading_normal + expected).len() < TOLERANCE);
+}
+
+#[test]
+fn bent_normals_do_not_leak_light() {
+    let (quad, material) = tilted_quad();
+    let ray = Ray::new(
+        Vector::new(-0.5, 1.0, 0.0),
+        Vector::new(0.5, -1.0, 0.0).normalize(),
+    );
+    let hit = shaded_hit(&quad, &material, &ray);
+    let wo = -ray.direction;
+    let mut below_shading = 0;
+    for sample in 0..2000 {
+        let mut sampler = Sampler::new(1, 0, sample);
+        if let Some(scatter) = material.scatter(&ray, &hit, &mut sampler) {
+            assert!(scatter.direction * hit.normal > 0.0);
+            assert!(scatter.direction * hit.shading_normal > 0.0);
+        }
+        // Directions above the bent normal but under the surface.
+        let below = Vector::new(
+            1.0,
+            -0.2 - 0.1 * (sample % 5) as Scalar,
+            0.01 * (sample % 7) as Scalar,
+        )
+        .normalize();
+        if below * hit.shading_normal > 0.0 {
+            below_shading += 1;
+            assert_eq!(material.eval(&hit, wo, below), Color::zero());
+            assert_eq!(material.pdf(&hit, wo, below), 0.0);
+        }
+    }
+    assert!(below_shading > 0);
+}
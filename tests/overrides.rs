@@ -0,0 +1,255 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use basic_raytracer::image::Image;
+use basic_raytracer::json::{self, Value};
+use basic_raytracer::overrides::{self, Override, Sweep};
+use basic_raytracer::scene_file;
+
+const SCENE: &str = r#"{
+    "camera": {"position": [0, 1, 5], "look_at": [0, 0, 0], "fov": 50},
+    "spheres": [
+        {"center": [0, 0, 0], "radius": 1,
+         "material": {"type": "lambertian", "albedo": [0.5, 0.5, 0.5]}},
+        {"center": [2, 0, 0], "radius": 0.5,
+         "material": {"type": "lambertian", "albedo": [0.2, 0.2, 0.2]}}
+    ],
+    "lights": [{"type": "point", "name": "key", "position": [0, 5, 0], "color": [1, 1, 1],
+                "intensity": 1}]
+}"#;
+
+/// The scene's values with `sets` made to them.
+fn set(sets: &[&str]) -> Result<Value, String> {
+    let mut value = json::parse(SCENE).unwrap();
+    let overrides: Vec<Override> = sets
+        .iter()
+        .map(|text| Override::parse(text))
+        .collect::<Result<_, _>>()?;
+    overrides::apply_all(&mut value, &overrides)?;
+    Ok(value)
+}
+
+fn at<'a>(value: &'a Value, path: &[&str]) -> &'a Value {
+    path.iter()
+        .fold(value, |value, step| match step.parse::<usize>() {
+            Ok(index) => &value.as_array().unwrap()[index],
+            Err(_) => value.get(step).unwrap(),
+        })
+}
+
+#[test]
+fn overrides_follow_members_and_indices() {
+    let value = set(&[
+        "lights[0].intensity=5.0",
+        "camera.fov=35",
+        "spheres[1].material.albedo=[1,0,0]",
+        "spheres[1].material.type=glossy",
+        "spheres[1].material.exponent=20",
+    ])
+    .unwrap();
+    assert_eq!(
+        at(&value, &["lights", "0", "intensity"]).as_f64(),
+        Some(5.0)
+    );
+    assert_eq!(at(&value, &["camera", "fov"]).as_f64(), Some(35.0));
+    let material = at(&value, &["spheres", "1", "material"]);
+    assert_eq!(
+        material.to_string(),
+        r#"{"type": "glossy", "albedo": [1, 0, 0], "exponent": 20}"#
+    );
+    // The one left alone stays as it was.
+    assert_eq!(
+        at(&value, &["spheres", "0", "material", "albedo"]).to_string(),
+        "[0.5, 0.5, 0.5]"
+    );
+}
+
+#[test]
+fn values_are_coerced_to_what_they_replace() {
+    let value = set(&[
+        "spheres[0].material.albedo=0.25",
+        "lights[0].name=42",
+        "spheres[0].center[1]=-2",
+        "spheres[0].radius=[1]",
+    ])
+    .unwrap();
+    // A number for an array of them sets each one.
+    assert_eq!(
+        at(&value, &["spheres", "0", "material", "albedo"]).to_string(),
+        "[0.25, 0.25, 0.25]"
+    );
+    assert_eq!(at(&value, &["lights", "0", "name"]).as_str(), Some("42"));
+    assert_eq!(
+        at(&value, &["spheres", "0", "center"]).to_string(),
+        "[0, -2, 0]"
+    );
+    // Anything else goes in as it's written, for the scene to check.
+    assert_eq!(at(&value, &["spheres", "0", "radius"]).to_string(), "[1]");
+}
+
+#[test]
+fn bad_paths_say_which_override_and_where() {
+    for (sets, message) in [
+        (
+            &["spheres[5].radius=1"][..],
+            "can't set spheres[5].radius=1: spheres: no item [5] in an array of 2",
+        ),
+        (
+            &["camera.position.x=1"],
+            "can't set camera.position.x=1: camera.position: expected an object, found an array",
+        ),
+        (
+            &["camera.fov=35", "medium.absorption=0.1"],
+            "can't set medium.absorption=0.1: missing member \"medium\"",
+        ),
+        (
+            &["lights.intensity=2"],
+            "can't set lights.intensity=2: lights: expected an object, found an array",
+        ),
+        (&["spheres[0]radius=1"], "invalid path \"spheres[0]radius\""),
+        (&["[0].radius=1"], "invalid path \"[0].radius\""),
+        (&["radius"], "expected path=value"),
+    ]
+    .iter()
+    {
+        assert_eq!(set(sets).unwrap_err(), *message);
+    }
+}
+
+#[test]
+fn scene_errors_are_blamed_on_the_override_that_caused_them() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("overrides");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("scene.json");
+    fs::write(&path, SCENE).unwrap();
+    let load = |sets: &[&str]| {
+        let overrides: Vec<Override> = sets.iter().map(|s| Override::parse(s).unwrap()).collect();
+        scene_file::load_with(&path, &overrides)
+    };
+    let scene = load(&["spheres[1].radius=3", "lights[0].intensity=5"]).unwrap();
+    assert_eq!(scene.objects.len(), 2);
+
+    for (sets, message) in [
+        (
+            &["spheres[0].radius=big"][..],
+            "can't set spheres[0].radius=big: spheres[0].radius: expected a number, found a string",
+        ),
+        (
+            &["spheres[1].material.albedo=[1,0]"],
+            "can't set spheres[1].material.albedo=[1,0]: spheres[1].material.albedo: \
+             expected an array of three numbers, found an array",
+        ),
+        (
+            &["camera.fovv=35"],
+            "can't set camera.fovv=35: camera: unknown member \"fovv\"",
+        ),
+        (
+            &["spheres[0].material={\"type\": \"velvet\"}"],
+            "can't set spheres[0].material={\"type\": \"velvet\"}: spheres[0].material: \
+             unknown material type \"velvet\"",
+        ),
+    ]
+    .iter()
+    {
+        let error = load(sets).err().unwrap().to_string();
+        let expected = format!("{}: {}", path.display(), message);
+        assert_eq!(error, expected);
+    }
+}
+
+#[test]
+fn sweeps_run_linearly_from_the_first_frame_to_the_last() {
+    let sweep = Sweep::parse("lights[0].intensity=1..10").unwrap();
+    assert_eq!(sweep.at(0.0).value().as_f64(), Some(1.0));
+    assert_eq!(sweep.at(0.5).value().as_f64(), Some(5.5));
+    assert_eq!(sweep.at(1.0).value().as_f64(), Some(10.0));
+    let colors = Sweep::parse("background=[0, 0, 1]..[1, 0, 0.5]").unwrap();
+    assert_eq!(colors.at(0.5).value().to_string(), "[0.5, 0, 0.75]");
+    assert_eq!(
+        colors.at(1.0).to_string(),
+        "background=[0, 0, 1]..[1, 0, 0.5]"
+    );
+
+    for text in ["a=1..[2]", "a=red..blue", "a=[1]..[1, 2]"].iter() {
+        let error = Sweep::parse(text).unwrap_err();
+        assert!(
+            error.contains("doesn't run between two numbers"),
+            "{}",
+            error
+        );
+    }
+    assert_eq!(Sweep::parse("a=1").unwrap_err(), "expected path=from..to");
+}
+
+#[test]
+fn the_command_line_sets_values_once_or_per_frame() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("overrides")
+        .join("cli");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("scene.json"), SCENE).unwrap();
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_basic-raytracer"))
+            .args(["render", "scene.json", "--width", "16", "--height", "12"])
+            .args(["--spp", "1"])
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        (output.status.code(), stderr)
+    };
+    let brightness = |name: &str| -> f64 {
+        let image = Image::read_png(&dir.join(name)).unwrap();
+        image
+            .pixels
+            .iter()
+            .map(|p| basic_raytracer::scalar::to_f64(p.x + p.y + p.z))
+            .sum()
+    };
+
+    let sweep = ["--set-per-frame", "lights[0].intensity=0..20"];
+    let (code, stderr) = run(&[&sweep[..], &["--frames", "3", "--output", "f#.png"]].concat());
+    assert_eq!(code, Some(0), "{}", stderr);
+    let (first, middle, last) = (
+        brightness("f0.png"),
+        brightness("f1.png"),
+        brightness("f2.png"),
+    );
+    assert!(
+        first < middle && middle < last,
+        "{} {} {}",
+        first,
+        middle,
+        last
+    );
+    // The middle frame is lit as a single render set to halfway is.
+    let set = ["--set", "lights[0].intensity=10", "--output", "middle.png"];
+    assert_eq!(run(&set).0, Some(0));
+    assert!(
+        Image::read_png(&dir.join("middle.png")).unwrap().pixels
+            == Image::read_png(&dir.join("f1.png")).unwrap().pixels
+    );
+
+    let (code, stderr) = run(&["--set", "spheres[9].radius=1"]);
+    assert_eq!(code, Some(3));
+    assert_eq!(
+        stderr.trim(),
+        "error: scene.json: can't set spheres[9].radius=1: spheres: no item [9] in an array of 2"
+    );
+    let (code, stderr) = run(&sweep);
+    assert_eq!(
+        (code, stderr.trim()),
+        (Some(2), "error: --set-per-frame needs --frames")
+    );
+    let (code, stderr) = run(&["--set", "fov"]);
+    assert_eq!(
+        (code, stderr.trim()),
+        (
+            Some(2),
+            "error: invalid value \"fov\" for --set: expected path=value"
+        )
+    );
+}
@@ -0,0 +1,184 @@
+use std::sync::Arc;
+
+use basic_raytracer::aabb::Aabb;
+use basic_raytracer::camera::Camera;
+use basic_raytracer::light::PointLight;
+use basic_raytracer::material::{Dielectric, Lambertian};
+use basic_raytracer::ray::{Ray, RayPacket, EPSILON, PACKET_SIZE};
+use basic_raytracer::render::{render, IntegratorKind, RenderSettings};
+use basic_raytracer::sampler::Sampler;
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene::{Hit, RayKind, Scene};
+use basic_raytracer::shapes::{HitRecord, Plane, Shape, Sphere, Triangle};
+use basic_raytracer::vector::{Color, Vector};
+
+/// A grid of spheres to give the BVH some depth, on a plane that isn't in
+/// it, with a triangle that has no packet test of its own.
+fn scene() -> Scene {
+    let mut scene = Scene::new(Camera::new(
+        Vector::new(0.0, 3.0, 9.0),
+        Vector::zero(),
+        50.0,
+    ));
+    scene.add(
+        Plane::new(Vector::new(0.0, -1.0, 0.0), Vector::new(0.0, 1.0, 0.0)),
+        Arc::new(Lambertian::new(Color::new(0.6, 0.6, 0.6))),
+    );
+    for i in 0..5 {
+        for j in 0..5 {
+            let center = Vector::new(
+                i as Scalar - 2.0,
+                0.4 * j as Scalar - 0.6,
+                j as Scalar - 2.0,
+            );
+            scene.add(
+                Sphere::new(center, 0.3 + 0.04 * i as Scalar),
+                Arc::new(Lambertian::new(Color::new(0.8, 0.3, 0.2))),
+            );
+        }
+    }
+    scene.add(
+        Triangle::new(
+            Vector::new(-3.0, -1.0, -3.0),
+            Vector::new(3.0, -1.0, -3.0),
+            Vector::new(0.0, 3.0, -3.0),
+        ),
+        Arc::new(Dielectric::new(1.5)),
+    );
+    scene.add_light(PointLight::new(
+        Vector::new(2.0, 5.0, 4.0),
+        Color::new(1.0, 1.0, 1.0),
+        60.0,
+    ));
+    scene
+}
+
+fn same_record(a: &HitRecord, b: &HitRecord) -> bool {
+    let bits = |v: Vector| [v.x.to_bits(), v.y.to_bits(), v.z.to_bits()];
+    a.t.to_bits() == b.t.to_bits()
+        && bits(a.point) == bits(b.point)
+        && bits(a.normal) == bits(b.normal)
+        && bits(a.shading_normal) == bits(b.shading_normal)
+        && a.front_face == b.front_face
+        && a.uv.0.to_bits() == b.uv.0.to_bits()
+        && a.uv.1.to_bits() == b.uv.1.to_bits()
+}
+
+fn same_hit(a: &Option<Hit>, b: &Option<Hit>) -> bool {
+    match (a, b) {
+        (None, None) => true,
+        (Some(a), Some(b)) => std::ptr::eq(a.object, b.object) && same_record(&a.record, &b.record),
+        _ => false,
+    }
+}
+
+/// Rays every which way from around the scene, nothing like coherent.
+fn scattered_rays(count: usize) -> Vec<Ray> {
+    let mut sampler = Sampler::new(3, 0, 0);
+    let mut next = || 2.0 * sampler.next_scalar() - 1.0;
+    (0..count)
+        .map(|_| {
+            let origin = Vector::new(4.0 * next(), 2.0 + next(), 4.0 * next());
+            Ray::new(origin, Vector::new(next(), next(), next()))
+        })
+        .collect()
+}
+
+#[test]
+fn packets_find_the_hits_single_rays_do() {
+    let scene = scene();
+    let aspect = 4.0 / 3.0;
+    let mut rays: Vec<Ray> = (0..48)
+        .flat_map(|y| (0..64).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let (u, v) = ((x as Scalar + 0.5) / 64.0, (y as Scalar + 0.5) / 48.0);
+            scene.camera.ray(u, v, aspect)
+        })
+        .collect();
+    rays.extend(scattered_rays(400));
+    let mut hits = 0;
+    // The last packets are short, with lanes left empty.
+    for size in [PACKET_SIZE, 3, 1] {
+        for rays in rays.chunks(size) {
+            let packet = RayPacket::new(rays);
+            let packed = scene.trace_packet(&packet, RayKind::Camera, EPSILON, Scalar::INFINITY);
+            for (i, hit) in packed.iter().enumerate() {
+                if i >= rays.len() {
+                    assert!(hit.is_none());
+                    continue;
+                }
+                let single = scene.trace(&rays[i], RayKind::Camera, EPSILON, Scalar::INFINITY);
+                assert!(same_hit(hit, &single), "{:?}", rays[i]);
+                hits += single.is_some() as usize;
+            }
+        }
+    }
+    assert!(hits > rays.len());
+}
+
+#[test]
+fn packet_tests_match_single_ray_ones_bit_for_bit() {
+    let sphere = Sphere::new(Vector::new(0.5, 2.0, -1.0), 1.5);
+    let boxes = [
+        Aabb::new(Vector::new(-1.0, 1.0, -2.0), Vector::new(1.0, 3.0, 0.0)),
+        // Flat, so that rays in its plane meet 0 * infinity.
+        Aabb::new(Vector::new(-4.0, 2.0, -4.0), Vector::new(4.0, 2.0, 4.0)),
+    ];
+    let mut rays = scattered_rays(2000);
+    rays.push(Ray::new(
+        Vector::new(-5.0, 2.0, 0.0),
+        Vector::new(1.0, 0.0, 0.0),
+    ));
+    for rays in rays.chunks(PACKET_SIZE) {
+        let packet = RayPacket::new(rays);
+        let t_max = [Scalar::INFINITY, 3.0, 1.0, 0.5];
+        let hits = sphere.intersect_packet(&packet, packet.active, EPSILON, &t_max);
+        for (i, ray) in rays.iter().enumerate() {
+            match (hits[i], sphere.intersect(ray, EPSILON, t_max[i])) {
+                (None, None) => {}
+                (Some(a), Some(b)) => assert!(same_record(&a, &b)),
+                other => panic!("{:?}: {:?}", ray, other),
+            }
+        }
+        for aabb in &boxes {
+            let hits = aabb.hit_packet(&packet, packet.active, EPSILON, &t_max);
+            for (i, ray) in rays.iter().enumerate() {
+                assert_eq!(hits[i], aabb.hit(ray, EPSILON, t_max[i]), "{:?}", ray);
+            }
+        }
+    }
+}
+
+#[test]
+fn packets_render_the_same_image_and_report_their_use() {
+    let scene = scene();
+    for (integrator, spp) in [
+        (IntegratorKind::Whitted, 1),
+        (IntegratorKind::Path, 2),
+        (IntegratorKind::AmbientOcclusion, 1),
+    ] {
+        let settings = RenderSettings {
+            width: 37,
+            height: 29,
+            integrator,
+            spp,
+            ao_samples: 4,
+            ..RenderSettings::default()
+        };
+        let (packed, packed_stats) = render(&scene, &settings).unwrap();
+        let single_settings = RenderSettings {
+            packets: false,
+            ..settings
+        };
+        let (single, single_stats) = render(&scene, &single_settings).unwrap();
+        assert!(packed.pixels == single.pixels, "{:?}", integrator);
+        assert_eq!(packed_stats.camera_rays, single_stats.camera_rays);
+        assert_eq!(packed_stats.rays, single_stats.rays);
+        assert_eq!(packed_stats.shadow_rays, single_stats.shadow_rays);
+        assert_eq!(single_stats.packet_utilization(), None);
+        // Primary rays next to each other mostly go the same way.
+        let utilization = packed_stats.packet_utilization().unwrap();
+        assert!(utilization > 0.5 && utilization <= 1.0, "{}", utilization);
+        assert!(packed_stats.to_string().contains("of packet lanes used"));
+    }
+}
@@ -0,0 +1,179 @@
+pub mod common;
+
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::image::Image;
+use basic_raytracer::light::PointLight;
+use basic_raytracer::material::Lambertian;
+use basic_raytracer::parts::Part;
+use basic_raytracer::render::{render, render_part, IntegratorKind, RenderSettings};
+use basic_raytracer::scalar;
+use basic_raytracer::scene::Scene;
+use basic_raytracer::shapes::{Plane, Sphere};
+use basic_raytracer::vector::{Color, Vector};
+use common::{run, scratch};
+
+fn scene() -> Scene {
+    let mut scene = Scene::new(Camera::new(
+        Vector::new(0.0, 1.0, 4.0),
+        Vector::zero(),
+        45.0,
+    ));
+    scene.add(
+        Plane::new(Vector::new(0.0, -1.0, 0.0), Vector::new(0.0, 1.0, 0.0)),
+        Arc::new(Lambertian::new(Color::new(0.2, 0.6, 0.3))),
+    );
+    scene.add(
+        Sphere::new(Vector::zero(), 0.8),
+        Arc::new(Lambertian::new(Color::new(0.9, 0.3, 0.1))),
+    );
+    scene.add_light(PointLight::new(
+        Vector::new(2.0, 4.0, 3.0),
+        Color::new(1.0, 1.0, 1.0),
+        40.0,
+    ));
+    scene
+}
+
+fn settings(spp: u32, seed: u64) -> RenderSettings {
+    RenderSettings {
+        width: 24,
+        height: 16,
+        spp,
+        integrator: IntegratorKind::Path,
+        seed,
+        ..RenderSettings::default()
+    }
+}
+
+/// The largest difference between a channel of `a` and of `b`.
+fn furthest(a: &Image, b: &Image) -> f64 {
+    a.pixels
+        .iter()
+        .zip(&b.pixels)
+        .flat_map(|(a, b)| vec![a.x - b.x, a.y - b.y, a.z - b.z])
+        .map(|d| scalar::to_f64(d).abs())
+        .fold(0.0, f64::max)
+}
+
+fn part(settings: &RenderSettings, samples: std::ops::Range<u32>) -> Part {
+    let stop = AtomicBool::new(false);
+    let (sums, _) = render_part(&scene(), settings, samples.clone(), &stop, |_| true).unwrap();
+    Part::new(1, settings.seed, samples, &sums)
+}
+
+#[test]
+fn shares_of_one_seeds_samples_merge_into_the_render_of_them_all() {
+    let dir = scratch("shares");
+    let whole = settings(64, 3);
+    let (first, second) = (dir.join("first.bin"), dir.join("second.bin"));
+    part(&whole, 0..32).write(&first).unwrap();
+    part(&whole, 32..64).write(&second).unwrap();
+
+    let mut merged = Part::read(&first).unwrap();
+    let second = Part::read(&second).unwrap();
+    assert_eq!(second.samples, 32..64);
+    assert!(!merged.overlaps(&second));
+    merged.add(&second).unwrap();
+    assert!(merged.counts.iter().all(|&count| count == 64));
+    let (expected, _) = render(&scene(), &whole).unwrap();
+    let error = furthest(&merged.image(), &expected);
+    assert!(error < 1e-4, "{}", error);
+}
+
+#[test]
+fn parts_of_other_seeds_merge_into_as_good_an_image() {
+    let (expected, _) = render(&scene(), &settings(128, 0)).unwrap();
+    let mut merged = part(&settings(32, 1), 0..32);
+    let one_seed = furthest(&merged.image(), &expected);
+    let other = part(&settings(32, 2), 0..32);
+    assert!(!merged.overlaps(&other));
+    merged.add(&other).unwrap();
+    let two_seeds = furthest(&merged.image(), &expected);
+    // Twice the samples are less noisy, and not the same image again.
+    assert!(two_seeds < one_seed, "{} {}", two_seeds, one_seed);
+    assert!(merged.image().pixels != other.image().pixels);
+
+    assert!(part(&settings(32, 5), 0..16).overlaps(&part(&settings(32, 5), 8..24)));
+    let mut wrong = part(&settings(32, 1), 0..32);
+    wrong.fingerprint = 2;
+    assert_eq!(
+        merged.add(&wrong).unwrap_err(),
+        "it's of a different scene or settings"
+    );
+}
+
+#[test]
+fn parts_that_arent_this_version_are_refused() {
+    let dir = scratch("version");
+    let path = dir.join("part.bin");
+    part(&settings(2, 0), 0..2).write(&path).unwrap();
+    let mut data = fs::read(&path).unwrap();
+    assert_eq!(&data[..8], b"RTPART\x01\x00");
+    data[6] = 2;
+    fs::write(&path, &data).unwrap();
+    let error = Part::read(&path).err().unwrap().to_string();
+    assert!(
+        error.ends_with("the part is in version 2 of the format, and only version 1 is supported"),
+        "{}",
+        error
+    );
+    data[6] = 1;
+    fs::write(&path, &data[..data.len() - 1]).unwrap();
+    let error = Part::read(&path).err().unwrap().to_string();
+    assert!(error.ends_with("the part is cut short"), "{}", error);
+}
+
+#[test]
+fn renders_shared_out_from_the_command_line_merge_into_one() {
+    let dir = scratch("cli");
+    let scene = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes/checker.json");
+    let scene = scene.to_str().unwrap();
+    let run = |args: &[&str]| {
+        let (code, _, stderr) = run(&dir, args);
+        (code, stderr)
+    };
+    let flags = [
+        "--width",
+        "24",
+        "--height",
+        "16",
+        "--spp",
+        "8",
+        "--integrator",
+        "path",
+    ];
+    for part in ["1/2", "2/2"].iter() {
+        let name = format!("part{}.bin", &part[..1]);
+        let args = ["render", scene, "--part", part, "--save-samples", &name];
+        let (code, stderr) = run(&[&args[..], &flags[..]].concat());
+        assert_eq!(code, Some(0), "{}", stderr);
+    }
+    let whole = ["render", scene, "--output", "whole.png"];
+    let (code, stderr) = run(&[&whole[..], &flags[..]].concat());
+    assert_eq!(code, Some(0), "{}", stderr);
+    let (code, stderr) = run(&["merge", "part1.bin", "part2.bin", "--output", "merged.png"]);
+    assert_eq!(code, Some(0), "{}", stderr);
+    let merged = Image::read_png(&dir.join("merged.png")).unwrap();
+    let whole = Image::read_png(&dir.join("whole.png")).unwrap();
+    // Only rounding to 8 bits apart.
+    assert!(furthest(&merged, &whole) <= 1.5 / 255.0);
+
+    let (code, stderr) = run(&["merge", "part1.bin", "part1.bin"]);
+    assert_eq!(
+        (code, stderr.trim()),
+        (
+            Some(2),
+            "error: invalid render settings: part1.bin and part1.bin both have samples of seed 0, so they'd be counted twice"
+        )
+    );
+    let (code, stderr) = run(&["render", scene, "--part", "1/2"]);
+    assert_eq!(
+        (code, stderr.trim()),
+        (Some(2), "error: --part needs --save-samples")
+    );
+}
@@ -0,0 +1,101 @@
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::integrator::{Integrator, Whitted};
+use basic_raytracer::light::PointLight;
+use basic_raytracer::material::{Dielectric, Lambertian};
+use basic_raytracer::photon::{Photon, PhotonMap};
+use basic_raytracer::ray::Ray;
+use basic_raytracer::sampler::Sampler;
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene::Scene;
+use basic_raytracer::shapes::{Plane, Sphere};
+use basic_raytracer::vector::{Color, Vector};
+
+#[test]
+fn nearest_matches_brute_force() {
+    let mut sampler = Sampler::new(1, 0, 0);
+    let photons: Vec<Photon> = (0..2000)
+        .map(|_| Photon {
+            position: Vector::new(
+                sampler.next_scalar(),
+                sampler.next_scalar(),
+                sampler.next_scalar(),
+            ),
+            direction: Vector::new(0.0, -1.0, 0.0),
+            power: Color::new(1.0, 1.0, 1.0),
+        })
+        .collect();
+    let map = PhotonMap::new(photons.clone(), 16, 0.2);
+    for _ in 0..50 {
+        let point = Vector::new(
+            sampler.next_scalar(),
+            sampler.next_scalar(),
+            sampler.next_scalar(),
+        );
+        let mut expected: Vec<Scalar> = photons
+            .iter()
+            .map(|photon| (photon.position - point) * (photon.position - point))
+            .filter(|&distance| distance <= 0.2 * 0.2)
+            .collect();
+        expected.sort_by(Scalar::total_cmp);
+        expected.truncate(16);
+        let found: Vec<Scalar> = map
+            .nearest(point, 16, 0.2)
+            .into_iter()
+            .map(|(distance, _)| distance)
+            .collect();
+        assert_eq!(found, expected);
+    }
+}
+
+fn glass_sphere() -> Scene {
+    let mut scene = Scene::new(Camera::new(
+        Vector::new(0.0, 3.0, 4.0),
+        Vector::new(0.0, 0.0, 0.0),
+        45.0,
+    ));
+    scene.add(
+        Plane::new(Vector::zero(), Vector::new(0.0, 1.0, 0.0)),
+        Arc::new(Lambertian::new(Color::new(0.75, 0.75, 0.75))),
+    );
+    scene.add(
+        Sphere::new(Vector::new(0.0, 1.0, 0.0), 0.6),
+        Arc::new(Dielectric::new(1.5)),
+    );
+    scene.add_light(PointLight::new(
+        Vector::new(0.0, 4.0, 0.0),
+        Color::new(1.0, 1.0, 1.0),
+        16.0,
+    ));
+    scene
+}
+
+fn floor_radiance(integrator: &Whitted, scene: &Scene, x: Scalar) -> Scalar {
+    let target = Vector::new(x, 0.0, 0.0);
+    let origin = Vector::new(x, 0.1, 2.0);
+    let ray = Ray::new(origin, (target - origin).normalize());
+    let mut sampler = Sampler::new(0, 0, 0);
+    integrator.radiance(&ray, scene, &mut sampler).x
+}
+
+#[test]
+fn glass_sphere_focuses_light_under_it() {
+    let scene = glass_sphere();
+    let photons = PhotonMap::caustics(&scene, 200_000, 8, 0, 64, 0.05);
+    assert!(!photons.is_empty());
+    let with = Whitted {
+        max_depth: 8,
+        caustics: Some(photons),
+    };
+    let without = Whitted {
+        max_depth: 8,
+        caustics: None,
+    };
+    // Directly under the sphere the floor is in its shadow, but the focused
+    // light makes it brighter than the unshadowed floor further out.
+    assert_eq!(floor_radiance(&without, &scene, 0.0), 0.0);
+    let focus = floor_radiance(&with, &scene, 0.0);
+    let open = floor_radiance(&with, &scene, 2.5);
+    assert!(focus > open, "focus {} vs open floor {}", focus, open);
+}
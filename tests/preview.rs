@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::error::Error;
+use basic_raytracer::image::Image;
+use basic_raytracer::light::PointLight;
+use basic_raytracer::material::Lambertian;
+use basic_raytracer::render::{render, render_watched, IntegratorKind, RenderSettings};
+use basic_raytracer::scene::Scene;
+use basic_raytracer::shapes::Plane;
+use basic_raytracer::vector::{Color, Vector};
+
+/// A wall filling the view, so that every finished row has lit pixels.
+fn wall() -> Scene {
+    let mut scene = Scene::new(Camera::new(
+        Vector::new(0.0, 0.0, 5.0),
+        Vector::zero(),
+        60.0,
+    ));
+    scene.add(
+        Plane::new(Vector::zero(), Vector::new(0.0, 0.0, 1.0)),
+        Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.8))),
+    );
+    scene.add_light(PointLight::new(
+        Vector::new(0.0, 0.0, 4.0),
+        Color::new(1.0, 1.0, 1.0),
+        20.0,
+    ));
+    scene
+}
+
+fn rows_done(image: &Image) -> u32 {
+    (0..image.height)
+        .filter(|&y| (0..image.width).all(|x| image.get(x, y) != Color::zero()))
+        .count() as u32
+}
+
+fn settings() -> RenderSettings {
+    RenderSettings {
+        width: 64,
+        height: 256,
+        spp: 4,
+        integrator: IntegratorKind::Whitted,
+        ..RenderSettings::default()
+    }
+}
+
+#[test]
+fn the_watcher_sees_the_image_fill_in() {
+    let scene = wall();
+    let mut seen = Vec::new();
+    let (image, _) = render_watched(&scene, &settings(), |image| {
+        seen.push(rows_done(image));
+        true
+    })
+    .unwrap();
+    assert!(!seen.is_empty());
+    assert!(seen.windows(2).all(|pair| pair[0] <= pair[1]), "{:?}", seen);
+    // The last look is at the finished image, which is what `render` makes.
+    assert_eq!(*seen.last().unwrap(), 256);
+    assert!(image.pixels == render(&scene, &settings()).unwrap().0.pixels);
+}
+
+#[test]
+fn the_watcher_can_cancel_the_render() {
+    let scene = wall();
+    let mut looks = 0;
+    let settings = RenderSettings {
+        height: 4096,
+        ..settings()
+    };
+    let result = render_watched(&scene, &settings, |image| {
+        looks += 1;
+        assert!(rows_done(image) < 4096);
+        false
+    });
+    assert!(matches!(result, Err(Error::Cancelled)));
+    // Once cancelled it isn't asked again.
+    assert_eq!(looks, 1);
+}
+
+#[cfg(not(feature = "preview"))]
+#[test]
+fn the_binary_needs_the_preview_feature() {
+    for flag in ["--preview", "--interactive"] {
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_basic-raytracer"))
+            .arg(flag)
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(2));
+        assert!(String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("--features preview"));
+    }
+}
@@ -0,0 +1,123 @@
+use std::fs;
+use std::process::Command;
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::light::PointLight;
+use basic_raytracer::material::Lambertian;
+use basic_raytracer::render::{render, render_progressive, IntegratorKind, RenderSettings};
+use basic_raytracer::scene::Scene;
+use basic_raytracer::shapes::{Plane, Sphere};
+use basic_raytracer::vector::{Color, Vector};
+
+fn scene() -> Scene {
+    let mut scene = Scene::new(Camera::new(
+        Vector::new(0.0, 1.0, 4.0),
+        Vector::zero(),
+        45.0,
+    ));
+    scene.add(
+        Plane::new(Vector::new(0.0, -1.0, 0.0), Vector::new(0.0, 1.0, 0.0)),
+        Arc::new(Lambertian::new(Color::new(0.2, 0.6, 0.3))),
+    );
+    scene.add(
+        Sphere::new(Vector::zero(), 0.8),
+        Arc::new(Lambertian::new(Color::new(0.9, 0.3, 0.1))),
+    );
+    scene.add_light(PointLight::new(
+        Vector::new(2.0, 4.0, 3.0),
+        Color::new(1.0, 1.0, 1.0),
+        40.0,
+    ));
+    scene
+}
+
+fn settings(spp: u32) -> RenderSettings {
+    RenderSettings {
+        width: 32,
+        height: 24,
+        spp,
+        integrator: IntegratorKind::Path,
+        seed: 7,
+        ..RenderSettings::default()
+    }
+}
+
+#[test]
+fn passes_add_up_to_the_same_image_as_one_render() {
+    let scene = scene();
+    for (passes, spp) in [(8, 1), (3, 2)] {
+        let mut seen = Vec::new();
+        let mut looks = 0;
+        let (image, stats) = render_progressive(
+            &scene,
+            &settings(spp),
+            passes,
+            |done, image| {
+                seen.push((done, image.clone()));
+                Ok(())
+            },
+            |_| {
+                looks += 1;
+                true
+            },
+        )
+        .unwrap();
+        let (one_shot, one_shot_stats) = render(&scene, &settings(passes * spp)).unwrap();
+        assert!(image.pixels == one_shot.pixels, "{} x {}", passes, spp);
+        assert_eq!(stats.camera_rays, one_shot_stats.camera_rays);
+        assert_eq!(
+            seen.iter().map(|(done, _)| *done).collect::<Vec<_>>(),
+            (1..=passes).collect::<Vec<_>>()
+        );
+        assert!(looks >= passes as usize);
+        // Part way through, the image is a render with fewer samples.
+        let (done, partial) = &seen[1];
+        let fewer = render(&scene, &settings(done * spp)).unwrap().0;
+        assert!(partial.pixels == fewer.pixels);
+        assert!(partial.pixels != image.pixels);
+    }
+    assert!(render_progressive(&scene, &settings(1), 0, |_, _| Ok(()), |_| true).is_err());
+}
+
+#[test]
+fn the_binary_saves_the_image_as_it_refines() {
+    let dir = std::env::temp_dir().join(format!("progressive-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_basic-raytracer"))
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .unwrap()
+    };
+    let output = run(&[
+        "--progressive",
+        "--passes",
+        "2",
+        "--save-every",
+        "1",
+        "--output",
+        "refined.png",
+    ]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Pass 1 of 2") && stdout.contains("Pass 2 of 2"));
+    assert!(dir.join("refined.png").exists());
+
+    for args in [
+        &["--passes", "4"][..],
+        &["--progressive", "--save-every", "0"],
+        &[
+            "--progressive",
+            "--save-every",
+            "1",
+            "--output",
+            "refined.gif",
+        ],
+    ] {
+        assert_eq!(run(args).status.code(), Some(2), "{:?}", args);
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,302 @@
+//! Invariants that should hold for any inputs, checked on many random ones
+//! with proptest. Each property runs on `CASES` inputs drawn from a fixed
+//! seed, so a failure happens again on every run; set `PROPTEST_RNG_SEED`
+//! to try others. A failing input is shrunk toward simpler values while it
+//! still fails, and the smallest one found is what the failure shows.
+
+use std::sync::Arc;
+
+use proptest::prelude::*;
+use proptest::sample::Index;
+use proptest::test_runner::RngSeed;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::material::Lambertian;
+use basic_raytracer::matrix::Matrix4;
+use basic_raytracer::ray::Ray;
+use basic_raytracer::scalar::{to_f64, Scalar};
+use basic_raytracer::scene::Scene;
+use basic_raytracer::shapes::{Cuboid, Shape, Sphere, Transformed};
+use basic_raytracer::vector::{Color, Vector};
+
+const CASES: u32 = 500;
+
+fn config() -> ProptestConfig {
+    let config = ProptestConfig::default();
+    let rng_seed = match config.rng_seed {
+        RngSeed::Random => RngSeed::Fixed(0),
+        seed => seed,
+    };
+    ProptestConfig {
+        cases: CASES,
+        rng_seed,
+        // The seed is fixed, so a failure comes back without a file of them.
+        failure_persistence: None,
+        ..config
+    }
+}
+
+/// A vector in the cube from -10 to 10.
+fn vector() -> impl Strategy<Value = Vector> {
+    let coordinate = || -10.0 as Scalar..10.0;
+    (coordinate(), coordinate(), coordinate()).prop_map(|(x, y, z)| Vector::new(x, y, z))
+}
+
+fn nonzero(vector: impl Strategy<Value = Vector>) -> impl Strategy<Value = Vector> {
+    vector.prop_filter("a zero vector", |v| *v != Vector::zero())
+}
+
+/// A vector of any size the math should cope with, from tiny to huge.
+fn any_size() -> impl Strategy<Value = Vector> {
+    // Squaring the components mustn't overflow or underflow.
+    let largest = (Scalar::MAX_10_EXP / 3) as Scalar;
+    nonzero(
+        (-largest..largest, vector())
+            .prop_map(|(exponent, v)| 10.0_f64.powf(to_f64(exponent)) as Scalar * v),
+    )
+}
+
+#[derive(Debug, Clone)]
+struct SphereCase {
+    center: Vector,
+    radius: Scalar,
+}
+
+fn sphere(radii: std::ops::Range<Scalar>) -> impl Strategy<Value = SphereCase> {
+    (vector(), radii).prop_map(|(center, radius)| SphereCase { center, radius })
+}
+
+/// A ray that passes near `target`, so that it hits what's there often:
+/// from `origin` toward `target` moved by `spread` times `aim`.
+fn ray_toward(origin: Vector, target: Vector, spread: Scalar, aim: Vector) -> Ray {
+    Ray::new(origin, target + spread * aim - origin)
+}
+
+fn inside(low: Vector, p: Vector, high: Vector) -> bool {
+    low.x <= p.x && p.x <= high.x && low.y <= p.y && p.y <= high.y && low.z <= p.z && p.z <= high.z
+}
+
+#[derive(Debug, Clone)]
+struct SphereHit {
+    sphere: SphereCase,
+    ray: Ray,
+    interval: (Scalar, Scalar),
+}
+
+fn sphere_hit() -> impl Strategy<Value = SphereHit> {
+    (
+        sphere(0.01..5.0),
+        vector(),
+        vector(),
+        0.0 as Scalar..0.5,
+        prop::option::of(0.0 as Scalar..1.0),
+    )
+        .prop_filter_map(
+            "a ray of no direction",
+            |(sphere, origin, aim, t_min, bound)| {
+                let ray = ray_toward(origin, sphere.center, 0.2 * sphere.radius, aim);
+                // Unbounded half the time, or ending before t = 3.
+                let t_max = bound.map_or(Scalar::INFINITY, |f| t_min + f * (3.0 - t_min));
+                (ray.direction != Vector::zero()).then_some(SphereHit {
+                    sphere,
+                    ray,
+                    interval: (t_min, t_max),
+                })
+            },
+        )
+}
+
+/// A transform of translation, rotation and scaling, kept as its parts so
+/// that it shrinks to simpler ones.
+#[derive(Debug, Clone)]
+struct Transform {
+    offset: Vector,
+    axis: Vector,
+    angle: Scalar,
+    scale: Vector,
+}
+
+impl Transform {
+    fn matrix(&self) -> Matrix4 {
+        Matrix4::translate(self.offset)
+            * Matrix4::rotate(self.axis, self.angle)
+            * Matrix4::scale(self.scale)
+    }
+}
+
+fn transform() -> impl Strategy<Value = Transform> {
+    let factor = || 0.2 as Scalar..3.0;
+    (
+        vector(),
+        vector().prop_filter("an axis too short", |axis| axis.len() >= 1e-3),
+        -3.0 as Scalar..3.0,
+        (factor(), factor(), factor()),
+    )
+        .prop_map(|(offset, axis, angle, (x, y, z))| Transform {
+            offset,
+            axis,
+            angle,
+            scale: Vector::new(x, y, z),
+        })
+}
+
+/// A ray aimed near the center of one of the spheres, which it's drawn
+/// along with.
+#[derive(Debug, Clone)]
+struct RayCase {
+    target: Index,
+    origin: Vector,
+    aim: Vector,
+}
+
+fn ray_case() -> impl Strategy<Value = RayCase> {
+    (any::<Index>(), vector(), vector()).prop_map(|(target, origin, aim)| RayCase {
+        target,
+        origin,
+        aim,
+    })
+}
+
+proptest! {
+    #![proptest_config(config())]
+
+    #[test]
+    fn normalizing_makes_unit_vectors(v in any_size()) {
+        let unit = v.normalize();
+        let length = unit.len();
+        prop_assert!(
+            (length - 1.0).abs() <= 8.0 * Scalar::EPSILON,
+            "normalized to {:?}, of length {}",
+            unit,
+            length
+        );
+        prop_assert!(unit * v > 0.0, "normalized to {:?}, the other way", unit);
+    }
+
+    #[test]
+    fn reflecting_keeps_length(v in vector(), normal in nonzero(vector())) {
+        let reflected = v.reflect(normal.normalize());
+        let tolerance = 16.0 * Scalar::EPSILON * v.len();
+        prop_assert!(
+            (reflected.len() - v.len()).abs() <= tolerance,
+            "reflected to {:?}, of length {} rather than {}",
+            reflected,
+            reflected.len(),
+            v.len()
+        );
+    }
+
+    #[test]
+    fn sphere_hits_are_on_the_sphere_and_in_range(case in sphere_hit()) {
+        let SphereCase { center, radius } = case.sphere;
+        let (t_min, t_max) = case.interval;
+        let ray = case.ray;
+        if let Some(hit) = Sphere::new(center, radius).intersect(&ray, t_min, t_max) {
+            prop_assert!(
+                hit.t > t_min && hit.t < t_max,
+                "hit at t = {}, outside the interval",
+                hit.t
+            );
+            let distance = (ray.at(hit.t) - center).len();
+            // Rounding grows with how far along the ray the hit is, as well
+            // as with the sphere.
+            let scale = radius + (ray.origin - center).len();
+            prop_assert!(
+                (distance - radius).abs() <= 1e3 * Scalar::EPSILON * scale,
+                "hit at t = {}, {} from the center",
+                hit.t,
+                distance
+            );
+        }
+    }
+
+    #[test]
+    fn transformed_bounds_hold_every_hit(
+        transform in transform(),
+        rays in prop::collection::vec((vector(), vector()), 20),
+    ) {
+        let matrix = transform.matrix();
+        let shapes: [Box<dyn Shape>; 2] = [
+            Box::new(Transformed::new(Sphere::new(Vector::zero(), 1.0), matrix)),
+            Box::new(Transformed::new(
+                Cuboid::new(Vector::new(-1.0, -0.5, -0.25), Vector::new(1.0, 0.5, 0.25)),
+                matrix,
+            )),
+        ];
+        for shape in &shapes {
+            let bounds = shape.bounds().unwrap();
+            // Grown by a little rounding, relative to its size.
+            let slack = 1e3 * Scalar::EPSILON * (bounds.extent().len() + transform.offset.len());
+            let slack = Vector::new(slack, slack, slack);
+            for &(origin, aim) in &rays {
+                let ray = ray_toward(origin, transform.offset, 1.0, aim);
+                for hit in shape.intersect_all(&ray) {
+                    let point = ray.at(hit.t);
+                    prop_assert!(
+                        inside(bounds.min - slack, point, bounds.max + slack),
+                        "hit {:?} is outside the bounds {:?} to {:?}, by {:?}",
+                        point,
+                        bounds.min,
+                        bounds.max,
+                        ray
+                    );
+                }
+            }
+        }
+    }
+
+    /// Up to 100 spheres, and up to 20 rays through them.
+    #[test]
+    fn the_bvh_finds_the_closest_hit_testing_every_sphere_would(
+        soup in prop::collection::vec(sphere(0.05..1.5), 1..=100),
+        rays in prop::collection::vec(ray_case(), 1..=20),
+    ) {
+        let mut scene = Scene::new(Camera::new(
+            Vector::zero(),
+            Vector::new(0.0, 0.0, -1.0),
+            45.0,
+        ));
+        let material = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let spheres: Vec<Sphere> = soup
+            .iter()
+            .map(|sphere| Sphere::new(sphere.center, sphere.radius))
+            .collect();
+        for sphere in &spheres {
+            scene.add(Sphere::new(sphere.center, sphere.radius), material.clone());
+        }
+        for case in &rays {
+            let target = soup[case.target.index(soup.len())].center;
+            let ray = ray_toward(case.origin, target, 1.0, case.aim);
+            let (t_min, t_max) = (1e-4, Scalar::INFINITY);
+            let mut closest: Option<(usize, Scalar)> = None;
+            for (i, sphere) in spheres.iter().enumerate() {
+                let limit = closest.map_or(t_max, |(_, t)| t);
+                if let Some(hit) = sphere.intersect(&ray, t_min, limit) {
+                    closest = Some((i, hit.t));
+                }
+            }
+            let found = scene.intersect(&ray, t_min, t_max).map(|hit| {
+                let index = scene
+                    .objects
+                    .iter()
+                    .position(|object| std::ptr::eq(object, hit.object))
+                    .unwrap();
+                (index, hit.record.t)
+            });
+            // Two spheres the ray meets at the very same t may come in
+            // either order.
+            let same = match (found, closest) {
+                (None, None) => true,
+                (Some((a, s)), Some((b, t))) => a == b || s == t,
+                _ => false,
+            };
+            prop_assert!(
+                same,
+                "along {:?} the BVH found {:?} but every sphere in turn {:?}, as (index, t)",
+                ray,
+                found,
+                closest
+            );
+        }
+    }
+}
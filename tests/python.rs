@@ -0,0 +1,117 @@
+//! The Python module: its conversions in an interpreter embedded in the
+//! test, and the module as Python imports it, built and run through the
+//! tests in `examples/python`.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use pyo3::prelude::*;
+use pyo3::types::{PyList, PyTuple};
+
+use basic_raytracer::python::{tuple, vector};
+use basic_raytracer::scalar::{to_f64, Scalar};
+use basic_raytracer::vector::Vector;
+
+#[test]
+fn vectors_go_to_python_and_back_the_same() {
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        for value in [
+            Vector::new(1.0, -2.5, 3.25),
+            Vector::zero(),
+            Vector::new(1e-30, 1e30, -0.1),
+        ]
+        .iter()
+        {
+            let (x, y, z) = tuple(*value);
+            let as_tuple = PyTuple::new(py, [x, y, z]).unwrap();
+            assert_eq!(vector(as_tuple.as_any(), "v").unwrap(), *value);
+            // Any sequence of three numbers will do, ints included.
+            let as_list = PyList::new(py, [x, y, z]).unwrap();
+            assert_eq!(vector(as_list.as_any(), "v").unwrap(), *value);
+        }
+        let ints = PyTuple::new(py, [1, 2, 3]).unwrap();
+        assert_eq!(
+            vector(ints.as_any(), "v").unwrap(),
+            Vector::new(1.0, 2.0, 3.0)
+        );
+        // Python floats are doubles, kept as they are unless the math is f32.
+        let third = tuple(Vector::new(1.0 / 3.0, 0.0, 0.0)).0;
+        assert_eq!(third, to_f64(1.0 / 3.0 as Scalar));
+
+        let short = PyTuple::new(py, [1.0, 2.0]).unwrap();
+        let error = vector(short.as_any(), "center").unwrap_err();
+        assert!(error.is_instance_of::<pyo3::exceptions::PyTypeError>(py));
+        assert_eq!(
+            error.value(py).to_string(),
+            "center must be three numbers, not (1.0, 2.0)"
+        );
+        let words = PyTuple::new(py, ["a", "b", "c"]).unwrap();
+        assert!(vector(words.as_any(), "color").is_err());
+        assert!(vector(py.None().bind(py), "color").is_err());
+    });
+}
+
+/// Builds the module as Python imports it and runs the tests of
+/// `examples/python` against it with the interpreter PyO3 was built for.
+#[test]
+fn the_module_builds_and_passes_the_examples_tests() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let build = Path::new(env!("CARGO_TARGET_TMPDIR")).join("python");
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let status = Command::new(cargo)
+        .args([
+            "build",
+            "--quiet",
+            "--lib",
+            "--features",
+            "extension-module",
+            "--target-dir",
+        ])
+        .arg(&build)
+        .current_dir(root)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let library = if cfg!(windows) {
+        "basic_raytracer.dll"
+    } else if cfg!(target_os = "macos") {
+        "libbasic_raytracer.dylib"
+    } else {
+        "libbasic_raytracer.so"
+    };
+    let module = build.join("module");
+    fs::create_dir_all(&module).unwrap();
+    let name = if cfg!(windows) {
+        "basic_raytracer.pyd"
+    } else {
+        "basic_raytracer.so"
+    };
+    fs::copy(build.join("debug").join(library), module.join(name)).unwrap();
+
+    pyo3::prepare_freethreaded_python();
+    let python = Python::with_gil(|py| {
+        let sys = py.import("sys").unwrap();
+        sys.getattr("executable")
+            .unwrap()
+            .extract::<String>()
+            .unwrap()
+    });
+    let output = Command::new(python)
+        .arg(root.join("examples/python/test_spheres.py"))
+        .env("PYTHONPATH", &module)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains("ok test_a_hundred_spheres_render"),
+        "{}",
+        stdout
+    );
+}
@@ -0,0 +1,156 @@
+use std::path::Path;
+
+use basic_raytracer::matrix::Matrix4;
+use basic_raytracer::quaternion::Quaternion;
+use basic_raytracer::ray::Ray;
+use basic_raytracer::scalar::consts::{FRAC_PI_2, PI};
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene_file;
+use basic_raytracer::vector::Vector;
+
+/// How near a rotation must be to the one expected, and how near to 1 the
+/// length of a unit quaternion.
+#[cfg(not(feature = "f32"))]
+const TOLERANCE: Scalar = 1e-9;
+#[cfg(feature = "f32")]
+const TOLERANCE: Scalar = 1e-4;
+#[cfg(not(feature = "f32"))]
+const EXACT: Scalar = 1e-12;
+#[cfg(feature = "f32")]
+const EXACT: Scalar = 1e-5;
+
+fn assert_matrices_close(a: Matrix4, b: Matrix4) {
+    for (row_a, row_b) in a.rows.iter().zip(b.rows.iter()) {
+        for (x, y) in row_a.iter().zip(row_b.iter()) {
+            assert!((x - y).abs() < EXACT, "{:?} != {:?}", a, b);
+        }
+    }
+}
+
+/// Whether two unit quaternions are the same rotation.
+fn same_rotation(a: Quaternion, b: Quaternion) -> bool {
+    (a.dot(b).abs() - 1.0).abs() < EXACT
+}
+
+#[test]
+fn composition_matches_the_matrices() {
+    let x = Quaternion::from_axis_angle(Vector::new(1.0, 0.0, 0.0), FRAC_PI_2);
+    let y = Quaternion::from_axis_angle(Vector::new(0.0, 1.0, 0.0), FRAC_PI_2);
+    assert_matrices_close(
+        (y * x).to_matrix(),
+        Matrix4::rotate_y(FRAC_PI_2) * Matrix4::rotate_x(FRAC_PI_2),
+    );
+    assert_matrices_close(
+        (x * y).to_matrix(),
+        Matrix4::rotate_x(FRAC_PI_2) * Matrix4::rotate_y(FRAC_PI_2),
+    );
+
+    let axis = Vector::new(1.0, -2.0, 0.5);
+    assert_matrices_close(
+        Quaternion::from_axis_angle(axis, 1.2).to_matrix(),
+        Matrix4::rotate(axis.normalize(), 1.2),
+    );
+    let angles = Vector::new(0.3, -1.1, 2.0);
+    assert_matrices_close(
+        Quaternion::from_euler(angles).to_matrix(),
+        Matrix4::rotate_z(angles.z) * Matrix4::rotate_y(angles.y) * Matrix4::rotate_x(angles.x),
+    );
+    let q = Quaternion::new(1.0, 2.0, -2.0, 4.0).normalize();
+    assert!((q.len() - 1.0).abs() < EXACT);
+    assert_eq!(Quaternion::identity().to_matrix(), Matrix4::identity());
+}
+
+#[test]
+fn slerp_turns_at_a_constant_rate() {
+    let up = Vector::new(0.0, 1.0, 0.0);
+    let start = Quaternion::from_axis_angle(up, 0.2);
+    let end = Quaternion::from_axis_angle(up, 1.8);
+    assert!(same_rotation(start.slerp(end, 0.0), start));
+    assert!(same_rotation(start.slerp(end, 1.0), end));
+    assert!(same_rotation(
+        start.slerp(end, 0.5),
+        Quaternion::from_axis_angle(up, 1.0)
+    ));
+    assert!(same_rotation(
+        start.slerp(end, 0.25),
+        Quaternion::from_axis_angle(up, 0.6)
+    ));
+
+    // Nearly a half turn from the identity about x passes through nearly a
+    // quarter turn. A whole half turn is as short one way round as the other,
+    // so which way it goes is down to how cos(π/2) rounds.
+    let x = Vector::new(1.0, 0.0, 0.0);
+    let half_turn = Quaternion::from_axis_angle(x, 0.99 * PI);
+    assert!(same_rotation(
+        Quaternion::identity().slerp(half_turn, 0.5),
+        Quaternion::from_axis_angle(x, 0.99 * FRAC_PI_2)
+    ));
+}
+
+#[test]
+fn slerp_takes_the_shorter_arc() {
+    // 350° is -10°, so halfway from 0 is -5° rather than 175°.
+    let up = Vector::new(0.0, 1.0, 0.0);
+    let far = Quaternion::from_axis_angle(up, Scalar::to_radians(350.0));
+    assert!(far.w < 0.0);
+    let middle = Quaternion::identity().slerp(far, 0.5);
+    assert!(same_rotation(
+        middle,
+        Quaternion::from_axis_angle(up, -Scalar::to_radians(5.0))
+    ));
+
+    // q and nearly -q are nearly the same rotation, so nothing blows up.
+    let q = Quaternion::from_axis_angle(Vector::new(0.3, 0.4, 0.5), 0.9);
+    let opposite = Quaternion::new(-q.x, -q.y, -q.z, -q.w - 1e-9).normalize();
+    for &t in &[0.0, 0.3, 1.0] {
+        let between = q.slerp(opposite, t);
+        assert!((between.len() - 1.0).abs() < EXACT);
+        assert!(same_rotation(between, q));
+    }
+}
+
+#[test]
+fn scene_files_take_quaternions_and_axes() {
+    let parse = |rotation: &str| {
+        scene_file::parse(
+            &format!(
+                r#"{{"boxes": [{{"min": [-1, -1, -1], "max": [1, 1, 1],
+                    "material": {{"type": "dielectric", "ior": 1.5}},
+                    "transform": {{"scale": [0.5, 1, 1], {}}}}}]}}"#,
+                rotation
+            ),
+            Path::new(""),
+        )
+    };
+    // Each turns the box's short x extent to face along z.
+    for rotation in &[
+        r#""rotate_deg": [0, 90, 0]"#,
+        r#""rotate_quat": [0, 2, 0, 2]"#,
+        r#""rotate_axis": {"axis": [0, 3, 0], "angle_deg": -270}"#,
+    ] {
+        let scene = parse(rotation).unwrap();
+        let ray = Ray::new(Vector::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, -1.0));
+        let hit = scene.objects[0]
+            .shape
+            .intersect(&ray, 0.0, Scalar::INFINITY)
+            .unwrap();
+        assert!((hit.t - 4.5).abs() < TOLERANCE, "{}: {}", rotation, hit.t);
+    }
+    let error = |rotation: &str| parse(rotation).err().unwrap();
+    assert_eq!(
+        error(r#""rotate_quat": [0, 0, 1]"#),
+        "boxes[0].transform.rotate_quat: expected an array of four numbers, found an array"
+    );
+    assert_eq!(
+        error(r#""rotate_quat": [0, 0, 0, 0]"#),
+        "boxes[0].transform.rotate_quat: a rotation can't be zero"
+    );
+    assert_eq!(
+        error(r#""rotate_axis": {"axis": [0, 1, 0]}"#),
+        "boxes[0].transform.rotate_axis: missing member \"angle_deg\""
+    );
+    assert_eq!(
+        error(r#""rotate_deg": [0, 0, 0], "rotate_quat": [0, 0, 0, 1]"#),
+        "boxes[0].transform: only one of \"rotate_deg\", \"rotate_quat\" and \"rotate_axis\" can be given"
+    );
+}
@@ -0,0 +1,169 @@
+pub mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use basic_raytracer::error::Error;
+use basic_raytracer::image::{Image, Tonemap};
+use basic_raytracer::json::{self, Value};
+use basic_raytracer::render::RenderSettings;
+use basic_raytracer::report::{Output, Report};
+use basic_raytracer::vector::Color;
+use common::{run, scratch};
+
+/// Runs `render` in `dir`, for its exit code and stderr.
+fn render(dir: &Path, args: &[&str]) -> (Option<i32>, String) {
+    let small = ["render", "--width", "16", "--height", "12", "--spp", "2"];
+    let (code, _, stderr) = run(dir, &[&small[..], args].concat());
+    (code, stderr)
+}
+
+fn read_report(path: &Path) -> Value {
+    json::parse(&fs::read_to_string(path).unwrap()).unwrap()
+}
+
+/// The member at `path`, a `.`-separated list of keys.
+fn at<'a>(report: &'a Value, path: &str) -> &'a Value {
+    path.split('.').fold(report, |value, key| {
+        value
+            .get(key)
+            .unwrap_or_else(|| panic!("no {} in {}", path, report))
+    })
+}
+
+#[test]
+fn a_render_is_reported_with_its_settings_scene_stats_and_output() {
+    let dir = scratch("render");
+    let scene = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes/checker.json");
+    let args = ["--scene", scene.to_str().unwrap(), "--output", "out.png"];
+    let (code, stderr) = render(&dir, &[&args[..], &["--report", "report.json"]].concat());
+    assert_eq!(code, Some(0), "{}", stderr);
+
+    let report = read_report(&dir.join("report.json"));
+    assert_eq!(at(&report, "version").as_f64(), Some(1.0));
+    assert_eq!(at(&report, "status").as_str(), Some("ok"));
+    assert_eq!(at(&report, "exit_code").as_f64(), Some(0.0));
+    assert_eq!(at(&report, "error"), &Value::Null);
+    assert_eq!(at(&report, "settings.width").as_f64(), Some(16.0));
+    assert_eq!(at(&report, "settings.spp").as_f64(), Some(2.0));
+    assert_eq!(at(&report, "settings.tonemap").as_str(), Some("clamp"));
+    // No limit is written as null, there being no infinity in JSON.
+    assert_eq!(at(&report, "settings.clamp_indirect"), &Value::Null);
+    assert!(at(&report, "scene.objects").as_f64().unwrap() >= 1.0);
+    assert!(at(&report, "scene.bvh.depth").as_f64().unwrap() >= 1.0);
+    assert!(at(&report, "scene.memory_bytes.bvh").as_f64().is_some());
+    assert_eq!(at(&report, "stats.renders").as_array().unwrap().len(), 1);
+    assert_eq!(
+        at(&report, "stats.total.camera_rays").as_f64(),
+        Some((16 * 12 * 2) as f64)
+    );
+    assert!(at(&report, "stats.total.rays").as_f64().unwrap() >= 16.0 * 12.0 * 2.0);
+    assert!(at(&report, "stats.total.seconds").as_f64().unwrap() >= 0.0);
+    assert_eq!(at(&report, "warnings").as_array(), Some(&[][..]));
+
+    let outputs = at(&report, "outputs").as_array().unwrap();
+    assert_eq!(outputs.len(), 1);
+    let png = fs::read(dir.join("out.png")).unwrap();
+    let expected = Output::of(PathBuf::from("out.png"), &png);
+    assert_eq!(at(&outputs[0], "path").as_str(), Some("out.png"));
+    assert_eq!(at(&outputs[0], "bytes").as_f64(), Some(png.len() as f64));
+    assert_eq!(
+        at(&outputs[0], "hash").as_str().unwrap(),
+        format!("fnv1a64:{:016x}", expected.hash)
+    );
+}
+
+#[test]
+fn a_broken_scene_exits_with_its_own_code_and_is_reported_as_failed() {
+    let dir = scratch("broken");
+    fs::write(dir.join("broken.json"), "{\"spheres\": [").unwrap();
+    let args = ["--scene", "broken.json", "--report", "report.json"];
+    let (code, stderr) = render(&dir, &args);
+    assert_eq!(code, Some(3));
+    assert!(
+        stderr.starts_with("error: broken.json: line 1"),
+        "{}",
+        stderr
+    );
+    let report = read_report(&dir.join("report.json"));
+    assert_eq!(at(&report, "status").as_str(), Some("failed"));
+    assert_eq!(at(&report, "exit_code").as_f64(), Some(3.0));
+    assert!(at(&report, "error")
+        .as_str()
+        .unwrap()
+        .starts_with("broken.json: line 1"));
+    assert_eq!(at(&report, "scene"), &Value::Null);
+    assert_eq!(at(&report, "outputs").as_array(), Some(&[][..]));
+
+    // A scene that parses but doesn't make sense has a code of its own.
+    fs::write(
+        dir.join("invalid.json"),
+        r#"{"spheres": [{"center": [0, 0, -3], "radius": -1,
+            "material": {"type": "lambertian", "albedo": [0.5, 0.5, 0.5]}}]}"#,
+    )
+    .unwrap();
+    let args = ["--scene", "invalid.json", "--report", "report.json"];
+    let (code, stderr) = render(&dir, &args);
+    assert_eq!(code, Some(4), "{}", stderr);
+    assert_eq!(
+        stderr.lines().next(),
+        Some(
+            "error: invalid scene: invalid.json: spheres[0].radius: \
+             expected a positive number, found -1"
+        )
+    );
+    let report = read_report(&dir.join("report.json"));
+    assert_eq!(at(&report, "exit_code").as_f64(), Some(4.0));
+    fs::write(
+        dir.join("invalid.json"),
+        r#"{"lights": [{"type": "point", "position": [0, 5, 0], "color": [1, 1, 1],
+            "intensity": -3}]}"#,
+    )
+    .unwrap();
+    let (code, stderr) = render(&dir, &["--scene", "invalid.json"]);
+    assert_eq!(code, Some(4), "{}", stderr);
+    assert!(stderr.contains("lights[0].intensity"), "{}", stderr);
+
+    // Flags that make no sense are reported too, with the code they exit with.
+    let (code, stderr) = render(&dir, &["--part", "1/2", "--report", "-"]);
+    assert_eq!(code, Some(2));
+    let mut lines = stderr.lines();
+    assert_eq!(lines.next(), Some("error: --part needs --save-samples"));
+    let report = json::parse(lines.next().unwrap()).unwrap();
+    assert_eq!(at(&report, "exit_code").as_f64(), Some(2.0));
+
+    let (code, stderr) = render(&dir, &["--output", "missing/out.png"]);
+    assert_eq!(code, Some(5), "{}", stderr);
+}
+
+#[test]
+fn cancelled_runs_and_pixels_that_arent_numbers_are_reported() {
+    let report = Report {
+        warnings: vec!["a warning".to_string()],
+        ..Report::default()
+    };
+    let json = report.to_json(
+        &RenderSettings::default(),
+        Tonemap::Aces,
+        Err(&Error::Cancelled),
+    );
+    assert_eq!(at(&json, "status").as_str(), Some("cancelled"));
+    assert_eq!(at(&json, "exit_code").as_f64(), Some(130.0));
+    assert_eq!(at(&json, "settings.tonemap").as_str(), Some("aces"));
+    assert_eq!(at(&json, "stats.total.camera_rays").as_f64(), Some(0.0));
+    assert_eq!(
+        at(&json, "warnings"),
+        &Value::Array(vec![Value::String("a warning".to_string())])
+    );
+    // What's written is JSON that reads back the same.
+    assert_eq!(json::parse(&json.to_string()).unwrap(), json);
+
+    let mut image = Image::new(3, 1);
+    image.pixels[0] = Color::new(f64::NAN as _, 0.5, 0.5);
+    image.pixels[1] = Color::new(0.5, f64::INFINITY as _, 0.5);
+    image.pixels[2] = Color::new(0.5, 0.5, 0.5);
+    assert_eq!(image.flush_non_finite(), 2);
+    assert_eq!(image.pixels[0], Color::zero());
+    assert_eq!(image.pixels[1], Color::zero());
+    assert_eq!(image.pixels[2], Color::new(0.5, 0.5, 0.5));
+}
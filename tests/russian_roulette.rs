@@ -0,0 +1,80 @@
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::image::Image;
+use basic_raytracer::light::PointLight;
+use basic_raytracer::material::Lambertian;
+use basic_raytracer::render::{render, IntegratorKind, RenderSettings};
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene::Scene;
+use basic_raytracer::shapes::Plane;
+use basic_raytracer::vector::{Color, Vector};
+
+// A closed, almost perfectly white room: paths keep most of their energy for
+// many bounces, which is where fixed-depth tracing wastes the most work.
+fn white_room() -> Scene {
+    let mut scene = Scene::new(Camera::new(
+        Vector::new(0.0, 0.0, 0.8),
+        Vector::new(0.0, 0.0, 0.0),
+        60.0,
+    ));
+    let white = Arc::new(Lambertian::new(Color::new(0.9, 0.9, 0.9)));
+    for axis in &[
+        Vector::new(1.0, 0.0, 0.0),
+        Vector::new(0.0, 1.0, 0.0),
+        Vector::new(0.0, 0.0, 1.0),
+    ] {
+        scene.add(Plane::new(*axis, -*axis), white.clone());
+        scene.add(Plane::new(-*axis, *axis), white.clone());
+    }
+    scene.add_light(PointLight::new(
+        Vector::new(0.2, 0.5, 0.0),
+        Color::new(1.0, 1.0, 1.0),
+        1.0,
+    ));
+    scene
+}
+
+fn mean_brightness(image: &Image) -> Scalar {
+    let total: Scalar = image.pixels.iter().map(|c| c.x + c.y + c.z).sum();
+    total / (3.0 * image.pixels.len() as Scalar)
+}
+
+#[test]
+fn russian_roulette_keeps_brightness_and_traces_fewer_rays() {
+    let scene = white_room();
+    let settings = RenderSettings {
+        width: 16,
+        height: 16,
+        integrator: IntegratorKind::Path,
+        spp: 64,
+        max_depth: 32,
+        russian_roulette: None,
+        ..RenderSettings::default()
+    };
+    let (full, full_stats) = render(&scene, &settings).unwrap();
+    let (roulette, roulette_stats) = render(
+        &scene,
+        &RenderSettings {
+            russian_roulette: Some(2),
+            ..settings
+        },
+    )
+    .unwrap();
+
+    let expected = mean_brightness(&full);
+    let actual = mean_brightness(&roulette);
+    assert!(
+        (actual - expected).abs() < 0.03 * expected,
+        "mean brightness {} with roulette, {} without",
+        actual,
+        expected
+    );
+    assert_eq!(full_stats.camera_rays, roulette_stats.camera_rays);
+    assert!(
+        roulette_stats.rays * 2 < full_stats.rays,
+        "{} rays with roulette, {} without",
+        roulette_stats.rays,
+        full_stats.rays
+    );
+}
@@ -0,0 +1,112 @@
+use basic_raytracer::sampler::Sampler;
+use basic_raytracer::sampling::{
+    sample_cosine_hemisphere, sample_uniform_sphere, sample_unit_disk, OrthonormalBasis,
+};
+use basic_raytracer::scalar::{self, Scalar};
+use basic_raytracer::vector::Vector;
+
+/// How near to unit length a sampled direction must be, and how near its
+/// pdf to the density worked out by hand; the few sums that are exact in
+/// `f64` are held to the tighter of the two.
+#[cfg(not(feature = "f32"))]
+const TOLERANCE: Scalar = 1e-9;
+#[cfg(feature = "f32")]
+const TOLERANCE: Scalar = 1e-5;
+#[cfg(not(feature = "f32"))]
+const EXACT: Scalar = 1e-12;
+#[cfg(feature = "f32")]
+const EXACT: Scalar = 1e-6;
+
+const SAMPLES: u64 = 100_000;
+
+fn normals() -> Vec<Vector> {
+    let mut normals = vec![
+        Vector::new(1.0, 0.0, 0.0),
+        Vector::new(-1.0, 0.0, 0.0),
+        Vector::new(0.0, 1.0, 0.0),
+        Vector::new(0.0, -1.0, 0.0),
+        Vector::new(0.0, 0.0, 1.0),
+        Vector::new(0.0, 0.0, -1.0),
+        Vector::new(1e-9, 1e-9, -1.0).normalize(),
+    ];
+    let mut sampler = Sampler::new(1, 0, 0);
+    for _ in 0..8 {
+        normals.push(sample_uniform_sphere(sampler.next_scalar(), sampler.next_scalar()).0);
+    }
+    normals
+}
+
+#[test]
+fn basis_is_orthonormal_and_right_handed() {
+    for n in normals() {
+        let basis = OrthonormalBasis::from_normal(n);
+        for axis in &[basis.u, basis.v, basis.w] {
+            assert!(
+                (axis.len() - 1.0).abs() < TOLERANCE,
+                "{:?} from {:?}",
+                axis,
+                n
+            );
+        }
+        assert!((basis.u * basis.v).abs() < TOLERANCE);
+        assert!((basis.u * basis.w).abs() < TOLERANCE);
+        assert!((basis.v * basis.w).abs() < TOLERANCE);
+        assert!((basis.u.cross(basis.v) - n).len() < TOLERANCE);
+    }
+}
+
+#[test]
+fn cosine_samples_average_two_thirds_along_the_normal() {
+    for n in normals() {
+        let basis = OrthonormalBasis::from_normal(n);
+        let mut sampler = Sampler::new(2, 0, 0);
+        let mut sum = 0.0;
+        for _ in 0..SAMPLES {
+            let (local, pdf) =
+                sample_cosine_hemisphere(sampler.next_scalar(), sampler.next_scalar());
+            let direction = basis.to_world(local);
+            let cosine = direction * n;
+            assert!(cosine >= 0.0, "{:?} is below {:?}", direction, n);
+            assert!((direction.len() - 1.0).abs() < TOLERANCE);
+            assert!((pdf - cosine / scalar::consts::PI).abs() < TOLERANCE);
+            sum += cosine;
+        }
+        let mean = sum / SAMPLES as Scalar;
+        assert!(
+            (mean - 2.0 / 3.0).abs() < 0.005,
+            "mean {} for {:?}",
+            mean,
+            n
+        );
+    }
+}
+
+#[test]
+fn uniform_sphere_samples_are_unit_and_centered() {
+    let mut sampler = Sampler::new(3, 0, 0);
+    let mut sum = Vector::zero();
+    for _ in 0..SAMPLES {
+        let (direction, pdf) = sample_uniform_sphere(sampler.next_scalar(), sampler.next_scalar());
+        assert!((direction.len() - 1.0).abs() < TOLERANCE);
+        assert!((pdf - 1.0 / (4.0 * scalar::consts::PI)).abs() < EXACT);
+        sum += direction;
+    }
+    assert!(((1.0 / SAMPLES as Scalar) * sum).len() < 0.01);
+}
+
+#[test]
+fn unit_disk_samples_stay_inside_the_disk() {
+    let mut sampler = Sampler::new(4, 0, 0);
+    let mut inner = 0;
+    for _ in 0..SAMPLES {
+        let (point, pdf) = sample_unit_disk(sampler.next_scalar(), sampler.next_scalar());
+        assert!(point.len() <= 1.0 + EXACT);
+        assert_eq!(point.z, 0.0);
+        assert!((pdf - 1.0 / scalar::consts::PI).abs() < EXACT);
+        if point.len() < 0.5 {
+            inner += 1;
+        }
+    }
+    // A quarter of the area lies within half the radius.
+    assert!((inner as Scalar / SAMPLES as Scalar - 0.25).abs() < 0.01);
+}
@@ -0,0 +1,186 @@
+use std::path::Path;
+
+use basic_raytracer::json::{self, Value};
+use basic_raytracer::scene_file;
+
+#[test]
+fn parses_json_values() {
+    let value = json::parse(r#" {"a": [1, -2.5e1, true, null], "b": "é\n😀"} "#);
+    assert_eq!(
+        value,
+        Ok(Value::Object(vec![
+            (
+                "a".to_string(),
+                Value::Array(vec![
+                    Value::Number(1.0),
+                    Value::Number(-25.0),
+                    Value::Bool(true),
+                    Value::Null,
+                ]),
+            ),
+            ("b".to_string(), Value::String("é\n😀".to_string())),
+        ]))
+    );
+}
+
+#[test]
+fn reports_where_json_is_malformed() {
+    let error = json::parse("{\n  \"a\": [1, 2,]\n}").unwrap_err();
+    assert_eq!((error.line, error.column), (2, 14));
+    assert!(json::parse("[1] 2").is_err());
+    assert!(json::parse(r#"{"a": 1, "a": 2}"#).is_err());
+    assert!(json::parse("01").is_err());
+    assert!(json::parse(r#""unterminated"#).is_err());
+}
+
+#[test]
+fn loads_the_checker_scene() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes/checker.json");
+    let scene = scene_file::load(&path).unwrap();
+    assert_eq!(scene.objects.len(), 2);
+    assert_eq!(scene.lights.len(), 1);
+}
+
+#[test]
+fn loads_the_shapes_scene() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes/shapes.json");
+    let scene = scene_file::load(&path).unwrap();
+    assert_eq!(scene.objects.len(), 5);
+}
+
+#[test]
+fn loads_the_marble_scene() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes/marble.json");
+    let scene = scene_file::load(&path).unwrap();
+    assert_eq!(scene.objects.len(), 2);
+}
+
+#[test]
+fn loads_the_triplanar_scene() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes/triplanar.json");
+    let scene = scene_file::load(&path).unwrap();
+    assert_eq!(scene.objects.len(), 2);
+}
+
+#[test]
+fn image_paths_are_relative_to_the_scene_file() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes/globe.json");
+    assert!(scene_file::load(&path).is_ok());
+    // Looked for elsewhere, the image isn't there, and it's made magenta
+    // with a warning of where it was looked for, as tests/logging.rs checks.
+    assert!(scene_file::parse(
+        r#"{"spheres": [{"center": [0, 0, 0], "radius": 1, "material": {"type": "lambertian",
+            "albedo": {"type": "image", "path": "textures/lat_long_grid.png"}}}]}"#,
+        Path::new("nowhere"),
+    )
+    .is_ok());
+}
+
+#[test]
+fn image_wrap_modes_are_checked() {
+    let scenes = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes");
+    let parse = |albedo: &str| {
+        scene_file::parse(
+            &format!(
+                r#"{{"spheres": [{{"center": [0, 0, 0], "radius": 1,
+                    "material": {{"type": "lambertian", "albedo": {}}}}}]}}"#,
+                albedo
+            ),
+            &scenes,
+        )
+    };
+    let image = r#"{"type": "image", "path": "textures/lat_long_grid.png""#;
+    assert!(parse(&format!(
+        r#"{}, "wrap": "mirror", "filter": "nearest"}}"#,
+        image
+    ))
+    .is_ok());
+    assert!(parse(&format!(r#"{}, "wrap": ["clamp", "repeat"]}}"#, image)).is_ok());
+    assert_eq!(
+        parse(&format!(r#"{}, "wrap": ["clamp", "tile"]}}"#, image))
+            .err()
+            .unwrap(),
+        "spheres[0].material.albedo.wrap[1]: unknown wrap mode \"tile\""
+    );
+    assert_eq!(
+        parse(&format!(r#"{}, "wrap": ["clamp"]}}"#, image))
+            .err()
+            .unwrap(),
+        "spheres[0].material.albedo.wrap: expected a wrap mode or an array of two, found an array"
+    );
+}
+
+#[test]
+fn textures_take_uv_transforms() {
+    let scenes = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes");
+    let parse = |albedo: &str| {
+        scene_file::parse(
+            &format!(
+                r#"{{"quads": [{{"corner": [0, 0, 0], "u": [1, 0, 0], "v": [0, 1, 0],
+                    "material": {{"type": "lambertian", "albedo": {}}}}}]}}"#,
+                albedo
+            ),
+            &scenes,
+        )
+    };
+    assert!(parse(
+        r#"{"type": "image", "path": "textures/lat_long_grid.png",
+            "uv_scale": [4, 2], "uv_offset": [0.25, 0], "uv_rotate_deg": 45}"#
+    )
+    .is_ok());
+    assert!(parse(r#"{"type": "noise", "scale": 2, "uv_scale": 3}"#).is_ok());
+    assert_eq!(
+        parse(r#"{"type": "solid", "color": [1, 1, 1], "uv_offset": [1]}"#)
+            .err()
+            .unwrap(),
+        "quads[0].material.albedo.uv_offset: expected an array of two numbers, found an array"
+    );
+    assert_eq!(
+        parse(r#"{"type": "solid", "color": [1, 1, 1], "uv_rotate": 45}"#)
+            .err()
+            .unwrap(),
+        "quads[0].material.albedo: unknown member \"uv_rotate\""
+    );
+}
+
+#[test]
+fn emissive_quads_become_area_lights() {
+    let scene = scene_file::parse(
+        r#"{"quads": [{"corner": [0, 1, 0], "u": [1, 0, 0], "v": [0, 0, 1],
+                       "material": {"type": "emissive", "radiance": [4, 4, 4]}}]}"#,
+        Path::new(""),
+    )
+    .unwrap();
+    assert_eq!(scene.lights.len(), 1);
+    assert_eq!(scene.objects[0].light, Some(0));
+}
+
+#[test]
+fn errors_name_the_offending_member() {
+    let error = |text: &str| scene_file::parse(text, Path::new("")).err().unwrap();
+    assert_eq!(
+        error(r#"{"spheres": [{"center": [0, 0, 0], "radius": "1", "material": {}}]}"#),
+        "spheres[0].radius: expected a number, found a string"
+    );
+    assert_eq!(
+        error(r#"{"planes": [{"point": [0, 0], "normal": [0, 1, 0], "material": {}}]}"#),
+        "planes[0].point: expected an array of three numbers, found an array"
+    );
+    assert_eq!(
+        error(
+            r#"{"spheres": [{"center": [0, 0, 0], "radius": 1,
+                  "material": {"type": "lambertian", "albedo": {"type": "checker",
+                  "scale": 1, "odd": [0, 0, 0]}}}]}"#
+        ),
+        "spheres[0].material.albedo: missing member \"even\""
+    );
+    assert_eq!(
+        error(
+            r#"{"spheres": [{"center": [0, 0, 0], "radius": 1,
+                  "material": {"type": "lambertian", "albedo": {"type": "noise",
+                  "scale": 4, "seed": 1.5}}}]}"#
+        ),
+        "spheres[0].material.albedo.seed: expected a non-negative integer, found a number"
+    );
+    assert_eq!(error(r#"{"sphere": []}"#), "unknown member \"sphere\"");
+}
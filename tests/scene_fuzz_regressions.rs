@@ -0,0 +1,327 @@
+//! Inputs that crashed, hung or exhausted the memory of the scene file
+//! parsers under `fuzz/`, each kept here so that it stays an error.
+
+use std::fs;
+use std::path::Path;
+
+use basic_raytracer::error::Error;
+use basic_raytracer::json::{self, Value};
+use basic_raytracer::render::{render, RenderSettings, MAX_DEPTH, MAX_SPP};
+use basic_raytracer::scene::Scene;
+use basic_raytracer::scene_file;
+use png::HasParameters;
+
+fn parse(scene: &str) -> Result<(), String> {
+    scene_file::parse(scene, Path::new("")).map(|_| ())
+}
+
+fn error(scene: &str) -> String {
+    match parse(scene) {
+        Ok(()) => panic!("loaded {:.80}...", scene),
+        Err(error) => error,
+    }
+}
+
+const MATERIAL: &str = r#"{"type": "lambertian", "albedo": [0.5, 0.5, 0.5]}"#;
+
+#[test]
+fn deeply_nested_json_is_refused_before_the_stack_overflows() {
+    let text = format!("{}{}", "[".repeat(100_000), "]".repeat(100_000));
+    let error = json::parse(&text).unwrap_err();
+    assert_eq!((error.line, error.column), (1, json::MAX_DEPTH + 1));
+    assert!(error.message.contains("nest"), "{}", error);
+    let objects = r#"{"a": "#.repeat(100_000);
+    assert!(json::parse(&objects).is_err());
+    assert!(json::parse(&format!("{}0{}", "[".repeat(128), "]".repeat(128))).is_ok());
+}
+
+#[test]
+fn objects_with_many_members_parse_in_linear_time() {
+    // Checking each name against the ones before it took minutes.
+    let members: Vec<String> = (0..100_000).map(|i| format!("\"m{}\": {}", i, i)).collect();
+    let value = json::parse(&format!("{{{}}}", members.join(", "))).unwrap();
+    assert_eq!(value.as_object().unwrap().len(), 100_000);
+    let error = json::parse(r#"{"a": 1, "b": 2, "a": 3}"#).unwrap_err();
+    assert_eq!(error.message, "duplicate member \"a\"");
+}
+
+#[test]
+fn numbers_too_large_for_a_double_are_refused() {
+    // They read as infinity, which the writer couldn't write back.
+    let error = json::parse("[1, -1e400]").unwrap_err();
+    assert_eq!(
+        (error.column, error.message.as_str()),
+        (5, "number out of range")
+    );
+    assert_eq!(json::parse("1e-400"), Ok(Value::Number(0.0)));
+}
+
+#[test]
+fn a_long_chain_of_groups_is_refused() {
+    let groups: Vec<String> = (0..10_000)
+        .map(|i| {
+            format!(
+                r#"{{"name": "g{}", "children": [{{"group": "g{}"}}]}}"#,
+                i,
+                i + 1
+            )
+        })
+        .collect();
+    let scene = format!(
+        r#"{{"groups": [{}, {{"name": "g10000"}}], "nodes": [{{"group": "g0"}}]}}"#,
+        groups.join(", ")
+    );
+    let error = error(&scene);
+    assert!(
+        error.ends_with("scene graph nodes can't nest more than 64 deep"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn groups_that_multiply_their_objects_are_capped() {
+    // Each group places the next twice, for 2^40 spheres.
+    let groups: Vec<String> = (0..40)
+        .map(|i| {
+            format!(
+                r#"{{"name": "g{0}", "children": [{{"group": "g{1}"}}, {{"group": "g{1}"}}]}}"#,
+                i,
+                i + 1
+            )
+        })
+        .collect();
+    let scene = format!(
+        r#"{{"groups": [{}, {{"name": "g40", "spheres": [{{"center": [0, 0, 0], "radius": 1,
+            "material": {}}}]}}], "nodes": [{{"group": "g0"}}]}}"#,
+        groups.join(", "),
+        MATERIAL
+    );
+    let error = error(&scene);
+    assert!(
+        error
+            .ends_with("nodes[0].group: placing group \"g0\" would make more than 1048576 objects"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn deeply_nested_csg_is_refused() {
+    let sphere = r#"{"type": "sphere", "center": [0, 0, 0], "radius": 1}"#;
+    let mut solid = sphere.to_string();
+    for _ in 0..100 {
+        solid = format!(
+            r#"{{"type": "csg", "operation": "union", "a": {}, "b": {}}}"#,
+            solid, sphere
+        );
+    }
+    let scene = format!(
+        r#"{{"csg": [{{"operation": "union", "a": {}, "b": {}, "material": {}}}]}}"#,
+        solid, sphere, MATERIAL
+    );
+    let error = error(&scene);
+    assert!(error.starts_with("csg[0].a.a.a."), "{}", error);
+    assert!(
+        error.ends_with("CSG combinations can't nest more than 64 deep"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn distance_fields_cant_ask_for_endless_marching() {
+    let scene = |field: &str, steps: u64| {
+        format!(
+            r#"{{"sdfs": [{{"field": {}, "max_steps": {}, "material": {}}}]}}"#,
+            field, steps, MATERIAL
+        )
+    };
+    let sphere = r#"{"type": "sphere", "center": [0, 0, -3], "radius": 1}"#;
+    assert!(parse(&scene(sphere, 100_000)).is_ok());
+    assert_eq!(
+        error(&scene(sphere, 1_000_000_000_000)),
+        "sdfs[0].max_steps: expected at most 100000, found 1000000000000"
+    );
+    let bulb = r#"{"type": "mandelbulb", "center": [0, 0, -3], "iterations": 4000000000}"#;
+    assert_eq!(
+        error(&scene(bulb, 10)),
+        "sdfs[0].field.iterations: expected at most 1000, found 4000000000"
+    );
+}
+
+#[test]
+fn heightfield_rows_all_need_as_many_heights_as_the_first() {
+    // An empty first row went unnoticed, and the last row was dropped.
+    let scene = format!(
+        r#"{{"heightfields": [{{"heights": [[], [0, 0, 0], [0, 0, 0]], "corner": [0, 0, 0],
+            "size": [1, 1, 1], "material": {}}}]}}"#,
+        MATERIAL
+    );
+    assert_eq!(
+        error(&scene),
+        "heightfields[0].heights[1]: expected 0 heights"
+    );
+}
+
+#[test]
+fn images_too_big_to_decode_are_refused_from_their_header() {
+    // A header claiming 100000x100000 pixels, with no pixels after it.
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("fuzz_regressions");
+    fs::create_dir_all(&dir).unwrap();
+    let file = fs::File::create(dir.join("huge.png")).unwrap();
+    let mut encoder = png::Encoder::new(file, 100_000, 100_000);
+    encoder
+        .set(png::ColorType::Grayscale)
+        .set(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().unwrap();
+    // Just enough for the decoder to read the header.
+    writer.write_chunk(*b"IDAT", &[]).unwrap();
+    drop(writer);
+    let scene = format!(
+        r#"{{"heightfields": [{{"path": "huge.png", "corner": [0, 0, 0], "size": [1, 1, 1],
+            "material": {}}}]}}"#,
+        MATERIAL
+    );
+    let error = scene_file::parse(&scene, &dir).map(|_| ()).unwrap_err();
+    assert!(
+        error.ends_with("100000x100000 is more than 67108864 pixels"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn settings_too_big_to_render_are_refused() {
+    // A 200000x200000 render overflowed counting its pixels, or aborted
+    // allocating them.
+    let settings = |members: &str| {
+        let value = json::parse(&format!(r#"{{"settings": {{{}}}}}"#, members)).unwrap();
+        scene_file::settings(&value).map(|_| ())
+    };
+    assert_eq!(
+        settings(r#""width": 200000, "height": 200000"#),
+        Err("settings.height: the 200000x200000 image is more than 67108864 pixels".to_string())
+    );
+    assert_eq!(
+        settings(r#""width": 100000000000"#),
+        Err("settings.width: expected at most 67108864, found 100000000000".to_string())
+    );
+    assert_eq!(
+        settings(r#""spp": 4294967295"#),
+        Err("settings.spp: expected at most 1048576, found 4294967295".to_string())
+    );
+    assert_eq!(
+        settings(r#""max_depth": 1000000"#),
+        Err("settings.max_depth: expected at most 1024, found 1000000".to_string())
+    );
+    assert!(
+        settings(r#""width": 8192, "height": 8192, "spp": 1048576, "max_depth": 1024"#).is_ok()
+    );
+
+    // Settings from elsewhere, such as flags or the library, are checked
+    // before a render starts.
+    let check = |width, height, spp, max_depth| {
+        let settings = RenderSettings {
+            width,
+            height,
+            spp,
+            max_depth,
+            ..RenderSettings::default()
+        };
+        settings.validate().map_err(|error| error.to_string())
+    };
+    assert_eq!(
+        check(200_000, 200_000, 1, 8),
+        Err(
+            "invalid render settings: the 200000x200000 image is more than 67108864 pixels"
+                .to_string()
+        )
+    );
+    assert!(check(u32::MAX, u32::MAX, 1, 8).is_err());
+    assert_eq!(
+        check(64, 64, u32::MAX, 8),
+        Err("invalid render settings: spp must be at most 1048576".to_string())
+    );
+    assert_eq!(
+        check(64, 64, 1, u32::MAX),
+        Err("invalid render settings: max_depth must be at most 1024".to_string())
+    );
+    assert_eq!(check(8192, 8192, MAX_SPP, MAX_DEPTH), Ok(()));
+    let error = render(
+        &Scene::demo(),
+        &RenderSettings {
+            width: 200_000,
+            height: 200_000,
+            ..RenderSettings::default()
+        },
+    );
+    assert!(matches!(error, Err(Error::InvalidSettings(_))));
+}
+
+#[test]
+fn transforms_too_small_to_invert_are_refused() {
+    // Scales short of zero, alone or multiplied out through groups, made a
+    // matrix `Transformed::new` panicked inverting.
+    let sphere = |transform: &str| {
+        format!(
+            r#"{{"center": [0, 0, 0], "radius": 1, "material": {}, "transform": {}}}"#,
+            MATERIAL, transform
+        )
+    };
+    let message = "the transform can't be inverted; is a scale too close to zero?";
+    let scene = format!(r#"{{"spheres": [{}]}}"#, sphere(r#"{"scale": 1e-13}"#));
+    assert_eq!(error(&scene), format!("spheres[0].transform: {}", message));
+    let scene = format!(
+        r#"{{"nodes": [{{"transform": {{"scale": 1e-5}}, "children": [{{"transform": {{"scale": 1e-5}},
+            "spheres": [{}]}}]}}]}}"#,
+        sphere(r#"{"scale": [1e-5, 1, 1]}"#)
+    );
+    assert_eq!(
+        error(&scene),
+        format!("nodes[0].children[0].spheres[0]: {}", message)
+    );
+    let scene = format!(
+        r#"{{"csg": [{{"operation": "union", "material": {},
+            "a": {{"type": "sphere", "center": [0, 0, 0], "radius": 1, "transform": {{"scale": 1e-13}}}},
+            "b": {{"type": "sphere", "center": [1, 0, 0], "radius": 1}}}}]}}"#,
+        MATERIAL
+    );
+    assert_eq!(error(&scene), format!("csg[0].a.transform: {}", message));
+    let scene = format!(
+        r#"{{"spheres": [{}]}}"#,
+        sphere(r#"[{"time": 0}, {"time": 1, "scale": 1e-13}]"#)
+    );
+    assert_eq!(
+        error(&scene),
+        format!("spheres[0].transform[1]: {}", message)
+    );
+    let scene = format!(
+        r#"{{"spheres": [{}]}}"#,
+        sphere(r#"[{"time": 0, "scale": 1}, {"time": 1, "scale": [1, -1, 1]}]"#)
+    );
+    assert_eq!(
+        error(&scene),
+        "spheres[0].transform: a keyframed scale can't change sign"
+    );
+    let scene = format!(
+        r#"{{"nodes": [{{"transform": {{"scale": 1e-7}}, "spheres": [{}]}}]}}"#,
+        sphere(r#"[{"time": 0}, {"time": 1, "scale": 1e-7}]"#)
+    );
+    assert_eq!(error(&scene), format!("nodes[0].spheres[0]: {}", message));
+    let scene = format!(
+        r#"{{"geometry": [{{"name": "cube", "path": "scenes/models/cube.obj"}}],
+            "nodes": [{{"transform": {{"scale": 1e-7}}, "instances": [{{"geometry": "cube",
+            "material": {}, "transform": {{"scale": 1e-7}}}}]}}]}}"#,
+        MATERIAL
+    );
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let error = scene_file::parse(&scene, root).map(|_| ()).unwrap_err();
+    assert_eq!(error, format!("nodes[0].instances[0]: {}", message));
+    // Small scales that can still be undone are fine.
+    assert!(parse(&format!(
+        r#"{{"spheres": [{}]}}"#,
+        sphere(r#"{"scale": 1e-6}"#)
+    ))
+    .is_ok());
+}
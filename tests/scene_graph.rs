@@ -0,0 +1,111 @@
+use std::path::Path;
+
+use basic_raytracer::ray::Ray;
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene::Scene;
+use basic_raytracer::scene_file;
+use basic_raytracer::vector::Vector;
+
+fn parse(text: &str) -> Result<Scene, String> {
+    scene_file::parse(text, Path::new(""))
+}
+
+/// Where the ray from `origin` along `direction` first meets the scene.
+fn first_hit(scene: &Scene, origin: Vector, direction: Vector) -> Option<Vector> {
+    let ray = Ray::new(origin, direction);
+    scene
+        .intersect(&ray, 1e-4, Scalar::INFINITY)
+        .map(|hit| hit.record.point)
+}
+
+const SPHERE: &str = r#"{"center": [0, 0, 0], "radius": 0.5,
+    "material": {"type": "lambertian", "albedo": [1, 1, 1]}}"#;
+
+#[test]
+fn stacked_translations_compose() {
+    let scene = parse(&format!(
+        r#"{{"nodes": [{{"transform": {{"translate": [1, 0, 0]}},
+            "children": [{{"transform": {{"translate": [0, 2, 0]}}, "spheres": [{}]}}]}}]}}"#,
+        SPHERE
+    ))
+    .unwrap();
+    let down = Vector::new(0.0, -1.0, 0.0);
+    let top = first_hit(&scene, Vector::new(1.0, 5.0, 0.0), down).unwrap();
+    assert!((top - Vector::new(1.0, 2.5, 0.0)).len() < 1e-9, "{:?}", top);
+    assert!(first_hit(&scene, Vector::new(0.0, 5.0, 0.0), down).is_none());
+}
+
+#[test]
+fn parents_transform_their_children_after_them() {
+    // Turning the parent a quarter about z swings the child's offset from x
+    // onto y, and its scale doubles the offset and the radius.
+    let scene = parse(&format!(
+        r#"{{"nodes": [{{"transform": {{"rotate_deg": [0, 0, 90], "scale": 2}},
+            "spheres": [{{"center": [1, 0, 0], "radius": 0.25,
+                "material": {{"type": "dielectric", "ior": 1.5}}}}],
+            "children": [{{"transform": {{"translate": [0, 0, 3]}}, "spheres": [{}]}}]}}]}}"#,
+        SPHERE
+    ))
+    .unwrap();
+    assert_eq!(scene.objects.len(), 2);
+    let down = Vector::new(0.0, 0.0, -1.0);
+    let near = first_hit(&scene, Vector::new(0.0, 2.0, 10.0), down).unwrap();
+    assert!(
+        (near - Vector::new(0.0, 2.0, 0.5)).len() < 1e-9,
+        "{:?}",
+        near
+    );
+    let stacked = first_hit(&scene, Vector::new(0.0, 0.0, 10.0), down).unwrap();
+    assert!(
+        (stacked - Vector::new(0.0, 0.0, 7.0)).len() < 1e-9,
+        "{:?}",
+        stacked
+    );
+}
+
+#[test]
+fn groups_are_placed_by_name() {
+    let scene = parse(&format!(
+        r#"{{"groups": [
+                {{"name": "bulb", "spheres": [{}]}},
+                {{"name": "lamp", "children": [
+                    {{"group": "bulb", "transform": {{"translate": [0, 1, 0]}}}},
+                    {{"group": "bulb", "transform": {{"translate": [0, -1, 0]}}}}]}}],
+            "nodes": [
+                {{"group": "lamp", "transform": {{"translate": [-2, 0, 0]}}}},
+                {{"group": "lamp", "transform": {{"translate": [2, 0, 0]}}}}]}}"#,
+        SPHERE
+    ))
+    .unwrap();
+    assert_eq!(scene.objects.len(), 4);
+    for &(x, y) in &[(-2.0, 1.0), (-2.0, -1.0), (2.0, 1.0), (2.0, -1.0)] {
+        let hit = first_hit(&scene, Vector::new(x, y, 5.0), Vector::new(0.0, 0.0, -1.0));
+        assert!((hit.unwrap() - Vector::new(x, y, 0.5)).len() < 1e-9);
+    }
+}
+
+#[test]
+fn groups_cannot_contain_themselves() {
+    let error = parse(
+        r#"{"groups": [
+                {"name": "a", "children": [{"group": "b"}]},
+                {"name": "b", "children": [{"children": [{"group": "a"}]}]}],
+            "nodes": [{"group": "a"}]}"#,
+    )
+    .err()
+    .unwrap();
+    assert_eq!(
+        error,
+        "groups[1].children[0].children[0].group: group \"a\" contains itself: \"a\" -> \"b\" -> \"a\""
+    );
+    assert_eq!(
+        parse(r#"{"nodes": [{"group": "c"}]}"#).err().unwrap(),
+        "nodes[0].group: no group named \"c\""
+    );
+    assert_eq!(
+        parse(r#"{"nodes": [{"group": "c", "spheres": []}]}"#)
+            .err()
+            .unwrap(),
+        "nodes[0]: unknown member \"spheres\""
+    );
+}
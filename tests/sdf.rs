@@ -0,0 +1,263 @@
+use std::path::Path;
+
+use basic_raytracer::matrix::Matrix4;
+use basic_raytracer::ray::Ray;
+use basic_raytracer::sampler::Sampler;
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene_file;
+use basic_raytracer::shapes::{
+    Csg, Cuboid, Mandelbulb, RoundedBox, SdfShape, Shape, SmoothUnion, Sphere, Torus, Transformed,
+};
+use basic_raytracer::vector::Vector;
+
+/// How far past the analytic sphere's hit a traced one may be, as that's
+/// only exact up to rounding; `f32` rounds at about 1e-7 of the distance.
+#[cfg(not(feature = "f32"))]
+const ROUNDING: Scalar = 0.0;
+#[cfg(feature = "f32")]
+const ROUNDING: Scalar = 1e-5;
+
+fn sphere_sdf(center: Vector, radius: Scalar) -> SdfShape {
+    SdfShape::new(Sphere::new(center, radius))
+}
+
+#[test]
+fn traced_spheres_match_analytic_ones() {
+    let center = Vector::new(0.3, -0.2, 0.1);
+    let analytic = Sphere::new(center, 1.0);
+    let traced = sphere_sdf(center, 1.0);
+    let mut sampler = Sampler::new(3, 0, 0);
+    let mut hits = 0;
+    for _ in 0..500 {
+        let origin = Vector::new(
+            6.0 * sampler.next_scalar() - 3.0,
+            6.0 * sampler.next_scalar() - 3.0,
+            4.0,
+        );
+        let target = Vector::new(
+            sampler.next_scalar() - 0.5,
+            sampler.next_scalar() - 0.5,
+            0.0,
+        );
+        // Unnormalized directions step in units of their length.
+        let ray = Ray::new(origin, 2.0 * (target - origin).normalize());
+        let expected = analytic.intersect(&ray, 1e-4, Scalar::INFINITY);
+        let found = traced.intersect(&ray, 1e-4, Scalar::INFINITY);
+        match (expected, found) {
+            (Some(expected), Some(found)) => {
+                hits += 1;
+                // The hit is within epsilon of the surface, though at a
+                // glancing angle that can be further along the ray.
+                assert!(((found.point - center).len() - 1.0).abs() < traced.epsilon);
+                assert!(found.t <= expected.t + ROUNDING);
+                assert!((expected.normal - found.normal).len() < 1e-4);
+                assert!(found.front_face);
+            }
+            (None, None) => {}
+            // Rays grazing the edge can run out of steps.
+            (Some(expected), None) => assert!(expected.normal * ray.direction > -0.05),
+            (None, Some(found)) => panic!("phantom hit at {:?}", found.point),
+        }
+    }
+    assert!(hits > 100, "{}", hits);
+}
+
+#[test]
+fn tracing_respects_the_ray_range() {
+    let shape = sphere_sdf(Vector::zero(), 1.0);
+    let ray = Ray::new(Vector::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, -1.0));
+    assert!(shape.intersect(&ray, 1e-4, 3.9).is_none());
+    let far = shape.intersect(&ray, 4.5, Scalar::INFINITY).unwrap();
+    assert!((far.t - 6.0).abs() < 1e-4);
+    assert!(!far.front_face);
+    assert!((far.normal - Vector::new(0.0, 0.0, 1.0)).len() < 1e-4);
+
+    // A ray leaving the surface finds the far side, not where it started.
+    let near = shape.intersect(&ray, 1e-4, Scalar::INFINITY).unwrap();
+    let inside = Ray::new(near.point, ray.direction);
+    let exit = shape.intersect(&inside, 1e-4, Scalar::INFINITY).unwrap();
+    assert!((exit.point.z + 1.0).abs() < 1e-4, "{:?}", exit.point);
+
+    let ts: Vec<Scalar> = shape.intersect_all(&ray).iter().map(|hit| hit.t).collect();
+    assert_eq!(ts.len(), 2, "{:?}", ts);
+    assert!((ts[0] - 4.0).abs() < 1e-4 && (ts[1] - 6.0).abs() < 1e-4);
+}
+
+#[test]
+fn smooth_unions_blend_across_the_gap() {
+    let (left, right) = (Vector::new(-0.6, 0.0, 0.0), Vector::new(0.6, 0.0, 0.0));
+    let plain = SdfShape::new(SmoothUnion::new(
+        Sphere::new(left, 0.5),
+        Sphere::new(right, 0.5),
+        0.0,
+    ));
+    let blend = SdfShape::new(SmoothUnion::new(
+        Sphere::new(left, 0.5),
+        Sphere::new(right, 0.5),
+        0.5,
+    ));
+    let down = Vector::new(0.0, -1.0, 0.0);
+    // Between the spheres the plain union has a gap, which the blend fills.
+    let gap = Ray::new(Vector::new(0.0, 5.0, 0.0), down);
+    assert!(plain.intersect(&gap, 1e-4, Scalar::INFINITY).is_none());
+    let bridge = blend.intersect(&gap, 1e-4, Scalar::INFINITY).unwrap();
+    assert!((bridge.normal - Vector::new(0.0, 1.0, 0.0)).len() < 1e-4);
+    // Far from the gap the blend leaves the spheres alone.
+    let top = Ray::new(Vector::new(-0.9, 5.0, 0.0), down);
+    let (a, b) = (
+        plain.intersect(&top, 1e-4, Scalar::INFINITY).unwrap(),
+        blend.intersect(&top, 1e-4, Scalar::INFINITY).unwrap(),
+    );
+    assert!((a.t - b.t).abs() < 1e-4);
+    // The surface rises smoothly from the bridge onto the spheres, with no
+    // steps between neighboring rays.
+    let mut last = bridge.point.y;
+    for i in 1..=20 {
+        let x = 0.6 * i as Scalar / 20.0;
+        let hit = blend
+            .intersect(&Ray::new(Vector::new(x, 5.0, 0.0), down), 1e-4, 10.0)
+            .unwrap();
+        assert!(hit.point.y >= last - 1e-6 && hit.point.y - last < 0.06);
+        last = hit.point.y;
+    }
+    assert!((last - 0.5).abs() < 1e-4);
+}
+
+#[test]
+fn built_in_fields_have_the_expected_shapes() {
+    let down = Vector::new(0.0, -1.0, 0.0);
+    let torus = SdfShape::new(Torus::new(Vector::zero(), 1.0, 0.25));
+    let through = Ray::new(Vector::new(0.0, 5.0, 0.0), down);
+    assert!(torus.intersect(&through, 1e-4, Scalar::INFINITY).is_none());
+    let ring = torus
+        .intersect(
+            &Ray::new(Vector::new(1.0, 5.0, 0.0), down),
+            1e-4,
+            Scalar::INFINITY,
+        )
+        .unwrap();
+    assert!((ring.point.y - 0.25).abs() < 1e-4);
+
+    let rounded = SdfShape::new(RoundedBox::new(
+        Vector::zero(),
+        Vector::new(1.0, 0.5, 0.5),
+        0.2,
+    ));
+    let face = rounded.intersect(&through, 1e-4, Scalar::INFINITY).unwrap();
+    assert!((face.point.y - 0.5).abs() < 1e-4);
+    // The corner is cut back along the diagonal by (√3 - 1) times the radius.
+    let diagonal = Vector::new(1.0, 1.0, 1.0).normalize();
+    let corner_ray = Ray::new(Vector::new(1.0, 0.5, 0.5) + 3.0 * diagonal, -diagonal);
+    let corner = rounded
+        .intersect(&corner_ray, 1e-4, Scalar::INFINITY)
+        .unwrap();
+    assert!((corner.t - 3.0 - 0.2 * (Scalar::sqrt(3.0) - 1.0)).abs() < 1e-4);
+
+    let bulb = SdfShape::new(Mandelbulb::new(Vector::zero(), 8.0, 10));
+    let hit = bulb
+        .intersect(
+            &Ray::new(Vector::new(0.0, 0.0, 3.0), Vector::new(0.0, 0.0, -1.0)),
+            1e-4,
+            Scalar::INFINITY,
+        )
+        .unwrap();
+    assert!(
+        hit.point.len() < 1.2 && hit.point.len() > 0.5,
+        "{:?}",
+        hit.point
+    );
+}
+
+#[test]
+fn distance_fields_work_inside_other_shapes() {
+    // Stretching the traced sphere into an ellipsoid and cutting it in half.
+    let ellipsoid = Transformed::new(
+        sphere_sdf(Vector::zero(), 1.0),
+        Matrix4::scale(Vector::new(2.0, 1.0, 1.0)),
+    );
+    let right = Vector::new(1.0, 0.0, 0.0);
+    let side = ellipsoid
+        .intersect(
+            &Ray::new(Vector::new(-5.0, 0.0, 0.0), right),
+            1e-4,
+            Scalar::INFINITY,
+        )
+        .unwrap();
+    assert!((side.t - 3.0).abs() < 1e-4);
+    let half = Csg::difference(
+        ellipsoid,
+        Cuboid::new(Vector::new(0.0, -2.0, -2.0), Vector::new(3.0, 2.0, 2.0)),
+    );
+    let ts: Vec<Scalar> = half
+        .intersect_all(&Ray::new(Vector::new(-5.0, 0.0, 0.0), right))
+        .iter()
+        .map(|hit| hit.t)
+        .collect();
+    assert_eq!(ts.len(), 2, "{:?}", ts);
+    assert!((ts[0] - 3.0).abs() < 1e-4 && (ts[1] - 5.0).abs() < 1e-9);
+
+    // A closure is a field too, though without bounds it marches from
+    // wherever the ray starts.
+    let slab = SdfShape::new(|point: Vector| point.y.abs() - 0.5);
+    let hit = slab
+        .intersect(
+            &Ray::new(Vector::new(0.0, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0)),
+            1e-4,
+            Scalar::INFINITY,
+        )
+        .unwrap();
+    assert!((hit.t - 4.5).abs() < 1e-4);
+    assert!(slab.bounds().is_none());
+}
+
+#[test]
+fn scene_files_use_named_presets() {
+    let parse = |sdf: &str| {
+        scene_file::parse(
+            &format!(
+                r#"{{"sdfs": [{}]}}"#,
+                sdf.replace(
+                    "MATERIAL",
+                    r#""material": {"type": "lambertian", "albedo": [1, 1, 1]}"#
+                )
+            ),
+            Path::new(""),
+        )
+    };
+    let scene = parse(
+        r#"{"field": {"type": "smooth_union", "smoothness": 0.3,
+            "a": {"type": "sphere", "center": [-0.6, 0, 0], "radius": 0.5},
+            "b": {"type": "torus", "center": [0.6, 0, 0], "major_radius": 0.5,
+                  "minor_radius": 0.1}},
+            "max_steps": 64, "epsilon": 1e-4, "transform": {"translate": [0, 1, 0]},
+            MATERIAL}"#,
+    )
+    .unwrap();
+    let hit = scene
+        .intersect(
+            &Ray::new(Vector::new(-0.6, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0)),
+            1e-4,
+            Scalar::INFINITY,
+        )
+        .unwrap();
+    assert!((hit.record.point.y - 1.5).abs() < 1e-3);
+
+    assert_eq!(
+        parse(r#"{"field": {"type": "cube"}, MATERIAL}"#)
+            .err()
+            .unwrap(),
+        "sdfs[0].field: unknown distance field type \"cube\""
+    );
+    assert_eq!(
+        parse(r#"{"field": {"type": "mandelbulb", "center": [0, 0, 0]}, "epsilon": 0, MATERIAL}"#)
+            .err()
+            .unwrap(),
+        "sdfs[0].epsilon: expected a positive number"
+    );
+    assert_eq!(
+        parse(r#"{"field": {"type": "torus", "center": [0, 0, 0], "major_radius": 1}, MATERIAL}"#)
+            .err()
+            .unwrap(),
+        "sdfs[0].field: missing member \"minor_radius\""
+    );
+}
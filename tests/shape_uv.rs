@@ -0,0 +1,299 @@
+use basic_raytracer::obj;
+use basic_raytracer::ray::Ray;
+use basic_raytracer::sampler::Sampler;
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::shapes::{
+    Cone, Cuboid, Cylinder, HitRecord, MeshTriangle, Plane, Quad, Shape, Triangle, TriangleMesh,
+};
+use basic_raytracer::vector::Vector;
+
+/// How near coordinates must be to those expected, and how far inside a
+/// face's corner a ray is aimed: a step `f32` can tell from the corner.
+#[cfg(not(feature = "f32"))]
+const TOLERANCE: Scalar = 1e-9;
+#[cfg(feature = "f32")]
+const TOLERANCE: Scalar = 1e-5;
+#[cfg(not(feature = "f32"))]
+const INSIDE: Scalar = 1e-6;
+#[cfg(feature = "f32")]
+const INSIDE: Scalar = 1e-3;
+
+fn close(a: (Scalar, Scalar), b: (Scalar, Scalar)) -> bool {
+    (a.0 - b.0).abs() < TOLERANCE && (a.1 - b.1).abs() < TOLERANCE
+}
+
+/// Hits `shape` at `target` with a ray from `from`.
+fn hit_at(shape: &dyn Shape, from: Vector, target: Vector) -> HitRecord {
+    let ray = Ray::new(from, (target - from).normalize());
+    shape
+        .intersect(&ray, 1e-9, Scalar::INFINITY)
+        .expect("ray should hit the shape")
+}
+
+fn assert_uv(shape: &dyn Shape, from: Vector, target: Vector, uv: (Scalar, Scalar)) {
+    let hit = hit_at(shape, from, target);
+    assert!(
+        close(hit.uv, uv),
+        "uv {:?} at {:?}, expected {:?}",
+        hit.uv,
+        target,
+        uv
+    );
+}
+
+#[test]
+fn box_faces_run_left_to_right_and_top_to_bottom() {
+    let cuboid = Cuboid::new(Vector::zero(), Vector::new(1.0, 1.0, 1.0));
+    // Just inside the corners of the +x face, seen from +x.
+    let from = Vector::new(5.0, 0.5, 0.5);
+    let e = INSIDE;
+    assert_uv(&cuboid, from, Vector::new(1.0, 1.0 - e, 1.0 - e), (e, e));
+    assert_uv(&cuboid, from, Vector::new(1.0, e, e), (1.0 - e, 1.0 - e));
+    assert_uv(&cuboid, from, Vector::new(1.0, 1.0 - e, e), (1.0 - e, e));
+    // The -x face, seen from -x, has +z on the right.
+    let from = Vector::new(-5.0, 0.5, 0.5);
+    assert_uv(&cuboid, from, Vector::new(0.0, 1.0 - e, e), (e, e));
+    // The top, seen from above, has -z up.
+    let from = Vector::new(0.5, 5.0, 0.5);
+    assert_uv(&cuboid, from, Vector::new(e, 1.0, e), (e, e));
+    assert_uv(&cuboid, from, Vector::new(0.75, 1.0, 0.25), (0.75, 0.25));
+    // The bottom, seen from below, has +z up.
+    let from = Vector::new(0.5, -5.0, 0.5);
+    assert_uv(&cuboid, from, Vector::new(0.75, 0.0, 0.25), (0.75, 0.75));
+}
+
+#[test]
+fn box_normals_point_out_of_each_face() {
+    let cuboid = Cuboid::new(Vector::new(-1.0, -2.0, -3.0), Vector::new(1.0, 2.0, 3.0));
+    for (from, normal) in [
+        (Vector::new(5.0, 0.1, 0.2), Vector::new(1.0, 0.0, 0.0)),
+        (Vector::new(0.1, -5.0, 0.2), Vector::new(0.0, -1.0, 0.0)),
+        (Vector::new(0.1, 0.2, 7.0), Vector::new(0.0, 0.0, 1.0)),
+    ] {
+        let hit = hit_at(&cuboid, from, Vector::zero());
+        assert_eq!(hit.normal, normal);
+        assert!(hit.front_face);
+    }
+    // From inside, the hit is on the far face and seen from behind.
+    let hit = hit_at(&cuboid, Vector::zero(), Vector::new(0.0, 0.0, 1.0));
+    assert!(!hit.front_face);
+    assert_eq!(hit.t, 3.0);
+}
+
+#[test]
+fn quad_uv_is_the_fraction_of_each_edge() {
+    let quad = Quad::new(
+        Vector::new(1.0, 0.0, 0.0),
+        Vector::new(2.0, 0.0, 0.0),
+        Vector::new(0.0, 4.0, 0.0),
+    );
+    let from = Vector::new(0.0, 0.0, 5.0);
+    assert_uv(&quad, from, Vector::new(1.5, 3.0, 0.0), (0.25, 0.75));
+}
+
+#[test]
+fn floor_plane_uv_follows_x_and_z() {
+    let plane = Plane::new(Vector::new(1.0, 0.0, 1.0), Vector::new(0.0, 1.0, 0.0));
+    let from = Vector::new(0.0, 5.0, 0.0);
+    assert_uv(&plane, from, Vector::new(3.5, 0.0, -2.0), (2.5, -3.0));
+}
+
+#[test]
+fn cylinder_side_maps_angle_and_height() {
+    let cylinder = Cylinder::new(Vector::new(0.0, 1.0, 0.0), 0.5, 2.0);
+    assert_uv(
+        &cylinder,
+        Vector::new(0.0, 1.5, 5.0),
+        Vector::new(0.0, 1.5, 0.5),
+        (0.5, 0.75),
+    );
+    assert_uv(
+        &cylinder,
+        Vector::new(5.0, 2.5, 0.0),
+        Vector::new(0.5, 2.5, 0.0),
+        (0.75, 0.25),
+    );
+    let hit = hit_at(
+        &cylinder,
+        Vector::new(5.0, 2.5, 0.0),
+        Vector::new(0.5, 2.5, 0.0),
+    );
+    assert!(close((hit.normal.x, hit.normal.y), (1.0, 0.0)));
+}
+
+#[test]
+fn cylinder_caps_map_like_box_faces() {
+    let cylinder = Cylinder::new(Vector::zero(), 1.0, 1.0);
+    let top = hit_at(
+        &cylinder,
+        Vector::new(0.5, 5.0, -0.5),
+        Vector::new(0.5, 1.0, -0.5),
+    );
+    assert_eq!(top.normal, Vector::new(0.0, 1.0, 0.0));
+    assert!(close(top.uv, (0.75, 0.25)));
+    let bottom = hit_at(
+        &cylinder,
+        Vector::new(0.5, -5.0, -0.5),
+        Vector::new(0.5, 0.0, -0.5),
+    );
+    assert_eq!(bottom.normal, Vector::new(0.0, -1.0, 0.0));
+    assert!(close(bottom.uv, (0.75, 0.75)));
+}
+
+#[test]
+fn cone_side_maps_angle_and_height() {
+    let cone = Cone::new(Vector::zero(), 1.0, 2.0);
+    // Halfway up, the radius is 0.5.
+    let hit = hit_at(
+        &cone,
+        Vector::new(0.0, 1.0, 5.0),
+        Vector::new(0.0, 1.0, 0.5),
+    );
+    assert!(close(hit.uv, (0.5, 0.5)));
+    assert!((hit.point.z - 0.5).abs() < TOLERANCE);
+    let expected = Vector::new(0.0, 0.5, 1.0).normalize();
+    assert!(
+        (hit.normal - expected).len() < TOLERANCE,
+        "normal {:?}",
+        hit.normal
+    );
+    let base = hit_at(
+        &cone,
+        Vector::new(0.2, -5.0, 0.0),
+        Vector::new(0.2, 0.0, 0.0),
+    );
+    assert_eq!(base.normal, Vector::new(0.0, -1.0, 0.0));
+    assert!(close(base.uv, (0.6, 0.5)));
+    assert!(cone
+        .intersect(
+            &Ray::new(Vector::new(0.0, 2.5, 5.0), Vector::new(0.0, 0.0, -1.0)),
+            1e-9,
+            Scalar::INFINITY
+        )
+        .is_none());
+}
+
+#[test]
+fn triangles_fall_back_to_barycentrics() {
+    let (a, b, c) = (
+        Vector::zero(),
+        Vector::new(1.0, 0.0, 0.0),
+        Vector::new(0.0, 1.0, 0.0),
+    );
+    let target = Vector::new(0.25, 0.5, 0.0);
+    let from = Vector::new(0.25, 0.5, 3.0);
+    assert_uv(&Triangle::new(a, b, c), from, target, (0.25, 0.5));
+    let mesh = TriangleMesh::new(
+        vec![a, b, c],
+        Vec::new(),
+        vec![MeshTriangle {
+            positions: [0, 1, 2],
+            uvs: None,
+        }],
+    );
+    assert_uv(&mesh, from, target, (0.25, 0.5));
+}
+
+#[test]
+fn mesh_uvs_are_interpolated() {
+    let mesh = TriangleMesh::new(
+        vec![
+            Vector::zero(),
+            Vector::new(1.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ],
+        vec![(0.5, 0.5), (1.0, 0.5), (0.5, 0.0)],
+        vec![MeshTriangle {
+            positions: [0, 1, 2],
+            uvs: Some([0, 1, 2]),
+        }],
+    );
+    let hit = hit_at(
+        &mesh,
+        Vector::new(0.5, 0.5, 3.0),
+        Vector::new(0.5, 0.5, 0.0),
+    );
+    assert!(close(hit.uv, (0.75, 0.25)));
+    assert_eq!(hit.normal, Vector::new(0.0, 0.0, 1.0));
+}
+
+#[test]
+fn obj_faces_flip_v_and_split_into_fans() {
+    let mesh = obj::parse(
+        "# a square\n\
+         v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n\
+         vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\n\
+         vn 0 0 1\n\
+         f 1/1/1 2/2/1 3/3/1 -1/-1/1\n\
+         f 1 2 3\n",
+    )
+    .unwrap();
+    assert_eq!(mesh.triangles.len(), 3);
+    assert_eq!(mesh.triangles[1].positions, [0, 2, 3]);
+    assert_eq!(mesh.triangles[1].uvs, Some([0, 2, 3]));
+    assert_eq!(mesh.triangles[2].uvs, None);
+    assert_eq!(mesh.uvs[0], (0.0, 1.0));
+    // The top left of the square is the top left of the texture.
+    let hit = hit_at(
+        &mesh,
+        Vector::new(0.1, 0.9, 3.0),
+        Vector::new(0.1, 0.9, 0.0),
+    );
+    assert!(close(hit.uv, (0.1, 0.1)));
+}
+
+#[test]
+fn obj_errors_name_the_line() {
+    let error = |text: &str| obj::parse(text).err().unwrap();
+    assert_eq!(error("v 0 0 0\nf 1 2 3\n"), "line 2: invalid vertex \"2\"");
+    assert_eq!(error("v 0 zero 0\n"), "line 1: invalid number \"zero\"");
+    assert_eq!(
+        error("v 0 0 0\nv 1 0 0\nf 1 2\n"),
+        "line 3: a face needs at least three vertices"
+    );
+}
+
+#[test]
+fn mesh_hits_match_its_triangles() {
+    let mut sampler = Sampler::new(3, 0, 0);
+    let mut random = || 4.0 * sampler.next_scalar() - 2.0;
+    let mut positions = Vec::new();
+    let mut triangles = Vec::new();
+    for i in 0..200 {
+        let center = Vector::new(random(), random(), random());
+        for _ in 0..3 {
+            positions.push(center + 0.3 * Vector::new(random(), random(), random()));
+        }
+        triangles.push(MeshTriangle {
+            positions: [3 * i, 3 * i + 1, 3 * i + 2],
+            uvs: None,
+        });
+    }
+    let singles: Vec<Triangle> = triangles
+        .iter()
+        .map(|t| {
+            Triangle::new(
+                positions[t.positions[0]],
+                positions[t.positions[1]],
+                positions[t.positions[2]],
+            )
+        })
+        .collect();
+    let mesh = TriangleMesh::new(positions, Vec::new(), triangles);
+    for _ in 0..500 {
+        let origin = 2.0 * Vector::new(random(), random(), random());
+        let ray = Ray::new(
+            origin,
+            Vector::new(random(), random(), random()).normalize(),
+        );
+        let expected = singles
+            .iter()
+            .filter_map(|t| t.intersect(&ray, 1e-9, Scalar::INFINITY))
+            .map(|hit| hit.t)
+            .fold(Scalar::INFINITY, Scalar::min);
+        let found = mesh
+            .intersect(&ray, 1e-9, Scalar::INFINITY)
+            .map_or(Scalar::INFINITY, |hit| hit.t);
+        assert_eq!(found, expected);
+    }
+}
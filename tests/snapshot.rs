@@ -0,0 +1,142 @@
+use std::cell::Cell;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::image::Image;
+use basic_raytracer::light::PointLight;
+use basic_raytracer::material::Lambertian;
+use basic_raytracer::render::{render, render_progressive, IntegratorKind, RenderSettings};
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene::Scene;
+use basic_raytracer::shapes::{Plane, Sphere};
+use basic_raytracer::snapshot::{partial_path, Snapshots};
+use basic_raytracer::vector::{Color, Vector};
+
+fn scene() -> Scene {
+    let mut scene = Scene::new(Camera::new(
+        Vector::new(0.0, 1.0, 4.0),
+        Vector::zero(),
+        45.0,
+    ));
+    scene.add(
+        Plane::new(Vector::new(0.0, -1.0, 0.0), Vector::new(0.0, 1.0, 0.0)),
+        Arc::new(Lambertian::new(Color::new(0.6, 0.6, 0.6))),
+    );
+    scene.add(
+        Sphere::new(Vector::zero(), 0.8),
+        Arc::new(Lambertian::new(Color::new(0.9, 0.3, 0.1))),
+    );
+    scene.add_light(PointLight::new(
+        Vector::new(2.0, 4.0, 3.0),
+        Color::new(1.0, 1.0, 1.0),
+        40.0,
+    ));
+    scene
+}
+
+fn settings(spp: u32) -> RenderSettings {
+    RenderSettings {
+        width: 40,
+        height: 30,
+        spp,
+        integrator: IntegratorKind::Path,
+        ..RenderSettings::default()
+    }
+}
+
+/// The mean difference of their 8-bit channels.
+fn difference(a: &Image, b: &Image) -> Scalar {
+    let (a, b) = (a.to_rgba8(), b.to_rgba8());
+    let total: i64 = a
+        .iter()
+        .zip(&b)
+        .map(|(&a, &b)| (a as i64 - b as i64).abs())
+        .sum();
+    total as Scalar / a.len() as Scalar
+}
+
+#[test]
+fn snapshots_come_out_on_the_clock_and_get_cleaner() {
+    let dir = std::env::temp_dir().join(format!("snapshot-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let output = dir.join("out.png");
+    assert_eq!(partial_path(&output), dir.join("out.partial.png"));
+    assert_eq!(
+        partial_path(Path::new("render")),
+        Path::new("render.partial")
+    );
+
+    // Each pass takes a minute.
+    let seconds = Rc::new(Cell::new(0));
+    let clock = Rc::clone(&seconds);
+    let mut snapshots = Snapshots::with_clock(&output, Duration::from_secs(120), move || {
+        Duration::from_secs(clock.get())
+    });
+    let mut taken = Vec::new();
+    let (image, _) = render_progressive(
+        &scene(),
+        &settings(2),
+        5,
+        |done, image| {
+            seconds.set(60 * done as u64);
+            if snapshots.update(image)? {
+                taken.push((done, Image::read_png(snapshots.path()).unwrap()));
+            }
+            Ok(())
+        },
+        |_| true,
+    )
+    .unwrap();
+    assert_eq!(
+        taken.iter().map(|(done, _)| *done).collect::<Vec<_>>(),
+        vec![2, 4]
+    );
+    // Only the snapshot is there, not the file it was written to first.
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+    let (reference, _) = render(&scene(), &settings(256)).unwrap();
+    let noise: Vec<Scalar> = taken
+        .iter()
+        .map(|(_, snapshot)| difference(snapshot, &reference))
+        .collect();
+    assert!(noise[1] < noise[0], "{:?}", noise);
+    assert!(difference(&image, &reference) < noise[1]);
+    // Besides the noise, it's what the output would have been then.
+    let (four, _) = render(&scene(), &settings(8)).unwrap();
+    assert_eq!(difference(&taken[1].1, &four), 0.0);
+
+    snapshots.finish().unwrap();
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn the_binary_checks_the_snapshot_interval() {
+    let dir = std::env::temp_dir().join(format!("snapshot-binary-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_basic-raytracer"))
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .unwrap()
+    };
+    assert!(run(&["--snapshot-every", "600"]).status.success());
+    // The output stands in for the snapshot once it's written.
+    assert!(dir.join("output.png").exists());
+    assert!(!dir.join("output.partial.png").exists());
+    for args in [
+        &["--snapshot-every", "0"][..],
+        &["--snapshot-every", "1", "--frames", "2"],
+    ] {
+        assert_eq!(run(args).status.code(), Some(2), "{:?}", args);
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,86 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+use basic_raytracer::image::Image;
+
+/// Runs `render` with `scene` piped into it.
+fn render(scene: &str, args: &[&str]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_basic-raytracer"))
+        .arg("render")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(scene.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn a_scene_piped_in_comes_out_as_a_png() {
+    let scene = r#"{
+        "camera": {"position": [0, 0, 5], "look_at": [0, 0, 0], "fov": 40},
+        "background": [0, 0, 1],
+        "spheres": [{"center": [0, 0, 0], "radius": 1,
+                     "material": {"type": "emissive", "radiance": [1, 0, 0]}}]
+    }"#;
+    let args = ["--scene", "-", "--scene-format", "json", "--output", "-"];
+    let output = render(
+        scene,
+        &[&args[..], &["--width", "40", "--height", "30"]].concat(),
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(output.status.success(), "{}", stderr);
+    // Only the image is on stdout; what's said goes to stderr.
+    assert!(output.stdout.starts_with(b"\x89PNG\r\n\x1a\n"));
+    assert!(
+        stderr.contains("Raytraced stdout successfully!"),
+        "{}",
+        stderr
+    );
+
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("stdio");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("piped.png");
+    fs::write(&path, &output.stdout).unwrap();
+    let image = Image::read_png(&path).unwrap();
+    assert_eq!((image.width, image.height), (40, 30));
+    assert_eq!(image.to_rgba8()[..4], [0, 0, 255, 255]);
+    let center = image.get(20, 15);
+    assert!(center.x > 0.9 && center.z < 0.1, "{:?}", center);
+}
+
+#[test]
+fn errors_in_a_piped_scene_are_said_to_be_in_stdin() {
+    let output = render("{\"spheres\": [", &["--scene", "-", "--output", "-"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap().trim(),
+        "error: stdin: line 1, column 14: unexpected end of input"
+    );
+    for (args, message) in [
+        (
+            &["--scene", "-", "--scene-format", "toml"][..],
+            "error: unknown scene format \"toml\", expected json",
+        ),
+        (
+            &["--scene", "-", "--watch"],
+            "error: --watch can't watch stdin",
+        ),
+    ]
+    .iter()
+    {
+        let output = render("{}", args);
+        assert_eq!(output.status.code(), Some(2));
+        assert_eq!(String::from_utf8(output.stderr).unwrap().trim(), *message);
+    }
+}
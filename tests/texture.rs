@@ -0,0 +1,416 @@
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::image::{decode_srgb, Image};
+use basic_raytracer::light::PointLight;
+use basic_raytracer::material::Lambertian;
+use basic_raytracer::obj;
+use basic_raytracer::ray::Ray;
+use basic_raytracer::render::{render, IntegratorKind, RenderSettings};
+use basic_raytracer::sampler::Sampler;
+use basic_raytracer::sampling::sample_uniform_sphere;
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene::Scene;
+use basic_raytracer::shapes::{spherical_uv, Plane, Shape, Sphere, TriangleMesh};
+use basic_raytracer::texture::{
+    Checker, Filter, ImageTexture, SolidColor, Texture, Triplanar, UvChecker, UvTransform,
+    UvTransformed, Wrap,
+};
+use basic_raytracer::vector::{Color, Vector};
+
+/// How near a texture's value or coordinates must be to those expected, and
+/// how near the sums and colors that are exact in `f64`.
+#[cfg(not(feature = "f32"))]
+const TOLERANCE: Scalar = 1e-9;
+#[cfg(feature = "f32")]
+const TOLERANCE: Scalar = 1e-5;
+#[cfg(not(feature = "f32"))]
+const EXACT: Scalar = 1e-12;
+#[cfg(feature = "f32")]
+const EXACT: Scalar = 1e-6;
+
+fn checker() -> Checker {
+    Checker::new(
+        0.5,
+        Box::new(SolidColor::new(Color::zero())),
+        Box::new(SolidColor::new(Color::new(1.0, 1.0, 1.0))),
+    )
+}
+
+#[test]
+fn checker_cells_include_their_lower_faces() {
+    let checker = checker();
+    assert!(checker.is_even(Vector::zero()));
+    assert!(checker.is_even(Vector::new(0.49, 0.49, 0.49)));
+    assert!(!checker.is_even(Vector::new(0.5, 0.0, 0.0)));
+    assert!(!checker.is_even(Vector::new(0.0, 0.5, 0.0)));
+    assert!(!checker.is_even(Vector::new(0.0, 0.0, 0.5)));
+    assert!(checker.is_even(Vector::new(0.5, 0.5, 0.0)));
+    assert!(!checker.is_even(Vector::new(0.5, 0.5, 0.5)));
+}
+
+#[test]
+fn checker_alternates_across_the_origin() {
+    let checker = checker();
+    assert!(!checker.is_even(Vector::new(-1e-9, 0.0, 0.0)));
+    assert!(checker.is_even(Vector::new(-1e-9, -1e-9, 0.0)));
+    assert!(!checker.is_even(Vector::new(-0.5, 0.0, 0.0)));
+    assert!(checker.is_even(Vector::new(-0.51, 0.0, 0.0)));
+    assert_eq!(checker.value(0.0, 0.0, Vector::new(0.25, 0.1, 0.1)).x, 1.0);
+    assert_eq!(checker.value(0.0, 0.0, Vector::new(0.75, 0.1, 0.1)).x, 0.0);
+}
+
+fn sphere_on_floor(floor: Lambertian) -> Scene {
+    let mut scene = Scene::new(Camera::new(
+        Vector::new(0.0, 1.0, 4.0),
+        Vector::new(0.0, 0.5, 0.0),
+        45.0,
+    ));
+    scene.add(
+        Plane::new(Vector::zero(), Vector::new(0.0, 1.0, 0.0)),
+        Arc::new(floor),
+    );
+    scene.add(
+        Sphere::new(Vector::new(0.0, 1.0, 0.0), 1.0),
+        Arc::new(Lambertian::new(Color::new(0.8, 0.3, 0.2))),
+    );
+    scene.add_light(PointLight::new(
+        Vector::new(2.0, 4.0, 3.0),
+        Color::new(1.0, 1.0, 1.0),
+        20.0,
+    ));
+    scene.background = Color::new(0.2, 0.3, 0.5);
+    scene
+}
+
+// FNV-1a over the 8-bit output.
+fn fingerprint(scene: &Scene) -> u64 {
+    let settings = RenderSettings {
+        width: 32,
+        height: 24,
+        integrator: IntegratorKind::Path,
+        spp: 4,
+        ..RenderSettings::default()
+    };
+    let (image, _) = render(scene, &settings).unwrap();
+    image
+        .to_rgba8()
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+#[test]
+fn solid_color_matches_flat_albedo() {
+    // Rendered with the floor's albedo a plain color, before textures existed.
+    const FLAT: u64 = 0x6cbf_48d6_b5a4_9614;
+    let constructed = sphere_on_floor(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    assert_eq!(fingerprint(&constructed), FLAT);
+    let textured = sphere_on_floor(Lambertian::textured(Box::new(SolidColor::new(Color::new(
+        0.5, 0.5, 0.5,
+    )))));
+    assert_eq!(fingerprint(&textured), FLAT);
+}
+
+fn close(a: (Scalar, Scalar), b: (Scalar, Scalar)) -> bool {
+    (a.0 - b.0).abs() < TOLERANCE && (a.1 - b.1).abs() < TOLERANCE
+}
+
+#[test]
+fn spherical_uv_landmarks() {
+    assert_eq!(spherical_uv(Vector::new(0.0, 1.0, 0.0)).1, 0.0);
+    assert_eq!(spherical_uv(Vector::new(0.0, -1.0, 0.0)).1, 1.0);
+    assert!(close(spherical_uv(Vector::new(0.0, 0.0, 1.0)), (0.5, 0.5)));
+    assert!(close(spherical_uv(Vector::new(1.0, 0.0, 0.0)), (0.75, 0.5)));
+    assert!(close(
+        spherical_uv(Vector::new(-1.0, 0.0, 0.0)),
+        (0.25, 0.5)
+    ));
+}
+
+#[test]
+fn sphere_hits_carry_uv() {
+    let sphere = Sphere::new(Vector::new(1.0, 2.0, 3.0), 2.0);
+    let ray = Ray::new(Vector::new(1.0, 10.0, 3.0), Vector::new(0.0, -1.0, 0.0));
+    let hit = sphere.intersect(&ray, 0.0, Scalar::INFINITY).unwrap();
+    assert_eq!(hit.uv.1, 0.0);
+    let ray = Ray::new(Vector::new(1.0, 2.0, 10.0), Vector::new(0.0, 0.0, -1.0));
+    let hit = sphere.intersect(&ray, 0.0, Scalar::INFINITY).unwrap();
+    assert!(close(hit.uv, (0.5, 0.5)));
+}
+
+fn tiny_texture() -> ImageTexture {
+    let mut image = Image::new(4, 4);
+    for y in 0..4 {
+        for x in 0..4 {
+            image.set(x, y, Color::new(x as Scalar, y as Scalar, 0.0));
+        }
+    }
+    ImageTexture {
+        filter: Filter::Nearest,
+        ..ImageTexture::new(image)
+    }
+}
+
+#[test]
+fn image_texture_lookups_are_pixel_exact() {
+    let texture = tiny_texture();
+    let at = |u: Scalar, v: Scalar| texture.value(u, v, Vector::zero());
+    assert_eq!(at(0.0, 0.0), Color::new(0.0, 0.0, 0.0));
+    assert_eq!(at(0.375, 0.875), Color::new(1.0, 3.0, 0.0));
+    assert_eq!(at(0.4999, 0.5), Color::new(1.0, 2.0, 0.0));
+    assert_eq!(at(1.0, 0.25), Color::new(0.0, 1.0, 0.0));
+    assert_eq!(at(0.9999, 0.9999), Color::new(3.0, 3.0, 0.0));
+}
+
+#[test]
+fn image_texture_wraps() {
+    let texture = tiny_texture();
+    let at = |u: Scalar, v: Scalar| texture.value(u, v, Vector::zero());
+    assert_eq!(at(1.3, -0.1), at(0.3, 0.9));
+    assert_eq!(at(-2.6, 5.6), at(0.4, 0.6));
+}
+
+fn bilinear(wrap: Wrap) -> ImageTexture {
+    ImageTexture {
+        filter: Filter::Bilinear,
+        wrap_u: wrap,
+        wrap_v: wrap,
+        ..tiny_texture()
+    }
+}
+
+fn near(a: Color, b: Color) -> bool {
+    (a - b).len() < TOLERANCE
+}
+
+#[test]
+fn bilinear_lookups_between_texels_average_them() {
+    let texture = bilinear(Wrap::Repeat);
+    let at = |u: Scalar, v: Scalar| texture.value(u, v, Vector::zero());
+    // The corner shared by texels (1, 2), (2, 2), (1, 3) and (2, 3).
+    assert!(near(at(0.5, 0.75), Color::new(1.5, 2.5, 0.0)));
+    // Texel centers still return the texel itself.
+    assert!(near(at(0.375, 0.875), Color::new(1.0, 3.0, 0.0)));
+    // A quarter of the way from one center to the next.
+    assert!(near(at(0.4375, 0.625), Color::new(1.25, 2.0, 0.0)));
+}
+
+#[test]
+fn bilinear_repeat_blends_across_the_seam() {
+    let texture = bilinear(Wrap::Repeat);
+    let at = |u: Scalar, v: Scalar| texture.value(u, v, Vector::zero());
+    assert!(near(at(1.01, 0.375), at(0.01, 0.375)));
+    // At the edge itself, texels 3 and 0 are blended half and half.
+    assert!(near(at(1.0, 0.375), Color::new(1.5, 1.0, 0.0)));
+    assert!(near(at(0.0, 0.375), Color::new(1.5, 1.0, 0.0)));
+}
+
+#[test]
+fn clamp_and_mirror_wrap_modes() {
+    let clamp = bilinear(Wrap::Clamp);
+    let at = |u: Scalar, v: Scalar| clamp.value(u, v, Vector::zero());
+    assert!(near(at(1.0, 0.375), Color::new(3.0, 1.0, 0.0)));
+    assert!(near(at(7.0, -3.0), Color::new(3.0, 0.0, 0.0)));
+
+    let mirror = bilinear(Wrap::Mirror);
+    let at = |u: Scalar, v: Scalar| mirror.value(u, v, Vector::zero());
+    assert!(near(at(1.0, 0.375), Color::new(3.0, 1.0, 0.0)));
+    assert!(near(at(1.125, 0.375), Color::new(3.0, 1.0, 0.0)));
+    assert!(near(at(1.375, 0.375), Color::new(2.0, 1.0, 0.0)));
+    assert!(near(at(-0.375, 0.375), Color::new(1.0, 1.0, 0.0)));
+    assert!(near(at(2.375, 0.375), at(0.375, 0.375)));
+}
+
+#[test]
+fn wrap_modes_apply_per_axis() {
+    let texture = ImageTexture {
+        wrap_u: Wrap::Clamp,
+        wrap_v: Wrap::Repeat,
+        ..tiny_texture()
+    };
+    let at = |u: Scalar, v: Scalar| texture.value(u, v, Vector::zero());
+    assert_eq!(at(1.3, 1.3), Color::new(3.0, 1.0, 0.0));
+    assert_eq!(at(-0.3, -0.3), Color::new(0.0, 2.0, 0.0));
+}
+
+#[test]
+fn png_textures_decode_to_linear() {
+    let mut image = Image::new(2, 1);
+    image.set(0, 0, Color::new(1.0, 0.5, 0.0));
+    image.set(1, 0, Color::new(0.2, 0.2, 0.2));
+    let path = std::env::temp_dir().join(format!("texture-{}.png", std::process::id()));
+    image.write_png(&path).unwrap();
+    let texture = ImageTexture::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let rgba = image.to_rgba8();
+    assert_eq!(
+        texture.value(0.25, 0.5, Vector::zero()),
+        Color::new(
+            decode_srgb(rgba[0]),
+            decode_srgb(rgba[1]),
+            decode_srgb(rgba[2])
+        )
+    );
+    let gray = texture.value(0.75, 0.5, Vector::zero()).x;
+    assert!((gray - 0.2).abs() < 0.005, "decoded {}", gray);
+}
+
+fn triplanar_checker(sharpness: Scalar) -> Triplanar {
+    Triplanar::new(
+        Box::new(UvChecker::new(
+            1.0,
+            1.0,
+            Box::new(SolidColor::new(Color::zero())),
+            Box::new(SolidColor::new(Color::new(1.0, 1.0, 1.0))),
+        )),
+        4.0,
+        sharpness,
+    )
+}
+
+#[test]
+fn triplanar_weights_sum_to_one() {
+    for &sharpness in &[0.5, 1.0, 4.0, 12.0] {
+        let texture = triplanar_checker(sharpness);
+        for sample in 0..1000 {
+            let mut sampler = Sampler::new(3, 0, sample);
+            let (normal, _) = sample_uniform_sphere(sampler.next_scalar(), sampler.next_scalar());
+            let weights = texture.weights(normal);
+            assert!(weights.iter().all(|&w| w >= 0.0));
+            assert!((weights.iter().sum::<Scalar>() - 1.0).abs() < EXACT);
+        }
+        assert_eq!(
+            texture.weights(Vector::new(0.0, -1.0, 0.0)),
+            [0.0, 1.0, 0.0]
+        );
+    }
+}
+
+#[test]
+fn triplanar_blending_does_not_darken() {
+    let red = Color::new(0.8, 0.1, 0.1);
+    let texture = Triplanar::new(Box::new(SolidColor::new(red)), 1.0, 4.0);
+    let sphere = Sphere::new(Vector::zero(), 1.0);
+    let diagonal = Vector::new(1.0, 1.0, 1.0).normalize();
+    let ray = Ray::new(3.0 * diagonal, -diagonal);
+    let hit = sphere.intersect(&ray, 0.0, Scalar::INFINITY).unwrap();
+    assert!((texture.value_at(&hit) - red).len() < EXACT);
+}
+
+#[test]
+fn triplanar_squares_are_square_on_every_face() {
+    // A cube without texture coordinates, so its surface coordinates are only
+    // barycentric and a plain UV checker would be sheared across each face.
+    let mesh: TriangleMesh = obj::parse(
+        "v -1 -1 -1\nv 1 -1 -1\nv 1 1 -1\nv -1 1 -1\n\
+         v -1 -1 1\nv 1 -1 1\nv 1 1 1\nv -1 1 1\n\
+         f 2 3 7 6\nf 1 5 8 4\nf 4 8 7 3\nf 1 2 6 5\nf 5 6 7 8\nf 1 4 3 2\n",
+    )
+    .unwrap();
+    let texture = triplanar_checker(8.0);
+    let axes = [
+        Vector::new(1.0, 0.0, 0.0),
+        Vector::new(0.0, 1.0, 0.0),
+        Vector::new(0.0, 0.0, 1.0),
+    ];
+    for (axis, &normal) in axes.iter().enumerate() {
+        for &side in &[1.0, -1.0] {
+            let (first, second) = (axes[(axis + 1) % 3], axes[(axis + 2) % 3]);
+            let shade = |a: Scalar, b: Scalar| {
+                let target = a * first + b * second;
+                let origin = target + (side * 3.0) * normal;
+                let ray = Ray::new(origin, -side * normal);
+                let hit = mesh.intersect(&ray, 0.0, Scalar::INFINITY).unwrap();
+                texture.value_at(&hit).x
+            };
+            // Squares are a quarter unit along both directions of the face.
+            for &(a, b) in &[(0.1, 0.1), (-0.6, 0.35), (0.3, -0.15)] {
+                let here = shade(a, b);
+                assert!(here == 0.0 || here == 1.0, "{}", here);
+                assert_eq!(shade(a + 0.25, b), 1.0 - here);
+                assert_eq!(shade(a, b + 0.25), 1.0 - here);
+                assert_eq!(shade(a + 0.5, b), here);
+                assert_eq!(shade(a, b - 0.5), here);
+            }
+        }
+    }
+}
+
+fn close_uv(a: (Scalar, Scalar), b: (Scalar, Scalar)) -> bool {
+    (a.0 - b.0).abs() < EXACT && (a.1 - b.1).abs() < EXACT
+}
+
+#[test]
+fn uv_transform_components() {
+    let identity = UvTransform::default();
+    assert_eq!(identity.apply((0.3, 0.7)), (0.3, 0.7));
+
+    let scale = UvTransform {
+        scale: (4.0, 2.0),
+        pivot: (0.0, 0.0),
+        ..UvTransform::default()
+    };
+    assert!(close_uv(scale.apply((0.25, 0.5)), (1.0, 1.0)));
+
+    let quarter_turn = UvTransform {
+        rotation: Scalar::to_radians(90.0),
+        pivot: (0.0, 0.0),
+        ..UvTransform::default()
+    };
+    assert!(close_uv(quarter_turn.apply((1.0, 0.0)), (0.0, 1.0)));
+    assert!(close_uv(quarter_turn.apply((0.0, 1.0)), (-1.0, 0.0)));
+
+    // The default pivot is the middle of the texture.
+    let half_turn = UvTransform {
+        rotation: Scalar::to_radians(180.0),
+        ..UvTransform::default()
+    };
+    assert!(close_uv(half_turn.apply((0.0, 0.0)), (1.0, 1.0)));
+    assert!(close_uv(half_turn.apply((0.5, 0.5)), (0.5, 0.5)));
+
+    let offset = UvTransform {
+        offset: (0.25, -0.5),
+        ..UvTransform::default()
+    };
+    assert!(close_uv(offset.apply((0.5, 0.5)), (0.75, 0.0)));
+}
+
+#[test]
+fn uv_transform_scales_then_rotates_then_offsets() {
+    let transform = UvTransform {
+        scale: (2.0, 1.0),
+        rotation: Scalar::to_radians(90.0),
+        pivot: (0.0, 0.0),
+        offset: (0.5, 0.0),
+    };
+    // (1, 0) scales to (2, 0), turns to (0, 2) and moves to (0.5, 2). Other
+    // orders would give (0, 1.5), (1, 1), (0.5, 1) or (0, 3).
+    assert!(close_uv(transform.apply((1.0, 0.0)), (0.5, 2.0)));
+    // The pivot is in scaled coordinates: scaling (0.5, 0.5) by 2 puts it on
+    // the pivot (1, 0.5), which the rotation leaves in place.
+    let about_pivot = UvTransform {
+        pivot: (1.0, 0.5),
+        ..transform
+    };
+    assert!(close_uv(about_pivot.apply((0.5, 0.5)), (1.5, 0.5)));
+}
+
+#[test]
+fn uv_transforms_apply_before_wrapping() {
+    let transformed = UvTransformed::new(
+        Box::new(tiny_texture()),
+        UvTransform {
+            scale: (2.0, 2.0),
+            ..UvTransform::default()
+        },
+    );
+    let plain = tiny_texture();
+    // Tiled twice, the second copy starts halfway across.
+    let tiled = |u: Scalar, v: Scalar| transformed.value(u, v, Vector::zero());
+    let lookup = |u: Scalar, v: Scalar| plain.value(u, v, Vector::zero());
+    assert_eq!(tiled(0.1, 0.2), lookup(0.2, 0.4));
+    assert_eq!(tiled(0.6, 0.7), lookup(0.2, 0.4));
+    assert_eq!(tiled(0.6, 0.7), tiled(0.1, 0.2));
+}
@@ -0,0 +1,124 @@
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::light::PointLight;
+use basic_raytracer::material::Lambertian;
+use basic_raytracer::render::{render, Crop, IntegratorKind, RenderSettings};
+use basic_raytracer::scene::Scene;
+use basic_raytracer::shapes::Sphere;
+use basic_raytracer::tile::{tiles, TileOrder, TILE_SIZE};
+use basic_raytracer::vector::{Color, Vector};
+
+const ORDERS: [TileOrder; 4] = [
+    TileOrder::Scanline,
+    TileOrder::Spiral,
+    TileOrder::Hilbert,
+    TileOrder::Random,
+];
+
+fn window(width: u32, height: u32) -> Crop {
+    Crop {
+        x: 0,
+        y: 0,
+        width,
+        height,
+    }
+}
+
+#[test]
+fn every_order_covers_every_pixel_once() {
+    for (width, height) in [
+        (640, 480),
+        (1, 1),
+        (100, 17),
+        (17, 300),
+        (256, 256),
+        (1000, 40),
+    ] {
+        for order in ORDERS {
+            let mut covered = vec![0; (width * height) as usize];
+            let tiles = tiles(window(width, height), order, 7);
+            for tile in &tiles {
+                assert!(tile.width >= 1 && tile.width <= TILE_SIZE);
+                assert!(tile.height >= 1 && tile.height <= TILE_SIZE);
+                assert_eq!(tile.x % TILE_SIZE, 0);
+                assert_eq!(tile.y % TILE_SIZE, 0);
+                for y in tile.y..tile.y + tile.height {
+                    for x in tile.x..tile.x + tile.width {
+                        covered[(x + y * width) as usize] += 1;
+                    }
+                }
+            }
+            assert!(
+                covered.iter().all(|&count| count == 1),
+                "{:?} {}x{}",
+                order,
+                width,
+                height
+            );
+            let grid = width.div_ceil(TILE_SIZE) * height.div_ceil(TILE_SIZE);
+            assert_eq!(tiles.len() as u32, grid);
+        }
+    }
+}
+
+#[test]
+fn the_spiral_starts_at_the_center() {
+    for (width, height) in [(640, 480), (100, 17), (17, 300), (33, 33)] {
+        let first = tiles(window(width, height), TileOrder::Spiral, 0)[0];
+        let (x, y) = (width / 2, height / 2);
+        assert!(
+            first.x <= x && x < first.x + first.width,
+            "{}x{}",
+            width,
+            height
+        );
+        assert!(
+            first.y <= y && y < first.y + first.height,
+            "{}x{}",
+            width,
+            height
+        );
+    }
+}
+
+#[test]
+fn each_hilbert_tile_is_next_to_the_last() {
+    let tiles = tiles(window(256, 256), TileOrder::Hilbert, 0);
+    for pair in tiles.windows(2) {
+        let step = pair[0].x.abs_diff(pair[1].x) + pair[0].y.abs_diff(pair[1].y);
+        assert_eq!(step, TILE_SIZE);
+    }
+    assert_eq!((tiles[0].x, tiles[0].y), (0, 0));
+}
+
+#[test]
+fn the_order_doesnt_change_the_image() {
+    let mut scene = Scene::new(Camera::new(
+        Vector::new(0.0, 0.0, 4.0),
+        Vector::zero(),
+        30.0,
+    ));
+    scene.add(
+        Sphere::new(Vector::zero(), 0.3),
+        Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.8))),
+    );
+    scene.add_light(PointLight::new(
+        Vector::new(0.0, 0.0, 4.0),
+        Color::new(1.0, 1.0, 1.0),
+        20.0,
+    ));
+    let settings = |tile_order| RenderSettings {
+        width: 96,
+        height: 80,
+        spp: 2,
+        integrator: IntegratorKind::Whitted,
+        tile_order,
+        ..RenderSettings::default()
+    };
+    let (expected, _) = render(&scene, &settings(TileOrder::Scanline)).unwrap();
+    for order in ORDERS {
+        let (image, _) = render(&scene, &settings(order)).unwrap();
+        assert!(image.pixels == expected.pixels, "{:?}", order);
+    }
+}
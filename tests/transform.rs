@@ -0,0 +1,176 @@
+use std::path::Path;
+
+use basic_raytracer::matrix::Matrix4;
+use basic_raytracer::ray::Ray;
+use basic_raytracer::scalar::consts::{FRAC_PI_2, PI};
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene_file;
+use basic_raytracer::shapes::{Cuboid, Shape, Sphere, Transformed};
+use basic_raytracer::vector::Vector;
+
+/// How near a transformed hit must be to where it's worked out to be, and
+/// how near the products of matrices that are exact in `f64`.
+#[cfg(not(feature = "f32"))]
+const TOLERANCE: Scalar = 1e-9;
+#[cfg(feature = "f32")]
+const TOLERANCE: Scalar = 1e-5;
+#[cfg(not(feature = "f32"))]
+const EXACT: Scalar = 1e-12;
+#[cfg(feature = "f32")]
+const EXACT: Scalar = 1e-6;
+
+fn assert_close(a: Vector, b: Vector) {
+    assert!((a - b).len() < TOLERANCE, "{:?} != {:?}", a, b);
+}
+
+#[test]
+fn matrices_invert_and_transpose() {
+    let m = Matrix4::translate(Vector::new(1.0, -2.0, 3.0))
+        * Matrix4::rotate(Vector::new(0.0, 0.6, 0.8), 0.7)
+        * Matrix4::scale(Vector::new(2.0, 0.5, -3.0));
+    let product = m * m.inverse().unwrap();
+    for (i, row) in product.rows.iter().enumerate() {
+        for (j, &entry) in row.iter().enumerate() {
+            let expected = if i == j { 1.0 } else { 0.0 };
+            assert!((entry - expected).abs() < EXACT, "{:?}", product);
+        }
+    }
+    assert_eq!(m.transpose().transpose(), m);
+    assert_eq!(m.transpose().rows[0][3], 0.0);
+    assert!((m.determinant3() - -3.0).abs() < EXACT);
+    assert!(Matrix4::scale(Vector::new(1.0, 0.0, 1.0))
+        .inverse()
+        .is_none());
+
+    let p = Vector::new(0.3, 0.2, -0.1);
+    assert_close(
+        m.transform_point(p) - m.transform_point(Vector::zero()),
+        m.transform_direction(p),
+    );
+    assert_close(
+        Matrix4::rotate_z(FRAC_PI_2).transform_direction(Vector::new(1.0, 0.0, 0.0)),
+        Vector::new(0.0, 1.0, 0.0),
+    );
+}
+
+#[test]
+fn rotated_boxes_are_hit_on_their_rotated_faces() {
+    // A unit cube turned 45° about y shows an edge to a ray down -z.
+    let cube = Cuboid::new(Vector::new(-0.5, -0.5, -0.5), Vector::new(0.5, 0.5, 0.5));
+    let rotated = Transformed::new(
+        cube,
+        Matrix4::translate(Vector::new(0.0, 0.0, -3.0)) * Matrix4::rotate_y(FRAC_PI_2 / 2.0),
+    );
+    let ray = Ray::new(Vector::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, -2.0));
+    let hit = rotated.intersect(&ray, 0.0, Scalar::INFINITY).unwrap();
+    assert!((hit.t - (3.0 - 0.5 * Scalar::sqrt(2.0)) / 2.0).abs() < TOLERANCE);
+    assert!(hit.front_face);
+
+    // Slightly right of the edge the face normal points to +x and +z.
+    let ray = Ray::new(Vector::new(0.1, 0.0, 0.0), Vector::new(0.0, 0.0, -1.0));
+    let hit = rotated.intersect(&ray, 0.0, Scalar::INFINITY).unwrap();
+    let s = Scalar::sqrt(0.5);
+    assert_close(hit.normal, Vector::new(s, 0.0, s));
+    assert_close(hit.point, Vector::new(0.1, 0.0, -3.0 + s - 0.1));
+    // The face's ∂p/∂v still runs down it.
+    assert_close(hit.bitangent, Vector::new(0.0, -1.0, 0.0));
+}
+
+#[test]
+fn scaled_spheres_have_unit_ellipsoid_normals() {
+    let scale = Vector::new(3.0, 1.0, 0.5);
+    let ellipsoid = Transformed::new(
+        Sphere::new(Vector::zero(), 1.0),
+        Matrix4::translate(Vector::new(0.0, 1.0, 0.0)) * Matrix4::scale(scale),
+    );
+    for &(x, y) in &[(0.0, 1.0), (2.0, 1.3), (-2.5, 0.6), (1.0, 1.8)] {
+        let ray = Ray::new(Vector::new(x, y, 5.0), Vector::new(0.0, 0.0, -1.0));
+        let hit = ellipsoid.intersect(&ray, 0.0, Scalar::INFINITY).unwrap();
+        // The gradient of (x/a)² + (y/b)² + (z/c)².
+        let p = hit.point - Vector::new(0.0, 1.0, 0.0);
+        let gradient = Vector::new(
+            p.x / (scale.x * scale.x),
+            p.y / (scale.y * scale.y),
+            p.z / (scale.z * scale.z),
+        );
+        assert!((hit.normal.len() - 1.0).abs() < EXACT);
+        assert_close(hit.normal, gradient.normalize());
+        let level = (p.x / scale.x).powi(2) + (p.y / scale.y).powi(2) + (p.z / scale.z).powi(2);
+        assert!((level - 1.0).abs() < TOLERANCE);
+    }
+    let inside = Ray::new(Vector::new(0.0, 1.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+    let hit = ellipsoid.intersect(&inside, 0.0, Scalar::INFINITY).unwrap();
+    assert!(!hit.front_face);
+    assert!((hit.t - 3.0).abs() < TOLERANCE);
+    assert_close(hit.normal, Vector::new(-1.0, 0.0, 0.0));
+}
+
+#[test]
+fn scaled_surfaces_sample_by_world_area() {
+    let ellipsoid = Transformed::new(
+        Sphere::new(Vector::zero(), 1.0),
+        Matrix4::scale(Vector::new(2.0, 1.0, 1.0)),
+    );
+    // Where the normal is +x the surface is only stretched along y and z,
+    // where it is +y along x and z.
+    let pdf = |point: Vector, normal: Vector| ellipsoid.surface_pdf(point, normal);
+    let unit = 1.0 / (4.0 * PI);
+    assert!((pdf(Vector::new(2.0, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0)) - unit).abs() < EXACT);
+    assert!(
+        (pdf(Vector::new(0.0, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0)) - unit / 2.0).abs() < EXACT
+    );
+    let sample = ellipsoid.sample_surface(0.3, 0.8).unwrap();
+    assert!((sample.pdf - pdf(sample.point, sample.normal)).abs() < EXACT);
+}
+
+#[test]
+fn transformed_bounds_contain_the_shape() {
+    let cube = Cuboid::new(Vector::new(0.0, 0.0, 0.0), Vector::new(1.0, 1.0, 1.0));
+    let rotated = Transformed::new(cube, Matrix4::rotate_z(FRAC_PI_2 / 2.0));
+    let bounds = rotated.bounds().unwrap();
+    let s = Scalar::sqrt(0.5);
+    assert_close(bounds.min, Vector::new(-s, 0.0, 0.0));
+    assert_close(bounds.max, Vector::new(s, 2.0 * s, 1.0));
+}
+
+#[test]
+fn scene_files_transform_shapes() {
+    let scene = scene_file::parse(
+        r#"{"boxes": [{"min": [-1, -1, -1], "max": [1, 1, 1], "material": {"type": "lambertian",
+            "albedo": [1, 1, 1]}, "transform": {"scale": [1, 2, 0.5], "rotate_deg": [0, 90, 0],
+            "translate": [0, 0, -5]}}],
+            "spheres": [{"center": [0, 0, 0], "radius": 1, "transform": {"scale": 2},
+            "material": {"type": "emissive", "radiance": [1, 1, 1]}}]}"#,
+        Path::new(""),
+    )
+    .unwrap();
+    assert_eq!(scene.lights.len(), 1);
+    // Scaled along z to half a unit, then turned so that depth lies along x.
+    let ray = Ray::new(Vector::new(5.0, 0.0, -5.0), Vector::new(-1.0, 0.0, 0.0));
+    let hit = scene.objects[1]
+        .shape
+        .intersect(&ray, 0.0, Scalar::INFINITY)
+        .unwrap();
+    assert!((hit.t - 4.5).abs() < TOLERANCE);
+
+    let error = |transform: &str| {
+        scene_file::parse(
+            &format!(
+                r#"{{"planes": [{{"point": [0, 0, 0], "normal": [0, 1, 0], "material":
+                    {{"type": "dielectric", "ior": 1.5}}, "transform": {}}}]}}"#,
+                transform
+            ),
+            Path::new(""),
+        )
+        .err()
+        .unwrap()
+    };
+    assert_eq!(
+        error(r#"{"scale": [1, 0, 1]}"#),
+        "planes[0].transform.scale: a scale can't be zero"
+    );
+    assert_eq!(
+        error(r#"{"rotate": [0, 0, 0]}"#),
+        "planes[0].transform: unknown member \"rotate\""
+    );
+}
@@ -0,0 +1,211 @@
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::error::Error;
+use basic_raytracer::light::{EnvironmentLight, Light, PointLight};
+use basic_raytracer::material::Lambertian;
+use basic_raytracer::render::{render, RenderSettings};
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene::Scene;
+use basic_raytracer::scene_file;
+use basic_raytracer::shapes::{Cuboid, Cylinder, Plane, Quad, Shape, Sphere, Triangle};
+use basic_raytracer::vector::{Color, Vector};
+
+fn camera() -> Camera {
+    Camera::new(Vector::zero(), Vector::new(0.0, 0.0, -1.0), 45.0)
+}
+
+fn shape_error(shape: impl Shape) -> String {
+    shape.validate().err().unwrap()
+}
+
+fn light_error(light: impl Light) -> String {
+    light.validate().err().unwrap()
+}
+
+#[test]
+fn shapes_name_the_field_that_makes_no_sense() {
+    let (x, y, z) = (
+        Vector::new(1.0, 0.0, 0.0),
+        Vector::new(0.0, 1.0, 0.0),
+        Vector::new(0.0, 0.0, 1.0),
+    );
+    assert_eq!(
+        shape_error(Sphere::new(Vector::zero(), -1.0)),
+        "radius: expected a positive number, found -1"
+    );
+    assert_eq!(
+        shape_error(Sphere::new(Vector::new(Scalar::NAN, 0.0, 0.0), 1.0)),
+        "center: expected finite coordinates, found (NaN, 0, 0)"
+    );
+    assert_eq!(
+        shape_error(Sphere::new(Vector::zero(), Scalar::INFINITY)),
+        "radius: expected a positive number, found inf"
+    );
+    assert!(shape_error(Plane::new(Vector::zero(), Vector::zero())).starts_with("normal: "));
+    assert_eq!(
+        shape_error(Quad::new(Vector::zero(), x, 2.0 * x)),
+        "v: expected an edge that isn't parallel to u"
+    );
+    assert_eq!(
+        shape_error(Quad::new(Vector::zero(), Vector::zero(), z)),
+        "u: expected a finite, nonzero direction, found (0, 0, 0)"
+    );
+    assert_eq!(
+        shape_error(Cuboid::new(x, y)),
+        "max: expected a corner above min on every axis"
+    );
+    assert_eq!(
+        shape_error(Cylinder::new(Vector::zero(), 1.0, 0.0)),
+        "height: expected a positive number, found 0"
+    );
+    assert_eq!(
+        shape_error(Triangle::new(
+            Vector::zero(),
+            x,
+            Vector::new(0.0, Scalar::INFINITY, 0.0)
+        )),
+        "vertices[2]: expected finite coordinates, found (0, inf, 0)"
+    );
+
+    assert!(Sphere::new(Vector::zero(), 1.0).validate().is_ok());
+    assert!(Quad::new(Vector::zero(), x, y).validate().is_ok());
+    assert!(Cuboid::new(-1.0 * (x + y + z), x + y + z)
+        .validate()
+        .is_ok());
+}
+
+#[test]
+fn lights_and_the_camera_are_checked_too() {
+    let white = Color::new(1.0, 1.0, 1.0);
+    assert_eq!(
+        light_error(PointLight::new(Vector::zero(), white, -2.0)),
+        "intensity: expected a non-negative number, found -2"
+    );
+    assert_eq!(
+        light_error(PointLight::new(
+            Vector::zero(),
+            Color::new(1.0, Scalar::NAN, 1.0),
+            1.0
+        )),
+        "color: expected a finite color with no negative channels, found (1, NaN, 1)"
+    );
+    assert_eq!(
+        light_error(EnvironmentLight {
+            radiance: Color::new(-1.0, 0.0, 0.0),
+        }),
+        "radiance: expected a finite color with no negative channels, found (-1, 0, 0)"
+    );
+    assert!(PointLight::new(Vector::zero(), white, 0.0)
+        .validate()
+        .is_ok());
+
+    let mut camera = camera();
+    camera.fov = 180.0;
+    assert_eq!(
+        camera.validate().err().unwrap(),
+        "fov: expected an angle between 0 and 180 degrees, found 180"
+    );
+    camera.fov = 60.0;
+    camera.look_at = camera.position;
+    assert_eq!(
+        camera.validate().err().unwrap(),
+        "look_at: expected a point away from the position"
+    );
+}
+
+#[test]
+fn scenes_say_which_object_is_wrong_and_refuse_to_render() {
+    let mut scene = Scene::new(camera());
+    let gray = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    scene.add(Sphere::new(Vector::new(0.0, 0.0, -5.0), 1.0), gray.clone());
+    scene.add_light(PointLight::new(
+        Vector::new(0.0, 5.0, 0.0),
+        Color::new(1.0, 1.0, 1.0),
+        10.0,
+    ));
+    assert!(scene.validate().is_ok());
+
+    scene.add(Sphere::new(Vector::new(2.0, 0.0, -5.0), -1.0), gray);
+    scene.add_light(PointLight::new(
+        Vector::zero(),
+        Color::new(1.0, 1.0, 1.0),
+        -1.0,
+    ));
+    let error = scene.validate().unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "invalid scene: objects[1].radius: expected a positive number, found -1"
+    );
+    let settings = RenderSettings {
+        width: 4,
+        height: 4,
+        ..RenderSettings::default()
+    };
+    assert!(matches!(
+        render(&scene, &settings),
+        Err(Error::InvalidScene(_))
+    ));
+
+    scene.objects.pop();
+    assert_eq!(
+        scene.validate().unwrap_err().to_string(),
+        "invalid scene: lights[1].intensity: expected a non-negative number, found -1"
+    );
+}
+
+#[test]
+fn scene_files_are_checked_as_they_load() {
+    let parse = |text: &str| scene_file::parse(text, Path::new("")).err().unwrap();
+    let material = r#""material": {"type": "lambertian", "albedo": [1, 1, 1]}"#;
+    assert_eq!(
+        parse(&format!(
+            r#"{{"spheres": [{{"center": [0, 0, 0], "radius": 0, {}}}]}}"#,
+            material
+        )),
+        "spheres[0].radius: expected a positive number, found 0"
+    );
+    assert_eq!(
+        parse(&format!(
+            r#"{{"csg": [{{"operation": "union",
+                "a": {{"type": "sphere", "center": [0, 0, 0], "radius": 1}},
+                "b": {{"type": "sphere", "center": [1, 0, 0], "radius": -1}}, {}}}]}}"#,
+            material
+        )),
+        "csg[0].b.radius: expected a positive number, found -1"
+    );
+    assert_eq!(
+        parse(
+            r#"{"lights": [{"type": "point", "position": [0, 0, 0],
+                "color": [1, 1, 1], "intensity": -1}]}"#
+        ),
+        "lights[0].intensity: expected a non-negative number, found -1"
+    );
+    assert_eq!(
+        parse(r#"{"camera": {"position": [0, 0, 0], "look_at": [0, 0, -1], "fov": 0}}"#),
+        "camera.fov: expected an angle between 0 and 180 degrees, found 0"
+    );
+    assert_eq!(
+        parse(
+            r#"{"quads": [{"corner": [0, 0, 0], "u": [1, 0, 0], "v": [0, 1, 0],
+                "material": {"type": "emissive", "radiance": [1, -1, 1]}}]}"#
+        ),
+        "quads[0].material.radiance: expected a finite color with no negative channels, \
+         found (1, -1, 1)"
+    );
+
+    // Every scene that ships with the renderer passes.
+    let scenes = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes");
+    for entry in fs::read_dir(&scenes).unwrap() {
+        let path = entry.unwrap().path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            let scene = scene_file::load(&path).unwrap();
+            assert!(scene.validate().is_ok(), "{}", path.display());
+        }
+    }
+}
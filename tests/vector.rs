@@ -0,0 +1,115 @@
+use basic_raytracer::assert_vec_eq;
+use basic_raytracer::ray::{Ray, EPSILON};
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::shapes::{Shape, Sphere};
+use basic_raytracer::vector::Vector;
+
+/// A few dozen rounding errors in numbers of about `size`, in whichever
+/// precision the crate was built with.
+fn rounding(size: Scalar) -> Scalar {
+    64.0 * Scalar::EPSILON * size
+}
+
+#[test]
+fn length_and_normalizing() {
+    assert_eq!(Vector::new(3.0, 4.0, 12.0).len(), 13.0);
+    assert_eq!(Vector::zero().len(), 0.0);
+    assert_eq!(Vector::new(-2.0, 0.0, 0.0).len(), 2.0);
+    for v in [[3.0, 4.0, 12.0], [1e-3, -2e-3, 5e-4], [-7e3, 2e3, 1e4]] {
+        let unit = Vector::from(v).normalize();
+        assert!((unit.len() - 1.0).abs() < rounding(1.0));
+        // Same direction: parallel, and not reversed.
+        assert_vec_eq!(
+            unit.cross(v.into()),
+            [0.0, 0.0, 0.0],
+            rounding(Vector::from(v).len())
+        );
+        assert!(unit * Vector::from(v) > 0.0);
+    }
+    assert_vec_eq!(Vector::new(0.0, -5.0, 0.0).normalize(), (0.0, -1.0, 0.0));
+    // The zero vector has no direction to keep.
+    let zero = Vector::zero().normalize();
+    assert!(zero.x.is_nan() && zero.y.is_nan() && zero.z.is_nan());
+}
+
+#[test]
+fn dot_and_cross_products() {
+    let (a, b, c) = (
+        Vector::new(1.0, -2.0, 3.0),
+        Vector::new(-4.0, 0.5, 2.0),
+        Vector::new(0.25, 6.0, -1.0),
+    );
+    let (x, y, z) = (
+        Vector::from([1.0, 0.0, 0.0]),
+        Vector::from([0.0, 1.0, 0.0]),
+        Vector::from([0.0, 0.0, 1.0]),
+    );
+    assert_eq!(a * b, 1.0);
+    assert!((a * a - a.len() * a.len()).abs() < rounding(a * a));
+    assert_eq!(a * b, b * a);
+    assert_vec_eq!(x.cross(y), z);
+    assert_vec_eq!(y.cross(z), x);
+    assert_vec_eq!(z.cross(x), y);
+    assert_vec_eq!(a.cross(b), -b.cross(a));
+    assert_vec_eq!(a.cross(a), Vector::zero());
+    // The cross product is perpendicular to both, and as long as the
+    // parallelogram they span is wide.
+    let cross = a.cross(b);
+    assert!((cross * a).abs() < rounding(10.0) && (cross * b).abs() < rounding(10.0));
+    let cosine = (a * b) / (a.len() * b.len());
+    let area = a.len() * b.len() * (1.0 - cosine * cosine).sqrt();
+    assert!((cross.len() - area).abs() < rounding(area));
+    // a × (b × c) = b (a · c) - c (a · b)
+    assert_vec_eq!(a.cross(b.cross(c)), (a * c) * b - (a * b) * c);
+    // The scalar triple product doesn't mind which pair is crossed.
+    assert!((a * b.cross(c) - b * c.cross(a)).abs() < rounding(100.0));
+    assert_vec_eq!(Vector::new(1.0, -1.0, 0.0).reflect(y), (1.0, 1.0, 0.0));
+}
+
+#[test]
+fn points_along_a_ray() {
+    let ray = Ray::new(Vector::new(1.0, 2.0, 3.0), Vector::new(0.0, -2.0, 0.5));
+    assert_vec_eq!(ray.at(0.0), ray.origin);
+    assert_vec_eq!(ray.at(2.0), (1.0, -2.0, 4.0));
+    assert_vec_eq!(ray.at(-1.0), (1.0, 4.0, 2.5));
+    assert_eq!(ray.to_string(), "(1, 2, 3) + t (0, -2, 0.5)");
+    assert_eq!(format!("{:.1}", ray.direction), "(0.0, -2.0, 0.5)");
+}
+
+#[test]
+fn sphere_hits_take_the_nearest_root_in_range() {
+    let sphere = Sphere::new(Vector::new(0.0, 0.0, -5.0), 2.0);
+    let at = |origin: [Scalar; 3], direction: [Scalar; 3], t_min: Scalar| {
+        let ray = Ray::new(origin.into(), direction.into());
+        sphere.intersect(&ray, t_min, Scalar::INFINITY)
+    };
+    // From outside, through the middle: in at 3, out at 7.
+    let hit = at([0.0, 0.0, 0.0], [0.0, 0.0, -1.0], EPSILON).unwrap();
+    assert!((hit.t - 3.0).abs() < rounding(10.0));
+    assert_vec_eq!(hit.point, (0.0, 0.0, -3.0));
+    assert_vec_eq!(hit.normal, (0.0, 0.0, 1.0));
+    assert!(hit.front_face);
+    let shown = format!("{:.3}", hit);
+    assert!(
+        shown.starts_with("hit at t = 3.000 at (0.000, 0.000, -3.000), front face"),
+        "{}",
+        shown
+    );
+    // A direction that isn't a unit vector scales t, not the point.
+    let hit = at([0.0, 0.0, 0.0], [0.0, 0.0, -4.0], EPSILON).unwrap();
+    assert!((hit.t - 0.75).abs() < rounding(10.0));
+    assert_vec_eq!(hit.point, (0.0, 0.0, -3.0));
+    // Past the near root, the far one; from inside, only the far one is
+    // ahead, and the normal faces the ray.
+    let hit = at([0.0, 0.0, 0.0], [0.0, 0.0, -1.0], 4.0).unwrap();
+    assert!((hit.t - 7.0).abs() < rounding(10.0));
+    let hit = at([0.0, 0.0, -5.0], [1.0, 0.0, 0.0], EPSILON).unwrap();
+    assert!((hit.t - 2.0).abs() < rounding(10.0) && !hit.front_face);
+    assert_vec_eq!(hit.normal, (-1.0, 0.0, 0.0));
+    // Behind the ray, or off to the side, it's missed.
+    assert!(at([0.0, 0.0, -10.0], [0.0, 0.0, -1.0], EPSILON).is_none());
+    assert!(at([0.0, 2.5, 0.0], [0.0, 0.0, -1.0], EPSILON).is_none());
+    // Grazing it, both roots are the same point.
+    let hit = at([0.0, 2.0, 0.0], [0.0, 0.0, -1.0], EPSILON).unwrap();
+    assert_vec_eq!(hit.point, (0.0, 2.0, -5.0), Scalar::EPSILON.sqrt());
+}
@@ -0,0 +1,146 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use basic_raytracer::camera::Camera;
+use basic_raytracer::light::PointLight;
+use basic_raytracer::material::Lambertian;
+use basic_raytracer::ray::Ray;
+use basic_raytracer::render::{render, IntegratorKind, RenderSettings};
+use basic_raytracer::scalar::Scalar;
+use basic_raytracer::scene::{RayKind, Scene};
+use basic_raytracer::scene_file;
+use basic_raytracer::shapes::{Plane, Sphere};
+use basic_raytracer::vector::{Color, Vector};
+
+/// A gray floor lit from straight above, seen from a little to the side,
+/// with a sphere hanging between the light and the middle of the floor
+/// unless `sphere` is false.
+fn floor(sphere: bool) -> Scene {
+    let mut scene = Scene::new(Camera::new(
+        Vector::new(0.0, 4.0, 3.0),
+        Vector::zero(),
+        40.0,
+    ));
+    let gray = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    scene.add(
+        Plane::new(Vector::zero(), Vector::new(0.0, 1.0, 0.0)),
+        gray.clone(),
+    );
+    if sphere {
+        scene.add(Sphere::new(Vector::new(0.0, 1.5, 0.0), 0.6), gray);
+    }
+    scene.add_light(PointLight::new(
+        Vector::new(0.0, 4.0, 0.0),
+        Color::new(1.0, 1.0, 1.0),
+        30.0,
+    ));
+    scene
+}
+
+fn settings() -> RenderSettings {
+    RenderSettings {
+        width: 32,
+        height: 32,
+        integrator: IntegratorKind::Whitted,
+        max_depth: 0,
+        ..RenderSettings::default()
+    }
+}
+
+/// The pixel looking at the middle of the floor.
+fn middle(scene: &Scene) -> (u32, u32) {
+    let settings = settings();
+    let (width, height) = (settings.width, settings.height);
+    (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .min_by(|&(ax, ay), &(bx, by)| {
+            let distance = |x: u32, y: u32| {
+                let ray = scene.camera.ray(
+                    (x as Scalar + 0.5) / width as Scalar,
+                    (y as Scalar + 0.5) / height as Scalar,
+                    1.0,
+                );
+                let floor = ray.origin + (-ray.origin.y / ray.direction.y) * ray.direction;
+                floor.len()
+            };
+            distance(ax, ay).partial_cmp(&distance(bx, by)).unwrap()
+        })
+        .unwrap()
+}
+
+#[test]
+fn objects_that_cast_no_shadows_leave_the_floor_lit() {
+    let (empty, _) = render(&floor(false), &settings()).unwrap();
+    let mut scene = floor(true);
+    let (shadowed, _) = render(&scene, &settings()).unwrap();
+    scene.objects[1].cast_shadows = false;
+    let (lit, _) = render(&scene, &settings()).unwrap();
+
+    let (x, y) = middle(&scene);
+    assert_eq!(shadowed.get(x, y), Color::zero());
+    assert!(empty.get(x, y).x > 0.1);
+    // The sphere still shows up, but every floor pixel is as bright as it is
+    // with nothing above it.
+    assert_ne!(lit.pixels, empty.pixels);
+    for y in 0..lit.height {
+        for x in 0..lit.width {
+            let ray = scene.camera.ray(
+                (x as Scalar + 0.5) / lit.width as Scalar,
+                (y as Scalar + 0.5) / lit.height as Scalar,
+                1.0,
+            );
+            let hit = scene.intersect(&ray, 1e-6, Scalar::INFINITY).unwrap();
+            if std::ptr::eq(hit.object, &scene.objects[0]) {
+                assert_eq!(lit.get(x, y), empty.get(x, y), "at ({}, {})", x, y);
+            }
+        }
+    }
+}
+
+#[test]
+fn objects_hidden_from_the_camera_still_cast_shadows() {
+    let mut scene = floor(true);
+    scene.objects[1].visible_to_camera = false;
+    let (image, _) = render(&scene, &settings()).unwrap();
+    let (x, y) = middle(&scene);
+    assert_eq!(image.get(x, y), Color::zero());
+
+    // The camera looks straight through the sphere at the floor, but rays
+    // bouncing off other surfaces still hit it.
+    let camera = scene.camera.position;
+    let ray = Ray::new(camera, (Vector::new(0.0, 1.5, 0.0) - camera).normalize());
+    let through = scene.trace(&ray, RayKind::Camera, 1e-6, Scalar::INFINITY);
+    assert!(std::ptr::eq(through.unwrap().object, &scene.objects[0]));
+    let bounced = scene.trace(&ray, RayKind::Secondary, 1e-6, Scalar::INFINITY);
+    assert!(std::ptr::eq(bounced.unwrap().object, &scene.objects[1]));
+
+    scene.objects[1].visible_to_camera = true;
+    scene.objects[1].visible_in_reflections = false;
+    let bounced = scene.trace(&ray, RayKind::Secondary, 1e-6, Scalar::INFINITY);
+    assert!(std::ptr::eq(bounced.unwrap().object, &scene.objects[0]));
+}
+
+#[test]
+fn scene_files_set_the_flags() {
+    let parse = |flags: &str| {
+        scene_file::parse(
+            &format!(
+                r#"{{"spheres": [{{"center": [0, 0, 0], "radius": 1,
+                    "material": {{"type": "lambertian", "albedo": [1, 1, 1]}}{}}}]}}"#,
+                flags
+            ),
+            Path::new(""),
+        )
+    };
+    let object = &parse("").unwrap().objects[0];
+    assert!(object.cast_shadows && object.visible_to_camera && object.visible_in_reflections);
+
+    let scene = parse(r#", "cast_shadows": false, "visible_in_reflections": false"#).unwrap();
+    let object = &scene.objects[0];
+    assert!(!object.cast_shadows && object.visible_to_camera && !object.visible_in_reflections);
+
+    assert_eq!(
+        parse(r#", "visible_to_camera": 0"#).err().unwrap(),
+        "spheres[0].visible_to_camera: expected a boolean, found a number"
+    );
+}
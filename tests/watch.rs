@@ -0,0 +1,207 @@
+pub mod common;
+
+use std::cell::Cell;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
+
+use basic_raytracer::error::Error;
+use basic_raytracer::scene_file;
+use basic_raytracer::watch::{render_on_changes, Settled, Trigger, WatchStats, Watcher};
+use common::{run, scratch};
+
+const DEBOUNCE: Duration = Duration::from_millis(50);
+
+#[test]
+fn a_burst_of_changes_cancels_the_render_and_makes_one_more() {
+    let trigger = Trigger::new();
+    let mut renders = 0;
+    let stats = render_on_changes(
+        &trigger,
+        DEBOUNCE,
+        |cancel| {
+            renders += 1;
+            if renders == 1 {
+                // Three saves in quick succession while the first render
+                // is going.
+                let saves = trigger.clone();
+                thread::spawn(move || {
+                    for _ in 0..3 {
+                        saves.fire();
+                        thread::sleep(Duration::from_millis(5));
+                    }
+                });
+                while !cancel.load(Ordering::SeqCst) {
+                    thread::sleep(Duration::from_millis(1));
+                }
+                return Err(Error::Cancelled);
+            }
+            // The render of the last change starts uncancelled.
+            assert!(!cancel.load(Ordering::SeqCst));
+            trigger.close();
+            Ok(())
+        },
+        || true,
+    );
+    assert_eq!(renders, 2);
+    assert_eq!(
+        stats,
+        WatchStats {
+            finished: 1,
+            cancelled: 1,
+            failed: 0
+        }
+    );
+}
+
+#[test]
+fn a_failed_render_is_counted_and_the_watch_carries_on() {
+    let trigger = Trigger::new();
+    let renders = Cell::new(0);
+    let stats = render_on_changes(
+        &trigger,
+        DEBOUNCE,
+        |_| {
+            renders.set(renders.get() + 1);
+            match renders.get() {
+                1 => {
+                    trigger.fire();
+                    Err(Error::InvalidScene("a broken save".to_string()))
+                }
+                _ => Ok(()),
+            }
+        },
+        // Nothing changes after the second render, until this gives up.
+        || renders.get() < 2,
+    );
+    assert_eq!((stats.failed, stats.finished), (1, 1));
+    assert!(trigger.is_closed());
+    // Once closed, it stays closed.
+    trigger.fire();
+    assert_eq!(trigger.settled(DEBOUNCE, DEBOUNCE), Settled::Closed);
+}
+
+#[test]
+fn changes_settle_once_they_stop_coming() {
+    let trigger = Trigger::new();
+    let short = Duration::from_millis(1);
+    assert_eq!(trigger.settled(DEBOUNCE, short), Settled::Waiting);
+    trigger.fire();
+    assert!(trigger.cancelled().load(Ordering::SeqCst));
+    assert_eq!(trigger.settled(DEBOUNCE, short), Settled::Waiting);
+    assert_eq!(trigger.settled(DEBOUNCE, 4 * DEBOUNCE), Settled::Changed);
+    assert!(!trigger.cancelled().load(Ordering::SeqCst));
+    // The change was taken, so there's nothing more to render.
+    assert_eq!(trigger.settled(DEBOUNCE, short), Settled::Waiting);
+}
+
+#[test]
+fn watchers_see_files_change_appear_and_go_away() {
+    let dir = scratch("files");
+    let (scene, mesh) = (dir.join("scene.json"), dir.join("mesh.obj"));
+    fs::write(&scene, "{}").unwrap();
+    let mut watcher = Watcher::new(vec![scene.clone(), mesh.clone()]);
+    assert!(!watcher.changed());
+    fs::write(&scene, "{\"spheres\": []}").unwrap();
+    assert!(watcher.changed());
+    assert!(!watcher.changed());
+    fs::write(&mesh, "v 0 0 0\n").unwrap();
+    assert!(watcher.changed());
+    fs::remove_file(&mesh).unwrap();
+    assert!(watcher.changed());
+
+    // A change made before the files are updated still counts.
+    fs::write(&scene, "{}").unwrap();
+    watcher.set_files(vec![scene.clone()]);
+    assert!(watcher.changed());
+    assert_eq!(watcher.files().collect::<Vec<_>>(), [scene.as_path()]);
+}
+
+#[test]
+fn notifications_say_when_a_watched_file_changes() {
+    let dir = scratch("notified");
+    let (scene, mesh) = (dir.join("scene.json"), dir.join("mesh.obj"));
+    fs::write(&scene, "{}").unwrap();
+    let mut watcher = Watcher::new(vec![scene.clone(), mesh.clone()]);
+    assert!(watcher.is_notified());
+    let long = Duration::from_secs(10);
+
+    // Other files in the same directory don't count.
+    fs::write(dir.join("output.png"), "").unwrap();
+    assert!(!watcher.wait(Duration::from_millis(200)));
+    fs::write(&scene, "{\"spheres\": []}").unwrap();
+    assert!(watcher.wait(long));
+    // Nor do reads.
+    let settle = |watcher: &mut Watcher| while watcher.wait(Duration::from_millis(200)) {};
+    settle(&mut watcher);
+    fs::read(&scene).unwrap();
+    assert!(!watcher.wait(Duration::from_millis(200)));
+
+    // Saving by renaming a new file over the old one, as editors do, and a
+    // file that wasn't there appearing.
+    fs::write(dir.join("scene.json.tmp"), "{}").unwrap();
+    settle(&mut watcher);
+    fs::rename(dir.join("scene.json.tmp"), &scene).unwrap();
+    assert!(watcher.wait(long));
+    settle(&mut watcher);
+    fs::write(&mesh, "v 0 0 0\n").unwrap();
+    assert!(watcher.wait(long));
+
+    // A file in a directory that wasn't watched before.
+    settle(&mut watcher);
+    let models = dir.join("models");
+    fs::create_dir(&models).unwrap();
+    watcher.set_files(vec![scene.clone(), models.join("cube.obj")]);
+    fs::write(models.join("cube.obj"), "v 0 0 0\n").unwrap();
+    assert!(watcher.wait(long));
+}
+
+#[test]
+fn the_files_a_scene_refers_to_are_watched_too() {
+    let dir = scratch("references");
+    let scene = dir.join("scene.json");
+    fs::write(
+        &scene,
+        r#"{
+            "meshes": [{"path": "models/teapot.obj", "material": {"type": "lambertian", "albedo": 1}}],
+            "instances": [],
+            "geometry": [{"name": "cube", "path": "cube.obj"}, {"name": "again", "path": "cube.obj"}],
+            "camera": {"position": [0, 0, 5], "look_at": [0, 0, 0], "path": "flight.json"}
+        }"#,
+    )
+    .unwrap();
+    let files = scene_file::references(&scene, &[]).unwrap();
+    assert_eq!(
+        files,
+        [
+            dir.join("models/teapot.obj"),
+            dir.join("cube.obj"),
+            dir.join("flight.json")
+        ]
+    );
+}
+
+#[test]
+fn watching_needs_a_single_image_of_a_scene_file() {
+    let dir = scratch("cli");
+    let scene = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes/checker.json");
+    let run = |args: &[&str]| {
+        let (code, _, stderr) = run(&dir, args);
+        (code, stderr)
+    };
+    let (code, stderr) = run(&["--watch"]);
+    assert_eq!(
+        (code, stderr.trim()),
+        (Some(2), "error: --watch needs a scene file")
+    );
+    let scene = scene.to_str().unwrap();
+    let (code, stderr) = run(&["render", scene, "--watch", "--frames", "2"]);
+    assert_eq!(code, Some(2));
+    assert!(
+        stderr.starts_with("error: --watch can't be used with --frames"),
+        "{}",
+        stderr
+    );
+}